@@ -1,16 +1,16 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use uuid::Uuid;
-use anyhow::{Context, Result};
-use async_trait::async_trait;
 
-use super::planner::TaskPlanner;
 use super::executor::TaskExecutor;
-use super::validator::TaskValidator;
-use super::recovery::ErrorRecovery;
 use super::monitor::ProgressMonitor;
+use super::planner::TaskPlanner;
+use super::recovery::ErrorRecovery;
+use super::validator::TaskValidator;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct AutomationTask {
@@ -107,7 +107,11 @@ pub trait AutomationEngine: Send + Sync {
     async fn execute_task(&self, task: AutomationTask) -> Result<AutomationResult>;
     async fn plan_task(&self, description: &str) -> Result<Vec<AutomationTask>>;
     async fn validate_result(&self, result: &AutomationResult) -> Result<bool>;
-    async fn recover_from_error(&self, error: &str, task: &AutomationTask) -> Result<Option<AutomationTask>>;
+    async fn recover_from_error(
+        &self,
+        error: &str,
+        task: &AutomationTask,
+    ) -> Result<Option<AutomationTask>>;
     fn get_progress(&self) -> Result<f64>;
 }
 
@@ -144,7 +148,7 @@ impl TauriHandsEngine {
 
     pub async fn execute_automation(&self, description: &str) -> Result<Vec<AutomationResult>> {
         log::info!("Starting automation: {}", description);
-        
+
         // Step 1: Plan the task
         let tasks = self.plan_task(description).await?;
         log::info!("Planned {} subtasks", tasks.len());
@@ -154,7 +158,10 @@ impl TauriHandsEngine {
         let mut executed_tasks = std::collections::HashSet::new();
 
         for task in &tasks {
-            if let Some(result) = self.execute_task_with_dependencies(task, &mut executed_tasks).await? {
+            if let Some(result) = self
+                .execute_task_with_dependencies(task, &mut executed_tasks)
+                .await?
+            {
                 results.push(result);
             }
         }
@@ -179,7 +186,10 @@ impl TauriHandsEngine {
         // Execute dependencies first
         for dep_id in &task.dependencies {
             if let Some(dep_task) = self.active_tasks.lock().unwrap().get(dep_id) {
-                if let Some(_) = self.execute_task_with_dependencies(dep_task, executed_tasks).await? {
+                if let Some(_) = self
+                    .execute_task_with_dependencies(dep_task, executed_tasks)
+                    .await?
+                {
                     // Dependency executed successfully
                 }
             }
@@ -194,7 +204,10 @@ impl TauriHandsEngine {
             let is_valid = self.validate_result(&result).await?;
             if !is_valid && self.config.auto_recovery {
                 log::warn!("Task validation failed, attempting recovery");
-                if let Some(recovery_task) = self.recover_from_error(&result.error.unwrap_or_default(), task).await? {
+                if let Some(recovery_task) = self
+                    .recover_from_error(&result.error.unwrap_or_default(), task)
+                    .await?
+                {
                     let recovery_result = self.execute_task(recovery_task).await?;
                     return Ok(Some(recovery_result));
                 }
@@ -226,7 +239,7 @@ impl TauriHandsEngine {
 impl AutomationEngine for TauriHandsEngine {
     async fn execute_task(&self, task: AutomationTask) -> Result<AutomationResult> {
         let start_time = std::time::Instant::now();
-        
+
         // Update task status
         {
             let mut active_tasks = self.active_tasks.lock().unwrap();
@@ -270,7 +283,11 @@ impl AutomationEngine for TauriHandsEngine {
         self.validator.validate(result).await
     }
 
-    async fn recover_from_error(&self, error: &str, task: &AutomationTask) -> Result<Option<AutomationTask>> {
+    async fn recover_from_error(
+        &self,
+        error: &str,
+        task: &AutomationTask,
+    ) -> Result<Option<AutomationTask>> {
         self.recovery.recover(error, task).await
     }
 
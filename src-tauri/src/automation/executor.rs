@@ -1,12 +1,12 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::process::Command;
 use uuid::Uuid;
-use anyhow::{Context, Result};
-use async_trait::async_trait;
 
-use super::engine::{AutomationTask, AutomationResult, TaskType, TaskStatus, AutomationConfig};
+use super::engine::{AutomationConfig, AutomationResult, AutomationTask, TaskStatus, TaskType};
 
 #[async_trait]
 pub trait TaskExecutor: Send + Sync {
@@ -29,16 +29,18 @@ impl CodeExecutor {
 
     async fn execute_code_generation(&self, task: &AutomationTask) -> Result<AutomationResult> {
         log::info!("Executing code generation task: {}", task.title);
-        
+
         // Read current workspace context
         let workspace_context = self.analyze_workspace().await?;
-        
+
         // Generate code using LLM
-        let generated_code = self.generate_code(&task.description, &workspace_context).await?;
-        
+        let generated_code = self
+            .generate_code(&task.description, &workspace_context)
+            .await?;
+
         // Write generated code to appropriate files
         let artifacts = self.save_generated_code(&generated_code).await?;
-        
+
         // Run validation
         let validation_result = self.validate_generated_code(&artifacts).await?;
 
@@ -52,7 +54,10 @@ impl CodeExecutor {
             artifacts,
             metrics: HashMap::from([
                 ("files_generated".to_string(), artifacts.len() as f64),
-                ("lines_of_code".to_string(), self.count_lines_in_artifacts(&artifacts) as f64),
+                (
+                    "lines_of_code".to_string(),
+                    self.count_lines_in_artifacts(&artifacts) as f64,
+                ),
             ]),
         })
     }
@@ -62,9 +67,9 @@ impl CodeExecutor {
 
         // Find files to modify
         let target_files = self.find_target_files(&task.description).await?;
-        
+
         let mut modified_files = Vec::new();
-        
+
         for file_path in &target_files {
             let modification_result = self.modify_file(file_path, &task.description).await?;
             if modification_result {
@@ -80,9 +85,7 @@ impl CodeExecutor {
             error: None,
             execution_time: std::time::Duration::from_secs(0),
             artifacts: modified_files,
-            metrics: HashMap::from([
-                ("files_modified".to_string(), modified_files.len() as f64),
-            ]),
+            metrics: HashMap::from([("files_modified".to_string(), modified_files.len() as f64)]),
         })
     }
 
@@ -91,10 +94,10 @@ impl CodeExecutor {
 
         // Run existing tests
         let test_results = self.run_tests().await?;
-        
+
         // Generate additional tests if needed
         let generated_tests = self.generate_tests(&task.description).await?;
-        
+
         // Run new tests
         let new_test_results = self.run_generated_tests(&generated_tests).await?;
 
@@ -112,8 +115,14 @@ impl CodeExecutor {
             execution_time: std::time::Duration::from_secs(0),
             artifacts: generated_tests,
             metrics: HashMap::from([
-                ("tests_passed".to_string(), (test_results.passed + new_test_results.passed) as f64),
-                ("tests_failed".to_string(), (test_results.failed + new_test_results.failed) as f64),
+                (
+                    "tests_passed".to_string(),
+                    (test_results.passed + new_test_results.passed) as f64,
+                ),
+                (
+                    "tests_failed".to_string(),
+                    (test_results.failed + new_test_results.failed) as f64,
+                ),
                 ("tests_generated".to_string(), generated_tests.len() as f64),
             ]),
         })
@@ -124,10 +133,12 @@ impl CodeExecutor {
 
         // Analyze codebase for documentation
         let code_analysis = self.analyze_codebase_for_docs().await?;
-        
+
         // Generate documentation
-        let documentation = self.generate_documentation(&task.description, &code_analysis).await?;
-        
+        let documentation = self
+            .generate_documentation(&task.description, &code_analysis)
+            .await?;
+
         // Save documentation files
         let doc_files = self.save_documentation(&documentation).await?;
 
@@ -141,14 +152,17 @@ impl CodeExecutor {
             artifacts: doc_files,
             metrics: HashMap::from([
                 ("docs_generated".to_string(), doc_files.len() as f64),
-                ("pages_written".to_string(), self.count_doc_pages(&doc_files) as f64),
+                (
+                    "pages_written".to_string(),
+                    self.count_doc_pages(&doc_files) as f64,
+                ),
             ]),
         })
     }
 
     async fn analyze_workspace(&self) -> Result<String> {
         let mut context = String::new();
-        
+
         // Get project structure
         if let Ok(output) = Command::new("find")
             .arg(&self.config.workspace)
@@ -211,9 +225,16 @@ Please provide complete, production-ready code with proper error handling, docum
             "max_tokens": 4000
         });
 
-        let response = self.client
+        let response = self
+            .client
             .post("https://api.openai.com/v1/chat/completions")
-            .header("Authorization", format!("Bearer {}", self.config.api_key.as_ref().ok_or("API key required")?))
+            .header(
+                "Authorization",
+                format!(
+                    "Bearer {}",
+                    self.config.api_key.as_ref().ok_or("API key required")?
+                ),
+            )
             .header("Content-Type", "application/json")
             .json(&request_body)
             .send()
@@ -221,7 +242,7 @@ Please provide complete, production-ready code with proper error handling, docum
 
         let response_text = response.text().await?;
         let response_json: serde_json::Value = serde_json::from_str(&response_text)?;
-        
+
         if let Some(content) = response_json
             .get("choices")
             .and_then(|c| c.get(0))
@@ -237,9 +258,10 @@ Please provide complete, production-ready code with proper error handling, docum
 
     async fn save_generated_code(&self, code: &str) -> Result<Vec<PathBuf>> {
         let mut artifacts = Vec::new();
-        
+
         // Parse code blocks from response
-        let code_blocks: Vec<&str> = code.lines()
+        let code_blocks: Vec<&str> = code
+            .lines()
             .filter(|line| line.trim().starts_with("```"))
             .collect();
 
@@ -258,10 +280,13 @@ Please provide complete, production-ready code with proper error handling, docum
                 if line.trim().starts_with("```") {
                     if in_code_block {
                         // End of code block
-                        let file_path = self.config.workspace.join(format!("generated_{}.rs", file_counter));
+                        let file_path = self
+                            .config
+                            .workspace
+                            .join(format!("generated_{}.rs", file_counter));
                         std::fs::write(&file_path, &current_code)?;
                         artifacts.push(file_path);
-                        
+
                         current_code.clear();
                         file_counter += 1;
                         in_code_block = false;
@@ -301,7 +326,7 @@ Please provide complete, production-ready code with proper error handling, docum
 
     async fn find_target_files(&self, description: &str) -> Result<Vec<PathBuf>> {
         let mut target_files = Vec::new();
-        
+
         // Simple heuristic: look for files mentioned in description
         for word in description.split_whitespace() {
             if word.ends_with(".rs") || word.ends_with(".js") || word.ends_with(".ts") {
@@ -333,7 +358,7 @@ Please provide complete, production-ready code with proper error handling, docum
 
     async fn modify_file(&self, file_path: &PathBuf, description: &str) -> Result<bool> {
         let current_content = std::fs::read_to_string(file_path)?;
-        
+
         let prompt = format!(
             r#"Modify the following code based on the requirements:
 
@@ -349,10 +374,10 @@ Please provide the modified code only, without explanations."#,
 
         // Use LLM to modify the code
         let modified_code = self.generate_code(&prompt, "").await?;
-        
+
         // Save modified code
         std::fs::write(file_path, modified_code)?;
-        
+
         Ok(true)
     }
 
@@ -368,7 +393,7 @@ Please provide the modified code only, without explanations."#,
             {
                 let output_str = String::from_utf8_lossy(&output.stdout);
                 let _error_str = String::from_utf8_lossy(&output.stderr);
-                
+
                 // Parse test results
                 for line in output_str.lines() {
                     if line.contains("test result:") {
@@ -398,7 +423,7 @@ Please provide complete test cases with proper setup, teardown, and assertions."
 
         let test_code = self.generate_code(&prompt, "").await?;
         let test_files = self.save_generated_code(&test_code).await?;
-        
+
         Ok(test_files)
     }
 
@@ -414,7 +439,7 @@ Please provide complete test cases with proper setup, teardown, and assertions."
                 .output()
             {
                 let output_str = String::from_utf8_lossy(&output.stdout);
-                
+
                 // Parse test results
                 for line in output_str.lines() {
                     if line.contains("test result:") {
@@ -433,7 +458,7 @@ Please provide complete test cases with proper setup, teardown, and assertions."
 
     async fn analyze_codebase_for_docs(&self) -> Result<String> {
         let mut analysis = String::new();
-        
+
         // Get all source files
         for entry in walkdir::WalkDir::new(&self.config.workspace)
             .into_iter()
@@ -455,7 +480,11 @@ Please provide complete test cases with proper setup, teardown, and assertions."
         Ok(analysis)
     }
 
-    async fn generate_documentation(&self, description: &str, code_analysis: &str) -> Result<String> {
+    async fn generate_documentation(
+        &self,
+        description: &str,
+        code_analysis: &str,
+    ) -> Result<String> {
         let prompt = format!(
             r#"Generate comprehensive documentation based on the following requirements and code analysis:
 
@@ -479,17 +508,20 @@ Please generate well-structured documentation including:
 
     async fn save_documentation(&self, documentation: &str) -> Result<Vec<PathBuf>> {
         let mut doc_files = Vec::new();
-        
+
         // Save main README
         let readme_path = self.config.workspace.join("README.md");
         std::fs::write(&readme_path, documentation)?;
         doc_files.push(readme_path);
-        
+
         // Save additional documentation files
         let sections: Vec<&str> = documentation.split("# ").collect();
         for (_i, section) in sections.iter().enumerate().skip(1) {
             if let Some(title) = section.lines().next() {
-                let filename = format!("{}.md", title.to_lowercase().replace(&[' ', '(', ')'][..], "_"));
+                let filename = format!(
+                    "{}.md",
+                    title.to_lowercase().replace(&[' ', '(', ')'][..], "_")
+                );
                 let file_path = self.config.workspace.join(filename);
                 std::fs::write(&file_path, section)?;
                 doc_files.push(file_path);
@@ -542,7 +574,10 @@ impl TaskExecutor for CodeExecutor {
     fn supports_task_type(&self, task_type: &TaskType) -> bool {
         matches!(
             task_type,
-            TaskType::CodeGeneration | TaskType::CodeModification | TaskType::Testing | TaskType::Documentation
+            TaskType::CodeGeneration
+                | TaskType::CodeModification
+                | TaskType::Testing
+                | TaskType::Documentation
         )
     }
 }
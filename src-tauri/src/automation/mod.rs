@@ -1,13 +1,13 @@
 pub mod engine;
-pub mod planner;
 pub mod executor;
-pub mod validator;
-pub mod recovery;
 pub mod monitor;
+pub mod planner;
+pub mod recovery;
+pub mod validator;
 
 pub use engine::*;
-pub use planner::*;
 pub use executor::*;
-pub use validator::*;
-pub use recovery::*;
 pub use monitor::*;
+pub use planner::*;
+pub use recovery::*;
+pub use validator::*;
@@ -1,11 +1,11 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
-use anyhow::{Context, Result};
-use async_trait::async_trait;
 
-use super::engine::{AutomationConfig, AutomationTask, AutomationResult, TaskStatus};
+use super::engine::{AutomationConfig, AutomationResult, AutomationTask, TaskStatus};
 
 #[async_trait]
 pub trait ProgressMonitor: Send + Sync {
@@ -71,7 +71,7 @@ impl RealTimeMonitor {
     fn calculate_overall_progress(&self) -> f64 {
         let progress_map = self.task_progress.lock().unwrap();
         let completed = self.completed_tasks.lock().unwrap();
-        
+
         if progress_map.is_empty() && completed.is_empty() {
             return 0.0;
         }
@@ -83,26 +83,26 @@ impl RealTimeMonitor {
 
         let active_progress: f64 = progress_map.values().map(|p| p.progress).sum();
         let completed_progress = completed.len() as f64 * 100.0;
-        
+
         (active_progress + completed_progress) / total_tasks as f64
     }
 
     fn estimate_completion_time(&self) -> Option<String> {
         let progress_map = self.task_progress.lock().unwrap();
         let start_times = self.start_times.lock().unwrap();
-        
+
         if progress_map.is_empty() {
             return None;
         }
 
         let mut total_remaining_time = Duration::from_secs(0);
-        
+
         for (task_id, progress) in progress_map.iter() {
             if let Some(start_time) = start_times.get(task_id) {
                 let elapsed = start_time.elapsed();
                 if progress.progress > 0.0 {
                     let estimated_total = Duration::from_millis(
-                        (elapsed.as_millis() as f64 / (progress.progress / 100.0)) as u64
+                        (elapsed.as_millis() as f64 / (progress.progress / 100.0)) as u64,
                     );
                     let remaining = estimated_total.saturating_sub(elapsed);
                     total_remaining_time += remaining;
@@ -111,7 +111,10 @@ impl RealTimeMonitor {
         }
 
         if total_remaining_time.as_secs() > 0 {
-            Some(format!("Estimated completion in {} seconds", total_remaining_time.as_secs()))
+            Some(format!(
+                "Estimated completion in {} seconds",
+                total_remaining_time.as_secs()
+            ))
         } else {
             None
         }
@@ -119,7 +122,7 @@ impl RealTimeMonitor {
 
     fn calculate_performance_metrics(&self) -> PerformanceMetrics {
         let completed = self.completed_tasks.lock().unwrap();
-        
+
         if completed.is_empty() {
             return PerformanceMetrics {
                 average_task_duration: Duration::from_secs(0),
@@ -132,11 +135,11 @@ impl RealTimeMonitor {
 
         let total_duration: Duration = completed.iter().map(|r| r.execution_time).sum();
         let average_duration = total_duration / completed.len() as u32;
-        
+
         let successful_tasks = completed.iter().filter(|r| r.success).count();
         let success_rate = successful_tasks as f64 / completed.len() as f64;
         let error_rate = 1.0 - success_rate;
-        
+
         let tasks_per_hour = if average_duration.as_secs() > 0 {
             3600.0 / average_duration.as_secs() as f64
         } else {
@@ -160,7 +163,7 @@ impl ProgressMonitor for RealTimeMonitor {
         if let Some(task_progress) = progress_map.get_mut(&task_id) {
             task_progress.progress = progress.min(100.0).max(0.0);
         }
-        
+
         // Update overall progress
         *self.overall_progress.lock().unwrap() = self.calculate_overall_progress();
     }
@@ -170,18 +173,22 @@ impl ProgressMonitor for RealTimeMonitor {
     }
 
     fn get_task_progress(&self, task_id: uuid::Uuid) -> Option<f64> {
-        self.task_progress.lock().unwrap().get(&task_id).map(|p| p.progress)
+        self.task_progress
+            .lock()
+            .unwrap()
+            .get(&task_id)
+            .map(|p| p.progress)
     }
 
     fn get_progress_report(&self) -> ProgressReport {
         let progress_map = self.task_progress.lock().unwrap();
         let completed = self.completed_tasks.lock().unwrap();
-        
+
         let total_tasks = progress_map.len() + completed.len();
         let completed_tasks = completed.len();
         let failed_tasks = completed.iter().filter(|r| !r.success).count();
         let active_tasks = progress_map.len();
-        
+
         let task_details: Vec<TaskProgress> = progress_map.values().cloned().collect();
         let estimated_completion = self.estimate_completion_time();
         let performance_metrics = self.calculate_performance_metrics();
@@ -201,24 +208,24 @@ impl ProgressMonitor for RealTimeMonitor {
     fn start_monitoring(&self, task: &AutomationTask) {
         let mut progress_map = self.task_progress.lock().unwrap();
         let mut start_times = self.start_times.lock().unwrap();
-        
+
         let task_progress = TaskProgress {
             task_id: task.id,
             title: task.title.clone(),
             status: task.status.clone(),
             progress: 0.0,
             started_at: std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs()
-                    .to_string(),
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+                .to_string(),
             estimated_completion: None,
             current_step: "Starting".to_string(),
         };
-        
+
         progress_map.insert(task.id, task_progress);
         start_times.insert(task.id, Instant::now());
-        
+
         log::info!("Started monitoring task: {}", task.title);
     }
 
@@ -226,17 +233,17 @@ impl ProgressMonitor for RealTimeMonitor {
         let mut progress_map = self.task_progress.lock().unwrap();
         let mut completed = self.completed_tasks.lock().unwrap();
         let mut start_times = self.start_times.lock().unwrap();
-        
+
         // Remove from active tasks
         progress_map.remove(&task_id);
         start_times.remove(&task_id);
-        
+
         // Add to completed tasks
         completed.push(result.clone());
-        
+
         // Update overall progress
         *self.overall_progress.lock().unwrap() = self.calculate_overall_progress();
-        
+
         log::info!("Completed task: {:?}, success: {}", task_id, result.success);
     }
 }
@@ -292,7 +299,7 @@ impl ProgressMonitor for FileMonitor {
                 }
             }
         }
-        
+
         // Return empty report if file doesn't exist or is invalid
         ProgressReport {
             overall_progress: 0.0,
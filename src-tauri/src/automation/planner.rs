@@ -1,15 +1,19 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use uuid::Uuid;
-use anyhow::{Context, Result};
-use async_trait::async_trait;
 
-use super::engine::{AutomationTask, TaskType, TaskPriority, TaskStatus, AutomationConfig};
+use super::engine::{AutomationConfig, AutomationTask, TaskPriority, TaskStatus, TaskType};
 
 #[async_trait]
 pub trait TaskPlanner: Send + Sync {
     async fn plan(&self, description: &str) -> Result<Vec<AutomationTask>>;
-    async fn refine_plan(&self, tasks: &[AutomationTask], feedback: &str) -> Result<Vec<AutomationTask>>;
+    async fn refine_plan(
+        &self,
+        tasks: &[AutomationTask],
+        feedback: &str,
+    ) -> Result<Vec<AutomationTask>>;
 }
 
 pub struct LLMTaskPlanner {
@@ -42,9 +46,16 @@ impl LLMTaskPlanner {
             "max_tokens": 2000
         });
 
-        let response = self.client
+        let response = self
+            .client
             .post("https://api.openai.com/v1/chat/completions")
-            .header("Authorization", format!("Bearer {}", self.config.api_key.as_ref().ok_or("API key required")?))
+            .header(
+                "Authorization",
+                format!(
+                    "Bearer {}",
+                    self.config.api_key.as_ref().ok_or("API key required")?
+                ),
+            )
             .header("Content-Type", "application/json")
             .json(&request_body)
             .send()
@@ -52,7 +63,7 @@ impl LLMTaskPlanner {
 
         let response_text = response.text().await?;
         let response_json: serde_json::Value = serde_json::from_str(&response_text)?;
-        
+
         if let Some(content) = response_json
             .get("choices")
             .and_then(|c| c.get(0))
@@ -123,7 +134,7 @@ impl LLMTaskPlanner {
             };
 
             let task_id = Uuid::new_v4();
-            
+
             // Parse dependencies
             if let Some(deps) = task_data.get("dependencies").and_then(|d| d.as_array()) {
                 let mut task_deps = Vec::new();
@@ -132,7 +143,9 @@ impl LLMTaskPlanner {
                         // Find dependency task by title (simplified)
                         for (dep_index, dep_task) in tasks_array.iter().enumerate() {
                             if dep_index < index {
-                                if let Some(dep_title_match) = dep_task.get("title").and_then(|t| t.as_str()) {
+                                if let Some(dep_title_match) =
+                                    dep_task.get("title").and_then(|t| t.as_str())
+                                {
                                     if dep_title_match == dep_title {
                                         task_deps.push(task_id); // This should be the actual dependency ID
                                     }
@@ -207,26 +220,32 @@ Guidelines:
 impl TaskPlanner for LLMTaskPlanner {
     async fn plan(&self, description: &str) -> Result<Vec<AutomationTask>> {
         log::info!("Planning task: {}", description);
-        
+
         let prompt = self.create_planning_prompt(description);
         let response = self.call_llm(&prompt).await?;
         let tasks = self.parse_task_plan(&response)?;
-        
+
         log::info!("Generated {} subtasks", tasks.len());
         Ok(tasks)
     }
 
-    async fn refine_plan(&self, tasks: &[AutomationTask], feedback: &str) -> Result<Vec<AutomationTask>> {
+    async fn refine_plan(
+        &self,
+        tasks: &[AutomationTask],
+        feedback: &str,
+    ) -> Result<Vec<AutomationTask>> {
         let current_plan: String = tasks
             .iter()
             .enumerate()
-            .map(|(i, task)| format!(
-                "{}. {} ({:?}) - {}",
-                i + 1,
-                task.title,
-                task.task_type,
-                task.description
-            ))
+            .map(|(i, task)| {
+                format!(
+                    "{}. {} ({:?}) - {}",
+                    i + 1,
+                    task.title,
+                    task.task_type,
+                    task.description
+                )
+            })
             .collect::<Vec<_>>()
             .join("\n");
 
@@ -245,7 +264,7 @@ Please provide an updated plan in the same JSON format as before."#,
 
         let response = self.call_llm(&prompt).await?;
         let refined_tasks = self.parse_task_plan(&response)?;
-        
+
         log::info!("Refined plan to {} subtasks", refined_tasks.len());
         Ok(refined_tasks)
     }
@@ -262,7 +281,7 @@ impl RuleBasedPlanner {
 
     fn analyze_task_type(&self, description: &str) -> TaskType {
         let desc_lower = description.to_lowercase();
-        
+
         if desc_lower.contains("test") || desc_lower.contains("spec") {
             TaskType::Testing
         } else if desc_lower.contains("document") || desc_lower.contains("readme") {
@@ -289,7 +308,7 @@ impl RuleBasedPlanner {
 impl TaskPlanner for RuleBasedPlanner {
     async fn plan(&self, description: &str) -> Result<Vec<AutomationTask>> {
         log::info!("Planning task with rules: {}", description);
-        
+
         let task_type = self.analyze_task_type(description);
         let mut tasks = Vec::new();
 
@@ -387,7 +406,11 @@ impl TaskPlanner for RuleBasedPlanner {
         Ok(tasks)
     }
 
-    async fn refine_plan(&self, tasks: &[AutomationTask], _feedback: &str) -> Result<Vec<AutomationTask>> {
+    async fn refine_plan(
+        &self,
+        tasks: &[AutomationTask],
+        _feedback: &str,
+    ) -> Result<Vec<AutomationTask>> {
         // For rule-based planner, just return the same tasks
         Ok(tasks.to_vec())
     }
@@ -1,9 +1,9 @@
-use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
 use anyhow::{Context, Result};
 use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 
-use super::engine::{AutomationTask, AutomationConfig, TaskType};
+use super::engine::{AutomationConfig, AutomationTask, TaskType};
 
 #[async_trait]
 pub trait ErrorRecovery: Send + Sync {
@@ -43,7 +43,7 @@ impl SmartRecovery {
 
     async fn analyze_error(&self, error: &str) -> ErrorAnalysis {
         let error_lower = error.to_lowercase();
-        
+
         if error_lower.contains("compilation") || error_lower.contains("syntax") {
             ErrorAnalysis {
                 error_type: ErrorType::Compilation,
@@ -89,7 +89,11 @@ impl SmartRecovery {
         }
     }
 
-    async fn create_recovery_task(&self, original_task: &AutomationTask, analysis: &ErrorAnalysis) -> Result<Option<AutomationTask>> {
+    async fn create_recovery_task(
+        &self,
+        original_task: &AutomationTask,
+        analysis: &ErrorAnalysis,
+    ) -> Result<Option<AutomationTask>> {
         match analysis.error_type {
             ErrorType::Compilation => {
                 // Create a task to fix compilation errors
@@ -111,7 +115,7 @@ impl SmartRecovery {
                     .unwrap()
                     .as_secs()
                     .to_string();
-                
+
                 Ok(Some(recovery_task))
             }
             ErrorType::Permission => {
@@ -135,7 +139,7 @@ impl SmartRecovery {
                     .unwrap()
                     .as_secs()
                     .to_string();
-                
+
                 Ok(Some(recovery_task))
             }
             ErrorType::Network => {
@@ -158,14 +162,15 @@ impl SmartRecovery {
                     .unwrap()
                     .as_secs()
                     .to_string();
-                
+
                 Ok(Some(recovery_task))
             }
             ErrorType::Timeout => {
                 // Break down the task into smaller pieces
                 let mut recovery_task = original_task.clone();
                 recovery_task.id = uuid::Uuid::new_v4();
-                recovery_task.title = format!("Break down {} into smaller tasks", original_task.title);
+                recovery_task.title =
+                    format!("Break down {} into smaller tasks", original_task.title);
                 recovery_task.description = format!(
                     "Break down the original task to avoid timeouts. Original error: {}. Suggested fix: {}",
                     analysis.suggested_fix, analysis.suggested_fix
@@ -182,7 +187,7 @@ impl SmartRecovery {
                     .unwrap()
                     .as_secs()
                     .to_string();
-                
+
                 Ok(Some(recovery_task))
             }
             ErrorType::Memory => {
@@ -205,7 +210,7 @@ impl SmartRecovery {
                     .unwrap()
                     .as_secs()
                     .to_string();
-                
+
                 Ok(Some(recovery_task))
             }
             ErrorType::API => {
@@ -229,18 +234,23 @@ impl SmartRecovery {
                     .unwrap()
                     .as_secs()
                     .to_string();
-                
+
                 Ok(Some(recovery_task))
             }
             ErrorType::Unknown => {
                 // Use LLM to suggest recovery
                 let error_msg = format!("Unknown error occurred in task: {}", original_task.title);
-                self.llm_recovery_suggestion(original_task, &error_msg).await
+                self.llm_recovery_suggestion(original_task, &error_msg)
+                    .await
             }
         }
     }
 
-    async fn llm_recovery_suggestion(&self, task: &AutomationTask, error: &str) -> Result<Option<AutomationTask>> {
+    async fn llm_recovery_suggestion(
+        &self,
+        task: &AutomationTask,
+        error: &str,
+    ) -> Result<Option<AutomationTask>> {
         let prompt = format!(
             r#"Analyze the following error and suggest a recovery approach:
 
@@ -275,9 +285,16 @@ Please suggest a specific recovery task that would fix this error. Respond with
             "max_tokens": 1000
         });
 
-        let response = self.client
+        let response = self
+            .client
             .post("https://api.openai.com/v1/chat/completions")
-            .header("Authorization", format!("Bearer {}", self.config.api_key.as_ref().ok_or("API key required")?))
+            .header(
+                "Authorization",
+                format!(
+                    "Bearer {}",
+                    self.config.api_key.as_ref().ok_or("API key required")?
+                ),
+            )
             .header("Content-Type", "application/json")
             .json(&request_body)
             .send()
@@ -285,7 +302,7 @@ Please suggest a specific recovery task that would fix this error. Respond with
 
         let response_text = response.text().await?;
         let response_json: serde_json::Value = serde_json::from_str(&response_text)?;
-        
+
         if let Some(content) = response_json
             .get("choices")
             .and_then(|c| c.get(0))
@@ -294,12 +311,17 @@ Please suggest a specific recovery task that would fix this error. Respond with
             .and_then(|c| c.as_str())
         {
             if let Ok(suggestion) = serde_json::from_str::<serde_json::Value>(content) {
-                if let Some(should_retry) = suggestion.get("should_retry").and_then(|v| v.as_bool()) {
+                if let Some(should_retry) = suggestion.get("should_retry").and_then(|v| v.as_bool())
+                {
                     if should_retry {
-                        if let Some(modified_desc) = suggestion.get("modified_description").and_then(|v| v.as_str()) {
+                        if let Some(modified_desc) = suggestion
+                            .get("modified_description")
+                            .and_then(|v| v.as_str())
+                        {
                             let mut recovery_task = task.clone();
                             recovery_task.id = uuid::Uuid::new_v4();
-                            recovery_task.title = format!("Retry {} with modifications", task.title);
+                            recovery_task.title =
+                                format!("Retry {} with modifications", task.title);
                             recovery_task.description = modified_desc.to_string();
                             recovery_task.dependencies = Vec::new();
                             recovery_task.created_at = std::time::SystemTime::now()
@@ -312,7 +334,7 @@ Please suggest a specific recovery task that would fix this error. Respond with
                                 .unwrap()
                                 .as_secs()
                                 .to_string();
-                            
+
                             return Ok(Some(recovery_task));
                         }
                     }
@@ -352,10 +374,14 @@ enum ErrorSeverity {
 #[async_trait]
 impl ErrorRecovery for SmartRecovery {
     async fn recover(&self, error: &str, task: &AutomationTask) -> Result<Option<AutomationTask>> {
-        log::info!("Attempting recovery for task: {}, error: {}", task.title, error);
-        
+        log::info!(
+            "Attempting recovery for task: {}, error: {}",
+            task.title,
+            error
+        );
+
         let analysis = self.analyze_error(error).await;
-        
+
         match analysis.severity {
             ErrorSeverity::High => {
                 log::warn!("High severity error detected: {}", analysis.suggested_fix);
@@ -375,7 +401,7 @@ impl ErrorRecovery for SmartRecovery {
 
     fn get_recovery_strategy(&self, error: &str, _task: &AutomationTask) -> RecoveryStrategy {
         let error_lower = error.to_lowercase();
-        
+
         if error_lower.contains("compilation") || error_lower.contains("syntax") {
             RecoveryStrategy::ModifyApproach
         } else if error_lower.contains("timeout") {
@@ -404,7 +430,7 @@ impl SimpleRecovery {
 impl ErrorRecovery for SimpleRecovery {
     async fn recover(&self, error: &str, task: &AutomationTask) -> Result<Option<AutomationTask>> {
         log::info!("Simple recovery for task: {}, error: {}", task.title, error);
-        
+
         // Simple recovery: just retry with a modified description
         if error.contains("failed") || error.contains("error") {
             let mut recovery_task = task.clone();
@@ -416,16 +442,16 @@ impl ErrorRecovery for SimpleRecovery {
             );
             recovery_task.dependencies = Vec::new();
             recovery_task.created_at = std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs()
-                    .to_string();
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+                .to_string();
             recovery_task.updated_at = std::time::SystemTime::now()
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap()
-                    .as_secs()
-                    .to_string();
-            
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap()
+                .as_secs()
+                .to_string();
+
             Ok(Some(recovery_task))
         } else {
             Ok(None)
@@ -1,11 +1,11 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::PathBuf;
 use std::process::Command;
-use anyhow::{Context, Result};
-use async_trait::async_trait;
 
-use super::engine::{AutomationResult, AutomationConfig, TaskType, TaskStatus};
+use super::engine::{AutomationConfig, AutomationResult, TaskStatus, TaskType};
 
 #[async_trait]
 pub trait TaskValidator: Send + Sync {
@@ -46,7 +46,10 @@ impl DefaultValidator {
         Ok(Self { config })
     }
 
-    async fn validate_code_generation(&self, result: &AutomationResult) -> Result<ValidationReport> {
+    async fn validate_code_generation(
+        &self,
+        result: &AutomationResult,
+    ) -> Result<ValidationReport> {
         let mut checks = Vec::new();
         let mut score = 100.0;
 
@@ -115,7 +118,8 @@ impl DefaultValidator {
                 checks.push(ValidationCheck {
                     name: "Code Volume".to_string(),
                     passed: false,
-                    message: "Excessive code generated, consider breaking into smaller modules".to_string(),
+                    message: "Excessive code generated, consider breaking into smaller modules"
+                        .to_string(),
                     severity: ValidationSeverity::Warning,
                 });
                 score -= 5.0;
@@ -124,7 +128,7 @@ impl DefaultValidator {
 
         let is_valid = score >= 70.0;
         let mut recommendations = Vec::new();
-        
+
         if !is_valid {
             recommendations.push("Review and fix compilation errors".to_string());
             if result.artifacts.is_empty() {
@@ -166,12 +170,12 @@ impl DefaultValidator {
         }
 
         // Check test results
-        let total_tests = result.metrics.get("tests_passed").unwrap_or(&0.0) 
+        let total_tests = result.metrics.get("tests_passed").unwrap_or(&0.0)
             + result.metrics.get("tests_failed").unwrap_or(&0.0);
-        
+
         if total_tests > 0.0 {
             let pass_rate = result.metrics.get("tests_passed").unwrap_or(&0.0) / total_tests;
-            
+
             if pass_rate < 0.8 {
                 checks.push(ValidationCheck {
                     name: "Test Pass Rate".to_string(),
@@ -192,7 +196,7 @@ impl DefaultValidator {
 
         let is_valid = score >= 70.0;
         let mut recommendations = Vec::new();
-        
+
         if !is_valid {
             recommendations.push("Improve test coverage and fix failing tests".to_string());
         }
@@ -223,7 +227,10 @@ impl DefaultValidator {
             checks.push(ValidationCheck {
                 name: "Documentation Files".to_string(),
                 passed: true,
-                message: format!("{} documentation files were created", result.artifacts.len()),
+                message: format!(
+                    "{} documentation files were created",
+                    result.artifacts.len()
+                ),
                 severity: ValidationSeverity::Info,
             });
         }
@@ -275,9 +282,10 @@ impl DefaultValidator {
 
         let is_valid = score >= 70.0;
         let mut recommendations = Vec::new();
-        
+
         if !is_valid {
-            recommendations.push("Expand documentation with more detailed explanations".to_string());
+            recommendations
+                .push("Expand documentation with more detailed explanations".to_string());
             if !has_readme {
                 recommendations.push("Create a comprehensive README.md file".to_string());
             }
@@ -300,7 +308,10 @@ impl TaskValidator for DefaultValidator {
         Ok(report.is_valid)
     }
 
-    fn get_validation_report(&self, result: &AutomationResult) -> Result<ValidationReport, anyhow::Error> {
+    fn get_validation_report(
+        &self,
+        result: &AutomationResult,
+    ) -> Result<ValidationReport, anyhow::Error> {
         // For now, we'll use a simplified synchronous validation
         // In a real implementation, this would be async
         match result.status {
@@ -372,7 +383,10 @@ impl TaskValidator for StrictValidator {
         Ok(report.is_valid && report.score >= 90.0)
     }
 
-    fn get_validation_report(&self, result: &AutomationResult) -> Result<ValidationReport, anyhow::Error> {
+    fn get_validation_report(
+        &self,
+        result: &AutomationResult,
+    ) -> Result<ValidationReport, anyhow::Error> {
         let mut checks = Vec::new();
         let mut score = 100.0;
 
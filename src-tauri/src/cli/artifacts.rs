@@ -0,0 +1,295 @@
+use schemars::JsonSchema;
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::fs::{self, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Component, Path, PathBuf};
+
+use crate::cli::http::{HttpBody, HttpRequest, HttpResponse};
+
+/// Largest body accepted by one `PUT /artifacts/...` chunk.
+pub const MAX_CHUNK_BYTES: usize = 8 * 1024 * 1024;
+const CHECKSUM_HEADER: &str = "X-Checksum-Sha256";
+const OFFSET_HEADER: &str = "Upload-Offset";
+
+/// `HEAD` body: a finished artifact, or how much of an upload has arrived.
+#[derive(Serialize, JsonSchema)]
+pub struct ArtifactStatus {
+    pub size: u64,
+    pub complete: bool,
+}
+
+/// `PUT` body after a chunk that does not finish the upload.
+#[derive(Serialize, JsonSchema)]
+pub struct UploadProgress {
+    pub offset: u64,
+    pub total: u64,
+}
+
+/// `PUT` body once the last chunk is in and the checksum matched.
+#[derive(Serialize, JsonSchema)]
+pub struct UploadComplete {
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// Run outputs under `.taurihands/artifacts/<run>/<name>`, served with ranged
+/// GETs and filled by resumable chunked PUTs:
+///
+/// - `GET` honours `Range: bytes=a-b` and returns the full-file SHA-256.
+/// - `HEAD` reports the size of a finished file, or `Upload-Offset` for a
+///   partial upload so clients know where to resume.
+/// - `PUT` with `Content-Range: bytes a-b/total` appends a chunk at `a`; the
+///   last chunk is checked against `X-Checksum-Sha256` before it is published.
+pub struct ArtifactStore {
+    root: PathBuf,
+}
+
+impl ArtifactStore {
+    pub fn new(workspace: &Path) -> Self {
+        Self {
+            root: workspace.join(".taurihands").join("artifacts"),
+        }
+    }
+
+    pub fn handle(&self, request: &HttpRequest) -> HttpResponse {
+        let relative = request.path.trim_start_matches("/artifacts/");
+        let path = match self.resolve(relative) {
+            Ok(path) => path,
+            Err(err) => return HttpResponse::error(400, err),
+        };
+        let result = match request.method.as_str() {
+            "GET" => self.get(&path, request.header("Range")),
+            "HEAD" => self.head(&path),
+            "PUT" => self.put(&path, request),
+            _ => Ok(HttpResponse::error(404, "not found")),
+        };
+        result.unwrap_or_else(|err| HttpResponse::error(500, err))
+    }
+
+    fn resolve(&self, relative: &str) -> Result<PathBuf, String> {
+        let relative = Path::new(relative);
+        let valid = relative.components().count() >= 2
+            && relative
+                .components()
+                .all(|component| matches!(component, Component::Normal(_)));
+        if !valid {
+            return Err("artifact path must look like <run>/<name>".to_string());
+        }
+        let name = relative.to_string_lossy();
+        if name.ends_with(".part") || name.ends_with(".sha256") {
+            return Err("reserved artifact name".to_string());
+        }
+        Ok(self.root.join(relative))
+    }
+
+    fn get(&self, path: &Path, range: Option<&str>) -> Result<HttpResponse, String> {
+        let Ok(meta) = fs::metadata(path) else {
+            return Ok(HttpResponse::error(404, "artifact not found"));
+        };
+        let total = meta.len();
+        let checksum = cached_checksum(path)?;
+        let (status, start, end) = match range {
+            None => (200, 0, total.saturating_sub(1)),
+            Some(range) => match parse_range(range, total) {
+                Some((start, end)) => (206, start, end),
+                None => {
+                    return Ok(HttpResponse::error(416, "range not satisfiable")
+                        .with_header("Content-Range", format!("bytes */{}", total)));
+                }
+            },
+        };
+        let len = if total == 0 { 0 } else { end - start + 1 };
+        let mut response = HttpResponse {
+            status,
+            headers: Vec::new(),
+            body: HttpBody::File {
+                path: path.to_path_buf(),
+                start,
+                len,
+            },
+        }
+        .with_header("Content-Type", "application/octet-stream")
+        .with_header("Accept-Ranges", "bytes")
+        .with_header("ETag", format!("\"{}\"", checksum))
+        .with_header(CHECKSUM_HEADER, checksum);
+        if status == 206 {
+            response = response.with_header(
+                "Content-Range",
+                format!("bytes {}-{}/{}", start, end, total),
+            );
+        }
+        Ok(response)
+    }
+
+    fn head(&self, path: &Path) -> Result<HttpResponse, String> {
+        if let Ok(meta) = fs::metadata(path) {
+            let status = ArtifactStatus {
+                size: meta.len(),
+                complete: true,
+            };
+            return Ok(HttpResponse::json(200, to_json(status))
+                .with_header("Accept-Ranges", "bytes")
+                .with_header(CHECKSUM_HEADER, cached_checksum(path)?));
+        }
+        match fs::metadata(part_path(path)) {
+            Ok(meta) => Ok(HttpResponse::json(
+                200,
+                to_json(ArtifactStatus {
+                    size: meta.len(),
+                    complete: false,
+                }),
+            )
+            .with_header(OFFSET_HEADER, meta.len().to_string())),
+            Err(_) => Ok(HttpResponse::error(404, "artifact not found")),
+        }
+    }
+
+    fn put(&self, path: &Path, request: &HttpRequest) -> Result<HttpResponse, String> {
+        let body = &request.body;
+        let (start, total) = match request.header("Content-Range") {
+            Some(range) => match parse_content_range(range, body.len() as u64) {
+                Some(parsed) => parsed,
+                None => return Ok(HttpResponse::error(400, "invalid Content-Range")),
+            },
+            None => (0, body.len() as u64),
+        };
+        let part = part_path(path);
+        if start == 0 {
+            if let Some(parent) = part.parent() {
+                fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            // A chunk at offset 0 restarts the upload.
+            File::create(&part).map_err(|e| e.to_string())?;
+        }
+        let offset = fs::metadata(&part).map(|meta| meta.len()).ok();
+        if offset != Some(start) {
+            let offset = offset.unwrap_or(0);
+            return Ok(
+                HttpResponse::error(409, format!("expected chunk at offset {}", offset))
+                    .with_header(OFFSET_HEADER, offset.to_string()),
+            );
+        }
+        let mut file = OpenOptions::new()
+            .append(true)
+            .open(&part)
+            .map_err(|e| e.to_string())?;
+        file.write_all(body).map_err(|e| e.to_string())?;
+        let offset = start + body.len() as u64;
+        if offset < total {
+            return Ok(
+                HttpResponse::json(202, to_json(UploadProgress { offset, total }))
+                    .with_header(OFFSET_HEADER, offset.to_string()),
+            );
+        }
+        let checksum = file_checksum(&part)?;
+        if let Some(expected) = request.header(CHECKSUM_HEADER) {
+            if !expected.eq_ignore_ascii_case(&checksum) {
+                let _ = fs::remove_file(&part);
+                return Ok(HttpResponse::error(
+                    422,
+                    format!("checksum mismatch: received {}", checksum),
+                ));
+            }
+        }
+        fs::rename(&part, path).map_err(|e| e.to_string())?;
+        let _ = fs::write(checksum_path(path), &checksum);
+        Ok(HttpResponse::json(
+            201,
+            to_json(UploadComplete {
+                size: offset,
+                sha256: checksum.clone(),
+            }),
+        )
+        .with_header(CHECKSUM_HEADER, checksum))
+    }
+}
+
+fn to_json<T: Serialize>(value: T) -> serde_json::Value {
+    serde_json::to_value(value).unwrap_or_default()
+}
+
+fn part_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".part");
+    PathBuf::from(name)
+}
+
+fn checksum_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".sha256");
+    PathBuf::from(name)
+}
+
+/// Reuses the sidecar written on upload unless the artifact changed since.
+fn cached_checksum(path: &Path) -> Result<String, String> {
+    let sidecar = checksum_path(path);
+    let fresh = match (fs::metadata(&sidecar), fs::metadata(path)) {
+        (Ok(sidecar_meta), Ok(meta)) => match (sidecar_meta.modified(), meta.modified()) {
+            (Ok(sidecar_time), Ok(time)) => sidecar_time >= time,
+            _ => false,
+        },
+        _ => false,
+    };
+    if fresh {
+        if let Ok(checksum) = fs::read_to_string(&sidecar) {
+            return Ok(checksum.trim().to_string());
+        }
+    }
+    let checksum = file_checksum(path)?;
+    let _ = fs::write(&sidecar, &checksum);
+    Ok(checksum)
+}
+
+pub fn file_checksum(path: &Path) -> Result<String, String> {
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    let mut hasher = Sha256::new();
+    let mut buffer = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buffer).map_err(|e| e.to_string())?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+/// Parses `bytes=a-b`, `bytes=a-` and `bytes=-n` into an inclusive range.
+fn parse_range(header: &str, total: u64) -> Option<(u64, u64)> {
+    let spec = header.trim().strip_prefix("bytes=")?;
+    // Multiple ranges are not supported; serve the first one.
+    let spec = spec.split(',').next()?.trim();
+    let (start, end) = spec.split_once('-')?;
+    if total == 0 {
+        return None;
+    }
+    let (start, end) = if start.is_empty() {
+        let suffix: u64 = end.parse().ok()?;
+        (total.saturating_sub(suffix), total - 1)
+    } else {
+        let start: u64 = start.parse().ok()?;
+        let end = if end.is_empty() {
+            total - 1
+        } else {
+            end.parse::<u64>().ok()?.min(total - 1)
+        };
+        (start, end)
+    };
+    (start <= end && start < total).then_some((start, end))
+}
+
+/// Parses `bytes a-b/total` and checks it matches the body length.
+fn parse_content_range(header: &str, body_len: u64) -> Option<(u64, u64)> {
+    let spec = header.trim().strip_prefix("bytes ")?;
+    let (range, total) = spec.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    let start: u64 = start.trim().parse().ok()?;
+    let end: u64 = end.trim().parse().ok()?;
+    let total: u64 = total.trim().parse().ok()?;
+    (end >= start && end - start + 1 == body_len && end < total).then_some((start, total))
+}
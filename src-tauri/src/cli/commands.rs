@@ -1,7 +1,9 @@
-use clap::{Parser, Subcommand, Args, ValueEnum};
+use clap::{Args, Parser, Subcommand, ValueEnum};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
 
+use crate::cli::tokens::TokenScope;
+
 #[derive(Parser)]
 #[command(name = "taurihands")]
 #[command(about = "TauriHands - AI-Driven Development Agent")]
@@ -46,8 +48,22 @@ pub enum Commands {
     Web(WebArgs),
     /// Start GUI server
     Serve(ServeArgs),
+    /// Issue, list and revoke scoped API tokens for `serve`
+    #[command(alias = "server_tokens")]
+    ServerTokens(ServerTokensArgs),
+    /// Mirror the workspace to a remote `serve --api` and pull changes back
+    Sync(SyncArgs),
     /// Configure settings
     Config(ConfigArgs),
+    /// Check runtime prerequisites and suggest fixes
+    Doctor(DoctorArgs),
+    /// Report disk usage of run data and delete what is past retention
+    #[command(alias = "storage_gc")]
+    StorageGc(StorageGcArgs),
+    /// Print a shell completion script to stdout
+    Completions(CompletionsArgs),
+    /// Generate man pages from the command definitions
+    Mangen(MangenArgs),
     /// Show version information
     Version,
 }
@@ -137,6 +153,151 @@ pub struct ServeArgs {
     /// Enable API access
     #[arg(short, long)]
     pub api: bool,
+
+    /// Maximum runs the /runs queue executes at once
+    #[arg(long, value_name = "NUM", default_value = "1")]
+    pub max_concurrency: usize,
+
+    /// Extra workspace a /runs request may name; the served workspace is
+    /// always allowed. Repeat for more
+    #[arg(long = "allow-workspace", value_name = "PATH")]
+    pub allow_workspaces: Vec<PathBuf>,
+
+    /// Detach and keep serving in the background
+    #[arg(long, conflicts_with_all = ["stop", "status"])]
+    pub daemon: bool,
+
+    /// Stop the background server
+    #[arg(long, conflicts_with = "status")]
+    pub stop: bool,
+
+    /// Show whether the background server is running
+    #[arg(long)]
+    pub status: bool,
+
+    /// Pidfile used by --daemon, --stop and --status
+    #[arg(long, value_name = "FILE")]
+    pub pidfile: Option<PathBuf>,
+}
+
+#[derive(Parser)]
+pub struct ServerTokensArgs {
+    #[command(subcommand)]
+    pub action: ServerTokenAction,
+
+    /// Workspace whose server tokens are managed
+    #[arg(short, long, value_name = "DIR", global = true)]
+    pub workspace: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+pub enum ServerTokenAction {
+    /// Issue a token; the secret is printed once
+    Issue {
+        /// Label shown in listings
+        #[arg(short, long)]
+        name: String,
+
+        /// Permissions granted to the token
+        #[arg(short, long, value_enum, default_value = "read")]
+        scope: TokenScope,
+
+        /// Expire the token after this many hours
+        #[arg(long, value_name = "HOURS")]
+        ttl_hours: Option<u64>,
+    },
+    /// List issued tokens
+    List,
+    /// Revoke a token by id
+    Revoke {
+        /// Token id from `list`
+        id: String,
+    },
+}
+
+#[derive(Parser)]
+pub struct SyncArgs {
+    #[command(subcommand)]
+    pub action: SyncAction,
+
+    /// Base URL of the remote server, e.g. http://buildbox:8080
+    #[arg(short, long, value_name = "URL", global = true)]
+    pub remote: Option<String>,
+
+    /// Server token; defaults to $TAURIHANDS_TOKEN
+    #[arg(long, value_name = "TOKEN", global = true)]
+    pub token: Option<String>,
+
+    /// Local workspace to sync
+    #[arg(short, long, value_name = "DIR", global = true)]
+    pub workspace: Option<PathBuf>,
+}
+
+#[derive(Subcommand)]
+pub enum SyncAction {
+    /// Upload local files that differ from the remote workspace
+    Push {
+        /// Delete remote files that do not exist locally
+        #[arg(long)]
+        delete: bool,
+    },
+    /// Download remote files that differ from the local workspace
+    Pull {
+        /// Delete local files that do not exist remotely
+        #[arg(long)]
+        delete: bool,
+    },
+    /// Push, run a task remotely, wait for it and pull the results
+    Run {
+        /// Task description
+        #[arg(short, long)]
+        task: String,
+
+        /// Maximum steps for the remote run
+        #[arg(short, long, value_name = "NUM")]
+        max_steps: Option<u32>,
+    },
+}
+
+#[derive(Parser)]
+pub struct DoctorArgs {
+    /// Print the checks as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Parser)]
+pub struct StorageGcArgs {
+    /// Delete the listed items; without it only the report is printed
+    #[arg(long)]
+    pub apply: bool,
+
+    /// Keep data younger than this many days instead of the saved policy
+    #[arg(long, value_name = "DAYS")]
+    pub retention_days: Option<u64>,
+
+    /// Workspace whose data is collected
+    #[arg(short, long, value_name = "DIR")]
+    pub workspace: Option<PathBuf>,
+
+    /// Print the report as JSON
+    #[arg(long)]
+    pub json: bool,
+}
+
+#[derive(Parser)]
+pub struct CompletionsArgs {
+    /// Shell to generate completions for
+    #[arg(value_enum)]
+    pub shell: clap_complete::Shell,
+}
+
+#[derive(Parser)]
+pub struct MangenArgs {
+    /// Write taurihands.1 and one page per subcommand here instead of
+    /// printing the top-level page to stdout
+    #[arg(short, long, value_name = "DIR")]
+    pub out_dir: Option<PathBuf>,
 }
 
 #[derive(Parser)]
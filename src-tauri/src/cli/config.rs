@@ -1,6 +1,6 @@
+use anyhow::{Context, Result};
 use serde::{Deserialize, Serialize};
 use std::path::PathBuf;
-use anyhow::{Context, Result};
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -33,10 +33,10 @@ pub fn load_config(config_path: Option<&PathBuf>) -> Result<Config> {
     if config_path.exists() {
         let content = std::fs::read_to_string(&config_path)
             .with_context(|| format!("Failed to read config file: {:?}", config_path))?;
-        
-        let config: Config = toml::from_str(&content)
-            .with_context(|| "Failed to parse config file")?;
-        
+
+        let config: Config =
+            toml::from_str(&content).with_context(|| "Failed to parse config file")?;
+
         log::debug!("Loaded configuration from: {:?}", config_path);
         Ok(config)
     } else {
@@ -55,19 +55,23 @@ pub fn save_config(config: &Config) -> Result<()> {
         std::fs::create_dir_all(parent)?;
     }
 
-    let content = toml::to_string_pretty(config)
-        .with_context(|| "Failed to serialize config")?;
-    
+    let content = toml::to_string_pretty(config).with_context(|| "Failed to serialize config")?;
+
     std::fs::write(&config_path, content)
         .with_context(|| format!("Failed to write config file: {:?}", config_path))?;
-    
+
     log::debug!("Saved configuration to: {:?}", config_path);
     Ok(())
 }
 
 pub fn get_workspace_path(config: &Config) -> Result<PathBuf> {
-    Ok(config.workspace
+    Ok(config
+        .workspace
         .clone()
-        .or_else(|| std::env::var("TAURIHANDS_WORKSPACE").ok().map(PathBuf::from))
+        .or_else(|| {
+            std::env::var("TAURIHANDS_WORKSPACE")
+                .ok()
+                .map(PathBuf::from)
+        })
         .unwrap_or_else(|| std::env::current_dir().unwrap()))
 }
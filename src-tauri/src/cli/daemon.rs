@@ -0,0 +1,107 @@
+use anyhow::{Context, Result};
+use std::fs::{self, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Default pidfile for `serve --daemon`, in the local data directory.
+pub fn default_pidfile() -> PathBuf {
+    dirs::data_local_dir()
+        .map(|dir| dir.join("taurihands").join("serve.pid"))
+        .unwrap_or_else(|| PathBuf::from("taurihands-serve.pid"))
+}
+
+/// Re-launches the current command without `--daemon`, detached from the
+/// terminal with output redirected to `serve.log` beside the pidfile.
+pub fn start_daemon(pidfile: &Path) -> Result<u32> {
+    if let Some(pid) = running_pid(pidfile) {
+        anyhow::bail!("Server already running (pid {})", pid);
+    }
+    if let Some(parent) = pidfile.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let log_path = pidfile.with_file_name("serve.log");
+    let log = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&log_path)
+        .with_context(|| format!("Failed to open log file: {:?}", log_path))?;
+    let args: Vec<String> = std::env::args()
+        .skip(1)
+        .filter(|arg| arg != "--daemon")
+        .collect();
+    let mut cmd = Command::new(std::env::current_exe()?);
+    cmd.args(&args)
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log);
+    detach(&mut cmd);
+    let child = cmd.spawn().context("Failed to start background server")?;
+    let pid = child.id();
+    fs::write(pidfile, format!("{}\n", pid))
+        .with_context(|| format!("Failed to write pidfile: {:?}", pidfile))?;
+    log::info!("Server logs: {:?}", log_path);
+    Ok(pid)
+}
+
+pub fn stop_daemon(pidfile: &Path) -> Result<Option<u32>> {
+    let Some(pid) = running_pid(pidfile) else {
+        return Ok(None);
+    };
+    let status = if cfg!(windows) {
+        Command::new("taskkill")
+            .args(["/PID", &pid.to_string(), "/T", "/F"])
+            .stdout(Stdio::null())
+            .status()?
+    } else {
+        Command::new("kill").arg(pid.to_string()).status()?
+    };
+    if !status.success() {
+        anyhow::bail!("Failed to stop server (pid {})", pid);
+    }
+    let _ = fs::remove_file(pidfile);
+    Ok(Some(pid))
+}
+
+/// Returns the pid from the pidfile if that process is still alive; a stale
+/// pidfile left by a crash is removed.
+pub fn running_pid(pidfile: &Path) -> Option<u32> {
+    let pid: u32 = fs::read_to_string(pidfile).ok()?.trim().parse().ok()?;
+    if process_alive(pid) {
+        Some(pid)
+    } else {
+        let _ = fs::remove_file(pidfile);
+        None
+    }
+}
+
+fn process_alive(pid: u32) -> bool {
+    if cfg!(windows) {
+        Command::new("tasklist")
+            .args(["/FI", &format!("PID eq {}", pid), "/NH"])
+            .output()
+            .map(|output| String::from_utf8_lossy(&output.stdout).contains(&pid.to_string()))
+            .unwrap_or(false)
+    } else {
+        Command::new("kill")
+            .args(["-0", &pid.to_string()])
+            .stderr(Stdio::null())
+            .status()
+            .map(|status| status.success())
+            .unwrap_or(false)
+    }
+}
+
+#[cfg(unix)]
+fn detach(cmd: &mut Command) {
+    use std::os::unix::process::CommandExt;
+    // A new process group keeps the server alive when the terminal closes.
+    cmd.process_group(0);
+}
+
+#[cfg(windows)]
+fn detach(cmd: &mut Command) {
+    use std::os::windows::process::CommandExt;
+    const DETACHED_PROCESS: u32 = 0x0000_0008;
+    const CREATE_NEW_PROCESS_GROUP: u32 = 0x0000_0200;
+    cmd.creation_flags(DETACHED_PROCESS | CREATE_NEW_PROCESS_GROUP);
+}
@@ -0,0 +1,77 @@
+use std::path::Path;
+use std::time::Duration;
+
+use crate::services::audit::AuditLog;
+use crate::services::events::EventSink;
+use crate::services::kernel::{
+    KernelManager, KernelPlanUpdateRequest, KernelStartRequest, RunAgentState, RunState,
+};
+use crate::services::pty::TerminalManager;
+use crate::services::workspace::WorkspaceState;
+
+const POLL_INTERVAL_MS: u64 = 500;
+
+/// One run without a UI, as given to `headless` or queued with `serve --api`.
+pub struct HeadlessTask {
+    pub goal: String,
+    /// Plan steps; the plan is generated by the LLM when empty.
+    pub steps: Vec<String>,
+    pub max_steps: Option<u32>,
+    pub tags: Vec<String>,
+}
+
+/// Starts a kernel run in `workspace` and polls it until it stops. Nobody can
+/// answer a question here, so a run waiting for the user is stopped and
+/// returned as `AwaitingUser`. `on_started` gets the run id once it exists.
+pub async fn run_to_completion(
+    workspace: &Path,
+    task: HeadlessTask,
+    sink: &dyn EventSink,
+    on_started: impl FnOnce(&str),
+) -> Result<RunState, String> {
+    let taurihands = workspace.join(".taurihands");
+    let kernel = KernelManager::new(
+        workspace.to_path_buf(),
+        TerminalManager::new(taurihands.join("terminal")),
+        WorkspaceState::new(workspace.to_path_buf()),
+        AuditLog::new(taurihands.join("audit.log")),
+        taurihands,
+    );
+    kernel
+        .update_plan(
+            sink,
+            KernelPlanUpdateRequest {
+                goal: task.goal,
+                auto_generate: Some(task.steps.is_empty()),
+                steps: task.steps,
+                generation_id: None,
+            },
+        )
+        .await?;
+    let started = kernel.start(
+        sink,
+        KernelStartRequest {
+            session_id: None,
+            max_steps: task.max_steps,
+            task_id: None,
+            readonly: false,
+            auto_clean_sessions: Some(true),
+            tags: task.tags,
+        },
+    )?;
+    on_started(&started.run_id);
+    loop {
+        tokio::time::sleep(Duration::from_millis(POLL_INTERVAL_MS)).await;
+        let state = kernel.snapshot();
+        if matches!(
+            state.agent_state,
+            RunAgentState::Running | RunAgentState::Paused
+        ) {
+            continue;
+        }
+        if state.agent_state == RunAgentState::AwaitingUser {
+            let _ = kernel.stop(sink);
+        }
+        return Ok(state);
+    }
+}
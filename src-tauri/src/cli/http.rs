@@ -0,0 +1,186 @@
+use anyhow::Result;
+use schemars::JsonSchema;
+use serde::Serialize;
+use std::path::PathBuf;
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+
+const MAX_HEADER_BYTES: usize = 64 * 1024;
+
+/// Minimal HTTP/1.1 request, enough for the REST endpoints served next to
+/// the WebSocket interface. Connections are closed after each response.
+pub struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(key, _)| key.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Body of every non-2xx JSON response.
+#[derive(Serialize, JsonSchema)]
+pub struct ErrorBody {
+    pub error: String,
+}
+
+pub enum HttpBody {
+    Bytes(Vec<u8>),
+    /// `len` bytes of the file starting at `start`, streamed from disk.
+    File {
+        path: PathBuf,
+        start: u64,
+        len: u64,
+    },
+}
+
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: HttpBody,
+}
+
+impl HttpResponse {
+    pub fn json(status: u16, value: serde_json::Value) -> Self {
+        Self {
+            status,
+            headers: vec![("Content-Type".to_string(), "application/json".to_string())],
+            body: HttpBody::Bytes(value.to_string().into_bytes()),
+        }
+    }
+
+    pub fn text(status: u16, content_type: &str, body: String) -> Self {
+        Self {
+            status,
+            headers: vec![("Content-Type".to_string(), content_type.to_string())],
+            body: HttpBody::Bytes(body.into_bytes()),
+        }
+    }
+
+    pub fn error(status: u16, message: impl Into<String>) -> Self {
+        let body = ErrorBody {
+            error: message.into(),
+        };
+        Self::json(status, serde_json::to_value(body).unwrap_or_default())
+    }
+
+    pub fn with_header(mut self, name: &str, value: impl Into<String>) -> Self {
+        self.headers.push((name.to_string(), value.into()));
+        self
+    }
+}
+
+/// Reads one request; bodies larger than `max_body` are rejected with 413.
+pub async fn read_request(
+    stream: &mut TcpStream,
+    max_body: usize,
+) -> Result<std::result::Result<HttpRequest, HttpResponse>> {
+    let mut buffer = Vec::new();
+    let mut chunk = [0u8; 8192];
+    let header_end = loop {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            anyhow::bail!("connection closed before request headers");
+        }
+        buffer.extend_from_slice(&chunk[..read]);
+        if let Some(index) = buffer.windows(4).position(|window| window == b"\r\n\r\n") {
+            break index + 4;
+        }
+        if buffer.len() > MAX_HEADER_BYTES {
+            return Ok(Err(HttpResponse::error(413, "request headers too large")));
+        }
+    };
+    let head = String::from_utf8_lossy(&buffer[..header_end]).to_string();
+    let mut lines = head.lines();
+    let mut parts = lines.next().unwrap_or_default().split_whitespace();
+    let method = parts.next().unwrap_or_default().to_string();
+    let path = parts
+        .next()
+        .unwrap_or_default()
+        .split('?')
+        .next()
+        .unwrap_or_default()
+        .to_string();
+    let headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_string(), value.trim().to_string()))
+        .collect();
+    let content_length = headers
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case("content-length"))
+        .and_then(|(_, value)| value.parse::<usize>().ok())
+        .unwrap_or(0);
+    if content_length > max_body {
+        return Ok(Err(HttpResponse::error(413, "request body too large")));
+    }
+    let mut body = buffer.split_off(header_end);
+    while body.len() < content_length {
+        let read = stream.read(&mut chunk).await?;
+        if read == 0 {
+            break;
+        }
+        body.extend_from_slice(&chunk[..read]);
+    }
+    body.truncate(content_length);
+    Ok(Ok(HttpRequest {
+        method,
+        path,
+        headers,
+        body,
+    }))
+}
+
+pub async fn write_response(stream: &mut TcpStream, response: HttpResponse) -> Result<()> {
+    let len = match &response.body {
+        HttpBody::Bytes(bytes) => bytes.len() as u64,
+        HttpBody::File { len, .. } => *len,
+    };
+    let mut head = format!(
+        "HTTP/1.1 {} {}\r\n",
+        response.status,
+        reason(response.status)
+    );
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    head.push_str(&format!(
+        "Content-Length: {}\r\nConnection: close\r\n\r\n",
+        len
+    ));
+    stream.write_all(head.as_bytes()).await?;
+    match response.body {
+        HttpBody::Bytes(bytes) => stream.write_all(&bytes).await?,
+        HttpBody::File { path, start, len } => {
+            let mut file = tokio::fs::File::open(path).await?;
+            file.seek(std::io::SeekFrom::Start(start)).await?;
+            tokio::io::copy(&mut file.take(len), stream).await?;
+        }
+    }
+    stream.shutdown().await?;
+    Ok(())
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        202 => "Accepted",
+        206 => "Partial Content",
+        400 => "Bad Request",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        404 => "Not Found",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        416 => "Range Not Satisfiable",
+        422 => "Unprocessable Entity",
+        _ => "Internal Server Error",
+    }
+}
@@ -1,13 +1,23 @@
-use clap::Parser;
+use clap::{CommandFactory, Parser};
 use std::path::PathBuf;
 
 use crate::cli::commands::{Cli, Commands};
-use crate::cli::config::{Config, load_config, save_config};
+use crate::cli::config::{Config, get_workspace_path, load_config, save_config};
+use crate::cli::headless::{run_to_completion, HeadlessTask};
+use crate::cli::daemon::{default_pidfile, running_pid, start_daemon, stop_daemon};
 use crate::cli::tui::start_terminal_mode;
 use crate::cli::server::{start_web_server, start_gui_server};
 use crate::cli::commands::{RunArgs, HeadlessArgs, WebArgs, ServeArgs, ConfigArgs};
-use crate::services::kernel::KernelManager;
+use crate::cli::commands::{CompletionsArgs, DoctorArgs, MangenArgs, StorageGcArgs};
+use crate::cli::commands::{ServerTokenAction, ServerTokensArgs, SyncAction, SyncArgs};
+use crate::cli::sync::{SyncClient, SyncReport};
+use crate::cli::tokens::TokenStore;
+use crate::services::doctor::{run_doctor, CheckStatus};
+use crate::services::at_rest::AtRest;
+use crate::services::events::{EventSink, JsonlEventSink};
+use crate::services::kernel::RunAgentState;
 use crate::services::llm::LlmStore;
+use crate::services::storage_gc::{load_gc_policy, storage_gc};
 use crate::automation::engine::{TauriHandsEngine, AutomationConfig};
 use anyhow::{Context, Result};
 
@@ -48,13 +58,24 @@ pub async fn run_cli() -> Result<()> {
             cli.codex_model, cli.codex_reasoning, cli.codex_approval);
     }
 
+    // Shares the desktop app's trust decisions and upload settings.
+    if let Some(dir) = dirs::data_dir() {
+        crate::services::app_data::configure_app_data_dir(dir.join(APP_IDENTIFIER));
+    }
+
     match cli.command {
         Commands::Run(args) => run_command(args, &config).await?,
         Commands::Terminal(args) => start_terminal_mode(args)?,
         Commands::Headless(args) => headless_command(args, &config).await?,
         Commands::Web(args) => web_command(args, &config).await?,
         Commands::Serve(args) => serve_command(args, &config).await?,
+        Commands::ServerTokens(args) => server_tokens_command(args, &config)?,
+        Commands::Sync(args) => sync_command(args, &config).await?,
         Commands::Config(args) => config_command(args, &config)?,
+        Commands::Doctor(args) => doctor_command(args).await?,
+        Commands::StorageGc(args) => storage_gc_command(args, &config)?,
+        Commands::Completions(args) => completions_command(args),
+        Commands::Mangen(args) => mangen_command(args)?,
         Commands::Version => {
             println!("TauriHands {}", env!("CARGO_PKG_VERSION"));
             println!("AI-Driven Development Agent");
@@ -143,6 +164,8 @@ async fn run_command(args: RunArgs, config: &Config) -> Result<()> {
     Ok(())
 }
 
+/// Drives one kernel run to completion, streaming its events to stdout as
+/// JSONL. Fails unless the run finishes.
 async fn headless_command(args: HeadlessArgs, config: &Config) -> Result<()> {
     let workspace = args.workspace.unwrap_or_else(|| config.workspace.clone().unwrap_or_else(|| std::env::current_dir().unwrap()));
     
@@ -151,63 +174,77 @@ async fn headless_command(args: HeadlessArgs, config: &Config) -> Result<()> {
     log::info!("Workspace: {:?}", workspace);
     log::info!("Output format: {:?}", args.output);
 
-    // Initialize kernel
-    let llm_store = LlmStore::new()?;
-    let mut kernel = KernelManager::new(
-        workspace.clone(),
-        Default::default(),
-        Default::default(),
-        Default::default(),
-        workspace.join(".taurihands"),
-    )?;
+    let events = JsonlEventSink;
+    let task = HeadlessTask {
+        goal: args.task.clone(),
+        steps: Vec::new(),
+        max_steps: None,
+        tags: vec!["headless".to_string()],
+    };
+    let state = run_to_completion(&workspace, task, &events, |_| {})
+        .await
+        .map_err(anyhow::Error::msg)?;
 
-    // Execute task
-    log::info!("Executing task: {}", args.task);
-    
-    // TODO: Implement actual task execution
-    let result = format!("Task completed: {}", args.task);
-    
+    let output = serde_json::json!({
+        "task": args.task,
+        "runId": state.run_id,
+        "status": state.agent_state,
+        "error": state.last_error,
+    });
     match args.output {
         crate::cli::commands::OutputFormat::Json => {
-            let output = serde_json::json!({
-                "task": args.task,
-                "result": result,
-                "status": "completed"
-            });
-            
             if let Some(output_file) = &args.output_file {
                 std::fs::write(output_file, serde_json::to_string_pretty(&output)?)?;
                 log::info!("Output saved to: {:?}", output_file);
             } else {
-                println!("{}", serde_json::to_string_pretty(&output)?);
+                // The last line of the stream, so stdout stays valid JSONL.
+                events.emit_json("HeadlessResult", output.clone());
             }
         }
         crate::cli::commands::OutputFormat::Yaml => {
-            let output = serde_yaml::to_string(&serde_json::json!({
-                "task": args.task,
-                "result": result,
-                "status": "completed"
-            }))?;
+            let rendered = serde_yaml::to_string(&output)?;
             
             if let Some(output_file) = &args.output_file {
-                std::fs::write(output_file, output)?;
+                std::fs::write(output_file, rendered)?;
                 log::info!("Output saved to: {:?}", output_file);
             } else {
-                println!("{}", output);
+                eprintln!("{}", rendered);
             }
         }
         crate::cli::commands::OutputFormat::Text => {
-            let output = format!("Task: {}\nResult: {}\nStatus: completed", args.task, result);
+            let rendered = format!(
+                "Task: {}\nRun: {}\nStatus: {:?}{}",
+                args.task,
+                state.run_id,
+                state.agent_state,
+                state
+                    .last_error
+                    .as_ref()
+                    .map(|error| format!("\nError: {}", error))
+                    .unwrap_or_default()
+            );
             
             if let Some(output_file) = &args.output_file {
-                std::fs::write(output_file, output)?;
+                std::fs::write(output_file, rendered)?;
                 log::info!("Output saved to: {:?}", output_file);
             } else {
-                println!("{}", output);
+                eprintln!("{}", rendered);
             }
         }
     }
 
+    // CI must not mistake a stalled or stopped run for a successful one.
+    if state.agent_state != RunAgentState::Finished {
+        anyhow::bail!(
+            "run {} ended as {:?}{}",
+            state.run_id,
+            state.agent_state,
+            state
+                .last_error
+                .map(|error| format!(": {}", error))
+                .unwrap_or_default()
+        );
+    }
     Ok(())
 }
 
@@ -221,11 +258,264 @@ async fn web_command(args: WebArgs, config: &Config) -> Result<()> {
 }
 
 async fn serve_command(args: ServeArgs, config: &Config) -> Result<()> {
+    let pidfile = args.pidfile.clone().unwrap_or_else(default_pidfile);
+    if args.status {
+        match running_pid(&pidfile) {
+            Some(pid) => println!("Server running (pid {}), pidfile {:?}", pid, pidfile),
+            None => println!("Server not running"),
+        }
+        return Ok(());
+    }
+    if args.stop {
+        match stop_daemon(&pidfile)? {
+            Some(pid) => println!("Stopped server (pid {})", pid),
+            None => println!("Server not running"),
+        }
+        return Ok(());
+    }
+    if args.daemon {
+        let pid = start_daemon(&pidfile)?;
+        println!("🖥️ GUI server started in background on {}:{} (pid {})", args.host, args.port, pid);
+        return Ok(());
+    }
+
     log::info!("Starting GUI server on {}:{}", args.host, args.port);
     
     let workspace = config.workspace.clone().unwrap_or_else(|| std::env::current_dir().unwrap());
     
-    start_gui_server(&workspace, &args.host, args.port, args.api).await?;
+    start_gui_server(
+        &workspace,
+        &args.host,
+        args.port,
+        args.api,
+        args.max_concurrency,
+        args.allow_workspaces,
+    )
+    .await?;
+    Ok(())
+}
+
+fn server_tokens_command(args: ServerTokensArgs, config: &Config) -> Result<()> {
+    let workspace = match args.workspace {
+        Some(workspace) => workspace,
+        None => get_workspace_path(config)?,
+    };
+    let store = TokenStore::new(&workspace);
+    match args.action {
+        ServerTokenAction::Issue { name, scope, ttl_hours } => {
+            let (token, secret) = store.issue(&name, scope, ttl_hours)?;
+            println!("Issued {:?} token {} ({})", token.scope, token.id, token.name);
+            println!("{}", secret);
+            println!("Store this secret now; it cannot be shown again.");
+        }
+        ServerTokenAction::List => {
+            let tokens = store.list();
+            if tokens.is_empty() {
+                println!("No tokens issued; the server accepts unauthenticated requests");
+            }
+            for token in tokens {
+                let expires = token
+                    .expires_at
+                    .map(|at| format!("expires at {}", at))
+                    .unwrap_or_else(|| "no expiry".to_string());
+                let scope = format!("{:?}", token.scope);
+                println!("{}  {:<8} {}  ({})", token.id, scope, token.name, expires);
+            }
+        }
+        ServerTokenAction::Revoke { id } => {
+            if store.revoke(&id)? {
+                println!("Revoked token {}", id);
+            } else {
+                anyhow::bail!("No token with id {}", id);
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn sync_command(args: SyncArgs, config: &Config) -> Result<()> {
+    let remote = args
+        .remote
+        .context("--remote is required, e.g. --remote http://buildbox:8080")?;
+    let workspace = match args.workspace {
+        Some(workspace) => workspace,
+        None => get_workspace_path(config)?,
+    };
+    let token = args
+        .token
+        .or_else(|| std::env::var("TAURIHANDS_TOKEN").ok());
+    let client = SyncClient::new(&remote, token, workspace);
+    match args.action {
+        SyncAction::Push { delete } => print_sync_report("Pushed", &client.push(delete).await?),
+        SyncAction::Pull { delete } => print_sync_report("Pulled", &client.pull(delete).await?),
+        SyncAction::Run { task, max_steps } => {
+            // Mirror exactly, so the remote run sees the same tree as this one.
+            print_sync_report("Pushed", &client.push(true).await?);
+            println!("🚀 Running on {}: {}", remote, task);
+            let run = client.run_remote(&task, max_steps).await?;
+            let status = run["status"].as_str().unwrap_or("unknown");
+            println!("Remote run {}: {}", run["id"].as_str().unwrap_or_default(), status);
+            if let Some(error) = run["error"].as_str() {
+                println!("  Error: {}", error);
+            }
+            print_sync_report("Pulled", &client.pull(false).await?);
+            if status != "finished" {
+                anyhow::bail!("remote run ended as {}", status);
+            }
+        }
+    }
+    Ok(())
+}
+
+fn print_sync_report(verb: &str, report: &SyncReport) {
+    println!(
+        "{} {} file(s), deleted {}, {} unchanged",
+        verb,
+        report.transferred.len(),
+        report.deleted.len(),
+        report.unchanged
+    );
+    for path in &report.transferred {
+        println!("  {}", path);
+    }
+    for path in &report.deleted {
+        println!("  - {}", path);
+    }
+    if !report.skipped.is_empty() {
+        println!("Skipped (too large): {}", report.skipped.join(", "));
+    }
+}
+
+fn storage_gc_command(args: StorageGcArgs, config: &Config) -> Result<()> {
+    let workspace = match args.workspace {
+        Some(workspace) => workspace,
+        None => get_workspace_path(config)?,
+    };
+    let mut policy = load_gc_policy(&workspace);
+    if let Some(days) = args.retention_days {
+        policy.retention_days = days;
+    }
+    // The CLI cannot see the desktop app's live run or terminals, so only the
+    // retention period and deleted tasks protect data here.
+    let at_rest = AtRest::for_workspace(&workspace);
+    let report = storage_gc(&workspace, &at_rest, &policy, &Default::default(), args.apply);
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+    for usage in &report.categories {
+        println!(
+            "{:<10} {:>6} file(s) {:>12}  reclaimable {}",
+            usage.category,
+            usage.files,
+            format_bytes(usage.bytes),
+            format_bytes(usage.reclaimable_bytes)
+        );
+    }
+    for candidate in &report.candidates {
+        println!(
+            "  {} {} ({}, {})",
+            if report.applied { "deleted" } else { "would delete" },
+            candidate.path,
+            format_bytes(candidate.bytes),
+            candidate.reason
+        );
+    }
+    for error in &report.errors {
+        println!("  ⚠️ {}", error);
+    }
+    if report.applied {
+        println!("Freed {}", format_bytes(report.freed_bytes));
+    } else if !report.candidates.is_empty() {
+        let total: u64 = report.candidates.iter().map(|candidate| candidate.bytes).sum();
+        println!("Dry run: {} reclaimable; rerun with --apply to delete", format_bytes(total));
+    } else {
+        println!(
+            "Nothing older than {} days or left by deleted tasks",
+            report.retention_days
+        );
+    }
+    Ok(())
+}
+
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: &[&str] = &["B", "KB", "MB", "GB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} B", bytes)
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+/// Same identifier as tauri.conf.json, so the CLI sees the desktop app's data.
+const APP_IDENTIFIER: &str = "com.dains.taurihands";
+
+async fn doctor_command(args: DoctorArgs) -> Result<()> {
+    let data_dir = dirs::data_dir()
+        .map(|dir| dir.join(APP_IDENTIFIER))
+        .unwrap_or_else(|| PathBuf::from(".taurihands"));
+    let profile = LlmStore::new(data_dir.clone()).get_active_profile();
+    let checks = run_doctor(&data_dir, profile.as_ref()).await;
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&checks)?);
+    } else {
+        for check in &checks {
+            let icon = match check.status {
+                CheckStatus::Ok => "✅",
+                CheckStatus::Warn => "⚠️",
+                CheckStatus::Fail => "❌",
+            };
+            println!("{} {:<12} {}", icon, check.name, check.detail);
+            if let Some(fix) = &check.fix {
+                println!("   fix: {}", fix);
+            }
+        }
+    }
+    let failed = checks
+        .iter()
+        .filter(|check| check.status == CheckStatus::Fail)
+        .count();
+    if failed > 0 {
+        anyhow::bail!("{} check(s) failed", failed);
+    }
+    Ok(())
+}
+
+fn completions_command(args: CompletionsArgs) {
+    let mut command = Cli::command();
+    let name = command.get_name().to_string();
+    clap_complete::generate(args.shell, &mut command, name, &mut std::io::stdout());
+}
+
+fn mangen_command(args: MangenArgs) -> Result<()> {
+    let command = Cli::command();
+    let Some(out_dir) = args.out_dir else {
+        clap_mangen::Man::new(command).render(&mut std::io::stdout())?;
+        return Ok(());
+    };
+    std::fs::create_dir_all(&out_dir)
+        .with_context(|| format!("Failed to create {:?}", out_dir))?;
+    let name = command.get_name().to_string();
+    let mut pages = vec![(name.clone(), command.clone())];
+    for subcommand in command.get_subcommands() {
+        let title = format!("{}-{}", name, subcommand.get_name());
+        pages.push((title, subcommand.clone()));
+    }
+    for (title, page) in pages {
+        let mut buffer = Vec::new();
+        clap_mangen::Man::new(page)
+            .title(title.clone())
+            .render(&mut buffer)?;
+        let path = out_dir.join(format!("{}.1", title));
+        std::fs::write(&path, buffer).with_context(|| format!("Failed to write {:?}", path))?;
+        println!("{}", path.display());
+    }
     Ok(())
 }
 
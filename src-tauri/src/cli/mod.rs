@@ -1,8 +1,16 @@
 #![cfg(feature = "cli")]
 
+pub mod artifacts;
 pub mod commands;
 pub mod config;
+pub mod daemon;
+pub mod headless;
+pub mod http;
+pub mod openapi;
+pub mod queue;
 pub mod server;
+pub mod sync;
+pub mod tokens;
 pub mod tui;
 
 pub use commands::*;
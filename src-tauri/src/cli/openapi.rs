@@ -0,0 +1,244 @@
+use schemars::gen::{SchemaGenerator, SchemaSettings};
+use schemars::JsonSchema;
+use serde_json::{json, Value};
+
+use crate::cli::artifacts::{ArtifactStatus, UploadComplete, UploadProgress};
+use crate::cli::http::ErrorBody;
+use crate::cli::queue::{QueueSnapshot, QueuedRun, RunRequest};
+use crate::cli::sync::{SyncEntry, SyncManifest};
+use crate::cli::tokens::{IssueTokenRequest, IssuedToken, ServerToken};
+
+/// OpenAPI 3.0 description of the serve-mode REST endpoints. Schemas are
+/// generated from the same structs the handlers serialize, so the document
+/// cannot drift from the wire format.
+pub fn openapi_document() -> Value {
+    let mut generator = SchemaSettings::openapi3().into_generator();
+    let error = json_content(schema::<ErrorBody>(&mut generator));
+    let artifact_params = json!([
+        path_param("run", "Run id the artifact belongs to"),
+        path_param("name", "Artifact file name"),
+    ]);
+    let paths = json!({
+        "/runs": {
+            "get": {
+                "summary": "List queued, running and finished runs",
+                "responses": {
+                    "200": response(
+                        "Queue snapshot",
+                        json_content(schema::<QueueSnapshot>(&mut generator)),
+                    ),
+                },
+            },
+            "post": {
+                "summary": "Enqueue a run",
+                "requestBody": {
+                    "required": true,
+                    "content": json_content(schema::<RunRequest>(&mut generator)),
+                },
+                "responses": {
+                    "201": response(
+                        "Run queued",
+                        json_content(schema::<QueuedRun>(&mut generator)),
+                    ),
+                    "400": response("Invalid request", error.clone()),
+                },
+            },
+        },
+        "/artifacts/{run}/{name}": {
+            "parameters": artifact_params,
+            "get": {
+                "summary": "Download an artifact, optionally a byte range",
+                "parameters": [header_param("Range", "`bytes=a-b`, `bytes=a-` or `bytes=-n`")],
+                "responses": {
+                    "200": response("Whole artifact", binary_content()),
+                    "206": response("Requested range", binary_content()),
+                    "404": response("Artifact not found", error.clone()),
+                    "416": response("Range not satisfiable", error.clone()),
+                },
+            },
+            "head": {
+                "summary": "Size and checksum, or the resume offset of a partial upload",
+                "responses": {
+                    "200": response(
+                        "Artifact status",
+                        json_content(schema::<ArtifactStatus>(&mut generator)),
+                    ),
+                    "404": response("Artifact not found", error.clone()),
+                },
+            },
+            "put": {
+                "summary": "Upload an artifact, whole or in resumable chunks",
+                "parameters": [
+                    header_param("Content-Range", "`bytes a-b/total` for chunked uploads"),
+                    header_param("X-Checksum-Sha256", "Expected SHA-256 of the whole file"),
+                ],
+                "requestBody": { "required": true, "content": binary_content() },
+                "responses": {
+                    "201": response(
+                        "Upload complete",
+                        json_content(schema::<UploadComplete>(&mut generator)),
+                    ),
+                    "202": response(
+                        "Chunk stored",
+                        json_content(schema::<UploadProgress>(&mut generator)),
+                    ),
+                    "409": response("Chunk does not start at the current offset", error.clone()),
+                    "422": response("Checksum mismatch", error.clone()),
+                },
+            },
+        },
+        "/sync/manifest": {
+            "get": {
+                "summary": "List workspace files with their sizes and SHA-256 hashes",
+                "responses": {
+                    "200": response(
+                        "Workspace manifest",
+                        json_content(schema::<SyncManifest>(&mut generator)),
+                    ),
+                },
+            },
+        },
+        "/sync/files/{path}": {
+            "parameters": [path_param(
+                "path",
+                "Workspace-relative path; segments percent-encoded, `/` kept",
+            )],
+            "get": {
+                "summary": "Download a workspace file",
+                "responses": {
+                    "200": response("File contents", binary_content()),
+                    "404": response("File not found", error.clone()),
+                },
+            },
+            "put": {
+                "summary": "Replace a workspace file",
+                "parameters": [header_param("X-Checksum-Sha256", "Expected SHA-256 of the body")],
+                "requestBody": { "required": true, "content": binary_content() },
+                "responses": {
+                    "200": response(
+                        "File written",
+                        json_content(schema::<SyncEntry>(&mut generator)),
+                    ),
+                    "422": response("Checksum mismatch", error.clone()),
+                },
+            },
+            "delete": {
+                "summary": "Delete a workspace file",
+                "responses": {
+                    "200": response("File deleted", json_content(json!({
+                        "type": "object",
+                        "properties": { "deleted": { "type": "boolean" } },
+                    }))),
+                    "404": response("File not found", error.clone()),
+                },
+            },
+        },
+        "/tokens": {
+            "get": {
+                "summary": "List server tokens",
+                "responses": {
+                    "200": response("Issued tokens", json_content(json!({
+                        "type": "array",
+                        "items": schema::<ServerToken>(&mut generator),
+                    }))),
+                },
+            },
+            "post": {
+                "summary": "Issue a server token",
+                "requestBody": {
+                    "required": true,
+                    "content": json_content(schema::<IssueTokenRequest>(&mut generator)),
+                },
+                "responses": {
+                    "201": response(
+                        "Token and its one-time secret",
+                        json_content(schema::<IssuedToken>(&mut generator)),
+                    ),
+                    "400": response("Invalid request", error.clone()),
+                },
+            },
+        },
+        "/tokens/{id}": {
+            "parameters": [path_param("id", "Token id")],
+            "delete": {
+                "summary": "Revoke a server token",
+                "responses": {
+                    "200": response("Token revoked", json_content(json!({
+                        "type": "object",
+                        "properties": { "revoked": { "type": "string" } },
+                    }))),
+                    "404": response("Token not found", error.clone()),
+                },
+            },
+        },
+        "/metrics": {
+            "get": {
+                "summary": "Prometheus metrics for runs, tool calls, LLM requests and terminals",
+                "responses": {
+                    "200": response("Prometheus text exposition", json!({
+                        "text/plain": { "schema": { "type": "string" } },
+                    })),
+                },
+            },
+        },
+        "/openapi.json": {
+            "get": {
+                "summary": "This document",
+                "security": [],
+                "responses": { "200": response("OpenAPI document", json_content(json!({}))) },
+            },
+        },
+    });
+    json!({
+        "openapi": "3.0.3",
+        "info": {
+            "title": "TauriHands serve API",
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        "paths": paths,
+        "components": {
+            "schemas": generator.take_definitions(),
+            "securitySchemes": {
+                "bearer": { "type": "http", "scheme": "bearer" },
+            },
+        },
+        // Only enforced once a token has been issued with `server-tokens`.
+        "security": [{ "bearer": [] }],
+    })
+}
+
+fn schema<T: JsonSchema>(generator: &mut SchemaGenerator) -> Value {
+    serde_json::to_value(generator.subschema_for::<T>()).unwrap_or_default()
+}
+
+fn json_content(schema: Value) -> Value {
+    json!({ "application/json": { "schema": schema } })
+}
+
+fn binary_content() -> Value {
+    json!({ "application/octet-stream": { "schema": { "type": "string", "format": "binary" } } })
+}
+
+fn response(description: &str, content: Value) -> Value {
+    json!({ "description": description, "content": content })
+}
+
+fn path_param(name: &str, description: &str) -> Value {
+    json!({
+        "name": name,
+        "in": "path",
+        "required": true,
+        "description": description,
+        "schema": { "type": "string" },
+    })
+}
+
+fn header_param(name: &str, description: &str) -> Value {
+    json!({
+        "name": name,
+        "in": "header",
+        "required": false,
+        "description": description,
+        "schema": { "type": "string" },
+    })
+}
@@ -0,0 +1,338 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::sync::Notify;
+use uuid::Uuid;
+
+use crate::cli::headless::{run_to_completion, HeadlessTask};
+use crate::services::audit::now_ms;
+use crate::services::events::{EventSink, SharedEventSink};
+use crate::services::kernel::RunAgentState;
+
+const DEFAULT_MAX_STEPS: u32 = 30;
+/// Ended runs kept for `GET /runs`; older ones are dropped as new runs end.
+const MAX_ENDED_RUNS: usize = 200;
+
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct RunRequest {
+    pub task: String,
+    /// Higher runs first; equal priorities run in submission order.
+    #[serde(default)]
+    pub priority: i32,
+    /// Plan steps; the plan is generated by the LLM when empty.
+    #[serde(default)]
+    pub steps: Vec<String>,
+    /// The served workspace or one passed with `--allow-workspace`.
+    pub workspace: Option<PathBuf>,
+    pub max_steps: Option<u32>,
+}
+
+#[derive(Clone, Copy, PartialEq, Serialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueueStatus {
+    Queued,
+    Running,
+    Finished,
+    Failed,
+    /// The agent asked a question nobody can answer in headless mode.
+    Blocked,
+}
+
+#[derive(Clone, Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QueuedRun {
+    pub id: String,
+    pub task: String,
+    pub priority: i32,
+    /// Canonical, so two spellings of one directory never run at once.
+    pub workspace: PathBuf,
+    pub status: QueueStatus,
+    pub run_id: Option<String>,
+    pub error: Option<String>,
+    pub created_at: u128,
+    pub started_at: Option<u128>,
+    pub finished_at: Option<u128>,
+    #[serde(skip)]
+    steps: Vec<String>,
+    #[serde(skip)]
+    max_steps: u32,
+    #[serde(skip)]
+    seq: u64,
+}
+
+#[derive(Serialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct QueueSnapshot {
+    pub max_concurrency: usize,
+    pub running: usize,
+    pub queued: usize,
+    pub runs: Vec<QueuedRun>,
+}
+
+#[derive(Default)]
+struct QueueInner {
+    runs: Vec<QueuedRun>,
+    next_seq: u64,
+}
+
+/// Priority queue of agent runs for `serve --api`. A scheduler starts one
+/// kernel per run whenever a slot frees up, never two on the same workspace.
+#[derive(Clone)]
+pub struct RunQueue {
+    inner: Arc<Mutex<QueueInner>>,
+    notify: Arc<Notify>,
+    max_concurrency: usize,
+    default_workspace: PathBuf,
+    /// Canonical roots runs may target, the default workspace included.
+    allowed_workspaces: Vec<PathBuf>,
+}
+
+impl RunQueue {
+    pub fn new(
+        default_workspace: PathBuf,
+        allowed_workspaces: Vec<PathBuf>,
+        max_concurrency: usize,
+    ) -> Self {
+        let allowed_workspaces = std::iter::once(&default_workspace)
+            .chain(&allowed_workspaces)
+            .filter_map(|root| root.canonicalize().ok())
+            .collect();
+        Self {
+            inner: Arc::new(Mutex::new(QueueInner::default())),
+            notify: Arc::new(Notify::new()),
+            max_concurrency: max_concurrency.max(1),
+            default_workspace,
+            allowed_workspaces,
+        }
+    }
+
+    pub fn enqueue(&self, request: RunRequest) -> Result<QueuedRun, String> {
+        let task = request.task.trim().to_string();
+        if task.is_empty() {
+            return Err("task cannot be empty".to_string());
+        }
+        let requested = request
+            .workspace
+            .unwrap_or_else(|| self.default_workspace.clone());
+        let workspace = requested
+            .canonicalize()
+            .ok()
+            .filter(|workspace| workspace.is_dir())
+            .ok_or_else(|| format!("workspace not found: {}", requested.display()))?;
+        if !self.allowed_workspaces.contains(&workspace) {
+            return Err(format!(
+                "workspace not allowed: {} (start the server with --allow-workspace)",
+                requested.display()
+            ));
+        }
+        let mut inner = self.inner.lock().map_err(|_| "queue lock poisoned")?;
+        let run = QueuedRun {
+            id: Uuid::new_v4().to_string(),
+            task,
+            priority: request.priority,
+            workspace,
+            status: QueueStatus::Queued,
+            run_id: None,
+            error: None,
+            created_at: now_ms(),
+            started_at: None,
+            finished_at: None,
+            steps: request.steps,
+            max_steps: request.max_steps.unwrap_or(DEFAULT_MAX_STEPS),
+            seq: inner.next_seq,
+        };
+        inner.next_seq += 1;
+        inner.runs.push(run.clone());
+        drop(inner);
+        self.notify.notify_one();
+        Ok(run)
+    }
+
+    /// Runs in scheduling order: running first, then queued by priority.
+    pub fn snapshot(&self) -> QueueSnapshot {
+        let mut runs = self
+            .inner
+            .lock()
+            .map(|inner| inner.runs.clone())
+            .unwrap_or_default();
+        runs.sort_by_key(|run| {
+            let rank = match run.status {
+                QueueStatus::Running => 0,
+                QueueStatus::Queued => 1,
+                _ => 2,
+            };
+            (rank, -run.priority, run.seq)
+        });
+        QueueSnapshot {
+            max_concurrency: self.max_concurrency,
+            running: count(&runs, QueueStatus::Running),
+            queued: count(&runs, QueueStatus::Queued),
+            runs,
+        }
+    }
+
+    pub fn spawn_scheduler(&self) {
+        let queue = self.clone();
+        tokio::spawn(async move {
+            loop {
+                while let Some(run) = queue.claim_next() {
+                    let worker = queue.clone();
+                    tokio::spawn(async move {
+                        let outcome = worker.execute(&run).await;
+                        worker.finish(&run.id, outcome);
+                        worker.notify.notify_one();
+                    });
+                }
+                queue.notify.notified().await;
+            }
+        });
+    }
+
+    /// Marks the best eligible queued run as running, if a slot is free.
+    fn claim_next(&self) -> Option<QueuedRun> {
+        let mut inner = self.inner.lock().ok()?;
+        if count(&inner.runs, QueueStatus::Running) >= self.max_concurrency {
+            return None;
+        }
+        let busy: Vec<PathBuf> = inner
+            .runs
+            .iter()
+            .filter(|run| run.status == QueueStatus::Running)
+            .map(|run| run.workspace.clone())
+            .collect();
+        let run = inner
+            .runs
+            .iter_mut()
+            .filter(|run| run.status == QueueStatus::Queued && !busy.contains(&run.workspace))
+            .min_by_key(|run| (-run.priority, run.seq))?;
+        run.status = QueueStatus::Running;
+        run.started_at = Some(now_ms());
+        Some(run.clone())
+    }
+
+    async fn execute(&self, run: &QueuedRun) -> Result<QueueStatus, String> {
+        let task = HeadlessTask {
+            goal: run.task.clone(),
+            steps: run.steps.clone(),
+            max_steps: Some(run.max_steps),
+            tags: Vec::new(),
+        };
+        // Events are still written to the run log under .taurihands.
+        let state = run_to_completion(&run.workspace, task, &DiscardEvents, |run_id| {
+            self.update(&run.id, |queued| queued.run_id = Some(run_id.to_string()))
+        })
+        .await?;
+        match state.agent_state {
+            RunAgentState::Finished => Ok(QueueStatus::Finished),
+            RunAgentState::AwaitingUser => Ok(QueueStatus::Blocked),
+            _ => Err(state
+                .last_error
+                .unwrap_or_else(|| format!("run ended as {:?}", state.agent_state))),
+        }
+    }
+
+    fn finish(&self, id: &str, outcome: Result<QueueStatus, String>) {
+        self.update(id, |run| {
+            run.finished_at = Some(now_ms());
+            match outcome {
+                Ok(status) => run.status = status,
+                Err(err) => {
+                    run.status = QueueStatus::Failed;
+                    run.error = Some(err);
+                }
+            }
+        });
+        if let Ok(mut inner) = self.inner.lock() {
+            prune_ended(&mut inner.runs, MAX_ENDED_RUNS);
+        }
+    }
+
+    fn update<F: FnOnce(&mut QueuedRun)>(&self, id: &str, updater: F) {
+        if let Ok(mut inner) = self.inner.lock() {
+            if let Some(run) = inner.runs.iter_mut().find(|run| run.id == id) {
+                updater(run);
+            }
+        }
+    }
+}
+
+struct DiscardEvents;
+
+impl EventSink for DiscardEvents {
+    fn emit_json(&self, _event: &str, _payload: serde_json::Value) {}
+
+    fn clone_sink(&self) -> SharedEventSink {
+        Arc::new(DiscardEvents)
+    }
+}
+
+fn count(runs: &[QueuedRun], status: QueueStatus) -> usize {
+    runs.iter().filter(|run| run.status == status).count()
+}
+
+fn is_ended(run: &QueuedRun) -> bool {
+    !matches!(run.status, QueueStatus::Queued | QueueStatus::Running)
+}
+
+/// Drops the oldest ended runs beyond `keep`; queued and running runs stay.
+fn prune_ended(runs: &mut Vec<QueuedRun>, keep: usize) {
+    let mut ended: Vec<(u128, u64)> = runs
+        .iter()
+        .filter(|run| is_ended(run))
+        .map(|run| (run.finished_at.unwrap_or_default(), run.seq))
+        .collect();
+    if ended.len() <= keep {
+        return;
+    }
+    ended.sort_unstable();
+    let cutoff = ended[ended.len() - keep - 1];
+    runs.retain(|run| !is_ended(run) || (run.finished_at.unwrap_or_default(), run.seq) > cutoff);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn request(task: &str) -> RunRequest {
+        RunRequest {
+            task: task.to_string(),
+            priority: 0,
+            steps: Vec::new(),
+            workspace: None,
+            max_steps: None,
+        }
+    }
+
+    #[test]
+    fn finish_drops_the_oldest_ended_runs() {
+        let queue = RunQueue::new(std::env::temp_dir(), Vec::new(), 1);
+        let mut ids = Vec::new();
+        for index in 0..MAX_ENDED_RUNS + 3 {
+            let run = queue.enqueue(request(&format!("task {}", index))).unwrap();
+            let claimed = queue.claim_next().unwrap();
+            assert_eq!(claimed.id, run.id);
+            let outcome = if index % 2 == 0 {
+                Ok(QueueStatus::Finished)
+            } else {
+                Err("failed".to_string())
+            };
+            queue.finish(&run.id, outcome);
+            ids.push(run.id);
+        }
+        let waiting = queue.enqueue(request("still queued")).unwrap();
+
+        let snapshot = queue.snapshot();
+        assert_eq!(snapshot.runs.len(), MAX_ENDED_RUNS + 1);
+        assert_eq!(snapshot.queued, 1);
+        let kept: Vec<&str> = snapshot.runs.iter().map(|run| run.id.as_str()).collect();
+        assert!(kept.contains(&waiting.id.as_str()));
+        for id in &ids[..3] {
+            assert!(!kept.contains(&id.as_str()));
+        }
+        for id in &ids[3..] {
+            assert!(kept.contains(&id.as_str()));
+        }
+    }
+}
@@ -1,17 +1,57 @@
-use std::path::PathBuf;
 use anyhow::Result;
+use futures_util::{SinkExt, StreamExt};
+use serde_json;
+use std::path::PathBuf;
 use tokio::net::{TcpListener, TcpStream};
 use tokio_tungstenite::{accept_hdr_async, tungstenite::Message};
 use tokio_tungstenite::{tungstenite::protocol::WebSocketConfig, WebSocketStream};
-use futures_util::{SinkExt, StreamExt};
-use serde_json;
 
-pub async fn start_web_server(workspace: &PathBuf, host: &str, port: u16, open_browser: bool) -> Result<()> {
+use crate::cli::artifacts::{ArtifactStore, MAX_CHUNK_BYTES};
+use crate::cli::http::{read_request, write_response, HttpRequest, HttpResponse};
+use crate::cli::openapi::openapi_document;
+use crate::cli::queue::{RunQueue, RunRequest};
+use crate::cli::sync::SyncStore;
+use crate::cli::tokens::{
+    request_secret, IssueTokenRequest, IssuedToken, ServerToken, TokenScope, TokenStore,
+};
+use crate::services::metrics;
+
+const MAX_REQUEST_BYTES: usize = 64 * 1024;
+const METRICS_CONTENT_TYPE: &str = "text/plain; version=0.0.4";
+
+/// Whether `host` only accepts connections from this machine.
+fn is_loopback(host: &str) -> bool {
+    host.eq_ignore_ascii_case("localhost")
+        || host
+            .trim_matches(|c| c == '[' || c == ']')
+            .parse::<std::net::IpAddr>()
+            .is_ok_and(|ip| ip.is_loopback())
+}
+
+/// Without tokens anyone who can reach the port may run commands.
+fn warn_if_exposed(workspace: &PathBuf, host: &str) {
+    if !is_loopback(host) && !TokenStore::new(workspace).is_enabled() {
+        eprintln!("⚠️  WARNING: listening on {} with token auth OFF.", host);
+        eprintln!(
+            "⚠️  Anyone who can reach this port can run commands in {:?}.",
+            workspace
+        );
+        eprintln!("⚠️  Issue a token with `taurihands server-tokens` or bind to localhost.");
+    }
+}
+
+pub async fn start_web_server(
+    workspace: &PathBuf,
+    host: &str,
+    port: u16,
+    open_browser: bool,
+) -> Result<()> {
     let addr = format!("{}:{}", host, port);
     let listener = TcpListener::bind(&addr).await?;
-    
+    warn_if_exposed(workspace, host);
+
     println!("🌐 Web server started at http://{}:{}", host, port);
-    
+
     if open_browser {
         if let Err(e) = webbrowser::open(&format!("http://{}:{}", host, port)) {
             eprintln!("Failed to open browser: {}", e);
@@ -19,31 +59,280 @@ pub async fn start_web_server(workspace: &PathBuf, host: &str, port: u16, open_b
     }
 
     while let Ok((stream, _addr)) = listener.accept().await {
-        tokio::spawn(handle_web_connection(stream, workspace.clone()));
+        tokio::spawn(accept_web_connection(stream, workspace.clone()));
     }
 
     Ok(())
 }
 
-pub async fn start_gui_server(workspace: &PathBuf, host: &str, port: u16, enable_api: bool) -> Result<()> {
+pub async fn start_gui_server(
+    workspace: &PathBuf,
+    host: &str,
+    port: u16,
+    enable_api: bool,
+    max_concurrency: usize,
+    allowed_workspaces: Vec<PathBuf>,
+) -> Result<()> {
     let addr = format!("{}:{}", host, port);
     let listener = TcpListener::bind(&addr).await?;
-    
+    warn_if_exposed(workspace, host);
+
     println!("🖥️ GUI server started at http://{}:{}", host, port);
+    let queue = RunQueue::new(workspace.clone(), allowed_workspaces, max_concurrency);
     if enable_api {
         println!("🔌 API enabled at http://{}:{}/api", host, port);
+        println!(
+            "📥 Run queue at http://{}:{}/runs (max {} concurrent)",
+            host, port, max_concurrency
+        );
+        println!(
+            "📦 Artifacts at http://{}:{}/artifacts/<run>/<name>",
+            host, port
+        );
+        println!("📜 OpenAPI schema at http://{}:{}/openapi.json", host, port);
+        println!("📈 Metrics at http://{}:{}/metrics", host, port);
+        queue.spawn_scheduler();
+    }
+
+    if TokenStore::new(workspace).is_enabled() {
+        println!("🔑 Token auth enabled (manage with `taurihands server-tokens`)");
     }
 
     while let Ok((stream, _addr)) = listener.accept().await {
-        tokio::spawn(handle_gui_connection(stream, workspace.clone(), enable_api));
+        tokio::spawn(accept_gui_connection(
+            stream,
+            workspace.clone(),
+            enable_api,
+            queue.clone(),
+        ));
     }
 
     Ok(())
 }
 
+/// Reads the request head without consuming it, so the WebSocket handshake
+/// or REST handler still sees the whole request.
+async fn peek_head(stream: &TcpStream) -> String {
+    let mut buffer = [0u8; 8192];
+    let mut last = 0;
+    for _ in 0..50 {
+        let Ok(read) = stream.peek(&mut buffer).await else {
+            break;
+        };
+        let complete = buffer[..read]
+            .windows(4)
+            .any(|window| window == b"\r\n\r\n");
+        if read == 0 || read == buffer.len() || complete {
+            last = read;
+            break;
+        }
+        if read == last {
+            tokio::time::sleep(std::time::Duration::from_millis(20)).await;
+        }
+        last = read;
+    }
+    String::from_utf8_lossy(&buffer[..last]).to_string()
+}
+
+/// Scope of the token presented with the request, if any.
+async fn authorize(stream: &TcpStream, workspace: &PathBuf) -> (String, Option<TokenScope>) {
+    let head = peek_head(stream).await;
+    let scope = TokenStore::new(workspace).authorize(request_secret(&head).as_deref());
+    (head, scope)
+}
+
+async fn reject(mut stream: TcpStream) -> Result<()> {
+    write_response(
+        &mut stream,
+        HttpResponse::error(401, "missing or invalid token"),
+    )
+    .await
+}
+
+async fn accept_web_connection(stream: TcpStream, workspace: PathBuf) -> Result<()> {
+    match authorize(&stream, &workspace).await {
+        (_, Some(scope)) => handle_web_connection(stream, workspace, scope).await,
+        (_, None) => reject(stream).await,
+    }
+}
+
+async fn accept_gui_connection(
+    stream: TcpStream,
+    workspace: PathBuf,
+    enable_api: bool,
+    queue: RunQueue,
+) -> Result<()> {
+    let (head, scope) = authorize(&stream, &workspace).await;
+    if enable_api {
+        if let Some(route) = rest_route(&head) {
+            return handle_rest_request(stream, route, scope, queue, workspace).await;
+        }
+    }
+    match scope {
+        Some(scope) => handle_gui_connection(stream, workspace, enable_api, scope).await,
+        None => reject(stream).await,
+    }
+}
+
+#[derive(Clone, Copy)]
+enum RestRoute {
+    Runs,
+    Artifacts,
+    Tokens,
+    OpenApi,
+    Metrics,
+    Sync,
+}
+
+/// Plain HTTP requests to `/runs`, `/artifacts`, `/sync`, `/tokens`, `/metrics`
+/// and `/openapi.json` share the port with the WebSocket endpoint.
+fn rest_route(head: &str) -> Option<RestRoute> {
+    if head.starts_with("GET /openapi.json") {
+        return Some(RestRoute::OpenApi);
+    }
+    if head.starts_with("GET /metrics") {
+        return Some(RestRoute::Metrics);
+    }
+    if head.starts_with("GET /runs") || head.starts_with("POST /runs") {
+        return Some(RestRoute::Runs);
+    }
+    if ["GET /tokens", "POST /tokens", "DELETE /tokens/"]
+        .iter()
+        .any(|prefix| head.starts_with(prefix))
+    {
+        return Some(RestRoute::Tokens);
+    }
+    if ["GET /sync/", "PUT /sync/", "DELETE /sync/"]
+        .iter()
+        .any(|prefix| head.starts_with(prefix))
+    {
+        return Some(RestRoute::Sync);
+    }
+    ["GET", "HEAD", "PUT"]
+        .iter()
+        .any(|method| head.starts_with(&format!("{} /artifacts/", method)))
+        .then_some(RestRoute::Artifacts)
+}
+
+/// Scope each endpoint requires: reads need `read`, anything that starts work
+/// or writes files needs `execute`, and token management needs `admin`. The
+/// schema exposes no data, so integrators can fetch it before holding a token.
+fn required_scope(route: RestRoute, method: &str) -> Option<TokenScope> {
+    match (route, method) {
+        (RestRoute::OpenApi, _) => None,
+        (RestRoute::Tokens, _) => Some(TokenScope::Admin),
+        (_, "GET" | "HEAD") => Some(TokenScope::Read),
+        _ => Some(TokenScope::Execute),
+    }
+}
+
+/// `POST /runs` enqueues a task (`{"task", "priority", "steps", "workspace", "maxSteps"}`);
+/// `GET /runs` lists the queue. `/artifacts/<run>/<name>` is served by [`ArtifactStore`],
+/// `/sync/...` by [`SyncStore`].
+/// `/tokens` issues (`POST {"name", "scope", "ttlHours"}`), lists and revokes tokens.
+async fn handle_rest_request(
+    mut stream: TcpStream,
+    route: RestRoute,
+    scope: Option<TokenScope>,
+    queue: RunQueue,
+    workspace: PathBuf,
+) -> Result<()> {
+    let max_body = match route {
+        RestRoute::Runs => MAX_REQUEST_BYTES,
+        RestRoute::Artifacts | RestRoute::Sync => MAX_CHUNK_BYTES,
+        RestRoute::Tokens | RestRoute::OpenApi | RestRoute::Metrics => MAX_REQUEST_BYTES,
+    };
+    let request = match read_request(&mut stream, max_body).await? {
+        Ok(request) => request,
+        Err(response) => return write_response(&mut stream, response).await,
+    };
+    if matches!(route, RestRoute::Tokens) && !TokenStore::new(&workspace).is_enabled() {
+        let message = "issue the first token locally with `taurihands server-tokens`";
+        return write_response(&mut stream, HttpResponse::error(403, message)).await;
+    }
+    if let Some(required) = required_scope(route, &request.method) {
+        let Some(scope) = scope else {
+            return reject(stream).await;
+        };
+        if !scope.allows(required) {
+            let message = format!("token scope {:?} cannot access this endpoint", scope);
+            return write_response(&mut stream, HttpResponse::error(403, message)).await;
+        }
+    }
+    let response = match route {
+        RestRoute::OpenApi => HttpResponse::json(200, openapi_document()),
+        RestRoute::Metrics => HttpResponse::text(200, METRICS_CONTENT_TYPE, metrics::render()),
+        RestRoute::Tokens => handle_tokens_request(&TokenStore::new(&workspace), &request)?,
+        RestRoute::Artifacts => {
+            let store = ArtifactStore::new(&workspace);
+            tokio::task::spawn_blocking(move || store.handle(&request)).await?
+        }
+        RestRoute::Sync => {
+            let store = SyncStore::new(&workspace);
+            tokio::task::spawn_blocking(move || store.handle(&request)).await?
+        }
+        RestRoute::Runs if request.method == "GET" => {
+            HttpResponse::json(200, serde_json::to_value(queue.snapshot())?)
+        }
+        RestRoute::Runs => match serde_json::from_slice::<RunRequest>(&request.body) {
+            Ok(request) => match queue.enqueue(request) {
+                Ok(run) => HttpResponse::json(201, serde_json::to_value(run)?),
+                Err(err) => HttpResponse::error(400, err),
+            },
+            Err(err) => HttpResponse::error(400, err.to_string()),
+        },
+    };
+    write_response(&mut stream, response).await
+}
+
+fn handle_tokens_request(store: &TokenStore, request: &HttpRequest) -> Result<HttpResponse> {
+    match request.method.as_str() {
+        "GET" => {
+            let tokens: Vec<ServerToken> = store.list().iter().map(ServerToken::public).collect();
+            Ok(HttpResponse::json(200, serde_json::to_value(tokens)?))
+        }
+        "POST" => match serde_json::from_slice::<IssueTokenRequest>(&request.body) {
+            Ok(issue) => {
+                let (token, secret) = store.issue(&issue.name, issue.scope, issue.ttl_hours)?;
+                let issued = IssuedToken { token, secret };
+                Ok(HttpResponse::json(201, serde_json::to_value(issued)?))
+            }
+            Err(err) => Ok(HttpResponse::error(400, err.to_string())),
+        },
+        _ => {
+            let id = request.path.trim_start_matches("/tokens/");
+            if store.revoke(id)? {
+                Ok(HttpResponse::json(
+                    200,
+                    serde_json::json!({ "revoked": id }),
+                ))
+            } else {
+                Ok(HttpResponse::error(404, "token not found"))
+            }
+        }
+    }
+}
+
+/// WebSocket messages that start work need the `execute` scope.
+fn message_scope(message_type: Option<&str>) -> TokenScope {
+    match message_type {
+        Some("execute_task") | Some("start_agent") => TokenScope::Execute,
+        _ => TokenScope::Read,
+    }
+}
+
+fn forbidden_message(scope: TokenScope) -> String {
+    serde_json::json!({
+        "type": "error",
+        "message": format!("token scope {:?} cannot perform this action", scope)
+    })
+    .to_string()
+}
+
 async fn handle_web_connection(
     stream: TcpStream,
     workspace: PathBuf,
+    scope: TokenScope,
 ) -> Result<()> {
     let ws_stream = accept_hdr_async(stream, WebSocketConfig::default()).await?;
     let (mut ws_sender, mut ws_receiver) = ws_stream.split();
@@ -54,13 +343,13 @@ async fn handle_web_connection(
         "workspace": workspace.to_string_lossy(),
         "version": env!("CARGO_PKG_VERSION")
     });
-    
+
     ws_sender.send(Message::Text(init_msg.to_string())).await?;
 
     while let Some(msg) = ws_receiver.next().await {
         match msg {
             Ok(Message::Text(text)) => {
-                if let Ok(response) = handle_web_message(&text, &workspace).await {
+                if let Ok(response) = handle_web_message(&text, &workspace, scope).await {
                     ws_sender.send(Message::Text(response)).await?;
                 }
             }
@@ -82,6 +371,7 @@ async fn handle_gui_connection(
     stream: TcpStream,
     workspace: PathBuf,
     enable_api: bool,
+    scope: TokenScope,
 ) -> Result<()> {
     let ws_stream = accept_hdr_async(stream, WebSocketConfig::default()).await?;
     let (mut ws_sender, mut ws_receiver) = ws_stream.split();
@@ -91,15 +381,17 @@ async fn handle_gui_connection(
         "type": "init",
         "workspace": workspace.to_string_lossy(),
         "api_enabled": enable_api,
+        "scope": scope,
         "version": env!("CARGO_PKG_VERSION")
     });
-    
+
     ws_sender.send(Message::Text(init_msg.to_string())).await?;
 
     while let Some(msg) = ws_receiver.next().await {
         match msg {
             Ok(Message::Text(text)) => {
-                if let Ok(response) = handle_gui_message(&text, &workspace, enable_api).await {
+                let response = handle_gui_message(&text, &workspace, enable_api, scope).await;
+                if let Ok(response) = response {
                     ws_sender.send(Message::Text(response)).await?;
                 }
             }
@@ -117,20 +409,30 @@ async fn handle_gui_connection(
     Ok(())
 }
 
-async fn handle_web_message(message: &str, workspace: &PathBuf) -> Result<String> {
+async fn handle_web_message(
+    message: &str,
+    workspace: &PathBuf,
+    scope: TokenScope,
+) -> Result<String> {
     let parsed: serde_json::Value = serde_json::from_str(message)?;
-    
+    let message_type = parsed.get("type").and_then(|v| v.as_str());
+    if !scope.allows(message_scope(message_type)) {
+        return Ok(forbidden_message(scope));
+    }
+
     match parsed.get("type").and_then(|v| v.as_str()) {
         Some("ping") => Ok(serde_json::json!({
             "type": "pong",
             "timestamp": chrono::Utc::now().to_rfc3339()
-        }).to_string()),
-        
+        })
+        .to_string()),
+
         Some("get_workspace") => Ok(serde_json::json!({
             "type": "workspace_info",
             "workspace": workspace.to_string_lossy()
-        }).to_string()),
-        
+        })
+        .to_string()),
+
         Some("execute_task") => {
             let task = parsed.get("task").and_then(|v| v.as_str()).unwrap_or("");
             // TODO: Execute task
@@ -139,45 +441,63 @@ async fn handle_web_message(message: &str, workspace: &PathBuf) -> Result<String
                 "task": task,
                 "status": "started",
                 "message": format!("Task '{}' started", task)
-            }).to_string())
+            })
+            .to_string())
         }
-        
+
         _ => Ok(serde_json::json!({
             "type": "error",
             "message": "Unknown message type"
-        }).to_string())
+        })
+        .to_string()),
     }
 }
 
-async fn handle_gui_message(message: &str, workspace: &PathBuf, enable_api: bool) -> Result<String> {
+async fn handle_gui_message(
+    message: &str,
+    workspace: &PathBuf,
+    enable_api: bool,
+    scope: TokenScope,
+) -> Result<String> {
     let parsed: serde_json::Value = serde_json::from_str(message)?;
-    
+    let message_type = parsed.get("type").and_then(|v| v.as_str());
+    if !scope.allows(message_scope(message_type)) {
+        return Ok(forbidden_message(scope));
+    }
+
     match parsed.get("type").and_then(|v| v.as_str()) {
         Some("ping") => Ok(serde_json::json!({
             "type": "pong",
             "timestamp": chrono::Utc::now().to_rfc3339()
-        }).to_string()),
-        
+        })
+        .to_string()),
+
         Some("get_status") => Ok(serde_json::json!({
             "type": "status",
             "status": "running",
             "workspace": workspace.to_string_lossy(),
             "api_enabled": enable_api
-        }).to_string()),
-        
+        })
+        .to_string()),
+
         Some("start_agent") => {
-            let task = parsed.get("task").and_then(|v| v.as_str()).unwrap_or("Interactive mode");
+            let task = parsed
+                .get("task")
+                .and_then(|v| v.as_str())
+                .unwrap_or("Interactive mode");
             // TODO: Start agent with task
             Ok(serde_json::json!({
                 "type": "agent_started",
                 "task": task,
                 "status": "running"
-            }).to_string())
+            })
+            .to_string())
         }
-        
+
         _ => Ok(serde_json::json!({
             "type": "error",
             "message": "Unknown message type"
-        }).to_string())
+        })
+        .to_string()),
     }
 }
@@ -0,0 +1,469 @@
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::time::Duration;
+use walkdir::WalkDir;
+
+use crate::cli::artifacts::{file_checksum, MAX_CHUNK_BYTES};
+use crate::cli::http::{HttpBody, HttpRequest, HttpResponse};
+
+/// Directories never mirrored: VCS metadata, agent state and build output
+/// that the other side regenerates.
+const SKIP_DIRS: &[&str] = &[".git", ".taurihands", "node_modules", "target", "dist"];
+const CHECKSUM_HEADER: &str = "X-Checksum-Sha256";
+const RUN_POLL_INTERVAL: Duration = Duration::from_secs(2);
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SyncEntry {
+    /// Workspace-relative path with `/` separators.
+    pub path: String,
+    pub size: u64,
+    pub sha256: String,
+}
+
+/// `GET /sync/manifest` body. Files over the per-request body limit are
+/// listed under `skipped` instead of `files`.
+#[derive(Serialize, Deserialize, JsonSchema)]
+pub struct SyncManifest {
+    pub files: Vec<SyncEntry>,
+    #[serde(default)]
+    pub skipped: Vec<String>,
+}
+
+pub fn build_manifest(root: &Path) -> Result<SyncManifest, String> {
+    let mut files = Vec::new();
+    let mut skipped = Vec::new();
+    let walker = WalkDir::new(root).into_iter().filter_entry(|entry| {
+        entry.depth() == 0
+            || !(entry.file_type().is_dir()
+                && SKIP_DIRS.contains(&entry.file_name().to_string_lossy().as_ref()))
+    });
+    for entry in walker.filter_map(|entry| entry.ok()) {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let Ok(relative) = entry.path().strip_prefix(root) else {
+            continue;
+        };
+        let path = relative
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        let size = entry.metadata().map(|meta| meta.len()).unwrap_or(0);
+        if size > MAX_CHUNK_BYTES as u64 {
+            skipped.push(path);
+            continue;
+        }
+        let sha256 = file_checksum(entry.path())?;
+        files.push(SyncEntry { path, size, sha256 });
+    }
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    skipped.sort();
+    Ok(SyncManifest { files, skipped })
+}
+
+/// Server side of workspace sync, mounted at `/sync`:
+///
+/// - `GET /sync/manifest` lists every file with its size and SHA-256.
+/// - `GET /sync/files/<path>` downloads a file.
+/// - `PUT /sync/files/<path>` replaces a file; the body must match
+///   `X-Checksum-Sha256`.
+/// - `DELETE /sync/files/<path>` removes a file.
+///
+/// Clients diff manifests and only move files whose hash differs.
+pub struct SyncStore {
+    root: PathBuf,
+}
+
+impl SyncStore {
+    pub fn new(workspace: &Path) -> Self {
+        Self {
+            root: workspace.to_path_buf(),
+        }
+    }
+
+    pub fn handle(&self, request: &HttpRequest) -> HttpResponse {
+        if request.path == "/sync/manifest" && request.method == "GET" {
+            return match build_manifest(&self.root) {
+                Ok(manifest) => {
+                    HttpResponse::json(200, serde_json::to_value(manifest).unwrap_or_default())
+                }
+                Err(err) => HttpResponse::error(500, err),
+            };
+        }
+        let Some(encoded) = request.path.strip_prefix("/sync/files/") else {
+            return HttpResponse::error(404, "not found");
+        };
+        let path = match decode_path(encoded).and_then(|relative| self.resolve(&relative)) {
+            Ok(path) => path,
+            Err(err) => return HttpResponse::error(400, err),
+        };
+        let result = match request.method.as_str() {
+            "GET" => self.get(&path),
+            "PUT" => self.put(&path, request),
+            "DELETE" => self.delete(&path),
+            _ => Ok(HttpResponse::error(404, "not found")),
+        };
+        result.unwrap_or_else(|err| HttpResponse::error(500, err))
+    }
+
+    fn resolve(&self, relative: &str) -> Result<PathBuf, String> {
+        resolve_sync_path(&self.root, relative)
+    }
+
+    fn get(&self, path: &Path) -> Result<HttpResponse, String> {
+        let Ok(meta) = fs::metadata(path) else {
+            return Ok(HttpResponse::error(404, "file not found"));
+        };
+        Ok(HttpResponse {
+            status: 200,
+            headers: Vec::new(),
+            body: HttpBody::File {
+                path: path.to_path_buf(),
+                start: 0,
+                len: meta.len(),
+            },
+        }
+        .with_header("Content-Type", "application/octet-stream")
+        .with_header(CHECKSUM_HEADER, file_checksum(path)?))
+    }
+
+    fn put(&self, path: &Path, request: &HttpRequest) -> Result<HttpResponse, String> {
+        let actual = sha256_hex(&request.body);
+        if let Some(expected) = request.header(CHECKSUM_HEADER) {
+            if !expected.trim().eq_ignore_ascii_case(&actual) {
+                return Ok(HttpResponse::error(422, "checksum mismatch"));
+            }
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        fs::write(path, &request.body).map_err(|e| e.to_string())?;
+        let entry = SyncEntry {
+            path: path
+                .strip_prefix(&self.root)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .replace('\\', "/"),
+            size: request.body.len() as u64,
+            sha256: actual,
+        };
+        Ok(HttpResponse::json(
+            200,
+            serde_json::to_value(entry).unwrap_or_default(),
+        ))
+    }
+
+    fn delete(&self, path: &Path) -> Result<HttpResponse, String> {
+        match fs::remove_file(path) {
+            Ok(()) => Ok(HttpResponse::json(
+                200,
+                serde_json::json!({ "deleted": true }),
+            )),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => {
+                Ok(HttpResponse::error(404, "file not found"))
+            }
+            Err(err) => Err(err.to_string()),
+        }
+    }
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn encode_path(path: &str) -> String {
+    let mut encoded = String::new();
+    for byte in path.bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/".contains(&byte) {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+fn decode_path(encoded: &str) -> Result<String, String> {
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        if bytes[index] == b'%' {
+            let hex = encoded
+                .get(index + 1..index + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                .ok_or_else(|| "invalid percent-encoding in path".to_string())?;
+            decoded.push(hex);
+            index += 3;
+        } else {
+            decoded.push(bytes[index]);
+            index += 1;
+        }
+    }
+    String::from_utf8(decoded).map_err(|_| "path is not valid UTF-8".to_string())
+}
+
+#[derive(Default, Serialize)]
+pub struct SyncReport {
+    pub transferred: Vec<String>,
+    pub deleted: Vec<String>,
+    pub unchanged: usize,
+    /// Files too large to sync, from either side.
+    pub skipped: Vec<String>,
+}
+
+/// Joins a path from a request or a remote manifest to `root`. Only plain
+/// relative components are accepted, and nothing under `SKIP_DIRS`, so the
+/// other side cannot reach outside the workspace or into `.git`. Symlinks are
+/// checked too: the deepest existing directory must resolve inside the root,
+/// and the file itself may not be a symlink.
+fn resolve_sync_path(root: &Path, relative: &str) -> Result<PathBuf, String> {
+    let relative = Path::new(relative);
+    let valid = relative.components().count() > 0
+        && relative
+            .components()
+            .all(|component| matches!(component, Component::Normal(_)));
+    if !valid {
+        return Err("sync path must be workspace-relative".to_string());
+    }
+    let skipped = relative
+        .components()
+        .any(|component| SKIP_DIRS.contains(&component.as_os_str().to_string_lossy().as_ref()));
+    if skipped {
+        return Err("path is excluded from sync".to_string());
+    }
+    let path = root.join(relative);
+    let canonical_root = root.canonicalize().map_err(|e| e.to_string())?;
+    let existing = path
+        .ancestors()
+        .skip(1)
+        .find(|ancestor| ancestor.exists())
+        .ok_or_else(|| "sync path must be workspace-relative".to_string())?;
+    let inside = existing
+        .canonicalize()
+        .is_ok_and(|dir| dir.starts_with(&canonical_root));
+    if !inside {
+        return Err("sync path leaves the workspace through a symlink".to_string());
+    }
+    let is_link = fs::symlink_metadata(&path).is_ok_and(|meta| meta.file_type().is_symlink());
+    if is_link {
+        return Err("sync path is a symlink".to_string());
+    }
+    Ok(path)
+}
+
+/// Mirrors a local workspace to, or back from, a remote `serve --api`.
+pub struct SyncClient {
+    http: reqwest::Client,
+    remote: String,
+    token: Option<String>,
+    root: PathBuf,
+}
+
+impl SyncClient {
+    pub fn new(remote: &str, token: Option<String>, root: PathBuf) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            remote: remote.trim_end_matches('/').to_string(),
+            token,
+            root,
+        }
+    }
+
+    fn request(&self, method: reqwest::Method, path: &str) -> reqwest::RequestBuilder {
+        let builder = self
+            .http
+            .request(method, format!("{}{}", self.remote, path));
+        match &self.token {
+            Some(token) => builder.bearer_auth(token),
+            None => builder,
+        }
+    }
+
+    async fn remote_manifest(&self) -> Result<SyncManifest> {
+        let response = self
+            .request(reqwest::Method::GET, "/sync/manifest")
+            .send()
+            .await
+            .with_context(|| format!("Failed to reach {}", self.remote))?;
+        Ok(checked(response).await?.json().await?)
+    }
+
+    fn local_manifest(&self) -> Result<SyncManifest> {
+        build_manifest(&self.root).map_err(anyhow::Error::msg)
+    }
+
+    /// Uploads local files whose hash differs from the remote copy; with
+    /// `delete`, also removes remote files that do not exist locally.
+    pub async fn push(&self, delete: bool) -> Result<SyncReport> {
+        let local = self.local_manifest()?;
+        let remote = self.remote_manifest().await?;
+        let (changed, extra, mut report) = diff_manifests(&local, &remote);
+        for entry in changed {
+            let bytes = fs::read(self.root.join(&entry.path))
+                .with_context(|| format!("Failed to read {}", entry.path))?;
+            let response = self
+                .request(reqwest::Method::PUT, &file_route(&entry.path))
+                .header(CHECKSUM_HEADER, &entry.sha256)
+                .body(bytes)
+                .send()
+                .await?;
+            checked(response).await?;
+            report.transferred.push(entry.path.clone());
+        }
+        if delete {
+            for path in extra {
+                let response = self
+                    .request(reqwest::Method::DELETE, &file_route(&path))
+                    .send()
+                    .await?;
+                checked(response).await?;
+                report.deleted.push(path);
+            }
+        }
+        Ok(report)
+    }
+
+    /// Downloads remote files whose hash differs from the local copy; with
+    /// `delete`, also removes local files the remote no longer has.
+    pub async fn pull(&self, delete: bool) -> Result<SyncReport> {
+        let local = self.local_manifest()?;
+        let remote = self.remote_manifest().await?;
+        let (changed, extra, mut report) = diff_manifests(&remote, &local);
+        // Checked before anything is written, so a bad manifest changes nothing.
+        let targets = changed
+            .iter()
+            .map(|entry| {
+                resolve_sync_path(&self.root, &entry.path).map_err(|err| {
+                    anyhow::anyhow!("remote path {:?} rejected: {}", entry.path, err)
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        for (entry, target) in changed.into_iter().zip(targets) {
+            let response = self
+                .request(reqwest::Method::GET, &file_route(&entry.path))
+                .send()
+                .await?;
+            let bytes = checked(response).await?.bytes().await?;
+            if sha256_hex(&bytes) != entry.sha256 {
+                anyhow::bail!("{} changed on the remote during the pull", entry.path);
+            }
+            if let Some(parent) = target.parent() {
+                fs::create_dir_all(parent)?;
+            }
+            fs::write(&target, &bytes)
+                .with_context(|| format!("Failed to write {}", entry.path))?;
+            report.transferred.push(entry.path.clone());
+        }
+        if delete {
+            for path in extra {
+                let target = resolve_sync_path(&self.root, &path).map_err(anyhow::Error::msg)?;
+                fs::remove_file(target).with_context(|| format!("Failed to delete {}", path))?;
+                report.deleted.push(path);
+            }
+        }
+        Ok(report)
+    }
+
+    /// Queues `task` on the remote and waits for it to leave the queue.
+    /// Returns the final queue entry.
+    pub async fn run_remote(
+        &self,
+        task: &str,
+        max_steps: Option<u32>,
+    ) -> Result<serde_json::Value> {
+        let response = self
+            .request(reqwest::Method::POST, "/runs")
+            .json(&serde_json::json!({ "task": task, "maxSteps": max_steps }))
+            .send()
+            .await?;
+        let queued: serde_json::Value = checked(response).await?.json().await?;
+        let id = queued["id"]
+            .as_str()
+            .context("remote did not return a run id")?
+            .to_string();
+        loop {
+            tokio::time::sleep(RUN_POLL_INTERVAL).await;
+            let response = self.request(reqwest::Method::GET, "/runs").send().await?;
+            let snapshot: serde_json::Value = checked(response).await?.json().await?;
+            let run = snapshot["runs"]
+                .as_array()
+                .and_then(|runs| runs.iter().find(|run| run["id"] == id.as_str()))
+                .cloned()
+                .context("run disappeared from the remote queue")?;
+            if !matches!(run["status"].as_str(), Some("queued") | Some("running")) {
+                return Ok(run);
+            }
+        }
+    }
+}
+
+/// Entries of `source` missing or different in `target`, paths only in
+/// `target`, and a report seeded with the unchanged and skipped counts.
+fn diff_manifests<'a>(
+    source: &'a SyncManifest,
+    target: &SyncManifest,
+) -> (Vec<&'a SyncEntry>, Vec<String>, SyncReport) {
+    let target_hashes: BTreeMap<&str, &str> = target
+        .files
+        .iter()
+        .map(|entry| (entry.path.as_str(), entry.sha256.as_str()))
+        .collect();
+    let source_paths: BTreeMap<&str, ()> = source
+        .files
+        .iter()
+        .map(|entry| (entry.path.as_str(), ()))
+        .collect();
+    let mut report = SyncReport::default();
+    let mut changed = Vec::new();
+    for entry in &source.files {
+        match target_hashes.get(entry.path.as_str()) {
+            Some(hash) if *hash == entry.sha256 => report.unchanged += 1,
+            _ => changed.push(entry),
+        }
+    }
+    let extra = target
+        .files
+        .iter()
+        .filter(|entry| !source_paths.contains_key(entry.path.as_str()))
+        // Never delete what the other side could not send.
+        .filter(|entry| !source.skipped.contains(&entry.path))
+        .map(|entry| entry.path.clone())
+        .collect();
+    report.skipped = source
+        .skipped
+        .iter()
+        .chain(&target.skipped)
+        .cloned()
+        .collect();
+    report.skipped.sort();
+    report.skipped.dedup();
+    (changed, extra, report)
+}
+
+fn file_route(path: &str) -> String {
+    format!("/sync/files/{}", encode_path(path))
+}
+
+async fn checked(response: reqwest::Response) -> Result<reqwest::Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let url = response.url().path().to_string();
+    let body = response.text().await.unwrap_or_default();
+    let message = serde_json::from_str::<serde_json::Value>(&body)
+        .ok()
+        .and_then(|value| value["error"].as_str().map(str::to_string))
+        .unwrap_or(body);
+    anyhow::bail!("{} {}: {}", status.as_u16(), url, message)
+}
@@ -0,0 +1,193 @@
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use subtle::ConstantTimeEq;
+use uuid::Uuid;
+
+use crate::services::audit::now_ms;
+
+/// What a server token may do. Scopes are ordered: `admin` implies
+/// `execute`, which implies `read`.
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Serialize,
+    Deserialize,
+    JsonSchema,
+    clap::ValueEnum,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum TokenScope {
+    /// Status, queue listings and artifact downloads.
+    Read,
+    /// Starting runs, executing tools and uploading artifacts.
+    Execute,
+    /// Managing tokens.
+    Admin,
+}
+
+impl TokenScope {
+    pub fn allows(self, required: TokenScope) -> bool {
+        self >= required
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ServerToken {
+    pub id: String,
+    pub name: String,
+    pub scope: TokenScope,
+    pub created_at: u128,
+    pub expires_at: Option<u128>,
+    /// SHA-256 of the secret; the secret itself is only shown once.
+    #[serde(skip_serializing_if = "String::is_empty", default)]
+    #[schemars(skip)]
+    hash: String,
+}
+
+impl ServerToken {
+    fn is_expired(&self) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at <= now_ms())
+    }
+
+    /// Copy without the secret hash, for listings.
+    pub fn public(&self) -> ServerToken {
+        ServerToken {
+            hash: String::new(),
+            ..self.clone()
+        }
+    }
+}
+
+/// Body of `POST /tokens`.
+#[derive(Deserialize, JsonSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct IssueTokenRequest {
+    pub name: String,
+    pub scope: TokenScope,
+    pub ttl_hours: Option<u64>,
+}
+
+/// Response of `POST /tokens`: the record plus its secret, shown only here.
+#[derive(Serialize, JsonSchema)]
+pub struct IssuedToken {
+    #[serde(flatten)]
+    pub token: ServerToken,
+    pub secret: String,
+}
+
+/// Tokens accepted by `serve`, stored per workspace in
+/// `.taurihands/server_tokens.json`. With no tokens issued the server stays
+/// open, as it was before tokens existed, except for token management: the
+/// first token can only be issued locally with `taurihands server-tokens`.
+pub struct TokenStore {
+    path: PathBuf,
+}
+
+impl TokenStore {
+    pub fn new(workspace: &Path) -> Self {
+        Self {
+            path: workspace.join(".taurihands").join("server_tokens.json"),
+        }
+    }
+
+    pub fn list(&self) -> Vec<ServerToken> {
+        std::fs::read_to_string(&self.path)
+            .ok()
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.list().is_empty()
+    }
+
+    /// Returns the token record and its secret.
+    pub fn issue(
+        &self,
+        name: &str,
+        scope: TokenScope,
+        ttl_hours: Option<u64>,
+    ) -> Result<(ServerToken, String)> {
+        let secret = format!("th_{}{}", Uuid::new_v4().simple(), Uuid::new_v4().simple());
+        let created_at = now_ms();
+        let token = ServerToken {
+            id: Uuid::new_v4().simple().to_string()[..12].to_string(),
+            name: name.to_string(),
+            scope,
+            created_at,
+            expires_at: ttl_hours.map(|hours| created_at + u128::from(hours) * 3_600_000),
+            hash: hash_secret(&secret),
+        };
+        let mut tokens = self.list();
+        tokens.retain(|existing| !existing.is_expired());
+        tokens.push(token.clone());
+        self.save(&tokens)?;
+        Ok((token.public(), secret))
+    }
+
+    /// Returns false when no token has that id.
+    pub fn revoke(&self, id: &str) -> Result<bool> {
+        let mut tokens = self.list();
+        let before = tokens.len();
+        tokens.retain(|token| token.id != id);
+        if tokens.len() == before {
+            return Ok(false);
+        }
+        self.save(&tokens)?;
+        Ok(true)
+    }
+
+    /// Scope granted to a request. Everything but `admin` is allowed while no
+    /// tokens are issued; otherwise an unknown or expired secret gets no scope
+    /// at all.
+    pub fn authorize(&self, secret: Option<&str>) -> Option<TokenScope> {
+        let tokens = self.list();
+        if tokens.is_empty() {
+            return Some(TokenScope::Execute);
+        }
+        let hash = hash_secret(secret?);
+        tokens
+            .iter()
+            .find(|token| {
+                bool::from(token.hash.as_bytes().ct_eq(hash.as_bytes())) && !token.is_expired()
+            })
+            .map(|token| token.scope)
+    }
+
+    fn save(&self, tokens: &[ServerToken]) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let data = serde_json::to_vec_pretty(tokens)?;
+        std::fs::write(&self.path, data)
+            .with_context(|| format!("Failed to write token file: {:?}", self.path))
+    }
+}
+
+fn hash_secret(secret: &str) -> String {
+    Sha256::digest(secret.trim().as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// Token from `Authorization: Bearer ...`. Secrets in the query string end up
+/// in logs and history, so they are not accepted.
+pub fn request_secret(head: &str) -> Option<String> {
+    head.lines()
+        .skip(1)
+        .filter_map(|line| line.split_once(':'))
+        .find(|(name, _)| name.trim().eq_ignore_ascii_case("authorization"))
+        .and_then(|(_, value)| value.trim().strip_prefix("Bearer "))
+        .map(|value| value.trim().to_string())
+}
@@ -1,11 +1,13 @@
 use crate::cli::commands::TerminalArgs;
+use crate::services::audit::AuditLog;
 use crate::services::kernel::KernelManager;
 use crate::services::llm::LlmStore;
 use crate::services::pty::TerminalManager;
 use crate::services::workspace::WorkspaceState;
-use crate::services::audit::AuditLog;
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType},
 };
@@ -13,8 +15,8 @@ use ratatui::{
     backend::CrosstermBackend,
     layout::{Constraint, Direction, Layout},
     style::{Color, Modifier, Style},
-    text::{Span},
-    widgets::{Block, Borders, List, ListItem, Paragraph, Wrap, Widget},
+    text::Span,
+    widgets::{Block, Borders, List, ListItem, Paragraph, Widget, Wrap},
     Frame, Terminal,
 };
 use std::io::{stdout, Write};
@@ -97,7 +99,11 @@ impl TerminalUI {
         let header = Block::default()
             .borders(Borders::ALL)
             .title("TauriHands - AI Development Agent")
-            .style(Style::default().fg(Color::Cyan).add_modifier(Modifier::BOLD))
+            .style(
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )
             .render(f, chunks[0]);
 
         // Main content
@@ -285,7 +291,9 @@ impl TerminalUI {
 
 pub fn start_terminal_mode(args: TerminalArgs) -> Result<(), Box<dyn std::error::Error>> {
     // Initialize kernel and LLM store
-    let workspace = args.workspace.unwrap_or_else(|| std::env::current_dir().unwrap());
+    let workspace = args
+        .workspace
+        .unwrap_or_else(|| std::env::current_dir().unwrap());
     let llm_store = Arc::new(Mutex::new(LlmStore::new(workspace.clone())));
     let terminal = TerminalManager::new(workspace.join(".taurihands"));
     let workspace_state = WorkspaceState::new(workspace.clone());
@@ -4,38 +4,120 @@ use std::fs::{self, File};
 use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::process::Command;
+use std::sync::{Arc, Mutex};
+use tauri::ipc::{Channel, InvokeResponseBody};
+use tauri::{AppHandle, Emitter, State};
 use uuid::Uuid;
-use tauri::{AppHandle, State};
 
 mod services;
-#[cfg(feature = "cli")]
-pub mod cli;
+mod tray;
+pub use services::events::{ChannelEventSink, EventSink, SharedEventSink, SinkEvent};
+#[cfg(feature = "test-support")]
+pub use services::harness;
 #[cfg(feature = "cli")]
 mod automation;
+#[cfg(feature = "cli")]
+pub mod cli;
 
-use services::audit::AuditLog;
 use services::agent::{
     AgentAutoRunRequest, AgentGeneratePlanRequest, AgentManager, AgentPlanItemStatusRequest,
-    AgentPlanItemsRequest, AgentRemovePlanItemRequest, AgentStartRequest, AgentState,
-    AgentVerifyRequest,
+    AgentPlanItemsRequest, AgentRemovePlanItemRequest, AgentRunPlanItemsRequest, AgentStartRequest,
+    AgentState, AgentVerifyRequest,
+};
+use services::app_data::app_data_dir;
+use services::artifact_upload::{
+    load_upload_policy, save_upload_policy, set_upload_secret, UploadPolicy,
+};
+use services::at_rest::AtRestStatus;
+use services::audit::{now_ms, AuditEntry, AuditLog};
+use services::backup::{
+    create_backup, list_backups, load_backup_policy, restore_backup, save_backup_policy,
+    BackupInfo, BackupPolicy, BackupRestoreReport,
+};
+use services::bug_report::{
+    system_info, DiagnosticsBuilder, DiagnosticsBundle, DiagnosticsCollectRequest,
+};
+use services::changed_files::{self, ChangedFile};
+use services::command_policy::{
+    configure_command_policy, evaluate_command, load_command_policy, save_command_policy,
+    set_task_command_mode, CommandPolicy, CommandVerdict,
+};
+use services::deps_audit::{load_deps_policy, save_deps_policy, DepsPolicy};
+use services::doctor::{run_doctor, DoctorCheck};
+use services::drafts::{load_draft, save_draft, Draft, DraftSaveRequest};
+use services::event_history::{
+    page_events, search_events, EventPage, EventSearchQuery, EventSearchResult,
+};
+use services::freshness::{load_freshness_policy, save_freshness_policy, FreshnessPolicy};
+use services::fuzzy_path::{load_path_policy, save_path_policy, PathPolicy};
+use services::git_ops::{
+    self, load_git_policy, save_git_policy, GitBranchRequest, GitCheckoutRequest, GitCommitRequest,
+    GitPolicy, GitStashRequest,
+};
+use services::history::{
+    FileHistory, FileHistoryEntry, FileHistoryRequest, FileRestoreRequest, FileVersion,
+};
+use services::hooks::{HookResult, HookRunner, WorkspaceHooks};
+use services::ignore::{
+    global_patterns, load_ignore_policy, normalize_patterns, save_ignore_policy,
+    set_global_patterns, workspace_patterns, IgnorePolicy, IgnoreRules, DEFAULT_IGNORE_PATTERNS,
 };
 use services::kernel::{
-    KernelManager, KernelPlanStatusRequest, KernelPlanUpdateRequest, KernelStartRequest,
-    KernelUserInputRequest, RunState,
+    normalize_tags, ChangesApplyRequest, ChangesApplyResponse, KernelApproveActionRequest,
+    KernelForkRunRequest, KernelManager, KernelPlanStatusRequest, KernelPlanUpdateRequest,
+    KernelRebuildStateRequest, KernelRebuildStateResponse, KernelRunStepsRequest,
+    KernelStartRequest, KernelUserInputRequest, PendingChangeView, RunCapabilities, RunState,
+    TaskConstraints,
+};
+use services::kernel::{validate_judge_rule, JudgeResult, JudgeRule};
+use services::llm::{
+    configure_replay, fetch_models, replay_status, LlmModelFetchRequest, LlmModelFetchResponse,
+    LlmProfile, LlmReplayMode, LlmReplayStatus,
+};
+use services::logging::{init_logging, log_dir, log_level, set_log_level};
+use services::mcp::{load_mcp_config, save_mcp_config, McpConfig, McpServerStatus};
+use services::patch;
+use services::profiles::{
+    is_protected, list_profiles, profile_audit_log, profile_from_args, profile_root,
+    set_active_profile, set_profile_passphrase, unlock_profile, validate_profile_name, ProfileInfo,
+    DEFAULT_PROFILE,
+};
+use services::provenance::{
+    load_provenance_policy, save_provenance_policy, FileProvenance, ProvenancePolicy,
+    ProvenanceStore,
 };
-use services::kernel::JudgeRule;
-use services::llm::{fetch_models, LlmModelFetchRequest, LlmModelFetchResponse, LlmProfile};
 use services::pty::{
-    TerminalCreateRequest, TerminalExecRequest, TerminalKillRequest, TerminalManager,
-    TerminalReplayRequest, TerminalReplayResponse, TerminalResizeRequest, TerminalSessionInfo,
-    TerminalSetOrderRequest, TerminalSetTitleRequest, TerminalWriteRequest,
+    TerminalCreateRequest, TerminalExecRequest, TerminalHistoryEntry, TerminalHistoryRequest,
+    TerminalKillRequest, TerminalListRequest, TerminalManager, TerminalOutputTransportRequest,
+    TerminalReplayRequest, TerminalReplayResponse, TerminalResizeRequest, TerminalSearchRequest,
+    TerminalSearchResponse, TerminalSessionInfo, TerminalSetOrderRequest, TerminalSetTitleRequest,
+    TerminalUpdateMetaRequest, TerminalWriteRequest,
+};
+use services::pty_output::{OutputTransport, PtyOutputPolicy};
+use services::rate_limit::ActionRateLimit;
+use services::report::{render_report, RunExportRequest, RunExportResponse};
+use services::resources::{load_resource_budget, save_resource_budget, ResourceBudget};
+use services::run_search::{search_runs, RunSearchQuery, RunSummary, RunTagsRequest, TaskLabels};
+use services::shutdown::ShutdownCoordinator;
+use services::storage_gc::{
+    self, load_gc_policy, save_gc_policy, GcPolicy, StorageGcReport, StorageGcRequest,
 };
+use services::todos::{scan_todos, TodoItem};
 use services::tools::{
-    max_read_bytes, read_file, run_command, search, write_file, CommandRequest, ReadFileRequest,
-    SearchMatch, SearchRequest, ToolResult, WriteFileRequest,
+    apply_patch, max_read_bytes, read_file, run_command, search, write_file, ApplyPatchRequest,
+    CommandRequest, ReadFileRequest, SearchMatch, SearchRequest, ToolResult, WriteFileRequest,
 };
+use services::transcript::{parse_transcript, TranscriptImportRequest};
+use services::trust::{set_workspace_trust, trust_config, workspace_trust, WorkspaceTrust};
+use services::verify::{load_verify_policy, save_verify_policy, VerifyPolicy};
+use services::visibility::{load_visibility_policy, save_visibility_policy, VisibilityPolicy};
 use services::workspace::{
-    default_workspace_root, display_path, resolve_read_path_with_fallback, WorkspaceState,
+    default_workspace_root, display_path, ignore_taurihands, resolve_read_path_with_fallback,
+    taurihands_ignore_status, IgnoreStatus, WorkspaceState,
+};
+use services::workspace_watch::{WatchStatus, WorkspaceWatcher};
+use services::write_preview::{
+    load_write_preview_policy, save_write_preview_policy, WritePreviewPolicy,
 };
 
 #[derive(Clone)]
@@ -45,7 +127,97 @@ struct AppState {
     audit: AuditLog,
     agent: AgentManager,
     kernel: KernelManager,
-    settings_path: PathBuf,
+    watcher: WorkspaceWatcher,
+    profiles: ProfileLayout,
+    profile: Arc<Mutex<String>>,
+    /// Set when launched with `--review`; reviewer mode then cannot be turned off.
+    reviewer_locked: bool,
+}
+
+impl AppState {
+    fn active_profile(&self) -> String {
+        self.profile
+            .lock()
+            .map(|profile| profile.clone())
+            .unwrap_or_else(|_| DEFAULT_PROFILE.to_string())
+    }
+
+    fn settings_path(&self) -> PathBuf {
+        self.profiles.settings_path(&self.active_profile())
+    }
+}
+
+/// Applies the visibility policy to a path named by a file command and
+/// audits every access to a hidden path, allowed or not.
+fn check_visibility(
+    state: &AppState,
+    command: &str,
+    path: &str,
+    include_hidden: Option<bool>,
+) -> Result<(), String> {
+    let root = state.workspace.root();
+    let checked = load_visibility_policy(&root).check_access(&root, path, include_hidden);
+    if !matches!(checked, Ok(false)) {
+        state.audit.write(AuditEntry {
+            timestamp_ms: now_ms(),
+            action: "fs.hidden_access".to_string(),
+            session_id: None,
+            command: Some(command.to_string()),
+            payload: serde_json::json!({
+                "path": path,
+                "allowed": checked.is_ok(),
+                "include_hidden": include_hidden,
+            }),
+        });
+    }
+    checked.map(|_| ())
+}
+
+/// Rejects commands that could modify the workspace while reviewer mode is on.
+fn ensure_writable(state: &AppState) -> Result<(), String> {
+    if state.kernel.is_reviewer_mode() {
+        return Err("Disabled in reviewer mode".to_string());
+    }
+    Ok(())
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ReviewerModeStatus {
+    enabled: bool,
+    locked: bool,
+}
+
+/// Where each user profile keeps its settings and LLM store.
+#[derive(Clone)]
+struct ProfileLayout {
+    identifier: String,
+    fallback_root: PathBuf,
+}
+
+impl ProfileLayout {
+    fn base(&self) -> PathBuf {
+        app_data_root(&self.identifier).unwrap_or_else(|| self.fallback_root.join(".taurihands"))
+    }
+
+    fn settings_path(&self, profile: &str) -> PathBuf {
+        if profile != DEFAULT_PROFILE {
+            return profile_root(&self.base(), profile).join("settings.json");
+        }
+        if let Some(base) = app_data_root(&self.identifier) {
+            return base.join("settings.json");
+        }
+        self.fallback_root
+            .join(".taurihands")
+            .join("app-settings.json")
+    }
+
+    fn llm_root(&self, profile: &str, workspace_root: &Path) -> PathBuf {
+        if profile != DEFAULT_PROFILE {
+            return profile_root(&self.base(), profile);
+        }
+        app_data_root(&self.identifier).unwrap_or_else(|| workspace_root.to_path_buf())
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -74,6 +246,12 @@ struct TaskConfig {
     budget: TaskBudget,
     risk_policy: TaskRiskPolicy,
     autonomy: String,
+    #[serde(default)]
+    constraints: Vec<String>,
+    #[serde(default)]
+    conventions_path: Option<String>,
+    #[serde(default)]
+    tags: Vec<String>,
 }
 
 #[derive(Serialize, Deserialize)]
@@ -82,16 +260,68 @@ struct TaskPointer {
     task_id: String,
 }
 
+/// Which of a task's judge rule sets is in use; stored next to its rules.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RulesetPointer {
+    name: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct JudgeRulesets {
+    active: String,
+    names: Vec<String>,
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct WorkspaceSettings {
     last_workspace: String,
+    #[serde(default)]
+    auto_ignore_target: Option<String>,
+    /// Most recent first, including `last_workspace`.
+    #[serde(default)]
+    recent_workspaces: Vec<String>,
+    /// Ignore globs applied to every workspace.
+    #[serde(default)]
+    ignore_patterns: Vec<String>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IgnorePatternsRequest {
+    /// "workspace" or "global".
+    scope: String,
+    patterns: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IgnorePatterns {
+    defaults: Vec<String>,
+    global: Vec<String>,
+    workspace: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct WorkspaceIgnoreRequest {
+    target: Option<String>,
+    remember: Option<bool>,
 }
 
 #[derive(Deserialize)]
 struct JudgeRulesRequest {
     task_id: String,
     rules: Vec<JudgeRule>,
+    /// Defaults to the task's active rule set.
+    #[serde(default)]
+    ruleset: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct JudgeEvaluateRequest {
+    rules: Option<Vec<JudgeRule>>,
 }
 
 #[derive(Deserialize)]
@@ -108,26 +338,293 @@ struct TreeNode {
     children: Option<Vec<TreeNode>>,
 }
 
+const MAX_RECENT_WORKSPACES: usize = 10;
+
 #[tauri::command]
 fn get_workspace_root(state: State<AppState>) -> Result<String, String> {
     Ok(display_path(&state.workspace.root()))
 }
 
 #[tauri::command]
-fn set_workspace_root(state: State<AppState>, root: String) -> Result<String, String> {
+fn set_workspace_root(
+    app: AppHandle,
+    state: State<AppState>,
+    root: String,
+) -> Result<String, String> {
+    let previous = state.workspace.root();
     let resolved = state.workspace.set_root(&root)?;
+    // Reviewer mode runs nothing from the repo, hooks included.
+    if previous != resolved && !state.kernel.is_reviewer_mode() {
+        spawn_workspace_hooks(
+            Some(app.clone()),
+            state.audit.clone(),
+            Some(previous),
+            Some(resolved.clone()),
+        );
+    }
     state.kernel.update_workspace_root(resolved.clone());
+    let watch = state.watcher.status();
+    if watch.watching {
+        let rules = tree_ignore_rules(&resolved, watch.show_hidden);
+        state
+            .watcher
+            .start(app.clone_sink(), resolved.clone(), rules, watch.show_hidden)?;
+    }
     let display = display_path(&resolved);
-    save_workspace_settings(&state.settings_path, &display)?;
+    let settings_path = state.settings_path();
+    save_workspace_settings(&settings_path, &display)?;
+    apply_auto_ignore(&settings_path, &resolved);
+    tray::refresh(&app);
     Ok(display)
 }
 
+#[tauri::command]
+fn profile_list(state: State<AppState>) -> Result<Vec<ProfileInfo>, String> {
+    Ok(list_profiles(
+        &state.profiles.base(),
+        &state.active_profile(),
+    ))
+}
+
+/// Switches settings, LLM store, audit log and per-workspace run data to
+/// another user profile, creating it on first use. Profiles other than the
+/// default are unlocked with their passphrase; a new profile takes the one
+/// given here. The workspace stays open.
+#[tauri::command]
+fn profile_switch(
+    state: State<AppState>,
+    name: String,
+    passphrase: Option<String>,
+) -> Result<Vec<ProfileInfo>, String> {
+    let name = name.trim().to_string();
+    validate_profile_name(&name)?;
+    if state.kernel.is_running() {
+        return Err("Stop the running agent before switching profiles".to_string());
+    }
+    let root = state.workspace.root();
+    let previous = state.active_profile();
+    if let Err(err) = unlock_profile(&state.profiles.base(), &name, passphrase.as_deref()) {
+        state.audit.write(AuditEntry {
+            timestamp_ms: now_ms(),
+            action: "profile.unlock_failed".to_string(),
+            session_id: None,
+            command: None,
+            payload: serde_json::json!({ "from": previous, "to": name }),
+        });
+        return Err(err);
+    }
+    set_active_profile(&name);
+    state.kernel.update_workspace_root(root.clone());
+    state
+        .kernel
+        .set_llm_root(state.profiles.llm_root(&name, &root));
+    state.audit.set_path(profile_audit_log(&root, &name));
+    *state
+        .profile
+        .lock()
+        .map_err(|_| "Profile lock poisoned".to_string())? = name.clone();
+    if load_workspace_settings(&state.settings_path()).is_none() {
+        save_workspace_settings(&state.settings_path(), &display_path(&root))?;
+    }
+    set_global_patterns(
+        load_workspace_settings(&state.settings_path())
+            .map(|settings| settings.ignore_patterns)
+            .unwrap_or_default(),
+    );
+    state.audit.write(AuditEntry {
+        timestamp_ms: now_ms(),
+        action: "profile.switch".to_string(),
+        session_id: None,
+        command: None,
+        payload: serde_json::json!({ "from": previous, "to": name }),
+    });
+    Ok(list_profiles(&state.profiles.base(), &name))
+}
+
+/// Changes the active profile's passphrase; the current one is required.
+#[tauri::command]
+fn profile_set_passphrase(
+    state: State<AppState>,
+    current: Option<String>,
+    passphrase: String,
+) -> Result<Vec<ProfileInfo>, String> {
+    let name = state.active_profile();
+    let base = state.profiles.base();
+    if is_protected(&base, &name) {
+        unlock_profile(&base, &name, current.as_deref())?;
+    }
+    set_profile_passphrase(&base, &name, &passphrase)?;
+    state.audit.write(AuditEntry {
+        timestamp_ms: now_ms(),
+        action: "profile.passphrase".to_string(),
+        session_id: None,
+        command: None,
+        payload: serde_json::json!({ "profile": name }),
+    });
+    Ok(list_profiles(&base, &name))
+}
+
+#[tauri::command]
+fn workspace_get_hooks(state: State<AppState>) -> Result<WorkspaceHooks, String> {
+    Ok(HookRunner::new(&state.workspace.root(), state.audit.clone()).load())
+}
+
+#[tauri::command]
+fn workspace_save_hooks(
+    state: State<AppState>,
+    hooks: WorkspaceHooks,
+) -> Result<WorkspaceHooks, String> {
+    ensure_writable(&state)?;
+    let root = state.workspace.root();
+    HookRunner::new(&root, state.audit.clone()).save(&hooks)?;
+    // Written by the user through the app, so it needs no separate approval.
+    trust_config(&root, "hooks.json")?;
+    Ok(hooks)
+}
+
+/// Which of the workspace's hook, verify and MCP configs the user trusts.
+#[tauri::command]
+fn workspace_get_trust(state: State<AppState>) -> Result<WorkspaceTrust, String> {
+    Ok(workspace_trust(&state.workspace.root()))
+}
+
+/// Trusts the current contents of the workspace's configs, or revokes trust.
+/// A config edited afterwards needs approval again.
+#[tauri::command]
+fn workspace_set_trust(state: State<AppState>, trusted: bool) -> Result<WorkspaceTrust, String> {
+    ensure_writable(&state)?;
+    let root = state.workspace.root();
+    let trust = set_workspace_trust(&root, trusted)?;
+    state.kernel.mcp().configure(&root);
+    state.audit.write(AuditEntry {
+        timestamp_ms: now_ms(),
+        action: "workspace.trust".to_string(),
+        session_id: None,
+        command: None,
+        payload: serde_json::json!({ "root": trust.root, "trusted": trusted }),
+    });
+    Ok(trust)
+}
+
+#[tauri::command]
+fn workspace_run_hooks(state: State<AppState>, event: String) -> Result<Vec<HookResult>, String> {
+    ensure_writable(&state)?;
+    let runner = HookRunner::new(&state.workspace.root(), state.audit.clone());
+    match event.as_str() {
+        "open" => Ok(runner.run_open()),
+        "close" => Ok(runner.run_close()),
+        other => Err(format!("Unknown hook event: {}", other)),
+    }
+}
+
+fn spawn_workspace_hooks(
+    app: Option<AppHandle>,
+    audit: AuditLog,
+    closed: Option<PathBuf>,
+    opened: Option<PathBuf>,
+) {
+    std::thread::spawn(move || {
+        let mut results = Vec::new();
+        if let Some(root) = closed {
+            results.extend(HookRunner::new(&root, audit.clone()).run_close());
+        }
+        let mut trust = None;
+        if let Some(root) = opened {
+            results.extend(HookRunner::new(&root, audit).run_open());
+            trust = Some(workspace_trust(&root)).filter(WorkspaceTrust::needs_approval);
+        }
+        if let Some(app) = app {
+            if !results.is_empty() {
+                let _ = app.emit("workspace-hooks", results);
+            }
+            // Untrusted configs were skipped; the frontend asks the user.
+            if let Some(trust) = trust {
+                let _ = app.emit("workspace-trust-required", trust);
+            }
+        }
+    });
+}
+
+#[tauri::command]
+fn workspace_ignore_status(state: State<AppState>) -> Result<IgnoreStatus, String> {
+    Ok(taurihands_ignore_status(&state.workspace.root()))
+}
+
+#[tauri::command]
+fn workspace_ignore_taurihands(
+    state: State<AppState>,
+    request: WorkspaceIgnoreRequest,
+) -> Result<IgnoreStatus, String> {
+    ensure_writable(&state)?;
+    let target = request.target.unwrap_or_else(|| "exclude".to_string());
+    let status = ignore_taurihands(&state.workspace.root(), &target)?;
+    if request.remember.unwrap_or(false) {
+        let settings_path = state.settings_path();
+        if let Some(mut settings) = load_workspace_settings(&settings_path) {
+            settings.auto_ignore_target = Some(target);
+            write_workspace_settings(&settings_path, &settings)?;
+        }
+    }
+    Ok(status)
+}
+
+#[tauri::command]
+fn workspace_get_visibility(state: State<AppState>) -> Result<VisibilityPolicy, String> {
+    Ok(load_visibility_policy(&state.workspace.root()))
+}
+
+#[tauri::command]
+fn workspace_set_visibility(
+    state: State<AppState>,
+    policy: VisibilityPolicy,
+) -> Result<VisibilityPolicy, String> {
+    ensure_writable(&state)?;
+    save_visibility_policy(&state.workspace.root(), &policy)?;
+    Ok(policy)
+}
+
+#[tauri::command]
+fn workspace_get_ignore_patterns(state: State<AppState>) -> Result<IgnorePatterns, String> {
+    Ok(IgnorePatterns {
+        defaults: DEFAULT_IGNORE_PATTERNS
+            .iter()
+            .map(|pattern| pattern.to_string())
+            .collect(),
+        global: global_patterns(),
+        workspace: load_ignore_policy(&state.workspace.root()).patterns,
+    })
+}
+
+#[tauri::command]
+fn workspace_set_ignore_patterns(
+    state: State<AppState>,
+    request: IgnorePatternsRequest,
+) -> Result<IgnorePatterns, String> {
+    ensure_writable(&state)?;
+    let patterns = normalize_patterns(request.patterns)?;
+    match request.scope.as_str() {
+        "workspace" => save_ignore_policy(&state.workspace.root(), &IgnorePolicy { patterns })?,
+        "global" => {
+            let settings_path = state.settings_path();
+            let mut settings = load_workspace_settings(&settings_path).ok_or_else(|| {
+                "Open a workspace before setting global ignore patterns".to_string()
+            })?;
+            settings.ignore_patterns = patterns.clone();
+            write_workspace_settings(&settings_path, &settings)?;
+            set_global_patterns(patterns);
+        }
+        other => return Err(format!("Unknown ignore scope: {}", other)),
+    }
+    workspace_get_ignore_patterns(state)
+}
+
 #[tauri::command]
 fn terminal_create_session(
     app: AppHandle,
     state: State<AppState>,
     request: TerminalCreateRequest,
 ) -> Result<TerminalSessionInfo, String> {
+    ensure_writable(&state)?;
     let cwd = match &request.cwd {
         Some(path) => state.workspace.resolve_path(path)?,
         None => state.workspace.root(),
@@ -137,11 +634,12 @@ fn terminal_create_session(
     }
     state
         .terminal
-        .create_session(app, request, cwd, &state.audit)
+        .create_session(&app, request, cwd, &state.audit)
 }
 
 #[tauri::command]
 fn terminal_write(state: State<AppState>, request: TerminalWriteRequest) -> Result<(), String> {
+    ensure_writable(&state)?;
     state.terminal.write(request, &state.audit)
 }
 
@@ -156,8 +654,14 @@ fn terminal_kill(state: State<AppState>, request: TerminalKillRequest) -> Result
 }
 
 #[tauri::command]
-fn terminal_list_sessions(state: State<AppState>) -> Result<Vec<TerminalSessionInfo>, String> {
-    state.terminal.list_sessions()
+fn terminal_list_sessions(
+    state: State<AppState>,
+    request: Option<TerminalListRequest>,
+) -> Result<Vec<TerminalSessionInfo>, String> {
+    match request {
+        Some(filter) => state.terminal.list_sessions_filtered(&filter),
+        None => state.terminal.list_sessions(),
+    }
 }
 
 #[tauri::command]
@@ -168,11 +672,60 @@ fn terminal_replay(
     state.terminal.replay(request)
 }
 
+#[tauri::command(async)]
+fn terminal_search_scrollback(
+    state: State<AppState>,
+    request: TerminalSearchRequest,
+) -> Result<TerminalSearchResponse, String> {
+    state.terminal.search_scrollback(request)
+}
+
+/// Streams a session's output as raw frames on `channel` instead of base64
+/// `terminal-output` events; see `encode_output_frame` for the layout.
+#[tauri::command]
+fn terminal_open_binary_output(
+    state: State<AppState>,
+    request: TerminalOutputTransportRequest,
+    channel: Channel<InvokeResponseBody>,
+) -> Result<OutputTransport, String> {
+    state
+        .terminal
+        .set_output_transport(&request.session_id, Some(Arc::new(channel)))
+}
+
+/// Goes back to `terminal-output` events, the fallback for consumers that
+/// cannot take binary frames.
+#[tauri::command]
+fn terminal_close_binary_output(
+    state: State<AppState>,
+    request: TerminalOutputTransportRequest,
+) -> Result<OutputTransport, String> {
+    state
+        .terminal
+        .set_output_transport(&request.session_id, None)
+}
+
+#[tauri::command]
+fn terminal_get_output_policy(state: State<AppState>) -> Result<PtyOutputPolicy, String> {
+    Ok(state.terminal.output_policy())
+}
+
+#[tauri::command]
+fn terminal_set_output_policy(
+    state: State<AppState>,
+    policy: PtyOutputPolicy,
+) -> Result<PtyOutputPolicy, String> {
+    ensure_writable(&state)?;
+    state.terminal.set_output_policy(&policy)?;
+    Ok(policy)
+}
+
 #[tauri::command]
 fn terminal_exec_interactive(
     state: State<AppState>,
     request: TerminalExecRequest,
 ) -> Result<ToolResult, String> {
+    ensure_writable(&state)?;
     let cwd = if request.session_id.is_some() {
         state.workspace.root()
     } else {
@@ -185,10 +738,29 @@ fn terminal_exec_interactive(
 }
 
 #[tauri::command]
-fn terminal_set_title(state: State<AppState>, request: TerminalSetTitleRequest) -> Result<(), String> {
+fn terminal_set_title(
+    state: State<AppState>,
+    request: TerminalSetTitleRequest,
+) -> Result<(), String> {
     state.terminal.set_title(request, &state.audit)
 }
 
+#[tauri::command]
+fn terminal_get_history(
+    state: State<AppState>,
+    request: TerminalHistoryRequest,
+) -> Result<Vec<TerminalHistoryEntry>, String> {
+    state.terminal.get_history(request)
+}
+
+#[tauri::command]
+fn terminal_update_session_meta(
+    state: State<AppState>,
+    request: TerminalUpdateMetaRequest,
+) -> Result<TerminalSessionInfo, String> {
+    state.terminal.update_session_meta(request, &state.audit)
+}
+
 #[tauri::command]
 fn terminal_set_order(
     state: State<AppState>,
@@ -199,6 +771,7 @@ fn terminal_set_order(
 
 #[tauri::command]
 fn tool_run_command(state: State<AppState>, request: CommandRequest) -> Result<ToolResult, String> {
+    ensure_writable(&state)?;
     let cwd = match &request.cwd {
         Some(path) => state.workspace.resolve_path(path)?,
         None => state.workspace.root(),
@@ -209,7 +782,12 @@ fn tool_run_command(state: State<AppState>, request: CommandRequest) -> Result<T
 }
 
 #[tauri::command]
-fn fs_read_file(state: State<AppState>, request: ReadFileRequest) -> Result<ToolResult, String> {
+fn fs_read_file(
+    state: State<AppState>,
+    request: ReadFileRequest,
+    include_hidden: Option<bool>,
+) -> Result<ToolResult, String> {
+    check_visibility(&state, "fs_read_file", &request.path, include_hidden)?;
     let path = resolve_read_path_with_fallback(&state.workspace, &request.path)?;
     let max_bytes = max_read_bytes();
     let file = File::open(&path).map_err(|e| e.to_string())?;
@@ -223,17 +801,143 @@ fn fs_read_file(state: State<AppState>, request: ReadFileRequest) -> Result<Tool
 }
 
 #[tauri::command]
-fn fs_write_file(state: State<AppState>, request: WriteFileRequest) -> Result<ToolResult, String> {
+fn fs_write_file(
+    state: State<AppState>,
+    request: WriteFileRequest,
+    include_hidden: Option<bool>,
+) -> Result<ToolResult, String> {
+    ensure_writable(&state)?;
+    check_visibility(&state, "fs_write_file", &request.path, include_hidden)?;
     let path = state.workspace.resolve_path_for_write(&request.path)?;
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent).map_err(|e| e.to_string())?;
     }
+    let _ = FileHistory::for_root(&state.workspace.root()).record(&path, "user", None);
+    let before = fs::read_to_string(&path).ok();
     fs::write(&path, request.content.as_bytes()).map_err(|e| e.to_string())?;
-    Ok(write_file(request, path.metadata().map(|m| m.len() as usize).unwrap_or(0), &state.audit))
+    let _ = ProvenanceStore::for_root(&state.workspace.root()).record(
+        &path,
+        before.as_deref(),
+        &request.content,
+        "user",
+        None,
+    );
+    Ok(write_file(
+        request,
+        path.metadata().map(|m| m.len() as usize).unwrap_or(0),
+        &state.audit,
+    ))
+}
+
+#[tauri::command]
+fn fs_apply_patch(
+    state: State<AppState>,
+    request: ApplyPatchRequest,
+    include_hidden: Option<bool>,
+) -> Result<ToolResult, String> {
+    ensure_writable(&state)?;
+    check_visibility(&state, "fs_apply_patch", &request.path, include_hidden)?;
+    let path = state.workspace.resolve_path_for_write(&request.path)?;
+    let before = patch::read_patch_target(&path)?;
+    let outcome = patch::apply_patch(before.as_deref().unwrap_or_default(), &request.patch)?;
+    let Some(content) = outcome.content.as_deref() else {
+        return Ok(apply_patch(&request, &outcome, 0, &state.audit));
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let _ = FileHistory::for_root(&state.workspace.root()).record(&path, "user", None);
+    fs::write(&path, content.as_bytes()).map_err(|e| e.to_string())?;
+    let _ = ProvenanceStore::for_root(&state.workspace.root()).record(
+        &path,
+        before.as_deref(),
+        content,
+        "user",
+        None,
+    );
+    Ok(apply_patch(&request, &outcome, content.len(), &state.audit))
+}
+
+#[tauri::command]
+fn fs_history(
+    state: State<AppState>,
+    request: FileHistoryRequest,
+) -> Result<Vec<FileHistoryEntry>, String> {
+    let path = state.workspace.resolve_path_for_write(&request.path)?;
+    Ok(FileHistory::for_root(&state.workspace.root()).history(&path))
+}
+
+#[tauri::command]
+fn provenance_for_file(
+    state: State<AppState>,
+    request: FileHistoryRequest,
+) -> Result<Option<FileProvenance>, String> {
+    let path = state.workspace.resolve_path_for_write(&request.path)?;
+    Ok(ProvenanceStore::for_root(&state.workspace.root()).for_file(&path))
+}
+
+#[tauri::command]
+fn fs_restore_version(
+    state: State<AppState>,
+    request: FileRestoreRequest,
+) -> Result<FileVersion, String> {
+    ensure_writable(&state)?;
+    let path = state.workspace.resolve_path_for_write(&request.path)?;
+    let history = FileHistory::for_root(&state.workspace.root());
+    let before = fs::read_to_string(&path).ok();
+    let version = history.restore(&path, &request.version_id)?;
+    if let Ok(after) = fs::read_to_string(&path) {
+        let _ = ProvenanceStore::for_root(&state.workspace.root()).record(
+            &path,
+            before.as_deref(),
+            &after,
+            "user",
+            None,
+        );
+    }
+    state.audit.write(AuditEntry {
+        timestamp_ms: now_ms(),
+        action: "fs.restore_version".to_string(),
+        session_id: None,
+        command: None,
+        payload: serde_json::json!({
+            "path": request.path,
+            "version_id": request.version_id,
+        }),
+    });
+    Ok(version)
+}
+
+#[derive(Deserialize)]
+struct CodeTodosRequest {
+    path: Option<String>,
+}
+
+#[tauri::command]
+fn code_todos(
+    state: State<AppState>,
+    request: Option<CodeTodosRequest>,
+) -> Result<Vec<TodoItem>, String> {
+    let root = state.workspace.root();
+    let scope = match request.and_then(|request| request.path) {
+        Some(path) => Some(state.workspace.resolve_path(&path)?),
+        None => None,
+    };
+    scan_todos(&root, scope.as_deref())
 }
 
 #[tauri::command]
 fn fs_search(state: State<AppState>, request: SearchRequest) -> Result<ToolResult, String> {
+    for path in request
+        .paths
+        .iter()
+        .flatten()
+        .filter(|path| !is_glob_like(path))
+    {
+        check_visibility(&state, "fs_search", path, request.include_hidden)?;
+    }
+    let hidden =
+        load_visibility_policy(&state.workspace.root()).lists_hidden(request.include_hidden);
     let trimmed = request.pattern.trim();
     let (paths, mut globs) = resolve_search_targets(&state.workspace, &request.paths);
     if let Some(glob) = &request.glob {
@@ -241,14 +945,14 @@ fn fs_search(state: State<AppState>, request: SearchRequest) -> Result<ToolResul
     }
 
     if trimmed == "*" {
-        let output = run_rg_files(&paths, &globs)?;
+        let output = run_rg_files(&paths, &globs, hidden)?;
         let max_results = request.max_results.unwrap_or(200);
         let matches = parse_rg_files(&output, max_results);
         return Ok(search(request, matches, &state.audit));
     }
 
     let (pattern, force_fixed) = normalize_search_pattern(trimmed);
-    let output = run_rg_search(&pattern, &paths, &globs, force_fixed)?;
+    let output = run_rg_search(&pattern, &paths, &globs, hidden, force_fixed)?;
     let max_results = request.max_results.unwrap_or(200);
     let matches = parse_rg_json(&output, max_results);
     Ok(search(request, matches, &state.audit))
@@ -258,12 +962,20 @@ fn fs_search(state: State<AppState>, request: SearchRequest) -> Result<ToolResul
 fn git_status(state: State<AppState>) -> Result<ToolResult, String> {
     let request = CommandRequest {
         program: "git".to_string(),
-        args: Some(vec!["status".into(), "--porcelain=v1".into(), "--untracked-files=all".into()]),
+        args: Some(vec![
+            "status".into(),
+            "--porcelain=v1".into(),
+            "--untracked-files=all".into(),
+        ]),
         cwd: Some(state.workspace.root().to_string_lossy().to_string()),
         env: None,
         timeout_ms: None,
     };
-    run_command(request, state.workspace.root().to_string_lossy().as_ref(), &state.audit)
+    run_command(
+        request,
+        state.workspace.root().to_string_lossy().as_ref(),
+        &state.audit,
+    )
 }
 
 #[tauri::command]
@@ -281,218 +993,1062 @@ fn git_diff(state: State<AppState>, request: GitDiffRequest) -> Result<ToolResul
         env: None,
         timeout_ms: None,
     };
-    run_command(request, state.workspace.root().to_string_lossy().as_ref(), &state.audit)
+    run_command(
+        request,
+        state.workspace.root().to_string_lossy().as_ref(),
+        &state.audit,
+    )
 }
 
 #[tauri::command]
-fn tests_run(state: State<AppState>, request: CommandRequest) -> Result<ToolResult, String> {
-    tool_run_command(state, request)
+fn git_commit(state: State<AppState>, request: GitCommitRequest) -> Result<ToolResult, String> {
+    ensure_writable(&state)?;
+    git_ops::git_commit(&state.workspace, &state.audit, &request, "user")
 }
 
 #[tauri::command]
-fn fs_list_tree(
+fn git_branch_create(
     state: State<AppState>,
-    max_depth: Option<usize>,
-    max_entries: Option<usize>,
-    show_hidden: Option<bool>,
-) -> Result<Vec<TreeNode>, String> {
-    let root = state.workspace.root();
-    let max_depth = max_depth.unwrap_or(4);
-    let max_entries = max_entries.unwrap_or(2000);
-    let show_hidden = show_hidden.unwrap_or(false);
-    let mut count = 0usize;
-    list_tree(
-        &root,
-        &root,
-        0,
-        max_depth,
-        max_entries,
-        show_hidden,
-        &mut count,
-    )
+    request: GitBranchRequest,
+) -> Result<ToolResult, String> {
+    ensure_writable(&state)?;
+    git_ops::git_branch(&state.workspace, &state.audit, &request, "user")
+}
+
+#[tauri::command]
+fn git_stash(state: State<AppState>, request: GitStashRequest) -> Result<ToolResult, String> {
+    ensure_writable(&state)?;
+    git_ops::git_stash(&state.workspace, &state.audit, &request, "user")
+}
+
+#[tauri::command]
+fn git_checkout(state: State<AppState>, request: GitCheckoutRequest) -> Result<ToolResult, String> {
+    ensure_writable(&state)?;
+    git_ops::git_checkout(&state.workspace, &state.audit, &request, "user")
+}
+
+#[tauri::command]
+fn git_get_policy(state: State<AppState>) -> Result<GitPolicy, String> {
+    Ok(load_git_policy(&state.workspace.root()))
+}
+
+#[tauri::command]
+fn git_set_policy(state: State<AppState>, policy: GitPolicy) -> Result<GitPolicy, String> {
+    ensure_writable(&state)?;
+    save_git_policy(&state.workspace.root(), &policy)?;
+    Ok(policy)
+}
+
+#[tauri::command]
+fn terminal_get_command_policy(state: State<AppState>) -> Result<CommandPolicy, String> {
+    Ok(load_command_policy(&state.workspace.root()))
+}
+
+#[tauri::command]
+fn terminal_set_command_policy(
+    state: State<AppState>,
+    policy: CommandPolicy,
+) -> Result<CommandPolicy, String> {
+    ensure_writable(&state)?;
+    let root = state.workspace.root();
+    save_command_policy(&root, &policy)?;
+    configure_command_policy(&root);
+    Ok(policy)
+}
+
+#[tauri::command]
+fn mcp_get_config(state: State<AppState>) -> Result<McpConfig, String> {
+    Ok(load_mcp_config(&state.workspace.root()))
+}
+
+/// Saving closes open MCP connections; they reconnect on next use.
+#[tauri::command]
+fn mcp_save_config(state: State<AppState>, config: McpConfig) -> Result<McpConfig, String> {
+    ensure_writable(&state)?;
+    let root = state.workspace.root();
+    save_mcp_config(&root, &config)?;
+    trust_config(&root, "mcp.json")?;
+    state.kernel.mcp().configure(&root);
+    Ok(config)
+}
+
+#[tauri::command]
+fn mcp_status(state: State<AppState>) -> Result<Vec<McpServerStatus>, String> {
+    Ok(state.kernel.mcp().status())
+}
+
+/// Reconnects to every enabled server and lists its tools.
+#[tauri::command(async)]
+fn mcp_refresh(state: State<AppState>) -> Result<Vec<McpServerStatus>, String> {
+    Ok(state.kernel.mcp().refresh())
+}
+
+/// Evaluates a command line against the active policy without running it.
+#[tauri::command]
+fn terminal_check_command(command: String) -> Result<CommandVerdict, String> {
+    Ok(evaluate_command(&command))
+}
+
+#[tauri::command]
+fn tests_run(state: State<AppState>, request: CommandRequest) -> Result<ToolResult, String> {
+    ensure_writable(&state)?;
+    tool_run_command(state, request)
+}
+
+#[tauri::command]
+fn fs_list_tree(
+    state: State<AppState>,
+    max_depth: Option<usize>,
+    max_entries: Option<usize>,
+    show_hidden: Option<bool>,
+) -> Result<Vec<TreeNode>, String> {
+    let root = state.workspace.root();
+    let max_depth = max_depth.unwrap_or(4);
+    let max_entries = max_entries.unwrap_or(2000);
+    let rules = tree_ignore_rules(&root, show_hidden);
+    let mut count = 0usize;
+    list_tree(&root, &root, 0, max_depth, max_entries, &rules, &mut count)
+}
+
+/// The entries `fs_list_tree` leaves out, which the watcher does not report.
+fn tree_ignore_rules(root: &Path, show_hidden: Option<bool>) -> IgnoreRules {
+    let mut patterns = workspace_patterns(root);
+    if !load_visibility_policy(root).lists_hidden(show_hidden) {
+        patterns.push(".*".to_string());
+    }
+    IgnoreRules::new(patterns)
+}
+
+/// Emits `workspace-changed` for files created, modified or deleted in the
+/// workspace, so the file tree does not have to be listed again.
+#[tauri::command]
+fn workspace_watch_start(
+    app: AppHandle,
+    state: State<AppState>,
+    show_hidden: Option<bool>,
+) -> Result<WatchStatus, String> {
+    let root = state.workspace.root();
+    let rules = tree_ignore_rules(&root, show_hidden);
+    state
+        .watcher
+        .start(app.clone_sink(), root, rules, show_hidden)
+}
+
+/// Per-path badges for the file tree: what the current run changed, what
+/// the user changed, and what is conflicted.
+#[tauri::command]
+fn workspace_changed_files(state: State<AppState>) -> Result<Vec<ChangedFile>, String> {
+    let run_id = state.kernel.snapshot().run_id;
+    Ok(changed_files::workspace_changed_files(
+        &state.workspace.root(),
+        &run_id,
+    ))
+}
+
+#[tauri::command]
+fn workspace_watch_stop(state: State<AppState>) -> Result<WatchStatus, String> {
+    state.watcher.stop()
+}
+
+#[tauri::command]
+fn workspace_watch_status(state: State<AppState>) -> Result<WatchStatus, String> {
+    Ok(state.watcher.status())
+}
+
+#[tauri::command]
+fn agent_get_state(state: State<AppState>) -> Result<AgentState, String> {
+    Ok(state.agent.snapshot())
+}
+
+#[tauri::command]
+fn agent_start(
+    app: AppHandle,
+    state: State<AppState>,
+    request: AgentStartRequest,
+) -> Result<AgentState, String> {
+    ensure_writable(&state)?;
+    state.agent.start(
+        &app,
+        state.terminal.clone(),
+        state.workspace.clone(),
+        state.audit.clone(),
+        request,
+    )
+}
+
+#[tauri::command]
+fn agent_run_plan_items(
+    app: AppHandle,
+    state: State<AppState>,
+    request: AgentRunPlanItemsRequest,
+) -> Result<AgentState, String> {
+    ensure_writable(&state)?;
+    state.agent.run_plan_items(
+        &app,
+        state.terminal.clone(),
+        state.workspace.clone(),
+        state.audit.clone(),
+        request,
+    )
+}
+
+#[tauri::command]
+fn agent_pause(app: AppHandle, state: State<AppState>) -> Result<AgentState, String> {
+    state.agent.pause(&app)
+}
+
+#[tauri::command]
+fn agent_resume(app: AppHandle, state: State<AppState>) -> Result<AgentState, String> {
+    ensure_writable(&state)?;
+    state.agent.resume(&app)
+}
+
+#[tauri::command]
+fn agent_reset(app: AppHandle, state: State<AppState>) -> Result<AgentState, String> {
+    state.agent.reset(&app)
+}
+
+#[tauri::command]
+fn agent_set_auto_run(
+    app: AppHandle,
+    state: State<AppState>,
+    request: AgentAutoRunRequest,
+) -> Result<AgentState, String> {
+    state.agent.set_auto_run(&app, request.auto_run)
+}
+
+#[tauri::command]
+fn agent_set_verify_preset(
+    app: AppHandle,
+    state: State<AppState>,
+    request: AgentVerifyRequest,
+) -> Result<AgentState, String> {
+    state.agent.set_verify_preset(&app, request.preset)
+}
+
+#[tauri::command]
+fn agent_add_plan_items(
+    app: AppHandle,
+    state: State<AppState>,
+    request: AgentPlanItemsRequest,
+) -> Result<AgentState, String> {
+    state.agent.add_plan_items(&app, request.items)
+}
+
+#[tauri::command]
+fn agent_remove_plan_item(
+    app: AppHandle,
+    state: State<AppState>,
+    request: AgentRemovePlanItemRequest,
+) -> Result<AgentState, String> {
+    state.agent.remove_plan_item(&app, request.id)
+}
+
+#[tauri::command]
+fn agent_clear_plan_items(app: AppHandle, state: State<AppState>) -> Result<AgentState, String> {
+    state.agent.clear_plan_items(&app)
+}
+
+#[tauri::command]
+fn agent_generate_plan(
+    app: AppHandle,
+    state: State<AppState>,
+    request: AgentGeneratePlanRequest,
+) -> Result<AgentState, String> {
+    state.agent.generate_plan(&app, request)
+}
+
+#[tauri::command]
+fn agent_skip_plan_item(
+    app: AppHandle,
+    state: State<AppState>,
+    request: AgentPlanItemStatusRequest,
+) -> Result<AgentState, String> {
+    state.agent.skip_plan_item(&app, request)
+}
+
+#[tauri::command]
+fn agent_retry_plan_item(
+    app: AppHandle,
+    state: State<AppState>,
+    request: AgentPlanItemStatusRequest,
+) -> Result<AgentState, String> {
+    ensure_writable(&state)?;
+    state.agent.retry_plan_item(&app, request)
+}
+
+#[tauri::command]
+fn kernel_get_state(state: State<AppState>) -> Result<RunState, String> {
+    Ok(state.kernel.snapshot())
+}
+
+#[tauri::command]
+fn kernel_get_capabilities(state: State<AppState>) -> Result<RunCapabilities, String> {
+    Ok(state.kernel.capabilities())
+}
+
+#[tauri::command]
+fn kernel_get_freshness_policy(state: State<AppState>) -> Result<FreshnessPolicy, String> {
+    Ok(load_freshness_policy(&state.workspace.root()))
+}
+
+#[tauri::command]
+fn kernel_set_freshness_policy(
+    state: State<AppState>,
+    policy: FreshnessPolicy,
+) -> Result<FreshnessPolicy, String> {
+    ensure_writable(&state)?;
+    save_freshness_policy(&state.workspace.root(), &policy)?;
+    Ok(policy)
+}
+
+#[tauri::command]
+fn backup_get_policy(state: State<AppState>) -> Result<BackupPolicy, String> {
+    Ok(load_backup_policy(&state.workspace.root()))
+}
+
+#[tauri::command]
+fn backup_set_policy(state: State<AppState>, policy: BackupPolicy) -> Result<BackupPolicy, String> {
+    ensure_writable(&state)?;
+    save_backup_policy(&state.workspace.root(), &policy)?;
+    Ok(policy)
+}
+
+#[tauri::command(async)]
+fn backup_create(state: State<AppState>, reason: Option<String>) -> Result<BackupInfo, String> {
+    ensure_writable(&state)?;
+    let root = state.workspace.root();
+    let policy = load_backup_policy(&root);
+    let reason = reason
+        .map(|reason| reason.trim().to_string())
+        .filter(|reason| !reason.is_empty())
+        .unwrap_or_else(|| "manual".to_string());
+    create_backup(
+        &root,
+        &IgnoreRules::for_workspace(&root),
+        &reason,
+        policy.retention,
+    )
+}
+
+#[tauri::command]
+fn backup_list(state: State<AppState>) -> Result<Vec<BackupInfo>, String> {
+    Ok(list_backups(&state.workspace.root()))
+}
+
+/// Writes a backup back into the workspace; `prune` also deletes files
+/// created since the backup was taken.
+#[tauri::command(async)]
+fn backup_restore(
+    state: State<AppState>,
+    id: String,
+    prune: Option<bool>,
+) -> Result<BackupRestoreReport, String> {
+    ensure_writable(&state)?;
+    if state.kernel.is_running() {
+        return Err("Stop the current run before restoring a backup".to_string());
+    }
+    let root = state.workspace.root();
+    let rules = IgnoreRules::for_workspace(&root);
+    restore_backup(&root, id.trim(), &rules, prune.unwrap_or(false))
+}
+
+#[tauri::command]
+fn kernel_rebuild_state(
+    state: State<AppState>,
+    request: KernelRebuildStateRequest,
+) -> Result<KernelRebuildStateResponse, String> {
+    state.kernel.rebuild_state(request)
+}
+
+#[tauri::command]
+fn kernel_get_rate_limits(state: State<AppState>) -> Result<Vec<ActionRateLimit>, String> {
+    state.kernel.get_rate_limits()
+}
+
+#[tauri::command]
+fn kernel_set_rate_limits(
+    state: State<AppState>,
+    limits: Vec<ActionRateLimit>,
+) -> Result<Vec<ActionRateLimit>, String> {
+    ensure_writable(&state)?;
+    state.kernel.set_rate_limits(limits)?;
+    state.kernel.get_rate_limits()
+}
+
+#[tauri::command]
+fn kernel_get_verify_policy(state: State<AppState>) -> Result<VerifyPolicy, String> {
+    Ok(load_verify_policy(&state.workspace.root()))
+}
+
+#[tauri::command]
+fn kernel_set_verify_policy(
+    state: State<AppState>,
+    policy: VerifyPolicy,
+) -> Result<VerifyPolicy, String> {
+    ensure_writable(&state)?;
+    let root = state.workspace.root();
+    save_verify_policy(&root, &policy)?;
+    trust_config(&root, "verify.json")?;
+    Ok(policy)
+}
+
+#[tauri::command]
+fn kernel_get_path_policy(state: State<AppState>) -> Result<PathPolicy, String> {
+    Ok(load_path_policy(&state.workspace.root()))
+}
+
+#[tauri::command]
+fn kernel_set_path_policy(
+    state: State<AppState>,
+    policy: PathPolicy,
+) -> Result<PathPolicy, String> {
+    ensure_writable(&state)?;
+    save_path_policy(&state.workspace.root(), &policy)?;
+    Ok(policy)
+}
+
+#[tauri::command]
+fn kernel_get_provenance_policy(state: State<AppState>) -> Result<ProvenancePolicy, String> {
+    Ok(load_provenance_policy(&state.workspace.root()))
+}
+
+#[tauri::command]
+fn kernel_set_provenance_policy(
+    state: State<AppState>,
+    policy: ProvenancePolicy,
+) -> Result<ProvenancePolicy, String> {
+    ensure_writable(&state)?;
+    save_provenance_policy(&state.workspace.root(), &policy)?;
+    Ok(policy)
+}
+
+#[tauri::command]
+fn kernel_get_deps_policy(state: State<AppState>) -> Result<DepsPolicy, String> {
+    Ok(load_deps_policy(&state.workspace.root()))
+}
+
+#[tauri::command]
+fn kernel_set_deps_policy(
+    state: State<AppState>,
+    policy: DepsPolicy,
+) -> Result<DepsPolicy, String> {
+    ensure_writable(&state)?;
+    save_deps_policy(&state.workspace.root(), &policy)?;
+    Ok(policy)
+}
+
+#[tauri::command]
+fn kernel_get_upload_policy(state: State<AppState>) -> Result<UploadPolicy, String> {
+    Ok(load_upload_policy(&state.workspace.root()))
+}
+
+#[tauri::command]
+fn kernel_set_upload_policy(
+    state: State<AppState>,
+    policy: UploadPolicy,
+) -> Result<UploadPolicy, String> {
+    ensure_writable(&state)?;
+    save_upload_policy(&state.workspace.root(), &policy)?;
+    Ok(policy)
+}
+
+#[tauri::command]
+fn kernel_get_resource_budget() -> Result<ResourceBudget, String> {
+    Ok(load_resource_budget())
+}
+
+#[tauri::command]
+fn kernel_set_resource_budget(
+    state: State<AppState>,
+    budget: ResourceBudget,
+) -> Result<ResourceBudget, String> {
+    ensure_writable(&state)?;
+    save_resource_budget(&budget)?;
+    Ok(budget)
+}
+
+#[tauri::command]
+fn kernel_set_upload_secret(
+    state: State<AppState>,
+    name: String,
+    value: String,
+) -> Result<(), String> {
+    ensure_writable(&state)?;
+    set_upload_secret(&name, &value)
+}
+
+#[tauri::command]
+fn storage_get_gc_policy(state: State<AppState>) -> Result<GcPolicy, String> {
+    Ok(load_gc_policy(&state.workspace.root()))
+}
+
+#[tauri::command]
+fn storage_set_gc_policy(state: State<AppState>, policy: GcPolicy) -> Result<GcPolicy, String> {
+    ensure_writable(&state)?;
+    save_gc_policy(&state.workspace.root(), &policy)?;
+    Ok(policy)
+}
+
+#[tauri::command]
+fn storage_get_encryption(state: State<AppState>) -> Result<AtRestStatus, String> {
+    Ok(state.kernel.at_rest().status(&state.workspace.root()))
+}
+
+#[tauri::command]
+fn storage_set_encryption(state: State<AppState>, enabled: bool) -> Result<AtRestStatus, String> {
+    ensure_writable(&state)?;
+    if state.kernel.is_running() {
+        return Err("Stop the current run before changing encryption".to_string());
+    }
+    state
+        .kernel
+        .at_rest()
+        .set_encryption(&state.workspace.root(), enabled)
+}
+
+#[tauri::command]
+fn storage_gc(
+    state: State<AppState>,
+    request: StorageGcRequest,
+) -> Result<StorageGcReport, String> {
+    if request.apply {
+        ensure_writable(&state)?;
+    }
+    let root = state.workspace.root();
+    let mut policy = load_gc_policy(&root);
+    if let Some(days) = request.retention_days {
+        policy.retention_days = days;
+    }
+    // The current run and every open terminal are kept whatever their age.
+    let mut in_use: std::collections::HashSet<String> = state
+        .terminal
+        .list_sessions()?
+        .into_iter()
+        .map(|session| session.id)
+        .collect();
+    in_use.insert(state.kernel.snapshot().run_id);
+    Ok(storage_gc::storage_gc(
+        &root,
+        state.kernel.at_rest(),
+        &policy,
+        &in_use,
+        request.apply,
+    ))
+}
+
+#[tauri::command]
+fn kernel_start(
+    app: AppHandle,
+    state: State<AppState>,
+    request: KernelStartRequest,
+) -> Result<RunState, String> {
+    if !request.readonly && !state.kernel.is_running() {
+        backup_before_autonomous_run(&state, request.task_id.as_deref())?;
+    }
+    state.kernel.start(&app, request)
+}
+
+/// Archives the workspace before a run of a task with `auto` autonomy, which
+/// makes changes without asking; git snapshots do not cover non-git workspaces.
+fn backup_before_autonomous_run(state: &AppState, task_id: Option<&str>) -> Result<(), String> {
+    let root = state.workspace.root();
+    let policy = load_backup_policy(&root);
+    if !policy.before_autonomous_runs || state.kernel.is_reviewer_mode() {
+        return Ok(());
+    }
+    let task_id = match task_id {
+        Some(task_id) => task_id.to_string(),
+        None => match read_json::<TaskPointer>(&task_base_dir(&root).join("active.json")) {
+            Ok(pointer) => pointer.task_id,
+            Err(_) => return Ok(()),
+        },
+    };
+    let Ok(config) = read_json::<TaskConfig>(&task_dir(&root, &task_id).join("task.json")) else {
+        return Ok(());
+    };
+    if config.autonomy != "auto" {
+        return Ok(());
+    }
+    let rules = IgnoreRules::for_workspace(&root);
+    create_backup(&root, &rules, "autonomous run", policy.retention)
+        .map(|_| ())
+        .map_err(|err| format!("Backup before autonomous run failed: {}", err))
+}
+
+#[tauri::command]
+fn kernel_set_narration(state: State<AppState>, enabled: bool) -> Result<(), String> {
+    state.kernel.set_narration(enabled);
+    Ok(())
+}
+
+#[tauri::command]
+fn app_get_reviewer_mode(state: State<AppState>) -> Result<ReviewerModeStatus, String> {
+    Ok(ReviewerModeStatus {
+        enabled: state.kernel.is_reviewer_mode(),
+        locked: state.reviewer_locked,
+    })
+}
+
+#[tauri::command]
+fn app_set_reviewer_mode(
+    app: AppHandle,
+    state: State<AppState>,
+    enabled: bool,
+) -> Result<ReviewerModeStatus, String> {
+    if !enabled && state.reviewer_locked {
+        return Err("Reviewer mode was enabled with --review and cannot be turned off".to_string());
+    }
+    state.kernel.set_reviewer_mode(enabled);
+    if enabled {
+        state.kernel.set_readonly(&app, true)?;
+    }
+    state.audit.write(AuditEntry {
+        timestamp_ms: now_ms(),
+        action: "app.reviewer_mode".to_string(),
+        session_id: None,
+        command: None,
+        payload: serde_json::json!({ "enabled": enabled }),
+    });
+    app_get_reviewer_mode(state)
+}
+
+#[tauri::command]
+async fn app_run_doctor(state: State<'_, AppState>) -> Result<Vec<DoctorCheck>, String> {
+    let profile = state.kernel.get_llm_profile();
+    Ok(run_doctor(&state.profiles.base(), profile.as_ref()).await)
+}
+
+/// Prometheus text for the same counters `serve` exposes at `/metrics`.
+#[tauri::command]
+fn app_get_metrics() -> String {
+    services::metrics::render()
+}
+
+/// Action outcomes and decision errors per model since the app started.
+#[tauri::command]
+fn llm_action_stats() -> Vec<services::metrics::ModelActionStats> {
+    services::metrics::model_action_stats()
+}
+
+#[tauri::command]
+fn kernel_set_readonly(
+    app: AppHandle,
+    state: State<AppState>,
+    readonly: bool,
+) -> Result<RunState, String> {
+    state.kernel.set_readonly(&app, readonly)
+}
+
+#[tauri::command]
+fn context_pin_file(
+    app: AppHandle,
+    state: State<AppState>,
+    path: String,
+) -> Result<RunState, String> {
+    ensure_writable(&state)?;
+    state.kernel.pin_file(&app, &path)
+}
+
+#[tauri::command]
+fn context_unpin_file(
+    app: AppHandle,
+    state: State<AppState>,
+    path: String,
+) -> Result<RunState, String> {
+    state.kernel.unpin_file(&app, &path)
+}
+
+#[tauri::command]
+fn changes_list_pending(state: State<AppState>) -> Result<Vec<PendingChangeView>, String> {
+    state.kernel.list_pending_changes()
+}
+
+#[tauri::command]
+fn changes_apply(
+    app: AppHandle,
+    state: State<AppState>,
+    request: ChangesApplyRequest,
+) -> Result<ChangesApplyResponse, String> {
+    state.kernel.apply_changes(&app, request)
+}
+
+#[tauri::command]
+fn kernel_pause(app: AppHandle, state: State<AppState>) -> Result<RunState, String> {
+    state.kernel.pause(&app)
+}
+
+#[tauri::command]
+fn kernel_resume(app: AppHandle, state: State<AppState>) -> Result<RunState, String> {
+    state.kernel.resume(&app)
+}
+
+#[tauri::command]
+fn kernel_stop(app: AppHandle, state: State<AppState>) -> Result<RunState, String> {
+    state.kernel.stop(&app)
+}
+
+#[tauri::command]
+fn kernel_continue(app: AppHandle, state: State<AppState>) -> Result<RunState, String> {
+    state.kernel.continue_run(&app)
 }
 
 #[tauri::command]
-fn agent_get_state(state: State<AppState>) -> Result<AgentState, String> {
-    Ok(state.agent.snapshot())
+fn kernel_run_steps(
+    app: AppHandle,
+    state: State<AppState>,
+    request: KernelRunStepsRequest,
+) -> Result<RunState, String> {
+    state.kernel.run_steps(&app, request)
 }
 
 #[tauri::command]
-fn agent_start(
+fn kernel_user_input(
     app: AppHandle,
     state: State<AppState>,
-    request: AgentStartRequest,
-) -> Result<AgentState, String> {
-    state.agent.start(
-        app,
-        state.terminal.clone(),
-        state.workspace.clone(),
-        state.audit.clone(),
-        request,
-    )
+    request: KernelUserInputRequest,
+) -> Result<RunState, String> {
+    state.kernel.user_input(&app, request)
 }
 
+/// Approves or rejects the `fs.write` shown in a `FileWritePreview`.
 #[tauri::command]
-fn agent_pause(app: AppHandle, state: State<AppState>) -> Result<AgentState, String> {
-    state.agent.pause(&app)
+fn kernel_approve_action(
+    app: AppHandle,
+    state: State<AppState>,
+    request: KernelApproveActionRequest,
+) -> Result<RunState, String> {
+    state.kernel.approve_action(&app, request)
 }
 
 #[tauri::command]
-fn agent_resume(app: AppHandle, state: State<AppState>) -> Result<AgentState, String> {
-    state.agent.resume(&app)
+fn kernel_get_write_preview_policy(state: State<AppState>) -> Result<WritePreviewPolicy, String> {
+    Ok(load_write_preview_policy(&state.workspace.root()))
 }
 
 #[tauri::command]
-fn agent_reset(app: AppHandle, state: State<AppState>) -> Result<AgentState, String> {
-    state.agent.reset(&app)
+fn kernel_set_write_preview_policy(
+    state: State<AppState>,
+    policy: WritePreviewPolicy,
+) -> Result<WritePreviewPolicy, String> {
+    ensure_writable(&state)?;
+    save_write_preview_policy(&state.workspace.root(), &policy)?;
+    Ok(policy)
 }
 
 #[tauri::command]
-fn agent_set_auto_run(
+async fn kernel_plan_update(
     app: AppHandle,
-    state: State<AppState>,
-    request: AgentAutoRunRequest,
-) -> Result<AgentState, String> {
-    state.agent.set_auto_run(&app, request.auto_run)
+    state: State<'_, AppState>,
+    request: KernelPlanUpdateRequest,
+) -> Result<RunState, String> {
+    state.kernel.update_plan(&app, request).await
 }
 
 #[tauri::command]
-fn agent_set_verify_preset(
-    app: AppHandle,
-    state: State<AppState>,
-    request: AgentVerifyRequest,
-) -> Result<AgentState, String> {
-    state.agent.set_verify_preset(&app, request.preset)
+fn kernel_cancel_generation(state: State<AppState>, generation_id: String) -> Result<(), String> {
+    state.kernel.cancel_generation(&generation_id)
 }
 
 #[tauri::command]
-fn agent_add_plan_items(
+fn kernel_plan_status(
     app: AppHandle,
     state: State<AppState>,
-    request: AgentPlanItemsRequest,
-) -> Result<AgentState, String> {
-    state.agent.add_plan_items(&app, request.items)
+    request: KernelPlanStatusRequest,
+) -> Result<RunState, String> {
+    state.kernel.update_plan_status(&app, request)
 }
 
 #[tauri::command]
-fn agent_remove_plan_item(
-    app: AppHandle,
-    state: State<AppState>,
-    request: AgentRemovePlanItemRequest,
-) -> Result<AgentState, String> {
-    state.agent.remove_plan_item(&app, request.id)
+fn llm_get_profile(state: State<AppState>) -> Result<Option<LlmProfile>, String> {
+    Ok(state.kernel.get_llm_profile())
 }
 
 #[tauri::command]
-fn agent_clear_plan_items(app: AppHandle, state: State<AppState>) -> Result<AgentState, String> {
-    state.agent.clear_plan_items(&app)
+fn llm_save_profile(state: State<AppState>, profile: LlmProfile) -> Result<LlmProfile, String> {
+    state.kernel.save_llm_profile(profile)
 }
 
+/// Token and cost totals of one run, or of the whole workspace by run and
+/// profile when no run is given.
 #[tauri::command]
-fn agent_generate_plan(
-    app: AppHandle,
+fn llm_get_usage(
     state: State<AppState>,
-    request: AgentGeneratePlanRequest,
-) -> Result<AgentState, String> {
-    state.agent.generate_plan(&app, request)
+    run_id: Option<String>,
+) -> Result<services::llm_usage::UsageReport, String> {
+    state.kernel.get_usage(run_id)
+}
+
+#[derive(Deserialize)]
+struct LlmReplayRequest {
+    mode: LlmReplayMode,
+    recording: Option<String>,
 }
 
 #[tauri::command]
-fn agent_skip_plan_item(
-    app: AppHandle,
-    state: State<AppState>,
-    request: AgentPlanItemStatusRequest,
-) -> Result<AgentState, String> {
-    state.agent.skip_plan_item(&app, request)
+fn llm_get_replay() -> Result<LlmReplayStatus, String> {
+    Ok(replay_status())
 }
 
 #[tauri::command]
-fn agent_retry_plan_item(
-    app: AppHandle,
+fn llm_set_replay(
     state: State<AppState>,
-    request: AgentPlanItemStatusRequest,
-) -> Result<AgentState, String> {
-    state.agent.retry_plan_item(&app, request)
+    request: LlmReplayRequest,
+) -> Result<LlmReplayStatus, String> {
+    let name = request
+        .recording
+        .map(|name| name.trim().to_string())
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| format!("run-{}", now_ms()));
+    if name.contains(['/', '\\']) || name.contains("..") {
+        return Err("Recording name must not contain path separators".to_string());
+    }
+    let path = state
+        .workspace
+        .root()
+        .join(".taurihands")
+        .join("llm-replay")
+        .join(format!("{}.jsonl", name));
+    configure_replay(request.mode, Some(path))
 }
 
 #[tauri::command]
-fn kernel_get_state(state: State<AppState>) -> Result<RunState, String> {
-    Ok(state.kernel.snapshot())
+fn logs_get_level() -> Result<String, String> {
+    Ok(log_level())
 }
 
 #[tauri::command]
-fn kernel_start(
+fn logs_set_level(level: String) -> Result<String, String> {
+    set_log_level(&level)
+}
+
+#[tauri::command]
+fn runs_set_tags(
     app: AppHandle,
     state: State<AppState>,
-    request: KernelStartRequest,
+    request: RunTagsRequest,
 ) -> Result<RunState, String> {
-    state.kernel.start(app, request)
+    ensure_writable(&state)?;
+    state
+        .kernel
+        .set_run_tags(&app, request.run_id.as_deref(), request.tags)
 }
 
 #[tauri::command]
-fn kernel_pause(app: AppHandle, state: State<AppState>) -> Result<RunState, String> {
-    state.kernel.pause(&app)
+fn runs_search(state: State<AppState>, query: RunSearchQuery) -> Result<Vec<RunSummary>, String> {
+    search_run_history(&state, &query)
 }
 
 #[tauri::command]
-fn kernel_resume(app: AppHandle, state: State<AppState>) -> Result<RunState, String> {
-    state.kernel.resume(&app)
+fn kernel_list_runs(state: State<AppState>) -> Result<Vec<RunSummary>, String> {
+    let query = RunSearchQuery {
+        limit: Some(usize::MAX),
+        ..Default::default()
+    };
+    search_run_history(&state, &query)
 }
 
 #[tauri::command]
-fn kernel_stop(app: AppHandle, state: State<AppState>) -> Result<RunState, String> {
-    state.kernel.stop(&app)
+fn kernel_fork_run(
+    app: AppHandle,
+    state: State<AppState>,
+    request: KernelForkRunRequest,
+) -> Result<RunState, String> {
+    ensure_writable(&state)?;
+    state.kernel.fork_run(&app, request)
 }
 
 #[tauri::command]
-fn kernel_continue(app: AppHandle, state: State<AppState>) -> Result<RunState, String> {
-    state.kernel.continue_run(&app)
+fn kernel_load_run(
+    app: AppHandle,
+    state: State<AppState>,
+    run_id: String,
+) -> Result<RunState, String> {
+    state.kernel.load_run(&app, &run_id)
 }
 
 #[tauri::command]
-fn kernel_user_input(
+fn kernel_resume_run(
     app: AppHandle,
     state: State<AppState>,
-    request: KernelUserInputRequest,
+    run_id: String,
 ) -> Result<RunState, String> {
-    state.kernel.user_input(&app, request)
+    ensure_writable(&state)?;
+    state.kernel.resume_run(&app, &run_id)
 }
 
 #[tauri::command]
-async fn kernel_plan_update(
-    app: AppHandle,
-    state: State<'_, AppState>,
-    request: KernelPlanUpdateRequest,
-) -> Result<RunState, String> {
-    state.kernel.update_plan(&app, request).await
+fn kernel_list_events(
+    state: State<AppState>,
+    run_id: Option<String>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Result<EventPage, String> {
+    let (run_id, events) = state.kernel.run_events(run_id.as_deref())?;
+    Ok(page_events(run_id, events, offset, limit))
 }
 
 #[tauri::command]
-fn kernel_plan_status(
+fn kernel_search_events(
+    state: State<AppState>,
+    query: EventSearchQuery,
+) -> Result<EventSearchResult, String> {
+    let run_ids = match query.run_id.as_deref().map(str::trim) {
+        Some(run_id) if !run_id.is_empty() => vec![run_id.to_string()],
+        _ => state.kernel.logged_run_ids(),
+    };
+    let logs = run_ids.into_iter().filter_map(|run_id| {
+        state
+            .kernel
+            .run_events(Some(&run_id))
+            .ok()
+            .map(|(_, events)| events)
+    });
+    Ok(search_events(logs, &query))
+}
+
+fn search_run_history(state: &AppState, query: &RunSearchQuery) -> Result<Vec<RunSummary>, String> {
+    let runs = state.kernel.list_runs()?;
+    let mut tasks = std::collections::HashMap::new();
+    if let Ok(entries) = fs::read_dir(task_base_dir(&state.workspace.root())) {
+        for entry in entries.flatten() {
+            if let Ok(config) = read_json::<TaskConfig>(&entry.path().join("task.json")) {
+                tasks.insert(
+                    config.task_id,
+                    TaskLabels {
+                        goal: config.goal,
+                        tags: config.tags,
+                    },
+                );
+            }
+        }
+    }
+    Ok(search_runs(runs, &tasks, query))
+}
+
+#[tauri::command]
+fn run_export_report(
+    state: State<AppState>,
+    request: RunExportRequest,
+) -> Result<RunExportResponse, String> {
+    ensure_writable(&state)?;
+    let (run, events) = state.kernel.run_record(request.run_id.as_deref())?;
+    let report = render_report(&run, &events, request.format);
+    let reports_dir = state.workspace.root().join(".taurihands").join("reports");
+    // Reports only ever land in the reports dir; the caller picks the file name.
+    let file_name = match request.path.map(|path| path.trim().to_string()) {
+        Some(name) if !name.is_empty() => {
+            let plain = matches!(
+                Path::new(&name).components().collect::<Vec<_>>().as_slice(),
+                [std::path::Component::Normal(_)]
+            );
+            if !plain {
+                return Err("Report path must be a file name".to_string());
+            }
+            name
+        }
+        _ => format!("run-{}.{}", run.run_id, request.format.extension()),
+    };
+    let path = reports_dir.join(file_name);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    fs::write(&path, report.as_bytes()).map_err(|e| e.to_string())?;
+    Ok(RunExportResponse {
+        path: display_path(&path),
+        format: request.format,
+        bytes: report.len(),
+    })
+}
+
+#[tauri::command]
+fn run_import_transcript(
     app: AppHandle,
     state: State<AppState>,
-    request: KernelPlanStatusRequest,
+    request: TranscriptImportRequest,
 ) -> Result<RunState, String> {
-    state.kernel.update_plan_status(&app, request)
+    ensure_writable(&state)?;
+    let raw = match (request.path, request.content) {
+        (Some(path), _) if !path.trim().is_empty() => {
+            let path = PathBuf::from(path.trim());
+            let path = if path.is_absolute() {
+                path
+            } else {
+                state.workspace.root().join(path)
+            };
+            fs::read_to_string(path).map_err(|e| e.to_string())?
+        }
+        (_, Some(content)) => content,
+        _ => return Err("Provide a transcript path or content".to_string()),
+    };
+    let transcript = parse_transcript(&raw)?;
+    state.kernel.import_run(&app, transcript)
 }
 
 #[tauri::command]
-fn llm_get_profile(state: State<AppState>) -> Result<Option<LlmProfile>, String> {
-    Ok(state.kernel.get_llm_profile())
+fn drafts_get(state: State<AppState>, run_id: String) -> Result<Option<Draft>, String> {
+    Ok(load_draft(&state.workspace.root(), &run_id))
+}
+
+#[tauri::command]
+fn drafts_save(state: State<AppState>, request: DraftSaveRequest) -> Result<Option<Draft>, String> {
+    ensure_writable(&state)?;
+    save_draft(&state.workspace.root(), &request.run_id, &request.content)
 }
 
 #[tauri::command]
-fn llm_save_profile(
+fn diagnostics_collect(
     state: State<AppState>,
-    profile: LlmProfile,
-) -> Result<LlmProfile, String> {
-    state.kernel.save_llm_profile(profile)
+    request: Option<DiagnosticsCollectRequest>,
+) -> Result<DiagnosticsBundle, String> {
+    let request = request.unwrap_or_default();
+    let mut bundle = DiagnosticsBuilder::default();
+    bundle.add_json(
+        "system.json",
+        "App version, OS and architecture",
+        &system_info(env!("CARGO_PKG_VERSION")),
+    );
+    bundle.add_json(
+        "settings.json",
+        "Workspace settings and active LLM profile, credentials redacted",
+        &serde_json::json!({
+            "workspace": load_workspace_settings(&state.settings_path()),
+            "llmProfile": state.kernel.get_llm_profile(),
+        }),
+    );
+    bundle.add_json(
+        "run/state.json",
+        "Current run state",
+        &state.kernel.snapshot(),
+    );
+    if let Ok(events) = state.kernel.current_run_events() {
+        bundle.add_json("run/events.json", "Events of the current run", &events);
+    }
+    bundle.add_file_tail("audit.log", "Recent audit entries", &state.audit.path());
+    if let Some(dir) = log_dir() {
+        bundle.add_recent_logs(&dir);
+    }
+    if request.preview {
+        return Ok(DiagnosticsBundle {
+            path: None,
+            items: bundle.items(),
+        });
+    }
+    // Kept out of the workspace, where the bundle could be committed or shared.
+    let path = app_data_dir()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(std::env::temp_dir)
+        .join("diagnostics")
+        .join(format!("taurihands-diagnostics-{}.zip", now_ms()));
+    bundle.write_zip(&path)?;
+    Ok(DiagnosticsBundle {
+        path: Some(display_path(&path)),
+        items: bundle.items(),
+    })
 }
 
 #[tauri::command]
-async fn llm_fetch_models(
-    request: LlmModelFetchRequest,
-) -> Result<LlmModelFetchResponse, String> {
+async fn llm_fetch_models(request: LlmModelFetchRequest) -> Result<LlmModelFetchResponse, String> {
     fetch_models(request).await
 }
 
@@ -509,7 +2065,15 @@ fn task_get_active(state: State<AppState>) -> Result<Option<TaskConfig>, String>
         return Ok(None);
     }
     let config: TaskConfig = read_json(&config_path)?;
-    let rules_path = task_dir(&root, &pointer.task_id).join("judge.json");
+    let _ = state.kernel.set_task_constraints(TaskConstraints {
+        constraints: config.constraints.clone(),
+        conventions_path: config.conventions_path.clone(),
+        allow_network: Some(config.risk_policy.allow_network),
+        autonomy: Some(config.autonomy.clone()),
+    });
+    set_task_command_mode(Some(config.risk_policy.command_policy.clone()));
+    let active = active_ruleset(&root, &pointer.task_id);
+    let rules_path = ruleset_path(&root, &pointer.task_id, &active);
     if rules_path.exists() {
         if let Ok(rules) = read_json(&rules_path) {
             let _ = state.kernel.set_judge_rules(rules);
@@ -520,6 +2084,7 @@ fn task_get_active(state: State<AppState>) -> Result<Option<TaskConfig>, String>
 
 #[tauri::command]
 fn task_save_config(state: State<AppState>, request: TaskConfig) -> Result<TaskConfig, String> {
+    ensure_writable(&state)?;
     let root = state.workspace.root();
     let task_id = if request.task_id.trim().is_empty() {
         Uuid::new_v4().to_string()
@@ -539,6 +2104,17 @@ fn task_save_config(state: State<AppState>, request: TaskConfig) -> Result<TaskC
         budget: request.budget,
         risk_policy: request.risk_policy,
         autonomy: request.autonomy,
+        constraints: request
+            .constraints
+            .into_iter()
+            .map(|rule| rule.trim().to_string())
+            .filter(|rule| !rule.is_empty())
+            .collect(),
+        conventions_path: request
+            .conventions_path
+            .map(|path| path.trim().to_string())
+            .filter(|path| !path.is_empty()),
+        tags: normalize_tags(request.tags),
     };
     let config_path = task_dir(&root, &task_id).join("task.json");
     write_json(&config_path, &config)?;
@@ -548,16 +2124,31 @@ fn task_save_config(state: State<AppState>, request: TaskConfig) -> Result<TaskC
     let pointer_path = task_base_dir(&root).join("active.json");
     write_json(&pointer_path, &pointer)?;
     let _ = state.kernel.set_task_id(Some(task_id));
+    let _ = state.kernel.set_task_constraints(TaskConstraints {
+        constraints: config.constraints.clone(),
+        conventions_path: config.conventions_path.clone(),
+        allow_network: Some(config.risk_policy.allow_network),
+        autonomy: Some(config.autonomy.clone()),
+    });
+    set_task_command_mode(Some(config.risk_policy.command_policy.clone()));
     Ok(config)
 }
 
 #[tauri::command]
-fn judge_get_rules(state: State<AppState>, task_id: String) -> Result<Vec<JudgeRule>, String> {
+fn judge_get_rules(
+    state: State<AppState>,
+    task_id: String,
+    ruleset: Option<String>,
+) -> Result<Vec<JudgeRule>, String> {
     if task_id.trim().is_empty() {
         return Ok(Vec::new());
     }
     let root = state.workspace.root();
-    let rules_path = task_dir(&root, &task_id).join("judge.json");
+    let name = match ruleset {
+        Some(name) => normalize_ruleset_name(&name)?,
+        None => active_ruleset(&root, &task_id),
+    };
+    let rules_path = ruleset_path(&root, &task_id, &name);
     if !rules_path.exists() {
         return Ok(Vec::new());
     }
@@ -569,16 +2160,90 @@ fn judge_set_rules(
     state: State<AppState>,
     request: JudgeRulesRequest,
 ) -> Result<Vec<JudgeRule>, String> {
+    ensure_writable(&state)?;
     if request.task_id.trim().is_empty() {
         return Err("task_id is required".to_string());
     }
+    for (index, rule) in request.rules.iter().enumerate() {
+        validate_judge_rule(rule).map_err(|err| format!("Rule {}: {}", index + 1, err))?;
+    }
     let root = state.workspace.root();
-    let rules_path = task_dir(&root, &request.task_id).join("judge.json");
+    let active = active_ruleset(&root, &request.task_id);
+    let name = match &request.ruleset {
+        Some(name) => normalize_ruleset_name(name)?,
+        None => active.clone(),
+    };
+    let rules_path = ruleset_path(&root, &request.task_id, &name);
     write_json(&rules_path, &request.rules)?;
-    let _ = state.kernel.set_judge_rules(request.rules.clone());
+    if name == active {
+        let _ = state.kernel.set_judge_rules(request.rules.clone());
+    }
     Ok(request.rules)
 }
 
+#[tauri::command]
+fn judge_list_rulesets(state: State<AppState>, task_id: String) -> Result<JudgeRulesets, String> {
+    let root = state.workspace.root();
+    let mut names = vec![DEFAULT_RULESET.to_string()];
+    if let Ok(entries) = fs::read_dir(task_dir(&root, &task_id).join("judge")) {
+        let mut extra: Vec<String> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                    return None;
+                }
+                path.file_stem()
+                    .map(|stem| stem.to_string_lossy().to_string())
+            })
+            .filter(|name| name != DEFAULT_RULESET)
+            .collect();
+        extra.sort();
+        names.extend(extra);
+    }
+    Ok(JudgeRulesets {
+        active: active_ruleset(&root, &task_id),
+        names,
+    })
+}
+
+/// Makes `name` the task's active rule set and loads its rules into the kernel.
+#[tauri::command]
+fn judge_select_ruleset(
+    state: State<AppState>,
+    task_id: String,
+    name: String,
+) -> Result<Vec<JudgeRule>, String> {
+    ensure_writable(&state)?;
+    if task_id.trim().is_empty() {
+        return Err("task_id is required".to_string());
+    }
+    let root = state.workspace.root();
+    let name = normalize_ruleset_name(&name)?;
+    let rules_path = ruleset_path(&root, &task_id, &name);
+    let rules: Vec<JudgeRule> = if rules_path.exists() {
+        read_json(&rules_path)?
+    } else if name == DEFAULT_RULESET {
+        Vec::new()
+    } else {
+        return Err(format!("Rule set {} does not exist", name));
+    };
+    let pointer_path = task_dir(&root, &task_id).join("judge-ruleset.json");
+    write_json(&pointer_path, &RulesetPointer { name })?;
+    let _ = state.kernel.set_judge_rules(rules.clone());
+    Ok(rules)
+}
+
+#[tauri::command]
+fn judge_evaluate_now(
+    app: AppHandle,
+    state: State<AppState>,
+    request: Option<JudgeEvaluateRequest>,
+) -> Result<JudgeResult, String> {
+    let rules = request.and_then(|request| request.rules);
+    state.kernel.evaluate_judge_now(&app, rules)
+}
+
 fn parse_rg_json(output: &[u8], max_results: usize) -> Vec<SearchMatch> {
     let mut matches = Vec::new();
     let stdout = String::from_utf8_lossy(output);
@@ -669,6 +2334,7 @@ fn resolve_search_targets(
     if resolved.is_empty() {
         resolved.push(workspace.root());
     }
+    globs.extend(IgnoreRules::for_workspace(&workspace.root()).rg_globs());
     (resolved, globs)
 }
 
@@ -680,15 +2346,16 @@ fn run_rg_search(
     pattern: &str,
     paths: &[PathBuf],
     globs: &[String],
+    hidden: bool,
     force_fixed: bool,
 ) -> Result<Vec<u8>, String> {
-    let output = run_rg_search_inner(pattern, paths, globs, force_fixed)?;
+    let output = run_rg_search_inner(pattern, paths, globs, hidden, force_fixed)?;
     if is_rg_ok(&output) {
         return Ok(output.stdout);
     }
     let stderr = String::from_utf8_lossy(&output.stderr);
     if !force_fixed && is_rg_regex_error(&stderr) {
-        let retry = run_rg_search_inner(pattern, paths, globs, true)?;
+        let retry = run_rg_search_inner(pattern, paths, globs, hidden, true)?;
         if is_rg_ok(&retry) {
             return Ok(retry.stdout);
         }
@@ -698,9 +2365,12 @@ fn run_rg_search(
     Err(stderr.trim().to_string())
 }
 
-fn run_rg_files(paths: &[PathBuf], globs: &[String]) -> Result<Vec<u8>, String> {
+fn run_rg_files(paths: &[PathBuf], globs: &[String], hidden: bool) -> Result<Vec<u8>, String> {
     let mut cmd = Command::new("rg");
     cmd.arg("--files");
+    if hidden {
+        cmd.arg("--hidden");
+    }
     for glob in globs {
         cmd.arg("--glob").arg(glob);
     }
@@ -719,10 +2389,14 @@ fn run_rg_search_inner(
     pattern: &str,
     paths: &[PathBuf],
     globs: &[String],
+    hidden: bool,
     force_fixed: bool,
 ) -> Result<std::process::Output, String> {
     let mut cmd = Command::new("rg");
     cmd.arg("--json");
+    if hidden {
+        cmd.arg("--hidden");
+    }
     for glob in globs {
         cmd.arg("--glob").arg(glob);
     }
@@ -753,6 +2427,41 @@ fn task_dir(root: &Path, task_id: &str) -> std::path::PathBuf {
     task_base_dir(root).join(task_id)
 }
 
+const DEFAULT_RULESET: &str = "default";
+
+/// The default rule set keeps living in `judge.json`; named ones go under `judge/`.
+fn ruleset_path(root: &Path, task_id: &str, name: &str) -> std::path::PathBuf {
+    if name == DEFAULT_RULESET {
+        task_dir(root, task_id).join("judge.json")
+    } else {
+        task_dir(root, task_id)
+            .join("judge")
+            .join(format!("{}.json", name))
+    }
+}
+
+fn active_ruleset(root: &Path, task_id: &str) -> String {
+    read_json::<RulesetPointer>(&task_dir(root, task_id).join("judge-ruleset.json"))
+        .ok()
+        .and_then(|pointer| normalize_ruleset_name(&pointer.name).ok())
+        .unwrap_or_else(|| DEFAULT_RULESET.to_string())
+}
+
+/// Rule set names become file names, so only plain names are accepted.
+fn normalize_ruleset_name(name: &str) -> Result<String, String> {
+    let name = name.trim();
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|ch| ch.is_alphanumeric() || matches!(ch, ' ' | '-' | '_' | '.'));
+    if valid {
+        Ok(name.to_string())
+    } else {
+        Err(format!("Invalid rule set name: {}", name))
+    }
+}
+
 fn write_json<T: Serialize>(path: &Path, value: &T) -> Result<(), String> {
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent).map_err(|e| e.to_string())?;
@@ -774,7 +2483,7 @@ fn list_tree(
     depth: usize,
     max_depth: usize,
     max_entries: usize,
-    show_hidden: bool,
+    rules: &IgnoreRules,
     count: &mut usize,
 ) -> Result<Vec<TreeNode>, String> {
     if depth > max_depth {
@@ -785,20 +2494,10 @@ fn list_tree(
     for entry in entries {
         let entry = entry.map_err(|e| e.to_string())?;
         let name = entry.file_name().to_string_lossy().to_string();
-        if !show_hidden && name.starts_with('.') {
-            continue;
-        }
         let file_type = entry.file_type().map_err(|e| e.to_string())?;
         if file_type.is_symlink() {
             continue;
         }
-        if file_type.is_dir() && is_ignored_dir(&name) {
-            continue;
-        }
-        *count += 1;
-        if *count > max_entries {
-            break;
-        }
         let path = entry.path();
         let rel = path
             .strip_prefix(root)
@@ -806,6 +2505,13 @@ fn list_tree(
             .to_string_lossy()
             .to_string()
             .replace('\\', "/");
+        if rules.is_ignored(&rel, file_type.is_dir()) {
+            continue;
+        }
+        *count += 1;
+        if *count > max_entries {
+            break;
+        }
         let children = if file_type.is_dir() && depth < max_depth {
             Some(list_tree(
                 root,
@@ -813,7 +2519,7 @@ fn list_tree(
                 depth + 1,
                 max_depth,
                 max_entries,
-                show_hidden,
+                rules,
                 count,
             )?)
         } else {
@@ -838,29 +2544,6 @@ fn list_tree(
     Ok(items)
 }
 
-fn is_ignored_dir(name: &str) -> bool {
-    matches!(
-        name,
-        ".git"
-            | ".idea"
-            | ".vscode"
-            | ".taurihands"
-            | "node_modules"
-            | "dist"
-            | "target"
-              | "out"
-      )
-  }
-
-fn workspace_settings_path(identifier: &str, fallback_root: &Path) -> PathBuf {
-    if let Some(base) = app_data_root(identifier) {
-        return base.join("settings.json");
-    }
-    fallback_root
-        .join(".taurihands")
-        .join("app-settings.json")
-}
-
 fn app_data_root(identifier: &str) -> Option<PathBuf> {
     #[cfg(windows)]
     {
@@ -871,10 +2554,11 @@ fn app_data_root(identifier: &str) -> Option<PathBuf> {
     }
     #[cfg(target_os = "macos")]
     {
-        return env::var("HOME")
-            .ok()
-            .map(PathBuf::from)
-            .map(|home| home.join("Library").join("Application Support").join(identifier));
+        return env::var("HOME").ok().map(PathBuf::from).map(|home| {
+            home.join("Library")
+                .join("Application Support")
+                .join(identifier)
+        });
     }
     #[cfg(all(not(windows), not(target_os = "macos")))]
     {
@@ -894,23 +2578,118 @@ fn load_workspace_settings(path: &Path) -> Option<WorkspaceSettings> {
 }
 
 fn save_workspace_settings(path: &Path, workspace: &str) -> Result<(), String> {
-    if let Some(parent) = path.parent() {
-        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    let previous = load_workspace_settings(path);
+    let auto_ignore_target = previous
+        .as_ref()
+        .and_then(|settings| settings.auto_ignore_target.clone());
+    let ignore_patterns = previous
+        .as_ref()
+        .map(|settings| settings.ignore_patterns.clone())
+        .unwrap_or_default();
+    let mut recent_workspaces = vec![workspace.to_string()];
+    if let Some(previous) = previous {
+        recent_workspaces.extend(
+            previous
+                .recent_workspaces
+                .into_iter()
+                .filter(|recent| recent != workspace),
+        );
     }
+    recent_workspaces.truncate(MAX_RECENT_WORKSPACES);
     let settings = WorkspaceSettings {
         last_workspace: workspace.to_string(),
+        auto_ignore_target,
+        recent_workspaces,
+        ignore_patterns,
     };
-    let data = serde_json::to_vec_pretty(&settings).map_err(|e| e.to_string())?;
+    write_workspace_settings(path, &settings)
+}
+
+fn write_workspace_settings(path: &Path, settings: &WorkspaceSettings) -> Result<(), String> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_vec_pretty(settings).map_err(|e| e.to_string())?;
     fs::write(path, data).map_err(|e| e.to_string())
 }
 
+fn apply_auto_ignore(settings_path: &Path, root: &Path) {
+    let Some(target) =
+        load_workspace_settings(settings_path).and_then(|settings| settings.auto_ignore_target)
+    else {
+        return;
+    };
+    let status = taurihands_ignore_status(root);
+    if status.is_git_repo && !status.ignored {
+        let _ = ignore_taurihands(root, &target);
+    }
+}
+
+pub fn run_exec_marker(args: &[String]) -> i32 {
+    services::pty::run_marker_helper(args)
+}
+
+/// Reads `--allow-actions <list>` / `--allow-actions=<list>`, a comma
+/// separated list of action types such as `fs.read,fs.search`.
+fn allowed_actions_from_args(args: &[String]) -> Option<Vec<String>> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let list = if arg == "--allow-actions" {
+            iter.next().map(String::as_str)
+        } else {
+            arg.strip_prefix("--allow-actions=")
+        };
+        if let Some(list) = list {
+            return Some(
+                list.split(',')
+                    .map(|action| action.trim().to_string())
+                    .filter(|action| !action.is_empty())
+                    .collect(),
+            );
+        }
+    }
+    None
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     let context = tauri::generate_context!();
     let fallback_root = default_workspace_root();
-    let identifier = context.config().identifier.clone();
-    let settings_path = workspace_settings_path(&identifier, &fallback_root);
-    let workspace_root = load_workspace_settings(&settings_path)
+    let profiles = ProfileLayout {
+        identifier: context.config().identifier.clone(),
+        fallback_root: fallback_root.clone(),
+    };
+    // Without an app data directory nothing can be trusted, so repo hooks
+    // never run and uploads stay off.
+    if let Some(base) = app_data_root(&profiles.identifier) {
+        services::app_data::configure_app_data_dir(base);
+    }
+    let args: Vec<String> = env::args().collect();
+    let profile = match profile_from_args(&args) {
+        Some(name) => match validate_profile_name(&name) {
+            // A passphrase can only be entered in the app, through a switch.
+            Ok(()) if is_protected(&profiles.base(), &name) => {
+                eprintln!("Profile {} is protected; switch to it in the app", name);
+                DEFAULT_PROFILE.to_string()
+            }
+            Ok(()) => name,
+            Err(err) => {
+                eprintln!("Ignoring --user {}: {}", name, err);
+                DEFAULT_PROFILE.to_string()
+            }
+        },
+        None => DEFAULT_PROFILE.to_string(),
+    };
+    set_active_profile(&profile);
+    let settings_path = profiles.settings_path(&profile);
+    let settings = load_workspace_settings(&settings_path);
+    set_global_patterns(
+        settings
+            .as_ref()
+            .map(|settings| settings.ignore_patterns.clone())
+            .unwrap_or_default(),
+    );
+    let workspace_root = settings
         .and_then(|settings| {
             let candidate = PathBuf::from(settings.last_workspace);
             if candidate.is_dir() {
@@ -920,18 +2699,25 @@ pub fn run() {
             }
         })
         .unwrap_or(fallback_root);
-    let llm_root = app_data_root(&identifier).unwrap_or_else(|| workspace_root.clone());
+    apply_auto_ignore(&settings_path, &workspace_root);
+    let llm_root = profiles.llm_root(&profile, &workspace_root);
+    if let Err(err) = init_logging(&llm_root.join(".taurihands").join("logs")) {
+        eprintln!("Unable to initialize logging: {}", err);
+    }
     let llm_store_path = llm_root.join(".taurihands").join("llm.json");
     let legacy_llm_path = workspace_root.join(".taurihands").join("llm.json");
-    if !llm_store_path.exists() && legacy_llm_path.exists() {
+    // Only the default profile inherits keys from the legacy workspace store.
+    if profile == DEFAULT_PROFILE && !llm_store_path.exists() && legacy_llm_path.exists() {
         if let Some(parent) = llm_store_path.parent() {
             let _ = fs::create_dir_all(parent);
         }
         let _ = fs::copy(&legacy_llm_path, &llm_store_path);
     }
-    let audit = AuditLog::new(workspace_root.join(".taurihands").join("audit.log"));
+    let audit = AuditLog::new(profile_audit_log(&workspace_root, &profile));
     let terminal = TerminalManager::new(workspace_root.join(".taurihands").join("terminal"));
     let workspace = WorkspaceState::new(workspace_root);
+    let exit_workspace = workspace.clone();
+    let exit_audit = audit.clone();
     let agent = AgentManager::new();
     let kernel = KernelManager::new(
         workspace.root(),
@@ -940,6 +2726,20 @@ pub fn run() {
         audit.clone(),
         llm_root,
     );
+    let reviewer_locked = args.iter().any(|arg| arg == "--review");
+    kernel.set_reviewer_mode(reviewer_locked);
+    if !reviewer_locked {
+        spawn_workspace_hooks(None, audit.clone(), None, Some(workspace.root()));
+    }
+    let exit_kernel = kernel.clone();
+    kernel.set_cli_allowed_actions(allowed_actions_from_args(&args));
+
+    let shutdown = ShutdownCoordinator::new(
+        kernel.clone(),
+        agent.clone(),
+        terminal.clone(),
+        audit.clone(),
+    );
 
     tauri::Builder::default()
         .plugin(tauri_plugin_opener::init())
@@ -950,30 +2750,77 @@ pub fn run() {
             audit,
             agent,
             kernel,
-            settings_path,
+            watcher: WorkspaceWatcher::new(),
+            profiles,
+            profile: Arc::new(Mutex::new(profile)),
+            reviewer_locked,
         })
         .invoke_handler(tauri::generate_handler![
             get_workspace_root,
             set_workspace_root,
+            profile_list,
+            profile_switch,
+            profile_set_passphrase,
+            workspace_get_hooks,
+            workspace_save_hooks,
+            workspace_run_hooks,
+            workspace_get_trust,
+            workspace_set_trust,
+            workspace_ignore_status,
+            workspace_ignore_taurihands,
+            workspace_get_ignore_patterns,
+            workspace_set_ignore_patterns,
+            workspace_get_visibility,
+            workspace_set_visibility,
+            workspace_watch_start,
+            workspace_watch_stop,
+            workspace_changed_files,
+            workspace_watch_status,
             terminal_create_session,
             terminal_write,
             terminal_resize,
             terminal_kill,
             terminal_list_sessions,
             terminal_replay,
+            terminal_search_scrollback,
+            terminal_open_binary_output,
+            terminal_close_binary_output,
+            terminal_get_output_policy,
+            terminal_set_output_policy,
             terminal_exec_interactive,
             terminal_set_title,
             terminal_set_order,
+            terminal_update_session_meta,
+            terminal_get_history,
             tool_run_command,
             fs_read_file,
             fs_write_file,
+            fs_apply_patch,
             fs_search,
+            code_todos,
+            fs_history,
+            provenance_for_file,
+            fs_restore_version,
             fs_list_tree,
             git_status,
             git_diff,
+            git_commit,
+            git_branch_create,
+            git_stash,
+            git_checkout,
+            git_get_policy,
+            git_set_policy,
+            terminal_get_command_policy,
+            terminal_set_command_policy,
+            terminal_check_command,
+            mcp_get_config,
+            mcp_save_config,
+            mcp_status,
+            mcp_refresh,
             tests_run,
             agent_get_state,
             agent_start,
+            agent_run_plan_items,
             agent_pause,
             agent_resume,
             agent_reset,
@@ -986,22 +2833,100 @@ pub fn run() {
             agent_skip_plan_item,
             agent_retry_plan_item,
             kernel_get_state,
+            kernel_rebuild_state,
+            kernel_get_rate_limits,
+            kernel_set_rate_limits,
+            kernel_get_verify_policy,
+            kernel_set_verify_policy,
+            kernel_get_path_policy,
+            kernel_set_path_policy,
+            kernel_get_provenance_policy,
+            kernel_set_provenance_policy,
+            kernel_get_deps_policy,
+            kernel_set_deps_policy,
+            kernel_get_upload_policy,
+            kernel_set_upload_policy,
+            kernel_set_upload_secret,
+            kernel_get_resource_budget,
+            kernel_set_resource_budget,
+            storage_get_gc_policy,
+            storage_set_gc_policy,
+            storage_gc,
+            storage_get_encryption,
+            storage_set_encryption,
             kernel_start,
+            kernel_get_capabilities,
+            kernel_get_freshness_policy,
+            kernel_set_freshness_policy,
+            backup_get_policy,
+            backup_set_policy,
+            backup_create,
+            backup_list,
+            backup_restore,
+            kernel_set_readonly,
+            kernel_set_narration,
+            app_get_reviewer_mode,
+            app_set_reviewer_mode,
+            app_get_metrics,
+            llm_action_stats,
+            app_run_doctor,
+            context_pin_file,
+            context_unpin_file,
+            changes_list_pending,
+            changes_apply,
             kernel_pause,
             kernel_resume,
             kernel_stop,
             kernel_continue,
+            kernel_run_steps,
             kernel_user_input,
+            kernel_approve_action,
+            kernel_get_write_preview_policy,
+            kernel_set_write_preview_policy,
             kernel_plan_update,
+            kernel_cancel_generation,
             kernel_plan_status,
             llm_get_profile,
             llm_save_profile,
             llm_fetch_models,
+            llm_get_usage,
+            llm_get_replay,
+            llm_set_replay,
+            logs_get_level,
+            logs_set_level,
+            diagnostics_collect,
+            drafts_get,
+            drafts_save,
+            run_export_report,
+            runs_set_tags,
+            runs_search,
+            kernel_list_runs,
+            kernel_fork_run,
+            kernel_load_run,
+            kernel_list_events,
+            kernel_search_events,
+            kernel_resume_run,
+            run_import_transcript,
             task_get_active,
             task_save_config,
             judge_get_rules,
-            judge_set_rules
+            judge_set_rules,
+            judge_list_rulesets,
+            judge_select_ruleset,
+            judge_evaluate_now
         ])
-        .run(context)
-        .expect("error while running tauri application");
+        .setup(|app| {
+            tray::init(app.handle())?;
+            Ok(())
+        })
+        .build(context)
+        .expect("error while running tauri application")
+        .run(move |app, event| {
+            if let tauri::RunEvent::Exit = event {
+                shutdown.shutdown(app);
+                if !exit_kernel.is_reviewer_mode() {
+                    HookRunner::new(&exit_workspace.root(), exit_audit.clone()).run_close();
+                }
+            }
+        });
 }
@@ -17,13 +17,18 @@ use tauri_app_lib::cli::main::run_cli;
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    let args: Vec<String> = std::env::args().collect();
+    if args.get(1).map(|arg| arg == "--th-mark").unwrap_or(false) {
+        std::process::exit(tauri_app_lib::run_exec_marker(&args[2..]));
+    }
+
     // Check if we're running in CLI mode
     if std::env::args().any(|arg| arg == "--cli" || arg == "-c") {
         #[cfg(feature = "cli")]
         {
             return run_cli().await;
         }
-        
+
         #[cfg(not(feature = "cli"))]
         {
             eprintln!("CLI support is not enabled. Please compile with --features cli");
@@ -5,15 +5,21 @@ use std::path::PathBuf;
 use std::process::Command;
 use std::sync::{Arc, Mutex};
 use std::time::Duration;
-use tauri::{AppHandle, Emitter};
+use tracing::Instrument;
 use uuid::Uuid;
 
 use crate::services::audit::{now_ms, AuditLog};
+use crate::services::coverage::{
+    coverage_from_result, detect_coverage_command, COVERAGE_TIMEOUT_MS,
+};
+use crate::services::events::{EventSink, SharedEventSink};
+use crate::services::ignore::IgnoreRules;
 use crate::services::pty::{TerminalExecRequest, TerminalManager};
 use crate::services::tools::{
     max_read_bytes, read_file, run_command, search, CommandRequest, ReadFileRequest, SearchMatch,
     SearchRequest, ToolResult,
 };
+use crate::services::visibility::load_visibility_policy;
 use crate::services::workspace::{resolve_read_path_with_fallback, WorkspaceState};
 
 const AGENT_STATE_EVENT: &str = "agent-state";
@@ -37,6 +43,7 @@ pub struct AgentState {
     pub tool_calls: Vec<ToolCall>,
     pub logs: Vec<AgentLog>,
     pub verify_preset: String,
+    pub selected_item_ids: Option<Vec<String>>,
 }
 
 #[derive(Clone, Serialize)]
@@ -116,12 +123,31 @@ pub struct AgentPlanItemStatusRequest {
     pub id: String,
 }
 
+#[derive(Deserialize)]
+pub struct AgentRunPlanItemsRequest {
+    pub ids: Vec<String>,
+    pub session_id: Option<String>,
+}
+
 enum PlanAction {
-    Terminal { command: String },
-    Run { program: String, args: Vec<String> },
-    Read { path: String },
-    Search { pattern: String, paths: Option<Vec<String>> },
-    Test { program: String, args: Vec<String> },
+    Terminal {
+        command: String,
+    },
+    Run {
+        program: String,
+        args: Vec<String>,
+    },
+    Read {
+        path: String,
+    },
+    Search {
+        pattern: String,
+        paths: Option<Vec<String>>,
+    },
+    Test {
+        program: String,
+        args: Vec<String>,
+    },
 }
 
 impl AgentManager {
@@ -138,7 +164,7 @@ impl AgentManager {
             .unwrap_or_else(|_| AgentState::new())
     }
 
-    pub fn set_auto_run(&self, app: &AppHandle, enabled: bool) -> Result<AgentState, String> {
+    pub fn set_auto_run(&self, app: &dyn EventSink, enabled: bool) -> Result<AgentState, String> {
         let snapshot = self.with_state(|state| {
             state.auto_run = enabled;
         })?;
@@ -146,8 +172,12 @@ impl AgentManager {
         Ok(snapshot)
     }
 
-    pub fn set_verify_preset(&self, app: &AppHandle, preset: String) -> Result<AgentState, String> {
-        let allowed = ["skip", "npm_build", "npm_test", "cargo_test"];
+    pub fn set_verify_preset(
+        &self,
+        app: &dyn EventSink,
+        preset: String,
+    ) -> Result<AgentState, String> {
+        let allowed = ["skip", "npm_build", "npm_test", "cargo_test", "coverage"];
         if !allowed.contains(&preset.as_str()) {
             return Err("Unknown verify preset".to_string());
         }
@@ -160,7 +190,7 @@ impl AgentManager {
 
     pub fn add_plan_items(
         &self,
-        app: &AppHandle,
+        app: &dyn EventSink,
         items: Vec<String>,
     ) -> Result<AgentState, String> {
         let normalized: Vec<String> = items
@@ -181,11 +211,7 @@ impl AgentManager {
         Ok(snapshot)
     }
 
-    pub fn remove_plan_item(
-        &self,
-        app: &AppHandle,
-        id: String,
-    ) -> Result<AgentState, String> {
+    pub fn remove_plan_item(&self, app: &dyn EventSink, id: String) -> Result<AgentState, String> {
         let snapshot = self.with_state(|state| {
             state.plan_items.retain(|item| item.id != id);
         })?;
@@ -193,7 +219,7 @@ impl AgentManager {
         Ok(snapshot)
     }
 
-    pub fn clear_plan_items(&self, app: &AppHandle) -> Result<AgentState, String> {
+    pub fn clear_plan_items(&self, app: &dyn EventSink) -> Result<AgentState, String> {
         let snapshot = self.with_state(|state| {
             state.plan_items.clear();
             state.plan_goal = None;
@@ -204,7 +230,7 @@ impl AgentManager {
 
     pub fn generate_plan(
         &self,
-        app: &AppHandle,
+        app: &dyn EventSink,
         request: AgentGeneratePlanRequest,
     ) -> Result<AgentState, String> {
         let goal = request.goal.trim().to_string();
@@ -238,12 +264,16 @@ impl AgentManager {
 
     pub fn skip_plan_item(
         &self,
-        app: &AppHandle,
+        app: &dyn EventSink,
         request: AgentPlanItemStatusRequest,
     ) -> Result<AgentState, String> {
         let mut found = false;
         let snapshot = self.with_state(|state| {
-            if let Some(item) = state.plan_items.iter_mut().find(|item| item.id == request.id) {
+            if let Some(item) = state
+                .plan_items
+                .iter_mut()
+                .find(|item| item.id == request.id)
+            {
                 item.status = "skipped".to_string();
                 found = true;
             }
@@ -257,12 +287,16 @@ impl AgentManager {
 
     pub fn retry_plan_item(
         &self,
-        app: &AppHandle,
+        app: &dyn EventSink,
         request: AgentPlanItemStatusRequest,
     ) -> Result<AgentState, String> {
         let mut found = false;
         let snapshot = self.with_state(|state| {
-            if let Some(item) = state.plan_items.iter_mut().find(|item| item.id == request.id) {
+            if let Some(item) = state
+                .plan_items
+                .iter_mut()
+                .find(|item| item.id == request.id)
+            {
                 item.status = "pending".to_string();
                 found = true;
             }
@@ -274,7 +308,7 @@ impl AgentManager {
         Ok(snapshot)
     }
 
-    pub fn reset(&self, app: &AppHandle) -> Result<AgentState, String> {
+    pub fn reset(&self, app: &dyn EventSink) -> Result<AgentState, String> {
         let snapshot = self.with_state(|state| {
             state.reset_steps();
             state.tool_calls.clear();
@@ -283,12 +317,13 @@ impl AgentManager {
             state.running = false;
             state.paused = false;
             state.current_step_id = None;
+            state.selected_item_ids = None;
         })?;
         self.emit_state(app);
         Ok(snapshot)
     }
 
-    pub fn pause(&self, app: &AppHandle) -> Result<AgentState, String> {
+    pub fn pause(&self, app: &dyn EventSink) -> Result<AgentState, String> {
         let snapshot = self.with_state(|state| {
             if state.running {
                 state.paused = true;
@@ -308,11 +343,14 @@ impl AgentManager {
         Ok(snapshot)
     }
 
-    pub fn resume(&self, app: &AppHandle) -> Result<AgentState, String> {
+    pub fn resume(&self, app: &dyn EventSink) -> Result<AgentState, String> {
         let snapshot = self.with_state(|state| {
             if state.paused {
                 state.paused = false;
-                state.phase = state.current_step_id.clone().unwrap_or_else(|| "idle".to_string());
+                state.phase = state
+                    .current_step_id
+                    .clone()
+                    .unwrap_or_else(|| "idle".to_string());
                 state.logs.insert(
                     0,
                     AgentLog {
@@ -330,11 +368,65 @@ impl AgentManager {
 
     pub fn start(
         &self,
-        app: AppHandle,
+        app: &dyn EventSink,
         terminal: TerminalManager,
         workspace: WorkspaceState,
         audit: AuditLog,
         request: AgentStartRequest,
+    ) -> Result<AgentState, String> {
+        self.start_with_selection(app, terminal, workspace, audit, request.session_id, None)
+    }
+
+    pub fn run_plan_items(
+        &self,
+        app: &dyn EventSink,
+        terminal: TerminalManager,
+        workspace: WorkspaceState,
+        audit: AuditLog,
+        request: AgentRunPlanItemsRequest,
+    ) -> Result<AgentState, String> {
+        let ids: Vec<String> = request
+            .ids
+            .iter()
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty())
+            .collect();
+        if ids.is_empty() {
+            return Err("At least one plan item id is required".to_string());
+        }
+        {
+            let state = self
+                .state
+                .lock()
+                .map_err(|_| "Agent state lock poisoned".to_string())?;
+            if state.running {
+                return Err("Agent is already running".to_string());
+            }
+            if let Some(missing) = ids
+                .iter()
+                .find(|id| !state.plan_items.iter().any(|item| &item.id == *id))
+            {
+                return Err(format!("Plan item not found: {}", missing));
+            }
+        }
+        self.start_with_selection(
+            app,
+            terminal,
+            workspace,
+            audit,
+            request.session_id,
+            Some(ids),
+        )
+    }
+
+    fn start_with_selection(
+        &self,
+        app: &dyn EventSink,
+        terminal: TerminalManager,
+        workspace: WorkspaceState,
+        audit: AuditLog,
+        session_id: Option<String>,
+        selection: Option<Vec<String>>,
     ) -> Result<AgentState, String> {
         let snapshot = self.with_state(|state| {
             if state.running {
@@ -346,27 +438,34 @@ impl AgentManager {
                 state.logs.clear();
             }
             for item in state.plan_items.iter_mut() {
-                if item.status != "skipped" {
+                let selected = selection
+                    .as_ref()
+                    .map(|ids| ids.contains(&item.id))
+                    .unwrap_or(item.status != "skipped");
+                if selected {
                     item.status = "pending".to_string();
                 }
             }
+            state.selected_item_ids = selection.clone();
             state.running = true;
             state.paused = false;
             state.phase = "plan".to_string();
             state.current_step_id = None;
         })?;
-        self.emit_state(&app);
+        self.emit_state(app);
         let manager = self.clone();
+        let app = app.clone_sink();
         tauri::async_runtime::spawn(async move {
             manager
-                .run_pipeline(app, terminal, workspace, audit, request.session_id)
+                .run_pipeline(app, terminal, workspace, audit, session_id)
                 .await;
         });
         Ok(snapshot)
     }
-    fn emit_state(&self, app: &AppHandle) {
-        let snapshot = self.snapshot();
-        let _ = app.emit(AGENT_STATE_EVENT, snapshot);
+    fn emit_state(&self, app: &dyn EventSink) {
+        if let Ok(snapshot) = serde_json::to_value(self.snapshot()) {
+            app.emit_json(AGENT_STATE_EVENT, snapshot);
+        }
     }
 
     fn with_state<F>(&self, updater: F) -> Result<AgentState, String>
@@ -383,21 +482,25 @@ impl AgentManager {
 
     async fn run_pipeline(
         &self,
-        app: AppHandle,
+        app: SharedEventSink,
         terminal: TerminalManager,
         workspace: WorkspaceState,
         audit: AuditLog,
         session_id: Option<String>,
     ) {
+        let span = tracing::info_span!("agent_run", session_id = ?session_id);
         let run = self
             .run_steps(&app, terminal, workspace, audit, session_id)
+            .instrument(span)
             .await;
         if let Err(message) = run {
+            tracing::warn!(error = %message, "agent run failed");
             let _ = self.with_state(|state| {
                 state.phase = "error".to_string();
                 state.running = false;
                 state.paused = false;
                 state.current_step_id = None;
+                state.selected_item_ids = None;
                 state.logs.insert(
                     0,
                     AgentLog {
@@ -414,7 +517,7 @@ impl AgentManager {
 
     async fn run_steps(
         &self,
-        app: &AppHandle,
+        app: &dyn EventSink,
         terminal: TerminalManager,
         workspace: WorkspaceState,
         audit: AuditLog,
@@ -454,6 +557,7 @@ impl AgentManager {
             state.running = false;
             state.paused = false;
             state.current_step_id = None;
+            state.selected_item_ids = None;
             state.logs.insert(
                 0,
                 AgentLog {
@@ -470,7 +574,7 @@ impl AgentManager {
 
     async fn run_step<F, Fut>(
         &self,
-        app: &AppHandle,
+        app: &dyn EventSink,
         id: &str,
         _title: &str,
         action: F,
@@ -524,7 +628,7 @@ impl AgentManager {
         Ok(())
     }
 
-    fn note_plan(&self, app: &AppHandle) -> Result<(), String> {
+    fn note_plan(&self, app: &dyn EventSink) -> Result<(), String> {
         let _ = self.with_state(|state| {
             let detail = if state.plan_items.is_empty() {
                 "No plan items provided".to_string()
@@ -555,7 +659,7 @@ impl AgentManager {
     }
     async fn execute_plan(
         &self,
-        app: &AppHandle,
+        app: &dyn EventSink,
         terminal: TerminalManager,
         workspace: WorkspaceState,
         audit: AuditLog,
@@ -569,7 +673,12 @@ impl AgentManager {
             .is_empty();
         if is_empty {
             let _ = self.with_state(|state| {
-                set_step_status(state, "execute", "running", Some("No plan items".to_string()));
+                set_step_status(
+                    state,
+                    "execute",
+                    "running",
+                    Some("No plan items".to_string()),
+                );
             });
             self.emit_state(app);
             return Ok(());
@@ -586,7 +695,7 @@ impl AgentManager {
                 }
                 state.plan_items[index].clone()
             };
-            if item.status == "skipped" {
+            if item.status == "skipped" || !is_item_selected(&self.snapshot(), &item.id) {
                 index += 1;
                 continue;
             }
@@ -606,12 +715,9 @@ impl AgentManager {
             let result = match action {
                 Some(action) => {
                     let detail = describe_action(&action);
-                    self.run_tool(
-                        app,
-                        action.tool_name(),
-                        detail,
-                        || run_action(action, &terminal, &workspace, &audit, session_id.clone()),
-                    )
+                    self.run_tool(app, action.tool_name(), detail, || {
+                        run_action(action, &terminal, &workspace, &audit, session_id.clone())
+                    })
                     .await
                 }
                 None => {
@@ -655,7 +761,7 @@ impl AgentManager {
 
     async fn verify_step(
         &self,
-        app: &AppHandle,
+        app: &dyn EventSink,
         workspace: WorkspaceState,
         audit: AuditLog,
     ) -> Result<(), String> {
@@ -668,32 +774,67 @@ impl AgentManager {
 
         let command = match preset.as_str() {
             "skip" => None,
-            "npm_build" => Some(("npm".to_string(), vec!["run".to_string(), "build".to_string()])),
+            "npm_build" => Some((
+                "npm".to_string(),
+                vec!["run".to_string(), "build".to_string()],
+            )),
             "npm_test" => Some(("npm".to_string(), vec!["test".to_string()])),
             "cargo_test" => Some(("cargo".to_string(), vec!["test".to_string()])),
+            "coverage" => detect_coverage_command(&workspace.root())
+                .map(|command| (command.program, command.args)),
             _ => None,
         };
 
         if let Some((program, args)) = command {
             let detail = format!("{} {}", program, args.join(" "));
-            self.run_tool(app, "tests.run", detail, || {
-                run_command(
-                    CommandRequest {
-                        program,
-                        args: Some(args),
-                        cwd: Some(workspace.root().to_string_lossy().to_string()),
-                        env: None,
-                        timeout_ms: Some(120_000),
-                    },
-                    workspace.root().to_string_lossy().as_ref(),
-                    &audit,
-                )
-            })
-            .await?;
+            let timeout_ms = if preset == "coverage" {
+                COVERAGE_TIMEOUT_MS
+            } else {
+                120_000
+            };
+            let result = self
+                .run_tool(app, "tests.run", detail, || {
+                    run_command(
+                        CommandRequest {
+                            program,
+                            args: Some(args),
+                            cwd: Some(workspace.root().to_string_lossy().to_string()),
+                            env: None,
+                            timeout_ms: Some(timeout_ms),
+                        },
+                        workspace.root().to_string_lossy().as_ref(),
+                        &audit,
+                    )
+                })
+                .await?;
+            if preset == "coverage" {
+                let message = match coverage_from_result(&result) {
+                    Some(percent) => format!("Coverage: {:.1}%", percent),
+                    None => "Coverage percentage not found in output".to_string(),
+                };
+                let _ = self.with_state(|state| {
+                    set_step_status(state, "verify", "done", Some(message.clone()));
+                    state.logs.insert(
+                        0,
+                        AgentLog {
+                            id: make_id("log"),
+                            level: "info".to_string(),
+                            message,
+                            timestamp: now_ms(),
+                        },
+                    );
+                });
+                self.emit_state(app);
+            }
             Ok(())
         } else {
+            let reason = if preset == "coverage" {
+                "No coverage tooling detected"
+            } else {
+                "Skipped by config"
+            };
             let _ = self.with_state(|state| {
-                set_step_status(state, "verify", "skipped", Some("Skipped by config".to_string()));
+                set_step_status(state, "verify", "skipped", Some(reason.to_string()));
                 state.logs.insert(
                     0,
                     AgentLog {
@@ -709,9 +850,13 @@ impl AgentManager {
         }
     }
 
-    fn commit_step(&self, app: &AppHandle) -> Result<(), String> {
+    fn commit_step(&self, app: &dyn EventSink) -> Result<(), String> {
         let _ = self.with_state(|state| {
-            let ok_calls = state.tool_calls.iter().filter(|call| call.status == "ok").count();
+            let ok_calls = state
+                .tool_calls
+                .iter()
+                .filter(|call| call.status == "ok")
+                .count();
             set_step_status(
                 state,
                 "commit",
@@ -734,7 +879,7 @@ impl AgentManager {
 
     async fn run_tool<F>(
         &self,
-        app: &AppHandle,
+        app: &dyn EventSink,
         tool: &str,
         detail: String,
         action: F,
@@ -807,7 +952,13 @@ impl AgentManager {
                         .clone()
                         .unwrap_or_else(|| "Tool failed".to_string());
                     let _ = self.with_state(|state| {
-                        update(state, "error", summary, Some(message.clone()), result.exit_code);
+                        update(
+                            state,
+                            "error",
+                            summary,
+                            Some(message.clone()),
+                            result.exit_code,
+                        );
                         state.logs.insert(
                             0,
                             AgentLog {
@@ -843,11 +994,7 @@ impl AgentManager {
 
     async fn wait_if_paused(&self) {
         loop {
-            let paused = self
-                .state
-                .lock()
-                .map(|state| state.paused)
-                .unwrap_or(false);
+            let paused = self.state.lock().map(|state| state.paused).unwrap_or(false);
             if !paused {
                 break;
             }
@@ -869,6 +1016,7 @@ impl AgentState {
             tool_calls: Vec::new(),
             logs: Vec::new(),
             verify_preset: "skip".to_string(),
+            selected_item_ids: None,
         }
     }
 
@@ -931,6 +1079,14 @@ fn set_step_status(state: &mut AgentState, id: &str, status: &str, detail: Optio
     }
 }
 
+fn is_item_selected(state: &AgentState, id: &str) -> bool {
+    state
+        .selected_item_ids
+        .as_ref()
+        .map(|ids| ids.iter().any(|selected| selected == id))
+        .unwrap_or(true)
+}
+
 fn set_plan_status(state: &mut AgentState, id: &str, status: &str) {
     if let Some(item) = state.plan_items.iter_mut().find(|item| item.id == id) {
         item.status = status.to_string();
@@ -1055,7 +1211,10 @@ fn build_plan_from_goal(goal: &str, max_steps: usize) -> Vec<String> {
     let lower = trimmed.to_lowercase();
     let mut steps = Vec::new();
     push_unique(&mut steps, format!("Clarify objective: {}", trimmed));
-    push_unique(&mut steps, "Inspect workspace and relevant files".to_string());
+    push_unique(
+        &mut steps,
+        "Inspect workspace and relevant files".to_string(),
+    );
 
     if lower.contains("ui") || lower.contains("layout") || lower.contains("style") {
         push_unique(&mut steps, "Update UI structure and styling".to_string());
@@ -1173,9 +1332,10 @@ fn search_tool(
 ) -> Result<ToolResult, String> {
     let original_pattern = pattern.clone();
     let (resolved_paths, globs) = resolve_search_targets(workspace, &paths);
+    let hidden = load_visibility_policy(&workspace.root()).lists_hidden(None);
     let trimmed = pattern.trim();
     if trimmed == "*" {
-        let output = run_rg_files(&resolved_paths, &globs)?;
+        let output = run_rg_files(&resolved_paths, &globs, hidden)?;
         let matches = parse_rg_files(&output, 200);
         return Ok(search(
             SearchRequest {
@@ -1183,13 +1343,14 @@ fn search_tool(
                 paths,
                 glob: None,
                 max_results: Some(200),
+                include_hidden: None,
             },
             matches,
             audit,
         ));
     }
     let (normalized, force_fixed) = normalize_search_pattern(trimmed);
-    let output = run_rg_search(&normalized, &resolved_paths, &globs, force_fixed)?;
+    let output = run_rg_search(&normalized, &resolved_paths, &globs, hidden, force_fixed)?;
     let matches = parse_rg_json(&output, 200);
     Ok(search(
         SearchRequest {
@@ -1197,6 +1358,7 @@ fn search_tool(
             paths,
             glob: None,
             max_results: Some(200),
+            include_hidden: None,
         },
         matches,
         audit,
@@ -1293,6 +1455,7 @@ fn resolve_search_targets(
     if resolved.is_empty() {
         resolved.push(workspace.root());
     }
+    globs.extend(IgnoreRules::for_workspace(&workspace.root()).rg_globs());
     (resolved, globs)
 }
 
@@ -1304,15 +1467,16 @@ fn run_rg_search(
     pattern: &str,
     paths: &[PathBuf],
     globs: &[String],
+    hidden: bool,
     force_fixed: bool,
 ) -> Result<Vec<u8>, String> {
-    let output = run_rg_search_inner(pattern, paths, globs, force_fixed)?;
+    let output = run_rg_search_inner(pattern, paths, globs, hidden, force_fixed)?;
     if is_rg_ok(&output) {
         return Ok(output.stdout);
     }
     let stderr = String::from_utf8_lossy(&output.stderr);
     if !force_fixed && is_rg_regex_error(&stderr) {
-        let retry = run_rg_search_inner(pattern, paths, globs, true)?;
+        let retry = run_rg_search_inner(pattern, paths, globs, hidden, true)?;
         if is_rg_ok(&retry) {
             return Ok(retry.stdout);
         }
@@ -1322,9 +1486,12 @@ fn run_rg_search(
     Err(stderr.trim().to_string())
 }
 
-fn run_rg_files(paths: &[PathBuf], globs: &[String]) -> Result<Vec<u8>, String> {
+fn run_rg_files(paths: &[PathBuf], globs: &[String], hidden: bool) -> Result<Vec<u8>, String> {
     let mut cmd = Command::new("rg");
     cmd.arg("--files");
+    if hidden {
+        cmd.arg("--hidden");
+    }
     for glob in globs {
         cmd.arg("--glob").arg(glob);
     }
@@ -1343,10 +1510,14 @@ fn run_rg_search_inner(
     pattern: &str,
     paths: &[PathBuf],
     globs: &[String],
+    hidden: bool,
     force_fixed: bool,
 ) -> Result<std::process::Output, String> {
     let mut cmd = Command::new("rg");
     cmd.arg("--json");
+    if hidden {
+        cmd.arg("--hidden");
+    }
     for glob in globs {
         cmd.arg("--glob").arg(glob);
     }
@@ -0,0 +1,26 @@
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+use crate::services::workspace::display_path;
+
+/// App-level data directory, outside every workspace. Settings that decide
+/// what runs or where data and credentials go live here, where a cloned repo
+/// cannot ship its own.
+static APP_DATA_DIR: OnceLock<PathBuf> = OnceLock::new();
+
+/// Set once at startup; the first call wins.
+pub fn configure_app_data_dir(dir: PathBuf) {
+    let _ = APP_DATA_DIR.set(dir);
+}
+
+/// `None` when the platform has no app data directory; callers then treat
+/// app-level settings as unset.
+pub fn app_data_dir() -> Option<&'static Path> {
+    APP_DATA_DIR.get().map(PathBuf::as_path)
+}
+
+/// Key for per-workspace entries in app-level settings.
+pub fn workspace_key(root: &Path) -> String {
+    let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    display_path(&root)
+}
@@ -0,0 +1,334 @@
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::Duration;
+
+use crate::services::app_data::{app_data_dir, workspace_key};
+use crate::services::kernel::{KernelEvent, RunState};
+use crate::services::report::{render_report, ReportFormat};
+use crate::services::secret_store::SecretStore;
+
+const MAX_ARTIFACT_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Post-run upload of the run summary, the workspace diff and selected files,
+/// so headless runs leave reviewable traces off the machine. Policies are
+/// app-level settings keyed by workspace, so a repo cannot point uploads at
+/// its own target. Credentials are never stored here: targets name entries in
+/// the OS keychain.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadPolicy {
+    #[serde(default)]
+    pub enabled: bool,
+    pub target: Option<UploadTarget>,
+    #[serde(default = "default_true")]
+    pub include_diff: bool,
+    /// Workspace-relative files uploaded alongside the summary.
+    #[serde(default)]
+    pub artifacts: Vec<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum UploadTarget {
+    /// Each file is sent as `PUT {url}/{run_id}/{name}`.
+    #[serde(rename_all = "camelCase")]
+    Http {
+        url: String,
+        #[serde(default)]
+        token_secret: Option<String>,
+    },
+    /// Path-style S3 API, so MinIO and other compatible stores work too.
+    #[serde(rename_all = "camelCase")]
+    S3 {
+        endpoint: String,
+        bucket: String,
+        region: String,
+        #[serde(default)]
+        prefix: String,
+        access_key_secret: String,
+        secret_key_secret: String,
+    },
+}
+
+impl UploadTarget {
+    fn describe(&self) -> String {
+        match self {
+            UploadTarget::Http { url, .. } => url.clone(),
+            UploadTarget::S3 { bucket, prefix, .. } => {
+                format!("s3://{}/{}", bucket, prefix.trim_matches('/'))
+            }
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UploadReport {
+    pub target: String,
+    pub uploaded: Vec<String>,
+    pub skipped: Vec<String>,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// Upload policy per workspace root.
+#[derive(Default, Serialize, Deserialize)]
+struct UploadSettings {
+    workspaces: BTreeMap<String, UploadPolicy>,
+}
+
+fn upload_settings_path() -> Option<PathBuf> {
+    app_data_dir().map(|dir| dir.join("upload.json"))
+}
+
+fn load_upload_settings() -> UploadSettings {
+    upload_settings_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+/// Without an app data directory uploads stay disabled.
+pub fn load_upload_policy(root: &Path) -> UploadPolicy {
+    load_upload_settings()
+        .workspaces
+        .remove(&workspace_key(root))
+        .unwrap_or_default()
+}
+
+pub fn save_upload_policy(root: &Path, policy: &UploadPolicy) -> Result<(), String> {
+    let path =
+        upload_settings_path().ok_or_else(|| "No app data directory for uploads".to_string())?;
+    let mut settings = load_upload_settings();
+    settings
+        .workspaces
+        .insert(workspace_key(root), policy.clone());
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_vec_pretty(&settings).map_err(|e| e.to_string())?;
+    std::fs::write(path, data).map_err(|e| e.to_string())
+}
+
+/// Stores an upload credential under the name a target refers to.
+pub fn set_upload_secret(name: &str, value: &str) -> Result<(), String> {
+    if value.is_empty() {
+        SecretStore::delete(&upload_secret_name(name))
+    } else {
+        SecretStore::set(&upload_secret_name(name), value)
+    }
+}
+
+fn upload_secret_name(name: &str) -> String {
+    format!("upload-{}", name.trim())
+}
+
+fn upload_secret(name: &str) -> Result<String, String> {
+    SecretStore::get(&upload_secret_name(name))
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .ok_or_else(|| format!("Upload credential {} is not set", name))
+}
+
+pub async fn upload_run_artifacts(
+    root: &Path,
+    policy: &UploadPolicy,
+    state: &RunState,
+    events: &[KernelEvent],
+) -> Result<UploadReport, String> {
+    let target = policy
+        .target
+        .as_ref()
+        .ok_or_else(|| "Upload is enabled but no target is configured".to_string())?;
+    let mut files = vec![(
+        "summary.md".to_string(),
+        render_report(state, events, ReportFormat::Markdown).into_bytes(),
+    )];
+    if policy.include_diff {
+        if let Some(diff) = workspace_diff(root) {
+            files.push(("diff.patch".to_string(), diff));
+        }
+    }
+    let canonical_root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+    let mut skipped = Vec::new();
+    for artifact in &policy.artifacts {
+        let path = root.join(artifact);
+        let too_large = path
+            .metadata()
+            .map(|meta| !meta.is_file() || meta.len() > MAX_ARTIFACT_BYTES)
+            .unwrap_or(true);
+        // Artifacts must stay inside the workspace.
+        let inside = path
+            .canonicalize()
+            .is_ok_and(|resolved| resolved.starts_with(&canonical_root));
+        match std::fs::read(&path) {
+            Ok(data) if !too_large && inside => {
+                files.push((format!("artifacts/{}", artifact.replace('\\', "/")), data))
+            }
+            _ => skipped.push(artifact.clone()),
+        }
+    }
+    let client = Client::builder()
+        .timeout(Duration::from_secs(120))
+        .build()
+        .map_err(|e| e.to_string())?;
+    let mut uploaded = Vec::new();
+    for (name, data) in files {
+        let key = format!("{}/{}", state.run_id, name);
+        put_object(&client, target, &key, data).await?;
+        uploaded.push(name);
+    }
+    Ok(UploadReport {
+        target: target.describe(),
+        uploaded,
+        skipped,
+    })
+}
+
+fn workspace_diff(root: &Path) -> Option<Vec<u8>> {
+    let output = Command::new("git")
+        .args(["diff", "HEAD", "--no-color"])
+        .current_dir(root)
+        .output()
+        .ok()?;
+    (output.status.success() && !output.stdout.is_empty()).then_some(output.stdout)
+}
+
+async fn put_object(
+    client: &Client,
+    target: &UploadTarget,
+    key: &str,
+    data: Vec<u8>,
+) -> Result<(), String> {
+    let request = match target {
+        UploadTarget::Http { url, token_secret } => {
+            let url = format!("{}/{}", url.trim_end_matches('/'), key);
+            let mut request = client.put(url).body(data);
+            if let Some(token_secret) = token_secret {
+                request = request.bearer_auth(upload_secret(token_secret)?);
+            }
+            request
+        }
+        UploadTarget::S3 {
+            endpoint,
+            bucket,
+            region,
+            prefix,
+            access_key_secret,
+            secret_key_secret,
+        } => {
+            let credentials = S3Credentials {
+                access_key: upload_secret(access_key_secret)?,
+                secret_key: upload_secret(secret_key_secret)?,
+                region: region.clone(),
+            };
+            let prefix = prefix.trim_matches('/');
+            let key = if prefix.is_empty() {
+                key.to_string()
+            } else {
+                format!("{}/{}", prefix, key)
+            };
+            let path = format!("/{}/{}", bucket, uri_encode_path(&key));
+            let url = format!("{}{}", endpoint.trim_end_matches('/'), path);
+            let host = url
+                .split("://")
+                .nth(1)
+                .and_then(|rest| rest.split('/').next())
+                .unwrap_or_default()
+                .to_string();
+            let headers = credentials.sign_put(&host, &path, &data);
+            let mut request = client.put(url).body(data);
+            for (name, value) in headers {
+                request = request.header(name, value);
+            }
+            request
+        }
+    };
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format!("Upload of {} failed: {}", key, e))?;
+    if !response.status().is_success() {
+        return Err(format!(
+            "Upload of {} failed with HTTP {}",
+            key,
+            response.status()
+        ));
+    }
+    Ok(())
+}
+
+struct S3Credentials {
+    access_key: String,
+    secret_key: String,
+    region: String,
+}
+
+impl S3Credentials {
+    /// AWS Signature Version 4 headers for a single-chunk PUT.
+    fn sign_put(&self, host: &str, path: &str, body: &[u8]) -> Vec<(&'static str, String)> {
+        let now = chrono::Utc::now();
+        let amz_date = now.format("%Y%m%dT%H%M%SZ").to_string();
+        let date = now.format("%Y%m%d").to_string();
+        let payload_hash = hex(&Sha256::digest(body));
+        let canonical_request = format!(
+            "PUT\n{}\n\nhost:{}\nx-amz-content-sha256:{}\nx-amz-date:{}\n\n{}\n{}",
+            path, host, payload_hash, amz_date, SIGNED_HEADERS, payload_hash
+        );
+        let scope = format!("{}/{}/s3/aws4_request", date, self.region);
+        let string_to_sign = format!(
+            "AWS4-HMAC-SHA256\n{}\n{}\n{}",
+            amz_date,
+            scope,
+            hex(&Sha256::digest(canonical_request.as_bytes()))
+        );
+        let mut key = hmac_sha256(format!("AWS4{}", self.secret_key).as_bytes(), &date);
+        for part in [self.region.as_str(), "s3", "aws4_request"] {
+            key = hmac_sha256(&key, part);
+        }
+        let signature = hex(&hmac_sha256(&key, &string_to_sign));
+        vec![
+            ("x-amz-date", amz_date),
+            ("x-amz-content-sha256", payload_hash),
+            (
+                "authorization",
+                format!(
+                    "AWS4-HMAC-SHA256 Credential={}/{}, SignedHeaders={}, Signature={}",
+                    self.access_key, scope, SIGNED_HEADERS, signature
+                ),
+            ),
+        ]
+    }
+}
+
+const SIGNED_HEADERS: &str = "host;x-amz-content-sha256;x-amz-date";
+
+fn hmac_sha256(key: &[u8], data: &str) -> Vec<u8> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(key).expect("HMAC accepts any key length");
+    mac.update(data.as_bytes());
+    mac.finalize().into_bytes().to_vec()
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn uri_encode_path(key: &str) -> String {
+    let mut encoded = String::new();
+    for byte in key.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
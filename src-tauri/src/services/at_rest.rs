@@ -0,0 +1,357 @@
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Nonce};
+use base64::{engine::general_purpose, Engine as _};
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::fs::{create_dir_all, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use uuid::Uuid;
+
+/// Same identifier as tauri.conf.json, so keys are grouped under the app.
+pub(crate) const KEYCHAIN_SERVICE: &str = "com.dains.taurihands";
+const SEALED_PREFIX: &str = "enc:v1:";
+const KEY_CONTEXT: &[u8] = b"taurihands-at-rest-v1";
+const NONCE_LEN: usize = 12;
+
+/// Whether run state, event logs and audit logs of a workspace are encrypted.
+/// The key itself lives in the OS keychain; only its id is stored here.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct AtRestPolicy {
+    pub enabled: bool,
+    pub key_id: Option<String>,
+}
+
+pub fn at_rest_policy_path(root: &Path) -> PathBuf {
+    root.join(".taurihands").join("encryption.json")
+}
+
+pub fn load_at_rest_policy(root: &Path) -> AtRestPolicy {
+    std::fs::read_to_string(at_rest_policy_path(root))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save_at_rest_policy(root: &Path, policy: &AtRestPolicy) -> Result<(), String> {
+    let path = at_rest_policy_path(root);
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_vec_pretty(policy).map_err(|e| e.to_string())?;
+    std::fs::write(path, data).map_err(|e| e.to_string())
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AtRestStatus {
+    pub enabled: bool,
+    pub key_id: Option<String>,
+    /// False when the policy names a key the keychain cannot provide.
+    pub key_available: bool,
+    pub rewritten_files: usize,
+    pub errors: Vec<String>,
+}
+
+#[derive(Default)]
+struct AtRestState {
+    enabled: bool,
+    key_id: Option<String>,
+    cipher: Option<Aes256Gcm>,
+}
+
+/// Encryption mode and key of one workspace, shared by every store that
+/// writes its data. Each kernel holds its own, so kernels on different
+/// workspaces never seal with each other's key. The default is plaintext.
+#[derive(Clone, Default)]
+pub struct AtRest {
+    state: Arc<Mutex<AtRestState>>,
+    /// Held by every write and by resealing, so no line is appended in the
+    /// old mode to a file that is being rewritten.
+    files: Arc<Mutex<()>>,
+}
+
+fn keychain_entry(key_id: &str) -> Result<keyring::Entry, String> {
+    keyring::Entry::new(KEYCHAIN_SERVICE, &format!("workspace-key-{}", key_id))
+        .map_err(|e| format!("OS keychain unavailable: {}", e))
+}
+
+/// The data key is derived from the keychain secret rather than used as is,
+/// so the secret can later key other purposes without reuse.
+fn derive_cipher(secret: &str) -> Result<Aes256Gcm, String> {
+    let secret = general_purpose::STANDARD
+        .decode(secret.trim())
+        .map_err(|e| format!("Malformed workspace key: {}", e))?;
+    let mut mac = <Hmac<Sha256> as Mac>::new_from_slice(&secret).map_err(|e| e.to_string())?;
+    mac.update(KEY_CONTEXT);
+    let key = mac.finalize().into_bytes();
+    Aes256Gcm::new_from_slice(&key).map_err(|e| e.to_string())
+}
+
+fn load_cipher(key_id: &str) -> Result<Aes256Gcm, String> {
+    let secret = keychain_entry(key_id)?.get_password().map_err(|e| {
+        format!(
+            "Workspace key {} not found in the OS keychain: {}",
+            key_id, e
+        )
+    })?;
+    derive_cipher(&secret)
+}
+
+fn create_key() -> Result<String, String> {
+    let key_id = Uuid::new_v4().to_string();
+    let secret = general_purpose::STANDARD.encode(Aes256Gcm::generate_key(&mut OsRng));
+    keychain_entry(&key_id)?
+        .set_password(&secret)
+        .map_err(|e| format!("Unable to store the workspace key: {}", e))?;
+    Ok(key_id)
+}
+
+impl AtRest {
+    pub fn for_workspace(root: &Path) -> Self {
+        let at_rest = Self::default();
+        at_rest.configure(root);
+        at_rest
+    }
+
+    fn state(&self) -> Result<std::sync::MutexGuard<'_, AtRestState>, String> {
+        self.state
+            .lock()
+            .map_err(|_| "Encryption state lock poisoned".to_string())
+    }
+
+    fn lock_files(&self) -> Result<std::sync::MutexGuard<'_, ()>, String> {
+        self.files
+            .lock()
+            .map_err(|_| "Encryption file lock poisoned".to_string())
+    }
+
+    /// Loads the workspace policy and its key. Called whenever the workspace
+    /// changes; data sealed with a missing key stays unreadable.
+    pub fn configure(&self, root: &Path) -> AtRestStatus {
+        let policy = load_at_rest_policy(root);
+        let mut errors = Vec::new();
+        let cipher = policy
+            .key_id
+            .as_deref()
+            .and_then(|key_id| load_cipher(key_id).map_err(|err| errors.push(err)).ok());
+        let key_available = policy.key_id.is_none() || cipher.is_some();
+        if let Ok(mut state) = self.state() {
+            *state = AtRestState {
+                enabled: policy.enabled,
+                key_id: policy.key_id.clone(),
+                cipher,
+            };
+        }
+        AtRestStatus {
+            enabled: policy.enabled,
+            key_id: policy.key_id,
+            key_available,
+            rewritten_files: 0,
+            errors,
+        }
+    }
+
+    pub fn status(&self, root: &Path) -> AtRestStatus {
+        let policy = load_at_rest_policy(root);
+        let key_available = self
+            .state()
+            .map(|state| state.cipher.is_some() && state.key_id == policy.key_id)
+            .unwrap_or(false);
+        AtRestStatus {
+            enabled: policy.enabled,
+            key_available: policy.key_id.is_none() || key_available,
+            key_id: policy.key_id,
+            rewritten_files: 0,
+            errors: Vec::new(),
+        }
+    }
+
+    /// Turns encryption on or off and rewrites existing data to match, so a
+    /// workspace is never left half encrypted. The key is kept when disabling.
+    pub fn set_encryption(&self, root: &Path, enabled: bool) -> Result<AtRestStatus, String> {
+        let mut policy = load_at_rest_policy(root);
+        if enabled && policy.key_id.is_none() {
+            policy.key_id = Some(create_key()?);
+        }
+        let cipher = match policy.key_id.as_deref() {
+            Some(key_id) => Some(load_cipher(key_id)?),
+            None => None,
+        };
+        policy.enabled = enabled;
+        // Writers wait until every file is in the new mode.
+        let _files = self.lock_files()?;
+        *self.state()? = AtRestState {
+            enabled,
+            key_id: policy.key_id.clone(),
+            cipher,
+        };
+        save_at_rest_policy(root, &policy)?;
+        let mut rewritten_files = 0;
+        let mut errors = Vec::new();
+        for path in sealed_files(root) {
+            match self.reseal_file(&path) {
+                Ok(true) => rewritten_files += 1,
+                Ok(false) => {}
+                Err(err) => errors.push(format!("{}: {}", path.display(), err)),
+            }
+        }
+        Ok(AtRestStatus {
+            enabled,
+            key_id: policy.key_id,
+            key_available: true,
+            rewritten_files,
+            errors,
+        })
+    }
+
+    /// Encrypts one line or document when encryption is on. Fails rather than
+    /// falling back to plaintext when the key is missing.
+    pub fn seal_text(&self, text: &str) -> Result<String, String> {
+        let state = self.state()?;
+        if !state.enabled {
+            return Ok(text.to_string());
+        }
+        let cipher = state
+            .cipher
+            .as_ref()
+            .ok_or_else(|| "Encryption is on but the workspace key is unavailable".to_string())?;
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let sealed = cipher
+            .encrypt(&nonce, text.as_bytes())
+            .map_err(|_| "Encryption failed".to_string())?;
+        let mut payload = nonce.to_vec();
+        payload.extend(sealed);
+        Ok(format!(
+            "{}{}",
+            SEALED_PREFIX,
+            general_purpose::STANDARD.encode(payload)
+        ))
+    }
+
+    /// Decrypts a sealed line or document; plaintext passes through unchanged
+    /// so data written before encryption was enabled stays readable.
+    pub fn open_text(&self, text: &str) -> Result<String, String> {
+        let Some(encoded) = text.trim().strip_prefix(SEALED_PREFIX) else {
+            return Ok(text.to_string());
+        };
+        let payload = general_purpose::STANDARD
+            .decode(encoded)
+            .map_err(|e| format!("Malformed encrypted data: {}", e))?;
+        if payload.len() < NONCE_LEN {
+            return Err("Malformed encrypted data".to_string());
+        }
+        let state = self.state()?;
+        let cipher = state
+            .cipher
+            .as_ref()
+            .ok_or_else(|| "Data is encrypted but the workspace key is unavailable".to_string())?;
+        let (nonce, sealed) = payload.split_at(NONCE_LEN);
+        let plain = cipher
+            .decrypt(Nonce::from_slice(nonce), sealed)
+            .map_err(|_| "Unable to decrypt data; wrong or rotated workspace key".to_string())?;
+        String::from_utf8(plain).map_err(|e| e.to_string())
+    }
+
+    /// Reads a file written with `seal_text`, line by line for logs and as
+    /// one document otherwise.
+    pub fn read_sealed(&self, path: &Path) -> Result<String, String> {
+        let raw = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        if is_line_file(path) {
+            let mut text = String::with_capacity(raw.len());
+            for line in raw.lines() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                text.push_str(&self.open_text(line)?);
+                text.push('\n');
+            }
+            Ok(text)
+        } else {
+            self.open_text(&raw)
+        }
+    }
+
+    /// Seals and writes a whole document.
+    pub fn write_sealed(&self, path: &Path, text: &str) -> Result<(), String> {
+        let _files = self.lock_files()?;
+        std::fs::write(path, self.seal_text(text)?).map_err(|e| e.to_string())
+    }
+
+    /// Seals one line and appends it to a log.
+    pub fn append_line(&self, path: &Path, line: &str) -> Result<(), String> {
+        let _files = self.lock_files()?;
+        let line = self.seal_text(line)?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(|e| e.to_string())?;
+        writeln!(file, "{}", line).map_err(|e| e.to_string())
+    }
+
+    /// Rewrites a file in the current mode. Returns whether it changed. The
+    /// caller holds the file lock.
+    fn reseal_file(&self, path: &Path) -> Result<bool, String> {
+        if !path.is_file() {
+            return Ok(false);
+        }
+        let raw = std::fs::read_to_string(path).map_err(|e| e.to_string())?;
+        let resealed = if is_line_file(path) {
+            let mut text = String::with_capacity(raw.len());
+            for line in raw.lines() {
+                if line.trim().is_empty() {
+                    continue;
+                }
+                text.push_str(&self.seal_text(&self.open_text(line)?)?);
+                text.push('\n');
+            }
+            text
+        } else {
+            self.seal_text(&self.open_text(&raw)?)?
+        };
+        if resealed == raw {
+            return Ok(false);
+        }
+        let staging = path.with_extension("resealing");
+        std::fs::write(&staging, resealed).map_err(|e| e.to_string())?;
+        std::fs::rename(&staging, path).map_err(|e| e.to_string())?;
+        Ok(true)
+    }
+}
+
+fn is_line_file(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|ext| ext.to_str()),
+        Some("jsonl") | Some("log")
+    )
+}
+
+/// Run state, event logs and audit logs of every profile.
+fn sealed_files(root: &Path) -> Vec<PathBuf> {
+    let data_dir = root.join(".taurihands");
+    let mut files = Vec::new();
+    let mut data_dirs = vec![data_dir.clone()];
+    if let Ok(entries) = std::fs::read_dir(data_dir.join("profiles")) {
+        data_dirs.extend(entries.flatten().map(|entry| entry.path()));
+    }
+    for dir in &data_dirs {
+        for (name, extension) in [("runs", "json"), ("events", "jsonl")] {
+            if let Ok(entries) = std::fs::read_dir(dir.join(name)) {
+                files.extend(entries.flatten().map(|entry| entry.path()).filter(|path| {
+                    path.extension().and_then(|ext| ext.to_str()) == Some(extension)
+                }));
+            }
+        }
+    }
+    if let Ok(entries) = std::fs::read_dir(&data_dir) {
+        files.extend(entries.flatten().map(|entry| entry.path()).filter(|path| {
+            let name = path.file_name().unwrap_or_default().to_string_lossy();
+            name.starts_with("audit") && name.ends_with(".log")
+        }));
+    }
+    files
+}
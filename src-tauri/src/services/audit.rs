@@ -1,14 +1,18 @@
 use serde::Serialize;
-use std::fs::{create_dir_all, OpenOptions};
-use std::io::Write;
+use std::fs::create_dir_all;
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+use crate::services::at_rest::AtRest;
+
 #[derive(Clone, Default)]
 pub struct AuditLog {
-    path: PathBuf,
+    path: Arc<Mutex<PathBuf>>,
     lock: Arc<Mutex<()>>,
+    /// Encryption of the workspace the log lives in; plaintext until the
+    /// kernel attaches its own.
+    at_rest: Arc<Mutex<AtRest>>,
 }
 
 #[derive(Serialize)]
@@ -23,25 +27,41 @@ pub struct AuditEntry {
 impl AuditLog {
     pub fn new(path: PathBuf) -> Self {
         Self {
-            path,
+            path: Arc::new(Mutex::new(path)),
             lock: Arc::new(Mutex::new(())),
+            at_rest: Arc::new(Mutex::new(AtRest::default())),
         }
     }
 
+    /// Seals entries of every clone of this log with the given workspace key.
+    pub fn set_at_rest(&self, at_rest: AtRest) {
+        *self.at_rest.lock().expect("audit log lock poisoned") = at_rest;
+    }
+
+    pub fn path(&self) -> PathBuf {
+        self.path.lock().expect("audit log lock poisoned").clone()
+    }
+
+    /// Redirects every clone of this log, e.g. when switching user profiles.
+    pub fn set_path(&self, path: PathBuf) {
+        *self.path.lock().expect("audit log lock poisoned") = path;
+    }
+
     pub fn write(&self, entry: AuditEntry) {
         let _guard = self.lock.lock().expect("audit log lock poisoned");
-        if let Some(parent) = self.path.parent() {
+        let path = self.path();
+        if let Some(parent) = path.parent() {
             let _ = create_dir_all(parent);
         }
-        if let Ok(mut file) = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&self.path)
-        {
-            if let Ok(line) = serde_json::to_string(&entry) {
-                let _ = writeln!(file, "{}", line);
-            }
-        }
+        let Ok(line) = serde_json::to_string(&entry) else {
+            return;
+        };
+        let at_rest = self
+            .at_rest
+            .lock()
+            .expect("audit log lock poisoned")
+            .clone();
+        let _ = at_rest.append_line(&path, &line);
     }
 }
 
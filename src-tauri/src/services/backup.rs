@@ -0,0 +1,228 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::{create_dir_all, File};
+use std::io::copy;
+use std::path::{Path, PathBuf};
+use walkdir::WalkDir;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::services::audit::now_ms;
+use crate::services::ignore::IgnoreRules;
+
+/// Whole-workspace archives for workspaces that cannot rely on git to undo
+/// an autonomous run.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct BackupPolicy {
+    /// Archive the workspace before each run of a task with `auto` autonomy.
+    pub before_autonomous_runs: bool,
+    /// How many backups to keep; older ones are deleted after each backup.
+    pub retention: usize,
+}
+
+impl Default for BackupPolicy {
+    fn default() -> Self {
+        Self {
+            before_autonomous_runs: true,
+            retention: 5,
+        }
+    }
+}
+
+pub fn backup_policy_path(root: &Path) -> PathBuf {
+    root.join(".taurihands").join("backup.json")
+}
+
+pub fn load_backup_policy(root: &Path) -> BackupPolicy {
+    std::fs::read_to_string(backup_policy_path(root))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_backup_policy(root: &Path, policy: &BackupPolicy) -> Result<(), String> {
+    let path = backup_policy_path(root);
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_vec_pretty(policy).map_err(|e| e.to_string())?;
+    std::fs::write(path, data).map_err(|e| e.to_string())
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    pub id: String,
+    pub created_at: u128,
+    /// Why the backup was taken, e.g. `autonomous run` or `manual`.
+    pub reason: String,
+    pub files: usize,
+    pub bytes: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupRestoreReport {
+    pub id: String,
+    pub restored: usize,
+    /// Files that were not in the backup and were deleted; only with `prune`.
+    pub removed: Vec<String>,
+}
+
+pub fn backups_dir(root: &Path) -> PathBuf {
+    root.join(".taurihands").join("backups")
+}
+
+/// Archives every file `rules` does not ignore, then applies the retention.
+pub fn create_backup(
+    root: &Path,
+    rules: &IgnoreRules,
+    reason: &str,
+    retention: usize,
+) -> Result<BackupInfo, String> {
+    let dir = backups_dir(root);
+    create_dir_all(&dir).map_err(|e| e.to_string())?;
+    let created_at = now_ms();
+    let id = format!("backup-{}", created_at);
+    let archive_path = dir.join(format!("{}.zip", id));
+    let file = File::create(&archive_path).map_err(|e| e.to_string())?;
+    let mut zip = ZipWriter::new(file);
+    let options = SimpleFileOptions::default()
+        .compression_method(CompressionMethod::Deflated)
+        .large_file(true);
+    let mut files = 0;
+    let mut bytes = 0;
+    for (relative, path) in workspace_files(root, rules) {
+        let Ok(mut source) = File::open(&path) else {
+            continue;
+        };
+        zip.start_file(relative.as_str(), options)
+            .map_err(|e| e.to_string())?;
+        bytes += copy(&mut source, &mut zip).map_err(|e| e.to_string())?;
+        files += 1;
+    }
+    zip.finish().map_err(|e| e.to_string())?;
+    let info = BackupInfo {
+        id,
+        created_at,
+        reason: reason.to_string(),
+        files,
+        bytes,
+    };
+    let data = serde_json::to_vec_pretty(&info).map_err(|e| e.to_string())?;
+    std::fs::write(dir.join(format!("{}.json", info.id)), data).map_err(|e| e.to_string())?;
+    prune_backups(root, retention.max(1));
+    Ok(info)
+}
+
+/// Newest first.
+pub fn list_backups(root: &Path) -> Vec<BackupInfo> {
+    let Ok(entries) = std::fs::read_dir(backups_dir(root)) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<BackupInfo> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().and_then(|ext| ext.to_str()) == Some("json"))
+        .filter_map(|path| {
+            let raw = std::fs::read_to_string(&path).ok()?;
+            serde_json::from_str::<BackupInfo>(&raw).ok()
+        })
+        .filter(|info| archive_path(root, &info.id).is_ok_and(|path| path.exists()))
+        .collect();
+    backups.sort_by_key(|info| std::cmp::Reverse(info.created_at));
+    backups
+}
+
+/// Writes the files of backup `id` back into the workspace. With `prune`,
+/// files `rules` would have archived but that are not in the backup are
+/// deleted, so the workspace matches the backup exactly.
+pub fn restore_backup(
+    root: &Path,
+    id: &str,
+    rules: &IgnoreRules,
+    prune: bool,
+) -> Result<BackupRestoreReport, String> {
+    let path = archive_path(root, id)?;
+    let file = File::open(&path).map_err(|_| format!("Backup {} not found", id))?;
+    let mut archive = ZipArchive::new(file).map_err(|e| e.to_string())?;
+    let mut restored = HashSet::new();
+    for index in 0..archive.len() {
+        let mut entry = archive.by_index(index).map_err(|e| e.to_string())?;
+        if entry.is_dir() {
+            continue;
+        }
+        let Some(relative) = entry.enclosed_name() else {
+            continue;
+        };
+        let target = root.join(&relative);
+        if let Some(parent) = target.parent() {
+            create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let mut output = File::create(&target).map_err(|e| e.to_string())?;
+        copy(&mut entry, &mut output).map_err(|e| e.to_string())?;
+        restored.insert(relative_slash_path(&relative));
+    }
+    let mut removed = Vec::new();
+    if prune {
+        for (relative, path) in workspace_files(root, rules) {
+            if !restored.contains(&relative) && std::fs::remove_file(&path).is_ok() {
+                removed.push(relative);
+            }
+        }
+    }
+    Ok(BackupRestoreReport {
+        id: id.to_string(),
+        restored: restored.len(),
+        removed,
+    })
+}
+
+fn archive_path(root: &Path, id: &str) -> Result<PathBuf, String> {
+    let valid =
+        id.starts_with("backup-") && id.chars().all(|ch| ch.is_ascii_alphanumeric() || ch == '-');
+    if !valid {
+        return Err(format!("Invalid backup id: {}", id));
+    }
+    Ok(backups_dir(root).join(format!("{}.zip", id)))
+}
+
+fn prune_backups(root: &Path, retention: usize) {
+    for info in list_backups(root).into_iter().skip(retention) {
+        let dir = backups_dir(root);
+        let _ = std::fs::remove_file(dir.join(format!("{}.zip", info.id)));
+        let _ = std::fs::remove_file(dir.join(format!("{}.json", info.id)));
+    }
+}
+
+/// Workspace files as (`/`-separated relative path, absolute path), never
+/// descending into ignored directories or `.taurihands`.
+fn workspace_files(root: &Path, rules: &IgnoreRules) -> Vec<(String, PathBuf)> {
+    let walker = WalkDir::new(root).into_iter().filter_entry(|entry| {
+        if entry.depth() == 0 {
+            return true;
+        }
+        let Ok(relative) = entry.path().strip_prefix(root) else {
+            return false;
+        };
+        let relative = relative_slash_path(relative);
+        relative != ".taurihands" && !rules.is_ignored(&relative, entry.file_type().is_dir())
+    });
+    walker
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_file())
+        .filter_map(|entry| {
+            let relative = entry.path().strip_prefix(root).ok()?;
+            Some((relative_slash_path(relative), entry.path().to_path_buf()))
+        })
+        .collect()
+}
+
+fn relative_slash_path(relative: &Path) -> String {
+    relative
+        .components()
+        .map(|component| component.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
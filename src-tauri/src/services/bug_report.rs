@@ -0,0 +1,206 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::fs::{create_dir_all, File};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use zip::write::SimpleFileOptions;
+use zip::ZipWriter;
+
+use crate::services::secrets::redact_text;
+
+const MAX_FILE_TAIL_BYTES: u64 = 256 * 1024;
+const MAX_LOG_FILES: usize = 2;
+const REDACTED: &str = "[redacted]";
+const SECRET_KEYS: &[&str] = &[
+    "apikey",
+    "api_key",
+    "token",
+    "secret",
+    "password",
+    "authorization",
+];
+
+/// `name: value`, `name=value` and `Bearer value` in free text; the first
+/// group is kept and the value masked.
+const NAMED_SECRET: &str = concat!(
+    r#"(?i)((?:\b(?:authorization|password|passwd|token|secret|api[_-]?key)"#,
+    r#""?\s*[:=]\s*"?(?:bearer\s+)?)|\bbearer\s+)[^\s"',;&]+"#,
+);
+
+#[derive(Deserialize, Default)]
+pub struct DiagnosticsCollectRequest {
+    #[serde(default)]
+    pub preview: bool,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsItem {
+    pub name: String,
+    pub bytes: usize,
+    pub description: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DiagnosticsBundle {
+    pub path: Option<String>,
+    pub items: Vec<DiagnosticsItem>,
+}
+
+struct BundleEntry {
+    item: DiagnosticsItem,
+    data: Vec<u8>,
+}
+
+/// Collects the files that go into a bug-report zip and reports what each one holds.
+#[derive(Default)]
+pub struct DiagnosticsBuilder {
+    entries: Vec<BundleEntry>,
+}
+
+impl DiagnosticsBuilder {
+    pub fn add_json<T: Serialize>(&mut self, name: &str, description: &str, value: &T) {
+        let mut value = serde_json::to_value(value).unwrap_or(serde_json::Value::Null);
+        redact_secrets(&mut value);
+        let data = serde_json::to_vec_pretty(&value).unwrap_or_default();
+        self.push(name, description, data);
+    }
+
+    pub fn add_file_tail(&mut self, name: &str, description: &str, path: &Path) {
+        if let Some(data) = read_tail(path, MAX_FILE_TAIL_BYTES) {
+            self.push(name, description, redact_file(&data));
+        }
+    }
+
+    /// Adds the most recently modified files from a rolling log directory.
+    pub fn add_recent_logs(&mut self, log_dir: &Path) {
+        let Ok(entries) = std::fs::read_dir(log_dir) else {
+            return;
+        };
+        let mut files: Vec<(std::time::SystemTime, PathBuf)> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let modified = entry.metadata().ok()?.modified().ok()?;
+                Some((modified, entry.path()))
+            })
+            .filter(|(_, path)| path.is_file())
+            .collect();
+        files.sort_by_key(|(modified, _)| std::cmp::Reverse(*modified));
+        for (_, path) in files.into_iter().take(MAX_LOG_FILES) {
+            let file_name = path
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .unwrap_or_default();
+            self.add_file_tail(
+                &format!("logs/{}", file_name),
+                "Recent application log",
+                &path,
+            );
+        }
+    }
+
+    pub fn items(&self) -> Vec<DiagnosticsItem> {
+        self.entries
+            .iter()
+            .map(|entry| entry.item.clone())
+            .collect()
+    }
+
+    pub fn write_zip(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let file = File::create(path).map_err(|e| e.to_string())?;
+        let mut zip = ZipWriter::new(file);
+        let options = SimpleFileOptions::default();
+        for entry in &self.entries {
+            zip.start_file(entry.item.name.as_str(), options)
+                .map_err(|e| e.to_string())?;
+            zip.write_all(&entry.data).map_err(|e| e.to_string())?;
+        }
+        zip.finish().map_err(|e| e.to_string())?;
+        Ok(())
+    }
+
+    fn push(&mut self, name: &str, description: &str, data: Vec<u8>) {
+        self.entries.push(BundleEntry {
+            item: DiagnosticsItem {
+                name: name.to_string(),
+                bytes: data.len(),
+                description: description.to_string(),
+            },
+            data,
+        });
+    }
+}
+
+pub fn system_info(app_version: &str) -> serde_json::Value {
+    serde_json::json!({
+        "appVersion": app_version,
+        "os": std::env::consts::OS,
+        "family": std::env::consts::FAMILY,
+        "arch": std::env::consts::ARCH,
+    })
+}
+
+/// Masks values whose key looks like a credential, at any depth.
+pub fn redact_secrets(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, entry) in map.iter_mut() {
+                let lower = key.to_lowercase();
+                let secret = SECRET_KEYS.iter().any(|name| lower.contains(name));
+                if secret && entry.as_str().is_some_and(|text| !text.is_empty()) {
+                    *entry = serde_json::Value::String(REDACTED.to_string());
+                } else {
+                    redact_secrets(entry);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(redact_secrets),
+        _ => {}
+    }
+}
+
+/// Masks credentials in a text file: JSON lines by key like `add_json`, then
+/// named values and known token shapes anywhere in a line.
+fn redact_file(data: &[u8]) -> Vec<u8> {
+    let named = Regex::new(NAMED_SECRET).ok();
+    let replacement = format!("${{1}}{}", REDACTED);
+    let text = String::from_utf8_lossy(data);
+    let mut redacted = String::with_capacity(text.len());
+    for line in text.split_inclusive('\n') {
+        let body = line.trim_end_matches(['\r', '\n']);
+        let mut masked = match serde_json::from_str::<serde_json::Value>(body) {
+            Ok(mut value) if value.is_object() => {
+                redact_secrets(&mut value);
+                value.to_string()
+            }
+            _ => body.to_string(),
+        };
+        if let Some(named) = &named {
+            masked = named.replace_all(&masked, replacement.as_str()).to_string();
+        }
+        redacted.push_str(&redact_text(&masked, REDACTED));
+        redacted.push_str(&line[body.len()..]);
+    }
+    redacted.into_bytes()
+}
+
+fn read_tail(path: &Path, max_bytes: u64) -> Option<Vec<u8>> {
+    let mut file = File::open(path).ok()?;
+    let len = file.metadata().ok()?.len();
+    if len > max_bytes {
+        file.seek(SeekFrom::Start(len - max_bytes)).ok()?;
+    }
+    let mut data = Vec::new();
+    file.read_to_end(&mut data).ok()?;
+    if len > max_bytes {
+        // Drop the partial first line so the excerpt starts on a record boundary.
+        if let Some(index) = data.iter().position(|byte| *byte == b'\n') {
+            data.drain(..=index);
+        }
+    }
+    Some(data)
+}
@@ -0,0 +1,106 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+
+use crate::services::provenance::ProvenanceStore;
+use crate::services::scratch::without_scratch_pathspec;
+
+/// Unmerged `XY` codes of `git status --porcelain`.
+const CONFLICT_CODES: &[&str] = &["DD", "AU", "UD", "UA", "DU", "AA", "UU"];
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum FileBadge {
+    ModifiedByAgent,
+    ModifiedByUser,
+    Conflicted,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangedFile {
+    pub path: String,
+    pub badge: FileBadge,
+    /// The porcelain `XY` code; unset outside git or for files git does not
+    /// report, e.g. ignored ones.
+    pub git_status: Option<String>,
+    /// The run that last wrote the file through TauriHands.
+    pub run_id: Option<String>,
+}
+
+/// Merges `git status` with the provenance index: a changed file is the
+/// agent's when run `run_id` was the last to write it, and the user's
+/// otherwise. Files the run wrote are listed even when git does not see them.
+pub fn workspace_changed_files(root: &Path, run_id: &str) -> Vec<ChangedFile> {
+    let provenance = ProvenanceStore::for_root(root).all();
+    let by_run = |path: &str| {
+        provenance
+            .get(path)
+            .map(|entry| &entry.last_modified)
+            .filter(|source| source.actor == "agent" && source.run_id.as_deref() == Some(run_id))
+    };
+    let mut files: BTreeMap<String, ChangedFile> = BTreeMap::new();
+    for (code, path) in git_changes(root) {
+        let badge = if CONFLICT_CODES.contains(&code.as_str()) {
+            FileBadge::Conflicted
+        } else if by_run(&path).is_some() {
+            FileBadge::ModifiedByAgent
+        } else {
+            FileBadge::ModifiedByUser
+        };
+        let run_id = provenance
+            .get(&path)
+            .and_then(|entry| entry.last_modified.run_id.clone());
+        files.insert(
+            path.clone(),
+            ChangedFile {
+                path,
+                badge,
+                git_status: Some(code),
+                run_id,
+            },
+        );
+    }
+    for (path, entry) in &provenance {
+        if files.contains_key(path) || by_run(path).is_none() {
+            continue;
+        }
+        files.insert(
+            path.clone(),
+            ChangedFile {
+                path: path.clone(),
+                badge: FileBadge::ModifiedByAgent,
+                git_status: None,
+                run_id: entry.last_modified.run_id.clone(),
+            },
+        );
+    }
+    files.into_values().collect()
+}
+
+/// `(XY, path)` for each change git reports; empty outside a repository.
+fn git_changes(root: &Path) -> Vec<(String, String)> {
+    let output = Command::new("git")
+        .args(["status", "--porcelain=v1", "-z", "--untracked-files=all"])
+        .args(without_scratch_pathspec())
+        .current_dir(root)
+        .output();
+    let Some(output) = output.ok().filter(|output| output.status.success()) else {
+        return Vec::new();
+    };
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let mut entries = stdout.split('\0').filter(|entry| !entry.is_empty());
+    let mut changes = Vec::new();
+    while let Some(entry) = entries.next() {
+        let (Some(code), Some(path)) = (entry.get(..2), entry.get(3..)) else {
+            continue;
+        };
+        // Renames and copies are followed by their source path.
+        if code.starts_with('R') || code.starts_with('C') {
+            entries.next();
+        }
+        changes.push((code.to_string(), path.to_string()));
+    }
+    changes
+}
@@ -1,9 +1,9 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::path::PathBuf;
 use std::process::Command;
-use std::collections::HashMap;
-use anyhow::{Context, Result};
-use async_trait::async_trait;
 use tokio::process::Command as TokioCommand;
 
 use super::llm::LlmProfile;
@@ -95,13 +95,14 @@ impl LocalCodexClient {
 
     async fn execute_codex_command(&self, args: Vec<String>) -> Result<String> {
         let mut cmd = TokioCommand::new("codex");
-        
+
         // Set model
         cmd.arg("--model").arg(&self.config.model);
-        
+
         // Set reasoning level
-        cmd.arg("--reasoning").arg(self.config.reasoning_level.to_string());
-        
+        cmd.arg("--reasoning")
+            .arg(self.config.reasoning_level.to_string());
+
         // Set approval mode
         let approval_arg = match self.config.approval_mode {
             CodexApprovalMode::Always => "always",
@@ -109,32 +110,33 @@ impl LocalCodexClient {
             CodexApprovalMode::Ask => "ask",
         };
         cmd.arg("--approval").arg(approval_arg);
-        
+
         // Set workspace
-        cmd.arg("--path").arg(self.config.workspace.to_string_lossy().as_ref());
-        
+        cmd.arg("--path")
+            .arg(self.config.workspace.to_string_lossy().as_ref());
+
         // Add custom args
         for arg in &args {
             cmd.arg(arg);
         }
-        
+
         // Set max tokens if specified
         if let Some(max_tokens) = self.config.max_tokens {
             cmd.arg("--max-tokens").arg(max_tokens.to_string());
         }
-        
-        log::info!("Executing codex with args: {:?}", args);
-        
+
+        tracing::info!(?args, "executing codex");
+
         let output = cmd
             .output()
             .await
             .context("Failed to execute codex command")?;
-        
+
         if !output.status.success() {
             let stderr = String::from_utf8_lossy(&output.stderr);
             return Err(anyhow::anyhow!("Codex command failed: {}", stderr));
         }
-        
+
         let stdout = String::from_utf8_lossy(&output.stdout).to_string();
         Ok(stdout)
     }
@@ -142,31 +144,32 @@ impl LocalCodexClient {
     fn parse_codex_response(&self, output: &str) -> Result<CodexResponse> {
         // Parse codex output to extract structured information
         // This is a simplified parser - in production, you'd want more robust parsing
-        
+
         let lines: Vec<&str> = output.lines().collect();
         let mut content = String::new();
         let mut reasoning = None;
         let mut files_modified = Vec::new();
         let mut tokens_used = 0;
         let model_used = self.config.model.clone();
-        
+
         for line in lines {
             if line.trim().is_empty() {
                 continue;
             }
-            
+
             // Look for reasoning indicators
             if line.contains("Reasoning:") || line.contains("🤔") {
                 reasoning = Some(line.to_string());
                 continue;
             }
-            
+
             // Look for file modification indicators
-            if line.contains("Modified:") || line.contains("Created:") || line.contains("Updated:") {
+            if line.contains("Modified:") || line.contains("Created:") || line.contains("Updated:")
+            {
                 files_modified.push(line.to_string());
                 continue;
             }
-            
+
             // Look for token usage
             if line.contains("Tokens used:") {
                 if let Some(tokens_str) = line.split(':').nth(1) {
@@ -176,19 +179,20 @@ impl LocalCodexClient {
                 }
                 continue;
             }
-            
+
             // Main content
-            if !line.starts_with("Codex") && 
-               !line.contains("Reasoning:") && 
-               !line.contains("Modified:") && 
-               !line.contains("Created:") && 
-               !line.contains("Updated:") && 
-               !line.contains("Tokens used:") {
+            if !line.starts_with("Codex")
+                && !line.contains("Reasoning:")
+                && !line.contains("Modified:")
+                && !line.contains("Created:")
+                && !line.contains("Updated:")
+                && !line.contains("Tokens used:")
+            {
                 content.push_str(line);
                 content.push('\n');
             }
         }
-        
+
         Ok(CodexResponse {
             content: content.trim().to_string(),
             reasoning,
@@ -203,81 +207,74 @@ impl LocalCodexClient {
 #[async_trait]
 impl CodexClient for LocalCodexClient {
     async fn execute(&self, request: CodexRequest) -> Result<CodexResponse> {
-        log::info!("Executing Codex with prompt: {}", request.prompt);
-        
+        tracing::info!(prompt = %request.prompt, "executing codex prompt");
+
         let mut args = vec![];
-        
+
         // Add prompt
         args.push(request.prompt.clone());
-        
+
         // Add files if any
         for file in &request.files {
             args.push("--file".to_string());
             args.push(file.clone());
         }
-        
+
         // Add context if any
         if let Some(context) = &request.context {
             args.push("--context".to_string());
             args.push(context.clone());
         }
-        
+
         // Override model if specified
         if let Some(model) = &request.model {
             args.push("--model".to_string());
             args.push(model.clone());
         }
-        
+
         // Override reasoning level if specified
         if let Some(reasoning) = request.reasoning_level {
             args.push("--reasoning".to_string());
             args.push(reasoning.to_string());
         }
-        
+
         let output = self.execute_codex_command(args).await?;
         self.parse_codex_response(&output)
     }
 
     async fn interactive_session(&self) -> Result<()> {
-        log::info!("Starting Codex interactive session");
-        
+        tracing::info!("starting codex interactive session");
+
         let args = vec![];
         let output = self.execute_codex_command(args).await?;
-        
-        println!("Codex Interactive Session Started");
-        println!("Workspace: {:?}", self.config.workspace);
-        println!("Model: {}", self.config.model);
-        println!("Reasoning Level: {}", self.config.reasoning_level);
-        println!("Approval Mode: {:?}", self.config.approval_mode);
-        println!();
-        println!("Codex Output:");
+
+        tracing::info!(
+            workspace = ?self.config.workspace,
+            model = %self.config.model,
+            reasoning_level = %self.config.reasoning_level,
+            approval_mode = ?self.config.approval_mode,
+            "codex interactive session started"
+        );
         println!("{}", output);
-        
+
         Ok(())
     }
 
     async fn code_review(&self, file_path: &PathBuf) -> Result<CodexResponse> {
-        log::info!("Starting code review for {:?}", file_path);
-        
+        tracing::info!(?file_path, "starting codex code review");
+
         let file_path_str = file_path.to_string_lossy().to_string();
-        let args = vec![
-            "codex".to_string(),
-            "review".to_string(),
-            file_path_str
-        ];
-        
+        let args = vec!["codex".to_string(), "review".to_string(), file_path_str];
+
         let output = self.execute_codex_command(args).await?;
         self.parse_codex_response(&output)
     }
 
     async fn search_web(&self, query: &str) -> Result<CodexResponse> {
-        log::info!("Starting web search for: {}", query);
-        
-        let args = vec![
-            "--search".to_string(),
-            query.to_string()
-        ];
-        
+        tracing::info!(%query, "starting codex web search");
+
+        let args = vec!["--search".to_string(), query.to_string()];
+
         let output = self.execute_codex_command(args).await?;
         self.parse_codex_response(&output)
     }
@@ -304,12 +301,11 @@ impl CloudCodexClient {
 
 #[async_trait]
 impl CodexClient for CloudCodexClient {
-
     async fn execute(&self, request: CodexRequest) -> Result<CodexResponse> {
         // For now, fall back to local codex
         // In a real implementation, you'd use OpenAI's API directly
-        log::warn!("Cloud Codex not implemented, falling back to local");
-        
+        tracing::warn!("cloud codex not implemented, falling back to local");
+
         let local_client = LocalCodexClient::new(self.config.clone());
         local_client.execute(request).await
     }
@@ -347,15 +343,13 @@ pub struct CodexManager {
 impl CodexManager {
     pub fn new(config: CodexConfig) -> Result<Self> {
         let client: Box<dyn CodexClient> = match config.client_type {
-            CodexClientType::Local => {
-                Box::new(LocalCodexClient::new(config.clone()))
-            }
+            CodexClientType::Local => Box::new(LocalCodexClient::new(config.clone())),
             CodexClientType::Cloud => {
                 let api_key = std::env::var("OPENAI_API_KEY").ok();
                 Box::new(CloudCodexClient::new(config.clone(), api_key))
             }
         };
-        
+
         Ok(Self { client, config })
     }
 
@@ -367,7 +361,7 @@ impl CodexManager {
             model: Some(self.config.model.clone()),
             reasoning_level: Some(self.config.reasoning_level),
         };
-        
+
         self.client.execute(request).await
     }
 
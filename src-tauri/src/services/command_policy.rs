@@ -0,0 +1,273 @@
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+use crate::services::audit::{now_ms, AuditEntry, AuditLog};
+
+/// Which commands `run_command` and the interactive terminal may start.
+/// Patterns are regexes. Allow patterns must match a whole command, and every
+/// command of a compound line; deny patterns match anywhere in the line.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct CommandPolicy {
+    /// "blocklist" runs anything not denied; "allowlist" runs only what
+    /// matches `allow`. The active task's command policy takes precedence.
+    pub mode: String,
+    pub allow: Vec<String>,
+    pub deny: Vec<String>,
+    /// Audits what would be blocked but lets it run.
+    pub dry_run: bool,
+}
+
+impl Default for CommandPolicy {
+    fn default() -> Self {
+        Self {
+            mode: "blocklist".to_string(),
+            allow: Vec::new(),
+            deny: Vec::new(),
+            dry_run: false,
+        }
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandVerdict {
+    pub command: String,
+    pub mode: String,
+    pub allowed: bool,
+    pub dry_run: bool,
+    /// The allow or deny pattern that decided the verdict.
+    pub matched: Option<String>,
+    pub reason: Option<String>,
+}
+
+pub fn command_policy_path(root: &Path) -> PathBuf {
+    root.join(".taurihands").join("command-policy.json")
+}
+
+pub fn load_command_policy(root: &Path) -> CommandPolicy {
+    std::fs::read_to_string(command_policy_path(root))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_command_policy(root: &Path, policy: &CommandPolicy) -> Result<(), String> {
+    if !matches!(policy.mode.as_str(), "allowlist" | "blocklist") {
+        return Err(format!("Unknown command policy mode: {}", policy.mode));
+    }
+    compile_allow(&policy.allow)?;
+    compile(&policy.deny)?;
+    let path = command_policy_path(root);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_vec_pretty(policy).map_err(|e| e.to_string())?;
+    std::fs::write(path, data).map_err(|e| e.to_string())
+}
+
+#[derive(Default)]
+struct ActivePolicy {
+    policy: CommandPolicy,
+    allow: Vec<(String, Regex)>,
+    deny: Vec<(String, Regex)>,
+    /// `TaskRiskPolicy.command_policy` of the active task.
+    task_mode: Option<String>,
+}
+
+static ACTIVE: OnceLock<Mutex<ActivePolicy>> = OnceLock::new();
+
+fn active_policy() -> &'static Mutex<ActivePolicy> {
+    ACTIVE.get_or_init(|| Mutex::new(ActivePolicy::default()))
+}
+
+type Compiled = Result<Vec<(String, Regex)>, String>;
+
+fn compile(patterns: &[String]) -> Compiled {
+    patterns
+        .iter()
+        .map(|pattern| {
+            Regex::new(pattern)
+                .map(|regex| (pattern.clone(), regex))
+                .map_err(|e| format!("Invalid command pattern {}: {}", pattern, e))
+        })
+        .collect()
+}
+
+/// Anchors allow patterns so `cargo test` cannot admit `cargo test; rm -rf ~`.
+fn compile_allow(patterns: &[String]) -> Compiled {
+    patterns
+        .iter()
+        .map(|pattern| {
+            Regex::new(&format!("^(?:{})$", pattern))
+                .map(|regex| (pattern.clone(), regex))
+                .map_err(|e| format!("Invalid command pattern {}: {}", pattern, e))
+        })
+        .collect()
+}
+
+/// Loads the policy of the workspace at `root`; invalid patterns are skipped.
+pub fn configure_command_policy(root: &Path) {
+    let policy = load_command_policy(root);
+    let compile_valid = |patterns: &[String], compile: fn(&[String]) -> Compiled| {
+        patterns
+            .iter()
+            .filter_map(|pattern| compile(std::slice::from_ref(pattern)).ok())
+            .flatten()
+            .collect()
+    };
+    if let Ok(mut active) = active_policy().lock() {
+        active.allow = compile_valid(&policy.allow, compile_allow);
+        active.deny = compile_valid(&policy.deny, compile);
+        active.policy = policy;
+    }
+}
+
+/// Splits a command line into the commands it runs, at `;`, `&&`, `||`, `|`,
+/// `&` and newlines outside quotes and redirections such as `2>&1`. Returns
+/// `None` for command substitution (`$(`, backticks, `<(`, `>(`), whose inner
+/// commands cannot be checked.
+fn command_segments(command: &str) -> Option<Vec<&str>> {
+    let bytes = command.as_bytes();
+    let mut segments = Vec::new();
+    let mut start = 0;
+    let mut single = false;
+    let mut double = false;
+    let mut index = 0;
+    while index < bytes.len() {
+        let byte = bytes[index];
+        let next = bytes.get(index + 1).copied();
+        match byte {
+            b'\\' if !single => {
+                index += 2;
+                continue;
+            }
+            b'\'' if !double => single = !single,
+            b'"' if !single => double = !double,
+            b'`' if !single => return None,
+            b'$' | b'<' | b'>' if !single && next == Some(b'(') => return None,
+            b'&' if matches!(bytes.get(index.wrapping_sub(1)), Some(b'>' | b'<'))
+                || next == Some(b'>') => {}
+            b';' | b'|' | b'&' | b'\n' if !single && !double => {
+                segments.push(&command[start..index]);
+                let doubled = matches!((byte, next), (b'|', Some(b'|')) | (b'&', Some(b'&')));
+                index += if doubled { 2 } else { 1 };
+                start = index;
+                continue;
+            }
+            _ => {}
+        }
+        index += 1;
+    }
+    segments.push(&command[start..]);
+    Some(
+        segments
+            .into_iter()
+            .map(str::trim)
+            .filter(|segment| !segment.is_empty())
+            .collect(),
+    )
+}
+
+/// "confirm" keeps the workspace mode; approval of risky commands is left
+/// to readonly mode and its pending changes.
+pub fn set_task_command_mode(mode: Option<String>) {
+    if let Ok(mut active) = active_policy().lock() {
+        active.task_mode = mode.filter(|mode| matches!(mode.as_str(), "allowlist" | "blocklist"));
+    }
+}
+
+pub fn evaluate_command(command: &str) -> CommandVerdict {
+    let Ok(active) = active_policy().lock() else {
+        return CommandVerdict {
+            command: command.to_string(),
+            mode: "blocklist".to_string(),
+            allowed: false,
+            dry_run: false,
+            matched: None,
+            reason: Some("Command policy lock poisoned".to_string()),
+        };
+    };
+    let mode = active
+        .task_mode
+        .clone()
+        .unwrap_or_else(|| active.policy.mode.clone());
+    let command = command.trim();
+    let segments = command_segments(command);
+    let denied = active.deny.iter().find(|(_, regex)| {
+        regex.is_match(command)
+            || segments
+                .iter()
+                .flatten()
+                .any(|segment| regex.is_match(segment))
+    });
+    // Every command of a compound line needs its own allow pattern.
+    let allowed = segments.as_ref().and_then(|segments| {
+        let matched = segments
+            .iter()
+            .map(|segment| {
+                active
+                    .allow
+                    .iter()
+                    .find(|(_, regex)| regex.is_match(segment))
+                    .map(|(pattern, _)| pattern.as_str())
+            })
+            .collect::<Option<Vec<_>>>()?;
+        let mut patterns: Vec<&str> = Vec::new();
+        for pattern in matched {
+            if !patterns.contains(&pattern) {
+                patterns.push(pattern);
+            }
+        }
+        (!patterns.is_empty()).then(|| patterns.join(", "))
+    });
+    let (allowed, matched, reason) = match (denied, allowed) {
+        (Some((pattern, _)), _) => (
+            false,
+            Some(pattern.clone()),
+            Some(format!("Command matches deny pattern {}", pattern)),
+        ),
+        (None, Some(patterns)) => (true, Some(patterns), None),
+        (None, None) if mode == "allowlist" && segments.is_none() => (
+            false,
+            None,
+            Some("Command substitution is not allowed in allowlist mode".to_string()),
+        ),
+        (None, None) if mode == "allowlist" => (
+            false,
+            None,
+            Some("Command does not match any allow pattern".to_string()),
+        ),
+        (None, None) => (true, None, None),
+    };
+    CommandVerdict {
+        command: command.to_string(),
+        mode,
+        allowed,
+        dry_run: active.policy.dry_run,
+        matched,
+        reason,
+    }
+}
+
+/// Returns the reason a command is blocked. In dry-run mode the block is
+/// only audited.
+pub fn enforce_command_policy(command: &str, audit: &AuditLog) -> Result<(), String> {
+    let verdict = evaluate_command(command);
+    let Some(reason) = verdict.reason.clone() else {
+        return Ok(());
+    };
+    if verdict.dry_run {
+        audit.write(AuditEntry {
+            timestamp_ms: now_ms(),
+            action: "command_policy.dry_run".to_string(),
+            session_id: None,
+            command: Some(verdict.command.clone()),
+            payload: serde_json::json!({ "verdict": verdict }),
+        });
+        return Ok(());
+    }
+    Err(reason)
+}
@@ -0,0 +1,324 @@
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// POSIX tools models reach for out of habit, even when told the shell is
+/// PowerShell.
+const POSIX_COMMANDS: &[&str] = &[
+    "ls", "cat", "grep", "head", "tail", "which", "touch", "rm", "mkdir", "find", "wc",
+];
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CommandTranslation {
+    pub original: String,
+    pub command: String,
+    /// `git-bash` or `powershell`.
+    pub via: &'static str,
+}
+
+impl CommandTranslation {
+    pub fn describe(&self) -> String {
+        format!(
+            "[translated for Windows via {}: `{}` -> `{}`]",
+            self.via, self.original, self.command
+        )
+    }
+}
+
+/// Rewrites a command that starts with a common POSIX tool when it is about
+/// to run in PowerShell on Windows: through Git Bash when it is installed,
+/// otherwise into the PowerShell equivalent if the command is simple enough
+/// to map. `None` means the command runs as written.
+pub fn translate_command(command: &str, shell: &str) -> Option<CommandTranslation> {
+    if !cfg!(windows) || !is_powershell(shell) {
+        return None;
+    }
+    translate_for_powershell(command, git_bash())
+}
+
+fn translate_for_powershell(command: &str, git_bash: Option<&Path>) -> Option<CommandTranslation> {
+    let trimmed = command.trim();
+    let head = trimmed.split_whitespace().next()?;
+    if !POSIX_COMMANDS.contains(&head) {
+        return None;
+    }
+    // Windows PowerShell drops embedded double quotes when it calls a native
+    // program, so such commands are mapped instead.
+    if let Some(bash) = git_bash.filter(|_| !trimmed.contains('"')) {
+        return Some(CommandTranslation {
+            original: trimmed.to_string(),
+            command: format!(
+                "& {} -c {}",
+                ps_quote(&bash.to_string_lossy()),
+                ps_quote(trimmed)
+            ),
+            via: "git-bash",
+        });
+    }
+    Some(CommandTranslation {
+        original: trimmed.to_string(),
+        command: to_powershell(trimmed)?,
+        via: "powershell",
+    })
+}
+
+/// Maps a single command without pipes, redirections or substitutions.
+fn to_powershell(command: &str) -> Option<String> {
+    if command.contains(['|', ';', '&', '>', '<', '`', '$', '(']) {
+        return None;
+    }
+    let words = split_words(command)?;
+    let (program, args) = words.split_first()?;
+    let (flags, operands): (Vec<&String>, Vec<&String>) = args
+        .iter()
+        .partition(|arg| arg.starts_with('-') && arg.len() > 1);
+    let paths = || {
+        operands
+            .iter()
+            .map(|path| ps_quote(path))
+            .collect::<Vec<_>>()
+            .join(", ")
+    };
+    match program.as_str() {
+        "ls" => {
+            let letters = short_flags(&flags)?;
+            let mut mapped = "Get-ChildItem".to_string();
+            if letters.contains('a') {
+                mapped.push_str(" -Force");
+            }
+            if !operands.is_empty() {
+                mapped.push_str(&format!(" -Path {}", paths()));
+            }
+            Some(mapped)
+        }
+        "cat" if flags.is_empty() && !operands.is_empty() => {
+            Some(format!("Get-Content -Path {}", paths()))
+        }
+        "grep" => grep_to_powershell(args),
+        "head" | "tail" => {
+            let (count, file) = line_count_args(args)?;
+            let option = if program == "head" {
+                "-TotalCount"
+            } else {
+                "-Tail"
+            };
+            Some(format!(
+                "Get-Content -Path {} {} {}",
+                ps_quote(file),
+                option,
+                count
+            ))
+        }
+        "which" if flags.is_empty() && operands.len() == 1 => Some(format!(
+            "Get-Command {} | Select-Object -ExpandProperty Source",
+            ps_quote(operands[0])
+        )),
+        "touch" if flags.is_empty() && !operands.is_empty() => Some(format!(
+            "foreach ($p in {}) {{ if (Test-Path $p) {{ (Get-Item $p).LastWriteTime = Get-Date }} \
+             else {{ New-Item -ItemType File -Path $p | Out-Null }} }}",
+            paths()
+        )),
+        "rm" if !operands.is_empty() => {
+            let letters = short_flags(&flags)?;
+            if !letters.chars().all(|flag| matches!(flag, 'r' | 'R' | 'f')) {
+                return None;
+            }
+            let mut mapped = format!("Remove-Item -Path {}", paths());
+            if letters.contains(['r', 'R']) {
+                mapped.push_str(" -Recurse");
+            }
+            if letters.contains('f') {
+                mapped.push_str(" -Force -ErrorAction SilentlyContinue");
+            }
+            Some(mapped)
+        }
+        "mkdir" if !operands.is_empty() => {
+            let letters = short_flags(&flags)?;
+            if !letters.chars().all(|flag| flag == 'p') {
+                return None;
+            }
+            let force = if letters.is_empty() { "" } else { " -Force" };
+            Some(format!(
+                "New-Item -ItemType Directory{} -Path {} | Out-Null",
+                force,
+                paths()
+            ))
+        }
+        "find" => find_to_powershell(args),
+        "wc" if flags.iter().all(|flag| flag.as_str() == "-l") && operands.len() == 1 => {
+            Some(format!(
+                "(Get-Content -Path {} | Measure-Object -Line).Lines",
+                ps_quote(operands[0])
+            ))
+        }
+        _ => None,
+    }
+}
+
+/// `grep [-rRinlFE] pattern path...`; reading stdin has no equivalent here.
+fn grep_to_powershell(args: &[String]) -> Option<String> {
+    let (flags, operands): (Vec<&String>, Vec<&String>) = args
+        .iter()
+        .partition(|arg| arg.starts_with('-') && arg.len() > 1);
+    let letters = short_flags(&flags)?;
+    if !letters
+        .chars()
+        .all(|flag| matches!(flag, 'r' | 'R' | 'i' | 'n' | 'l' | 'F' | 'E'))
+    {
+        return None;
+    }
+    let (pattern, paths) = operands.split_first()?;
+    if paths.is_empty() {
+        return None;
+    }
+    let paths = paths
+        .iter()
+        .map(|path| ps_quote(path))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let mut search = format!("Select-String -Pattern {}", ps_quote(pattern));
+    if !letters.contains('i') {
+        search.push_str(" -CaseSensitive");
+    }
+    if letters.contains('F') {
+        search.push_str(" -SimpleMatch");
+    }
+    if letters.contains('l') {
+        search.push_str(" -List");
+    }
+    let mut mapped = if letters.contains(['r', 'R']) {
+        format!("Get-ChildItem -Path {} -Recurse -File | {}", paths, search)
+    } else {
+        format!("{} -Path {}", search, paths)
+    };
+    if letters.contains('l') {
+        mapped.push_str(" | ForEach-Object { $_.Path }");
+    }
+    Some(mapped)
+}
+
+/// `find [dir] [-name pattern] [-type f|d]`.
+fn find_to_powershell(args: &[String]) -> Option<String> {
+    let mut words = args.iter().peekable();
+    let mut dir = ".".to_string();
+    if let Some(first) = words.next_if(|word| !word.starts_with('-')) {
+        dir = first.clone();
+    }
+    let mut mapped = format!("Get-ChildItem -Path {} -Recurse", ps_quote(&dir));
+    while let Some(option) = words.next() {
+        let value = words.next()?;
+        match (option.as_str(), value.as_str()) {
+            ("-name", pattern) => mapped.push_str(&format!(" -Filter {}", ps_quote(pattern))),
+            ("-type", "f") => mapped.push_str(" -File"),
+            ("-type", "d") => mapped.push_str(" -Directory"),
+            _ => return None,
+        }
+    }
+    mapped.push_str(" | ForEach-Object { $_.FullName }");
+    Some(mapped)
+}
+
+/// `-n N`, `-nN`, `-N` or nothing (10 lines), followed by exactly one file.
+fn line_count_args(args: &[String]) -> Option<(usize, &str)> {
+    match args {
+        [file] if !file.starts_with('-') => Some((10, file)),
+        [flag, count, file] if flag == "-n" => Some((count.parse().ok()?, file)),
+        [flag, file] => {
+            let digits = flag.strip_prefix("-n").or_else(|| flag.strip_prefix('-'))?;
+            Some((digits.parse().ok()?, file))
+        }
+        _ => None,
+    }
+}
+
+/// The letters of `-abc`-style flags; `None` for long options.
+fn short_flags(flags: &[&String]) -> Option<String> {
+    let mut letters = String::new();
+    for flag in flags {
+        let rest = flag.strip_prefix('-')?;
+        if rest.starts_with('-') {
+            return None;
+        }
+        letters.push_str(rest);
+    }
+    Some(letters)
+}
+
+/// Splits on whitespace, honouring single and double quotes. Backslashes are
+/// literal, since they are path separators here. `None` on unbalanced quotes.
+fn split_words(command: &str) -> Option<Vec<String>> {
+    let mut words = Vec::new();
+    let mut current = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    for ch in command.chars() {
+        match quote {
+            Some(open) if ch == open => quote = None,
+            Some(_) => current.push(ch),
+            None if ch == '\'' || ch == '"' => {
+                quote = Some(ch);
+                in_word = true;
+            }
+            None if ch.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut current));
+                    in_word = false;
+                }
+            }
+            None => {
+                current.push(ch);
+                in_word = true;
+            }
+        }
+    }
+    if quote.is_some() {
+        return None;
+    }
+    if in_word {
+        words.push(current);
+    }
+    Some(words)
+}
+
+fn ps_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn is_powershell(shell: &str) -> bool {
+    let program = shell
+        .trim()
+        .rsplit(['/', '\\'])
+        .next()
+        .unwrap_or_default()
+        .to_lowercase();
+    matches!(
+        program.strip_suffix(".exe").unwrap_or(&program),
+        "powershell" | "pwsh"
+    )
+}
+
+fn git_bash() -> Option<&'static Path> {
+    static GIT_BASH: OnceLock<Option<PathBuf>> = OnceLock::new();
+    GIT_BASH.get_or_init(find_git_bash).as_deref()
+}
+
+/// Git for Windows' bash, found next to `git.exe` on PATH or in the default
+/// install locations. `System32\bash.exe` is never used; it starts WSL.
+fn find_git_bash() -> Option<PathBuf> {
+    let from_path = std::env::var_os("PATH").and_then(|path| {
+        std::env::split_paths(&path)
+            .filter(|dir| dir.join("git.exe").is_file())
+            .find_map(|dir| {
+                let bash = dir.parent()?.join("bin").join("bash.exe");
+                bash.is_file().then_some(bash)
+            })
+    });
+    from_path.or_else(|| {
+        ["ProgramW6432", "ProgramFiles", "ProgramFiles(x86)"]
+            .iter()
+            .filter_map(std::env::var_os)
+            .map(|dir| PathBuf::from(dir).join("Git").join("bin").join("bash.exe"))
+            .find(|bash| bash.is_file())
+    })
+}
@@ -0,0 +1,181 @@
+use serde::Serialize;
+
+use crate::services::kernel::ChatMessage;
+use crate::services::llm::LlmProfile;
+
+/// Used when a profile leaves `context_window` unset.
+const DEFAULT_CONTEXT_WINDOW: usize = 128_000;
+/// Share of the window kept free because the token estimate is approximate.
+const SAFETY_MARGIN_PERCENT: usize = 10;
+/// No single message may take more than this share of the conversation budget.
+const MESSAGE_SHARE_PERCENT: usize = 40;
+const MIN_MESSAGE_TOKENS: usize = 64;
+const SUMMARY_LINE_CHARS: usize = 160;
+const MAX_SUMMARY_LINES: usize = 20;
+
+/// Estimates BPE tokens without a model vocabulary: about four ASCII
+/// characters per token, and one token per other character, which keeps CJK
+/// text from being underestimated.
+pub fn estimate_tokens(text: &str) -> usize {
+    let (ascii, other) = text.chars().fold((0, 0), |(ascii, other), c| {
+        if c.is_ascii() {
+            (ascii + 1, other)
+        } else {
+            (ascii, other + 1)
+        }
+    });
+    usize::div_ceil(ascii, 4) + other
+}
+
+/// Cuts `text` to roughly `max_tokens`, marking the cut.
+pub fn truncate_to_tokens(text: &str, max_tokens: usize) -> String {
+    if estimate_tokens(text) <= max_tokens {
+        return text.to_string();
+    }
+    // Counted in quarter tokens so ASCII characters cost one unit each.
+    let limit = max_tokens.saturating_sub(4) * 4;
+    let mut used = 0;
+    let mut end = 0;
+    for (index, c) in text.char_indices() {
+        used += if c.is_ascii() { 1 } else { 4 };
+        if used > limit {
+            break;
+        }
+        end = index + c.len_utf8();
+    }
+    format!("{} [truncated]", &text[..end])
+}
+
+/// How much of the model's context window a prompt may use, from the
+/// profile's `context_window`, `max_tokens` and `context_policy`.
+pub struct ContextPolicy {
+    pub context_window: usize,
+    pub reserved_output: usize,
+    /// With `summary-first`, only the last two messages are kept verbatim.
+    pub summary_first: bool,
+}
+
+impl ContextPolicy {
+    pub fn for_profile(profile: &LlmProfile) -> Self {
+        let context_window = match profile.context_window {
+            0 => DEFAULT_CONTEXT_WINDOW,
+            window => window as usize,
+        };
+        Self {
+            context_window,
+            reserved_output: profile.max_tokens as usize,
+            summary_first: profile.context_policy == "summary-first",
+        }
+    }
+
+    /// Tokens left for the user prompt once `reserved` tokens are taken by
+    /// the system prompt, tool definitions and other fixed sections.
+    pub fn prompt_budget(&self, reserved: usize) -> usize {
+        let usable = self.context_window - self.context_window * SAFETY_MARGIN_PERCENT / 100;
+        usable.saturating_sub(self.reserved_output + reserved)
+    }
+
+    fn verbatim_limit(&self) -> usize {
+        if self.summary_first {
+            2
+        } else {
+            usize::MAX
+        }
+    }
+}
+
+/// Reported with the `ContextCompacted` event.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ContextCompaction {
+    pub budget_tokens: usize,
+    pub used_tokens: usize,
+    pub kept_messages: usize,
+    pub truncated_messages: usize,
+    pub summarized_messages: usize,
+    pub dropped_messages: usize,
+}
+
+pub struct CompactedConversation {
+    pub text: String,
+    /// Set when anything was truncated, summarized or dropped.
+    pub compaction: Option<ContextCompaction>,
+}
+
+/// Renders the conversation within `budget` tokens. The newest messages are
+/// kept verbatim while they fit; older ones collapse to one line each, and
+/// the oldest are dropped once the summary is full too.
+pub fn compact_conversation(
+    messages: &[ChatMessage],
+    budget: usize,
+    policy: &ContextPolicy,
+) -> CompactedConversation {
+    let message_cap = (budget * MESSAGE_SHARE_PERCENT / 100).max(MIN_MESSAGE_TOKENS);
+    let mut used = estimate_tokens("Conversation:\n");
+    let mut kept: Vec<String> = Vec::new();
+    let mut truncated = 0;
+    let mut first_kept = messages.len();
+    for (index, message) in messages.iter().enumerate().rev() {
+        if kept.len() >= policy.verbatim_limit() {
+            break;
+        }
+        let content = truncate_to_tokens(&message.content, message_cap);
+        let line = format!("- {}: {}\n", message.role, content);
+        let cost = estimate_tokens(&line);
+        // The latest message is always sent, even over budget.
+        if !kept.is_empty() && used + cost > budget {
+            break;
+        }
+        if content.len() != message.content.len() {
+            truncated += 1;
+        }
+        used += cost;
+        kept.push(line);
+        first_kept = index;
+    }
+    let mut summary: Vec<String> = Vec::new();
+    for message in messages[..first_kept].iter().rev() {
+        if summary.len() >= MAX_SUMMARY_LINES {
+            break;
+        }
+        let first_line = message.content.lines().next().unwrap_or_default().trim();
+        let line = format!(
+            "- {}: {}\n",
+            message.role,
+            first_line
+                .chars()
+                .take(SUMMARY_LINE_CHARS)
+                .collect::<String>()
+        );
+        let cost = estimate_tokens(&line);
+        if used + cost > budget {
+            break;
+        }
+        used += cost;
+        summary.push(line);
+    }
+    let dropped = first_kept - summary.len();
+    let mut text = String::new();
+    if !summary.is_empty() || dropped > 0 {
+        text.push_str(&format!(
+            "Earlier conversation (first lines only; {} older messages omitted):\n",
+            dropped
+        ));
+        for line in summary.iter().rev() {
+            text.push_str(line);
+        }
+    }
+    text.push_str("Conversation:\n");
+    for line in kept.iter().rev() {
+        text.push_str(line);
+    }
+    let compaction = (truncated > 0 || first_kept > 0).then_some(ContextCompaction {
+        budget_tokens: budget,
+        used_tokens: used,
+        kept_messages: kept.len(),
+        truncated_messages: truncated,
+        summarized_messages: summary.len(),
+        dropped_messages: dropped,
+    });
+    CompactedConversation { text, compaction }
+}
@@ -0,0 +1,133 @@
+use serde::Serialize;
+use std::path::Path;
+
+use crate::services::tools::ToolResult;
+
+pub const COVERAGE_TIMEOUT_MS: u64 = 600_000;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CoverageCommand {
+    pub tool: String,
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl CoverageCommand {
+    fn new(tool: &str, program: &str, args: &[&str]) -> Self {
+        Self {
+            tool: tool.to_string(),
+            program: program.to_string(),
+            args: args.iter().map(|arg| arg.to_string()).collect(),
+        }
+    }
+
+    /// Wraps an explicit `[program, args...]` command, e.g. from a judge rule.
+    pub fn custom(command: &[String]) -> Option<Self> {
+        let (program, args) = command.split_first()?;
+        Some(Self {
+            tool: "custom".to_string(),
+            program: program.clone(),
+            args: args.to_vec(),
+        })
+    }
+
+    pub fn display(&self) -> String {
+        let mut parts = vec![self.program.clone()];
+        parts.extend(self.args.iter().cloned());
+        parts.join(" ")
+    }
+}
+
+/// Picks the coverage tool for the workspace: cargo tarpaulin, nyc or pytest-cov.
+pub fn detect_coverage_command(root: &Path) -> Option<CoverageCommand> {
+    if root.join("Cargo.toml").is_file() {
+        return Some(CoverageCommand::new(
+            "tarpaulin",
+            "cargo",
+            &["tarpaulin", "--skip-clean", "--out", "Stdout"],
+        ));
+    }
+    if root.join("package.json").is_file() {
+        return Some(CoverageCommand::new(
+            "nyc",
+            "npx",
+            &["nyc", "--reporter=text-summary", "npm", "test"],
+        ));
+    }
+    let python_markers = ["pyproject.toml", "setup.py", "setup.cfg", "pytest.ini"];
+    if python_markers
+        .iter()
+        .any(|marker| root.join(marker).is_file())
+    {
+        return Some(CoverageCommand::new(
+            "pytest-cov",
+            "python",
+            &["-m", "pytest", "--cov", "--cov-report=term"],
+        ));
+    }
+    None
+}
+
+/// Reads the coverage from a command's output, including the kept tail of a
+/// truncated stream where the summary ends up.
+pub fn coverage_from_result(result: &ToolResult) -> Option<f64> {
+    let tail = |key: &str| {
+        result
+            .artifacts
+            .as_ref()
+            .and_then(|artifacts| artifacts.get(key))
+            .and_then(|value| value.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+    let stdout = result.stdout_excerpt.clone().unwrap_or_default();
+    let stderr = result.stderr_excerpt.clone().unwrap_or_default();
+    parse_coverage_percent(&format!(
+        "{}\n{}\n{}\n{}",
+        stdout,
+        tail("stdout_tail"),
+        stderr,
+        tail("stderr_tail")
+    ))
+}
+
+/// Reads the overall line coverage from tarpaulin, nyc/istanbul or pytest-cov output.
+/// The last match wins since summaries are printed after per-file tables.
+pub fn parse_coverage_percent(output: &str) -> Option<f64> {
+    let mut percent = None;
+    for line in output.lines() {
+        let line = line.trim();
+        let found = if line.contains("% coverage,") {
+            // tarpaulin: "85.71% coverage, 120/140 lines covered"
+            line.split('%').next().and_then(parse_number)
+        } else if line.starts_with("Lines") && line.contains(':') {
+            // nyc text-summary: "Lines        : 85.5% ( 171/200 )"
+            line.split_once(':')
+                .and_then(|(_, rest)| rest.split('%').next())
+                .and_then(parse_number)
+        } else if line.starts_with("All files") && line.contains('|') {
+            // nyc/istanbul text table: "All files |   85.5 |  70 |  90 |  85.5 |"
+            line.split('|').nth(4).and_then(parse_number)
+        } else if line.starts_with("TOTAL") {
+            // pytest-cov: "TOTAL    200     30    85%"
+            line.split_whitespace()
+                .last()
+                .and_then(|value| value.strip_suffix('%'))
+                .and_then(parse_number)
+        } else {
+            None
+        };
+        if found.is_some() {
+            percent = found;
+        }
+    }
+    percent
+}
+
+fn parse_number(value: &str) -> Option<f64> {
+    value
+        .trim()
+        .parse::<f64>()
+        .ok()
+        .filter(|value| (0.0..=100.0).contains(value))
+}
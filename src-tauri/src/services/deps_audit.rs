@@ -0,0 +1,552 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::services::audit::{now_ms, AuditEntry, AuditLog};
+use crate::services::git_ops::git_output;
+use crate::services::tools::ToolResult;
+
+const MANIFESTS: &[&str] = &["Cargo.toml", "package.json"];
+const CARGO_SECTIONS: &[&str] = &["dependencies", "dev-dependencies", "build-dependencies"];
+const NPM_SECTIONS: &[&str] = &[
+    "dependencies",
+    "devDependencies",
+    "peerDependencies",
+    "optionalDependencies",
+];
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepsPolicy {
+    /// SPDX ids that may be used; empty allows anything not denied.
+    #[serde(default = "default_allowed_licenses")]
+    pub allowed_licenses: Vec<String>,
+    #[serde(default = "default_denied_licenses")]
+    pub denied_licenses: Vec<String>,
+    #[serde(default)]
+    pub denied_packages: Vec<String>,
+    #[serde(default = "default_true")]
+    pub allow_unknown_licenses: bool,
+    #[serde(default = "default_true")]
+    pub block_vulnerabilities: bool,
+}
+
+impl Default for DepsPolicy {
+    fn default() -> Self {
+        Self {
+            allowed_licenses: default_allowed_licenses(),
+            denied_licenses: default_denied_licenses(),
+            denied_packages: Vec::new(),
+            allow_unknown_licenses: true,
+            block_vulnerabilities: true,
+        }
+    }
+}
+
+fn default_allowed_licenses() -> Vec<String> {
+    [
+        "MIT",
+        "Apache-2.0",
+        "BSD-2-Clause",
+        "BSD-3-Clause",
+        "ISC",
+        "MPL-2.0",
+        "Zlib",
+        "Unlicense",
+        "0BSD",
+        "CC0-1.0",
+        "Unicode-DFS-2016",
+        "Unicode-3.0",
+    ]
+    .iter()
+    .map(|license| license.to_string())
+    .collect()
+}
+
+fn default_denied_licenses() -> Vec<String> {
+    ["GPL-2.0", "GPL-3.0", "AGPL-3.0", "SSPL-1.0"]
+        .iter()
+        .map(|license| license.to_string())
+        .collect()
+}
+
+fn default_true() -> bool {
+    true
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct NewDependency {
+    pub ecosystem: String,
+    pub name: String,
+    pub version: String,
+    pub manifest: String,
+    pub license: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Vulnerability {
+    pub ecosystem: String,
+    pub package: String,
+    pub id: String,
+    pub title: String,
+    pub severity: Option<String>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DepsAuditReport {
+    pub manifests: Vec<String>,
+    pub new_dependencies: Vec<NewDependency>,
+    pub vulnerabilities: Vec<Vulnerability>,
+    pub violations: Vec<String>,
+    pub notes: Vec<String>,
+}
+
+impl DepsAuditReport {
+    pub fn describe(&self) -> String {
+        if self.manifests.is_empty() {
+            return "No dependency manifest changes since HEAD".to_string();
+        }
+        let mut lines = vec![format!("Changed manifests: {}", self.manifests.join(", "))];
+        if self.new_dependencies.is_empty() {
+            lines.push("No new dependencies".to_string());
+        }
+        for dep in &self.new_dependencies {
+            lines.push(format!(
+                "+ {} {} {} ({}) license: {}",
+                dep.ecosystem,
+                dep.name,
+                dep.version,
+                dep.manifest,
+                dep.license.as_deref().unwrap_or("unknown")
+            ));
+        }
+        for vuln in &self.vulnerabilities {
+            lines.push(format!(
+                "! {} {}: {} {}",
+                vuln.ecosystem, vuln.package, vuln.id, vuln.title
+            ));
+        }
+        if self.violations.is_empty() {
+            lines.push("Policy: ok".to_string());
+        } else {
+            lines.push("Policy violations:".to_string());
+            lines.extend(
+                self.violations
+                    .iter()
+                    .map(|violation| format!("- {}", violation)),
+            );
+        }
+        lines.extend(self.notes.iter().map(|note| format!("note: {}", note)));
+        lines.join("\n")
+    }
+}
+
+pub fn deps_policy_path(root: &Path) -> PathBuf {
+    root.join(".taurihands").join("deps-policy.json")
+}
+
+pub fn load_deps_policy(root: &Path) -> DepsPolicy {
+    std::fs::read_to_string(deps_policy_path(root))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_deps_policy(root: &Path, policy: &DepsPolicy) -> Result<(), String> {
+    let path = deps_policy_path(root);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_vec_pretty(policy).map_err(|e| e.to_string())?;
+    std::fs::write(path, data).map_err(|e| e.to_string())
+}
+
+/// Compares dependency manifests with HEAD, resolves licenses and advisories for
+/// the dependencies that were added, and checks them against the policy.
+pub fn audit_dependencies(root: &Path, policy: &DepsPolicy, audit: &AuditLog) -> ToolResult {
+    let report = build_report(root, policy);
+    audit.write(AuditEntry {
+        timestamp_ms: now_ms(),
+        action: "deps.audit".to_string(),
+        session_id: None,
+        command: None,
+        payload: serde_json::json!({
+            "manifests": report.manifests,
+            "new_dependencies": report.new_dependencies.len(),
+            "violations": report.violations,
+        }),
+    });
+    ToolResult {
+        ok: report.violations.is_empty(),
+        stdout_excerpt: Some(report.describe()),
+        stderr_excerpt: None,
+        exit_code: None,
+        artifacts: Some(serde_json::json!({ "deps_audit": report })),
+        next_suggestion: None,
+        requires_user: false,
+    }
+}
+
+fn build_report(root: &Path, policy: &DepsPolicy) -> DepsAuditReport {
+    let manifests = changed_manifests(root);
+    let mut new_dependencies = Vec::new();
+    for manifest in &manifests {
+        let current = std::fs::read_to_string(root.join(manifest)).unwrap_or_default();
+        let previous =
+            git_output(root, &["show", &format!("HEAD:{}", manifest)]).unwrap_or_default();
+        let (ecosystem, before, after) = if manifest.ends_with("package.json") {
+            (
+                "npm",
+                npm_dependencies(&previous),
+                npm_dependencies(&current),
+            )
+        } else {
+            (
+                "cargo",
+                cargo_dependencies(&previous),
+                cargo_dependencies(&current),
+            )
+        };
+        let manifest_dir = root.join(manifest);
+        let manifest_dir = manifest_dir.parent().unwrap_or(root);
+        for (name, version) in after {
+            if before.contains_key(&name) {
+                continue;
+            }
+            let license = match ecosystem {
+                "npm" => npm_license(manifest_dir, &name),
+                _ => cargo_license(&name),
+            };
+            new_dependencies.push(NewDependency {
+                ecosystem: ecosystem.to_string(),
+                name,
+                version,
+                manifest: manifest.clone(),
+                license,
+            });
+        }
+    }
+
+    // Each manifest is audited in its own directory, where its lockfile lives.
+    let mut audited: Vec<(&str, PathBuf)> = Vec::new();
+    for dep in &new_dependencies {
+        let dir = root
+            .join(&dep.manifest)
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| root.to_path_buf());
+        let key = (dep.ecosystem.as_str(), dir);
+        if !audited.contains(&key) {
+            audited.push(key);
+        }
+    }
+    let mut notes: Vec<String> = Vec::new();
+    let mut vulnerabilities = Vec::new();
+    for (ecosystem, dir) in &audited {
+        let found = match *ecosystem {
+            "npm" => npm_advisories(dir),
+            _ => cargo_advisories(dir),
+        };
+        match found {
+            Ok(found) => vulnerabilities.extend(found),
+            Err(note) if !notes.contains(&note) => notes.push(note),
+            Err(_) => {}
+        }
+    }
+    vulnerabilities.retain(|vuln| {
+        new_dependencies
+            .iter()
+            .any(|dep| dep.ecosystem == vuln.ecosystem && dep.name == vuln.package)
+    });
+
+    let violations = policy_violations(policy, &new_dependencies, &vulnerabilities);
+    DepsAuditReport {
+        manifests,
+        new_dependencies,
+        vulnerabilities,
+        violations,
+        notes,
+    }
+}
+
+fn policy_violations(
+    policy: &DepsPolicy,
+    dependencies: &[NewDependency],
+    vulnerabilities: &[Vulnerability],
+) -> Vec<String> {
+    let mut violations = Vec::new();
+    for dep in dependencies {
+        if policy.denied_packages.iter().any(|name| name == &dep.name) {
+            violations.push(format!("{} is on the denied package list", dep.name));
+            continue;
+        }
+        let Some(license) = dep.license.as_deref() else {
+            if !policy.allow_unknown_licenses {
+                violations.push(format!("{} has an unknown license", dep.name));
+            }
+            continue;
+        };
+        let ids = license_ids(license);
+        if let Some(denied) = ids.iter().find(|id| {
+            policy
+                .denied_licenses
+                .iter()
+                .any(|denied| id.starts_with(denied.as_str()))
+        }) {
+            // "MIT OR GPL-3.0" is fine as long as one alternative is allowed.
+            let has_allowed_alternative =
+                license.contains(" OR ") && ids.iter().any(|id| license_allowed(policy, id));
+            if !has_allowed_alternative {
+                violations.push(format!("{} uses denied license {}", dep.name, denied));
+            }
+            continue;
+        }
+        if !policy.allowed_licenses.is_empty() && !ids.iter().any(|id| license_allowed(policy, id))
+        {
+            violations.push(format!("{} license {} is not allowed", dep.name, license));
+        }
+    }
+    if policy.block_vulnerabilities {
+        for vuln in vulnerabilities {
+            violations.push(format!(
+                "{} has known vulnerability {}: {}",
+                vuln.package, vuln.id, vuln.title
+            ));
+        }
+    }
+    violations
+}
+
+fn license_allowed(policy: &DepsPolicy, id: &str) -> bool {
+    policy
+        .allowed_licenses
+        .iter()
+        .any(|allowed| allowed.eq_ignore_ascii_case(id))
+}
+
+fn license_ids(expression: &str) -> Vec<String> {
+    expression
+        .split(|ch: char| ch == '/' || ch == '(' || ch == ')' || ch.is_whitespace())
+        .filter(|part| !part.is_empty() && !matches!(*part, "OR" | "AND" | "WITH"))
+        .map(|part| part.to_string())
+        .collect()
+}
+
+fn changed_manifests(root: &Path) -> Vec<String> {
+    let mut paths: Vec<String> = Vec::new();
+    let tracked = git_output(root, &["diff", "HEAD", "--name-only"]).unwrap_or_default();
+    let untracked =
+        git_output(root, &["ls-files", "--others", "--exclude-standard"]).unwrap_or_default();
+    for path in tracked.lines().chain(untracked.lines()) {
+        let path = path.trim();
+        let is_manifest = MANIFESTS
+            .iter()
+            .any(|name| path == *name || path.ends_with(&format!("/{}", name)));
+        if is_manifest && root.join(path).is_file() && !paths.iter().any(|seen| seen == path) {
+            paths.push(path.to_string());
+        }
+    }
+    paths
+}
+
+/// Reads `name = ...` entries from the dependency tables of a Cargo manifest,
+/// including `[dependencies.name]` and `[target.'...'.dependencies]` forms.
+fn cargo_dependencies(manifest: &str) -> BTreeMap<String, String> {
+    let mut deps = BTreeMap::new();
+    let mut in_table = false;
+    for line in manifest.lines() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.starts_with('[') {
+            let header = line.trim_matches(['[', ']']).trim();
+            in_table = false;
+            for section in CARGO_SECTIONS {
+                if let Some(name) = header
+                    .strip_prefix(&format!("{}.", section))
+                    .or_else(|| header.split_once(&format!(".{}.", section)).map(|(_, n)| n))
+                {
+                    deps.insert(name.trim_matches('"').to_string(), "*".to_string());
+                } else if header == *section || header.ends_with(&format!(".{}", section)) {
+                    in_table = true;
+                }
+            }
+            continue;
+        }
+        if !in_table {
+            continue;
+        }
+        if let Some((name, value)) = line.split_once('=') {
+            let name = name.trim().trim_matches('"');
+            if name.is_empty() {
+                continue;
+            }
+            deps.insert(name.to_string(), cargo_version(value.trim()));
+        }
+    }
+    deps
+}
+
+fn cargo_version(value: &str) -> String {
+    if value.starts_with('"') {
+        return value.trim_matches('"').to_string();
+    }
+    value
+        .split_once("version")
+        .and_then(|(_, rest)| rest.split('"').nth(1))
+        .unwrap_or("*")
+        .to_string()
+}
+
+fn npm_dependencies(manifest: &str) -> BTreeMap<String, String> {
+    let mut deps = BTreeMap::new();
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(manifest) else {
+        return deps;
+    };
+    for section in NPM_SECTIONS {
+        if let Some(entries) = value.get(*section).and_then(|entries| entries.as_object()) {
+            for (name, version) in entries {
+                deps.insert(name.clone(), version.as_str().unwrap_or("*").to_string());
+            }
+        }
+    }
+    deps
+}
+
+fn npm_license(manifest_dir: &Path, name: &str) -> Option<String> {
+    let path = manifest_dir
+        .join("node_modules")
+        .join(name)
+        .join("package.json");
+    let raw = std::fs::read_to_string(path).ok()?;
+    let value: serde_json::Value = serde_json::from_str(&raw).ok()?;
+    match value.get("license")? {
+        serde_json::Value::String(license) => Some(license.clone()),
+        other => other
+            .get("type")
+            .and_then(|license| license.as_str())
+            .map(|license| license.to_string()),
+    }
+}
+
+/// Looks the crate up in the local cargo registry cache and reads its `license` field.
+fn cargo_license(name: &str) -> Option<String> {
+    let cargo_home = std::env::var_os("CARGO_HOME")
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cargo")))
+        .or_else(|| {
+            std::env::var_os("USERPROFILE").map(|home| PathBuf::from(home).join(".cargo"))
+        })?;
+    let prefix = format!("{}-", name);
+    let mut candidates = Vec::new();
+    for registry in std::fs::read_dir(cargo_home.join("registry").join("src"))
+        .ok()?
+        .flatten()
+    {
+        let Ok(entries) = std::fs::read_dir(registry.path()) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let file_name = entry.file_name().to_string_lossy().to_string();
+            let version = file_name
+                .strip_prefix(&prefix)
+                .and_then(|rest| semver::Version::parse(rest).ok());
+            if let Some(version) = version {
+                candidates.push((version, entry.path()));
+            }
+        }
+    }
+    candidates.sort_by(|(left, _), (right, _)| left.cmp(right));
+    let (_, newest) = candidates.last()?;
+    let manifest = std::fs::read_to_string(newest.join("Cargo.toml")).ok()?;
+    manifest.lines().find_map(|line| {
+        let (key, value) = line.split_once('=')?;
+        (key.trim() == "license").then(|| value.trim().trim_matches('"').to_string())
+    })
+}
+
+fn cargo_advisories(root: &Path) -> Result<Vec<Vulnerability>, String> {
+    let output = command_output(root, "cargo", &["audit", "--json"])
+        .ok_or_else(|| "cargo-audit is not available; skipped advisory check".to_string())?;
+    let value: serde_json::Value = serde_json::from_str(&output)
+        .map_err(|_| "cargo-audit output could not be parsed".to_string())?;
+    let list = value
+        .pointer("/vulnerabilities/list")
+        .and_then(|list| list.as_array())
+        .cloned()
+        .unwrap_or_default();
+    Ok(list
+        .iter()
+        .filter_map(|entry| {
+            let advisory = entry.get("advisory")?;
+            Some(Vulnerability {
+                ecosystem: "cargo".to_string(),
+                package: advisory.get("package")?.as_str()?.to_string(),
+                id: advisory.get("id")?.as_str()?.to_string(),
+                title: advisory
+                    .get("title")
+                    .and_then(|title| title.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                severity: advisory
+                    .get("cvss")
+                    .and_then(|cvss| cvss.as_str())
+                    .map(|cvss| cvss.to_string()),
+            })
+        })
+        .collect())
+}
+
+fn npm_advisories(root: &Path) -> Result<Vec<Vulnerability>, String> {
+    let output = command_output(root, "npm", &["audit", "--json"])
+        .ok_or_else(|| "npm audit is not available; skipped advisory check".to_string())?;
+    let value: serde_json::Value = serde_json::from_str(&output)
+        .map_err(|_| "npm audit output could not be parsed".to_string())?;
+    let Some(entries) = value
+        .get("vulnerabilities")
+        .and_then(|entries| entries.as_object())
+    else {
+        return Ok(Vec::new());
+    };
+    Ok(entries
+        .iter()
+        .map(|(name, entry)| {
+            let via = entry
+                .get("via")
+                .and_then(|via| via.as_array())
+                .and_then(|via| via.iter().find(|item| item.is_object()));
+            Vulnerability {
+                ecosystem: "npm".to_string(),
+                package: name.clone(),
+                id: via
+                    .and_then(|item| item.get("url"))
+                    .and_then(|url| url.as_str())
+                    .unwrap_or("npm-advisory")
+                    .to_string(),
+                title: via
+                    .and_then(|item| item.get("title"))
+                    .and_then(|title| title.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+                severity: entry
+                    .get("severity")
+                    .and_then(|severity| severity.as_str())
+                    .map(|severity| severity.to_string()),
+            }
+        })
+        .collect())
+}
+
+/// Runs an advisory tool; these exit non-zero when they find issues, so only a
+/// missing binary or empty output counts as unavailable.
+fn command_output(root: &Path, program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program)
+        .args(args)
+        .current_dir(root)
+        .output()
+        .ok()?;
+    let stdout = String::from_utf8_lossy(&output.stdout).to_string();
+    (!stdout.trim().is_empty()).then_some(stdout)
+}
@@ -0,0 +1,265 @@
+use serde::{Deserialize, Serialize};
+
+const MAX_DIAGNOSTICS: usize = 50;
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Diagnostic {
+    pub source: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+    pub column: Option<u32>,
+    pub code: Option<String>,
+    pub severity: String,
+    pub message: String,
+}
+
+pub fn parse_diagnostics(output: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    diagnostics.extend(parse_rustc(output));
+    diagnostics.extend(parse_tsc(output));
+    diagnostics.extend(parse_eslint(output));
+    diagnostics.extend(parse_pytest(output));
+    diagnostics.truncate(MAX_DIAGNOSTICS);
+    diagnostics
+}
+
+pub fn format_diagnostics(diagnostics: &[Diagnostic], limit: usize) -> String {
+    let mut lines = Vec::new();
+    for diagnostic in diagnostics.iter().take(limit) {
+        let location = match (&diagnostic.file, diagnostic.line) {
+            (Some(file), Some(line)) => format!("{}:{}", file, line),
+            (Some(file), None) => file.clone(),
+            _ => "<unknown>".to_string(),
+        };
+        let code = diagnostic
+            .code
+            .as_ref()
+            .map(|code| format!(" [{}]", code))
+            .unwrap_or_default();
+        lines.push(format!(
+            "{} {}{}: {}",
+            location, diagnostic.severity, code, diagnostic.message
+        ));
+    }
+    if diagnostics.len() > limit {
+        lines.push(format!(
+            "... {} more diagnostics",
+            diagnostics.len() - limit
+        ));
+    }
+    lines.join("\n")
+}
+
+fn parse_rustc(output: &str) -> Vec<Diagnostic> {
+    let lines: Vec<&str> = output.lines().collect();
+    let mut diagnostics = Vec::new();
+    for (index, line) in lines.iter().enumerate() {
+        let (severity, rest) = if let Some(rest) = line.strip_prefix("error") {
+            ("error", rest)
+        } else if let Some(rest) = line.strip_prefix("warning") {
+            ("warning", rest)
+        } else {
+            continue;
+        };
+        let (code, message) = if let Some(stripped) = rest.strip_prefix('[') {
+            match stripped.split_once("]:") {
+                Some((code, message)) => (Some(code.to_string()), message),
+                None => continue,
+            }
+        } else if let Some(message) = rest.strip_prefix(':') {
+            (None, message)
+        } else {
+            continue;
+        };
+        let message = message.trim();
+        if message.starts_with("could not compile") || message.contains("generated ") {
+            continue;
+        }
+        let location = lines
+            .iter()
+            .skip(index + 1)
+            .take(3)
+            .find_map(|next| next.trim_start().strip_prefix("--> "));
+        let (file, line_no, column) = match location {
+            Some(location) => split_location(location.trim()),
+            None => (None, None, None),
+        };
+        diagnostics.push(Diagnostic {
+            source: "rustc".to_string(),
+            file,
+            line: line_no,
+            column,
+            code,
+            severity: severity.to_string(),
+            message: message.to_string(),
+        });
+    }
+    diagnostics
+}
+
+fn parse_tsc(output: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for line in output.lines() {
+        let trimmed = line.trim();
+        let (location, rest) = if let Some(idx) = trimmed.find("): error TS") {
+            (&trimmed[..=idx], &trimmed[idx + 3..])
+        } else if let Some(idx) = trimmed.find(" - error TS") {
+            (&trimmed[..idx], &trimmed[idx + 3..])
+        } else {
+            continue;
+        };
+        let rest = rest.trim_start_matches("error ").trim();
+        let (code, message) = match rest.split_once(':') {
+            Some((code, message)) => (Some(code.trim().to_string()), message.trim()),
+            None => (None, rest),
+        };
+        let (file, line_no, column) = if let Some(open) = location.rfind('(') {
+            let coords = location[open + 1..].trim_end_matches(')');
+            let mut parts = coords.split(',');
+            (
+                Some(location[..open].to_string()),
+                parts.next().and_then(|value| value.trim().parse().ok()),
+                parts.next().and_then(|value| value.trim().parse().ok()),
+            )
+        } else {
+            split_location(location)
+        };
+        diagnostics.push(Diagnostic {
+            source: "tsc".to_string(),
+            file,
+            line: line_no,
+            column,
+            code,
+            severity: "error".to_string(),
+            message: message.to_string(),
+        });
+    }
+    diagnostics
+}
+
+fn parse_eslint(output: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    let mut current_file: Option<String> = None;
+    for line in output.lines() {
+        if line.trim().is_empty() {
+            continue;
+        }
+        if !line.starts_with(' ') && !line.starts_with('\t') {
+            let candidate = line.trim();
+            current_file = if looks_like_path(candidate) {
+                Some(candidate.to_string())
+            } else {
+                None
+            };
+            continue;
+        }
+        let Some(file) = &current_file else {
+            continue;
+        };
+        let trimmed = line.trim();
+        let mut parts = trimmed.splitn(2, char::is_whitespace);
+        let position = parts.next().unwrap_or_default();
+        let rest = parts.next().unwrap_or_default().trim_start();
+        let (severity, rest) = if let Some(rest) = rest.strip_prefix("error") {
+            ("error", rest)
+        } else if let Some(rest) = rest.strip_prefix("warning") {
+            ("warning", rest)
+        } else {
+            continue;
+        };
+        let mut coords = position.split(':');
+        let line_no = coords.next().and_then(|value| value.parse().ok());
+        let column = coords.next().and_then(|value| value.parse().ok());
+        if line_no.is_none() {
+            continue;
+        }
+        let rest = rest.trim();
+        let (message, code) = match rest.rfind("  ") {
+            Some(idx) => (rest[..idx].trim(), Some(rest[idx..].trim().to_string())),
+            None => (rest, None),
+        };
+        diagnostics.push(Diagnostic {
+            source: "eslint".to_string(),
+            file: Some(file.clone()),
+            line: line_no,
+            column,
+            code,
+            severity: severity.to_string(),
+            message: message.to_string(),
+        });
+    }
+    diagnostics
+}
+
+fn parse_pytest(output: &str) -> Vec<Diagnostic> {
+    let mut diagnostics = Vec::new();
+    for line in output.lines() {
+        let trimmed = line.trim();
+        if let Some(rest) = trimmed.strip_prefix("FAILED ") {
+            let (target, message) = match rest.split_once(" - ") {
+                Some((target, message)) => (target, message),
+                None => (rest, "test failed"),
+            };
+            let file = target.split("::").next().map(|value| value.to_string());
+            diagnostics.push(Diagnostic {
+                source: "pytest".to_string(),
+                file,
+                line: None,
+                column: None,
+                code: Some(target.to_string()),
+                severity: "error".to_string(),
+                message: message.to_string(),
+            });
+            continue;
+        }
+        let mut parts = trimmed.splitn(3, ':');
+        let (Some(file), Some(line_no), Some(error)) = (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        if !file.ends_with(".py") {
+            continue;
+        }
+        let Ok(line_no) = line_no.parse::<u32>() else {
+            continue;
+        };
+        let error = error.trim();
+        if error.is_empty() || error.contains(' ') {
+            continue;
+        }
+        diagnostics.push(Diagnostic {
+            source: "pytest".to_string(),
+            file: Some(file.to_string()),
+            line: Some(line_no),
+            column: None,
+            code: Some(error.to_string()),
+            severity: "error".to_string(),
+            message: error.to_string(),
+        });
+    }
+    diagnostics
+}
+
+fn split_location(location: &str) -> (Option<String>, Option<u32>, Option<u32>) {
+    let mut parts = location.rsplitn(3, ':');
+    let last = parts.next();
+    let middle = parts.next();
+    let first = parts.next();
+    match (first, middle, last) {
+        (Some(file), Some(line), Some(column)) => (
+            Some(file.to_string()),
+            line.parse().ok(),
+            column.parse().ok(),
+        ),
+        (None, Some(file), Some(line)) => (Some(file.to_string()), line.parse().ok(), None),
+        _ => (Some(location.to_string()), None, None),
+    }
+}
+
+fn looks_like_path(value: &str) -> bool {
+    let lower = value.to_lowercase();
+    [".js", ".jsx", ".ts", ".tsx", ".vue", ".mjs", ".cjs"]
+        .iter()
+        .any(|ext| lower.ends_with(ext))
+}
@@ -0,0 +1,283 @@
+use serde::{Deserialize, Serialize};
+
+const MAX_DIFF_CELLS: usize = 4_000_000;
+const CONTEXT_LINES: usize = 3;
+const MAX_SYMBOL_LOOKBACK: usize = 200;
+const MAX_SYMBOLS: usize = 8;
+const DEFINITION_KEYWORDS: &[&str] = &[
+    "fn",
+    "def",
+    "function",
+    "func",
+    "class",
+    "struct",
+    "enum",
+    "trait",
+    "impl",
+    "interface",
+    "mod",
+    "module",
+    "namespace",
+];
+const DEFINITION_MODIFIERS: &[&str] = &[
+    "pub",
+    "pub(crate)",
+    "pub(super)",
+    "async",
+    "unsafe",
+    "export",
+    "default",
+    "static",
+    "public",
+    "private",
+    "protected",
+    "abstract",
+    "final",
+    "override",
+];
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangeSummary {
+    pub path: String,
+    pub created: bool,
+    pub lines_added: usize,
+    pub lines_removed: usize,
+    pub hunks: usize,
+    pub symbols: Vec<String>,
+}
+
+impl ChangeSummary {
+    pub fn describe(&self) -> String {
+        let verb = if self.created { "Created" } else { "Updated" };
+        let mut text = format!(
+            "{} {}: +{} -{} lines in {} hunk(s)",
+            verb, self.path, self.lines_added, self.lines_removed, self.hunks
+        );
+        if !self.symbols.is_empty() {
+            text.push_str(&format!("; touched {}", self.symbols.join(", ")));
+        }
+        text
+    }
+}
+
+enum DiffOp<'a> {
+    Equal(&'a str),
+    Delete(&'a str),
+    Insert(&'a str),
+}
+
+pub fn unified_diff(path: &str, old: &str, new: &str) -> String {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let mut output = format!("--- a/{}\n+++ b/{}\n", path, path);
+    if old == new {
+        return output;
+    }
+    let ops = compute_ops(&old_lines, &new_lines);
+    let changed: Vec<usize> = ops
+        .iter()
+        .enumerate()
+        .filter(|(_, op)| !matches!(op, DiffOp::Equal(_)))
+        .map(|(index, _)| index)
+        .collect();
+    let mut hunks: Vec<(usize, usize)> = Vec::new();
+    for index in changed {
+        let start = index.saturating_sub(CONTEXT_LINES);
+        let end = (index + CONTEXT_LINES + 1).min(ops.len());
+        match hunks.last_mut() {
+            Some(last) if start <= last.1 => last.1 = end,
+            _ => hunks.push((start, end)),
+        }
+    }
+    for (start, end) in hunks {
+        let mut old_start = 1;
+        let mut new_start = 1;
+        for op in &ops[..start] {
+            match op {
+                DiffOp::Equal(_) => {
+                    old_start += 1;
+                    new_start += 1;
+                }
+                DiffOp::Delete(_) => old_start += 1,
+                DiffOp::Insert(_) => new_start += 1,
+            }
+        }
+        let mut old_count = 0;
+        let mut new_count = 0;
+        let mut body = String::new();
+        for op in &ops[start..end] {
+            match op {
+                DiffOp::Equal(line) => {
+                    old_count += 1;
+                    new_count += 1;
+                    body.push_str(&format!(" {}\n", line));
+                }
+                DiffOp::Delete(line) => {
+                    old_count += 1;
+                    body.push_str(&format!("-{}\n", line));
+                }
+                DiffOp::Insert(line) => {
+                    new_count += 1;
+                    body.push_str(&format!("+{}\n", line));
+                }
+            }
+        }
+        output.push_str(&format!(
+            "@@ -{},{} +{},{} @@\n",
+            old_start, old_count, new_start, new_count
+        ));
+        output.push_str(&body);
+    }
+    output
+}
+
+/// Summarises an edit as line counts plus the enclosing definitions that changed.
+/// For each line of `new`, the index of the unchanged `old` line it came from,
+/// or `None` when the line was inserted or edited.
+pub fn line_origins(old: &str, new: &str) -> Vec<Option<usize>> {
+    let old_lines: Vec<&str> = old.lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let mut origins = Vec::with_capacity(new_lines.len());
+    let mut old_index = 0;
+    for op in compute_ops(&old_lines, &new_lines) {
+        match op {
+            DiffOp::Equal(_) => {
+                origins.push(Some(old_index));
+                old_index += 1;
+            }
+            DiffOp::Delete(_) => old_index += 1,
+            DiffOp::Insert(_) => origins.push(None),
+        }
+    }
+    origins
+}
+
+pub fn change_summary(path: &str, old: Option<&str>, new: &str) -> ChangeSummary {
+    let old_lines: Vec<&str> = old.unwrap_or_default().lines().collect();
+    let new_lines: Vec<&str> = new.lines().collect();
+    let ops = compute_ops(&old_lines, &new_lines);
+    let mut summary = ChangeSummary {
+        path: path.to_string(),
+        created: old.is_none(),
+        lines_added: 0,
+        lines_removed: 0,
+        hunks: 0,
+        symbols: Vec::new(),
+    };
+    let (mut old_index, mut new_index) = (0usize, 0usize);
+    let mut in_hunk = false;
+    for op in &ops {
+        let symbol = match op {
+            DiffOp::Equal(_) => {
+                old_index += 1;
+                new_index += 1;
+                in_hunk = false;
+                continue;
+            }
+            DiffOp::Delete(_) => {
+                summary.lines_removed += 1;
+                old_index += 1;
+                enclosing_definition(&old_lines[..old_index])
+            }
+            DiffOp::Insert(_) => {
+                summary.lines_added += 1;
+                new_index += 1;
+                enclosing_definition(&new_lines[..new_index])
+            }
+        };
+        if !in_hunk {
+            summary.hunks += 1;
+            in_hunk = true;
+        }
+        if let Some(symbol) = symbol {
+            if summary.symbols.len() < MAX_SYMBOLS && !summary.symbols.contains(&symbol) {
+                summary.symbols.push(symbol);
+            }
+        }
+    }
+    summary
+}
+
+fn enclosing_definition(lines: &[&str]) -> Option<String> {
+    lines
+        .iter()
+        .rev()
+        .take(MAX_SYMBOL_LOOKBACK)
+        .find_map(|line| definition_name(line))
+}
+
+fn definition_name(line: &str) -> Option<String> {
+    let mut words = line.split_whitespace().peekable();
+    while words
+        .peek()
+        .map(|word| DEFINITION_MODIFIERS.contains(word))
+        .unwrap_or(false)
+    {
+        words.next();
+    }
+    let keyword = words.next()?;
+    if !DEFINITION_KEYWORDS.contains(&keyword) {
+        return None;
+    }
+    let rest: Vec<&str> = words.collect();
+    let rest = rest.join(" ");
+    let name: String = if keyword == "impl" {
+        rest.split('{')
+            .next()
+            .unwrap_or_default()
+            .trim()
+            .to_string()
+    } else {
+        rest.chars()
+            .take_while(|ch| ch.is_alphanumeric() || matches!(ch, '_' | '$' | '.'))
+            .collect()
+    };
+    if name.is_empty() {
+        return None;
+    }
+    Some(format!("{} {}", keyword, name))
+}
+
+fn compute_ops<'a>(old_lines: &[&'a str], new_lines: &[&'a str]) -> Vec<DiffOp<'a>> {
+    if old_lines.len().saturating_mul(new_lines.len()) > MAX_DIFF_CELLS {
+        let mut ops: Vec<DiffOp> = old_lines.iter().map(|line| DiffOp::Delete(line)).collect();
+        ops.extend(new_lines.iter().map(|line| DiffOp::Insert(line)));
+        ops
+    } else {
+        diff_lines(old_lines, new_lines)
+    }
+}
+
+fn diff_lines<'a>(old: &[&'a str], new: &[&'a str]) -> Vec<DiffOp<'a>> {
+    let rows = old.len() + 1;
+    let cols = new.len() + 1;
+    let mut table = vec![0u32; rows * cols];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            table[i * cols + j] = if old[i] == new[j] {
+                table[(i + 1) * cols + j + 1] + 1
+            } else {
+                table[(i + 1) * cols + j].max(table[i * cols + j + 1])
+            };
+        }
+    }
+    let mut ops = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < old.len() && j < new.len() {
+        if old[i] == new[j] {
+            ops.push(DiffOp::Equal(old[i]));
+            i += 1;
+            j += 1;
+        } else if table[(i + 1) * cols + j] >= table[i * cols + j + 1] {
+            ops.push(DiffOp::Delete(old[i]));
+            i += 1;
+        } else {
+            ops.push(DiffOp::Insert(new[j]));
+            j += 1;
+        }
+    }
+    ops.extend(old[i..].iter().map(|line| DiffOp::Delete(line)));
+    ops.extend(new[j..].iter().map(|line| DiffOp::Insert(line)));
+    ops
+}
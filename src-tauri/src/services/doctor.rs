@@ -0,0 +1,225 @@
+use portable_pty::{native_pty_system, PtySize};
+use serde::Serialize;
+use std::path::Path;
+use std::process::Command;
+
+use crate::services::llm::{
+    default_tls_backend, probe_profile, probe_tls, resolve_base_url, LlmProfile,
+};
+
+const TLS_FALLBACK_URL: &str = "https://api.openai.com/v1";
+
+#[derive(Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CheckStatus {
+    Ok,
+    Warn,
+    Fail,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DoctorCheck {
+    pub name: String,
+    pub status: CheckStatus,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+impl DoctorCheck {
+    pub(crate) fn ok(name: &str, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            status: CheckStatus::Ok,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    pub(crate) fn problem(
+        name: &str,
+        status: CheckStatus,
+        detail: impl Into<String>,
+        fix: impl Into<String>,
+    ) -> Self {
+        Self {
+            name: name.to_string(),
+            status,
+            detail: detail.into(),
+            fix: Some(fix.into()),
+        }
+    }
+}
+
+/// Checks the prerequisites that otherwise surface as errors halfway through
+/// a run: external tools, PTY support, a writable data dir and a reachable LLM.
+pub async fn run_doctor(data_dir: &Path, profile: Option<&LlmProfile>) -> Vec<DoctorCheck> {
+    let mut checks = vec![
+        check_ripgrep(),
+        check_git(),
+        check_pty(),
+        check_data_dir(data_dir),
+    ];
+    checks.push(check_llm(profile).await);
+    let tls_url = profile
+        .map(resolve_base_url)
+        .filter(|url| url.starts_with("https://"))
+        .unwrap_or_else(|| TLS_FALLBACK_URL.to_string());
+    checks.push(check_tls(&tls_url).await);
+    checks
+}
+
+pub(crate) fn check_ripgrep() -> DoctorCheck {
+    check_tool(
+        "ripgrep",
+        "rg",
+        "Install ripgrep (https://github.com/BurntSushi/ripgrep#installation) \
+         and make sure `rg` is on PATH; file search depends on it.",
+    )
+}
+
+pub(crate) fn check_git() -> DoctorCheck {
+    check_tool(
+        "git",
+        "git",
+        "Install git and make sure it is on PATH; diffs, status and \
+         change tracking depend on it.",
+    )
+}
+
+pub(crate) fn check_tool(name: &str, binary: &str, fix: &str) -> DoctorCheck {
+    match Command::new(binary).arg("--version").output() {
+        Ok(output) if output.status.success() => {
+            let version = String::from_utf8_lossy(&output.stdout);
+            DoctorCheck::ok(name, version.lines().next().unwrap_or_default().trim())
+        }
+        Ok(output) => DoctorCheck::problem(
+            name,
+            CheckStatus::Fail,
+            format!("`{} --version` exited with {}", binary, output.status),
+            fix,
+        ),
+        Err(err) => DoctorCheck::problem(
+            name,
+            CheckStatus::Fail,
+            format!("`{}` could not be started: {}", binary, err),
+            fix,
+        ),
+    }
+}
+
+fn check_pty() -> DoctorCheck {
+    let size = PtySize {
+        rows: 24,
+        cols: 80,
+        pixel_width: 0,
+        pixel_height: 0,
+    };
+    match native_pty_system().openpty(size) {
+        Ok(_) => DoctorCheck::ok("pty", "pseudo-terminal opened"),
+        Err(err) => DoctorCheck::problem(
+            "pty",
+            CheckStatus::Fail,
+            format!("could not open a pseudo-terminal: {}", err),
+            if cfg!(windows) {
+                "Terminal sessions need ConPTY (Windows 10 1809 or later)."
+            } else {
+                "Make sure /dev/ptmx is accessible; in containers mount devpts \
+                 (e.g. run docker with the default /dev/pts)."
+            },
+        ),
+    }
+}
+
+fn check_data_dir(data_dir: &Path) -> DoctorCheck {
+    let probe = data_dir.join(".doctor-probe");
+    let result = std::fs::create_dir_all(data_dir)
+        .and_then(|_| std::fs::write(&probe, b"ok"))
+        .and_then(|_| std::fs::remove_file(&probe));
+    match result {
+        Ok(()) => DoctorCheck::ok("data_dir", format!("{} is writable", data_dir.display())),
+        Err(err) => DoctorCheck::problem(
+            "data_dir",
+            CheckStatus::Fail,
+            format!("cannot write to {}: {}", data_dir.display(), err),
+            "Fix the directory's ownership or permissions, or point XDG_DATA_HOME \
+             (APPDATA on Windows) at a writable location.",
+        ),
+    }
+}
+
+pub(crate) async fn check_llm(profile: Option<&LlmProfile>) -> DoctorCheck {
+    let Some(profile) = profile else {
+        return DoctorCheck::problem(
+            "llm_profile",
+            CheckStatus::Warn,
+            "no active LLM profile",
+            "Create a profile in Settings > LLM before starting a run.",
+        );
+    };
+    let name = format!("{} ({})", profile.provider, profile.model);
+    match probe_profile(profile).await {
+        Ok(status) if status == 401 || status == 403 => DoctorCheck::problem(
+            "llm_profile",
+            CheckStatus::Fail,
+            format!("{} rejected the API key (HTTP {})", name, status),
+            "Check the API key in the active profile and that it has access to this model.",
+        ),
+        Ok(status) if status >= 500 => DoctorCheck::problem(
+            "llm_profile",
+            CheckStatus::Warn,
+            format!("{} is reachable but returned HTTP {}", name, status),
+            "The provider reported a server error; retry later or switch profiles.",
+        ),
+        Ok(status) => DoctorCheck::ok(
+            "llm_profile",
+            format!("{} reachable (HTTP {})", name, status),
+        ),
+        Err(err) => DoctorCheck::problem(
+            "llm_profile",
+            CheckStatus::Fail,
+            format!("{} unreachable: {}", name, err),
+            "Check the base URL, that the server is running, and HTTPS_PROXY if a \
+             proxy is required.",
+        ),
+    }
+}
+
+async fn check_tls(url: &str) -> DoctorCheck {
+    let (rustls, native) = probe_tls(url).await;
+    let default = default_tls_backend();
+    let (default_result, other_result) = if default == "rustls" {
+        (rustls, native)
+    } else {
+        (native, rustls)
+    };
+    match (default_result, other_result) {
+        (Ok(()), Ok(())) => DoctorCheck::ok("tls", format!("rustls and native-tls reach {}", url)),
+        (Err(err), Err(_)) => DoctorCheck::problem(
+            "tls",
+            CheckStatus::Warn,
+            format!("neither TLS stack reached {}: {}", url, err),
+            "Check network access and HTTPS_PROXY; the endpoint may simply be offline.",
+        ),
+        (Err(err), Ok(())) => DoctorCheck::problem(
+            "tls",
+            CheckStatus::Fail,
+            format!(
+                "{} (used here) fails for {} while the other stack works: {}",
+                default, url, err
+            ),
+            "A proxy is likely re-signing HTTPS with a private CA. Use an endpoint \
+             with a public certificate, or bypass the proxy for this host via NO_PROXY.",
+        ),
+        (Ok(()), Err(err)) => DoctorCheck::problem(
+            "tls",
+            CheckStatus::Warn,
+            format!(
+                "{} (used here) works for {}; the other stack fails: {}",
+                default, url, err
+            ),
+            "No action needed on this machine; builds for other platforms may fail \
+             against this endpoint.",
+        ),
+    }
+}
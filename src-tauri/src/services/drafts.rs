@@ -0,0 +1,74 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::services::audit::now_ms;
+
+const MAX_DRAFTS: usize = 50;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Draft {
+    pub run_id: String,
+    pub content: String,
+    pub updated_at: u128,
+}
+
+#[derive(Deserialize)]
+pub struct DraftSaveRequest {
+    pub run_id: String,
+    pub content: String,
+}
+
+pub fn drafts_path(root: &Path) -> PathBuf {
+    root.join(".taurihands").join("drafts.json")
+}
+
+pub fn load_draft(root: &Path, run_id: &str) -> Option<Draft> {
+    load_drafts(root).remove(run_id)
+}
+
+/// Stores the unsent input for a run; an empty draft removes the entry.
+pub fn save_draft(root: &Path, run_id: &str, content: &str) -> Result<Option<Draft>, String> {
+    let run_id = run_id.trim();
+    if run_id.is_empty() {
+        return Err("run_id is required".to_string());
+    }
+    let mut drafts = load_drafts(root);
+    let draft = if content.trim().is_empty() {
+        drafts.remove(run_id);
+        None
+    } else {
+        let draft = Draft {
+            run_id: run_id.to_string(),
+            content: content.to_string(),
+            updated_at: now_ms(),
+        };
+        drafts.insert(run_id.to_string(), draft.clone());
+        Some(draft)
+    };
+    if drafts.len() > MAX_DRAFTS {
+        let mut by_age: Vec<(u128, String)> = drafts
+            .values()
+            .map(|draft| (draft.updated_at, draft.run_id.clone()))
+            .collect();
+        by_age.sort();
+        for (_, id) in by_age.into_iter().take(drafts.len() - MAX_DRAFTS) {
+            drafts.remove(&id);
+        }
+    }
+    let path = drafts_path(root);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_vec_pretty(&drafts).map_err(|e| e.to_string())?;
+    std::fs::write(path, data).map_err(|e| e.to_string())?;
+    Ok(draft)
+}
+
+fn load_drafts(root: &Path) -> HashMap<String, Draft> {
+    std::fs::read_to_string(drafts_path(root))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
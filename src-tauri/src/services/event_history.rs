@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+
+use crate::services::kernel::KernelEvent;
+
+const DEFAULT_PAGE_SIZE: usize = 200;
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventPage {
+    pub run_id: String,
+    /// Events in the whole log, for sizing the timeline scrollbar.
+    pub total: usize,
+    pub offset: usize,
+    pub events: Vec<KernelEvent>,
+}
+
+/// Filters for `kernel_search_events`; every field that is set must match.
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct EventSearchQuery {
+    /// Searches every run when unset.
+    pub run_id: Option<String>,
+    /// Case-insensitive substring of the event type or payload.
+    pub text: Option<String>,
+    /// Event types such as `ToolCallStarted` or `ToolCallFinished`.
+    pub types: Vec<String>,
+    /// Inclusive bounds on the event time, in epoch milliseconds.
+    pub since: Option<u128>,
+    pub until: Option<u128>,
+    pub offset: Option<usize>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct EventSearchResult {
+    /// Matches before `offset` and `limit` were applied.
+    pub total: usize,
+    pub events: Vec<KernelEvent>,
+}
+
+/// Slices one run's log, which is already ordered by `seq`.
+pub fn page_events(
+    run_id: String,
+    events: Vec<KernelEvent>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> EventPage {
+    let total = events.len();
+    let offset = offset.unwrap_or(0).min(total);
+    let events = events
+        .into_iter()
+        .skip(offset)
+        .take(limit.unwrap_or(DEFAULT_PAGE_SIZE))
+        .collect();
+    EventPage {
+        run_id,
+        total,
+        offset,
+        events,
+    }
+}
+
+/// Matches events of several runs against `query`, newest first.
+pub fn search_events(
+    logs: impl IntoIterator<Item = Vec<KernelEvent>>,
+    query: &EventSearchQuery,
+) -> EventSearchResult {
+    let text = query
+        .text
+        .as_deref()
+        .map(|text| text.trim().to_lowercase())
+        .filter(|text| !text.is_empty());
+    let mut matches: Vec<KernelEvent> = logs
+        .into_iter()
+        .flatten()
+        .filter(|event| {
+            if !query.types.is_empty() && !query.types.contains(&event.event_type) {
+                return false;
+            }
+            if query.since.is_some_and(|since| event.ts < since)
+                || query.until.is_some_and(|until| event.ts > until)
+            {
+                return false;
+            }
+            match text.as_deref() {
+                Some(text) => {
+                    event.event_type.to_lowercase().contains(text)
+                        || event.payload.to_string().to_lowercase().contains(text)
+                }
+                None => true,
+            }
+        })
+        .collect();
+    matches.sort_by(|left, right| right.ts.cmp(&left.ts).then(right.seq.cmp(&left.seq)));
+    let total = matches.len();
+    let events = matches
+        .into_iter()
+        .skip(query.offset.unwrap_or(0))
+        .take(query.limit.unwrap_or(DEFAULT_PAGE_SIZE))
+        .collect();
+    EventSearchResult { total, events }
+}
@@ -0,0 +1,201 @@
+use serde::Serialize;
+use std::sync::Arc;
+use tauri::ipc::{Channel, InvokeResponseBody};
+use tauri::{AppHandle, Emitter};
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+
+/// Destination for events produced by the managers. The desktop app forwards
+/// them to the webview; the TUI and server modes consume them from a channel.
+pub trait EventSink: Send + Sync {
+    fn emit_json(&self, event: &str, payload: serde_json::Value);
+
+    /// Returns an owned handle so background tasks can keep emitting.
+    fn clone_sink(&self) -> SharedEventSink;
+
+    /// A channel for raw frames next to the JSON events, for sinks that have
+    /// one. High-frequency output uses it to skip base64 and JSON.
+    fn binary_sink(&self) -> Option<SharedBinarySink> {
+        None
+    }
+}
+
+pub type SharedEventSink = Arc<dyn EventSink>;
+
+/// Destination for raw frames; an error means the consumer is gone.
+pub trait BinarySink: Send + Sync {
+    fn send_frame(&self, frame: Vec<u8>) -> Result<(), String>;
+}
+
+pub type SharedBinarySink = Arc<dyn BinarySink>;
+
+/// A channel the webview opened for one consumer; frames arrive there as an
+/// `ArrayBuffer`.
+impl BinarySink for Channel<InvokeResponseBody> {
+    fn send_frame(&self, frame: Vec<u8>) -> Result<(), String> {
+        self.send(InvokeResponseBody::Raw(frame))
+            .map_err(|e| e.to_string())
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct SinkEvent {
+    pub event: String,
+    pub payload: serde_json::Value,
+}
+
+impl EventSink for AppHandle {
+    fn emit_json(&self, event: &str, payload: serde_json::Value) {
+        let _ = self.emit(event, payload);
+    }
+
+    fn clone_sink(&self) -> SharedEventSink {
+        Arc::new(self.clone())
+    }
+}
+
+impl EventSink for SharedEventSink {
+    fn emit_json(&self, event: &str, payload: serde_json::Value) {
+        self.as_ref().emit_json(event, payload);
+    }
+
+    fn clone_sink(&self) -> SharedEventSink {
+        self.clone()
+    }
+
+    fn binary_sink(&self) -> Option<SharedBinarySink> {
+        self.as_ref().binary_sink()
+    }
+}
+
+/// Forwards events to a single in-process consumer such as the TUI.
+#[derive(Clone)]
+pub struct ChannelEventSink {
+    sender: UnboundedSender<SinkEvent>,
+}
+
+impl ChannelEventSink {
+    pub fn new() -> (Self, UnboundedReceiver<SinkEvent>) {
+        let (sender, receiver) = unbounded_channel();
+        (Self { sender }, receiver)
+    }
+}
+
+impl EventSink for ChannelEventSink {
+    fn emit_json(&self, event: &str, payload: serde_json::Value) {
+        let _ = self.sender.send(SinkEvent {
+            event: event.to_string(),
+            payload,
+        });
+    }
+
+    fn clone_sink(&self) -> SharedEventSink {
+        Arc::new(self.clone())
+    }
+}
+
+/// Writes every event as one JSON line to stdout, for headless runs.
+#[cfg(feature = "cli")]
+#[derive(Clone, Default)]
+pub struct JsonlEventSink;
+
+#[cfg(feature = "cli")]
+impl EventSink for JsonlEventSink {
+    fn emit_json(&self, event: &str, payload: serde_json::Value) {
+        let message = SinkEvent {
+            event: event.to_string(),
+            payload,
+        };
+        if let Ok(line) = serde_json::to_string(&message) {
+            use std::io::Write;
+            let mut stdout = std::io::stdout().lock();
+            let _ = writeln!(stdout, "{}", line);
+            let _ = stdout.flush();
+        }
+    }
+
+    fn clone_sink(&self) -> SharedEventSink {
+        Arc::new(self.clone())
+    }
+}
+
+#[cfg(feature = "cli")]
+pub use websocket::WebSocketEventSink;
+
+#[cfg(feature = "cli")]
+mod websocket {
+    use futures_util::SinkExt;
+    use std::sync::Arc;
+    use tokio::net::{TcpListener, TcpStream};
+    use tokio::sync::broadcast::{self, error::RecvError};
+    use tokio_tungstenite::{accept_async, tungstenite::Message};
+
+    use super::{BinarySink, EventSink, SharedBinarySink, SharedEventSink, SinkEvent};
+
+    const WEBSOCKET_BUFFER: usize = 1024;
+
+    /// Broadcasts every event as a JSON text frame to all connected WebSocket
+    /// clients, and binary frames as binary messages.
+    #[derive(Clone)]
+    pub struct WebSocketEventSink {
+        sender: broadcast::Sender<Message>,
+    }
+
+    impl WebSocketEventSink {
+        pub async fn bind(addr: &str) -> Result<Self, String> {
+            let listener = TcpListener::bind(addr).await.map_err(|e| e.to_string())?;
+            let (sender, _) = broadcast::channel(WEBSOCKET_BUFFER);
+            let accept_sender = sender.clone();
+            tokio::spawn(async move {
+                while let Ok((stream, _)) = listener.accept().await {
+                    tokio::spawn(forward_events(stream, accept_sender.subscribe()));
+                }
+            });
+            Ok(Self { sender })
+        }
+    }
+
+    impl EventSink for WebSocketEventSink {
+        fn emit_json(&self, event: &str, payload: serde_json::Value) {
+            let message = SinkEvent {
+                event: event.to_string(),
+                payload,
+            };
+            if let Ok(text) = serde_json::to_string(&message) {
+                let _ = self.sender.send(Message::Text(text));
+            }
+        }
+
+        fn clone_sink(&self) -> SharedEventSink {
+            Arc::new(self.clone())
+        }
+
+        fn binary_sink(&self) -> Option<SharedBinarySink> {
+            Some(Arc::new(self.clone()))
+        }
+    }
+
+    impl BinarySink for WebSocketEventSink {
+        /// Having no clients connected is not an error; the sink outlives them.
+        fn send_frame(&self, frame: Vec<u8>) -> Result<(), String> {
+            let _ = self.sender.send(Message::Binary(frame));
+            Ok(())
+        }
+    }
+
+    async fn forward_events(stream: TcpStream, mut receiver: broadcast::Receiver<Message>) {
+        let Ok(mut socket) = accept_async(stream).await else {
+            return;
+        };
+        loop {
+            match receiver.recv().await {
+                Ok(message) => {
+                    if socket.send(message).await.is_err() {
+                        break;
+                    }
+                }
+                Err(RecvError::Lagged(_)) => continue,
+                Err(RecvError::Closed) => break,
+            }
+        }
+    }
+}
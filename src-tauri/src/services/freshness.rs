@@ -0,0 +1,46 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// When a run resumed from AwaitingUser re-checks the workspace before its
+/// next decision.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct FreshnessPolicy {
+    /// How long a run may wait for the user before its context is refreshed;
+    /// 0 turns the refresh off.
+    pub stale_after_minutes: u64,
+}
+
+impl Default for FreshnessPolicy {
+    fn default() -> Self {
+        Self {
+            stale_after_minutes: 24 * 60,
+        }
+    }
+}
+
+impl FreshnessPolicy {
+    pub fn is_stale(&self, waited_ms: u128) -> bool {
+        self.stale_after_minutes > 0 && waited_ms >= self.stale_after_minutes as u128 * 60_000
+    }
+}
+
+pub fn freshness_policy_path(root: &Path) -> PathBuf {
+    root.join(".taurihands").join("freshness.json")
+}
+
+pub fn load_freshness_policy(root: &Path) -> FreshnessPolicy {
+    std::fs::read_to_string(freshness_policy_path(root))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_freshness_policy(root: &Path, policy: &FreshnessPolicy) -> Result<(), String> {
+    let path = freshness_policy_path(root);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_vec_pretty(policy).map_err(|e| e.to_string())?;
+    std::fs::write(path, data).map_err(|e| e.to_string())
+}
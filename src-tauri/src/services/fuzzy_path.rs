@@ -0,0 +1,204 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::services::ignore::IgnoreRules;
+use crate::services::visibility::{load_visibility_policy, HiddenMode};
+use crate::services::workspace::edit_distance;
+
+const MAX_INDEXED_FILES: usize = 50_000;
+const MAX_CANDIDATES: usize = 5;
+
+/// Controls when paths the model gets wrong are replaced by a fuzzy match.
+/// Reads auto-pick unambiguous matches by default; writes only when enabled,
+/// since a write to a missing path may be meant to create a new file.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PathPolicy {
+    #[serde(default = "default_true")]
+    pub fuzzy_reads: bool,
+    #[serde(default)]
+    pub fuzzy_writes: bool,
+}
+
+impl Default for PathPolicy {
+    fn default() -> Self {
+        Self {
+            fuzzy_reads: true,
+            fuzzy_writes: false,
+        }
+    }
+}
+
+fn default_true() -> bool {
+    true
+}
+
+pub fn path_policy_path(root: &Path) -> PathBuf {
+    root.join(".taurihands").join("paths.json")
+}
+
+pub fn load_path_policy(root: &Path) -> PathPolicy {
+    std::fs::read_to_string(path_policy_path(root))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_path_policy(root: &Path, policy: &PathPolicy) -> Result<(), String> {
+    let path = path_policy_path(root);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_vec_pretty(policy).map_err(|e| e.to_string())?;
+    std::fs::write(path, data).map_err(|e| e.to_string())
+}
+
+pub enum FuzzyMatch {
+    /// A single best match, relative to the workspace root.
+    Unique(String),
+    /// Ranked candidates, best first, when none is clearly right.
+    Ambiguous(Vec<String>),
+    None,
+}
+
+impl FuzzyMatch {
+    pub fn candidates(&self) -> Vec<String> {
+        match self {
+            FuzzyMatch::Unique(path) => vec![path.clone()],
+            FuzzyMatch::Ambiguous(paths) => paths.clone(),
+            FuzzyMatch::None => Vec::new(),
+        }
+    }
+}
+
+/// Looks up workspace files by basename and ranks them by how many directory
+/// names they share with the requested path, e.g. `src/kernel.rs` finds
+/// `src-tauri/src/services/kernel.rs`. Files with a similar name are offered as
+/// candidates but never picked automatically.
+pub fn resolve_fuzzy(root: &Path, input: &str) -> FuzzyMatch {
+    let requested: Vec<String> = input
+        .trim()
+        .replace('\\', "/")
+        .split('/')
+        .filter(|part| !part.is_empty() && *part != ".")
+        .map(|part| part.to_lowercase())
+        .collect();
+    let Some(basename) = requested.last().cloned() else {
+        return FuzzyMatch::None;
+    };
+    let index = build_basename_index(root);
+    if let Some(paths) = index.get(&basename) {
+        let mut ranked: Vec<(usize, &String)> = paths
+            .iter()
+            .map(|path| (shared_dirs(&requested, path), path))
+            .collect();
+        ranked.sort_by(|left, right| right.0.cmp(&left.0).then(left.1.len().cmp(&right.1.len())));
+        let unambiguous = match ranked.as_slice() {
+            [_] => true,
+            [first, second, ..] => first.0 > second.0,
+            [] => false,
+        };
+        if unambiguous {
+            return FuzzyMatch::Unique(ranked[0].1.clone());
+        }
+        return FuzzyMatch::Ambiguous(
+            ranked
+                .into_iter()
+                .take(MAX_CANDIDATES)
+                .map(|(_, path)| path.clone())
+                .collect(),
+        );
+    }
+    let stem = basename.split('.').next().unwrap_or_default().to_string();
+    let mut similar: Vec<(usize, &String)> = index
+        .iter()
+        .filter_map(|(name, paths)| {
+            let name_stem = name.split('.').next().unwrap_or_default();
+            let distance = if name_stem == stem {
+                0
+            } else {
+                let distance = edit_distance(&basename, name);
+                if distance > (basename.len() / 4).max(1) {
+                    return None;
+                }
+                distance
+            };
+            Some(paths.iter().map(move |path| (distance, path)))
+        })
+        .flatten()
+        .collect();
+    if similar.is_empty() {
+        return FuzzyMatch::None;
+    }
+    similar.sort_by(|left, right| {
+        left.0
+            .cmp(&right.0)
+            .then(shared_dirs(&requested, right.1).cmp(&shared_dirs(&requested, left.1)))
+            .then(left.1.cmp(right.1))
+    });
+    FuzzyMatch::Ambiguous(
+        similar
+            .into_iter()
+            .take(MAX_CANDIDATES)
+            .map(|(_, path)| path.clone())
+            .collect(),
+    )
+}
+
+/// Maps lowercase file names to workspace-relative paths. Hidden entries are
+/// left out unless the visibility policy lists them.
+fn build_basename_index(root: &Path) -> HashMap<String, Vec<String>> {
+    let rules = IgnoreRules::for_workspace(root);
+    let show_hidden = load_visibility_policy(root).hidden == HiddenMode::Show;
+    let mut index: HashMap<String, Vec<String>> = HashMap::new();
+    let mut indexed = 0;
+    let mut queue = vec![root.to_path_buf()];
+    while let Some(dir) = queue.pop() {
+        let Ok(entries) = std::fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !show_hidden && name.starts_with('.') {
+                continue;
+            }
+            let Ok(file_type) = entry.file_type() else {
+                continue;
+            };
+            let Ok(relative) = entry.path().strip_prefix(root).map(Path::to_path_buf) else {
+                continue;
+            };
+            let relative = relative.to_string_lossy().replace('\\', "/");
+            if rules.is_ignored(&relative, file_type.is_dir()) {
+                continue;
+            }
+            if file_type.is_dir() {
+                queue.push(entry.path());
+                continue;
+            }
+            index.entry(name.to_lowercase()).or_default().push(relative);
+            indexed += 1;
+            if indexed >= MAX_INDEXED_FILES {
+                return index;
+            }
+        }
+    }
+    index
+}
+
+/// Counts requested directory names that appear in the candidate's directories.
+fn shared_dirs(requested: &[String], candidate: &str) -> usize {
+    let dirs: Vec<String> = candidate
+        .split('/')
+        .rev()
+        .skip(1)
+        .map(|part| part.to_lowercase())
+        .collect();
+    requested
+        .iter()
+        .rev()
+        .skip(1)
+        .filter(|part| dirs.contains(part))
+        .count()
+}
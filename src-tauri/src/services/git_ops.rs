@@ -0,0 +1,329 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+use crate::services::audit::{now_ms, AuditEntry, AuditLog};
+use crate::services::tools::{run_command, CommandRequest, ToolResult};
+use crate::services::workspace::WorkspaceState;
+
+/// Which git write operations are allowed, checked before anything touches
+/// the repository history or working tree.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GitPolicy {
+    pub allow_commit: bool,
+    pub allow_branch: bool,
+    pub allow_stash: bool,
+    pub allow_checkout: bool,
+    /// Branches that may not be committed to directly.
+    pub protected_branches: Vec<String>,
+}
+
+impl Default for GitPolicy {
+    fn default() -> Self {
+        Self {
+            allow_commit: true,
+            allow_branch: true,
+            allow_stash: true,
+            allow_checkout: true,
+            protected_branches: vec!["main".to_string(), "master".to_string()],
+        }
+    }
+}
+
+pub fn git_policy_path(root: &Path) -> PathBuf {
+    root.join(".taurihands").join("git-policy.json")
+}
+
+pub fn load_git_policy(root: &Path) -> GitPolicy {
+    std::fs::read_to_string(git_policy_path(root))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_git_policy(root: &Path, policy: &GitPolicy) -> Result<(), String> {
+    let path = git_policy_path(root);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_vec_pretty(policy).map_err(|e| e.to_string())?;
+    std::fs::write(path, data).map_err(|e| e.to_string())
+}
+
+#[derive(Clone, Deserialize)]
+pub struct GitCommitRequest {
+    pub message: String,
+    /// Paths to stage; all changes when empty.
+    #[serde(default)]
+    pub paths: Vec<String>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct GitBranchRequest {
+    pub name: String,
+    #[serde(default)]
+    pub start_point: Option<String>,
+    /// Switch to the new branch after creating it.
+    #[serde(default)]
+    pub checkout: bool,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct GitStashRequest {
+    /// Restore the latest stash instead of saving one.
+    #[serde(default)]
+    pub pop: bool,
+    #[serde(default)]
+    pub message: Option<String>,
+}
+
+#[derive(Clone, Deserialize)]
+pub struct GitCheckoutRequest {
+    /// Branch, tag or commit.
+    pub target: String,
+}
+
+/// Stages and commits. `actor` is "user" or "agent" and ends up in the audit log.
+pub fn git_commit(
+    workspace: &WorkspaceState,
+    audit: &AuditLog,
+    request: &GitCommitRequest,
+    actor: &str,
+) -> Result<ToolResult, String> {
+    let root = workspace.root();
+    let payload = serde_json::json!({
+        "actor": actor,
+        "message": request.message,
+        "paths": request.paths,
+    });
+    check(audit, "git.commit", &payload, || {
+        let policy = load_git_policy(&root);
+        if !policy.allow_commit {
+            return Err("Commits are disabled by the git policy".to_string());
+        }
+        if request.message.trim().is_empty() {
+            return Err("A commit message is required".to_string());
+        }
+        let branch = current_branch(&root)?;
+        if policy.protected_branches.contains(&branch) {
+            return Err(format!(
+                "Branch {} is protected; create a branch with git.branch first",
+                branch
+            ));
+        }
+        Ok(())
+    })?;
+    let mut add = vec!["add".to_string()];
+    if request.paths.is_empty() {
+        add.push("-A".to_string());
+    } else {
+        add.push("--".to_string());
+        for path in &request.paths {
+            add.push(workspace.resolve_path(path)?.to_string_lossy().to_string());
+        }
+    }
+    let staged = git(&root, audit, add)?;
+    if !staged.ok {
+        return Ok(staged);
+    }
+    // Passed as a file so the message is neither quoted nor matched against
+    // the dangerous-command patterns.
+    let message_path =
+        std::env::temp_dir().join(format!("taurihands-commit-{}.txt", uuid::Uuid::new_v4()));
+    std::fs::write(&message_path, request.message.as_bytes()).map_err(|e| e.to_string())?;
+    let result = git(
+        &root,
+        audit,
+        vec![
+            "commit".to_string(),
+            "-F".to_string(),
+            message_path.to_string_lossy().to_string(),
+        ],
+    );
+    let _ = std::fs::remove_file(&message_path);
+    record(audit, "git.commit", payload, result)
+}
+
+pub fn git_branch(
+    workspace: &WorkspaceState,
+    audit: &AuditLog,
+    request: &GitBranchRequest,
+    actor: &str,
+) -> Result<ToolResult, String> {
+    let root = workspace.root();
+    let payload = serde_json::json!({
+        "actor": actor,
+        "name": request.name,
+        "start_point": request.start_point,
+        "checkout": request.checkout,
+    });
+    check(audit, "git.branch", &payload, || {
+        let policy = load_git_policy(&root);
+        if !policy.allow_branch {
+            return Err("Creating branches is disabled by the git policy".to_string());
+        }
+        if request.checkout && !policy.allow_checkout {
+            return Err("Checkouts are disabled by the git policy".to_string());
+        }
+        validate_ref(&request.name)?;
+        if let Some(start_point) = request.start_point.as_deref() {
+            validate_ref(start_point)?;
+        }
+        Ok(())
+    })?;
+    let mut args = if request.checkout {
+        vec!["switch".to_string(), "-c".to_string()]
+    } else {
+        vec!["branch".to_string()]
+    };
+    args.push(request.name.trim().to_string());
+    if let Some(start_point) = request.start_point.as_deref() {
+        args.push(start_point.trim().to_string());
+    }
+    record(audit, "git.branch", payload, git(&root, audit, args))
+}
+
+pub fn git_stash(
+    workspace: &WorkspaceState,
+    audit: &AuditLog,
+    request: &GitStashRequest,
+    actor: &str,
+) -> Result<ToolResult, String> {
+    let root = workspace.root();
+    let payload = serde_json::json!({
+        "actor": actor,
+        "pop": request.pop,
+        "message": request.message,
+    });
+    check(audit, "git.stash", &payload, || {
+        if !load_git_policy(&root).allow_stash {
+            return Err("Stashing is disabled by the git policy".to_string());
+        }
+        Ok(())
+    })?;
+    let mut args = vec!["stash".to_string()];
+    if request.pop {
+        args.push("pop".to_string());
+    } else {
+        args.push("push".to_string());
+        args.push("--include-untracked".to_string());
+        if let Some(message) = request.message.as_deref().filter(|m| !m.trim().is_empty()) {
+            args.push("-m".to_string());
+            args.push(message.trim().to_string());
+        }
+    }
+    record(audit, "git.stash", payload, git(&root, audit, args))
+}
+
+/// Switches to a branch or commit. Refused while there are uncommitted
+/// changes, which git would otherwise carry over or refuse halfway.
+pub fn git_checkout(
+    workspace: &WorkspaceState,
+    audit: &AuditLog,
+    request: &GitCheckoutRequest,
+    actor: &str,
+) -> Result<ToolResult, String> {
+    let root = workspace.root();
+    let payload = serde_json::json!({ "actor": actor, "target": request.target });
+    check(audit, "git.checkout", &payload, || {
+        if !load_git_policy(&root).allow_checkout {
+            return Err("Checkouts are disabled by the git policy".to_string());
+        }
+        validate_ref(&request.target)?;
+        if is_dirty(&root)? {
+            return Err(
+                "Uncommitted changes; commit or stash them before checking out".to_string(),
+            );
+        }
+        Ok(())
+    })?;
+    let args = vec!["checkout".to_string(), request.target.trim().to_string()];
+    record(audit, "git.checkout", payload, git(&root, audit, args))
+}
+
+/// Runs the policy check and audits a refusal.
+fn check(
+    audit: &AuditLog,
+    action: &str,
+    payload: &serde_json::Value,
+    policy_check: impl FnOnce() -> Result<(), String>,
+) -> Result<(), String> {
+    policy_check().inspect_err(|reason| {
+        audit.write(AuditEntry {
+            timestamp_ms: now_ms(),
+            action: format!("{}.blocked", action),
+            session_id: None,
+            command: None,
+            payload: serde_json::json!({ "request": payload, "reason": reason }),
+        });
+    })
+}
+
+fn record(
+    audit: &AuditLog,
+    action: &str,
+    payload: serde_json::Value,
+    result: Result<ToolResult, String>,
+) -> Result<ToolResult, String> {
+    let (ok, exit_code) = match &result {
+        Ok(result) => (result.ok, result.exit_code),
+        Err(_) => (false, None),
+    };
+    audit.write(AuditEntry {
+        timestamp_ms: now_ms(),
+        action: action.to_string(),
+        session_id: None,
+        command: None,
+        payload: serde_json::json!({ "request": payload, "ok": ok, "exit_code": exit_code }),
+    });
+    result
+}
+
+fn git(root: &Path, audit: &AuditLog, args: Vec<String>) -> Result<ToolResult, String> {
+    run_command(
+        CommandRequest {
+            program: "git".to_string(),
+            args: Some(args),
+            cwd: Some(root.to_string_lossy().to_string()),
+            env: None,
+            timeout_ms: Some(60_000),
+        },
+        root.to_string_lossy().as_ref(),
+        audit,
+    )
+}
+
+/// Stdout of a git command run in `root`, or its stderr when it fails.
+pub fn git_output(root: &Path, args: &[&str]) -> Result<String, String> {
+    let output = Command::new("git")
+        .args(args)
+        .current_dir(root)
+        .output()
+        .map_err(|e| e.to_string())?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+fn current_branch(root: &Path) -> Result<String, String> {
+    git_output(root, &["rev-parse", "--abbrev-ref", "HEAD"]).map(|branch| branch.trim().to_string())
+}
+
+fn is_dirty(root: &Path) -> Result<bool, String> {
+    git_output(root, &["status", "--porcelain"]).map(|status| !status.trim().is_empty())
+}
+
+/// Rejects names git would read as options or ranges.
+fn validate_ref(name: &str) -> Result<(), String> {
+    let name = name.trim();
+    if name.is_empty()
+        || name.starts_with('-')
+        || name.contains("..")
+        || name.chars().any(|c| c.is_whitespace() || c.is_control())
+    {
+        return Err(format!("Invalid git ref: {}", name));
+    }
+    Ok(())
+}
@@ -0,0 +1,216 @@
+use std::fs::{create_dir_all, remove_dir_all, write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+use crate::services::audit::AuditLog;
+use crate::services::events::{EventSink, SharedEventSink};
+use crate::services::kernel::{
+    KernelEvent, KernelManager, KernelPlanUpdateRequest, KernelStartRequest, KERNEL_EVENT_NAME,
+};
+pub use crate::services::kernel::{RunAgentState, RunState};
+use crate::services::pty::TerminalManager;
+use crate::services::workspace::WorkspaceState;
+
+const POLL_INTERVAL_MS: u64 = 25;
+
+#[derive(Clone)]
+pub struct CollectedEvent {
+    pub channel: String,
+    pub payload: serde_json::Value,
+}
+
+/// Keeps every emitted event in memory so runs can be inspected afterwards.
+#[derive(Clone, Default)]
+pub struct MemoryEventSink {
+    events: Arc<Mutex<Vec<CollectedEvent>>>,
+}
+
+impl MemoryEventSink {
+    pub fn events(&self) -> Vec<CollectedEvent> {
+        self.events
+            .lock()
+            .map(|events| events.clone())
+            .unwrap_or_default()
+    }
+
+    pub fn kernel_events(&self) -> Vec<KernelEvent> {
+        self.events()
+            .into_iter()
+            .filter(|event| event.channel == KERNEL_EVENT_NAME)
+            .filter_map(|event| serde_json::from_value(event.payload).ok())
+            .collect()
+    }
+
+    pub fn kernel_event_types(&self) -> Vec<String> {
+        self.kernel_events()
+            .into_iter()
+            .map(|event| event.event_type)
+            .collect()
+    }
+
+    pub fn clear(&self) {
+        if let Ok(mut events) = self.events.lock() {
+            events.clear();
+        }
+    }
+}
+
+impl EventSink for MemoryEventSink {
+    fn emit_json(&self, event: &str, payload: serde_json::Value) {
+        if let Ok(mut events) = self.events.lock() {
+            events.push(CollectedEvent {
+                channel: event.to_string(),
+                payload,
+            });
+        }
+    }
+
+    fn clone_sink(&self) -> SharedEventSink {
+        Arc::new(self.clone())
+    }
+}
+
+/// A kernel wired to a throwaway workspace and the scripted `mock` LLM provider.
+/// The temp directory is removed when the harness is dropped.
+pub struct HeadlessKernel {
+    pub kernel: KernelManager,
+    pub events: MemoryEventSink,
+    root: PathBuf,
+    workspace_root: PathBuf,
+}
+
+impl HeadlessKernel {
+    /// `responses` follows the mock script format: strings or `{content, delayMs, error}`.
+    pub fn new(responses: Vec<serde_json::Value>) -> Result<Self, String> {
+        let root = std::env::temp_dir().join(format!("taurihands-harness-{}", Uuid::new_v4()));
+        let workspace_root = root.join("workspace");
+        let llm_root = root.join("app");
+        create_dir_all(&workspace_root).map_err(|e| e.to_string())?;
+        let script_path = root.join("mock-llm.json");
+        let script = serde_json::to_vec_pretty(&responses).map_err(|e| e.to_string())?;
+        write(&script_path, script).map_err(|e| e.to_string())?;
+        write_mock_profile(&llm_root, &script_path)?;
+
+        let taurihands = workspace_root.join(".taurihands");
+        let audit = AuditLog::new(taurihands.join("audit.log"));
+        let terminal = TerminalManager::new(taurihands.join("terminal"));
+        let workspace = WorkspaceState::new(workspace_root.clone());
+        let kernel =
+            KernelManager::new(workspace_root.clone(), terminal, workspace, audit, llm_root);
+        Ok(Self {
+            kernel,
+            events: MemoryEventSink::default(),
+            root,
+            workspace_root,
+        })
+    }
+
+    pub fn workspace_root(&self) -> &Path {
+        &self.workspace_root
+    }
+
+    pub fn write_file(&self, path: &str, content: &str) -> Result<(), String> {
+        let target = self.workspace_root.join(path);
+        if let Some(parent) = target.parent() {
+            create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        write(target, content).map_err(|e| e.to_string())
+    }
+
+    pub fn read_file(&self, path: &str) -> Result<String, String> {
+        std::fs::read_to_string(self.workspace_root.join(path)).map_err(|e| e.to_string())
+    }
+
+    /// Sets the plan goal and steps, then starts the run loop.
+    pub async fn start(
+        &self,
+        goal: &str,
+        steps: Vec<String>,
+        max_steps: u32,
+    ) -> Result<RunState, String> {
+        self.kernel
+            .update_plan(
+                &self.events,
+                KernelPlanUpdateRequest {
+                    goal: goal.to_string(),
+                    steps,
+                    auto_generate: Some(false),
+                    generation_id: None,
+                },
+            )
+            .await?;
+        self.kernel.start(
+            &self.events,
+            KernelStartRequest {
+                session_id: None,
+                max_steps: Some(max_steps),
+                task_id: None,
+                readonly: false,
+                auto_clean_sessions: Some(true),
+                tags: Vec::new(),
+            },
+        )
+    }
+
+    /// Polls until the run leaves the running state, failing once `timeout` has elapsed.
+    pub async fn wait_until_idle(&self, timeout: Duration) -> Result<RunState, String> {
+        let deadline = Instant::now() + timeout;
+        while self.kernel.snapshot().agent_state == RunAgentState::Running {
+            if Instant::now() >= deadline {
+                return Err(format!(
+                    "Kernel still running after {}ms",
+                    timeout.as_millis()
+                ));
+            }
+            tokio::time::sleep(Duration::from_millis(POLL_INTERVAL_MS)).await;
+        }
+        Ok(self.kernel.snapshot())
+    }
+}
+
+impl Drop for HeadlessKernel {
+    fn drop(&mut self) {
+        let _ = self.kernel.stop(&self.events);
+        let _ = remove_dir_all(&self.root);
+    }
+}
+
+fn write_mock_profile(llm_root: &Path, script_path: &Path) -> Result<(), String> {
+    let store = serde_json::json!({
+        "active": "Mock",
+        "profiles": {
+            "Mock": {
+                "profileName": "Mock",
+                "provider": "mock",
+                "apiKey": "",
+                "baseUrl": script_path.to_string_lossy(),
+                "model": "mock",
+                "temperature": 0.0,
+                "topP": 1.0,
+                "maxTokens": 2048,
+                "contextWindow": 32000,
+                "streamResponses": false,
+                "toolCalling": true,
+                "safetyMode": false,
+                "retries": 0,
+                "concurrency": 0,
+                "prompt": "",
+                "contextPolicy": "",
+                "memoryMode": "",
+                "enableCaching": false,
+                "maxTerminalLines": 200,
+                "redactSecrets": false,
+                "auditLogs": false,
+                "toolToggles": []
+            }
+        }
+    });
+    let path = llm_root.join(".taurihands").join("llm.json");
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_vec_pretty(&store).map_err(|e| e.to_string())?;
+    write(path, data).map_err(|e| e.to_string())
+}
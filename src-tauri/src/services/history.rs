@@ -0,0 +1,162 @@
+use serde::{Deserialize, Serialize};
+use std::fs::{create_dir_all, read_to_string, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+use crate::services::audit::now_ms;
+use crate::services::diff::unified_diff;
+use crate::services::profiles::workspace_data_dir;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileVersion {
+    pub id: String,
+    pub path: String,
+    pub actor: String,
+    pub run_id: Option<String>,
+    pub timestamp_ms: u128,
+    pub existed: bool,
+    pub size: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileHistoryEntry {
+    #[serde(flatten)]
+    pub version: FileVersion,
+    pub diff: String,
+}
+
+#[derive(Deserialize)]
+pub struct FileHistoryRequest {
+    pub path: String,
+}
+
+#[derive(Deserialize)]
+pub struct FileRestoreRequest {
+    pub path: String,
+    pub version_id: String,
+}
+
+pub struct FileHistory {
+    root: PathBuf,
+    base_dir: PathBuf,
+}
+
+impl FileHistory {
+    pub fn for_root(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            base_dir: workspace_data_dir(root).join("history"),
+        }
+    }
+
+    pub fn record(
+        &self,
+        resolved: &Path,
+        actor: &str,
+        run_id: Option<String>,
+    ) -> Result<FileVersion, String> {
+        let blobs = self.base_dir.join("blobs");
+        create_dir_all(&blobs).map_err(|e| e.to_string())?;
+        let existing = std::fs::read(resolved).ok();
+        let version = FileVersion {
+            id: Uuid::new_v4().to_string(),
+            path: self.relative_path(resolved),
+            actor: actor.to_string(),
+            run_id,
+            timestamp_ms: now_ms(),
+            existed: existing.is_some(),
+            size: existing
+                .as_ref()
+                .map(|bytes| bytes.len() as u64)
+                .unwrap_or(0),
+        };
+        if let Some(bytes) = &existing {
+            std::fs::write(blobs.join(&version.id), bytes).map_err(|e| e.to_string())?;
+        }
+        let line = serde_json::to_string(&version).map_err(|e| e.to_string())?;
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(self.base_dir.join("index.jsonl"))
+            .map_err(|e| e.to_string())?;
+        writeln!(file, "{}", line).map_err(|e| e.to_string())?;
+        Ok(version)
+    }
+
+    pub fn versions(&self, resolved: &Path) -> Vec<FileVersion> {
+        let path = self.relative_path(resolved);
+        let Ok(content) = read_to_string(self.base_dir.join("index.jsonl")) else {
+            return Vec::new();
+        };
+        content
+            .lines()
+            .filter_map(|line| serde_json::from_str::<FileVersion>(line).ok())
+            .filter(|version| version.path == path)
+            .collect()
+    }
+
+    pub fn history(&self, resolved: &Path) -> Vec<FileHistoryEntry> {
+        let versions = self.versions(resolved);
+        let current = read_to_string(resolved).unwrap_or_default();
+        let mut entries = Vec::new();
+        for (index, version) in versions.iter().enumerate() {
+            let before = self.read_version(version).unwrap_or_default();
+            let after = match versions.get(index + 1) {
+                Some(next) => self.read_version(next).unwrap_or_default(),
+                None => current.clone(),
+            };
+            entries.push(FileHistoryEntry {
+                version: version.clone(),
+                diff: unified_diff(&version.path, &before, &after),
+            });
+        }
+        entries.reverse();
+        entries
+    }
+
+    pub fn restore(&self, resolved: &Path, version_id: &str) -> Result<FileVersion, String> {
+        let version = self
+            .versions(resolved)
+            .into_iter()
+            .find(|version| version.id == version_id)
+            .ok_or_else(|| format!("Unknown version: {}", version_id))?;
+        let content = if version.existed {
+            Some(
+                std::fs::read(self.base_dir.join("blobs").join(&version.id))
+                    .map_err(|e| e.to_string())?,
+            )
+        } else {
+            None
+        };
+        let snapshot = self.record(resolved, "user", None)?;
+        match content {
+            Some(bytes) => {
+                if let Some(parent) = resolved.parent() {
+                    create_dir_all(parent).map_err(|e| e.to_string())?;
+                }
+                std::fs::write(resolved, bytes).map_err(|e| e.to_string())?;
+            }
+            None => {
+                if resolved.exists() {
+                    std::fs::remove_file(resolved).map_err(|e| e.to_string())?;
+                }
+            }
+        }
+        Ok(snapshot)
+    }
+
+    fn read_version(&self, version: &FileVersion) -> Option<String> {
+        if !version.existed {
+            return Some(String::new());
+        }
+        read_to_string(self.base_dir.join("blobs").join(&version.id)).ok()
+    }
+
+    fn relative_path(&self, resolved: &Path) -> String {
+        let relative = resolved.strip_prefix(&self.root).unwrap_or(resolved);
+        relative.to_string_lossy().replace('\\', "/")
+    }
+}
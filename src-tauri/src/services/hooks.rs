@@ -0,0 +1,303 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+use crate::services::audit::{now_ms, AuditEntry, AuditLog};
+use crate::services::tools::{is_dangerous_command, join_reader, spawn_reader};
+use crate::services::trust::read_trusted_config;
+
+const DEFAULT_HOOK_TIMEOUT_MS: u64 = 60_000;
+const MAX_HOOK_OUTPUT_BYTES: usize = 8_000;
+
+#[derive(Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceHooks {
+    #[serde(default)]
+    pub on_open: Vec<HookCommand>,
+    #[serde(default)]
+    pub on_close: Vec<HookCommand>,
+    #[serde(default)]
+    pub before_action: Vec<ActionHook>,
+    #[serde(default)]
+    pub after_action: Vec<ActionHook>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionHook {
+    #[serde(flatten)]
+    pub command: HookCommand,
+    #[serde(default)]
+    pub action_types: Vec<String>,
+    #[serde(default)]
+    pub extensions: Vec<String>,
+}
+
+impl ActionHook {
+    fn matches(&self, action_type: &str, path: Option<&str>) -> bool {
+        if !self.command.enabled {
+            return false;
+        }
+        if !self.action_types.is_empty() && !self.action_types.iter().any(|t| t == action_type) {
+            return false;
+        }
+        if self.extensions.is_empty() {
+            return true;
+        }
+        let Some(extension) = path
+            .and_then(|path| Path::new(path).extension())
+            .and_then(|ext| ext.to_str())
+        else {
+            return false;
+        };
+        self.extensions
+            .iter()
+            .any(|ext| ext.trim_start_matches('.').eq_ignore_ascii_case(extension))
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookCommand {
+    pub name: String,
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub cwd: Option<String>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HookResult {
+    pub name: String,
+    pub event: String,
+    pub ok: bool,
+    pub exit_code: Option<i32>,
+    pub timed_out: bool,
+    pub duration_ms: u128,
+    pub output: String,
+    pub error: Option<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+pub struct HookRunner {
+    root: PathBuf,
+    audit: AuditLog,
+}
+
+impl HookRunner {
+    pub fn new(root: &Path, audit: AuditLog) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            audit,
+        }
+    }
+
+    pub fn config_path(&self) -> PathBuf {
+        self.root.join(".taurihands").join("hooks.json")
+    }
+
+    pub fn load(&self) -> WorkspaceHooks {
+        std::fs::read_to_string(self.config_path())
+            .ok()
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default()
+    }
+
+    /// The hooks to run: none until the user trusts this hooks.json.
+    fn load_trusted(&self) -> WorkspaceHooks {
+        read_trusted_config(&self.root, "hooks.json")
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, hooks: &WorkspaceHooks) -> Result<(), String> {
+        let path = self.config_path();
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let data = serde_json::to_vec_pretty(hooks).map_err(|e| e.to_string())?;
+        std::fs::write(path, data).map_err(|e| e.to_string())
+    }
+
+    pub fn run_open(&self) -> Vec<HookResult> {
+        let hooks = self.load_trusted();
+        self.run_all("open", &hooks.on_open)
+    }
+
+    pub fn run_close(&self) -> Vec<HookResult> {
+        let hooks = self.load_trusted();
+        self.run_all("close", &hooks.on_close)
+    }
+
+    pub fn run_action_hooks(
+        &self,
+        stage: &str,
+        action_type: &str,
+        path: Option<&str>,
+    ) -> Vec<HookResult> {
+        let hooks = self.load_trusted();
+        let candidates = match stage {
+            "before" => hooks.before_action,
+            "after" => hooks.after_action,
+            _ => Vec::new(),
+        };
+        let event = format!("{}_action", stage);
+        candidates
+            .iter()
+            .filter(|hook| hook.matches(action_type, path))
+            .map(|hook| {
+                let mut command = hook.command.clone();
+                if let Some(path) = path {
+                    command.args = command
+                        .args
+                        .iter()
+                        .map(|arg| arg.replace("{path}", path))
+                        .collect();
+                }
+                self.run_hook(&event, &command)
+            })
+            .collect()
+    }
+
+    fn run_all(&self, event: &str, hooks: &[HookCommand]) -> Vec<HookResult> {
+        hooks
+            .iter()
+            .filter(|hook| hook.enabled)
+            .map(|hook| self.run_hook(event, hook))
+            .collect()
+    }
+
+    fn run_hook(&self, event: &str, hook: &HookCommand) -> HookResult {
+        let started = Instant::now();
+        let mut result = HookResult {
+            name: hook.name.clone(),
+            event: event.to_string(),
+            ok: false,
+            exit_code: None,
+            timed_out: false,
+            duration_ms: 0,
+            output: String::new(),
+            error: None,
+        };
+        if let Some(reason) = is_dangerous_command(&hook.program, &hook.args) {
+            result.error = Some(reason);
+            self.audit_result(hook, &result);
+            return result;
+        }
+        let cwd = match &hook.cwd {
+            Some(cwd) => self.root.join(cwd),
+            None => self.root.clone(),
+        };
+        let timeout = Duration::from_millis(hook.timeout_ms.unwrap_or(DEFAULT_HOOK_TIMEOUT_MS));
+        let spawned = Command::new(&hook.program)
+            .args(&hook.args)
+            .current_dir(cwd)
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn();
+        let mut child = match spawned {
+            Ok(child) => child,
+            Err(err) => {
+                result.error = Some(err.to_string());
+                self.audit_result(hook, &result);
+                return result;
+            }
+        };
+        let stdout = child.stdout.take().map(spawn_reader);
+        let stderr = child.stderr.take().map(spawn_reader);
+        loop {
+            match child.try_wait() {
+                Ok(Some(status)) => {
+                    result.ok = status.success();
+                    result.exit_code = status.code();
+                    break;
+                }
+                Ok(None) if started.elapsed() >= timeout => {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    result.timed_out = true;
+                    result.error = Some(format!("Hook timed out after {}ms", timeout.as_millis()));
+                    break;
+                }
+                Ok(None) => sleep(Duration::from_millis(50)),
+                Err(err) => {
+                    result.error = Some(err.to_string());
+                    break;
+                }
+            }
+        }
+        let mut output = String::new();
+        for reader in [stdout, stderr] {
+            output.push_str(&String::from_utf8_lossy(&join_reader(reader)));
+        }
+        if output.len() > MAX_HOOK_OUTPUT_BYTES {
+            let mut end = MAX_HOOK_OUTPUT_BYTES;
+            while end > 0 && !output.is_char_boundary(end) {
+                end -= 1;
+            }
+            output.truncate(end);
+        }
+        result.output = output;
+        result.duration_ms = started.elapsed().as_millis();
+        self.audit_result(hook, &result);
+        result
+    }
+
+    fn audit_result(&self, hook: &HookCommand, result: &HookResult) {
+        let mut command = hook.program.clone();
+        if !hook.args.is_empty() {
+            command.push(' ');
+            command.push_str(&hook.args.join(" "));
+        }
+        self.audit.write(AuditEntry {
+            timestamp_ms: now_ms(),
+            action: format!("workspace.hook.{}", result.event),
+            session_id: None,
+            command: Some(command),
+            payload: serde_json::json!({
+                "name": result.name,
+                "ok": result.ok,
+                "exit_code": result.exit_code,
+                "timed_out": result.timed_out,
+                "duration_ms": result.duration_ms,
+                "error": result.error,
+            }),
+        });
+    }
+}
+
+pub fn format_hook_results(results: &[HookResult]) -> String {
+    let mut lines = Vec::new();
+    for result in results {
+        let status = if result.ok {
+            "ok".to_string()
+        } else if result.timed_out {
+            "timed out".to_string()
+        } else {
+            match result.exit_code {
+                Some(code) => format!("failed (exit {})", code),
+                None => "failed".to_string(),
+            }
+        };
+        lines.push(format!("[{}] {} {}", result.event, result.name, status));
+        if let Some(error) = &result.error {
+            lines.push(error.clone());
+        }
+        let output = result.output.trim();
+        if !output.is_empty() {
+            lines.push(output.to_string());
+        }
+    }
+    lines.join("\n")
+}
@@ -0,0 +1,153 @@
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+
+/// Always skipped by tree listing, search and the file index.
+pub const DEFAULT_IGNORE_PATTERNS: &[&str] = &[
+    ".git/",
+    ".idea/",
+    ".vscode/",
+    ".taurihands/",
+    "node_modules/",
+    "dist/",
+    "target/",
+    "out/",
+];
+
+/// Extra ignore globs for one workspace, on top of the defaults and the
+/// global patterns from the app settings.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct IgnorePolicy {
+    pub patterns: Vec<String>,
+}
+
+pub fn ignore_policy_path(root: &Path) -> PathBuf {
+    root.join(".taurihands").join("ignore.json")
+}
+
+pub fn load_ignore_policy(root: &Path) -> IgnorePolicy {
+    std::fs::read_to_string(ignore_policy_path(root))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_ignore_policy(root: &Path, policy: &IgnorePolicy) -> Result<(), String> {
+    let path = ignore_policy_path(root);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_vec_pretty(policy).map_err(|e| e.to_string())?;
+    std::fs::write(path, data).map_err(|e| e.to_string())
+}
+
+static GLOBAL_PATTERNS: OnceLock<Mutex<Vec<String>>> = OnceLock::new();
+
+fn global_patterns_lock() -> &'static Mutex<Vec<String>> {
+    GLOBAL_PATTERNS.get_or_init(|| Mutex::new(Vec::new()))
+}
+
+/// Installs the patterns from the active profile's settings.
+pub fn set_global_patterns(patterns: Vec<String>) {
+    if let Ok(mut current) = global_patterns_lock().lock() {
+        *current = patterns;
+    }
+}
+
+pub fn global_patterns() -> Vec<String> {
+    global_patterns_lock()
+        .lock()
+        .map(|patterns| patterns.clone())
+        .unwrap_or_default()
+}
+
+/// Trims patterns, drops blanks and duplicates, and rejects invalid globs.
+pub fn normalize_patterns(patterns: Vec<String>) -> Result<Vec<String>, String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for pattern in patterns {
+        let pattern = pattern.trim().replace('\\', "/");
+        if pattern.is_empty() || normalized.contains(&pattern) {
+            continue;
+        }
+        Glob::new(pattern.trim_end_matches('/'))
+            .map_err(|e| format!("Invalid ignore pattern {}: {}", pattern, e))?;
+        normalized.push(pattern);
+    }
+    Ok(normalized)
+}
+
+/// Defaults, global patterns and the workspace policy of `root`.
+pub fn workspace_patterns(root: &Path) -> Vec<String> {
+    let mut patterns: Vec<String> = DEFAULT_IGNORE_PATTERNS
+        .iter()
+        .map(|pattern| pattern.to_string())
+        .collect();
+    patterns.extend(global_patterns());
+    patterns.extend(load_ignore_policy(root).patterns);
+    patterns
+}
+
+/// Ignore patterns in gitignore style: a trailing `/` matches directories
+/// only, and a pattern without `/` matches the entry name at any depth,
+/// e.g. `__pycache__/`, `.venv/`, `*.egg-info`, `docs/generated/**`.
+pub struct IgnoreRules {
+    patterns: Vec<String>,
+    names: GlobSet,
+    dir_names: GlobSet,
+    paths: GlobSet,
+    dir_paths: GlobSet,
+}
+
+impl IgnoreRules {
+    pub fn for_workspace(root: &Path) -> Self {
+        Self::new(workspace_patterns(root))
+    }
+
+    pub fn new(patterns: Vec<String>) -> Self {
+        let mut names = GlobSetBuilder::new();
+        let mut dir_names = GlobSetBuilder::new();
+        let mut paths = GlobSetBuilder::new();
+        let mut dir_paths = GlobSetBuilder::new();
+        for pattern in &patterns {
+            let dir_only = pattern.ends_with('/');
+            let trimmed = pattern.trim_end_matches('/');
+            let anchored = trimmed.contains('/');
+            let Ok(glob) = Glob::new(trimmed.trim_start_matches('/')) else {
+                continue;
+            };
+            match (anchored, dir_only) {
+                (false, false) => names.add(glob),
+                (false, true) => dir_names.add(glob),
+                (true, false) => paths.add(glob),
+                (true, true) => dir_paths.add(glob),
+            };
+        }
+        let build = |builder: GlobSetBuilder| builder.build().unwrap_or_else(|_| GlobSet::empty());
+        Self {
+            patterns,
+            names: build(names),
+            dir_names: build(dir_names),
+            paths: build(paths),
+            dir_paths: build(dir_paths),
+        }
+    }
+
+    /// `relative` is the workspace-relative path with `/` separators.
+    pub fn is_ignored(&self, relative: &str, is_dir: bool) -> bool {
+        let relative = relative.trim_start_matches("./").trim_matches('/');
+        let name = relative.rsplit('/').next().unwrap_or(relative);
+        self.names.is_match(name)
+            || self.paths.is_match(relative)
+            || (is_dir && (self.dir_names.is_match(name) || self.dir_paths.is_match(relative)))
+    }
+
+    /// Exclusion globs for ripgrep, which shares the gitignore semantics.
+    pub fn rg_globs(&self) -> Vec<String> {
+        self.patterns
+            .iter()
+            .map(|pattern| format!("!{}", pattern))
+            .collect()
+    }
+}
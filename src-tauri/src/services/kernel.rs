@@ -1,32 +1,101 @@
 use async_trait::async_trait;
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
-use std::fs::{create_dir_all, OpenOptions};
-use std::io::{Read, Write};
-use std::path::PathBuf;
+use std::fs::create_dir_all;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread::sleep;
-use std::time::Duration;
-use tauri::{AppHandle, Emitter};
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+use tracing::Instrument;
 use uuid::Uuid;
 
+use crate::services::artifact_upload::{load_upload_policy, upload_run_artifacts};
+use crate::services::at_rest::AtRest;
 use crate::services::audit::now_ms;
-use crate::services::audit::AuditLog;
+use crate::services::audit::{AuditEntry, AuditLog};
+use crate::services::command_policy::configure_command_policy;
+use crate::services::command_translation::{translate_command, CommandTranslation};
+use crate::services::context_budget::{
+    compact_conversation, estimate_tokens, ContextCompaction, ContextPolicy,
+};
+use crate::services::coverage::{
+    coverage_from_result, detect_coverage_command, CoverageCommand, COVERAGE_TIMEOUT_MS,
+};
+use crate::services::deps_audit::{audit_dependencies, load_deps_policy};
+use crate::services::diagnostics::{format_diagnostics, parse_diagnostics, Diagnostic};
+use crate::services::diff::{change_summary, unified_diff};
+use crate::services::events::{EventSink, SharedEventSink};
+use crate::services::freshness::load_freshness_policy;
+use crate::services::fuzzy_path::{load_path_policy, resolve_fuzzy, FuzzyMatch};
+use crate::services::git_ops::{
+    git_branch, git_checkout, git_commit, git_stash, GitBranchRequest, GitCheckoutRequest,
+    GitCommitRequest, GitStashRequest,
+};
+use crate::services::history::FileHistory;
+use crate::services::hooks::{format_hook_results, HookResult, HookRunner};
+use crate::services::ignore::IgnoreRules;
 use crate::services::llm::{
-    request_completion, request_completion_stream, LlmProfile, LlmResponseFormat, LlmStore,
+    request_completion_metered, LlmProfile, LlmResponseFormat, LlmStore, LlmToolCommand,
+    LlmToolToggle, ProviderHealth,
+};
+use crate::services::llm_tools::{supports_native_tools, ToolDefinition};
+use crate::services::llm_usage::{TokenUsage, UsageReport, UsageTracker};
+use crate::services::mcp::{McpManager, McpTool};
+use crate::services::metrics;
+use crate::services::missing_deps::{detect_missing_dependency, MissingDependency};
+use crate::services::narration::{narrate, NARRATION_EVENT_NAME};
+use crate::services::observation::SummaryPipeline;
+use crate::services::patch;
+use crate::services::preflight::{run_preflight, PreflightInput};
+use crate::services::profiles::workspace_data_dir;
+use crate::services::provenance::{commit_trailer, load_provenance_policy, ProvenanceStore};
+use crate::services::pty::{
+    default_shell, TerminalCreateRequest, TerminalExecRequest, TerminalHistoryRequest,
+    TerminalManager,
 };
-use crate::services::pty::{TerminalExecRequest, TerminalManager};
+use crate::services::rate_limit::{ActionRateLimit, RateLimiter};
+use crate::services::regression::{parse_test_results, RegressionWatchdog};
+use crate::services::scratch::{
+    expand_scratch, remove_scratch_dir, scratch_dir, scratch_relative, without_scratch_pathspec,
+};
+use crate::services::secrets::{format_findings, scan_workspace, SecretFinding};
+use crate::services::todos::{scan_todos, todos_tool_result};
 use crate::services::tools::{
-    max_read_bytes, read_file, run_command, search, write_file, CommandRequest, ReadFileRequest,
-    SearchMatch, SearchRequest, ToolResult, WriteFileRequest,
+    apply_patch, is_mutating_command, read_file, run_command, search, write_file,
+    ApplyPatchRequest, CommandRequest, ReadFileRequest, SearchMatch, SearchRequest, ToolResult,
+    WriteFileRequest,
+};
+use crate::services::transcript::ImportedTranscript;
+use crate::services::verify::{
+    format_verify_results, load_trusted_verify_policy, verify_written_file, VerifyResult,
 };
-use crate::services::workspace::{display_path, resolve_read_path_with_fallback, WorkspaceState};
+use crate::services::visibility::load_visibility_policy;
+use crate::services::workspace::{
+    display_path, resolve_read_path_with_fallback, WorkspaceState, PATH_ALIASES,
+};
+use crate::services::write_preview::{backup_before_write, load_write_preview_policy};
 
 #[derive(Clone, Debug, Serialize)]
 pub struct JudgeResult {
     pub status: String,
     pub message: String,
+    pub score: f64,
+    pub reasons: Vec<String>,
+    pub checks: Vec<JudgeRuleVerdict>,
+}
+
+/// Only blockers can fail a run; warnings and info rules lower the score.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JudgeSeverity {
+    #[default]
+    Blocker,
+    Warning,
+    Info,
 }
 
 #[derive(Clone, Debug)]
@@ -42,6 +111,91 @@ pub struct JudgeRule {
     pub command: Option<Vec<String>>,
     pub fail_match: Option<String>,
     pub success_match: Option<String>,
+    #[serde(default)]
+    pub severity: JudgeSeverity,
+    #[serde(default = "default_judge_weight")]
+    pub weight: f64,
+    #[serde(default)]
+    pub min_coverage: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub when: Option<JudgeCondition>,
+}
+
+fn default_judge_weight() -> f64 {
+    1.0
+}
+
+/// Limits a rule to the environments it makes sense in; a rule whose
+/// condition does not hold is skipped rather than failed.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct JudgeCondition {
+    /// `windows`, `macos`, `linux`, or a family such as `unix`.
+    #[serde(default)]
+    pub os: Option<String>,
+    /// Workspace-relative paths that must all exist.
+    #[serde(default)]
+    pub files_exist: Vec<String>,
+    /// Words of which the run's goal must contain at least one, ignoring case.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub goal_keywords: Vec<String>,
+}
+
+/// Goal words that mark a task as writing tests, which coverage rules are
+/// limited to unless they name their own `goal_keywords`.
+const TEST_GOAL_KEYWORDS: &[&str] = &["test", "coverage", "spec"];
+
+impl JudgeCondition {
+    /// Returns why the condition does not hold in `workspace` for a run with
+    /// `goal`, if it does not.
+    pub fn unmet(&self, workspace: &Path, goal: &str) -> Option<String> {
+        if let Some(os) = self
+            .os
+            .as_deref()
+            .map(str::trim)
+            .filter(|os| !os.is_empty())
+        {
+            let os = os.to_lowercase();
+            if os != std::env::consts::OS && os != std::env::consts::FAMILY {
+                return Some(format!("only runs on {}", os));
+            }
+        }
+        if let Some(path) = self
+            .files_exist
+            .iter()
+            .find(|path| !workspace.join(path.as_str()).exists())
+        {
+            return Some(format!("only runs when {} exists", path));
+        }
+        let keywords: Vec<&str> = self.goal_keywords.iter().map(String::as_str).collect();
+        goal_unmet(goal, &keywords)
+    }
+}
+
+fn goal_unmet(goal: &str, keywords: &[&str]) -> Option<String> {
+    let goal = goal.to_lowercase();
+    if keywords.is_empty()
+        || keywords
+            .iter()
+            .any(|keyword| goal.contains(&keyword.to_lowercase()))
+    {
+        return None;
+    }
+    Some(format!(
+        "only runs when the goal mentions {}",
+        keywords.join(", ")
+    ))
+}
+
+/// Why a rule does not apply to this run, if it does not.
+fn rule_unmet(rule: &JudgeRule, context: &JudgeContext) -> Option<String> {
+    let when = rule.when.clone().unwrap_or_default();
+    if let Some(reason) = when.unmet(&context.workspace, &context.goal) {
+        return Some(reason);
+    }
+    if rule.rule_type == "coverage" && when.goal_keywords.is_empty() {
+        return goal_unmet(&context.goal, TEST_GOAL_KEYWORDS);
+    }
+    None
 }
 
 #[derive(Clone, Debug)]
@@ -71,9 +225,7 @@ impl JudgeRuleOutcome {
 
 impl JudgeEngine {
     pub fn new() -> Self {
-        Self {
-            rules: Vec::new(),
-        }
+        Self { rules: Vec::new() }
     }
 
     pub fn set_rules(&mut self, rules: Vec<JudgeRule>) {
@@ -92,29 +244,280 @@ impl JudgeEngine {
     where
         F: Fn(&JudgeRule, &JudgeContext) -> JudgeRuleOutcome,
     {
-        for rule in rules {
-            let outcome = evaluator(rule, context);
-            if !outcome.success {
-                return JudgeResult {
-                    status: "fail".to_string(),
-                    message: outcome.message,
+        let checks = rules
+            .iter()
+            .enumerate()
+            .map(|(index, rule)| {
+                let unmet = rule_unmet(rule, context);
+                let (status, reason, evidence) = match validate_judge_rule(rule) {
+                    Err(reason) => ("invalid", Some(reason), Vec::new()),
+                    Ok(()) if unmet.is_some() => ("skipped", unmet, Vec::new()),
+                    Ok(()) => {
+                        let outcome = evaluator(rule, context);
+                        if outcome.success {
+                            ("pass", None, outcome.evidence)
+                        } else {
+                            ("fail", Some(outcome.message), outcome.evidence)
+                        }
+                    }
                 };
+                JudgeRuleVerdict {
+                    id: format!("rule_{}", index + 1),
+                    rule_type: rule.rule_type.clone(),
+                    severity: rule.severity,
+                    weight: rule.weight,
+                    status: status.to_string(),
+                    description: describe_judge_rule(rule),
+                    reason,
+                    evidence,
+                }
+            })
+            .collect();
+        Self::summarize(checks)
+    }
+
+    /// Scores the checks by weight; the run fails only if a blocker did not pass.
+    /// Skipped checks count neither way.
+    fn summarize(checks: Vec<JudgeRuleVerdict>) -> JudgeResult {
+        let counted: Vec<&JudgeRuleVerdict> = checks
+            .iter()
+            .filter(|check| check.status != "skipped")
+            .collect();
+        let total: f64 = counted.iter().map(|check| check.weight).sum();
+        let passed: f64 = counted
+            .iter()
+            .filter(|check| check.status == "pass")
+            .map(|check| check.weight)
+            .sum();
+        let score = if total > 0.0 { passed / total } else { 1.0 };
+        let failed: Vec<&JudgeRuleVerdict> = counted
+            .into_iter()
+            .filter(|check| check.status != "pass")
+            .collect();
+        let reasons: Vec<String> = failed
+            .iter()
+            .map(|check| {
+                let reason = check.reason.clone().unwrap_or_default();
+                match check.severity {
+                    JudgeSeverity::Blocker => reason,
+                    JudgeSeverity::Warning => format!("warning: {}", reason),
+                    JudgeSeverity::Info => format!("info: {}", reason),
+                }
+            })
+            .collect();
+        let blocker = failed
+            .iter()
+            .find(|check| check.severity == JudgeSeverity::Blocker);
+        let (status, message) = match blocker {
+            Some(check) => ("fail", check.reason.clone().unwrap_or_default()),
+            None if failed.is_empty() => ("pass", "All rules passed".to_string()),
+            None => (
+                "pass",
+                format!(
+                    "All blockers passed; {} non-blocking rule(s) failed",
+                    failed.len()
+                ),
+            ),
+        };
+        JudgeResult {
+            status: status.to_string(),
+            message,
+            score,
+            reasons,
+            checks,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct JudgeRuleVerdict {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub rule_type: String,
+    pub severity: JudgeSeverity,
+    pub weight: f64,
+    pub status: String,
+    pub description: String,
+    pub reason: Option<String>,
+    pub evidence: Vec<String>,
+}
+
+/// Checks a rule for mistakes that would make it fail regardless of the workspace.
+pub fn validate_judge_rule(rule: &JudgeRule) -> Result<(), String> {
+    if !rule.weight.is_finite() || rule.weight < 0.0 {
+        return Err("weight must be a non-negative number".to_string());
+    }
+    match rule.rule_type.trim().to_lowercase().as_str() {
+        "command" | "command_succeeds" | "tests" | "tests.run" => {
+            let has_program = rule
+                .command
+                .as_ref()
+                .and_then(|items| items.first())
+                .is_some_and(|program| !program.trim().is_empty());
+            if has_program {
+                Ok(())
+            } else {
+                Err("command is required".to_string())
+            }
+        }
+        "coverage" => match rule.min_coverage {
+            Some(min) if (0.0..=100.0).contains(&min) => Ok(()),
+            Some(_) => Err("min_coverage must be between 0 and 100".to_string()),
+            None => Err("min_coverage is required".to_string()),
+        },
+        "constraint" => {
+            if parse_constraint_check(&rule.pattern).is_some() {
+                Ok(())
+            } else {
+                Err(format!("unrecognized constraint: {}", rule.pattern))
+            }
+        }
+        "file_exists" => {
+            if rule.pattern.trim().is_empty() {
+                Err("pattern must name the file".to_string())
+            } else {
+                Ok(())
+            }
+        }
+        "output_matches" => {
+            if rule.pattern.is_empty() {
+                return Err("pattern is required".to_string());
             }
+            Regex::new(&rule.pattern)
+                .map(|_| ())
+                .map_err(|e| format!("invalid regex: {}", e))
         }
+        "git_clean" | "git.clean" | "diff_nonempty" | "no_error" | "last_error" => Ok(()),
+        _ => Err(format!("unsupported rule type: {}", rule.rule_type)),
+    }
+}
 
-        JudgeResult {
-            status: "pass".to_string(),
-            message: "All rules passed".to_string(),
+fn describe_judge_rule(rule: &JudgeRule) -> String {
+    match rule.rule_type.trim().to_lowercase().as_str() {
+        "command" | "command_succeeds" | "tests" | "tests.run" => {
+            let command = rule.command.clone().unwrap_or_default().join(" ");
+            let mut text = format!("Runs `{}` and expects exit code 0", command);
+            if let Some(success) = rule.success_match.as_ref().filter(|v| !v.trim().is_empty()) {
+                text.push_str(&format!(", output containing \"{}\"", success));
+            }
+            if let Some(fail) = rule.fail_match.as_ref().filter(|v| !v.trim().is_empty()) {
+                text.push_str(&format!(", output without \"{}\"", fail));
+            }
+            text
+        }
+        "coverage" => {
+            let command = rule
+                .command
+                .clone()
+                .filter(|command| !command.is_empty())
+                .map(|command| format!("`{}`", command.join(" ")))
+                .unwrap_or_else(|| "the detected coverage tool".to_string());
+            format!(
+                "Runs {} and expects at least {}% coverage",
+                command,
+                rule.min_coverage.unwrap_or_default()
+            )
+        }
+        "git_clean" | "git.clean" => "Expects `git status` to report no changes".to_string(),
+        "constraint" => format!("Checks the task constraint \"{}\"", rule.pattern),
+        "file_exists" => format!("Expects `{}` to exist", rule.pattern.trim()),
+        "output_matches" => format!("Expects the last observation to match /{}/", rule.pattern),
+        "diff_nonempty" if rule.pattern.trim().is_empty() => {
+            "Expects `git status` to report changes".to_string()
+        }
+        "diff_nonempty" => format!(
+            "Expects `git status` to report changes in `{}`",
+            rule.pattern.trim()
+        ),
+        "no_error" | "last_error" => "Expects the run to have no last_error".to_string(),
+        _ => format!("Unsupported rule type {}", rule.rule_type),
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskConstraints {
+    #[serde(default)]
+    pub constraints: Vec<String>,
+    #[serde(default)]
+    pub conventions_path: Option<String>,
+    /// From the task risk policy; unset when no task is configured.
+    #[serde(default)]
+    pub allow_network: Option<bool>,
+    /// The task's autonomy; `auto` writes without a preview.
+    #[serde(default)]
+    pub autonomy: Option<String>,
+}
+
+/// Actions that reach out to the network on their own. MCP servers run
+/// outside the kernel's control, so calls to them count too.
+const NETWORK_ACTIONS: &[&str] = &["deps.audit", "mcp.call"];
+
+/// The actions a run may use, negotiated at start from the profile tool
+/// toggles, the task risk policy and the `--allow-actions` flag.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunCapabilities {
+    pub allowed: Vec<String>,
+    pub denied: Vec<DeniedAction>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DeniedAction {
+    pub action: String,
+    /// "profile", "risk_policy" or "cli".
+    pub source: String,
+}
+
+impl RunCapabilities {
+    fn allowed_set(&self) -> Option<HashSet<String>> {
+        Some(self.allowed.iter().cloned().collect())
+    }
+
+    /// Which source denied `action_type`, or `None` when it is allowed.
+    fn denied_by(&self, action_type: &str) -> Option<&str> {
+        if self.allowed.iter().any(|allowed| allowed == action_type) {
+            return None;
         }
+        Some(
+            self.denied
+                .iter()
+                .find(|denied| denied.action == action_type)
+                .map(|denied| denied.source.as_str())
+                .unwrap_or("policy"),
+        )
     }
 }
 
+enum ConstraintCheck {
+    NoNewDependencies,
+    NoNewFiles,
+    Untouched(String),
+}
+
+const DEPENDENCY_MANIFESTS: &[&str] = &[
+    "Cargo.toml",
+    "package.json",
+    "requirements.txt",
+    "pyproject.toml",
+    "go.mod",
+    "Gemfile",
+    "pom.xml",
+    "build.gradle",
+];
+
 #[derive(Clone, Debug)]
 pub struct JudgeContext {
+    pub workspace: PathBuf,
     pub command: String,
     pub exit_code: i32,
     pub stdout: String,
     pub stderr: String,
+    /// The most recent observation, for `output_matches` rules.
+    pub last_observation: String,
+    /// The plan goal, for rules limited to some kinds of task.
+    pub goal: String,
 }
 
 #[async_trait]
@@ -135,7 +538,13 @@ pub struct KernelConfig {
     pub log_level: String,
 }
 
-const KERNEL_EVENT_NAME: &str = "kernel-event";
+pub const KERNEL_EVENT_NAME: &str = "kernel-event";
+const MAX_PINNED_FILE_BYTES: usize = 4_000;
+const MAX_PINNED_PROMPT_BYTES: usize = 12_000;
+const READ_CHUNK_BYTES: usize = 8_000;
+const MAX_RECENT_OBSERVATIONS: usize = 6;
+const MAX_READ_MANY_FILES: usize = 8;
+const READ_MANY_BUDGET_BYTES: usize = 12_000;
 
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -154,14 +563,18 @@ struct EventBus {
     base_dir: Arc<Mutex<PathBuf>>,
     run_id: Arc<Mutex<String>>,
     seq: Arc<AtomicU64>,
+    narration: Arc<AtomicBool>,
+    at_rest: AtRest,
 }
 
 impl EventBus {
-    fn new(base_dir: PathBuf, run_id: String) -> Self {
+    fn new(base_dir: PathBuf, run_id: String, at_rest: AtRest) -> Self {
         Self {
             base_dir: Arc::new(Mutex::new(base_dir)),
             run_id: Arc::new(Mutex::new(run_id)),
             seq: Arc::new(AtomicU64::new(0)),
+            narration: Arc::new(AtomicBool::new(false)),
+            at_rest,
         }
     }
 
@@ -172,13 +585,26 @@ impl EventBus {
         self.seq.store(0, Ordering::SeqCst);
     }
 
+    /// Switches to an existing run, continuing its sequence numbers.
+    fn resume_run(&self, run_id: String, next_seq: u64) {
+        if let Ok(mut current) = self.run_id.lock() {
+            *current = run_id;
+        }
+        self.seq.store(next_seq, Ordering::SeqCst);
+    }
+
     fn set_base_dir(&self, base_dir: PathBuf) {
         if let Ok(mut current) = self.base_dir.lock() {
             *current = base_dir;
         }
     }
 
-    fn emit<T: Serialize>(&self, app: &AppHandle, event_type: &str, payload: &T) -> KernelEvent {
+    fn emit<T: Serialize>(
+        &self,
+        app: &dyn EventSink,
+        event_type: &str,
+        payload: &T,
+    ) -> KernelEvent {
         let run_id = self
             .run_id
             .lock()
@@ -194,33 +620,163 @@ impl EventBus {
             payload: serde_json::to_value(payload).unwrap_or_else(|_| serde_json::json!({})),
         };
         self.append_event(&event);
-        let _ = app.emit(KERNEL_EVENT_NAME, event.clone());
+        metrics::observe_kernel_event(&event.event_type, &event.payload);
+        if let Ok(value) = serde_json::to_value(&event) {
+            app.emit_json(KERNEL_EVENT_NAME, value);
+        }
+        if self.narration.load(Ordering::SeqCst) {
+            if let Some(text) = narrate(&event.event_type, &event.payload) {
+                app.emit_json(
+                    NARRATION_EVENT_NAME,
+                    serde_json::json!({
+                        "runId": event.run_id,
+                        "seq": event.seq,
+                        "ts": event.ts,
+                        "text": text,
+                    }),
+                );
+            }
+        }
         event
     }
 
     fn append_event(&self, event: &KernelEvent) {
-        let path = self.log_path(&event.run_id);
+        let path = match self.log_path(&event.run_id) {
+            Ok(path) => path,
+            Err(err) => {
+                tracing::warn!("Event not persisted: {}", err);
+                return;
+            }
+        };
         if let Some(parent) = path.parent() {
             let _ = create_dir_all(parent);
         }
-        if let Ok(mut file) = OpenOptions::new().create(true).append(true).open(&path) {
-            if let Ok(line) = serde_json::to_string(event) {
-                let _ = writeln!(file, "{}", line);
-            }
+        let Ok(line) = serde_json::to_string(event) else {
+            return;
+        };
+        if let Err(err) = self.at_rest.append_line(&path, &line) {
+            tracing::warn!("Event not persisted: {}", err);
         }
     }
 
-    fn log_path(&self, run_id: &str) -> PathBuf {
+    fn log_path(&self, run_id: &str) -> Result<PathBuf, String> {
+        validate_run_id(run_id)?;
         let base_dir = self
             .base_dir
             .lock()
             .map(|value| value.clone())
             .unwrap_or_else(|_| PathBuf::from("."));
-        base_dir.join(format!("{}.jsonl", run_id))
+        Ok(base_dir.join(format!("{}.jsonl", run_id)))
+    }
+
+    /// Runs with an event log, in no particular order.
+    fn logged_run_ids(&self) -> Vec<String> {
+        let base_dir = self
+            .base_dir
+            .lock()
+            .map(|value| value.clone())
+            .unwrap_or_else(|_| PathBuf::from("."));
+        let Ok(entries) = std::fs::read_dir(base_dir) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                name.strip_suffix(".jsonl").map(str::to_string)
+            })
+            .collect()
+    }
+
+    fn current_run_id(&self) -> String {
+        self.run_id
+            .lock()
+            .map(|value| value.clone())
+            .unwrap_or_else(|_| "default".to_string())
+    }
+
+    fn read_events(&self, run_id: &str) -> Result<Vec<KernelEvent>, String> {
+        let path = self.log_path(run_id)?;
+        let raw = self
+            .at_rest
+            .read_sealed(&path)
+            .map_err(|e| format!("Unable to read event log: {}", e))?;
+        let mut events = Vec::new();
+        for line in raw.lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            if let Ok(event) = serde_json::from_str::<KernelEvent>(line) {
+                events.push(event);
+            }
+        }
+        events.sort_by_key(|event| event.seq);
+        Ok(events)
     }
 }
 
-#[derive(Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Deserialize)]
+pub struct KernelRebuildStateRequest {
+    pub run_id: Option<String>,
+    pub seq: Option<u64>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KernelForkRunRequest {
+    /// Defaults to the current run.
+    pub run_id: Option<String>,
+    /// Forks the state as it was after this event.
+    pub seq: Option<u64>,
+    /// Keeps only the messages before this index.
+    pub message_index: Option<usize>,
+}
+
+/// Where a forked run branched off.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunFork {
+    pub run_id: String,
+    pub seq: Option<u64>,
+    pub message_index: Option<usize>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct KernelRebuildStateResponse {
+    pub state: RunState,
+    pub last_seq: Option<u64>,
+    pub events_applied: usize,
+    pub consistent: Option<bool>,
+    pub mismatches: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingChangeView {
+    pub id: String,
+    pub action_type: String,
+    pub path: Option<String>,
+    pub command: Option<String>,
+    pub reason: String,
+    pub created_at: u128,
+    pub diff: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct ChangesApplyRequest {
+    pub ids: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ChangesApplyResponse {
+    pub applied: Vec<String>,
+    pub paths: Vec<String>,
+    pub state: RunState,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum RunAgentState {
     Idle,
@@ -229,6 +785,7 @@ pub enum RunAgentState {
     AwaitingUser,
     Error,
     Finished,
+    Interrupted,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -247,6 +804,56 @@ pub struct RunState {
     pub recent_observations: Vec<String>,
     pub auto_run: bool,
     pub last_error: Option<String>,
+    #[serde(default)]
+    pub selected_step_ids: Option<Vec<String>>,
+    #[serde(default)]
+    pub readonly: bool,
+    #[serde(default)]
+    pub pending_changes: Vec<PendingChange>,
+    #[serde(default = "default_auto_clean_sessions")]
+    pub auto_clean_sessions: bool,
+    #[serde(default)]
+    pub pinned_files: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub started_at: u128,
+    /// Fixed when the run starts; runs saved before this was recorded have none.
+    #[serde(default)]
+    pub capabilities: Option<RunCapabilities>,
+    #[serde(default)]
+    pub forked_from: Option<RunFork>,
+    /// When the run last started waiting for the user; cleared once the run
+    /// loop picks it up again.
+    #[serde(default)]
+    pub awaiting_since: Option<u128>,
+    /// Which provider and model made each decision, so failovers show up in
+    /// the saved run.
+    #[serde(default)]
+    pub decision_sources: Vec<DecisionSource>,
+}
+
+/// The provider and model that answered one decision request.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DecisionSource {
+    /// The step the decision was made for, counting from 1.
+    pub step: u32,
+    pub provider: String,
+    pub model: String,
+}
+
+fn default_auto_clean_sessions() -> bool {
+    true
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PendingChange {
+    pub id: String,
+    pub action: Action,
+    pub reason: String,
+    pub created_at: u128,
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -305,6 +912,14 @@ pub struct Task {
 }
 
 impl RunState {
+    /// Keeps the latest observations for the next prompt, dropping the oldest.
+    fn push_observation(&mut self, observation: String) {
+        self.recent_observations.push(observation);
+        if self.recent_observations.len() > MAX_RECENT_OBSERVATIONS {
+            self.recent_observations.remove(0);
+        }
+    }
+
     fn new(run_id: String, cwd: String) -> Self {
         Self {
             run_id,
@@ -326,8 +941,31 @@ impl RunState {
             recent_observations: Vec::new(),
             auto_run: true,
             last_error: None,
+            selected_step_ids: None,
+            readonly: false,
+            pending_changes: Vec::new(),
+            auto_clean_sessions: true,
+            pinned_files: Vec::new(),
+            tags: Vec::new(),
+            started_at: now_ms(),
+            capabilities: None,
+            forked_from: None,
+            awaiting_since: None,
+            decision_sources: Vec::new(),
+        }
+    }
+}
+
+/// Trims, lowercases and de-duplicates user supplied tags, keeping their order.
+pub fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut normalized: Vec<String> = Vec::new();
+    for tag in tags {
+        let tag = tag.trim().to_lowercase();
+        if !tag.is_empty() && !normalized.contains(&tag) {
+            normalized.push(tag);
         }
     }
+    normalized
 }
 
 fn is_stop_command(input: &str) -> bool {
@@ -340,7 +978,10 @@ fn is_stop_command(input: &str) -> bool {
 
 fn is_continue_command(input: &str) -> bool {
     let normalized = input.trim().to_lowercase();
-    matches!(normalized.as_str(), "continue" | "继续" | "继续吧" | "go on" | "继续执行")
+    matches!(
+        normalized.as_str(),
+        "continue" | "继续" | "继续吧" | "go on" | "继续执行"
+    )
 }
 
 fn infer_default_continue_reply(state: &RunState) -> Option<String> {
@@ -374,7 +1015,6 @@ fn infer_default_continue_reply(state: &RunState) -> Option<String> {
     Some("请在当前工作区实际创建/修改文件并继续执行。".to_string())
 }
 
-
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(tag = "type")]
 pub enum Action {
@@ -392,29 +1032,93 @@ pub enum Action {
         cwd: Option<String>,
     },
     #[serde(rename = "fs.read")]
-    FsRead { id: String, path: String },
+    FsRead {
+        id: String,
+        path: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        offset: Option<u64>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        include_hidden: Option<bool>,
+    },
+    #[serde(rename = "fs.read_many")]
+    FsReadMany {
+        id: String,
+        paths: Vec<String>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        include_hidden: Option<bool>,
+    },
     #[serde(rename = "fs.write")]
     FsWrite {
         id: String,
         path: String,
         content: String,
     },
+    #[serde(rename = "fs.apply_patch")]
+    FsApplyPatch {
+        id: String,
+        path: String,
+        patch: String,
+    },
     #[serde(rename = "fs.search")]
     FsSearch {
         id: String,
         pattern: String,
         paths: Option<Vec<String>>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        include_hidden: Option<bool>,
     },
     #[serde(rename = "git.status")]
     GitStatus { id: String },
     #[serde(rename = "git.diff")]
     GitDiff { id: String, path: Option<String> },
+    #[serde(rename = "git.commit")]
+    GitCommit {
+        id: String,
+        message: String,
+        #[serde(default, skip_serializing_if = "Vec::is_empty")]
+        paths: Vec<String>,
+    },
+    #[serde(rename = "git.branch")]
+    GitBranch {
+        id: String,
+        name: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        start_point: Option<String>,
+        #[serde(default)]
+        checkout: bool,
+    },
+    #[serde(rename = "git.stash")]
+    GitStash {
+        id: String,
+        #[serde(default)]
+        pop: bool,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        message: Option<String>,
+    },
+    #[serde(rename = "git.checkout")]
+    GitCheckout { id: String, target: String },
     #[serde(rename = "tests.run")]
     TestsRun {
         id: String,
         program: String,
         args: Vec<String>,
     },
+    #[serde(rename = "deps.audit")]
+    DepsAudit { id: String },
+    #[serde(rename = "code.todos")]
+    CodeTodos {
+        id: String,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        path: Option<String>,
+    },
+    #[serde(rename = "mcp.call")]
+    McpCall {
+        id: String,
+        server: String,
+        tool: String,
+        #[serde(default)]
+        arguments: serde_json::Value,
+    },
     #[serde(rename = "plan.update")]
     PlanUpdate { id: String, plan: Plan },
     #[serde(rename = "task.update")]
@@ -435,9 +1139,37 @@ pub struct Observation {
     pub requires_user: bool,
 }
 
+struct PendingInstall {
+    dependency: MissingDependency,
+    approved: bool,
+}
+
+/// An `fs.write` held back by the write preview until the user decides.
+struct PendingWrite {
+    action: Action,
+    approved: bool,
+}
+
+/// An action that passed its checks and is ready to execute.
+struct PreparedAction {
+    action: Action,
+    path_note: Option<String>,
+    content_before: Option<String>,
+    hook_results: Vec<HookResult>,
+}
+
+enum ActionGate {
+    Ready(PreparedAction),
+    Skip,
+    Stop,
+}
+
 struct LlmDecision {
     message: Option<String>,
     actions: Vec<Action>,
+    errors: Vec<String>,
+    /// The provider and model that answered, which may be a failover.
+    source: DecisionSource,
 }
 
 #[derive(Clone)]
@@ -445,6 +1177,8 @@ struct Runtime {
     terminal: TerminalManager,
     workspace: WorkspaceState,
     audit: AuditLog,
+    summaries: Arc<SummaryPipeline>,
+    mcp: McpManager,
 }
 
 impl Runtime {
@@ -453,6 +1187,8 @@ impl Runtime {
             terminal,
             workspace,
             audit,
+            summaries: Arc::new(SummaryPipeline::with_defaults()),
+            mcp: McpManager::new(),
         }
     }
 
@@ -464,8 +1200,15 @@ impl Runtime {
     ) -> Result<Observation, String> {
         let result = match action {
             Action::TerminalExec { cmd, cwd, .. } => {
+                let shell = session_id
+                    .as_deref()
+                    .and_then(|id| self.terminal.session_shell(id))
+                    .unwrap_or_else(|| default_shell().to_string());
+                let translation = translate_command(cmd, &shell);
                 let request = TerminalExecRequest {
-                    command: cmd.clone(),
+                    command: translation
+                        .as_ref()
+                        .map_or_else(|| cmd.clone(), |translation| translation.command.clone()),
                     session_id,
                     shell: None,
                     cwd: cwd.clone(),
@@ -474,21 +1217,24 @@ impl Runtime {
                     timeout_ms: Some(15_000),
                     max_bytes: Some(24_000),
                 };
-                let resolved_cwd = match cwd {
-                    Some(path) => self.workspace.resolve_path(path)?,
-                    None => self.workspace.root(),
+                let resolved_cwd = match self.resolve_cwd(cwd.as_deref()) {
+                    Ok(path) => path,
+                    Err(observation) => return Ok(observation),
                 };
-                self.terminal.exec_interactive(request, resolved_cwd, &self.audit)
+                let result = self
+                    .terminal
+                    .exec_interactive(request, resolved_cwd, &self.audit);
+                match translation {
+                    Some(translation) => result.map(|result| with_translation(result, translation)),
+                    None => result,
+                }
             }
             Action::TerminalRun {
-                program,
-                args,
-                cwd,
-                ..
+                program, args, cwd, ..
             } => {
-                let resolved_cwd = match cwd {
-                    Some(path) => self.workspace.resolve_path(path)?,
-                    None => self.workspace.root(),
+                let resolved_cwd = match self.resolve_cwd(cwd.as_deref()) {
+                    Ok(path) => path,
+                    Err(observation) => return Ok(observation),
                 };
                 run_command(
                     CommandRequest {
@@ -502,13 +1248,23 @@ impl Runtime {
                     &self.audit,
                 )
             }
-            Action::FsRead { path, .. } => read_file_tool(&self.workspace, &self.audit, path),
-            Action::FsSearch { pattern, paths, .. } => {
-                search_tool(&self.workspace, &self.audit, pattern, paths)
+            Action::FsRead { path, offset, .. } => {
+                read_file_tool(&self.workspace, &self.audit, path, offset.unwrap_or(0))
             }
-            Action::TestsRun {
-                program, args, ..
-            } => {
+            Action::FsReadMany { paths, .. } => read_many_tool(&self.workspace, &self.audit, paths),
+            Action::FsSearch {
+                pattern,
+                paths,
+                include_hidden,
+                ..
+            } => search_tool(
+                &self.workspace,
+                &self.audit,
+                pattern,
+                paths,
+                *include_hidden,
+            ),
+            Action::TestsRun { program, args, .. } => {
                 let cwd = self.workspace.root();
                 run_command(
                     CommandRequest {
@@ -527,11 +1283,16 @@ impl Runtime {
                 run_command(
                     CommandRequest {
                         program: "git".to_string(),
-                        args: Some(vec![
-                            "status".to_string(),
-                            "--porcelain=v1".to_string(),
-                            "--untracked-files=all".to_string(),
-                        ]),
+                        args: Some(
+                            [
+                                "status".to_string(),
+                                "--porcelain=v1".to_string(),
+                                "--untracked-files=all".to_string(),
+                            ]
+                            .into_iter()
+                            .chain(without_scratch_pathspec())
+                            .collect(),
+                        ),
                         cwd: Some(cwd.to_string_lossy().to_string()),
                         env: None,
                         timeout_ms: None,
@@ -547,6 +1308,8 @@ impl Runtime {
                     let resolved = self.workspace.resolve_path(path)?;
                     args.push("--".to_string());
                     args.push(resolved.to_string_lossy().to_string());
+                } else {
+                    args.extend(without_scratch_pathspec());
                 }
                 run_command(
                     CommandRequest {
@@ -560,35 +1323,156 @@ impl Runtime {
                     &self.audit,
                 )
             }
+            Action::GitCommit { message, paths, .. } => git_commit(
+                &self.workspace,
+                &self.audit,
+                &GitCommitRequest {
+                    message: message.clone(),
+                    paths: paths.clone(),
+                },
+                "agent",
+            ),
+            Action::GitBranch {
+                name,
+                start_point,
+                checkout,
+                ..
+            } => git_branch(
+                &self.workspace,
+                &self.audit,
+                &GitBranchRequest {
+                    name: name.clone(),
+                    start_point: start_point.clone(),
+                    checkout: *checkout,
+                },
+                "agent",
+            ),
+            Action::GitStash { pop, message, .. } => git_stash(
+                &self.workspace,
+                &self.audit,
+                &GitStashRequest {
+                    pop: *pop,
+                    message: message.clone(),
+                },
+                "agent",
+            ),
+            Action::GitCheckout { target, .. } => git_checkout(
+                &self.workspace,
+                &self.audit,
+                &GitCheckoutRequest {
+                    target: target.clone(),
+                },
+                "agent",
+            ),
+            Action::DepsAudit { .. } => {
+                let root = self.workspace.root();
+                let policy = load_deps_policy(&root);
+                Ok(audit_dependencies(&root, &policy, &self.audit))
+            }
+            Action::CodeTodos { path, .. } => {
+                let root = self.workspace.root();
+                let scope = match path {
+                    Some(path) => Some(self.workspace.resolve_path(path)?),
+                    None => None,
+                };
+                let todos = scan_todos(&root, scope.as_deref())?;
+                Ok(todos_tool_result(&todos, &self.audit))
+            }
+            Action::McpCall {
+                server,
+                tool,
+                arguments,
+                ..
+            } => self.mcp.call_tool(server, tool, arguments, &self.audit),
             Action::FsWrite { path, content, .. } => {
                 let resolved = self.workspace.resolve_path_for_write(path)?;
                 if let Some(parent) = resolved.parent() {
                     create_dir_all(parent).map_err(|e| e.to_string())?;
                 }
+                let previous = std::fs::read_to_string(&resolved).ok();
                 std::fs::write(&resolved, content.as_bytes()).map_err(|e| e.to_string())?;
                 let request = WriteFileRequest {
                     path: path.clone(),
                     content: content.clone(),
                 };
-                Ok(write_file(request, content.len(), &self.audit))
+                let changes = change_summary(path, previous.as_deref(), content);
+                let mut result = write_file(request, content.len(), &self.audit);
+                result.stdout_excerpt = Some(changes.describe());
+                if let Some(serde_json::Value::Object(artifacts)) = result.artifacts.as_mut() {
+                    artifacts.insert("changes".to_string(), serde_json::json!(changes));
+                }
+                Ok(result)
             }
-            Action::PlanUpdate { .. }
-            | Action::TaskUpdate { .. }
-            | Action::UserAsk { .. } => {
-                return Ok(Observation {
-                    ok: true,
-                    summary: "State update".to_string(),
-                    exit_code: None,
+            Action::FsApplyPatch { path, patch, .. } => {
+                let resolved = self.workspace.resolve_path_for_write(path)?;
+                let previous = patch::read_patch_target(&resolved)?;
+                let outcome = patch::apply_patch(previous.as_deref().unwrap_or_default(), patch)?;
+                let request = ApplyPatchRequest {
+                    path: path.clone(),
+                    patch: patch.clone(),
+                };
+                match outcome.content.as_deref() {
+                    Some(content) => {
+                        if let Some(parent) = resolved.parent() {
+                            create_dir_all(parent).map_err(|e| e.to_string())?;
+                        }
+                        std::fs::write(&resolved, content.as_bytes()).map_err(|e| e.to_string())?;
+                        let changes = change_summary(path, previous.as_deref(), content);
+                        let mut result =
+                            apply_patch(&request, &outcome, content.len(), &self.audit);
+                        result.stdout_excerpt =
+                            Some(format!("{}\n{}", changes.describe(), outcome.describe()));
+                        if let Some(serde_json::Value::Object(artifacts)) =
+                            result.artifacts.as_mut()
+                        {
+                            artifacts.insert("changes".to_string(), serde_json::json!(changes));
+                        }
+                        Ok(result)
+                    }
+                    // Nothing is written unless every hunk applies.
+                    None => Ok(apply_patch(&request, &outcome, 0, &self.audit)),
+                }
+            }
+            Action::PlanUpdate { .. } | Action::TaskUpdate { .. } | Action::UserAsk { .. } => {
+                return Ok(Observation {
+                    ok: true,
+                    summary: "State update".to_string(),
+                    exit_code: None,
                     artifacts: None,
                     raw: None,
                     requires_user: false,
                 });
             }
         }?;
-        let observation = tool_result_to_observation(result, on_chunk);
+        let mut observation = tool_result_to_observation(result, on_chunk);
+        if matches!(
+            action,
+            Action::TerminalExec { .. } | Action::TerminalRun { .. } | Action::TestsRun { .. }
+        ) {
+            attach_diagnostics(&mut observation);
+        }
+        self.summaries.apply(action_type(action), &mut observation);
         Ok(observation)
     }
 
+    /// A bad cwd becomes a failed observation with directory suggestions rather
+    /// than a runtime error, so the model can retry with a valid directory.
+    fn resolve_cwd(&self, cwd: Option<&str>) -> Result<PathBuf, Observation> {
+        let Some(cwd) = cwd else {
+            return Ok(self.workspace.root());
+        };
+        self.workspace
+            .resolve_dir(cwd)
+            .map_err(|diagnosis| Observation {
+                ok: false,
+                summary: diagnosis.describe(),
+                exit_code: None,
+                artifacts: Some(serde_json::json!({ "cwd": diagnosis })),
+                raw: None,
+                requires_user: false,
+            })
+    }
+
     fn dispatch(
         &self,
         action: &Action,
@@ -610,14 +1494,29 @@ impl ToolDispatcher for Runtime {
     }
 }
 
+/// Run ids come from requests too, so only plain names such as UUIDs may
+/// become a file name under the runs or event log directory.
+fn validate_run_id(run_id: &str) -> Result<(), String> {
+    let valid = !run_id.is_empty()
+        && run_id
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid run id: {}", run_id))
+    }
+}
+
 #[derive(Clone)]
 struct StateStore {
     base_dir: PathBuf,
+    at_rest: AtRest,
 }
 
 impl StateStore {
-    fn new(base_dir: PathBuf) -> Self {
-        Self { base_dir }
+    fn new(base_dir: PathBuf, at_rest: AtRest) -> Self {
+        Self { base_dir, at_rest }
     }
 
     fn set_base_dir(&mut self, base_dir: PathBuf) {
@@ -625,12 +1524,51 @@ impl StateStore {
     }
 
     fn save(&self, state: &RunState) -> Result<(), String> {
+        validate_run_id(&state.run_id)?;
         let path = self.base_dir.join(format!("{}.json", state.run_id));
         if let Some(parent) = path.parent() {
             create_dir_all(parent).map_err(|e| e.to_string())?;
         }
-        let data = serde_json::to_vec_pretty(state).map_err(|e| e.to_string())?;
-        std::fs::write(path, data).map_err(|e| e.to_string())
+        let data = serde_json::to_string_pretty(state).map_err(|e| e.to_string())?;
+        self.at_rest.write_sealed(&path, &data)
+    }
+
+    fn load(&self, run_id: &str) -> Option<RunState> {
+        validate_run_id(run_id).ok()?;
+        let path = self.base_dir.join(format!("{}.json", run_id));
+        let raw = self.at_rest.read_sealed(&path).ok()?;
+        serde_json::from_str(&raw).ok()
+    }
+
+    /// Every persisted run with the time its state was last written.
+    fn list(&self) -> Vec<(RunState, u128)> {
+        let Ok(entries) = std::fs::read_dir(&self.base_dir) else {
+            return Vec::new();
+        };
+        let mut runs = Vec::new();
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                continue;
+            }
+            let Some(state) = self
+                .at_rest
+                .read_sealed(&path)
+                .ok()
+                .and_then(|raw| serde_json::from_str::<RunState>(&raw).ok())
+            else {
+                continue;
+            };
+            let updated_at = entry
+                .metadata()
+                .and_then(|meta| meta.modified())
+                .ok()
+                .and_then(|modified| modified.duration_since(std::time::UNIX_EPOCH).ok())
+                .map(|elapsed| elapsed.as_millis())
+                .unwrap_or(0);
+            runs.push((state, updated_at));
+        }
+        runs
     }
 }
 
@@ -642,8 +1580,26 @@ pub struct KernelManager {
     events: EventBus,
     llm: LlmStore,
     judge: Arc<Mutex<JudgeEngine>>,
+    constraints: Arc<Mutex<TaskConstraints>>,
+    /// Failover state per profile name.
+    provider_health: Arc<Mutex<HashMap<String, ProviderHealth>>>,
+    usage: UsageTracker,
+    rate_limiter: Arc<Mutex<RateLimiter>>,
+    regressions: Arc<Mutex<RegressionWatchdog>>,
+    acknowledged_secrets: Arc<Mutex<HashSet<String>>>,
+    /// Findings the user was asked about; acknowledged only on "continue".
+    pending_secrets: Arc<Mutex<Vec<String>>>,
+    pending_install: Arc<Mutex<Option<PendingInstall>>>,
+    pending_write: Arc<Mutex<Option<PendingWrite>>>,
+    reviewer: Arc<AtomicBool>,
+    cli_allowed_actions: Arc<Mutex<Option<HashSet<String>>>>,
+    /// In-flight plan generations by generation id.
+    plan_generations: Arc<Mutex<HashMap<String, CancellationToken>>>,
+    /// Set by `start` so only a fresh run goes through pre-flight checks.
+    preflight_pending: Arc<AtomicBool>,
     paused: Arc<AtomicBool>,
     running: Arc<AtomicBool>,
+    at_rest: AtRest,
 }
 
 #[derive(Deserialize)]
@@ -651,6 +1607,11 @@ pub struct KernelStartRequest {
     pub session_id: Option<String>,
     pub max_steps: Option<u32>,
     pub task_id: Option<String>,
+    #[serde(default)]
+    pub readonly: bool,
+    pub auto_clean_sessions: Option<bool>,
+    #[serde(default)]
+    pub tags: Vec<String>,
 }
 
 #[derive(Deserialize)]
@@ -660,11 +1621,21 @@ pub struct KernelUserInputRequest {
     pub chat_only: bool,
 }
 
+#[derive(Deserialize)]
+pub struct KernelApproveActionRequest {
+    pub action_id: String,
+    pub approved: bool,
+}
+
 #[derive(Deserialize)]
 pub struct KernelPlanUpdateRequest {
     pub goal: String,
     pub steps: Vec<String>,
     pub auto_generate: Option<bool>,
+    /// Lets the caller cancel an auto-generation it starts; one is made up
+    /// when unset and reported with `PlanGenerationStarted`.
+    #[serde(default)]
+    pub generation_id: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -673,6 +1644,12 @@ pub struct KernelPlanStatusRequest {
     pub status: String,
 }
 
+#[derive(Deserialize)]
+pub struct KernelRunStepsRequest {
+    pub step_ids: Vec<String>,
+    pub session_id: Option<String>,
+}
+
 impl KernelManager {
     pub fn new(
         workspace_root: PathBuf,
@@ -683,34 +1660,72 @@ impl KernelManager {
     ) -> Self {
         let run_id = "default".to_string();
         let state = RunState::new(run_id.clone(), display_path(&workspace_root));
-        let events = EventBus::new(
-            workspace_root.join(".taurihands").join("events"),
-            run_id,
-        );
-        let store = StateStore::new(workspace_root.join(".taurihands").join("runs"));
+        let data_dir = workspace_data_dir(&workspace_root);
+        let at_rest = AtRest::for_workspace(&workspace_root);
+        audit.set_at_rest(at_rest.clone());
+        let events = EventBus::new(data_dir.join("events"), run_id, at_rest.clone());
+        let store = StateStore::new(data_dir.join("runs"), at_rest.clone());
         let llm = LlmStore::new(llm_root);
+        configure_command_policy(&workspace_root);
+        let runtime = Runtime::new(terminal, workspace, audit);
+        runtime.mcp.configure(&workspace_root);
         Self {
             state: Arc::new(Mutex::new(state)),
-            runtime: Runtime::new(terminal, workspace, audit),
+            runtime,
             store: Arc::new(Mutex::new(store)),
             events,
             llm,
             judge: Arc::new(Mutex::new(JudgeEngine::new())),
+            constraints: Arc::new(Mutex::new(TaskConstraints::default())),
+            provider_health: Arc::new(Mutex::new(HashMap::new())),
+            usage: UsageTracker::new(),
+            rate_limiter: Arc::new(Mutex::new(RateLimiter::with_defaults())),
+            regressions: Arc::new(Mutex::new(RegressionWatchdog::default())),
+            acknowledged_secrets: Arc::new(Mutex::new(HashSet::new())),
+            pending_secrets: Arc::new(Mutex::new(Vec::new())),
+            pending_install: Arc::new(Mutex::new(None)),
+            pending_write: Arc::new(Mutex::new(None)),
+            reviewer: Arc::new(AtomicBool::new(false)),
+            cli_allowed_actions: Arc::new(Mutex::new(None)),
+            plan_generations: Arc::new(Mutex::new(HashMap::new())),
+            preflight_pending: Arc::new(AtomicBool::new(false)),
             paused: Arc::new(AtomicBool::new(false)),
             running: Arc::new(AtomicBool::new(false)),
+            at_rest,
         }
     }
 
     pub fn update_workspace_root(&self, root: PathBuf) {
+        self.at_rest.configure(&root);
+        configure_command_policy(&root);
+        self.runtime.mcp.configure(&root);
+        let data_dir = workspace_data_dir(&root);
         if let Ok(mut store) = self.store.lock() {
-            store.set_base_dir(root.join(".taurihands").join("runs"));
+            store.set_base_dir(data_dir.join("runs"));
         }
-        self.events.set_base_dir(root.join(".taurihands").join("events"));
+        self.events.set_base_dir(data_dir.join("events"));
         let _ = self.update_state(|state| {
             state.tool_context.cwd = display_path(&root);
         });
     }
 
+    /// Encryption state of the kernel's workspace.
+    pub fn at_rest(&self) -> &AtRest {
+        &self.at_rest
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.running.load(Ordering::SeqCst)
+    }
+
+    pub fn mcp(&self) -> &McpManager {
+        &self.runtime.mcp
+    }
+
+    pub fn set_llm_root(&self, root: PathBuf) {
+        self.llm.set_root(root);
+    }
+
     pub fn get_llm_profile(&self) -> Option<LlmProfile> {
         self.llm.get_active_profile()
     }
@@ -744,6 +1759,364 @@ impl KernelManager {
         Ok(judge.rules().to_vec())
     }
 
+    pub fn set_task_constraints(&self, constraints: TaskConstraints) -> Result<(), String> {
+        let mut current = self
+            .constraints
+            .lock()
+            .map_err(|_| "Constraints lock poisoned".to_string())?;
+        *current = constraints;
+        Ok(())
+    }
+
+    pub fn get_task_constraints(&self) -> Result<TaskConstraints, String> {
+        self.constraints
+            .lock()
+            .map(|constraints| constraints.clone())
+            .map_err(|_| "Constraints lock poisoned".to_string())
+    }
+
+    fn constraints_prompt(&self) -> String {
+        let constraints = self.get_task_constraints().unwrap_or_default();
+        let mut prompt = String::new();
+        let rules: Vec<&String> = constraints
+            .constraints
+            .iter()
+            .filter(|rule| !rule.trim().is_empty())
+            .collect();
+        if !rules.is_empty() {
+            prompt.push_str("Hard rules for this task (never violate these):\n");
+            for rule in rules {
+                prompt.push_str(&format!("- {}\n", trim_to(rule.trim(), 400)));
+            }
+        }
+        let conventions = constraints
+            .conventions_path
+            .as_deref()
+            .map(str::trim)
+            .filter(|path| !path.is_empty());
+        if let Some(path) = conventions {
+            let content = self
+                .runtime
+                .workspace
+                .resolve_path(path)
+                .and_then(|resolved| std::fs::read_to_string(resolved).map_err(|e| e.to_string()));
+            if let Ok(content) = content {
+                prompt.push_str(&format!(
+                    "Project conventions ({}), treat as hard rules:\n{}\n",
+                    path,
+                    trim_to(content.trim(), MAX_PINNED_FILE_BYTES)
+                ));
+            }
+        }
+        prompt
+    }
+
+    fn select_llm_profile(&self, profile: &LlmProfile) -> LlmProfile {
+        self.provider_health
+            .lock()
+            .map(|mut health| {
+                health
+                    .entry(profile.profile_name.clone())
+                    .or_default()
+                    .select(profile)
+            })
+            .unwrap_or_else(|_| profile.clone())
+    }
+
+    fn record_llm_result(
+        &self,
+        app: &dyn EventSink,
+        profile: &LlmProfile,
+        used: &LlmProfile,
+        result: &Result<String, String>,
+        usage: TokenUsage,
+    ) {
+        self.record_llm_usage(app, used, usage);
+        let Ok(mut profiles) = self.provider_health.lock() else {
+            return;
+        };
+        let health = profiles.entry(profile.profile_name.clone()).or_default();
+        let failover = match result {
+            Ok(_) => {
+                health.record_success();
+                None
+            }
+            Err(err) => health.record_failure(profile, used, err),
+        };
+        drop(profiles);
+        if let Some(failover) = failover {
+            self.events.emit(
+                app,
+                "ProviderFailover",
+                &serde_json::json!({ "failover": failover }),
+            );
+        }
+    }
+
+    /// Adds the request to the current run's usage and reports it with an
+    /// `LlmUsage` event. Requests the provider reported no tokens for are
+    /// left out, and bookkeeping failures never fail the step.
+    fn record_llm_usage(&self, app: &dyn EventSink, used: &LlmProfile, usage: TokenUsage) {
+        if usage.prompt_tokens == 0 && usage.completion_tokens == 0 {
+            return;
+        }
+        let run_id = self
+            .state
+            .lock()
+            .map(|state| state.run_id.clone())
+            .unwrap_or_default();
+        let root = self.runtime.workspace.root();
+        let Ok(cost) = self.usage.record(&root, &run_id, used, usage) else {
+            return;
+        };
+        self.events.emit(
+            app,
+            "LlmUsage",
+            &serde_json::json!({
+                "runId": run_id,
+                "profile": used.profile_name,
+                "model": used.model,
+                "usage": usage,
+                "costUsd": cost,
+            }),
+        );
+    }
+
+    pub fn get_usage(&self, run_id: Option<String>) -> Result<UsageReport, String> {
+        let root = self.runtime.workspace.root();
+        let run_id = run_id.filter(|id| !id.trim().is_empty());
+        self.usage.report(&root, run_id.as_deref())
+    }
+
+    pub fn set_rate_limits(&self, limits: Vec<ActionRateLimit>) -> Result<(), String> {
+        let mut limiter = self
+            .rate_limiter
+            .lock()
+            .map_err(|_| "Rate limiter lock poisoned".to_string())?;
+        limiter.set_limits(limits);
+        Ok(())
+    }
+
+    pub fn get_rate_limits(&self) -> Result<Vec<ActionRateLimit>, String> {
+        let limiter = self
+            .rate_limiter
+            .lock()
+            .map_err(|_| "Rate limiter lock poisoned".to_string())?;
+        Ok(limiter.limits().to_vec())
+    }
+
+    /// Applies the visibility policy to the paths an action names and reports
+    /// every hidden path it touches with a `HiddenPathAccess` event. The run's
+    /// own scratch directory is always accessible.
+    fn check_visibility(
+        &self,
+        app: &dyn EventSink,
+        action: &Action,
+        run_id: &str,
+    ) -> Result<(), String> {
+        let (paths, include_hidden): (Vec<&str>, Option<bool>) = match action {
+            Action::FsRead {
+                path,
+                include_hidden,
+                ..
+            } => (vec![path.as_str()], *include_hidden),
+            Action::FsReadMany {
+                paths,
+                include_hidden,
+                ..
+            } => (paths.iter().map(String::as_str).collect(), *include_hidden),
+            Action::FsSearch {
+                paths,
+                include_hidden,
+                ..
+            } => (
+                paths
+                    .iter()
+                    .flatten()
+                    .map(String::as_str)
+                    .filter(|path| !is_glob_like(path))
+                    .collect(),
+                *include_hidden,
+            ),
+            Action::FsWrite { path, .. } | Action::FsApplyPatch { path, .. } => {
+                (vec![path.as_str()], None)
+            }
+            _ => return Ok(()),
+        };
+        let root = self.runtime.workspace.root();
+        let policy = load_visibility_policy(&root);
+        let include_hidden = policy.agent_include_hidden(include_hidden);
+        let scratch = scratch_relative(run_id);
+        let mut hidden = Vec::new();
+        let in_scratch = |path: &str| path.starts_with(&scratch) && !path.contains("..");
+        for path in paths.into_iter().filter(|path| !in_scratch(path)) {
+            match policy.check_access(&root, path, include_hidden) {
+                Ok(true) => hidden.push(path),
+                Ok(false) => {}
+                Err(err) => {
+                    self.events.emit(
+                        app,
+                        "HiddenPathAccess",
+                        &serde_json::json!({
+                            "action_id": action_id(action),
+                            "paths": [path],
+                            "allowed": false,
+                        }),
+                    );
+                    return Err(err);
+                }
+            }
+        }
+        if !hidden.is_empty() {
+            self.events.emit(
+                app,
+                "HiddenPathAccess",
+                &serde_json::json!({
+                    "action_id": action_id(action),
+                    "paths": hidden,
+                    "allowed": true,
+                }),
+            );
+        }
+        Ok(())
+    }
+
+    fn check_rate_limit(&self, action: &Action) -> Result<(), String> {
+        let mut limiter = self
+            .rate_limiter
+            .lock()
+            .map_err(|_| "Rate limiter lock poisoned".to_string())?;
+        limiter.check(action_type(action), now_ms())
+    }
+
+    pub fn rebuild_state(
+        &self,
+        request: KernelRebuildStateRequest,
+    ) -> Result<KernelRebuildStateResponse, String> {
+        let run_id = request
+            .run_id
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .unwrap_or_else(|| self.events.current_run_id());
+        let events = self.events.read_events(&run_id)?;
+        let cwd = display_path(&self.runtime.workspace.root());
+        let mut state = RunState::new(run_id.clone(), cwd);
+        let mut last_seq = None;
+        let mut events_applied = 0usize;
+        for event in &events {
+            if let Some(limit) = request.seq {
+                if event.seq > limit {
+                    break;
+                }
+            }
+            apply_event_to_state(&mut state, event);
+            last_seq = Some(event.seq);
+            events_applied += 1;
+        }
+        let replayed_all = events_applied == events.len();
+        let mut consistent = None;
+        let mut mismatches = Vec::new();
+        if replayed_all {
+            let persisted = self
+                .store
+                .lock()
+                .map_err(|_| "State store lock poisoned".to_string())?
+                .load(&run_id);
+            if let Some(persisted) = persisted {
+                mismatches = diff_run_states(&state, &persisted);
+                consistent = Some(mismatches.is_empty());
+            }
+        }
+        Ok(KernelRebuildStateResponse {
+            state,
+            last_seq,
+            events_applied,
+            consistent,
+            mismatches,
+        })
+    }
+
+    pub fn current_run_events(&self) -> Result<Vec<KernelEvent>, String> {
+        self.events.read_events(&self.events.current_run_id())
+    }
+
+    /// The event log of `run_id`, or of the current run, ordered by `seq`.
+    pub fn run_events(&self, run_id: Option<&str>) -> Result<(String, Vec<KernelEvent>), String> {
+        let run_id = run_id
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .map(str::to_string)
+            .unwrap_or_else(|| self.events.current_run_id());
+        let events = self.events.read_events(&run_id)?;
+        Ok((run_id, events))
+    }
+
+    pub fn logged_run_ids(&self) -> Vec<String> {
+        self.events.logged_run_ids()
+    }
+
+    /// Returns the state and event log of `run_id`, or of the current run.
+    pub fn run_record(&self, run_id: Option<&str>) -> Result<(RunState, Vec<KernelEvent>), String> {
+        let current = self.snapshot();
+        let run_id = run_id
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .unwrap_or(&current.run_id)
+            .to_string();
+        let state = if run_id == current.run_id {
+            current
+        } else {
+            self.store
+                .lock()
+                .map_err(|_| "State store lock poisoned".to_string())?
+                .load(&run_id)
+                .ok_or_else(|| format!("Run not found: {}", run_id))?
+        };
+        let events = self.events.read_events(&run_id).unwrap_or_default();
+        Ok((state, events))
+    }
+
+    /// Replaces the tags of `run_id`, or of the current run.
+    pub fn set_run_tags(
+        &self,
+        app: &dyn EventSink,
+        run_id: Option<&str>,
+        tags: Vec<String>,
+    ) -> Result<RunState, String> {
+        let tags = normalize_tags(tags);
+        let current = self.snapshot().run_id;
+        let run_id = run_id
+            .map(str::trim)
+            .filter(|value| !value.is_empty())
+            .unwrap_or(&current)
+            .to_string();
+        validate_run_id(&run_id)?;
+        if run_id == current {
+            let snapshot = self.update_state(|state| state.tags = tags)?;
+            self.emit_state(app, "tags");
+            return Ok(snapshot);
+        }
+        let store = self
+            .store
+            .lock()
+            .map_err(|_| "State store lock poisoned".to_string())?;
+        let mut state = store
+            .load(&run_id)
+            .ok_or_else(|| format!("Run not found: {}", run_id))?;
+        state.tags = tags;
+        store.save(&state)?;
+        Ok(state)
+    }
+
+    /// Persisted runs of the workspace, paired with when each was last saved.
+    pub fn list_runs(&self) -> Result<Vec<(RunState, u128)>, String> {
+        let store = self
+            .store
+            .lock()
+            .map_err(|_| "State store lock poisoned".to_string())?;
+        Ok(store.list())
+    }
+
     pub fn snapshot(&self) -> RunState {
         self.state
             .lock()
@@ -751,12 +2124,36 @@ impl KernelManager {
             .unwrap_or_else(|_| RunState::new("default".to_string(), "".to_string()))
     }
 
-    pub fn start(&self, app: AppHandle, request: KernelStartRequest) -> Result<RunState, String> {
+    pub fn start(
+        &self,
+        app: &dyn EventSink,
+        request: KernelStartRequest,
+    ) -> Result<RunState, String> {
         if self.running.swap(true, Ordering::SeqCst) {
             return Err("Kernel already running".to_string());
         }
         let run_id = Uuid::new_v4().to_string();
         self.events.set_run(run_id.clone());
+        if let Ok(mut watchdog) = self.regressions.lock() {
+            watchdog.reset();
+        }
+        if let Ok(mut acknowledged) = self.acknowledged_secrets.lock() {
+            acknowledged.clear();
+        }
+        if let Ok(mut pending) = self.pending_secrets.lock() {
+            pending.clear();
+        }
+        if let Ok(mut pending) = self.pending_install.lock() {
+            *pending = None;
+        }
+        if let Ok(mut pending) = self.pending_write.lock() {
+            *pending = None;
+        }
+        let capabilities = self.negotiate_capabilities();
+        let session_id = request
+            .session_id
+            .clone()
+            .or_else(|| self.open_run_session(app, &run_id, request.task_id.clone()));
         let snapshot = {
             let mut state = self
                 .state
@@ -768,74 +2165,756 @@ impl KernelManager {
             let existing_messages = state.messages.clone();
             let existing_turn = state.turn;
             let existing_task_id = state.task_id.clone();
+            let existing_pending = std::mem::take(&mut state.pending_changes);
+            let existing_pinned = std::mem::take(&mut state.pinned_files);
             *state = RunState::new(run_id.clone(), cwd);
+            state.pinned_files = existing_pinned;
+            state.readonly = request.readonly || self.reviewer.load(Ordering::SeqCst);
+            if let Some(auto_clean) = request.auto_clean_sessions {
+                state.auto_clean_sessions = auto_clean;
+            }
+            state.pending_changes = existing_pending;
             state.plan = existing_plan;
             state.tasks = existing_tasks;
             state.messages = existing_messages;
             state.turn = existing_turn;
             state.task_id = request.task_id.clone().or(existing_task_id);
             state.agent_state = RunAgentState::Running;
-            state.tool_context.session_id = request.session_id.clone();
+            state.tags = normalize_tags(request.tags.clone());
+            state.tool_context.session_id = session_id;
             if let Some(max_steps) = request.max_steps {
                 state.budget.max_steps = max_steps;
             }
+            state.capabilities = Some(capabilities.clone());
             state.clone()
         };
-        self.emit_state(&app, "start");
+        self.events.emit(
+            app,
+            "CapabilitiesNegotiated",
+            &serde_json::json!({ "capabilities": capabilities }),
+        );
+        metrics::record_run_started();
+        self.preflight_pending.store(true, Ordering::SeqCst);
+        self.emit_state(app, "start");
         let manager = self.clone();
+        let app = app.clone_sink();
         tauri::async_runtime::spawn(async move {
             manager.run_loop(app).await;
         });
         Ok(snapshot)
     }
 
-    pub fn pause(&self, app: &AppHandle) -> Result<RunState, String> {
-        self.paused.store(true, Ordering::SeqCst);
+    /// Replaces the idle run with a new one seeded from an imported transcript.
+    pub fn import_run(
+        &self,
+        app: &dyn EventSink,
+        transcript: ImportedTranscript,
+    ) -> Result<RunState, String> {
+        if self.running.load(Ordering::SeqCst) {
+            return Err("Stop the current run before importing a transcript".to_string());
+        }
+        let run_id = Uuid::new_v4().to_string();
+        self.events.set_run(run_id.clone());
+        let cwd = display_path(&self.runtime.workspace.root());
+        let message_count = transcript.messages.len();
         let snapshot = self.update_state(|state| {
-            if state.agent_state == RunAgentState::Running {
-                state.agent_state = RunAgentState::Paused;
+            let pinned = std::mem::take(&mut state.pinned_files);
+            *state = RunState::new(run_id.clone(), cwd);
+            state.pinned_files = pinned;
+            state.readonly = self.reviewer.load(Ordering::SeqCst);
+            state.turn = transcript
+                .messages
+                .iter()
+                .filter(|message| message.role == "user")
+                .count() as u32;
+            state.messages = transcript.messages;
+        })?;
+        self.events.emit(
+            app,
+            "RunImported",
+            &serde_json::json!({ "format": transcript.format, "messages": message_count }),
+        );
+        match transcript.plan {
+            Some(plan) => self.apply_plan(app, plan, "PlanUpdated"),
+            None => {
+                self.emit_state(app, "import");
+                Ok(snapshot)
+            }
+        }
+    }
+
+    /// Starts a new idle run from an earlier point of another one. The source
+    /// run and its event log are left untouched.
+    pub fn fork_run(
+        &self,
+        app: &dyn EventSink,
+        request: KernelForkRunRequest,
+    ) -> Result<RunState, String> {
+        if self.running.load(Ordering::SeqCst) {
+            return Err("Stop the current run before forking".to_string());
+        }
+        let source_id = request
+            .run_id
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .unwrap_or_else(|| self.events.current_run_id());
+        let source = if request.seq.is_some() {
+            self.rebuild_state(KernelRebuildStateRequest {
+                run_id: Some(source_id.clone()),
+                seq: request.seq,
+            })?
+            .state
+        } else if source_id == self.snapshot().run_id {
+            self.snapshot()
+        } else {
+            self.store
+                .lock()
+                .map_err(|_| "State store lock poisoned".to_string())?
+                .load(&source_id)
+                .ok_or_else(|| format!("Run not found: {}", source_id))?
+        };
+        let mut messages = source.messages;
+        if let Some(index) = request.message_index {
+            if index > messages.len() {
+                return Err(format!(
+                    "Message index {} is past the {} messages of run {}",
+                    index,
+                    messages.len(),
+                    source_id
+                ));
             }
+            messages.truncate(index);
+        }
+        let run_id = Uuid::new_v4().to_string();
+        self.events.set_run(run_id.clone());
+        let cwd = display_path(&self.runtime.workspace.root());
+        let message_count = messages.len();
+        let fork = RunFork {
+            run_id: source_id,
+            seq: request.seq,
+            message_index: request.message_index,
+        };
+        let snapshot = self.update_state(|state| {
+            let pinned = std::mem::take(&mut state.pinned_files);
+            *state = RunState::new(run_id.clone(), cwd);
+            state.pinned_files = pinned;
+            state.readonly = source.readonly || self.reviewer.load(Ordering::SeqCst);
+            state.turn = messages
+                .iter()
+                .filter(|message| message.role == "user")
+                .count() as u32;
+            state.messages = messages;
+            state.plan = source.plan;
+            state.tasks = source.tasks;
+            state.task_id = source.task_id;
+            state.tags = source.tags;
+            state.budget.max_steps = source.budget.max_steps;
+            state.forked_from = Some(fork.clone());
         })?;
-        self.emit_state(app, "pause");
+        self.events.emit(
+            app,
+            "RunForked",
+            &serde_json::json!({ "from": fork, "messages": message_count }),
+        );
+        self.emit_state(app, "fork");
         Ok(snapshot)
     }
 
-    pub fn resume(&self, app: &AppHandle) -> Result<RunState, String> {
+    /// Makes a persisted run the current one and replays its event log to the
+    /// frontend. A run that was still executing when the app closed comes back
+    /// as interrupted.
+    pub fn load_run(&self, app: &dyn EventSink, run_id: &str) -> Result<RunState, String> {
+        if self.running.load(Ordering::SeqCst) {
+            return Err("Stop the current run before loading another".to_string());
+        }
+        let run_id = run_id.trim();
+        let mut loaded = self
+            .store
+            .lock()
+            .map_err(|_| "State store lock poisoned".to_string())?
+            .load(run_id)
+            .ok_or_else(|| format!("Run not found: {}", run_id))?;
+        let events = self.events.read_events(run_id).unwrap_or_default();
+        if matches!(
+            loaded.agent_state,
+            RunAgentState::Running | RunAgentState::Paused
+        ) {
+            loaded.agent_state = RunAgentState::Interrupted;
+            loaded.last_error = Some("Interrupted by application restart".to_string());
+        }
+        // Terminal sessions do not survive a restart; the next command opens a
+        // fresh one.
+        let live_sessions = self.runtime.terminal.list_sessions().unwrap_or_default();
+        if let Some(session_id) = loaded.tool_context.session_id.clone() {
+            if !live_sessions.iter().any(|session| session.id == session_id) {
+                loaded.tool_context.session_id = None;
+            }
+        }
+        loaded.readonly = loaded.readonly || self.reviewer.load(Ordering::SeqCst);
+        let next_seq = events.last().map(|event| event.seq + 1).unwrap_or(0);
+        self.events.resume_run(loaded.run_id.clone(), next_seq);
         self.paused.store(false, Ordering::SeqCst);
-        let mut should_spawn = false;
-        let snapshot = self.update_state(|state| {
-            if state.agent_state == RunAgentState::Paused {
-                state.agent_state = RunAgentState::Running;
-                should_spawn = true;
+        let snapshot = self.update_state(|state| *state = loaded)?;
+        for event in &events {
+            if let Ok(value) = serde_json::to_value(event) {
+                app.emit_json(KERNEL_EVENT_NAME, value);
             }
-        })?;
-        self.emit_state(app, "resume");
-        if should_spawn && !self.running.swap(true, Ordering::SeqCst) {
-            let manager = self.clone();
-            let app_handle = app.clone();
-            tauri::async_runtime::spawn(async move {
-                manager.run_loop(app_handle).await;
-            });
         }
+        self.events.emit(
+            app,
+            "RunLoaded",
+            &serde_json::json!({ "replayedEvents": events.len() }),
+        );
+        self.emit_state(app, "load");
         Ok(snapshot)
     }
 
-    pub fn stop(&self, app: &AppHandle) -> Result<RunState, String> {
-        self.paused.store(false, Ordering::SeqCst);
+    /// Loads a persisted run and continues executing it from its last step.
+    pub fn resume_run(&self, app: &dyn EventSink, run_id: &str) -> Result<RunState, String> {
+        let loaded = self.load_run(app, run_id)?;
+        if loaded.agent_state == RunAgentState::Finished {
+            return Err("Run already finished; start a new run instead".to_string());
+        }
+        if self.running.swap(true, Ordering::SeqCst) {
+            return Err("Kernel already running".to_string());
+        }
         let snapshot = self.update_state(|state| {
-            if state.agent_state != RunAgentState::Idle {
-                state.agent_state = RunAgentState::Finished;
+            state.agent_state = RunAgentState::Running;
+            state.last_error = None;
+            if state.budget.used_steps >= state.budget.max_steps {
+                state.budget.used_steps = 0;
             }
         })?;
-        self.emit_state(app, "stop");
+        self.emit_state(app, "resume");
+        let manager = self.clone();
+        let app = app.clone_sink();
+        tauri::async_runtime::spawn(async move {
+            manager.run_loop(app).await;
+        });
         Ok(snapshot)
     }
 
-    pub fn continue_run(&self, app: &AppHandle) -> Result<RunState, String> {
+    pub fn set_readonly(&self, app: &dyn EventSink, readonly: bool) -> Result<RunState, String> {
+        if !readonly && self.is_reviewer_mode() {
+            return Err("Readonly mode cannot be turned off in reviewer mode".to_string());
+        }
+        let snapshot = self.update_state(|state| {
+            state.readonly = readonly;
+        })?;
+        self.emit_state(app, "readonly");
+        Ok(snapshot)
+    }
+
+    /// Also emits each event as a plain-text `narration` sentence.
+    pub fn set_narration(&self, enabled: bool) {
+        self.events.narration.store(enabled, Ordering::SeqCst);
+    }
+
+    pub fn is_reviewer_mode(&self) -> bool {
+        self.reviewer.load(Ordering::SeqCst)
+    }
+
+    /// Limits every run to these action types, from `--allow-actions`.
+    pub fn set_cli_allowed_actions(&self, actions: Option<Vec<String>>) {
+        if let Ok(mut current) = self.cli_allowed_actions.lock() {
+            *current = actions.map(|actions| actions.into_iter().collect());
+        }
+    }
+
+    /// The current run's capabilities, or what a run started now would get.
+    pub fn capabilities(&self) -> RunCapabilities {
+        self.snapshot()
+            .capabilities
+            .unwrap_or_else(|| self.negotiate_capabilities())
+    }
+
+    fn negotiate_capabilities(&self) -> RunCapabilities {
+        let profile = self.llm.get_active_profile();
+        let allow_network = self
+            .get_task_constraints()
+            .ok()
+            .and_then(|constraints| constraints.allow_network);
+        let cli = self
+            .cli_allowed_actions
+            .lock()
+            .ok()
+            .and_then(|actions| actions.clone());
+        negotiate_capabilities(profile.as_ref(), allow_network, cli.as_ref())
+    }
+
+    /// Reviewer mode keeps every run in readonly (shadow) mode and refuses to
+    /// apply pending changes, so nothing the agent proposes reaches the disk.
+    pub fn set_reviewer_mode(&self, enabled: bool) {
+        self.reviewer.store(enabled, Ordering::SeqCst);
+        if enabled {
+            let _ = self.update_state(|state| {
+                state.readonly = true;
+            });
+        }
+    }
+
+    pub fn pin_file(&self, app: &dyn EventSink, path: &str) -> Result<RunState, String> {
+        let resolved = self.runtime.workspace.resolve_path(path)?;
+        if !resolved.is_file() {
+            return Err(format!("Not a file: {}", path));
+        }
+        let root = self.runtime.workspace.root();
+        let relative = resolved
+            .strip_prefix(&root)
+            .unwrap_or(&resolved)
+            .to_string_lossy()
+            .replace('\\', "/");
+        let snapshot = self.update_state(|state| {
+            if !state.pinned_files.contains(&relative) {
+                state.pinned_files.push(relative.clone());
+            }
+        })?;
+        self.emit_state(app, "pinned_files");
+        Ok(snapshot)
+    }
+
+    pub fn unpin_file(&self, app: &dyn EventSink, path: &str) -> Result<RunState, String> {
+        let normalized = path.trim().trim_start_matches("./").replace('\\', "/");
+        let snapshot = self.update_state(|state| {
+            state.pinned_files.retain(|pinned| pinned != &normalized);
+        })?;
+        self.emit_state(app, "pinned_files");
+        Ok(snapshot)
+    }
+
+    fn pinned_files_prompt(&self, pinned_files: &[String]) -> String {
+        let mut section = String::new();
+        let mut remaining = MAX_PINNED_PROMPT_BYTES;
+        for path in pinned_files {
+            if remaining == 0 {
+                break;
+            }
+            let Ok(content) = self
+                .runtime
+                .workspace
+                .resolve_path(path)
+                .and_then(|resolved| std::fs::read_to_string(resolved).map_err(|e| e.to_string()))
+            else {
+                continue;
+            };
+            let budget = remaining.min(MAX_PINNED_FILE_BYTES);
+            let mut excerpt = trim_to(content.trim(), budget);
+            if excerpt.len() < content.trim().len() {
+                excerpt.push_str("\n... [truncated]");
+            }
+            remaining = remaining.saturating_sub(excerpt.len());
+            section.push_str(&format!("--- {} ---\n{}\n", path, excerpt));
+        }
+        section
+    }
+
+    pub fn list_pending_changes(&self) -> Result<Vec<PendingChangeView>, String> {
+        let snapshot = self.snapshot_agent_state()?;
+        let mut views = Vec::new();
+        for change in &snapshot.pending_changes {
+            let mut view = PendingChangeView {
+                id: change.id.clone(),
+                action_type: action_type(&change.action).to_string(),
+                path: None,
+                command: None,
+                reason: change.reason.clone(),
+                created_at: change.created_at,
+                diff: None,
+            };
+            match &change.action {
+                Action::FsWrite { path, content, .. } => {
+                    let current = self
+                        .runtime
+                        .workspace
+                        .resolve_path_for_write(path)
+                        .ok()
+                        .and_then(|resolved| std::fs::read_to_string(resolved).ok())
+                        .unwrap_or_default();
+                    view.diff = Some(unified_diff(path, &current, content));
+                    view.path = Some(path.clone());
+                }
+                Action::FsApplyPatch { path, patch, .. } => {
+                    view.diff = Some(patch.clone());
+                    view.path = Some(path.clone());
+                }
+                Action::TerminalExec { cmd, .. } => view.command = Some(cmd.clone()),
+                Action::TerminalRun { program, args, .. } => {
+                    view.command =
+                        Some(format!("{} {}", program, args.join(" ")).trim().to_string())
+                }
+                _ => {}
+            }
+            views.push(view);
+        }
+        Ok(views)
+    }
+
+    pub fn apply_changes(
+        &self,
+        app: &dyn EventSink,
+        request: ChangesApplyRequest,
+    ) -> Result<ChangesApplyResponse, String> {
+        if self.is_reviewer_mode() {
+            return Err("Pending changes cannot be applied in reviewer mode".to_string());
+        }
+        if request.ids.is_empty() {
+            return Err("No pending changes selected".to_string());
+        }
+        let snapshot = self.snapshot_agent_state()?;
+        let mut writes = Vec::new();
+        for id in &request.ids {
+            let change = snapshot
+                .pending_changes
+                .iter()
+                .find(|change| &change.id == id)
+                .ok_or_else(|| format!("Unknown pending change: {}", id))?;
+            match &change.action {
+                Action::FsWrite { path, content, .. } => {
+                    let resolved = self.runtime.workspace.resolve_path_for_write(path)?;
+                    writes.push((path.clone(), resolved, content.clone()));
+                }
+                Action::FsApplyPatch { path, patch, .. } => {
+                    let resolved = self.runtime.workspace.resolve_path_for_write(path)?;
+                    let current = patch::read_patch_target(&resolved)?.unwrap_or_default();
+                    let outcome = patch::apply_patch(&current, patch)?;
+                    let content = outcome.content.clone().ok_or_else(|| {
+                        format!(
+                            "Pending change {} no longer applies to {}: {}",
+                            id,
+                            path,
+                            outcome.describe()
+                        )
+                    })?;
+                    writes.push((path.clone(), resolved, content));
+                }
+                other => {
+                    return Err(format!(
+                        "Pending change {} ({}) is not a file edit and must be run manually",
+                        id,
+                        action_type(other)
+                    ));
+                }
+            }
+        }
+        let mut originals: Vec<(PathBuf, Option<Vec<u8>>)> = Vec::new();
+        let mut failure = None;
+        let history = FileHistory::for_root(&self.runtime.workspace.root());
+        for (_, resolved, content) in &writes {
+            originals.push((resolved.clone(), std::fs::read(resolved).ok()));
+            let _ = history.record(resolved, "user", Some(snapshot.run_id.clone()));
+            let result = resolved
+                .parent()
+                .map(create_dir_all)
+                .unwrap_or(Ok(()))
+                .and_then(|_| std::fs::write(resolved, content.as_bytes()));
+            if let Err(err) = result {
+                failure = Some(format!("{}: {}", display_path(resolved), err));
+                break;
+            }
+        }
+        if let Some(err) = failure {
+            for (resolved, original) in originals.into_iter().rev() {
+                let _ = match original {
+                    Some(bytes) => std::fs::write(&resolved, bytes),
+                    None => std::fs::remove_file(&resolved),
+                };
+            }
+            return Err(format!(
+                "Failed to apply pending changes, rolled back: {}",
+                err
+            ));
+        }
+        let provenance = ProvenanceStore::for_root(&self.runtime.workspace.root());
+        for ((_, resolved, content), (_, original)) in writes.iter().zip(&originals) {
+            let before = original
+                .as_ref()
+                .map(|bytes| String::from_utf8_lossy(bytes).to_string());
+            let _ = provenance.record(
+                resolved,
+                before.as_deref(),
+                content,
+                "agent",
+                Some(snapshot.run_id.clone()),
+            );
+        }
+        let paths: Vec<String> = writes.iter().map(|(path, _, _)| path.clone()).collect();
+        self.runtime.audit.write(AuditEntry {
+            timestamp_ms: now_ms(),
+            action: "changes.apply".to_string(),
+            session_id: snapshot.tool_context.session_id.clone(),
+            command: None,
+            payload: serde_json::json!({
+                "run_id": snapshot.run_id,
+                "ids": request.ids,
+                "paths": paths,
+            }),
+        });
+        let summary = format!(
+            "Applied {} pending change(s): {}",
+            paths.len(),
+            paths.join(", ")
+        );
+        let state = self.update_state(|state| {
+            state
+                .pending_changes
+                .retain(|change| !request.ids.contains(&change.id));
+            state.messages.push(ChatMessage {
+                role: "assistant".to_string(),
+                content: summary.clone(),
+            });
+        })?;
+        self.events.emit(
+            app,
+            "ChangesApplied",
+            &serde_json::json!({ "ids": request.ids, "paths": paths }),
+        );
+        self.events.emit(
+            app,
+            "AgentMessage",
+            &serde_json::json!({ "content": summary }),
+        );
+        self.emit_state(app, "changes_applied");
+        Ok(ChangesApplyResponse {
+            applied: request.ids,
+            paths,
+            state,
+        })
+    }
+
+    /// Writes to a missing path are redirected to an unambiguous fuzzy match when
+    /// the path policy allows it; otherwise similar existing files are only noted,
+    /// since the model may mean to create a new file.
+    fn resolve_write_path(&self, action: Action) -> (Action, Option<String>) {
+        let Action::FsWrite { id, path, content } = action else {
+            return (action, None);
+        };
+        let exists = self
+            .runtime
+            .workspace
+            .resolve_path_for_write(&path)
+            .map(|resolved| resolved.exists())
+            .unwrap_or(false);
+        if exists {
+            return (Action::FsWrite { id, path, content }, None);
+        }
+        let root = self.runtime.workspace.root();
+        let note = match resolve_fuzzy(&root, &path) {
+            FuzzyMatch::None => None,
+            FuzzyMatch::Unique(found)
+                if load_path_policy(&root).fuzzy_writes
+                    && load_visibility_policy(&root).allows_resolved(&root, &found) =>
+            {
+                let note = format!("Resolved {} to existing file {}", path, found);
+                return (
+                    Action::FsWrite {
+                        id,
+                        path: found,
+                        content,
+                    },
+                    Some(note),
+                );
+            }
+            matches => Some(format!(
+                "Created new file {}; similar existing files: {}",
+                path,
+                matches.candidates().join(", ")
+            )),
+        };
+        (Action::FsWrite { id, path, content }, note)
+    }
+
+    fn run_action_hooks(&self, stage: &str, action: &Action) -> Vec<HookResult> {
+        if !is_execution_action(action) || self.is_reviewer_mode() {
+            return Vec::new();
+        }
+        let path = match action {
+            Action::FsRead { path, .. }
+            | Action::FsWrite { path, .. }
+            | Action::FsApplyPatch { path, .. } => Some(path.as_str()),
+            Action::GitDiff { path, .. } => path.as_deref(),
+            _ => None,
+        };
+        let runner = HookRunner::new(&self.runtime.workspace.root(), self.runtime.audit.clone());
+        runner.run_action_hooks(stage, action_type(action), path)
+    }
+
+    fn verify_action(&self, action: &Action) -> Vec<VerifyResult> {
+        let Some(path) = written_path(action) else {
+            return Vec::new();
+        };
+        let root = self.runtime.workspace.root();
+        let policy = load_trusted_verify_policy(&root);
+        let Ok(resolved) = self.runtime.workspace.resolve_path_for_write(path) else {
+            return Vec::new();
+        };
+        verify_written_file(&root, &policy, path, &resolved, &self.runtime.audit)
+    }
+
+    fn write_target_content(&self, action: &Action) -> Option<String> {
+        let path = written_path(action)?;
+        let resolved = self.runtime.workspace.resolve_path_for_write(path).ok()?;
+        std::fs::read_to_string(resolved).ok()
+    }
+
+    fn record_provenance(&self, action: &Action, before: Option<&str>, run_id: &str) {
+        let Some(path) = written_path(action) else {
+            return;
+        };
+        if let Ok(resolved) = self.runtime.workspace.resolve_path_for_write(path) {
+            let content = match action {
+                Action::FsWrite { content, .. } => content.clone(),
+                _ => std::fs::read_to_string(&resolved).unwrap_or_default(),
+            };
+            let store = ProvenanceStore::for_root(&self.runtime.workspace.root());
+            let _ = store.record(
+                &resolved,
+                before,
+                &content,
+                "agent",
+                Some(run_id.to_string()),
+            );
+        }
+    }
+
+    /// Appends the run's attribution trailer to `git commit` when the provenance
+    /// policy asks for it.
+    fn with_commit_trailer(&self, action: Action, run_id: &str) -> Action {
+        if !is_git_commit(&action)
+            || !load_provenance_policy(&self.runtime.workspace.root()).commit_trailer
+        {
+            return action;
+        }
+        let trailer = commit_trailer(run_id);
+        match action {
+            Action::TerminalRun {
+                id,
+                program,
+                mut args,
+                cwd,
+            } => {
+                if let Some(index) = args.iter().position(|arg| arg == "commit") {
+                    args.insert(index + 1, "--trailer".to_string());
+                    args.insert(index + 2, trailer);
+                }
+                Action::TerminalRun {
+                    id,
+                    program,
+                    args,
+                    cwd,
+                }
+            }
+            Action::TerminalExec { id, cmd, cwd } => Action::TerminalExec {
+                id,
+                cmd: cmd.replacen(
+                    "git commit",
+                    &format!("git commit --trailer \"{}\"", trailer),
+                    1,
+                ),
+                cwd,
+            },
+            Action::GitCommit { id, message, paths } => Action::GitCommit {
+                id,
+                message: format!("{}\n\n{}", message.trim_end(), trailer),
+                paths,
+            },
+            other => other,
+        }
+    }
+
+    fn record_file_version(&self, action: &Action, actor: &str, run_id: Option<String>) {
+        if let Some(path) = written_path(action) {
+            if let Ok(resolved) = self.runtime.workspace.resolve_path_for_write(path) {
+                let history = FileHistory::for_root(&self.runtime.workspace.root());
+                let _ = history.record(&resolved, actor, run_id);
+            }
+        }
+    }
+
+    pub fn pause(&self, app: &dyn EventSink) -> Result<RunState, String> {
+        self.paused.store(true, Ordering::SeqCst);
+        let snapshot = self.update_state(|state| {
+            if state.agent_state == RunAgentState::Running {
+                state.agent_state = RunAgentState::Paused;
+            }
+        })?;
+        self.emit_state(app, "pause");
+        Ok(snapshot)
+    }
+
+    /// Marks an in-flight run as interrupted so the run loop winds down, then waits
+    /// briefly for it to exit. Used when the application is closing.
+    pub fn shutdown(&self, app: &dyn EventSink, wait_ms: u64) -> Result<RunState, String> {
+        self.paused.store(false, Ordering::SeqCst);
+        let mut interrupted = false;
+        let snapshot = self.update_state(|state| {
+            if matches!(
+                state.agent_state,
+                RunAgentState::Running | RunAgentState::Paused
+            ) {
+                state.agent_state = RunAgentState::Interrupted;
+                state.last_error = Some("Interrupted by application shutdown".to_string());
+                interrupted = true;
+            }
+        })?;
+        if interrupted {
+            self.events.emit(
+                app,
+                "RunInterrupted",
+                &serde_json::json!({ "reason": "shutdown" }),
+            );
+            self.emit_state(app, "shutdown");
+        }
+        let deadline = Instant::now() + Duration::from_millis(wait_ms);
+        while self.running.load(Ordering::SeqCst) && Instant::now() < deadline {
+            sleep(Duration::from_millis(50));
+        }
+        Ok(snapshot)
+    }
+
+    pub fn resume(&self, app: &dyn EventSink) -> Result<RunState, String> {
+        self.paused.store(false, Ordering::SeqCst);
+        let mut should_spawn = false;
+        let snapshot = self.update_state(|state| {
+            if state.agent_state == RunAgentState::Paused {
+                state.agent_state = RunAgentState::Running;
+                should_spawn = true;
+            }
+        })?;
+        self.emit_state(app, "resume");
+        if should_spawn && !self.running.swap(true, Ordering::SeqCst) {
+            let manager = self.clone();
+            let app_handle = app.clone_sink();
+            tauri::async_runtime::spawn(async move {
+                manager.run_loop(app_handle).await;
+            });
+        }
+        Ok(snapshot)
+    }
+
+    pub fn stop(&self, app: &dyn EventSink) -> Result<RunState, String> {
+        self.paused.store(false, Ordering::SeqCst);
+        let snapshot = self.update_state(|state| {
+            if state.agent_state != RunAgentState::Idle {
+                state.agent_state = RunAgentState::Finished;
+            }
+            state.selected_step_ids = None;
+        })?;
+        self.cleanup_run_sessions(app, &snapshot);
+        if !self.running.load(Ordering::SeqCst) {
+            self.cleanup_run_scratch(app, &snapshot);
+        }
+        self.emit_state(app, "stop");
+        Ok(snapshot)
+    }
+
+    pub fn continue_run(&self, app: &dyn EventSink) -> Result<RunState, String> {
         self.paused.store(false, Ordering::SeqCst);
         let mut should_spawn = false;
         let snapshot = self.update_state(|state| {
-            if state.agent_state == RunAgentState::AwaitingUser {
+            if matches!(
+                state.agent_state,
+                RunAgentState::AwaitingUser | RunAgentState::Interrupted
+            ) {
                 state.agent_state = RunAgentState::Running;
                 state.budget.used_steps = 0;
                 state.last_error = None;
@@ -845,7 +2924,7 @@ impl KernelManager {
         self.emit_state(app, "continue");
         if should_spawn && !self.running.swap(true, Ordering::SeqCst) {
             let manager = self.clone();
-            let app_handle = app.clone();
+            let app_handle = app.clone_sink();
             tauri::async_runtime::spawn(async move {
                 manager.run_loop(app_handle).await;
             });
@@ -853,9 +2932,106 @@ impl KernelManager {
         Ok(snapshot)
     }
 
+    pub fn run_steps(
+        &self,
+        app: &dyn EventSink,
+        request: KernelRunStepsRequest,
+    ) -> Result<RunState, String> {
+        let step_ids: Vec<String> = request
+            .step_ids
+            .iter()
+            .map(|id| id.trim().to_string())
+            .filter(|id| !id.is_empty())
+            .collect();
+        if step_ids.is_empty() {
+            return Err("At least one plan step id is required".to_string());
+        }
+        if self.running.swap(true, Ordering::SeqCst) {
+            return Err("Kernel already running".to_string());
+        }
+        let mut missing = Vec::new();
+        let result = self.update_state(|state| {
+            let plan = match &mut state.plan {
+                Some(plan) => plan,
+                None => {
+                    missing = step_ids.clone();
+                    return;
+                }
+            };
+            for id in &step_ids {
+                if !plan.steps.iter().any(|step| &step.id == id) {
+                    missing.push(id.clone());
+                }
+            }
+            if !missing.is_empty() {
+                return;
+            }
+            for step in plan.steps.iter_mut() {
+                if step_ids.contains(&step.id) {
+                    step.status = "pending".to_string();
+                    step.done = false;
+                }
+            }
+            if let Some(tasks) = &mut state.tasks {
+                for task in tasks.items.iter_mut() {
+                    if step_ids.contains(&task.id) {
+                        task.status = "todo".to_string();
+                    }
+                }
+            }
+            state.selected_step_ids = Some(step_ids.clone());
+            state.agent_state = RunAgentState::Running;
+            state.budget.used_steps = 0;
+            state.last_error = None;
+            if request.session_id.is_some() {
+                state.tool_context.session_id = request.session_id.clone();
+            }
+        });
+        let snapshot = match result {
+            Ok(snapshot) => snapshot,
+            Err(err) => {
+                self.running.store(false, Ordering::SeqCst);
+                return Err(err);
+            }
+        };
+        if !missing.is_empty() {
+            self.running.store(false, Ordering::SeqCst);
+            return Err(format!("Unknown plan step id: {}", missing.join(", ")));
+        }
+        // The run's own session was closed when it last ended.
+        let live_session = snapshot
+            .tool_context
+            .session_id
+            .as_deref()
+            .is_some_and(|id| self.runtime.terminal.session_shell(id).is_some());
+        let snapshot = if live_session {
+            snapshot
+        } else {
+            self.open_run_session(app, &snapshot.run_id, snapshot.task_id.clone())
+                .and_then(|id| {
+                    self.update_state(|state| state.tool_context.session_id = Some(id))
+                        .ok()
+                })
+                .unwrap_or(snapshot)
+        };
+        self.paused.store(false, Ordering::SeqCst);
+        self.events.emit(
+            app,
+            "PlanUpdated",
+            &serde_json::json!({ "plan": snapshot.plan }),
+        );
+        self.emit_state(app, "run_steps");
+        let manager = self.clone();
+        let app_handle = app.clone_sink();
+        tauri::async_runtime::spawn(async move {
+            manager.run_loop(app_handle).await;
+        });
+        Ok(snapshot)
+    }
+
     pub fn user_input(
         &self,
-        app: &AppHandle,
+        app: &dyn EventSink,
         request: KernelUserInputRequest,
     ) -> Result<RunState, String> {
         let content = request.content.trim();
@@ -868,6 +3044,7 @@ impl KernelManager {
         let mut should_spawn = false;
         let mut chat_only = request.chat_only;
         let mut chat_blocked = false;
+        let mut answered_ask = false;
         let snapshot = self.update_state(|state| {
             if stop_command {
                 control_stop = true;
@@ -882,12 +3059,16 @@ impl KernelManager {
             if !chat_only
                 && matches!(
                     state.agent_state,
-                    RunAgentState::Idle | RunAgentState::Finished | RunAgentState::Error
+                    RunAgentState::Idle
+                        | RunAgentState::Finished
+                        | RunAgentState::Error
+                        | RunAgentState::Interrupted
                 )
             {
                 chat_only = true;
             }
             if state.agent_state == RunAgentState::AwaitingUser && !chat_only {
+                answered_ask = true;
                 if is_continue_command(content) {
                     if let Some(reply) = infer_default_continue_reply(state) {
                         final_content = reply;
@@ -911,6 +3092,30 @@ impl KernelManager {
         if chat_blocked {
             return Err("Agent is running. Pause or stop before chat-only messages.".to_string());
         }
+        if answered_ask {
+            if let Ok(mut pending) = self.pending_secrets.lock() {
+                // Only "continue" marks the reported findings as safe.
+                let fingerprints = std::mem::take(&mut *pending);
+                if is_continue_command(content) {
+                    if let Ok(mut acknowledged) = self.acknowledged_secrets.lock() {
+                        acknowledged.extend(fingerprints);
+                    }
+                }
+            }
+            if let Ok(mut pending) = self.pending_install.lock() {
+                // "continue" approves a proposed install; any other reply discards it.
+                match pending.as_mut() {
+                    Some(install) if is_continue_command(content) => install.approved = true,
+                    _ => *pending = None,
+                }
+            }
+            if let Ok(mut pending) = self.pending_write.lock() {
+                match pending.as_mut() {
+                    Some(write) if is_continue_command(content) => write.approved = true,
+                    _ => *pending = None,
+                }
+            }
+        }
         self.events.emit(
             app,
             "UserMessage",
@@ -924,7 +3129,7 @@ impl KernelManager {
         self.emit_state(app, "user_input");
         if chat_only {
             let manager = self.clone();
-            let app_handle = app.clone();
+            let app_handle = app.clone_sink();
             tauri::async_runtime::spawn(async move {
                 manager.respond_in_chat_mode(app_handle).await;
             });
@@ -932,7 +3137,7 @@ impl KernelManager {
         }
         if should_spawn && !self.running.swap(true, Ordering::SeqCst) {
             let manager = self.clone();
-            let app_handle = app.clone();
+            let app_handle = app.clone_sink();
             tauri::async_runtime::spawn(async move {
                 manager.run_loop(app_handle).await;
             });
@@ -940,7 +3145,7 @@ impl KernelManager {
         Ok(snapshot)
     }
 
-    async fn respond_in_chat_mode(&self, app: AppHandle) {
+    async fn respond_in_chat_mode(&self, app: SharedEventSink) {
         let profile = match self.llm.get_active_profile() {
             Some(profile) => profile,
             None => {
@@ -964,14 +3169,25 @@ impl KernelManager {
             }
         };
         let system_prompt = build_chat_system_prompt(&profile);
-        let user_prompt = build_chat_user_prompt(&snapshot);
+        let policy = ContextPolicy::for_profile(&profile);
+        let (user_prompt, compaction) = build_chat_user_prompt(
+            &snapshot,
+            &policy,
+            policy.prompt_budget(estimate_tokens(&system_prompt)),
+        );
+        if let Some(compaction) = compaction {
+            self.events
+                .emit(&app, "ContextCompacted", &serde_json::json!(compaction));
+        }
         let events = self.events.clone();
         let app_handle = app.clone();
-        let raw = match request_completion_stream(
-            &profile,
+        let used = self.select_llm_profile(&profile);
+        let (result, trace) = request_completion_metered(
+            &used,
             &system_prompt,
             &user_prompt,
             LlmResponseFormat::Text,
+            &[],
             |chunk| {
                 if !chunk.trim().is_empty() {
                     events.emit(
@@ -982,8 +3198,9 @@ impl KernelManager {
                 }
             },
         )
-        .await
-        {
+        .await;
+        self.record_llm_result(&app, &profile, &used, &result, trace.usage);
+        let raw = match result {
             Ok(content) => content,
             Err(err) => {
                 self.events
@@ -997,8 +3214,15 @@ impl KernelManager {
                 return;
             }
         };
-        self.events
-            .emit(&app, "AgentMessageDone", &serde_json::json!({}));
+        self.events.emit(
+            &app,
+            "AgentMessageDone",
+            &serde_json::json!({
+                "provider": used.provider,
+                "model": used.model,
+                "reasoning": trace.reasoning(),
+            }),
+        );
         let content = raw.trim().to_string();
         if content.is_empty() {
             let message = "LLM response is empty".to_string();
@@ -1017,14 +3241,17 @@ impl KernelManager {
             });
             state.last_error = None;
         });
-        self.events
-            .emit(&app, "AgentMessage", &serde_json::json!({ "content": content }));
+        self.events.emit(
+            &app,
+            "AgentMessage",
+            &serde_json::json!({ "content": content }),
+        );
         self.emit_state(&app, "chat_response");
     }
 
     pub async fn update_plan(
         &self,
-        app: &AppHandle,
+        app: &dyn EventSink,
         request: KernelPlanUpdateRequest,
     ) -> Result<RunState, String> {
         let goal = request.goal.trim().to_string();
@@ -1032,7 +3259,14 @@ impl KernelManager {
             return Err("Plan goal cannot be empty".to_string());
         }
         if request.auto_generate.unwrap_or(false) {
-            let plan = self.generate_plan_from_llm(&goal).await?;
+            let generation_id = request
+                .generation_id
+                .map(|id| id.trim().to_string())
+                .filter(|id| !id.is_empty())
+                .unwrap_or_else(|| make_id("plangen"));
+            let plan = self
+                .generate_plan_cancellable(app, &goal, &generation_id)
+                .await?;
             return self.apply_plan(app, plan, "PlanUpdated");
         }
         let steps = request
@@ -1059,7 +3293,7 @@ impl KernelManager {
 
     pub fn update_plan_status(
         &self,
-        app: &AppHandle,
+        app: &dyn EventSink,
         request: KernelPlanStatusRequest,
     ) -> Result<RunState, String> {
         let status = request.status.trim().to_string();
@@ -1085,26 +3319,117 @@ impl KernelManager {
         Ok(snapshot)
     }
 
-    async fn generate_plan_from_llm(&self, goal: &str) -> Result<Plan, String> {
-        let profile = self.llm.get_active_profile().ok_or_else(|| {
-            "LLM profile not configured. Save a profile in LLM Settings.".to_string()
-        })?;
-        let system_prompt = build_plan_system_prompt(&profile);
-        let user_prompt = format!(
-            "Goal: {}\nReturn JSON only. Format: {{\"goal\":\"...\",\"steps\":[\"step 1\",\"step 2\"]}}.",
-            goal
-        );
-        let raw = request_completion(
-            &profile,
+    /// Cancels an in-flight plan auto-generation; `kernel_plan_update` then
+    /// returns an error and the plan is left as it was.
+    pub fn cancel_generation(&self, generation_id: &str) -> Result<(), String> {
+        let token = self
+            .plan_generations
+            .lock()
+            .map_err(|_| "Plan generation lock poisoned".to_string())?
+            .remove(generation_id)
+            .ok_or_else(|| format!("No plan generation in progress: {}", generation_id))?;
+        token.cancel();
+        Ok(())
+    }
+
+    async fn generate_plan_cancellable(
+        &self,
+        app: &dyn EventSink,
+        goal: &str,
+        generation_id: &str,
+    ) -> Result<Plan, String> {
+        let token = CancellationToken::new();
+        {
+            let mut generations = self
+                .plan_generations
+                .lock()
+                .map_err(|_| "Plan generation lock poisoned".to_string())?;
+            if generations.contains_key(generation_id) {
+                return Err(format!(
+                    "Plan generation already running: {}",
+                    generation_id
+                ));
+            }
+            generations.insert(generation_id.to_string(), token.clone());
+        }
+        self.events.emit(
+            app,
+            "PlanGenerationStarted",
+            &serde_json::json!({ "generationId": generation_id, "goal": goal }),
+        );
+        let result = tokio::select! {
+            result = self.generate_plan_from_llm(app, goal, generation_id) => result,
+            _ = token.cancelled() => Err("Plan generation cancelled".to_string()),
+        };
+        if let Ok(mut generations) = self.plan_generations.lock() {
+            generations.remove(generation_id);
+        }
+        let event_type = if token.is_cancelled() {
+            "PlanGenerationCancelled"
+        } else {
+            "PlanGenerationFinished"
+        };
+        self.events.emit(
+            app,
+            event_type,
+            &serde_json::json!({
+                "generationId": generation_id,
+                "ok": result.is_ok(),
+                "error": result.as_ref().err(),
+            }),
+        );
+        result
+    }
+
+    async fn generate_plan_from_llm(
+        &self,
+        app: &dyn EventSink,
+        goal: &str,
+        generation_id: &str,
+    ) -> Result<Plan, String> {
+        let profile = self.llm.get_active_profile().ok_or_else(|| {
+            "LLM profile not configured. Save a profile in LLM Settings.".to_string()
+        })?;
+        let system_prompt = build_plan_system_prompt(&profile);
+        let user_prompt = format!(
+            "Goal: {}\nReturn JSON only. Format: {{\"goal\":\"...\",\"steps\":[\"step 1\",\"step 2\"]}}.",
+            goal
+        );
+        let used = self.select_llm_profile(&profile);
+        let (result, trace) = request_completion_metered(
+            &used,
             &system_prompt,
             &user_prompt,
             LlmResponseFormat::PlanJson,
+            &[],
+            |chunk| {
+                if !chunk.trim().is_empty() {
+                    self.events.emit(
+                        app,
+                        "PlanGenerationChunk",
+                        &serde_json::json!({ "generationId": generation_id, "content": chunk }),
+                    );
+                }
+            },
         )
-        .await?;
-        parse_plan_response(&raw, Some(goal))
+        .await;
+        self.record_llm_result(app, &profile, &used, &result, trace.usage);
+        if let Some(reasoning) = trace.reasoning() {
+            self.events.emit(
+                app,
+                "PlanGenerationReasoning",
+                &serde_json::json!({ "generationId": generation_id, "reasoning": reasoning }),
+            );
+        }
+        parse_plan_response(&result?, Some(goal))
     }
 
-    fn apply_plan(&self, app: &AppHandle, plan: Plan, event_type: &str) -> Result<RunState, String> {
+    fn apply_plan(
+        &self,
+        app: &dyn EventSink,
+        plan: Plan,
+        event_type: &str,
+    ) -> Result<RunState, String> {
         let snapshot = self.update_state(|state| {
             state.plan = Some(plan.clone());
             state.tasks = Some(TaskList {
@@ -1121,7 +3446,8 @@ impl KernelManager {
                     .collect(),
             });
         })?;
-        self.events.emit(app, event_type, &serde_json::json!({ "plan": plan }));
+        self.events
+            .emit(app, event_type, &serde_json::json!({ "plan": plan }));
         self.emit_state(app, "plan_update");
         if let Some(task_id) = snapshot.task_id.clone() {
             let _ = self.save_plan_for_task(&task_id, &plan);
@@ -1143,7 +3469,7 @@ impl KernelManager {
         std::fs::write(path, data).map_err(|e| e.to_string())
     }
 
-    fn emit_state(&self, app: &AppHandle, reason: &str) {
+    fn emit_state(&self, app: &dyn EventSink, reason: &str) {
         let snapshot = self.snapshot();
         let payload = serde_json::json!({
             "reason": reason,
@@ -1163,11 +3489,35 @@ impl KernelManager {
             .state
             .lock()
             .map_err(|_| "Kernel state lock poisoned".to_string())?;
+        let was_awaiting = state.agent_state == RunAgentState::AwaitingUser;
         updater(&mut state);
+        if !was_awaiting && state.agent_state == RunAgentState::AwaitingUser {
+            state.awaiting_since = Some(now_ms());
+        }
         Ok(state.clone())
     }
 
-    async fn run_loop(&self, app: AppHandle) {
+    async fn run_loop(&self, app: SharedEventSink) {
+        let span = tracing::info_span!("run", run_id = %self.events.current_run_id());
+        self.drive_run(app).instrument(span).await;
+    }
+
+    async fn drive_run(&self, app: SharedEventSink) {
+        tracing::info!("run loop started");
+        // Loaded, imported and pre-existing runs negotiate on their first loop.
+        if self.snapshot().capabilities.is_none() {
+            let capabilities = self.negotiate_capabilities();
+            let _ = self.update_state(|state| state.capabilities = Some(capabilities.clone()));
+            self.events.emit(
+                &app,
+                "CapabilitiesNegotiated",
+                &serde_json::json!({ "capabilities": capabilities }),
+            );
+        }
+        if self.preflight_pending.swap(false, Ordering::SeqCst) {
+            self.preflight(app.as_ref()).await;
+        }
+        self.refresh_stale_context(app.as_ref());
         'run: loop {
             if self.paused.load(Ordering::SeqCst) {
                 sleep(Duration::from_millis(300));
@@ -1176,13 +3526,37 @@ impl KernelManager {
             let snapshot = match self.snapshot_agent_state() {
                 Ok(state) => state,
                 Err(err) => {
-                    self.events.emit(&app, "Error", &serde_json::json!({ "message": err }));
+                    self.events
+                        .emit(&app, "Error", &serde_json::json!({ "message": err }));
                     break;
                 }
             };
             if snapshot.agent_state != RunAgentState::Running {
                 break;
             }
+            if self.run_approved_install(app.as_ref(), &snapshot) {
+                break;
+            }
+            if self.run_approved_write(app.as_ref(), &snapshot) {
+                break;
+            }
+            if let Some(selected) = &snapshot.selected_step_ids {
+                let remaining = snapshot
+                    .plan
+                    .as_ref()
+                    .and_then(|plan| select_next_plan_step(plan, Some(selected)));
+                if remaining.is_none() {
+                    if self.block_on_secrets(&app, true) {
+                        break;
+                    }
+                    let _ = self.update_state(|state| {
+                        state.agent_state = RunAgentState::Finished;
+                        state.selected_step_ids = None;
+                    });
+                    self.emit_state(&app, "steps_complete");
+                    break;
+                }
+            }
             if snapshot.budget.used_steps >= snapshot.budget.max_steps {
                 let notice = format!(
                     "Step budget reached ({} steps). Reply \"continue\" to proceed or \"stop\" to end.",
@@ -1204,9 +3578,19 @@ impl KernelManager {
                 self.emit_state(&app, "step_budget");
                 break;
             }
-            let decision = match self.decide_actions_with_llm(&app, &snapshot).await {
+            let step_span = tracing::info_span!(
+                "step",
+                run_id = %snapshot.run_id,
+                step = snapshot.budget.used_steps + 1
+            );
+            let decision = match self
+                .decide_actions_with_llm(&app, &snapshot)
+                .instrument(step_span.clone())
+                .await
+            {
                 Ok(decision) => decision,
                 Err(err) => {
+                    tracing::warn!(parent: &step_span, error = %err, "decision failed");
                     let _ = self.update_state(|state| {
                         state.agent_state = RunAgentState::Error;
                         state.last_error = Some(err.clone());
@@ -1215,12 +3599,17 @@ impl KernelManager {
                             content: err.clone(),
                         });
                     });
-                    self.events.emit(&app, "Error", &serde_json::json!({ "message": err }));
+                    self.events
+                        .emit(&app, "Error", &serde_json::json!({ "message": err }));
                     self.emit_state(&app, "agent_error");
                     break;
                 }
             };
             let actions = decision.actions;
+            let mut source = decision.source;
+            source.step = snapshot.budget.used_steps + 1;
+            let model = source.model.clone();
+            let _ = self.update_state(|state| state.decision_sources.push(source));
             let mut message = decision.message;
             if message.is_none() {
                 for action in &actions {
@@ -1246,6 +3635,9 @@ impl KernelManager {
             }
             if actions.is_empty() {
                 if message.is_some() {
+                    if self.block_on_secrets(&app, true) {
+                        break;
+                    }
                     let _ = self.update_state(|state| {
                         state.agent_state = RunAgentState::Finished;
                     });
@@ -1261,14 +3653,15 @@ impl KernelManager {
                         content: message.clone(),
                     });
                 });
-                self.events.emit(&app, "Error", &serde_json::json!({ "message": message }));
+                self.events
+                    .emit(&app, "Error", &serde_json::json!({ "message": message }));
                 self.emit_state(&app, "awaiting_user");
                 break;
             }
             self.events.emit(
                 &app,
                 "AgentActionProposed",
-                &serde_json::json!({ "actions": actions }),
+                &serde_json::json!({ "actions": actions, "model": model }),
             );
             let exec_indices: Vec<usize> = actions
                 .iter()
@@ -1284,10 +3677,9 @@ impl KernelManager {
             let exec_step_id = if exec_indices.is_empty() {
                 None
             } else {
-                snapshot
-                    .plan
-                    .as_ref()
-                    .and_then(|plan| select_next_plan_step(plan))
+                snapshot.plan.as_ref().and_then(|plan| {
+                    select_next_plan_step(plan, snapshot.selected_step_ids.as_deref())
+                })
             };
             if let Some(step_id) = &exec_step_id {
                 let _ = self.update_plan_status(
@@ -1299,112 +3691,41 @@ impl KernelManager {
                 );
             }
             let last_exec_index = exec_indices.last().copied();
-            for (index, action) in actions.into_iter().enumerate() {
-                let current_state = match self.snapshot_agent_state() {
-                    Ok(state) => state,
-                    Err(err) => {
-                        self.events.emit(&app, "Error", &serde_json::json!({ "message": err }));
-                        break 'run;
-                    }
-                };
-                if current_state.agent_state != RunAgentState::Running {
-                    break 'run;
-                }
-                if matches!(action, Action::UserAsk { .. }) {
-                    let _ = self.update_state(|state| {
-                        state.agent_state = RunAgentState::AwaitingUser;
-                    });
-                    self.events.emit(
-                        &app,
-                        "AgentActionProposed",
-                        &serde_json::json!({ "action": action }),
-                    );
-                    self.emit_state(&app, "awaiting_user");
-                    break 'run;
-                }
-
-                self.events.emit(
-                    &app,
-                    "ToolCallStarted",
-                    &serde_json::json!({ "action": action }),
-                );
-                let mut chunk_handler = |chunk: String| {
-                    let _ = self.events.emit(
-                        &app,
-                        "ToolCallChunk",
-                        &serde_json::json!({ "action_id": action_id(&action), "chunk": chunk }),
-                    );
-                };
-                let observation = match self.runtime.dispatch(
-                    &action,
-                    snapshot.tool_context.session_id.clone(),
-                    &mut chunk_handler,
-                ) {
-                    Ok(obs) => obs,
-                    Err(err) => {
-                        let message = if err.trim().is_empty() {
-                            "Runtime error".to_string()
-                        } else {
-                            err.clone()
-                        };
-                        let _ = self.update_state(|state| {
-                            state.agent_state = RunAgentState::Error;
-                            state.last_error = Some(message.clone());
-                        });
-                        self.events.emit(
-                            &app,
-                            "ToolCallFinished",
-                            &serde_json::json!({
-                                "action": action,
-                                "summary": message,
-                                "ok": false,
-                                "exit_code": serde_json::Value::Null,
-                            }),
-                        );
-                        self.events
-                            .emit(&app, "Error", &serde_json::json!({ "message": message }));
-                        self.emit_state(&app, "runtime_error");
-                        break 'run;
+            if let Ok(mut limiter) = self.rate_limiter.lock() {
+                limiter.begin_step();
+            }
+            let session_id = snapshot.tool_context.session_id.clone();
+            for batch in batch_independent_actions(actions) {
+                let prepared = {
+                    let _step = step_span.enter();
+                    let mut prepared = Vec::new();
+                    for (index, action) in batch {
+                        match self.prepare_action(&app, &snapshot.run_id, action) {
+                            ActionGate::Ready(ready) => prepared.push((index, ready)),
+                            ActionGate::Skip => {}
+                            ActionGate::Stop => break 'run,
+                        }
                     }
+                    prepared
                 };
-                self.events.emit(
-                    &app,
-                    "ToolCallFinished",
-                    &serde_json::json!({
-                        "action": action,
-                        "summary": observation.summary,
-                        "ok": observation.ok,
-                        "exit_code": observation.exit_code,
-                    }),
-                );
-                self.events
-                    .emit(&app, "Observation", &serde_json::json!({ "observation": observation }));
-                let _ = self.apply_observation(&app, &action, &observation);
-                if let Some(step_id) = &exec_step_id {
-                    if is_execution_action(&action) {
-                        if observation.ok {
-                            if Some(index) == last_exec_index {
-                                let _ = self.update_plan_status(
-                                    &app,
-                                    KernelPlanStatusRequest {
-                                        id: step_id.clone(),
-                                        status: "done".to_string(),
-                                    },
-                                );
-                            }
-                        } else {
-                            let _ = self.update_plan_status(
-                                &app,
-                                KernelPlanStatusRequest {
-                                    id: step_id.clone(),
-                                    status: "error".to_string(),
-                                },
-                            );
-                        }
+                let results = self
+                    .dispatch_prepared(&app, &prepared, session_id.clone())
+                    .instrument(step_span.clone())
+                    .await;
+                let _step = step_span.enter();
+                let mut stopped = false;
+                for ((index, ready), result) in prepared.into_iter().zip(results) {
+                    if stopped {
+                        // Already ran alongside the action that stopped the run.
+                        self.emit_tool_call_failed(&app, &ready.action, "Skipped: the run stopped");
+                        continue;
                     }
+                    let exec_step = exec_step_id
+                        .as_ref()
+                        .map(|step_id| (step_id, Some(index) == last_exec_index));
+                    stopped = self.finish_action(&app, &snapshot.run_id, ready, result, exec_step);
                 }
-                if observation.requires_user {
-                    self.emit_state(&app, "awaiting_user");
+                if stopped {
                     break 'run;
                 }
             }
@@ -1420,118 +3741,650 @@ impl KernelManager {
                         }
                     }
                     Err(err) => {
-                        self.events.emit(&app, "Error", &serde_json::json!({ "message": err }));
+                        self.events
+                            .emit(&app, "Error", &serde_json::json!({ "message": err }));
                     }
                 }
             }
         }
+        if let Ok(snapshot) = self.snapshot_agent_state() {
+            if matches!(
+                snapshot.agent_state,
+                RunAgentState::Finished | RunAgentState::Error
+            ) {
+                let outcome = match snapshot.agent_state {
+                    RunAgentState::Error => "error",
+                    _ => "finished",
+                };
+                metrics::record_run_finished(outcome);
+                self.cleanup_run_sessions(&app, &snapshot);
+                self.upload_run_artifacts(&app, &snapshot).await;
+                self.cleanup_run_scratch(&app, &snapshot);
+            }
+            tracing::info!(state = ?snapshot.agent_state, "run loop stopped");
+        }
         self.running.store(false, Ordering::SeqCst);
     }
 
-    fn evaluate_judge(&self, app: &AppHandle, snapshot: &RunState) -> Result<bool, String> {
-        let rules = self.get_judge_rules().unwrap_or_default();
-        let context = JudgeContext {
-            command: snapshot.tool_context.cwd.clone(),
-            exit_code: 0,
-            stdout: snapshot.recent_observations.join("\n"),
-            stderr: snapshot.last_error.clone().unwrap_or_default(),
+    /// Compares parsed test results with earlier ones and pushes a corrective
+    /// observation when tests that passed start failing after an edit.
+    fn watch_for_regressions(
+        &self,
+        app: &dyn EventSink,
+        action: &Action,
+        observation: &Observation,
+    ) {
+        let Ok(mut watchdog) = self.regressions.lock() else {
+            return;
         };
-        let result = JudgeEngine::evaluate_rules(&rules, &context, |rule, ctx| {
-            self.evaluate_judge_rule(app, rule, ctx)
-        });
-        self.events
-            .emit(app, "JudgeResult", &serde_json::json!({ "result": result }));
-        self.apply_judge_result(app, &result)
+        if observation.ok {
+            if let Some(edit) = mutating_action_reason(action) {
+                watchdog.note_edit(edit);
+                return;
+            }
+        }
+        if !matches!(
+            action,
+            Action::TestsRun { .. } | Action::TerminalRun { .. } | Action::TerminalExec { .. }
+        ) {
+            return;
+        }
+        let output = observation
+            .artifacts
+            .as_ref()
+            .and_then(|artifacts| artifacts.get("full_output"))
+            .and_then(|value| value.as_str())
+            .unwrap_or(&observation.summary);
+        let results = parse_test_results(output);
+        let step = self
+            .snapshot_agent_state()
+            .map(|state| state.budget.used_steps)
+            .unwrap_or_default();
+        let Some(regression) = watchdog.record(step, results) else {
+            return;
+        };
+        drop(watchdog);
+        self.events.emit(
+            app,
+            "Regression",
+            &serde_json::json!({ "action_id": action_id(action), "regression": regression }),
+        );
+        let notice = regression.corrective_observation();
+        let _ = self.update_state(|state| state.push_observation(notice.clone()));
+        self.emit_state(app, "regression");
     }
 
-    fn apply_judge_result(
+    /// Classifies failed commands that hit a missing module or crate and proposes
+    /// the install command. Outside readonly mode the run stops until the user
+    /// approves it with "continue"; the install then runs before the next step.
+    fn propose_dependency_install(
         &self,
-        app: &AppHandle,
-        result: &JudgeResult,
-    ) -> Result<bool, String> {
-        match result.status.as_str() {
-            "pass" => {
-                let _ = self.update_state(|state| {
-                    state.agent_state = RunAgentState::Finished;
-                    state.last_error = None;
-                });
-                self.emit_state(app, "judge_pass");
-                Ok(true)
-            }
-            "fail" => {
-                let summary = if result.message.trim().is_empty() {
-                    "Judge failed".to_string()
-                } else {
-                    trim_to(&result.message, 800)
-                };
-                let _ = self.update_state(|state| {
-                    state.last_error = Some(summary);
-                });
-                self.emit_state(app, "judge_fail");
-                Ok(false)
-            }
-            _ => Ok(false),
+        app: &dyn EventSink,
+        action: &Action,
+        observation: &Observation,
+    ) -> bool {
+        if observation.ok
+            || !matches!(
+                action,
+                Action::TestsRun { .. } | Action::TerminalRun { .. } | Action::TerminalExec { .. }
+            )
+        {
+            return false;
+        }
+        let output = observation
+            .artifacts
+            .as_ref()
+            .and_then(|artifacts| artifacts.get("full_output"))
+            .and_then(|value| value.as_str())
+            .unwrap_or(&observation.summary);
+        let root = self.runtime.workspace.root();
+        let Some(dependency) = detect_missing_dependency(&root, output) else {
+            return false;
+        };
+        let command = dependency.install_command();
+        self.events.emit(
+            app,
+            "MissingDependency",
+            &serde_json::json!({
+                "action_id": action_id(action),
+                "dependency": dependency,
+                "command": command,
+            }),
+        );
+        let notice = format!(
+            "Missing dependency: {} ({}). Install it with `{}` instead of retrying the command.",
+            dependency.name, dependency.ecosystem, command
+        );
+        let readonly = self
+            .update_state(|state| state.push_observation(notice.clone()))
+            .map(|state| state.readonly)
+            .unwrap_or(true);
+        if readonly || self.is_reviewer_mode() {
+            self.emit_state(app, "missing_dependency");
+            return false;
+        }
+        if let Ok(mut pending) = self.pending_install.lock() {
+            *pending = Some(PendingInstall {
+                dependency: dependency.clone(),
+                approved: false,
+            });
         }
+        let question = format!(
+            "`{}` failed because {} is not installed.\n\
+             Reply \"continue\" to run `{}`, or reply with other instructions.",
+            action_type(action),
+            dependency.name,
+            command
+        );
+        let ask = Action::UserAsk {
+            id: make_id("install"),
+            question: question.clone(),
+        };
+        let _ = self.update_state(|state| {
+            state.agent_state = RunAgentState::AwaitingUser;
+            state.messages.push(ChatMessage {
+                role: "assistant".to_string(),
+                content: question.clone(),
+            });
+        });
+        self.events.emit(
+            app,
+            "AgentActionProposed",
+            &serde_json::json!({ "action": ask }),
+        );
+        self.emit_state(app, "missing_dependency");
+        true
     }
 
-    fn evaluate_judge_rule(
-        &self,
-        app: &AppHandle,
-        rule: &JudgeRule,
-        context: &JudgeContext,
-    ) -> JudgeRuleOutcome {
-        let rule_type = rule.rule_type.trim().to_lowercase();
-        match rule_type.as_str() {
-            "command" => self.run_judge_command(app, rule, false),
-            "tests" | "tests.run" => self.run_judge_command(app, rule, true),
-            "git_clean" | "git.clean" => self.run_judge_git_clean(app),
-            "no_error" | "last_error" => {
-                if !context.stderr.is_empty() {
-                    JudgeRuleOutcome::fail("last_error is set".to_string())
-                } else {
-                    JudgeRuleOutcome::pass()
-                }
+    /// With the write preview on, stops the run at an `fs.write` and emits
+    /// `FileWritePreview` with the diff. Returns whether the write was held;
+    /// a write that goes ahead is first backed up.
+    fn hold_write_for_preview(&self, app: &dyn EventSink, action: &Action) -> Result<bool, String> {
+        let Action::FsWrite { path, content, .. } = action else {
+            return Ok(false);
+        };
+        let root = self.runtime.workspace.root();
+        if !load_write_preview_policy(&root).enabled {
+            return Ok(false);
+        }
+        // Bad paths are reported by the write itself.
+        let Ok(resolved) = self.runtime.workspace.resolve_path_for_write(path) else {
+            return Ok(false);
+        };
+        let approved = match self.pending_write.lock() {
+            Ok(mut pending)
+                if pending.as_ref().is_some_and(|write| {
+                    write.approved && action_id(&write.action) == action_id(action)
+                }) =>
+            {
+                *pending = None;
+                true
             }
-            _ => JudgeRuleOutcome::fail(format!("unsupported rule type: {}", rule.rule_type)),
+            _ => false,
+        };
+        let autonomous = self
+            .get_task_constraints()
+            .ok()
+            .and_then(|constraints| constraints.autonomy)
+            .is_some_and(|autonomy| autonomy == "auto");
+        if approved || autonomous {
+            backup_before_write(&root, &resolved)
+                .map_err(|err| format!("Could not back up {} before writing: {}", path, err))?;
+            return Ok(false);
+        }
+        let current = std::fs::read_to_string(&resolved).ok();
+        let diff = unified_diff(path, current.as_deref().unwrap_or_default(), content);
+        if let Ok(mut pending) = self.pending_write.lock() {
+            *pending = Some(PendingWrite {
+                action: action.clone(),
+                approved: false,
+            });
         }
+        let question = format!(
+            "Review the proposed write to {}. Approve it or reply \"continue\" to apply \
+             it, or reply with other instructions.",
+            path
+        );
+        let _ = self.update_state(|state| {
+            state.agent_state = RunAgentState::AwaitingUser;
+            state.messages.push(ChatMessage {
+                role: "assistant".to_string(),
+                content: question.clone(),
+            });
+        });
+        self.events.emit(
+            app,
+            "FileWritePreview",
+            &serde_json::json!({
+                "action_id": action_id(action),
+                "path": path,
+                "diff": diff,
+                "created": current.is_none(),
+            }),
+        );
+        self.emit_state(app, "write_preview");
+        Ok(true)
     }
 
-    fn run_judge_command(
+    /// Approves or rejects the write held by the write preview and resumes the run.
+    pub fn approve_action(
         &self,
-        app: &AppHandle,
-        rule: &JudgeRule,
-        use_tests: bool,
-    ) -> JudgeRuleOutcome {
-        let command = match rule.command.as_ref() {
-            Some(items) if !items.is_empty() => items.clone(),
-            _ => return JudgeRuleOutcome::fail("command is required".to_string()),
-        };
-        let program = command[0].clone();
-        let args = command[1..].to_vec();
-        let id = make_id("judge");
-        let action = if use_tests {
-            Action::TestsRun {
-                id,
-                program: program.clone(),
-                args: args.clone(),
+        app: &dyn EventSink,
+        request: KernelApproveActionRequest,
+    ) -> Result<RunState, String> {
+        let rejected = {
+            let mut pending = self
+                .pending_write
+                .lock()
+                .map_err(|_| "Pending write lock poisoned".to_string())?;
+            let matches = pending
+                .as_ref()
+                .is_some_and(|write| action_id(&write.action) == request.action_id);
+            if !matches {
+                return Err(format!(
+                    "No write is waiting for approval as {}",
+                    request.action_id
+                ));
             }
-        } else {
-            Action::TerminalRun {
-                id,
-                program: program.clone(),
-                args: args.clone(),
-                cwd: None,
+            if request.approved {
+                if let Some(write) = pending.as_mut() {
+                    write.approved = true;
+                }
+                None
+            } else {
+                pending.take().map(|write| write.action)
             }
         };
-        self.emit_tool_call_started(app, &action);
-        let cwd = self.runtime.workspace.root();
-        let result = run_command(
-            CommandRequest {
-                program,
-                args: Some(args),
-                cwd: Some(cwd.to_string_lossy().to_string()),
-                env: None,
-                timeout_ms: None,
+        if let Some(action) = &rejected {
+            self.push_observation(
+                action,
+                "The user rejected this write; the file is unchanged.",
+            );
+        }
+        let snapshot = self.update_state(|state| {
+            state.agent_state = RunAgentState::Running;
+            state.last_error = None;
+        })?;
+        self.events.emit(
+            app,
+            "WritePreviewResolved",
+            &serde_json::json!({ "action_id": request.action_id, "approved": request.approved }),
+        );
+        self.emit_state(app, "write_preview_resolved");
+        if !self.running.swap(true, Ordering::SeqCst) {
+            let manager = self.clone();
+            let app_handle = app.clone_sink();
+            tauri::async_runtime::spawn(async move {
+                manager.run_loop(app_handle).await;
+            });
+        }
+        Ok(snapshot)
+    }
+
+    /// Executes a write approved from its preview. Returns whether the run has to stop.
+    fn run_approved_write(&self, app: &dyn EventSink, snapshot: &RunState) -> bool {
+        let action = match self.pending_write.lock() {
+            Ok(pending) => pending
+                .as_ref()
+                .filter(|write| write.approved)
+                .map(|write| write.action.clone()),
+            Err(_) => None,
+        };
+        let Some(action) = action else {
+            return false;
+        };
+        match self.prepare_action(app, &snapshot.run_id, action) {
+            ActionGate::Ready(ready) => {
+                let session_id = snapshot.tool_context.session_id.clone();
+                let result = self.dispatch_action(app, &ready.action, session_id);
+                self.finish_action(app, &snapshot.run_id, ready, result, None)
+            }
+            ActionGate::Skip => false,
+            ActionGate::Stop => true,
+        }
+    }
+
+    /// Runs an install the user approved through the same gate as any other
+    /// action, so command policy, capabilities and auditing still apply.
+    fn run_approved_install(&self, app: &dyn EventSink, snapshot: &RunState) -> bool {
+        let dependency = match self.pending_install.lock() {
+            Ok(mut pending) if pending.as_ref().is_some_and(|install| install.approved) => {
+                pending.take().map(|install| install.dependency)
+            }
+            _ => None,
+        };
+        let Some(dependency) = dependency else {
+            return false;
+        };
+        let action = Action::TerminalRun {
+            id: make_id("install"),
+            program: dependency.program,
+            args: dependency.args,
+            cwd: None,
+        };
+        match self.prepare_action(app, &snapshot.run_id, action) {
+            ActionGate::Ready(ready) => {
+                let session_id = snapshot.tool_context.session_id.clone();
+                let result = self.dispatch_action(app, &ready.action, session_id);
+                self.finish_action(app, &snapshot.run_id, ready, result, None)
+            }
+            ActionGate::Skip => false,
+            ActionGate::Stop => true,
+        }
+    }
+
+    /// Scans the workspace changes for secrets and, when new hits are found, stops
+    /// to ask the user. Hits the user has already been asked about are not raised again.
+    fn block_on_secrets(&self, app: &dyn EventSink, include_untracked: bool) -> bool {
+        let findings = scan_workspace(&self.runtime.workspace.root(), include_untracked);
+        let Ok(acknowledged) = self.acknowledged_secrets.lock() else {
+            return false;
+        };
+        let new_findings: Vec<SecretFinding> = findings
+            .into_iter()
+            .filter(|finding| !acknowledged.contains(&finding.fingerprint()))
+            .collect();
+        drop(acknowledged);
+        if new_findings.is_empty() {
+            return false;
+        }
+        if let Ok(mut pending) = self.pending_secrets.lock() {
+            *pending = new_findings
+                .iter()
+                .map(SecretFinding::fingerprint)
+                .collect();
+        }
+        let question = format!(
+            "Possible secrets found in the changes:\n{}\n\
+             Remove them, or reply \"continue\" if they are safe.",
+            format_findings(&new_findings)
+        );
+        let action = Action::UserAsk {
+            id: make_id("secrets"),
+            question: question.clone(),
+        };
+        let _ = self.update_state(|state| {
+            state.agent_state = RunAgentState::AwaitingUser;
+            state.last_error = Some(trim_to(&question, 800));
+            state.messages.push(ChatMessage {
+                role: "assistant".to_string(),
+                content: question.clone(),
+            });
+        });
+        self.events.emit(
+            app,
+            "SecretsDetected",
+            &serde_json::json!({ "findings": new_findings }),
+        );
+        self.events.emit(
+            app,
+            "AgentActionProposed",
+            &serde_json::json!({ "action": action }),
+        );
+        self.emit_state(app, "secrets_detected");
+        true
+    }
+
+    /// Runs the configured post-run upload; failures are reported but never
+    /// change the run outcome.
+    async fn upload_run_artifacts(&self, app: &dyn EventSink, snapshot: &RunState) {
+        let root = self.runtime.workspace.root();
+        let policy = load_upload_policy(&root);
+        if !policy.enabled {
+            return;
+        }
+        let events = self
+            .events
+            .read_events(&snapshot.run_id)
+            .unwrap_or_default();
+        match upload_run_artifacts(&root, &policy, snapshot, &events).await {
+            Ok(report) => {
+                self.runtime.audit.write(AuditEntry {
+                    timestamp_ms: now_ms(),
+                    action: "run.upload".to_string(),
+                    session_id: None,
+                    command: None,
+                    payload: serde_json::json!({ "runId": snapshot.run_id, "report": report }),
+                });
+                self.events.emit(
+                    app,
+                    "ArtifactsUploaded",
+                    &serde_json::json!({ "report": report }),
+                );
+            }
+            Err(err) => {
+                self.events.emit(
+                    app,
+                    "Error",
+                    &serde_json::json!({ "message": format!("Artifact upload failed: {}", err) }),
+                );
+            }
+        }
+    }
+
+    /// Opens a terminal session owned by the run, so session lists can tell it
+    /// apart from the user's and it is closed when the run ends. Without one
+    /// each command runs in a throwaway shell.
+    fn open_run_session(
+        &self,
+        app: &dyn EventSink,
+        run_id: &str,
+        task_id: Option<String>,
+    ) -> Option<String> {
+        let request = TerminalCreateRequest {
+            shell: None,
+            shell_args: None,
+            cwd: None,
+            cols: 120,
+            rows: 30,
+            title: Some("Agent run".to_string()),
+            color: None,
+            icon: None,
+            profile: None,
+            run_id: Some(run_id.to_string()),
+            task_id,
+        };
+        self.runtime
+            .terminal
+            .create_session(
+                app,
+                request,
+                self.runtime.workspace.root(),
+                &self.runtime.audit,
+            )
+            .ok()
+            .map(|info| info.id)
+    }
+
+    fn cleanup_run_sessions(&self, app: &dyn EventSink, snapshot: &RunState) {
+        if !snapshot.auto_clean_sessions {
+            return;
+        }
+        let closed = self
+            .runtime
+            .terminal
+            .kill_run_sessions(&snapshot.run_id, &self.runtime.audit);
+        if !closed.is_empty() {
+            self.events.emit(
+                app,
+                "RunSessionsClosed",
+                &serde_json::json!({ "session_ids": closed }),
+            );
+        }
+    }
+
+    /// Deletes the run's `$SCRATCH` directory once the run has ended.
+    fn cleanup_run_scratch(&self, app: &dyn EventSink, snapshot: &RunState) {
+        let root = self.runtime.workspace.root();
+        self.runtime.workspace.set_scratch_run(None);
+        match remove_scratch_dir(&root, &snapshot.run_id) {
+            Ok(true) => {
+                self.events.emit(
+                    app,
+                    "RunScratchRemoved",
+                    &serde_json::json!({ "run_id": snapshot.run_id }),
+                );
+            }
+            Ok(false) => {}
+            Err(err) => tracing::warn!(error = %err, "failed to remove scratch directory"),
+        }
+    }
+
+    /// Runs every judge rule against the current workspace and run without
+    /// changing the run's state, so rules can be debugged before they gate completion.
+    /// `rules` overrides the configured rules, e.g. unsaved edits.
+    pub fn evaluate_judge_now(
+        &self,
+        app: &dyn EventSink,
+        rules: Option<Vec<JudgeRule>>,
+    ) -> Result<JudgeResult, String> {
+        if self.running.load(Ordering::SeqCst) {
+            return Err("Wait for the run to stop before evaluating judge rules".to_string());
+        }
+        let snapshot = self.snapshot_agent_state()?;
+        let configured = match rules {
+            Some(rules) => rules,
+            None => self.get_judge_rules()?,
+        };
+        let rules = self.with_constraint_rules(configured);
+        let context = judge_context(&snapshot, self.runtime.workspace.root());
+        Ok(JudgeEngine::evaluate_rules(
+            &rules,
+            &context,
+            |rule, ctx| self.evaluate_judge_rule(app, rule, ctx),
+        ))
+    }
+
+    fn with_constraint_rules(&self, mut rules: Vec<JudgeRule>) -> Vec<JudgeRule> {
+        let constraints = self.get_task_constraints().unwrap_or_default();
+        rules.extend(
+            constraints
+                .constraints
+                .iter()
+                .filter(|text| parse_constraint_check(text).is_some())
+                .map(|text| JudgeRule {
+                    rule_type: "constraint".to_string(),
+                    pattern: text.clone(),
+                    action: String::new(),
+                    command: None,
+                    fail_match: None,
+                    success_match: None,
+                    severity: JudgeSeverity::Blocker,
+                    weight: 1.0,
+                    min_coverage: None,
+                    when: None,
+                }),
+        );
+        rules
+    }
+
+    fn evaluate_judge(&self, app: &dyn EventSink, snapshot: &RunState) -> Result<bool, String> {
+        let rules = self.with_constraint_rules(self.get_judge_rules().unwrap_or_default());
+        let context = judge_context(snapshot, self.runtime.workspace.root());
+        let result = JudgeEngine::evaluate_rules(&rules, &context, |rule, ctx| {
+            self.evaluate_judge_rule(app, rule, ctx)
+        });
+        self.events
+            .emit(app, "JudgeResult", &serde_json::json!({ "result": result }));
+        self.apply_judge_result(app, &result)
+    }
+
+    fn apply_judge_result(
+        &self,
+        app: &dyn EventSink,
+        result: &JudgeResult,
+    ) -> Result<bool, String> {
+        match result.status.as_str() {
+            "pass" => {
+                if self.block_on_secrets(app, true) {
+                    return Ok(true);
+                }
+                let _ = self.update_state(|state| {
+                    state.agent_state = RunAgentState::Finished;
+                    state.last_error = None;
+                });
+                self.emit_state(app, "judge_pass");
+                Ok(true)
+            }
+            "fail" => {
+                let summary = if result.message.trim().is_empty() {
+                    "Judge failed".to_string()
+                } else {
+                    trim_to(&result.message, 800)
+                };
+                let _ = self.update_state(|state| {
+                    state.last_error = Some(summary);
+                });
+                self.emit_state(app, "judge_fail");
+                Ok(false)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    fn evaluate_judge_rule(
+        &self,
+        app: &dyn EventSink,
+        rule: &JudgeRule,
+        context: &JudgeContext,
+    ) -> JudgeRuleOutcome {
+        let rule_type = rule.rule_type.trim().to_lowercase();
+        match rule_type.as_str() {
+            "command" | "command_succeeds" => self.run_judge_command(app, rule, false),
+            "tests" | "tests.run" => self.run_judge_command(app, rule, true),
+            "coverage" => self.run_judge_coverage(app, rule),
+            "git_clean" | "git.clean" => self.run_judge_git_clean(app),
+            "diff_nonempty" => self.run_judge_diff_nonempty(app, &rule.pattern),
+            "constraint" => self.check_constraint(&rule.pattern),
+            "file_exists" => self.check_file_exists(&rule.pattern),
+            "output_matches" => check_output_matches(&rule.pattern, context),
+            "no_error" | "last_error" => {
+                if !context.stderr.is_empty() {
+                    JudgeRuleOutcome::fail("last_error is set".to_string())
+                } else {
+                    JudgeRuleOutcome::pass()
+                }
+            }
+            _ => JudgeRuleOutcome::fail(format!("unsupported rule type: {}", rule.rule_type)),
+        }
+    }
+
+    fn run_judge_command(
+        &self,
+        app: &dyn EventSink,
+        rule: &JudgeRule,
+        use_tests: bool,
+    ) -> JudgeRuleOutcome {
+        let command = match rule.command.as_ref() {
+            Some(items) if !items.is_empty() => items.clone(),
+            _ => return JudgeRuleOutcome::fail("command is required".to_string()),
+        };
+        let program = command[0].clone();
+        let args = command[1..].to_vec();
+        let id = make_id("judge");
+        let action = if use_tests {
+            Action::TestsRun {
+                id,
+                program: program.clone(),
+                args: args.clone(),
+            }
+        } else {
+            Action::TerminalRun {
+                id,
+                program: program.clone(),
+                args: args.clone(),
+                cwd: None,
+            }
+        };
+        self.emit_tool_call_started(app, &action);
+        let cwd = self.runtime.workspace.root();
+        let result = run_command(
+            CommandRequest {
+                program,
+                args: Some(args),
+                cwd: Some(cwd.to_string_lossy().to_string()),
+                env: None,
+                timeout_ms: None,
             },
             cwd.to_string_lossy().as_ref(),
             &self.runtime.audit,
@@ -1553,7 +4406,151 @@ impl KernelManager {
         }
     }
 
-    fn run_judge_git_clean(&self, app: &AppHandle) -> JudgeRuleOutcome {
+    fn check_constraint(&self, constraint: &str) -> JudgeRuleOutcome {
+        let Some(check) = parse_constraint_check(constraint) else {
+            return JudgeRuleOutcome::pass();
+        };
+        let (args, label): (Vec<String>, &str) = match &check {
+            ConstraintCheck::NoNewDependencies => {
+                let mut args = vec!["diff".to_string(), "HEAD".to_string(), "-U0".to_string()];
+                args.push("--".to_string());
+                args.extend(
+                    DEPENDENCY_MANIFESTS
+                        .iter()
+                        .map(|name| format!(":(glob)**/{}", name)),
+                );
+                (args, "dependency manifests changed")
+            }
+            ConstraintCheck::NoNewFiles => (
+                [
+                    "status".to_string(),
+                    "--porcelain=v1".to_string(),
+                    "--untracked-files=all".to_string(),
+                ]
+                .into_iter()
+                .chain(without_scratch_pathspec())
+                .collect(),
+                "new files were added",
+            ),
+            ConstraintCheck::Untouched(path) => (
+                vec![
+                    "status".to_string(),
+                    "--porcelain=v1".to_string(),
+                    "--".to_string(),
+                    path.clone(),
+                ],
+                "protected path was modified",
+            ),
+        };
+        let cwd = self.runtime.workspace.root();
+        let result = run_command(
+            CommandRequest {
+                program: "git".to_string(),
+                args: Some(args),
+                cwd: Some(cwd.to_string_lossy().to_string()),
+                env: None,
+                timeout_ms: Some(30_000),
+            },
+            cwd.to_string_lossy().as_ref(),
+            &self.runtime.audit,
+        );
+        let output = match result {
+            Ok(result) if result.ok => result.stdout_excerpt.unwrap_or_default(),
+            Ok(_) => return JudgeRuleOutcome::pass(),
+            Err(err) => return JudgeRuleOutcome::fail(err),
+        };
+        let offending: Vec<&str> = output
+            .lines()
+            .filter(|line| match &check {
+                ConstraintCheck::NoNewDependencies => {
+                    let added = line.strip_prefix('+').filter(|_| !line.starts_with("+++"));
+                    added
+                        .map(|line| {
+                            let line = line.trim();
+                            !line.is_empty()
+                                && !line.starts_with("version")
+                                && !line.starts_with("\"version\"")
+                        })
+                        .unwrap_or(false)
+                }
+                ConstraintCheck::NoNewFiles => line.starts_with("??") || line.starts_with("A "),
+                ConstraintCheck::Untouched(_) => !line.trim().is_empty(),
+            })
+            .collect();
+        if offending.is_empty() {
+            return JudgeRuleOutcome::pass();
+        }
+        let mut outcome =
+            JudgeRuleOutcome::fail(format!("Constraint violated ({}): {}", label, constraint));
+        outcome.evidence.push(trim_to(&offending.join("\n"), 2000));
+        outcome
+    }
+
+    fn run_judge_coverage(&self, app: &dyn EventSink, rule: &JudgeRule) -> JudgeRuleOutcome {
+        let cwd = self.runtime.workspace.root();
+        let command = match rule.command.as_deref().and_then(CoverageCommand::custom) {
+            Some(command) => command,
+            None => match detect_coverage_command(&cwd) {
+                Some(command) => command,
+                None => return JudgeRuleOutcome::fail("no coverage tooling detected".to_string()),
+            },
+        };
+        let action = Action::TestsRun {
+            id: make_id("judge"),
+            program: command.program.clone(),
+            args: command.args.clone(),
+        };
+        self.emit_tool_call_started(app, &action);
+        let result = run_command(
+            CommandRequest {
+                program: command.program.clone(),
+                args: Some(command.args.clone()),
+                cwd: Some(cwd.to_string_lossy().to_string()),
+                env: None,
+                timeout_ms: Some(COVERAGE_TIMEOUT_MS),
+            },
+            cwd.to_string_lossy().as_ref(),
+            &self.runtime.audit,
+        );
+        let tool_result = match result {
+            Ok(tool_result) => tool_result,
+            Err(err) => {
+                self.emit_tool_call_failed(app, &action, &err);
+                return JudgeRuleOutcome::fail(err);
+            }
+        };
+        let min = rule.min_coverage.unwrap_or_default();
+        let percent = coverage_from_result(&tool_result);
+        let mut outcome = match percent {
+            Some(percent) if percent >= min => JudgeRuleOutcome::pass(),
+            Some(percent) => JudgeRuleOutcome::fail(format!(
+                "coverage {:.1}% is below the {}% threshold",
+                percent, min
+            )),
+            None if !tool_result.ok => JudgeRuleOutcome::fail(format!(
+                "{} failed (exit_code: {})",
+                command.display(),
+                tool_result
+                    .exit_code
+                    .map(|value| value.to_string())
+                    .unwrap_or_else(|| "unknown".to_string())
+            )),
+            None => JudgeRuleOutcome::fail("coverage percentage not found in output".to_string()),
+        };
+        if let Some(percent) = percent {
+            outcome
+                .evidence
+                .push(format!("{}: {:.1}% coverage", command.tool, percent));
+        }
+        let mut on_chunk = |chunk: String| {
+            self.emit_tool_call_chunk(app, &action, &chunk);
+        };
+        let observation = tool_result_to_observation(tool_result, &mut on_chunk);
+        self.emit_tool_call_finished(app, &action, &observation);
+        outcome
+    }
+
+    fn run_judge_git_clean(&self, app: &dyn EventSink) -> JudgeRuleOutcome {
         let id = make_id("judge");
         let action = Action::GitStatus { id };
         self.emit_tool_call_started(app, &action);
@@ -1561,11 +4558,16 @@ impl KernelManager {
         let result = run_command(
             CommandRequest {
                 program: "git".to_string(),
-                args: Some(vec![
-                    "status".to_string(),
-                    "--porcelain=v1".to_string(),
-                    "--untracked-files=all".to_string(),
-                ]),
+                args: Some(
+                    [
+                        "status".to_string(),
+                        "--porcelain=v1".to_string(),
+                        "--untracked-files=all".to_string(),
+                    ]
+                    .into_iter()
+                    .chain(without_scratch_pathspec())
+                    .collect(),
+                ),
                 cwd: Some(cwd.to_string_lossy().to_string()),
                 env: None,
                 timeout_ms: None,
@@ -1607,11 +4609,85 @@ impl KernelManager {
         }
     }
 
-    fn evaluate_command_result(
-        &self,
-        rule: &JudgeRule,
-        result: &ToolResult,
-    ) -> JudgeRuleOutcome {
+    /// Passes when git reports changes, limited to `path` when one is given.
+    fn run_judge_diff_nonempty(&self, app: &dyn EventSink, path: &str) -> JudgeRuleOutcome {
+        let id = make_id("judge");
+        let action = Action::GitStatus { id };
+        self.emit_tool_call_started(app, &action);
+        let path = path.trim();
+        let mut args = vec![
+            "status".to_string(),
+            "--porcelain=v1".to_string(),
+            "--untracked-files=all".to_string(),
+        ];
+        if path.is_empty() {
+            args.extend(without_scratch_pathspec());
+        } else {
+            let expanded = self.runtime.workspace.expand_alias(path).ok().flatten();
+            args.push("--".to_string());
+            args.push(expanded.unwrap_or_else(|| path.to_string()));
+        }
+        let cwd = self.runtime.workspace.root();
+        let result = run_command(
+            CommandRequest {
+                program: "git".to_string(),
+                args: Some(args),
+                cwd: Some(cwd.to_string_lossy().to_string()),
+                env: None,
+                timeout_ms: None,
+            },
+            cwd.to_string_lossy().as_ref(),
+            &self.runtime.audit,
+        );
+        match result {
+            Ok(tool_result) => {
+                let stdout = tool_result.stdout_excerpt.clone().unwrap_or_default();
+                let mut outcome = if !tool_result.ok {
+                    let code = tool_result
+                        .exit_code
+                        .map(|value| value.to_string())
+                        .unwrap_or_else(|| "unknown".to_string());
+                    JudgeRuleOutcome::fail(format!("git status failed (exit_code: {})", code))
+                } else if stdout.trim().is_empty() && path.is_empty() {
+                    JudgeRuleOutcome::fail("the workspace has no changes".to_string())
+                } else if stdout.trim().is_empty() {
+                    JudgeRuleOutcome::fail(format!("{} has no changes", path))
+                } else {
+                    JudgeRuleOutcome::pass()
+                };
+                if !stdout.trim().is_empty() {
+                    outcome
+                        .evidence
+                        .push(format!("status: {}", trim_to(stdout.trim(), 2000)));
+                }
+                let mut on_chunk = |chunk: String| {
+                    self.emit_tool_call_chunk(app, &action, &chunk);
+                };
+                let observation = tool_result_to_observation(tool_result, &mut on_chunk);
+                self.emit_tool_call_finished(app, &action, &observation);
+                outcome
+            }
+            Err(err) => {
+                self.emit_tool_call_failed(app, &action, &err);
+                JudgeRuleOutcome::fail(err)
+            }
+        }
+    }
+
+    fn check_file_exists(&self, path: &str) -> JudgeRuleOutcome {
+        match self.runtime.workspace.resolve_path(path) {
+            Ok(resolved) => {
+                let mut outcome = JudgeRuleOutcome::pass();
+                outcome
+                    .evidence
+                    .push(format!("found: {}", display_path(&resolved)));
+                outcome
+            }
+            Err(_) => JudgeRuleOutcome::fail(format!("{} does not exist", path.trim())),
+        }
+    }
+
+    fn evaluate_command_result(&self, rule: &JudgeRule, result: &ToolResult) -> JudgeRuleOutcome {
         let mut outcome = if result.ok {
             JudgeRuleOutcome::pass()
         } else {
@@ -1629,54 +4705,605 @@ impl KernelManager {
                 outcome = JudgeRuleOutcome::fail(format!("fail_match triggered: {}", fail_match));
             }
         }
-        if let Some(success_match) = &rule.success_match {
-            if !success_match.trim().is_empty() && !combined.contains(success_match) {
-                outcome = JudgeRuleOutcome::fail(format!("success_match not found: {}", success_match));
-            }
+        if let Some(success_match) = &rule.success_match {
+            if !success_match.trim().is_empty() && !combined.contains(success_match) {
+                outcome =
+                    JudgeRuleOutcome::fail(format!("success_match not found: {}", success_match));
+            }
+        }
+        if !stdout.trim().is_empty() {
+            outcome
+                .evidence
+                .push(format!("stdout: {}", trim_to(stdout.trim(), 2000)));
+        }
+        if !stderr.trim().is_empty() {
+            outcome
+                .evidence
+                .push(format!("stderr: {}", trim_to(stderr.trim(), 2000)));
+        }
+        outcome
+    }
+
+    /// Checks the LLM, workspace, tools, budget and judge rules before the
+    /// first step; on failure the run ends in Error with the fixes.
+    async fn preflight(&self, app: &dyn EventSink) {
+        let snapshot = self.snapshot();
+        let profile = self.llm.get_active_profile();
+        let allowed = snapshot
+            .capabilities
+            .as_ref()
+            .map(|capabilities| capabilities.allowed.clone())
+            .unwrap_or_default();
+        let judge_rules = self.get_judge_rules().unwrap_or_default();
+        let root = self.runtime.workspace.root();
+        let report = run_preflight(PreflightInput {
+            root: &root,
+            profile: profile.as_ref(),
+            max_steps: snapshot.budget.max_steps,
+            allowed: &allowed,
+            readonly: snapshot.readonly,
+            judge_rules: &judge_rules,
+        })
+        .await;
+        self.events.emit(
+            app,
+            "PreflightReport",
+            &serde_json::json!({ "report": report }),
+        );
+        if report.ok {
+            return;
+        }
+        let message = report.failure_message();
+        let _ = self.update_state(|state| {
+            state.agent_state = RunAgentState::Error;
+            state.last_error = Some(message.clone());
+        });
+        self.events
+            .emit(app, "Error", &serde_json::json!({ "message": message }));
+        self.emit_state(app, "preflight_failed");
+    }
+
+    /// A run that waited for the user longer than the freshness policy allows
+    /// re-reads git status and diff, so its next decision sees changes made
+    /// to the workspace in the meantime.
+    fn refresh_stale_context(&self, app: &dyn EventSink) {
+        let mut awaiting_since = None;
+        let _ = self.update_state(|state| awaiting_since = state.awaiting_since.take());
+        let Some(since) = awaiting_since else {
+            return;
+        };
+        let waited_ms = now_ms().saturating_sub(since);
+        if !load_freshness_policy(&self.runtime.workspace.root()).is_stale(waited_ms) {
+            return;
+        }
+        let actions = [
+            Action::GitStatus {
+                id: make_id("refresh"),
+            },
+            Action::GitDiff {
+                id: make_id("refresh"),
+                path: None,
+            },
+        ];
+        let mut sections = Vec::new();
+        for action in &actions {
+            self.emit_tool_call_started(app, action);
+            match self.dispatch_action(app, action, None) {
+                Ok(observation) => {
+                    self.emit_tool_call_finished(app, action, &observation);
+                    let summary = observation.summary.trim();
+                    sections.push(format!(
+                        "{}: {}",
+                        action_type(action),
+                        if summary.is_empty() {
+                            "no output"
+                        } else {
+                            summary
+                        }
+                    ));
+                }
+                Err(err) => {
+                    self.emit_tool_call_failed(app, action, &err);
+                    sections.push(format!("{}: failed: {}", action_type(action), err));
+                }
+            }
+        }
+        let summary = trim_to(
+            &format!(
+                "Workspace refreshed after waiting {} minutes for the user; \
+                 earlier observations may be out of date.\n{}",
+                waited_ms / 60_000,
+                sections.join("\n")
+            ),
+            2000,
+        );
+        self.push_observation(&actions[0], &summary);
+        self.events.emit(
+            app,
+            "ContextRefreshed",
+            &serde_json::json!({ "waitedMs": waited_ms, "summary": summary }),
+        );
+    }
+
+    fn emit_tool_call_started(&self, app: &dyn EventSink, action: &Action) {
+        self.events.emit(
+            app,
+            "ToolCallStarted",
+            &serde_json::json!({ "action": action }),
+        );
+    }
+
+    fn emit_tool_call_chunk(&self, app: &dyn EventSink, action: &Action, chunk: &str) {
+        if chunk.trim().is_empty() {
+            return;
+        }
+        self.events.emit(
+            app,
+            "ToolCallChunk",
+            &serde_json::json!({ "action_id": action_id(action), "chunk": chunk }),
+        );
+    }
+
+    fn emit_tool_call_finished(
+        &self,
+        app: &dyn EventSink,
+        action: &Action,
+        observation: &Observation,
+    ) {
+        tracing::info!(
+            action_id = %action_id(action),
+            ok = observation.ok,
+            exit_code = ?observation.exit_code,
+            "tool call finished"
+        );
+        self.events.emit(
+            app,
+            "ToolCallFinished",
+            &serde_json::json!({
+                "action": action,
+                "ok": observation.ok,
+                "exit_code": observation.exit_code,
+                "summary": observation.summary,
+                "resources": observation
+                    .artifacts
+                    .as_ref()
+                    .and_then(|artifacts| artifacts.get("resources")),
+            }),
+        );
+    }
+
+    /// Runs the checks before an action executes and announces it. Refusals
+    /// are recorded as observations and skip the action.
+    fn prepare_action(&self, app: &dyn EventSink, run_id: &str, action: Action) -> ActionGate {
+        let _tool_call = tracing::info_span!(
+            "tool_call",
+            action_id = %action_id(&action),
+            action = action_type(&action)
+        )
+        .entered();
+        let current_state = match self.snapshot_agent_state() {
+            Ok(state) => state,
+            Err(err) => {
+                self.events
+                    .emit(app, "Error", &serde_json::json!({ "message": err }));
+                return ActionGate::Stop;
+            }
+        };
+        if current_state.agent_state != RunAgentState::Running {
+            return ActionGate::Stop;
+        }
+        if matches!(action, Action::UserAsk { .. }) {
+            let _ = self.update_state(|state| {
+                state.agent_state = RunAgentState::AwaitingUser;
+            });
+            self.events.emit(
+                app,
+                "AgentActionProposed",
+                &serde_json::json!({ "action": action }),
+            );
+            self.emit_state(app, "awaiting_user");
+            return ActionGate::Stop;
+        }
+        // Checks below see the paths the action really touches.
+        let (action, aliased, in_scratch) = self.with_path_aliases(action, run_id);
+        if let Err(notice) = self.check_capabilities(app, &current_state, &action) {
+            self.emit_tool_call_failed(app, &action, &notice);
+            self.push_observation(&action, &notice);
+            return ActionGate::Skip;
+        }
+        if current_state.readonly || self.is_reviewer_mode() {
+            if let Some(reason) = mutating_action_reason(&action) {
+                let change = PendingChange {
+                    id: make_id("change"),
+                    action: action.clone(),
+                    reason,
+                    created_at: now_ms(),
+                };
+                let notice = format!(
+                    "Readonly mode: {} was not executed and is recorded as pending change {}.",
+                    action_type(&action),
+                    change.id
+                );
+                self.events.emit(
+                    app,
+                    "ChangeProposed",
+                    &serde_json::json!({ "change": change }),
+                );
+                let _ = self.update_state(|state| {
+                    state.pending_changes.push(change.clone());
+                });
+                self.push_observation(&action, &notice);
+                self.emit_state(app, "change_proposed");
+                return ActionGate::Skip;
+            }
+        }
+        if let Err(notice) = self.check_rate_limit(&action) {
+            self.events.emit(
+                app,
+                "RateLimited",
+                &serde_json::json!({ "action": action, "message": notice }),
+            );
+            self.emit_tool_call_failed(app, &action, &notice);
+            self.push_observation(&action, &notice);
+            return ActionGate::Skip;
+        }
+        if let Err(notice) = self.check_visibility(app, &action, run_id) {
+            self.emit_tool_call_failed(app, &action, &notice);
+            self.push_observation(&action, &notice);
+            return ActionGate::Skip;
+        }
+
+        // Aliased paths are exact, so they skip fuzzy matching.
+        let (action, path_note) = if aliased {
+            (action, None)
+        } else {
+            self.resolve_write_path(action)
+        };
+        if let Err(notice) = self.check_internal_write(&action, run_id) {
+            self.emit_tool_call_failed(app, &action, &notice);
+            self.push_observation(&action, &notice);
+            return ActionGate::Skip;
+        }
+        // Scratch files are the run's own notes; workspace files still get a preview.
+        let held = if in_scratch {
+            Ok(false)
+        } else {
+            self.hold_write_for_preview(app, &action)
+        };
+        match held {
+            Ok(true) => return ActionGate::Stop,
+            Ok(false) => {}
+            Err(notice) => {
+                self.emit_tool_call_failed(app, &action, &notice);
+                self.push_observation(&action, &notice);
+                return ActionGate::Skip;
+            }
+        }
+        if is_git_commit(&action) && self.block_on_secrets(app, false) {
+            self.emit_tool_call_failed(
+                app,
+                &action,
+                "Blocked: possible secrets in the changes to commit",
+            );
+            return ActionGate::Stop;
+        }
+        let action = self.with_commit_trailer(action, run_id);
+        let content_before = self.write_target_content(&action);
+        self.events.emit(
+            app,
+            "ToolCallStarted",
+            &serde_json::json!({ "action": action }),
+        );
+        self.record_file_version(&action, "agent", Some(run_id.to_string()));
+        let hook_results = self.run_action_hooks("before", &action);
+        ActionGate::Ready(PreparedAction {
+            action,
+            path_note,
+            content_before,
+            hook_results,
+        })
+    }
+
+    /// Expands `$SCRATCH` and the workspace's `@` path aliases in the action,
+    /// creating the run's scratch directory when it is named. File paths get
+    /// the workspace-relative form. Commands only expand `$SCRATCH`, to the
+    /// absolute path since they may run in another cwd; `@` is common in
+    /// command lines, e.g. scoped npm packages. Also tells whether any alias
+    /// was expanded and whether one pointed into the scratch directory.
+    fn with_path_aliases(&self, mut action: Action, run_id: &str) -> (Action, bool, bool) {
+        let workspace = &self.runtime.workspace;
+        workspace.set_scratch_run(Some(run_id.to_string()));
+        let root = workspace.root();
+        let relative = scratch_relative(run_id);
+        let absolute = scratch_dir(&root, run_id).to_string_lossy().to_string();
+        let mut aliased = false;
+        let mut in_scratch = false;
+        let mut expand = |value: &mut String, path: bool| {
+            let replacement = if path { &relative } else { &absolute };
+            let expanded = expand_scratch(value, replacement).or_else(|| {
+                path.then(|| workspace.expand_alias(value).ok().flatten())
+                    .flatten()
+            });
+            if let Some(expanded) = expanded {
+                in_scratch |= expanded.contains(&relative) || expanded.contains(&absolute);
+                *value = expanded;
+                aliased = true;
+            }
+        };
+        match &mut action {
+            Action::FsRead { path, .. }
+            | Action::FsWrite { path, .. }
+            | Action::FsApplyPatch { path, .. }
+            | Action::CodeTodos {
+                path: Some(path), ..
+            } => expand(path, true),
+            Action::FsReadMany { paths, .. }
+            | Action::FsSearch {
+                paths: Some(paths), ..
+            } => paths.iter_mut().for_each(|path| expand(path, true)),
+            Action::TerminalExec { cmd, cwd, .. } => {
+                expand(cmd, false);
+                if let Some(cwd) = cwd {
+                    expand(cwd, true);
+                }
+            }
+            Action::TerminalRun {
+                program, args, cwd, ..
+            } => {
+                expand(program, false);
+                args.iter_mut().for_each(|arg| expand(arg, false));
+                if let Some(cwd) = cwd {
+                    expand(cwd, true);
+                }
+            }
+            Action::TestsRun { program, args, .. } => {
+                expand(program, false);
+                args.iter_mut().for_each(|arg| expand(arg, false));
+            }
+            _ => {}
+        }
+        if in_scratch {
+            let _ = create_dir_all(scratch_dir(&root, run_id));
+        }
+        (action, aliased, in_scratch)
+    }
+
+    fn push_observation(&self, action: &Action, notice: &str) {
+        let _ = self.update_state(|state| {
+            state.push_observation(format!("{}: {}", action_id(action), notice))
+        });
+    }
+
+    /// Runs a batch of independent reads concurrently; anything else runs on
+    /// its own. Results come back in the order of `prepared`.
+    async fn dispatch_prepared(
+        &self,
+        app: &SharedEventSink,
+        prepared: &[(usize, PreparedAction)],
+        session_id: Option<String>,
+    ) -> Vec<Result<Observation, String>> {
+        if prepared.len() < 2 {
+            return prepared
+                .iter()
+                .map(|(_, ready)| {
+                    self.dispatch_action(app.as_ref(), &ready.action, session_id.clone())
+                })
+                .collect();
+        }
+        let handles: Vec<_> = prepared
+            .iter()
+            .map(|(_, ready)| {
+                let manager = self.clone();
+                let app = app.clone();
+                let action = ready.action.clone();
+                let session_id = session_id.clone();
+                tauri::async_runtime::spawn_blocking(move || {
+                    manager.dispatch_action(app.as_ref(), &action, session_id)
+                })
+            })
+            .collect();
+        let mut results = Vec::with_capacity(handles.len());
+        for handle in handles {
+            results.push(
+                handle
+                    .await
+                    .map_err(|e| e.to_string())
+                    .and_then(|result| result),
+            );
+        }
+        results
+    }
+
+    fn dispatch_action(
+        &self,
+        app: &dyn EventSink,
+        action: &Action,
+        session_id: Option<String>,
+    ) -> Result<Observation, String> {
+        let mut chunk_handler = |chunk: String| {
+            let _ = self.events.emit(
+                app,
+                "ToolCallChunk",
+                &serde_json::json!({ "action_id": action_id(action), "chunk": chunk }),
+            );
+        };
+        self.runtime
+            .dispatch(action, session_id, &mut chunk_handler)
+    }
+
+    /// Records the outcome of an executed action. `exec_step` is the plan step
+    /// the action works on, and whether it is the step's last action. Returns
+    /// whether the run has to stop.
+    fn finish_action(
+        &self,
+        app: &dyn EventSink,
+        run_id: &str,
+        ready: PreparedAction,
+        result: Result<Observation, String>,
+        exec_step: Option<(&String, bool)>,
+    ) -> bool {
+        let PreparedAction {
+            action,
+            path_note,
+            content_before,
+            mut hook_results,
+        } = ready;
+        let _tool_call = tracing::info_span!(
+            "tool_call",
+            action_id = %action_id(&action),
+            action = action_type(&action)
+        )
+        .entered();
+        let mut observation = match result {
+            Ok(obs) => obs,
+            Err(err) => {
+                let message = if err.trim().is_empty() {
+                    "Runtime error".to_string()
+                } else {
+                    err.clone()
+                };
+                let _ = self.update_state(|state| {
+                    state.agent_state = RunAgentState::Error;
+                    state.last_error = Some(message.clone());
+                });
+                self.events.emit(
+                    app,
+                    "ToolCallFinished",
+                    &serde_json::json!({
+                        "action": action,
+                        "summary": message,
+                        "ok": false,
+                        "exit_code": serde_json::Value::Null,
+                    }),
+                );
+                self.events
+                    .emit(app, "Error", &serde_json::json!({ "message": message }));
+                self.emit_state(app, "runtime_error");
+                return true;
+            }
+        };
+        if let Some(note) = &path_note {
+            observation.summary = format!("{}\n{}", note, observation.summary);
+        }
+        if observation.ok {
+            self.record_provenance(&action, content_before.as_deref(), run_id);
         }
-        if !stdout.trim().is_empty() {
-            outcome
-                .evidence
-                .push(format!("stdout: {}", trim_to(stdout.trim(), 2000)));
+        if observation.ok {
+            hook_results.extend(self.run_action_hooks("after", &action));
         }
-        if !stderr.trim().is_empty() {
-            outcome
-                .evidence
-                .push(format!("stderr: {}", trim_to(stderr.trim(), 2000)));
+        if !hook_results.is_empty() {
+            attach_hook_results(&mut observation, &hook_results);
         }
-        outcome
-    }
-
-    fn emit_tool_call_started(&self, app: &AppHandle, action: &Action) {
-        self.events
-            .emit(app, "ToolCallStarted", &serde_json::json!({ "action": action }));
-    }
-
-    fn emit_tool_call_chunk(&self, app: &AppHandle, action: &Action, chunk: &str) {
-        if chunk.trim().is_empty() {
-            return;
+        if observation.ok {
+            let verify_results = self.verify_action(&action);
+            if !verify_results.is_empty() {
+                attach_verify_results(&mut observation, &verify_results);
+            }
         }
-        self.events.emit(
-            app,
-            "ToolCallChunk",
-            &serde_json::json!({ "action_id": action_id(action), "chunk": chunk }),
-        );
-    }
-
-    fn emit_tool_call_finished(&self, app: &AppHandle, action: &Action, observation: &Observation) {
         self.events.emit(
             app,
             "ToolCallFinished",
             &serde_json::json!({
                 "action": action,
+                "summary": observation.summary,
                 "ok": observation.ok,
                 "exit_code": observation.exit_code,
-                "summary": observation.summary,
             }),
         );
+        self.events.emit(
+            app,
+            "Observation",
+            &serde_json::json!({ "observation": observation }),
+        );
+        let _ = self.apply_observation(app, &action, &observation);
+        self.watch_for_regressions(app, &action, &observation);
+        if self.propose_dependency_install(app, &action, &observation) {
+            return true;
+        }
+        if let Some((step_id, last_exec)) = exec_step {
+            if is_execution_action(&action) {
+                if observation.ok {
+                    if last_exec {
+                        let _ = self.update_plan_status(
+                            app,
+                            KernelPlanStatusRequest {
+                                id: step_id.clone(),
+                                status: "done".to_string(),
+                            },
+                        );
+                    }
+                } else {
+                    let _ = self.update_plan_status(
+                        app,
+                        KernelPlanStatusRequest {
+                            id: step_id.clone(),
+                            status: "error".to_string(),
+                        },
+                    );
+                }
+            }
+        }
+        if observation.requires_user {
+            self.emit_state(app, "awaiting_user");
+            return true;
+        }
+        false
+    }
+
+    /// Keeps agent writes out of the app's own state under `.taurihands`;
+    /// only the run's scratch directory and the artifact store are writable.
+    fn check_internal_write(&self, action: &Action, run_id: &str) -> Result<(), String> {
+        let (Action::FsWrite { path, .. } | Action::FsApplyPatch { path, .. }) = action else {
+            return Ok(());
+        };
+        let workspace = &self.runtime.workspace;
+        // Unresolvable paths are reported when the write runs.
+        let Ok(resolved) = workspace.resolve_path_for_write(path) else {
+            return Ok(());
+        };
+        let resolved = resolved.canonicalize().unwrap_or(resolved);
+        let root = workspace.root();
+        let internal = root.join(".taurihands");
+        let writable = [scratch_dir(&root, run_id), internal.join("artifacts")];
+        if resolved.starts_with(&internal)
+            && !writable.iter().any(|allowed| resolved.starts_with(allowed))
+        {
+            return Err(format!(
+                "{} is TauriHands state; write to @scratch or @artifacts instead",
+                path
+            ));
+        }
+        Ok(())
+    }
+
+    /// Refuses actions outside the run's negotiated capabilities.
+    fn check_capabilities(
+        &self,
+        app: &dyn EventSink,
+        state: &RunState,
+        action: &Action,
+    ) -> Result<(), String> {
+        let Some(capabilities) = &state.capabilities else {
+            return Ok(());
+        };
+        let Some(source) = capabilities.denied_by(action_type(action)) else {
+            return Ok(());
+        };
+        let notice = format!(
+            "PolicyDenied: {} is not allowed in this run (denied by {}); use one of: {}",
+            action_type(action),
+            source,
+            capabilities.allowed.join(", ")
+        );
+        self.events.emit(
+            app,
+            "PolicyDenied",
+            &serde_json::json!({ "action": action, "source": source, "message": notice }),
+        );
+        Err(notice)
     }
 
-    fn emit_tool_call_failed(&self, app: &AppHandle, action: &Action, error: &str) {
+    fn emit_tool_call_failed(&self, app: &dyn EventSink, action: &Action, error: &str) {
         self.events.emit(
             app,
             "ToolCallFinished",
@@ -1698,47 +5325,184 @@ impl KernelManager {
 
     async fn decide_actions_with_llm(
         &self,
-        app: &AppHandle,
+        app: &dyn EventSink,
         state: &RunState,
     ) -> Result<LlmDecision, String> {
         let profile = self.llm.get_active_profile().ok_or_else(|| {
             "LLM profile not configured. Save a profile in LLM Settings.".to_string()
         })?;
-        let allowed = build_allowed_action_set(&profile);
-        let system_prompt = build_system_prompt(&profile, &allowed);
-        let user_prompt = build_user_prompt(state);
+        let allowed = match &state.capabilities {
+            Some(capabilities) => capabilities.allowed_set(),
+            None => build_allowed_action_set(&profile),
+        };
+        let constraints = self.constraints_prompt();
+        let mcp_tools = if allowed
+            .as_ref()
+            .is_none_or(|allowed| allowed.contains("mcp.call"))
+        {
+            self.runtime.mcp.tools()
+        } else {
+            Vec::new()
+        };
+        let mut extra = String::new();
+        let pinned = self.pinned_files_prompt(&state.pinned_files);
+        if !pinned.is_empty() {
+            extra.push_str("\nPinned files (always follow these conventions):\n");
+            extra.push_str(&pinned);
+        }
+        if let Some(session_id) = &state.tool_context.session_id {
+            let history = self
+                .runtime
+                .terminal
+                .get_history(TerminalHistoryRequest {
+                    session_id: session_id.clone(),
+                    limit: Some(10),
+                })
+                .unwrap_or_default();
+            if !history.is_empty() {
+                extra.push_str("\nRecent terminal commands (avoid re-running unless needed):\n");
+                for entry in history {
+                    extra.push_str(&format!("- {}\n", entry.command));
+                }
+            }
+        }
+        let policy = ContextPolicy::for_profile(&profile);
+        let reserved = reserved_prompt_tokens(&profile, &allowed, &constraints, &mcp_tools)
+            + estimate_tokens(&extra);
+        let (mut user_prompt, compaction) =
+            build_user_prompt(state, &policy, policy.prompt_budget(reserved));
+        if let Some(compaction) = compaction {
+            self.events
+                .emit(app, "ContextCompacted", &serde_json::json!(compaction));
+        }
+        user_prompt.push_str(&extra);
+        let (raw, source) = self
+            .request_decision(
+                app,
+                &profile,
+                &allowed,
+                &mcp_tools,
+                &constraints,
+                &user_prompt,
+            )
+            .await?;
+        let goal_hint = state
+            .plan
+            .as_ref()
+            .map(|plan| plan.goal.as_str())
+            .or_else(|| state.messages.last().map(|msg| msg.content.as_str()));
+        let prompt_tools = prompt_tools(&profile);
+        let mut decision = validate_decision(&raw, goal_hint, &prompt_tools);
+        decision.source = source;
+        if !decision.errors.is_empty() {
+            self.events.emit(
+                app,
+                "DecisionInvalid",
+                &serde_json::json!({
+                    "errors": decision.errors,
+                    "retry": true,
+                    "model": decision.source.model,
+                }),
+            );
+            let corrective = build_corrective_prompt(&user_prompt, &raw, &decision.errors);
+            let (retry_raw, retry_source) = self
+                .request_decision(
+                    app,
+                    &profile,
+                    &allowed,
+                    &mcp_tools,
+                    &constraints,
+                    &corrective,
+                )
+                .await?;
+            decision = validate_decision(&retry_raw, goal_hint, &prompt_tools);
+            decision.source = retry_source;
+            if !decision.errors.is_empty() {
+                self.events.emit(
+                    app,
+                    "DecisionInvalid",
+                    &serde_json::json!({
+                        "errors": decision.errors,
+                        "retry": false,
+                        "model": decision.source.model,
+                    }),
+                );
+                if decision.actions.is_empty() && decision.message.is_none() {
+                    return Err(format!(
+                        "LLM response failed validation after retry: {}",
+                        decision.errors.join("; ")
+                    ));
+                }
+                let dropped = format!("Dropped invalid actions: {}", decision.errors.join("; "));
+                let _ = self.update_state(|state| state.push_observation(trim_to(&dropped, 2000)));
+            }
+        }
+        Ok(decision)
+    }
+
+    async fn request_decision(
+        &self,
+        app: &dyn EventSink,
+        profile: &LlmProfile,
+        allowed: &Option<HashSet<String>>,
+        mcp_tools: &[McpTool],
+        constraints: &str,
+        user_prompt: &str,
+    ) -> Result<(String, DecisionSource), String> {
         let events = self.events.clone();
-        let app_handle = app.clone();
-        let raw = request_completion_stream(
-            &profile,
+        let used = self.select_llm_profile(profile);
+        // Decided per request: failover may land on a provider without tool support.
+        let native_tools = supports_native_tools(&used);
+        let mut system_prompt = build_system_prompt(profile, allowed, native_tools, mcp_tools);
+        if !constraints.is_empty() {
+            system_prompt.push('\n');
+            system_prompt.push_str(constraints);
+        }
+        let tools = if native_tools {
+            action_tool_definitions(profile, allowed, mcp_tools)
+        } else {
+            Vec::new()
+        };
+        let (result, trace) = request_completion_metered(
+            &used,
             &system_prompt,
-            &user_prompt,
+            user_prompt,
             LlmResponseFormat::ActionJson,
+            &tools,
             |chunk| {
                 if !chunk.trim().is_empty() {
                     events.emit(
-                        &app_handle,
+                        app,
                         "AgentMessageChunk",
                         &serde_json::json!({ "content": chunk }),
                     );
                 }
             },
         )
-        .await?;
-        events.emit(&app_handle, "AgentMessageDone", &serde_json::json!({}));
-        let goal_hint = state
-            .plan
-            .as_ref()
-            .map(|plan| plan.goal.as_str())
-            .or_else(|| state.messages.last().map(|msg| msg.content.as_str()));
-        let mut decision = parse_llm_response(&raw, goal_hint)?;
-        decision.actions.retain(|action| action_allowed(action, &allowed));
-        Ok(decision)
+        .await;
+        self.record_llm_result(app, profile, &used, &result, trace.usage);
+        let raw = result?;
+        // Kept apart from the message so it never reaches the prompt history.
+        events.emit(
+            app,
+            "AgentMessageDone",
+            &serde_json::json!({
+                "provider": used.provider,
+                "model": used.model,
+                "reasoning": trace.reasoning(),
+            }),
+        );
+        let source = DecisionSource {
+            step: 0,
+            provider: used.provider,
+            model: used.model,
+        };
+        Ok((raw, source))
     }
 
     fn apply_observation(
         &self,
-        app: &AppHandle,
+        app: &dyn EventSink,
         action: &Action,
         observation: &Observation,
     ) -> Result<(), String> {
@@ -1749,14 +5513,23 @@ impl KernelManager {
             if let Action::TaskUpdate { tasks, .. } = action {
                 state.tasks = Some(tasks.clone());
             }
-            let summary = trim_to(&observation.summary, 2000);
+            // Reads are already held to their own byte budget.
+            let limit = match action {
+                Action::FsRead { .. } => READ_CHUNK_BYTES + 1_000,
+                Action::FsReadMany { .. } => READ_MANY_BUDGET_BYTES + 1_000,
+                _ => 2000,
+            };
+            let mut summary = trim_to(&observation.summary, limit);
+            let diagnostics = observation_diagnostics(observation);
+            if !diagnostics.is_empty() {
+                summary = format!(
+                    "{}\nDiagnostics:\n{}",
+                    summary,
+                    format_diagnostics(&diagnostics, 10)
+                );
+            }
             if !summary.is_empty() {
-                state
-                    .recent_observations
-                    .push(format!("{}: {}", action_id(action), summary));
-                if state.recent_observations.len() > 6 {
-                    state.recent_observations.remove(0);
-                }
+                state.push_observation(format!("{}: {}", action_id(action), summary));
             }
             if observation.requires_user {
                 state.agent_state = RunAgentState::AwaitingUser;
@@ -1765,16 +5538,20 @@ impl KernelManager {
             }
             if observation.ok {
                 if let Some(plan) = &mut state.plan {
-                    if let Some(step) =
-                        plan.steps.iter_mut().find(|step| step.id == action_id(action))
+                    if let Some(step) = plan
+                        .steps
+                        .iter_mut()
+                        .find(|step| step.id == action_id(action))
                     {
                         step.status = "done".to_string();
                         step.done = true;
                     }
                 }
                 if let Some(tasks) = &mut state.tasks {
-                    if let Some(task) =
-                        tasks.items.iter_mut().find(|item| item.id == action_id(action))
+                    if let Some(task) = tasks
+                        .items
+                        .iter_mut()
+                        .find(|item| item.id == action_id(action))
                     {
                         task.status = "done".to_string();
                     }
@@ -1802,22 +5579,172 @@ impl KernelManager {
     }
 }
 
+fn apply_event_to_state(state: &mut RunState, event: &KernelEvent) {
+    let payload = &event.payload;
+    match event.event_type.as_str() {
+        "StateChanged" => {
+            if let Some(value) = payload.get("state") {
+                if let Ok(snapshot) = serde_json::from_value::<RunState>(value.clone()) {
+                    *state = snapshot;
+                }
+            }
+        }
+        "UserMessage" | "AgentMessage" => {
+            if let Some(content) = payload.get("content").and_then(|value| value.as_str()) {
+                let role = if event.event_type == "UserMessage" {
+                    "user"
+                } else {
+                    "assistant"
+                };
+                state.messages.push(ChatMessage {
+                    role: role.to_string(),
+                    content: content.to_string(),
+                });
+            }
+        }
+        "PlanUpdated" => {
+            if let Some(value) = payload.get("plan") {
+                if let Ok(plan) = serde_json::from_value::<Option<Plan>>(value.clone()) {
+                    state.plan = plan;
+                }
+            }
+        }
+        "TaskUpdated" => {
+            if let Some(value) = payload.get("tasks") {
+                if let Ok(tasks) = serde_json::from_value::<Option<TaskList>>(value.clone()) {
+                    state.tasks = tasks;
+                }
+            }
+        }
+        "Error" => {
+            if let Some(message) = payload.get("message").and_then(|value| value.as_str()) {
+                state.last_error = Some(message.to_string());
+            }
+        }
+        _ => {}
+    }
+}
+
+fn diff_run_states(rebuilt: &RunState, persisted: &RunState) -> Vec<String> {
+    let rebuilt = serde_json::to_value(rebuilt).unwrap_or_default();
+    let persisted = serde_json::to_value(persisted).unwrap_or_default();
+    let mut mismatches = Vec::new();
+    if let (Some(left), Some(right)) = (rebuilt.as_object(), persisted.as_object()) {
+        for (key, value) in left {
+            if right.get(key) != Some(value) {
+                mismatches.push(key.clone());
+            }
+        }
+        for key in right.keys() {
+            if !left.contains_key(key) {
+                mismatches.push(key.clone());
+            }
+        }
+    }
+    mismatches
+}
+
 fn action_id(action: &Action) -> String {
     match action {
         Action::TerminalExec { id, .. }
         | Action::TerminalRun { id, .. }
         | Action::FsRead { id, .. }
+        | Action::FsReadMany { id, .. }
         | Action::FsWrite { id, .. }
+        | Action::FsApplyPatch { id, .. }
         | Action::FsSearch { id, .. }
         | Action::GitStatus { id, .. }
         | Action::GitDiff { id, .. }
+        | Action::GitCommit { id, .. }
+        | Action::GitBranch { id, .. }
+        | Action::GitStash { id, .. }
+        | Action::GitCheckout { id, .. }
         | Action::TestsRun { id, .. }
+        | Action::DepsAudit { id }
+        | Action::CodeTodos { id, .. }
+        | Action::McpCall { id, .. }
         | Action::PlanUpdate { id, .. }
         | Action::TaskUpdate { id, .. }
         | Action::UserAsk { id, .. } => id.clone(),
     }
 }
 
+fn mutating_action_reason(action: &Action) -> Option<String> {
+    match action {
+        Action::FsWrite { path, .. } => Some(format!("Writes file {}", path)),
+        Action::FsApplyPatch { path, .. } => Some(format!("Patches file {}", path)),
+        Action::TerminalExec { cmd, .. } => {
+            let mut parts = cmd.split_whitespace();
+            let program = parts.next().unwrap_or_default();
+            let args: Vec<String> = parts.map(|part| part.to_string()).collect();
+            is_mutating_command(program, &args)
+        }
+        Action::TerminalRun { program, args, .. } => is_mutating_command(program, args),
+        Action::GitCommit { message, .. } => Some(format!(
+            "Commits: {}",
+            message.lines().next().unwrap_or_default()
+        )),
+        Action::GitBranch { name, .. } => Some(format!("Creates branch {}", name)),
+        Action::GitStash { pop: true, .. } => Some("Pops the latest stash".to_string()),
+        Action::GitStash { .. } => Some("Stashes uncommitted changes".to_string()),
+        Action::GitCheckout { target, .. } => Some(format!("Checks out {}", target)),
+        Action::McpCall { server, tool, .. } => Some(format!("Calls MCP tool {}/{}", server, tool)),
+        _ => None,
+    }
+}
+
+/// Path of the file an action rewrites, for history, provenance and verify.
+fn written_path(action: &Action) -> Option<&str> {
+    match action {
+        Action::FsWrite { path, .. } | Action::FsApplyPatch { path, .. } => Some(path.as_str()),
+        _ => None,
+    }
+}
+
+fn is_git_commit(action: &Action) -> bool {
+    match action {
+        Action::TerminalExec { cmd, .. } => cmd.split([';', '&', '|']).any(|part| {
+            let mut words = part.split_whitespace();
+            words.next() == Some("git") && words.any(|word| word == "commit")
+        }),
+        Action::TerminalRun { program, args, .. } => {
+            program == "git" && args.iter().any(|arg| arg == "commit")
+        }
+        Action::GitCommit { .. } => true,
+        _ => false,
+    }
+}
+
+/// Reads with no side effects, which may run concurrently with each other.
+fn is_parallel_safe(action: &Action) -> bool {
+    matches!(
+        action,
+        Action::FsRead { .. }
+            | Action::FsReadMany { .. }
+            | Action::FsSearch { .. }
+            | Action::GitStatus { .. }
+            | Action::GitDiff { .. }
+    )
+}
+
+/// Groups consecutive parallel-safe actions into one batch and gives every
+/// other action a batch of its own, so nothing is reordered around a write.
+fn batch_independent_actions(actions: Vec<Action>) -> Vec<Vec<(usize, Action)>> {
+    let mut batches: Vec<Vec<(usize, Action)>> = Vec::new();
+    for (index, action) in actions.into_iter().enumerate() {
+        let joins_previous = is_parallel_safe(&action)
+            && batches
+                .last()
+                .and_then(|batch| batch.last())
+                .is_some_and(|(_, previous)| is_parallel_safe(previous));
+        match batches.last_mut() {
+            Some(batch) if joins_previous => batch.push((index, action)),
+            _ => batches.push(vec![(index, action)]),
+        }
+    }
+    batches
+}
+
 fn is_execution_action(action: &Action) -> bool {
     !matches!(
         action,
@@ -1825,8 +5752,13 @@ fn is_execution_action(action: &Action) -> bool {
     )
 }
 
-fn select_next_plan_step(plan: &Plan) -> Option<String> {
+fn select_next_plan_step(plan: &Plan, selected: Option<&[String]>) -> Option<String> {
     for step in &plan.steps {
+        if let Some(selected) = selected {
+            if !selected.contains(&step.id) {
+                continue;
+            }
+        }
         if step.status != "done" && step.status != "skipped" {
             return Some(step.id.clone());
         }
@@ -1834,57 +5766,296 @@ fn select_next_plan_step(plan: &Plan) -> Option<String> {
     None
 }
 
+/// Adds `key` to the observation's artifacts, keeping a non-object value that
+/// was there before under `data`.
+pub(crate) fn merge_artifacts(observation: &mut Observation, key: &str, value: serde_json::Value) {
+    let mut artifacts = match observation.artifacts.take() {
+        Some(serde_json::Value::Object(map)) => map,
+        Some(other) => {
+            let mut map = serde_json::Map::new();
+            map.insert("data".to_string(), other);
+            map
+        }
+        None => serde_json::Map::new(),
+    };
+    artifacts.insert(key.to_string(), value);
+    observation.artifacts = Some(serde_json::Value::Object(artifacts));
+}
+
+fn attach_diagnostics(observation: &mut Observation) {
+    let diagnostics = parse_diagnostics(&observation.summary);
+    if diagnostics.is_empty() {
+        return;
+    }
+    merge_artifacts(observation, "diagnostics", serde_json::json!(diagnostics));
+}
+
+fn attach_hook_results(observation: &mut Observation, results: &[HookResult]) {
+    observation.summary = format!(
+        "{}\n\nHooks:\n{}",
+        observation.summary,
+        format_hook_results(results)
+    );
+    merge_artifacts(observation, "hooks", serde_json::json!(results));
+}
+
+fn attach_verify_results(observation: &mut Observation, results: &[VerifyResult]) {
+    observation.summary = format!(
+        "{}\n\nVerification:\n{}",
+        observation.summary,
+        format_verify_results(results)
+    );
+    merge_artifacts(observation, "verification", serde_json::json!(results));
+}
+
+fn observation_diagnostics(observation: &Observation) -> Vec<Diagnostic> {
+    observation
+        .artifacts
+        .as_ref()
+        .and_then(|artifacts| artifacts.get("diagnostics"))
+        .and_then(|value| serde_json::from_value(value.clone()).ok())
+        .unwrap_or_default()
+}
+
+/// Tells the model its command was rewritten, so it can adapt instead of
+/// being confused by PowerShell output for a POSIX command.
+fn with_translation(mut result: ToolResult, translation: CommandTranslation) -> ToolResult {
+    let note = translation.describe();
+    result.stdout_excerpt = Some(match result.stdout_excerpt.take() {
+        Some(stdout) if !stdout.trim().is_empty() => format!("{}\n{}", note, stdout),
+        _ => note,
+    });
+    let value = serde_json::json!(translation);
+    match result.artifacts.as_mut() {
+        Some(serde_json::Value::Object(artifacts)) => {
+            artifacts.insert("translation".to_string(), value);
+        }
+        _ => result.artifacts = Some(serde_json::json!({ "translation": value })),
+    }
+    result
+}
+
 fn tool_result_to_observation(result: ToolResult, on_chunk: &mut dyn FnMut(String)) -> Observation {
     let mut summary = String::new();
     if let Some(stdout) = &result.stdout_excerpt {
         if !stdout.trim().is_empty() {
             summary.push_str(stdout.trim());
         }
-    }
-    if let Some(stderr) = &result.stderr_excerpt {
-        if !stderr.trim().is_empty() {
-            if !summary.is_empty() {
-                summary.push('\n');
+    }
+    if let Some(stderr) = &result.stderr_excerpt {
+        if !stderr.trim().is_empty() {
+            if !summary.is_empty() {
+                summary.push('\n');
+            }
+            summary.push_str(stderr.trim());
+        }
+    }
+    if summary.is_empty() {
+        summary = if result.ok {
+            "ok".to_string()
+        } else {
+            "error".to_string()
+        };
+    }
+    on_chunk(summary.clone());
+    Observation {
+        ok: result.ok,
+        summary,
+        exit_code: result.exit_code,
+        artifacts: result.artifacts,
+        raw: None,
+        requires_user: result.requires_user,
+    }
+}
+
+fn read_file_tool(
+    workspace: &WorkspaceState,
+    audit: &AuditLog,
+    path: &str,
+    offset: u64,
+) -> Result<ToolResult, String> {
+    let request = ReadFileRequest {
+        path: path.to_string(),
+    };
+    let mut resolved_from = None;
+    let resolved = match resolve_read_path_with_fallback(workspace, &request.path) {
+        Ok(resolved) => resolved,
+        Err(err) => {
+            let root = workspace.root();
+            match resolve_fuzzy(&root, path) {
+                FuzzyMatch::None => return Err(err),
+                FuzzyMatch::Unique(found)
+                    if load_path_policy(&root).fuzzy_reads
+                        && load_visibility_policy(&root).allows_resolved(&root, &found) =>
+                {
+                    let resolved = workspace.resolve_path(&found)?;
+                    resolved_from = Some(found);
+                    resolved
+                }
+                matches => return Ok(path_not_found_result(path, &err, &matches.candidates())),
+            }
+        }
+    };
+    let mut file = std::fs::File::open(&resolved).map_err(|e| e.to_string())?;
+    let total = file.metadata().map_err(|e| e.to_string())?.len();
+    if offset > total {
+        return Err(format!(
+            "Offset {} is past the end of {} ({} bytes)",
+            offset, path, total
+        ));
+    }
+    file.seek(SeekFrom::Start(offset))
+        .map_err(|e| e.to_string())?;
+    let mut buffer = Vec::new();
+    file.take(READ_CHUNK_BYTES as u64)
+        .read_to_end(&mut buffer)
+        .map_err(|e| e.to_string())?;
+    // Stop before a multi-byte character split by the chunk boundary; the next
+    // chunk starts with it instead.
+    if let Err(err) = std::str::from_utf8(&buffer) {
+        if err.error_len().is_none() && err.valid_up_to() > 0 {
+            buffer.truncate(err.valid_up_to());
+        }
+    }
+    let next_offset = offset + buffer.len() as u64;
+    let remaining = total - next_offset;
+    let content = String::from_utf8_lossy(&buffer).to_string();
+    let display = resolved_from.as_deref().unwrap_or(path).to_string();
+    let mut summary = String::new();
+    if resolved_from.is_some() {
+        summary.push_str(&format!("Resolved {} to {}\n", path, display));
+    }
+    summary.push_str(&content);
+    if remaining > 0 {
+        summary.push_str(&format!(
+            "\n[truncated: bytes {}-{} of {}; remaining_bytes={}, next_offset={}. \
+             Continue with fs.read path=\"{}\" offset={}]",
+            offset, next_offset, total, remaining, next_offset, display, next_offset
+        ));
+    }
+    let mut result = read_file(request, content, remaining > 0, audit);
+    result.stdout_excerpt = Some(summary);
+    if let Some(serde_json::Value::Object(artifacts)) = result.artifacts.as_mut() {
+        artifacts.insert("offset".to_string(), serde_json::json!(offset));
+        artifacts.insert("total_bytes".to_string(), serde_json::json!(total));
+        if remaining > 0 {
+            artifacts.insert("next_offset".to_string(), serde_json::json!(next_offset));
+            artifacts.insert("remaining_bytes".to_string(), serde_json::json!(remaining));
+        }
+        if let Some(found) = resolved_from {
+            artifacts.insert("path".to_string(), serde_json::json!(found));
+            artifacts.insert("requested_path".to_string(), serde_json::json!(path));
+        }
+    }
+    Ok(result)
+}
+
+/// Reads several files into one observation with a section per file. Content is
+/// cut once the combined budget is spent; unreadable paths get an error section.
+fn read_many_tool(
+    workspace: &WorkspaceState,
+    audit: &AuditLog,
+    paths: &[String],
+) -> Result<ToolResult, String> {
+    let mut remaining = READ_MANY_BUDGET_BYTES;
+    let mut sections = Vec::new();
+    let mut files = Vec::new();
+    let mut read_count = 0;
+    for path in paths.iter().take(MAX_READ_MANY_FILES) {
+        if remaining == 0 {
+            sections.push(format!(
+                "--- {} ---\n[skipped: byte budget exhausted]",
+                path
+            ));
+            files.push(serde_json::json!({ "path": path, "ok": false, "skipped": true }));
+            continue;
+        }
+        let result = match read_file_tool(workspace, audit, path, 0) {
+            Ok(result) if result.ok => result,
+            Ok(result) => {
+                let error = result.stderr_excerpt.unwrap_or_default();
+                sections.push(format!("--- {} ---\n[error] {}", path, error));
+                files.push(serde_json::json!({ "path": path, "ok": false, "error": error }));
+                continue;
             }
-            summary.push_str(stderr.trim());
+            Err(err) => {
+                sections.push(format!("--- {} ---\n[error] {}", path, err));
+                files.push(serde_json::json!({ "path": path, "ok": false, "error": err }));
+                continue;
+            }
+        };
+        let artifacts = result.artifacts.unwrap_or_default();
+        let resolved = artifacts
+            .get("path")
+            .and_then(|value| value.as_str())
+            .unwrap_or(path);
+        let content = artifacts
+            .get("content")
+            .and_then(|value| value.as_str())
+            .unwrap_or_default();
+        let mut excerpt = trim_to(content, remaining);
+        remaining = remaining.saturating_sub(excerpt.len());
+        let next_offset = if excerpt.len() < content.len() {
+            Some(excerpt.len() as u64)
+        } else {
+            artifacts
+                .get("next_offset")
+                .and_then(|value| value.as_u64())
+        };
+        let truncated = next_offset.is_some();
+        if let Some(next_offset) = next_offset {
+            excerpt.push_str(&format!(
+                "\n... [truncated: continue with fs.read path=\"{}\" offset={}]",
+                resolved, next_offset
+            ));
         }
-    }
-    if summary.is_empty() {
-        summary = if result.ok {
-            "ok".to_string()
+        let header = if resolved == path {
+            format!("--- {} ---", path)
         } else {
-            "error".to_string()
+            format!("--- {} (requested {}) ---", resolved, path)
         };
+        sections.push(format!("{}\n{}", header, excerpt));
+        files.push(serde_json::json!({
+            "path": resolved,
+            "ok": true,
+            "bytes": content.len(),
+            "truncated": truncated,
+        }));
+        read_count += 1;
     }
-    on_chunk(summary.clone());
-    Observation {
-        ok: result.ok,
-        summary,
-        exit_code: result.exit_code,
-        artifacts: result.artifacts,
-        raw: None,
-        requires_user: result.requires_user,
+    if paths.len() > MAX_READ_MANY_FILES {
+        sections.push(format!(
+            "[skipped {} path(s): fs.read_many reads at most {} files]",
+            paths.len() - MAX_READ_MANY_FILES,
+            MAX_READ_MANY_FILES
+        ));
     }
+    Ok(ToolResult {
+        ok: read_count > 0,
+        stdout_excerpt: Some(sections.join("\n")),
+        stderr_excerpt: None,
+        exit_code: None,
+        artifacts: Some(serde_json::json!({ "files": files })),
+        next_suggestion: None,
+        requires_user: false,
+    })
 }
 
-fn read_file_tool(
-    workspace: &WorkspaceState,
-    audit: &AuditLog,
-    path: &str,
-) -> Result<ToolResult, String> {
-    let request = ReadFileRequest {
-        path: path.to_string(),
-    };
-    let resolved = resolve_read_path_with_fallback(workspace, &request.path)?;
-    let max_bytes = max_read_bytes();
-    let file = std::fs::File::open(&resolved).map_err(|e| e.to_string())?;
-    let metadata = file.metadata().map_err(|e| e.to_string())?;
-    let mut buffer = Vec::new();
-    let mut handle = file.take(max_bytes as u64);
-    std::io::Read::read_to_end(&mut handle, &mut buffer).map_err(|e| e.to_string())?;
-    let truncated = metadata.len() as usize > buffer.len();
-    let content = String::from_utf8_lossy(&buffer).to_string();
-    Ok(read_file(request, content, truncated, audit))
+fn path_not_found_result(path: &str, error: &str, candidates: &[String]) -> ToolResult {
+    ToolResult {
+        ok: false,
+        stdout_excerpt: None,
+        stderr_excerpt: Some(format!(
+            "{}: {}\nDid you mean: {}",
+            error,
+            path,
+            candidates.join(", ")
+        )),
+        exit_code: None,
+        artifacts: Some(serde_json::json!({ "path": path, "candidates": candidates })),
+        next_suggestion: None,
+        requires_user: false,
+    }
 }
 
 fn search_tool(
@@ -1892,11 +6063,14 @@ fn search_tool(
     audit: &AuditLog,
     pattern: &str,
     paths: &Option<Vec<String>>,
+    include_hidden: Option<bool>,
 ) -> Result<ToolResult, String> {
     let (resolved_paths, globs) = resolve_search_targets(workspace, paths);
+    let policy = load_visibility_policy(&workspace.root());
+    let hidden = policy.lists_hidden(policy.agent_include_hidden(include_hidden));
     let trimmed = pattern.trim();
     if trimmed == "*" {
-        let output = run_rg_files(&resolved_paths, &globs)?;
+        let output = run_rg_files(&resolved_paths, &globs, hidden)?;
         let matches = parse_rg_files(&output, 200);
         return Ok(search(
             SearchRequest {
@@ -1904,13 +6078,14 @@ fn search_tool(
                 paths: paths.clone(),
                 glob: None,
                 max_results: Some(200),
+                include_hidden,
             },
             matches,
             audit,
         ));
     }
     let (normalized, force_fixed) = normalize_search_pattern(trimmed);
-    let output = run_rg_search(&normalized, &resolved_paths, &globs, force_fixed)?;
+    let output = run_rg_search(&normalized, &resolved_paths, &globs, hidden, force_fixed)?;
     let matches = parse_rg_json(&output, 200);
     Ok(search(
         SearchRequest {
@@ -1918,6 +6093,7 @@ fn search_tool(
             paths: paths.clone(),
             glob: None,
             max_results: Some(200),
+            include_hidden,
         },
         matches,
         audit,
@@ -2014,6 +6190,7 @@ fn resolve_search_targets(
     if resolved.is_empty() {
         resolved.push(workspace.root());
     }
+    globs.extend(IgnoreRules::for_workspace(&workspace.root()).rg_globs());
     (resolved, globs)
 }
 
@@ -2025,15 +6202,16 @@ fn run_rg_search(
     pattern: &str,
     paths: &[PathBuf],
     globs: &[String],
+    hidden: bool,
     force_fixed: bool,
 ) -> Result<Vec<u8>, String> {
-    let output = run_rg_search_inner(pattern, paths, globs, force_fixed)?;
+    let output = run_rg_search_inner(pattern, paths, globs, hidden, force_fixed)?;
     if is_rg_ok(&output) {
         return Ok(output.stdout);
     }
     let stderr = String::from_utf8_lossy(&output.stderr);
     if !force_fixed && is_rg_regex_error(&stderr) {
-        let retry = run_rg_search_inner(pattern, paths, globs, true)?;
+        let retry = run_rg_search_inner(pattern, paths, globs, hidden, true)?;
         if is_rg_ok(&retry) {
             return Ok(retry.stdout);
         }
@@ -2043,9 +6221,12 @@ fn run_rg_search(
     Err(stderr.trim().to_string())
 }
 
-fn run_rg_files(paths: &[PathBuf], globs: &[String]) -> Result<Vec<u8>, String> {
+fn run_rg_files(paths: &[PathBuf], globs: &[String], hidden: bool) -> Result<Vec<u8>, String> {
     let mut cmd = std::process::Command::new("rg");
     cmd.arg("--files");
+    if hidden {
+        cmd.arg("--hidden");
+    }
     for glob in globs {
         cmd.arg("--glob").arg(glob);
     }
@@ -2064,10 +6245,14 @@ fn run_rg_search_inner(
     pattern: &str,
     paths: &[PathBuf],
     globs: &[String],
+    hidden: bool,
     force_fixed: bool,
 ) -> Result<std::process::Output, String> {
     let mut cmd = std::process::Command::new("rg");
     cmd.arg("--json");
+    if hidden {
+        cmd.arg("--hidden");
+    }
     for glob in globs {
         cmd.arg("--glob").arg(glob);
     }
@@ -2115,24 +6300,60 @@ fn map_tool_toggle_to_action(toggle_id: &str) -> Option<&'static str> {
         "terminal.exec_interactive" | "terminal.exec" => Some("terminal.exec"),
         "terminal.run_command" | "terminal.run" => Some("terminal.run"),
         "fs.read_file" | "fs.read" => Some("fs.read"),
+        "fs.read_many" => Some("fs.read_many"),
         "fs.write_file" | "fs.write" => Some("fs.write"),
-        "fs.apply_patch" => Some("fs.write"),
+        "fs.apply_patch" => Some("fs.apply_patch"),
         "fs.search" => Some("fs.search"),
         "git.status" => Some("git.status"),
         "git.diff" => Some("git.diff"),
+        "git.commit" => Some("git.commit"),
+        "git.branch" => Some("git.branch"),
+        "git.stash" => Some("git.stash"),
+        "git.checkout" => Some("git.checkout"),
         "tests.run" => Some("tests.run"),
+        "deps.audit" => Some("deps.audit"),
+        "code.todos" => Some("code.todos"),
+        "mcp.call" => Some("mcp.call"),
         _ => None,
     }
 }
 
-fn action_allowed(action: &Action, allowed: &Option<HashSet<String>>) -> bool {
-    match action {
-        Action::PlanUpdate { .. } | Action::TaskUpdate { .. } | Action::UserAsk { .. } => true,
-        _ => match allowed {
-            Some(allowed) => allowed.contains(action_type(action)),
-            None => true,
-        },
+/// Intersects the profile toggles, the risk policy and the CLI allowlist.
+/// Plan, task and question actions are always allowed.
+fn negotiate_capabilities(
+    profile: Option<&LlmProfile>,
+    allow_network: Option<bool>,
+    cli: Option<&HashSet<String>>,
+) -> RunCapabilities {
+    let toggles = profile.and_then(build_allowed_action_set);
+    let mut capabilities = RunCapabilities {
+        allowed: Vec::new(),
+        denied: Vec::new(),
+    };
+    for action in allowed_action_list(&None) {
+        let source = if matches!(action.as_str(), "plan.update" | "task.update" | "user.ask") {
+            None
+        } else if toggles
+            .as_ref()
+            .is_some_and(|toggles| !toggles.contains(&action))
+        {
+            Some("profile")
+        } else if allow_network == Some(false) && NETWORK_ACTIONS.contains(&action.as_str()) {
+            Some("risk_policy")
+        } else if cli.is_some_and(|cli| !cli.contains(&action)) {
+            Some("cli")
+        } else {
+            None
+        };
+        match source {
+            Some(source) => capabilities.denied.push(DeniedAction {
+                action,
+                source: source.to_string(),
+            }),
+            None => capabilities.allowed.push(action),
+        }
     }
+    capabilities
 }
 
 fn action_type(action: &Action) -> &'static str {
@@ -2140,38 +6361,93 @@ fn action_type(action: &Action) -> &'static str {
         Action::TerminalExec { .. } => "terminal.exec",
         Action::TerminalRun { .. } => "terminal.run",
         Action::FsRead { .. } => "fs.read",
+        Action::FsReadMany { .. } => "fs.read_many",
         Action::FsWrite { .. } => "fs.write",
+        Action::FsApplyPatch { .. } => "fs.apply_patch",
         Action::FsSearch { .. } => "fs.search",
         Action::GitStatus { .. } => "git.status",
         Action::GitDiff { .. } => "git.diff",
+        Action::GitCommit { .. } => "git.commit",
+        Action::GitBranch { .. } => "git.branch",
+        Action::GitStash { .. } => "git.stash",
+        Action::GitCheckout { .. } => "git.checkout",
         Action::TestsRun { .. } => "tests.run",
+        Action::DepsAudit { .. } => "deps.audit",
+        Action::CodeTodos { .. } => "code.todos",
+        Action::McpCall { .. } => "mcp.call",
         Action::PlanUpdate { .. } => "plan.update",
         Action::TaskUpdate { .. } => "task.update",
         Action::UserAsk { .. } => "user.ask",
     }
 }
 
-fn build_system_prompt(profile: &LlmProfile, allowed: &Option<HashSet<String>>) -> String {
+/// With `native_tools` the actions are offered as provider tool definitions,
+/// so the JSON envelope and per-action schemas are left out.
+fn build_system_prompt(
+    profile: &LlmProfile,
+    allowed: &Option<HashSet<String>>,
+    native_tools: bool,
+    mcp_tools: &[McpTool],
+) -> String {
     let mut prompt = String::new();
     let base = profile.prompt.trim();
     if !base.is_empty() {
         prompt.push_str(base);
         prompt.push_str("\n\n");
     }
-    let allowed_list = allowed_action_list(allowed);
+    let mut allowed_list = allowed_action_list(allowed);
+    if mcp_tools.is_empty() {
+        allowed_list.retain(|action| action != "mcp.call");
+    }
     prompt.push_str("You are the TauriHands kernel agent.\n");
-    prompt.push_str("Respond with strict JSON only. Do not wrap in markdown.\n");
-    prompt.push_str("If the user asks to run a command or list files, you must include a tool action.\n");
+    if native_tools {
+        prompt.push_str("Act by calling the provided tools.\n");
+        prompt.push_str("Reply text is shown to the user as a brief update.\n");
+    } else {
+        prompt.push_str("Respond with strict JSON only. Do not wrap in markdown.\n");
+    }
+    prompt.push_str(
+        "If the user asks to run a command or list files, you must include a tool action.\n",
+    );
     prompt.push_str("Do not reply with only text when a tool action is required.\n");
     if !allowed_list.is_empty() {
-        prompt.push_str(&format!(
-            "Allowed actions: {}.\n",
-            allowed_list.join(", ")
-        ));
+        prompt.push_str(&format!("Allowed actions: {}.\n", allowed_list.join(", ")));
     }
     prompt.push_str("Default behavior: apply changes directly in the current workspace.\n");
     prompt.push_str("Do not ask whether to show sample code vs create files; proceed with workspace changes unless the user explicitly asks for sample-only.\n");
     prompt.push_str("If you must ask for confirmation, ask once and wait for user input. If the user replies \"continue\" or \"继续\", treat that as approval to proceed with the default.\n");
+    if !native_tools {
+        prompt.push_str(&action_schema_prompt(profile, allowed));
+        if !mcp_tools.is_empty() {
+            prompt.push_str(
+                "MCP tools (call with mcp.call, passing the tool input as \"arguments\"):\n",
+            );
+            for tool in mcp_tools {
+                prompt.push_str(&format!(
+                    "- server \"{}\", tool \"{}\": {} Arguments schema: {}\n",
+                    tool.server, tool.name, tool.description, tool.input_schema
+                ));
+            }
+        }
+    }
+    prompt.push_str(
+        "Use plan.update when planning is needed, but execute tools for direct requests.\n",
+    );
+    prompt.push_str("Ask the user only if required inputs are missing.\n");
+    prompt.push_str("Avoid repeating identical tool calls when recent observations already contain the answer.\n");
+    prompt.push_str("If the user asks to scan or read the entire project, confirm with user.ask before broad searches.\n");
+    prompt.push_str("A read that ends with a [truncated: ... next_offset=N] marker did not return the whole file; call fs.read again with offset N before relying on the rest of it.\n");
+    prompt.push_str("For directory listing on Windows, use terminal.exec with command \"dir\".\n");
+    prompt.push_str("Put temporary files (scripts, logs, experiments) under $SCRATCH/, e.g. \"$SCRATCH/notes.txt\"; it works in paths and commands, is ignored by git and judge rules, and is deleted when the run ends.\n");
+    prompt.push_str("Write path and cwd fields relative to the workspace or with one of these aliases, never as absolute paths, which break on other machines:\n");
+    for (alias, meaning) in PATH_ALIASES {
+        prompt.push_str(&format!("- {}: {}\n", alias, meaning));
+    }
+    prompt
+}
+
+fn action_schema_prompt(profile: &LlmProfile, allowed: &Option<HashSet<String>>) -> String {
+    let mut prompt = String::new();
     prompt.push_str("Return a single JSON object with this shape:\n");
     prompt.push_str("{\"message\":\"brief update\",\"actions\":[...]}.\n");
     prompt.push_str("Action schemas:\n");
@@ -2181,18 +6457,57 @@ fn build_system_prompt(profile: &LlmProfile, allowed: &Option<HashSet<String>>)
     prompt.push_str(
         "- terminal.run: {\"type\":\"terminal.run\",\"id\":\"...\",\"program\":\"...\",\"args\":[\"arg\"],\"cwd\":\"optional\"}\n",
     );
-    prompt.push_str("- fs.read: {\"type\":\"fs.read\",\"id\":\"...\",\"path\":\"...\"}\n");
+    prompt.push_str(&format!(
+        "- fs.read: {{\"type\":\"fs.read\",\"id\":\"...\",\"path\":\"...\",\"offset\":0}} \
+         (returns up to {} bytes from offset; offset is optional)\n",
+        READ_CHUNK_BYTES
+    ));
+    prompt.push_str(&format!(
+        "- fs.read_many: {{\"type\":\"fs.read_many\",\"id\":\"...\",\"paths\":[\"...\"]}} \
+         (up to {} files, e.g. a module and its tests)\n",
+        MAX_READ_MANY_FILES
+    ));
     prompt.push_str(
         "- fs.write: {\"type\":\"fs.write\",\"id\":\"...\",\"path\":\"...\",\"content\":\"...\"}\n",
     );
+    prompt.push_str(
+        "- fs.apply_patch: {\"type\":\"fs.apply_patch\",\"id\":\"...\",\"path\":\"...\",\"patch\":\"@@ -a,b +c,d @@\\n...\"} \
+         (unified diff for one file; applied only if every hunk matches, otherwise \
+         rejected hunks are reported so they can be fixed and resent)\n",
+    );
     prompt.push_str(
         "- fs.search: {\"type\":\"fs.search\",\"id\":\"...\",\"pattern\":\"...\",\"paths\":[\"...\"]}\n",
     );
+    prompt.push_str(
+        "Dotfiles and dot-directories are hidden from fs.search by default; fs.read, \
+         fs.read_many and fs.search accept \"include_hidden\":true to include them, \
+         unless the workspace denies hidden paths.\n",
+    );
     prompt.push_str("- git.status: {\"type\":\"git.status\",\"id\":\"...\"}\n");
     prompt.push_str("- git.diff: {\"type\":\"git.diff\",\"id\":\"...\",\"path\":\"optional\"}\n");
+    prompt.push_str(
+        "- git.commit: {\"type\":\"git.commit\",\"id\":\"...\",\"message\":\"...\",\"paths\":[\"optional\"]} \
+         (stages the paths, or all changes, and commits; protected branches are refused)\n",
+    );
+    prompt.push_str(
+        "- git.branch: {\"type\":\"git.branch\",\"id\":\"...\",\"name\":\"...\",\"start_point\":\"optional\",\"checkout\":true}\n",
+    );
+    prompt.push_str(
+        "- git.stash: {\"type\":\"git.stash\",\"id\":\"...\",\"pop\":false,\"message\":\"optional\"}\n",
+    );
+    prompt.push_str(
+        "- git.checkout: {\"type\":\"git.checkout\",\"id\":\"...\",\"target\":\"branch or commit\"} \
+         (requires a clean working tree)\n",
+    );
     prompt.push_str(
         "- tests.run: {\"type\":\"tests.run\",\"id\":\"...\",\"program\":\"...\",\"args\":[\"arg\"]}\n",
     );
+    prompt.push_str("- deps.audit: {\"type\":\"deps.audit\",\"id\":\"...\"}\n");
+    prompt
+        .push_str("- code.todos: {\"type\":\"code.todos\",\"id\":\"...\",\"path\":\"optional\"}\n");
+    prompt.push_str(
+        "- mcp.call: {\"type\":\"mcp.call\",\"id\":\"...\",\"server\":\"...\",\"tool\":\"...\",\"arguments\":{}}\n",
+    );
     prompt.push_str(
         "- plan.update: {\"type\":\"plan.update\",\"id\":\"...\",\"plan\":{\"goal\":\"...\",\"steps\":[{\"id\":\"...\",\"title\":\"...\",\"status\":\"pending\",\"done\":false}]}}\n",
     );
@@ -2200,14 +6515,308 @@ fn build_system_prompt(profile: &LlmProfile, allowed: &Option<HashSet<String>>)
         "- task.update: {\"type\":\"task.update\",\"id\":\"...\",\"tasks\":{\"items\":[{\"id\":\"...\",\"title\":\"...\",\"status\":\"todo\"}]}}\n",
     );
     prompt.push_str("- user.ask: {\"type\":\"user.ask\",\"id\":\"...\",\"question\":\"...\"}\n");
-    prompt.push_str("Use plan.update when planning is needed, but execute tools for direct requests.\n");
-    prompt.push_str("Ask the user only if required inputs are missing.\n");
-    prompt.push_str("Avoid repeating identical tool calls when recent observations already contain the answer.\n");
-    prompt.push_str("If the user asks to scan or read the entire project, confirm with user.ask before broad searches.\n");
-    prompt.push_str("For directory listing on Windows, use terminal.exec with command \"dir\".\n");
+    let terminal_run_allowed = allowed
+        .as_ref()
+        .map(|allowed| allowed.contains("terminal.run"))
+        .unwrap_or(true);
+    let tools = prompt_tools(profile);
+    if terminal_run_allowed && !tools.is_empty() {
+        prompt.push_str("Custom actions (fill {{param}} placeholders via \"params\"):\n");
+        for tool in tools {
+            let name = tool.name.as_deref().unwrap_or(&tool.id);
+            let description = tool.description.as_deref().unwrap_or("");
+            let params = tool
+                .command
+                .as_ref()
+                .map(prompt_tool_params)
+                .unwrap_or_default();
+            let params_schema = params
+                .iter()
+                .map(|param| format!("\"{}\":\"...\"", param))
+                .collect::<Vec<_>>()
+                .join(",");
+            prompt.push_str(&format!(
+                "- {}: {{\"type\":\"{}\",\"id\":\"...\",\"params\":{{{}}}}} {} {}\n",
+                tool.id, tool.id, params_schema, name, description
+            ));
+        }
+    }
     prompt
 }
 
+/// The allowed actions, and custom prompt tools, as native tool definitions.
+/// Parameters mirror the fields `parse_action` reads.
+fn action_tool_definitions(
+    profile: &LlmProfile,
+    allowed: &Option<HashSet<String>>,
+    mcp_tools: &[McpTool],
+) -> Vec<ToolDefinition> {
+    let string = || serde_json::json!({ "type": "string" });
+    let strings = || serde_json::json!({ "type": "array", "items": { "type": "string" } });
+    let object = |properties: serde_json::Value, required: &[&str]| {
+        serde_json::json!({
+            "type": "object",
+            "properties": properties,
+            "required": required,
+        })
+    };
+    let mut tools = Vec::new();
+    for action in allowed_action_list(allowed) {
+        let (description, parameters) = match action.as_str() {
+            "terminal.exec" => (
+                "Run a shell command in the workspace terminal".to_string(),
+                object(
+                    serde_json::json!({ "cmd": string(), "cwd": string() }),
+                    &["cmd"],
+                ),
+            ),
+            "terminal.run" => (
+                "Run a program with arguments, without a shell".to_string(),
+                object(
+                    serde_json::json!({ "program": string(), "args": strings(), "cwd": string() }),
+                    &["program"],
+                ),
+            ),
+            "fs.read" => (
+                format!(
+                    "Read up to {} bytes of a file starting at an optional byte offset",
+                    READ_CHUNK_BYTES
+                ),
+                object(
+                    serde_json::json!({
+                        "path": string(),
+                        "offset": { "type": "integer" },
+                        "include_hidden": { "type": "boolean" },
+                    }),
+                    &["path"],
+                ),
+            ),
+            "fs.read_many" => (
+                format!("Read up to {} files at once", MAX_READ_MANY_FILES),
+                object(
+                    serde_json::json!({
+                        "paths": strings(),
+                        "include_hidden": { "type": "boolean" },
+                    }),
+                    &["paths"],
+                ),
+            ),
+            "fs.write" => (
+                "Write the full content of a file".to_string(),
+                object(
+                    serde_json::json!({ "path": string(), "content": string() }),
+                    &["path", "content"],
+                ),
+            ),
+            "fs.apply_patch" => (
+                "Apply a unified diff to one file; rejected hunks are reported".to_string(),
+                object(
+                    serde_json::json!({ "path": string(), "patch": string() }),
+                    &["path", "patch"],
+                ),
+            ),
+            "fs.search" => (
+                "Search file contents for a pattern".to_string(),
+                object(
+                    serde_json::json!({
+                        "pattern": string(),
+                        "paths": strings(),
+                        "include_hidden": { "type": "boolean" },
+                    }),
+                    &["pattern"],
+                ),
+            ),
+            "git.status" => (
+                "Show git status of the workspace".to_string(),
+                object(serde_json::json!({}), &[]),
+            ),
+            "git.diff" => (
+                "Show the git diff, optionally for one path".to_string(),
+                object(serde_json::json!({ "path": string() }), &[]),
+            ),
+            "git.commit" => (
+                "Stage the given paths, or all changes, and commit".to_string(),
+                object(
+                    serde_json::json!({ "message": string(), "paths": strings() }),
+                    &["message"],
+                ),
+            ),
+            "git.branch" => (
+                "Create a branch, optionally switching to it".to_string(),
+                object(
+                    serde_json::json!({
+                        "name": string(),
+                        "start_point": string(),
+                        "checkout": { "type": "boolean" },
+                    }),
+                    &["name"],
+                ),
+            ),
+            "git.stash" => (
+                "Stash uncommitted changes, or pop the latest stash".to_string(),
+                object(
+                    serde_json::json!({ "pop": { "type": "boolean" }, "message": string() }),
+                    &[],
+                ),
+            ),
+            "git.checkout" => (
+                "Check out a branch or commit; the working tree must be clean".to_string(),
+                object(serde_json::json!({ "target": string() }), &["target"]),
+            ),
+            "tests.run" => (
+                "Run the test command".to_string(),
+                object(
+                    serde_json::json!({ "program": string(), "args": strings() }),
+                    &["program"],
+                ),
+            ),
+            "deps.audit" => (
+                "Audit dependencies for known vulnerabilities".to_string(),
+                object(serde_json::json!({}), &[]),
+            ),
+            "code.todos" => (
+                "List TODO/FIXME comments, optionally under a path".to_string(),
+                object(serde_json::json!({ "path": string() }), &[]),
+            ),
+            "plan.update" => (
+                "Replace the plan with a goal and ordered steps".to_string(),
+                object(
+                    serde_json::json!({
+                        "plan": object(
+                            serde_json::json!({
+                                "goal": string(),
+                                "steps": {
+                                    "type": "array",
+                                    "items": object(
+                                        serde_json::json!({
+                                            "id": string(),
+                                            "title": string(),
+                                            "status": string(),
+                                        }),
+                                        &["title"],
+                                    ),
+                                },
+                            }),
+                            &["goal", "steps"],
+                        ),
+                    }),
+                    &["plan"],
+                ),
+            ),
+            "task.update" => (
+                "Replace the task list".to_string(),
+                object(
+                    serde_json::json!({
+                        "tasks": object(
+                            serde_json::json!({
+                                "items": {
+                                    "type": "array",
+                                    "items": object(
+                                        serde_json::json!({
+                                            "id": string(),
+                                            "title": string(),
+                                            "status": string(),
+                                        }),
+                                        &["title"],
+                                    ),
+                                },
+                            }),
+                            &["items"],
+                        ),
+                    }),
+                    &["tasks"],
+                ),
+            ),
+            "user.ask" => (
+                "Ask the user a question and wait for the answer".to_string(),
+                object(serde_json::json!({ "question": string() }), &["question"]),
+            ),
+            _ => continue,
+        };
+        tools.push(ToolDefinition {
+            name: action.replace('.', "_"),
+            action_type: action,
+            description,
+            parameters,
+            fields: serde_json::Map::new(),
+        });
+    }
+    // Each MCP tool is its own definition; `fields` fills in server and tool.
+    for tool in mcp_tools {
+        let name: String = format!("mcp_{}_{}", tool.server, tool.name)
+            .chars()
+            .map(|c| {
+                if c.is_ascii_alphanumeric() || c == '-' {
+                    c
+                } else {
+                    '_'
+                }
+            })
+            .take(64)
+            .collect();
+        let mut fields = serde_json::Map::new();
+        fields.insert("server".to_string(), serde_json::json!(tool.server));
+        fields.insert("tool".to_string(), serde_json::json!(tool.name));
+        tools.push(ToolDefinition {
+            name,
+            action_type: "mcp.call".to_string(),
+            description: format!(
+                "MCP tool {} of server {}: {}",
+                tool.name, tool.server, tool.description
+            ),
+            parameters: object(
+                serde_json::json!({ "arguments": tool.input_schema }),
+                &["arguments"],
+            ),
+            fields,
+        });
+    }
+    let terminal_run_allowed = allowed
+        .as_ref()
+        .map(|allowed| allowed.contains("terminal.run"))
+        .unwrap_or(true);
+    if terminal_run_allowed {
+        for tool in prompt_tools(profile) {
+            let params = tool
+                .command
+                .as_ref()
+                .map(prompt_tool_params)
+                .unwrap_or_default();
+            let properties: serde_json::Map<String, serde_json::Value> = params
+                .iter()
+                .map(|param| (param.clone(), string()))
+                .collect();
+            let required: Vec<&str> = params.iter().map(String::as_str).collect();
+            let description = [tool.name.as_deref(), tool.description.as_deref()]
+                .into_iter()
+                .flatten()
+                .collect::<Vec<_>>()
+                .join(": ");
+            tools.push(ToolDefinition {
+                name: tool
+                    .id
+                    .chars()
+                    .map(|c| {
+                        if c.is_ascii_alphanumeric() || c == '-' {
+                            c
+                        } else {
+                            '_'
+                        }
+                    })
+                    .collect(),
+                action_type: tool.id.clone(),
+                description,
+                parameters: object(
+                    serde_json::json!({ "params": object(properties.into(), &required) }),
+                    &["params"],
+                ),
+                fields: serde_json::Map::new(),
+            });
+        }
+    }
+    tools
+}
+
 fn build_chat_system_prompt(profile: &LlmProfile) -> String {
     let mut prompt = String::new();
     let base = profile.prompt.trim();
@@ -2234,16 +6843,44 @@ fn build_plan_system_prompt(profile: &LlmProfile) -> String {
     prompt
 }
 
+fn prompt_tool_params(command: &LlmToolCommand) -> Vec<String> {
+    let mut params = Vec::new();
+    let templates = command.args.iter().chain(command.cwd.iter());
+    for template in templates {
+        let mut rest = template.as_str();
+        while let Some(start) = rest.find("{{") {
+            let Some(end) = rest[start..].find("}}") else {
+                break;
+            };
+            let key = rest[start + 2..start + end].trim().to_string();
+            if !params.contains(&key) {
+                params.push(key);
+            }
+            rest = &rest[start + end + 2..];
+        }
+    }
+    params
+}
+
 fn allowed_action_list(allowed: &Option<HashSet<String>>) -> Vec<String> {
     let ordered = [
         "terminal.exec",
         "terminal.run",
         "fs.read",
+        "fs.read_many",
         "fs.write",
+        "fs.apply_patch",
         "fs.search",
         "git.status",
         "git.diff",
+        "git.commit",
+        "git.branch",
+        "git.stash",
+        "git.checkout",
         "tests.run",
+        "deps.audit",
+        "code.todos",
+        "mcp.call",
         "plan.update",
         "task.update",
         "user.ask",
@@ -2265,7 +6902,13 @@ fn allowed_action_list(allowed: &Option<HashSet<String>>) -> Vec<String> {
     list
 }
 
-fn build_user_prompt(state: &RunState) -> String {
+/// The run state for the next decision, with the conversation compacted to
+/// fit `budget` tokens.
+fn build_user_prompt(
+    state: &RunState,
+    policy: &ContextPolicy,
+    budget: usize,
+) -> (String, Option<ContextCompaction>) {
     let mut prompt = String::new();
     prompt.push_str(&format!("Platform: {}\n", std::env::consts::OS));
     prompt.push_str(&format!("Workspace: {}\n", state.tool_context.cwd));
@@ -2290,6 +6933,16 @@ fn build_user_prompt(state: &RunState) -> String {
     } else {
         prompt.push_str("none\n");
     }
+    if let (Some(plan), Some(selected)) = (&state.plan, &state.selected_step_ids) {
+        prompt.push_str("Selected steps (work only on these, leave other steps untouched):\n");
+        for step in plan.steps.iter().filter(|step| selected.contains(&step.id)) {
+            prompt.push_str(&format!(
+                "- {} ({})\n",
+                trim_to(&step.title, 240),
+                step.status
+            ));
+        }
+    }
     prompt.push_str("Tasks:\n");
     if let Some(tasks) = &state.tasks {
         for task in &tasks.items {
@@ -2308,31 +6961,58 @@ fn build_user_prompt(state: &RunState) -> String {
             prompt.push_str(&format!("- {}\n", trim_to(obs, 600)));
         }
     }
-    prompt.push_str("Conversation:\n");
-    let start = state.messages.len().saturating_sub(6);
-    for msg in state.messages.iter().skip(start) {
-        prompt.push_str(&format!(
-            "- {}: {}\n",
-            msg.role,
-            trim_to(&msg.content, 1200)
-        ));
-    }
-    prompt
+    let conversation = compact_conversation(
+        &state.messages,
+        budget.saturating_sub(estimate_tokens(&prompt)),
+        policy,
+    );
+    prompt.push_str(&conversation.text);
+    (prompt, conversation.compaction)
 }
 
-fn build_chat_user_prompt(state: &RunState) -> String {
-    let mut prompt = String::new();
-    prompt.push_str("Conversation:\n");
-    let start = state.messages.len().saturating_sub(8);
-    for msg in state.messages.iter().skip(start) {
-        prompt.push_str(&format!(
-            "- {}: {}\n",
-            msg.role,
-            trim_to(&msg.content, 1200)
-        ));
-    }
-    prompt.push_str("Respond to the last user message.\n");
-    prompt
+fn build_chat_user_prompt(
+    state: &RunState,
+    policy: &ContextPolicy,
+    budget: usize,
+) -> (String, Option<ContextCompaction>) {
+    let instruction = "Respond to the last user message.\n";
+    let conversation = compact_conversation(
+        &state.messages,
+        budget.saturating_sub(estimate_tokens(instruction)),
+        policy,
+    );
+    let mut prompt = conversation.text;
+    prompt.push_str(instruction);
+    (prompt, conversation.compaction)
+}
+
+/// Tokens the system prompt, task constraints and tool definitions take.
+fn reserved_prompt_tokens(
+    profile: &LlmProfile,
+    allowed: &Option<HashSet<String>>,
+    constraints: &str,
+    mcp_tools: &[McpTool],
+) -> usize {
+    let native_tools = supports_native_tools(profile);
+    let tools: usize = if native_tools {
+        action_tool_definitions(profile, allowed, mcp_tools)
+            .iter()
+            .map(|tool| {
+                estimate_tokens(&tool.name)
+                    + estimate_tokens(&tool.description)
+                    + estimate_tokens(&tool.parameters.to_string())
+            })
+            .sum()
+    } else {
+        0
+    };
+    estimate_tokens(&build_system_prompt(
+        profile,
+        allowed,
+        native_tools,
+        mcp_tools,
+    )) + estimate_tokens(constraints)
+        + tools
 }
 
 fn parse_plan_response(raw: &str, goal_hint: Option<&str>) -> Result<Plan, String> {
@@ -2353,25 +7033,67 @@ fn parse_plan_response(raw: &str, goal_hint: Option<&str>) -> Result<Plan, Strin
     }
 }
 
-fn parse_llm_response(raw: &str, goal_hint: Option<&str>) -> Result<LlmDecision, String> {
+fn parse_llm_response(
+    raw: &str,
+    goal_hint: Option<&str>,
+    prompt_tools: &[&LlmToolToggle],
+) -> Result<LlmDecision, String> {
     let value = parse_json_payload(raw)?;
     let message = extract_message(&value);
+    let mut errors = Vec::new();
     let actions = match &value {
-        serde_json::Value::Array(_) => parse_actions_value(&value, goal_hint)?,
+        serde_json::Value::Array(_) => {
+            parse_actions_value(&value, goal_hint, prompt_tools, &mut errors)?
+        }
         serde_json::Value::Object(map) => {
             if let Some(actions_value) = map.get("actions") {
-                parse_actions_value(actions_value, goal_hint)?
+                parse_actions_value(actions_value, goal_hint, prompt_tools, &mut errors)?
             } else if let Some(action_value) = map.get("action") {
-                parse_actions_value(action_value, goal_hint)?
+                parse_actions_value(action_value, goal_hint, prompt_tools, &mut errors)?
             } else if map.get("type").is_some() {
-                parse_actions_value(&value, goal_hint)?
+                parse_actions_value(&value, goal_hint, prompt_tools, &mut errors)?
             } else {
                 Vec::new()
             }
         }
         _ => Vec::new(),
     };
-    Ok(LlmDecision { message, actions })
+    Ok(LlmDecision {
+        message,
+        actions,
+        errors,
+        source: DecisionSource::default(),
+    })
+}
+
+fn validate_decision(
+    raw: &str,
+    goal_hint: Option<&str>,
+    prompt_tools: &[&LlmToolToggle],
+) -> LlmDecision {
+    // Disallowed actions are kept; the run loop answers them with PolicyDenied.
+    parse_llm_response(raw, goal_hint, prompt_tools).unwrap_or_else(|err| LlmDecision {
+        message: None,
+        actions: Vec::new(),
+        errors: vec![err],
+        source: DecisionSource::default(),
+    })
+}
+
+fn build_corrective_prompt(user_prompt: &str, raw: &str, errors: &[String]) -> String {
+    let mut prompt = user_prompt.to_string();
+    prompt.push_str("\nYour previous response did not match the action schema:\n");
+    for error in errors {
+        prompt.push_str(&format!("- {}\n", trim_to(error, 400)));
+    }
+    prompt.push_str(&format!(
+        "Previous response:\n{}\n",
+        trim_to(raw.trim(), 2000)
+    ));
+    prompt.push_str(
+        "Return a corrected JSON response with every action using a supported type and all required fields.\n",
+    );
+    prompt
 }
 
 fn parse_json_payload(raw: &str) -> Result<serde_json::Value, String> {
@@ -2405,7 +7127,13 @@ fn parse_json_payload(raw: &str) -> Result<serde_json::Value, String> {
                     }
                 }
             }
-            Err(build_json_parse_error(raw, &cleaned, None, &primary_err, None))
+            Err(build_json_parse_error(
+                raw,
+                &cleaned,
+                None,
+                &primary_err,
+                None,
+            ))
         }
     }
 }
@@ -2624,21 +7352,29 @@ fn extract_message(value: &serde_json::Value) -> Option<String> {
 fn parse_actions_value(
     value: &serde_json::Value,
     goal_hint: Option<&str>,
+    prompt_tools: &[&LlmToolToggle],
+    errors: &mut Vec<String>,
 ) -> Result<Vec<Action>, String> {
-    match value {
-        serde_json::Value::Array(items) => {
-            let mut actions = Vec::new();
-            for item in items {
-                actions.push(parse_action(item, goal_hint)?);
-            }
-            Ok(actions)
+    let items = match value {
+        serde_json::Value::Array(items) => items.iter().collect::<Vec<_>>(),
+        serde_json::Value::Object(_) => vec![value],
+        _ => return Err("Actions must be a JSON array or object".to_string()),
+    };
+    let mut actions = Vec::new();
+    for (index, item) in items.into_iter().enumerate() {
+        match parse_action(item, goal_hint, prompt_tools) {
+            Ok(action) => actions.push(action),
+            Err(err) => errors.push(format!("actions[{}]: {}", index, err)),
         }
-        serde_json::Value::Object(_) => Ok(vec![parse_action(value, goal_hint)?]),
-        _ => Err("Actions must be a JSON array or object".to_string()),
     }
+    Ok(actions)
 }
 
-fn parse_action(value: &serde_json::Value, goal_hint: Option<&str>) -> Result<Action, String> {
+fn parse_action(
+    value: &serde_json::Value,
+    goal_hint: Option<&str>,
+    prompt_tools: &[&LlmToolToggle],
+) -> Result<Action, String> {
     let obj = value
         .as_object()
         .ok_or_else(|| "Action must be an object".to_string())?;
@@ -2666,29 +7402,111 @@ fn parse_action(value: &serde_json::Value, goal_hint: Option<&str>) -> Result<Ac
         }
         "fs.read" => {
             let path = required_string_field(obj, "path")?;
-            Ok(Action::FsRead { id, path })
+            let offset = obj.get("offset").and_then(|value| {
+                value
+                    .as_u64()
+                    .or_else(|| value.as_str().and_then(|raw| raw.trim().parse().ok()))
+            });
+            Ok(Action::FsRead {
+                id,
+                path,
+                offset,
+                include_hidden: parse_include_hidden(obj),
+            })
+        }
+        "fs.read_many" => {
+            let paths = parse_string_list(obj.get("paths"));
+            if paths.is_empty() {
+                return Err("Missing field: paths".to_string());
+            }
+            Ok(Action::FsReadMany {
+                id,
+                paths,
+                include_hidden: parse_include_hidden(obj),
+            })
         }
         "fs.write" => {
             let path = required_string_field(obj, "path")?;
             let content = required_string_field(obj, "content")?;
             Ok(Action::FsWrite { id, path, content })
         }
+        "fs.apply_patch" => {
+            let path = required_string_field(obj, "path")?;
+            let patch = required_string_field(obj, "patch")?;
+            Ok(Action::FsApplyPatch { id, path, patch })
+        }
         "fs.search" => {
             let pattern = required_string_field(obj, "pattern")?;
             let paths = parse_string_list(obj.get("paths"));
             let paths = if paths.is_empty() { None } else { Some(paths) };
-            Ok(Action::FsSearch { id, pattern, paths })
+            Ok(Action::FsSearch {
+                id,
+                pattern,
+                paths,
+                include_hidden: parse_include_hidden(obj),
+            })
         }
         "git.status" => Ok(Action::GitStatus { id }),
         "git.diff" => {
             let path = coerce_string(obj.get("path")).filter(|value| !value.is_empty());
             Ok(Action::GitDiff { id, path })
         }
+        "git.commit" => {
+            let message = required_string_field(obj, "message")?;
+            let paths = parse_string_list(obj.get("paths"));
+            Ok(Action::GitCommit { id, message, paths })
+        }
+        "git.branch" => {
+            let name = required_string_field(obj, "name")?;
+            let start_point =
+                coerce_string(obj.get("start_point")).filter(|value| !value.is_empty());
+            let checkout = obj
+                .get("checkout")
+                .and_then(|value| value.as_bool())
+                .unwrap_or(false);
+            Ok(Action::GitBranch {
+                id,
+                name,
+                start_point,
+                checkout,
+            })
+        }
+        "git.stash" => {
+            let pop = obj
+                .get("pop")
+                .and_then(|value| value.as_bool())
+                .unwrap_or(false);
+            let message = coerce_string(obj.get("message")).filter(|value| !value.is_empty());
+            Ok(Action::GitStash { id, pop, message })
+        }
+        "git.checkout" => {
+            let target = required_string_field(obj, "target")?;
+            Ok(Action::GitCheckout { id, target })
+        }
         "tests.run" => {
             let program = required_string_field(obj, "program")?;
             let args = parse_string_list(obj.get("args"));
             Ok(Action::TestsRun { id, program, args })
         }
+        "deps.audit" => Ok(Action::DepsAudit { id }),
+        "code.todos" => {
+            let path = coerce_string(obj.get("path")).filter(|value| !value.is_empty());
+            Ok(Action::CodeTodos { id, path })
+        }
+        "mcp.call" => {
+            let server = required_string_field(obj, "server")?;
+            let tool = required_string_field(obj, "tool")?;
+            let arguments = obj
+                .get("arguments")
+                .cloned()
+                .unwrap_or_else(|| serde_json::json!({}));
+            Ok(Action::McpCall {
+                id,
+                server,
+                tool,
+                arguments,
+            })
+        }
         "plan.update" => {
             let plan_value = obj.get("plan").cloned().unwrap_or_else(|| value.clone());
             let plan = parse_plan_value(&plan_value, goal_hint)?;
@@ -2703,8 +7521,72 @@ fn parse_action(value: &serde_json::Value, goal_hint: Option<&str>) -> Result<Ac
             let question = required_string_field(obj, "question")?;
             Ok(Action::UserAsk { id, question })
         }
-        _ => Err(format!("Unsupported action type: {}", action_type)),
+        _ => match prompt_tools.iter().find(|tool| tool.id == action_type) {
+            Some(tool) => expand_prompt_tool(tool, id, obj.get("params")),
+            None => Err(format!("Unsupported action type: {}", action_type)),
+        },
+    }
+}
+
+fn prompt_tools(profile: &LlmProfile) -> Vec<&LlmToolToggle> {
+    profile
+        .tool_toggles
+        .iter()
+        .filter(|toggle| toggle.enabled && toggle.command.is_some())
+        .filter(|toggle| map_tool_toggle_to_action(&toggle.id).is_none())
+        .collect()
+}
+
+fn expand_prompt_tool(
+    tool: &LlmToolToggle,
+    id: String,
+    params: Option<&serde_json::Value>,
+) -> Result<Action, String> {
+    let command = tool
+        .command
+        .as_ref()
+        .ok_or_else(|| format!("Prompt tool {} has no command", tool.id))?;
+    let mut args = Vec::new();
+    for arg in &command.args {
+        args.push(fill_prompt_tool_template(arg, params)?);
+    }
+    let cwd = match &command.cwd {
+        Some(cwd) => Some(fill_prompt_tool_template(cwd, params)?),
+        None => None,
+    };
+    Ok(Action::TerminalRun {
+        id,
+        program: command.program.clone(),
+        args,
+        cwd,
+    })
+}
+
+fn fill_prompt_tool_template(
+    template: &str,
+    params: Option<&serde_json::Value>,
+) -> Result<String, String> {
+    let mut output = String::new();
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        let Some(end) = rest[start..].find("}}") else {
+            break;
+        };
+        output.push_str(&rest[..start]);
+        let key = rest[start + 2..start + end].trim();
+        let value = coerce_string(params.and_then(|params| params.get(key)))
+            .ok_or_else(|| format!("Missing prompt tool param: {}", key))?;
+        output.push_str(&value);
+        rest = &rest[start + end + 2..];
     }
+    output.push_str(rest);
+    Ok(output)
+}
+
+/// The model's request only; the kernel caps it with
+/// `VisibilityPolicy::agent_include_hidden` before it takes effect.
+fn parse_include_hidden(obj: &serde_json::Map<String, serde_json::Value>) -> Option<bool> {
+    obj.get("include_hidden").and_then(|value| value.as_bool())
 }
 
 fn action_id_prefix(action_type: &str) -> &str {
@@ -2712,11 +7594,20 @@ fn action_id_prefix(action_type: &str) -> &str {
         "terminal.exec" => "term",
         "terminal.run" => "run",
         "fs.read" => "read",
+        "fs.read_many" => "read",
         "fs.write" => "write",
+        "fs.apply_patch" => "patch",
         "fs.search" => "search",
         "git.status" => "git",
         "git.diff" => "diff",
+        "git.commit" => "commit",
+        "git.branch" => "branch",
+        "git.stash" => "stash",
+        "git.checkout" => "checkout",
         "tests.run" => "test",
+        "deps.audit" => "deps",
+        "code.todos" => "todos",
+        "mcp.call" => "mcp",
         "plan.update" => "plan",
         "task.update" => "task",
         "user.ask" => "ask",
@@ -2724,10 +7615,7 @@ fn action_id_prefix(action_type: &str) -> &str {
     }
 }
 
-fn parse_plan_value(
-    value: &serde_json::Value,
-    goal_hint: Option<&str>,
-) -> Result<Plan, String> {
+fn parse_plan_value(value: &serde_json::Value, goal_hint: Option<&str>) -> Result<Plan, String> {
     let obj = value
         .as_object()
         .ok_or_else(|| "Plan must be an object".to_string())?;
@@ -2818,10 +7706,7 @@ fn parse_task_list(value: &serde_json::Value) -> Result<TaskList, String> {
     if items.is_empty() {
         return Err("Task items are required".to_string());
     }
-    Ok(TaskList {
-        version: 1,
-        items,
-    })
+    Ok(TaskList { version: 1, items })
 }
 
 fn parse_task_items(value: &serde_json::Value) -> Vec<Task> {
@@ -2912,6 +7797,78 @@ fn coerce_string(value: Option<&serde_json::Value>) -> Option<String> {
     }
 }
 
+fn judge_context(snapshot: &RunState, workspace: PathBuf) -> JudgeContext {
+    JudgeContext {
+        workspace,
+        command: snapshot.tool_context.cwd.clone(),
+        exit_code: 0,
+        stdout: snapshot.recent_observations.join("\n"),
+        stderr: snapshot.last_error.clone().unwrap_or_default(),
+        last_observation: snapshot
+            .recent_observations
+            .last()
+            .cloned()
+            .unwrap_or_default(),
+        goal: snapshot
+            .plan
+            .as_ref()
+            .map(|plan| plan.goal.clone())
+            .unwrap_or_default(),
+    }
+}
+
+fn check_output_matches(pattern: &str, context: &JudgeContext) -> JudgeRuleOutcome {
+    let regex = match Regex::new(pattern) {
+        Ok(regex) => regex,
+        Err(err) => return JudgeRuleOutcome::fail(format!("invalid regex: {}", err)),
+    };
+    if context.last_observation.is_empty() {
+        return JudgeRuleOutcome::fail("there is no observation yet".to_string());
+    }
+    match regex.find(&context.last_observation) {
+        Some(found) => {
+            let mut outcome = JudgeRuleOutcome::pass();
+            outcome
+                .evidence
+                .push(format!("matched: {}", trim_to(found.as_str(), 200)));
+            outcome
+        }
+        None => {
+            let mut outcome = JudgeRuleOutcome::fail(format!(
+                "the last observation does not match /{}/",
+                pattern
+            ));
+            outcome.evidence.push(format!(
+                "last observation: {}",
+                trim_to(&context.last_observation, 500)
+            ));
+            outcome
+        }
+    }
+}
+
+fn parse_constraint_check(constraint: &str) -> Option<ConstraintCheck> {
+    let lower = constraint.trim().to_lowercase();
+    if lower.contains("no new dependenc")
+        || lower.contains("no new deps")
+        || lower.contains("do not add dependenc")
+        || lower.contains("don't add dependenc")
+    {
+        return Some(ConstraintCheck::NoNewDependencies);
+    }
+    if lower.contains("no new files") {
+        return Some(ConstraintCheck::NoNewFiles);
+    }
+    // Matched on the original text: lowercasing can change byte offsets.
+    let untouched = Regex::new(r"(?i)\b(?:do not|don't) (?:modify|touch) +(\S+)").ok()?;
+    let path = untouched
+        .captures(constraint)?
+        .get(1)?
+        .as_str()
+        .trim_matches(|ch: char| matches!(ch, '`' | '"' | '\'' | ',' | '.' | ';'));
+    (!path.is_empty()).then(|| ConstraintCheck::Untouched(path.to_string()))
+}
+
 fn trim_to(value: &str, max_len: usize) -> String {
     if value.len() <= max_len {
         return value.to_string();
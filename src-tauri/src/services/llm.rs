@@ -1,11 +1,24 @@
 use futures_util::StreamExt;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::fs::{create_dir_all, read_to_string, write};
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::fs::{create_dir_all, read_to_string, write, OpenOptions};
+use std::io::Write;
 use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
-use std::time::Duration;
+use std::sync::{Arc, Mutex, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::services::llm_mock::request_mock;
+use crate::services::llm_tools::{
+    anthropic_tool_calls, anthropic_tools, gemini_tool_calls, gemini_tools,
+    is_openai_responses_tool_event, openai_chat_tool_calls, openai_chat_tools,
+    openai_responses_stream_tool_call, openai_responses_tool_calls, openai_responses_tools,
+    supports_native_tools, tool_calls_to_decision, ToolCall, ToolCallAccumulator, ToolDefinition,
+};
+use crate::services::llm_usage::TokenUsage;
+use crate::services::metrics;
 
 #[derive(Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -34,6 +47,12 @@ pub struct LlmProfile {
     pub redact_secrets: bool,
     pub audit_logs: bool,
     pub tool_toggles: Vec<LlmToolToggle>,
+    #[serde(default)]
+    pub fallback_providers: Vec<String>,
+    /// Asks reasoning models for summaries or thinking blocks. Reasoning a
+    /// provider sends unasked is captured either way.
+    #[serde(default)]
+    pub reasoning_trace: bool,
 }
 
 #[derive(Clone, Copy, PartialEq)]
@@ -48,6 +67,22 @@ pub enum LlmResponseFormat {
 pub struct LlmToolToggle {
     pub id: String,
     pub enabled: bool,
+    #[serde(default)]
+    pub name: Option<String>,
+    #[serde(default)]
+    pub description: Option<String>,
+    #[serde(default)]
+    pub command: Option<LlmToolCommand>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LlmToolCommand {
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub cwd: Option<String>,
 }
 
 #[derive(Clone, Serialize, Deserialize, Default)]
@@ -162,62 +197,435 @@ fn save_store_to_disk(path: &PathBuf, store: &LlmProfileStore) -> Result<(), Str
     write(path, data).map_err(|e| e.to_string())
 }
 
-pub async fn request_completion(
+const FAILOVER_THRESHOLD: u32 = 3;
+const FAILOVER_COOLDOWN: Duration = Duration::from_secs(300);
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProviderFailover {
+    pub from: String,
+    pub to: String,
+    pub reason: String,
+    pub failures: u32,
+}
+
+/// Tracks consecutive provider errors and which fallback provider is currently in use.
+#[derive(Default)]
+pub struct ProviderHealth {
+    failures: u32,
+    active: Option<String>,
+    failed_over_at: Option<Instant>,
+}
+
+impl ProviderHealth {
+    pub fn select(&mut self, profile: &LlmProfile) -> LlmProfile {
+        let expired = self
+            .failed_over_at
+            .map(|at| at.elapsed() >= FAILOVER_COOLDOWN)
+            .unwrap_or(false);
+        if expired {
+            self.active = None;
+            self.failed_over_at = None;
+            self.failures = 0;
+        }
+        self.active
+            .as_deref()
+            .filter(|provider| profile.fallback_providers.iter().any(|p| p == provider))
+            .and_then(|provider| profile_for_provider(profile, provider))
+            .unwrap_or_else(|| profile.clone())
+    }
+
+    pub fn record_success(&mut self) {
+        self.failures = 0;
+    }
+
+    pub fn record_failure(
+        &mut self,
+        profile: &LlmProfile,
+        used: &LlmProfile,
+        error: &str,
+    ) -> Option<ProviderFailover> {
+        self.failures = self.failures.saturating_add(1);
+        let lower = error.to_lowercase();
+        let timed_out = lower.contains("timed out") || lower.contains("timeout");
+        if !timed_out && self.failures < FAILOVER_THRESHOLD {
+            return None;
+        }
+        let mut chain = vec![profile.provider.clone()];
+        chain.extend(profile.fallback_providers.iter().cloned());
+        let position = chain
+            .iter()
+            .position(|provider| provider == &used.provider)?;
+        let next = chain
+            .iter()
+            .skip(position + 1)
+            .find(|provider| profile_for_provider(profile, provider).is_some())?
+            .clone();
+        let failover = ProviderFailover {
+            from: used.provider.clone(),
+            to: next.clone(),
+            reason: error.to_string(),
+            failures: self.failures,
+        };
+        self.active = Some(next);
+        self.failed_over_at = Some(Instant::now());
+        self.failures = 0;
+        Some(failover)
+    }
+}
+
+pub fn profile_for_provider(profile: &LlmProfile, provider: &str) -> Option<LlmProfile> {
+    if provider == profile.provider {
+        return Some(profile.clone());
+    }
+    let config = profile.provider_configs.get(provider)?;
+    if config.model.trim().is_empty() {
+        return None;
+    }
+    let mut fallback = profile.clone();
+    fallback.provider = provider.to_string();
+    fallback.api_key = config.api_key.clone();
+    fallback.base_url = config.base_url.clone();
+    fallback.model = config.model.clone();
+    fallback.fallback_providers = Vec::new();
+    Some(fallback)
+}
+
+struct LlmConcurrency {
+    limit: u32,
+    semaphore: Arc<Semaphore>,
+}
+
+/// One limiter per profile name, so profiles with different limits do not
+/// replace each other's semaphore and release requests still in flight.
+static LLM_CONCURRENCY: OnceLock<Mutex<HashMap<String, LlmConcurrency>>> = OnceLock::new();
+
+/// Waits for a slot in the profile's LLM request limiter sized by `profile.concurrency`.
+/// A limit of 0 disables the limiter. Changing the limit only affects requests started afterwards.
+async fn acquire_llm_permit(profile: &LlmProfile) -> Option<OwnedSemaphorePermit> {
+    if profile.concurrency == 0 {
+        return None;
+    }
+    let semaphore = {
+        let mut limiters = LLM_CONCURRENCY
+            .get_or_init(|| Mutex::new(HashMap::new()))
+            .lock()
+            .ok()?;
+        match limiters.get(&profile.profile_name) {
+            Some(limiter) if limiter.limit == profile.concurrency => limiter.semaphore.clone(),
+            _ => {
+                let semaphore = Arc::new(Semaphore::new(profile.concurrency as usize));
+                limiters.insert(
+                    profile.profile_name.clone(),
+                    LlmConcurrency {
+                        limit: profile.concurrency,
+                        semaphore: semaphore.clone(),
+                    },
+                );
+                semaphore
+            }
+        }
+    };
+    semaphore.acquire_owned().await.ok()
+}
+
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LlmReplayMode {
+    #[default]
+    Off,
+    Record,
+    Replay,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LlmReplayStatus {
+    pub mode: LlmReplayMode,
+    pub path: Option<String>,
+    pub recorded: usize,
+    pub remaining: usize,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ReplayRecord {
+    key: String,
+    provider: String,
+    model: String,
+    response: String,
+}
+
+#[derive(Default)]
+struct ReplayState {
+    mode: LlmReplayMode,
+    path: Option<PathBuf>,
+    recorded: usize,
+    responses: HashMap<String, VecDeque<String>>,
+}
+
+static LLM_REPLAY: OnceLock<Mutex<ReplayState>> = OnceLock::new();
+
+fn replay_state() -> &'static Mutex<ReplayState> {
+    LLM_REPLAY.get_or_init(|| Mutex::new(ReplayState::default()))
+}
+
+/// Switches the process-wide record/replay layer. Recordings are JSONL files of
+/// prompt-hash/response pairs; replay serves them in recorded order per hash.
+pub fn configure_replay(
+    mode: LlmReplayMode,
+    path: Option<PathBuf>,
+) -> Result<LlmReplayStatus, String> {
+    let mut next = ReplayState {
+        mode,
+        path: path.clone(),
+        ..ReplayState::default()
+    };
+    match (mode, &path) {
+        (LlmReplayMode::Off, _) => {
+            next.path = None;
+        }
+        (_, None) => return Err("A recording path is required".to_string()),
+        (LlmReplayMode::Record, Some(path)) => {
+            if let Some(parent) = path.parent() {
+                create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+        }
+        (LlmReplayMode::Replay, Some(path)) => {
+            let raw =
+                read_to_string(path).map_err(|e| format!("Unable to read recording: {}", e))?;
+            for line in raw.lines().filter(|line| !line.trim().is_empty()) {
+                let record: ReplayRecord = serde_json::from_str(line).map_err(|e| e.to_string())?;
+                next.responses
+                    .entry(record.key)
+                    .or_default()
+                    .push_back(record.response);
+                next.recorded += 1;
+            }
+        }
+    }
+    let mut state = replay_state()
+        .lock()
+        .map_err(|_| "Replay lock poisoned".to_string())?;
+    *state = next;
+    Ok(status_of(&state))
+}
+
+pub fn replay_status() -> LlmReplayStatus {
+    replay_state()
+        .lock()
+        .map(|state| status_of(&state))
+        .unwrap_or_else(|_| status_of(&ReplayState::default()))
+}
+
+fn status_of(state: &ReplayState) -> LlmReplayStatus {
+    LlmReplayStatus {
+        mode: state.mode,
+        path: state
+            .path
+            .as_ref()
+            .map(|path| path.to_string_lossy().to_string()),
+        recorded: state.recorded,
+        remaining: state.responses.values().map(|queue| queue.len()).sum(),
+    }
+}
+
+fn replay_key(
     profile: &LlmProfile,
     system_prompt: &str,
     user_prompt: &str,
     response_format: LlmResponseFormat,
-) -> Result<String, String> {
-    let provider = profile.provider.to_lowercase();
-    let base_url = resolve_base_url(profile);
-    if base_url.is_empty() {
-        return Err("Base URL is required".to_string());
+) -> String {
+    let format = match response_format {
+        LlmResponseFormat::Text => "text",
+        LlmResponseFormat::ActionJson => "action_json",
+        LlmResponseFormat::PlanJson => "plan_json",
+    };
+    // FNV-1a keeps keys stable across builds, unlike std's DefaultHasher.
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for part in [profile.model.as_str(), format, system_prompt, user_prompt] {
+        for byte in part.bytes().chain(std::iter::once(0u8)) {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(0x100000001b3);
+        }
     }
-    if !matches!(provider.as_str(), "local" | "ollama") && profile.api_key.trim().is_empty() {
-        return Err("API key is required".to_string());
+    format!("{:016x}", hash)
+}
+
+fn replay_lookup(key: &str) -> Option<Result<String, String>> {
+    let mut state = replay_state().lock().ok()?;
+    if state.mode != LlmReplayMode::Replay {
+        return None;
     }
+    let response = state
+        .responses
+        .get_mut(key)
+        .and_then(|queue| queue.pop_front())
+        .ok_or_else(|| format!("No recorded LLM response for prompt hash {}", key));
+    Some(response)
+}
 
-    let client = build_http_client()?;
+fn replay_record(key: &str, profile: &LlmProfile, response: &str) {
+    let Ok(mut state) = replay_state().lock() else {
+        return;
+    };
+    if state.mode != LlmReplayMode::Record {
+        return;
+    }
+    let Some(path) = state.path.clone() else {
+        return;
+    };
+    let record = ReplayRecord {
+        key: key.to_string(),
+        provider: profile.provider.clone(),
+        model: profile.model.clone(),
+        response: response.to_string(),
+    };
+    let Ok(line) = serde_json::to_string(&record) else {
+        return;
+    };
+    let written = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .and_then(|mut file| writeln!(file, "{}", line));
+    if written.is_ok() {
+        state.recorded += 1;
+    }
+}
 
-    if provider == "anthropic" {
-        return request_anthropic(&client, profile, &base_url, system_prompt, user_prompt).await;
+fn log_request_failure(profile: &LlmProfile, error: &str) {
+    tracing::warn!(
+        provider = %profile.provider,
+        model = %profile.model,
+        error = %error,
+        "llm request failed"
+    );
+}
+
+/// What a request reported besides its reply.
+#[derive(Clone, Default)]
+pub struct CompletionTrace {
+    pub usage: TokenUsage,
+    /// Reasoning summaries or thinking blocks, kept out of the reply.
+    pub reasoning: String,
+}
+
+impl CompletionTrace {
+    pub fn reasoning(&self) -> Option<&str> {
+        let reasoning = self.reasoning.trim();
+        (!reasoning.is_empty()).then_some(reasoning)
     }
-    if provider == "openai" {
-        let mode = resolve_openai_request_mode(&base_url);
-        if mode == OpenAiRequestMode::Responses {
-            return request_openai_responses(
-                &client,
+}
+
+tokio::task_local! {
+    /// Filled in by the replies of the request in flight.
+    static REQUEST_TRACE: RefCell<CompletionTrace>;
+}
+
+/// Counts a reply's tokens in the metrics and in the request in flight.
+fn record_usage(provider: &str, response: &serde_json::Value) {
+    let (prompt, completion) = metrics::record_token_usage(provider, response);
+    let _ = REQUEST_TRACE.try_with(|trace| {
+        let mut trace = trace.borrow_mut();
+        trace.usage.prompt_tokens += prompt;
+        trace.usage.completion_tokens += completion;
+    });
+}
+
+fn record_reasoning(text: &str) {
+    if text.is_empty() {
+        return;
+    }
+    let _ = REQUEST_TRACE.try_with(|trace| trace.borrow_mut().reasoning.push_str(text));
+}
+
+/// `tools` are offered as native tool calls where the provider supports them;
+/// the reply is then normalized to the JSON action format. Also returns the
+/// tokens and reasoning the provider reported; replayed responses report
+/// neither.
+pub async fn request_completion_metered<F>(
+    profile: &LlmProfile,
+    system_prompt: &str,
+    user_prompt: &str,
+    response_format: LlmResponseFormat,
+    tools: &[ToolDefinition],
+    on_chunk: F,
+) -> (Result<String, String>, CompletionTrace)
+where
+    F: FnMut(String),
+{
+    REQUEST_TRACE
+        .scope(RefCell::new(CompletionTrace::default()), async move {
+            let result = request_completion_stream(
                 profile,
-                &base_url,
                 system_prompt,
                 user_prompt,
                 response_format,
+                tools,
+                on_chunk,
             )
             .await;
-        }
+            (result, REQUEST_TRACE.with(RefCell::take))
+        })
+        .await
+}
+
+async fn request_completion_stream<F>(
+    profile: &LlmProfile,
+    system_prompt: &str,
+    user_prompt: &str,
+    response_format: LlmResponseFormat,
+    tools: &[ToolDefinition],
+    mut on_chunk: F,
+) -> Result<String, String>
+where
+    F: FnMut(String),
+{
+    let key = replay_key(profile, system_prompt, user_prompt, response_format);
+    if let Some(recorded) = replay_lookup(&key) {
+        let content = recorded?;
+        on_chunk(content.clone());
+        return Ok(content);
     }
-    request_openai_compatible(
-        &client,
+    let _permit = acquire_llm_permit(profile).await;
+    let started = Instant::now();
+    let result = request_completion_stream_uncached(
         profile,
-        &base_url,
         system_prompt,
         user_prompt,
         response_format,
+        tools,
+        on_chunk,
     )
-    .await
+    .await;
+    metrics::record_llm_request(&profile.provider, started.elapsed(), result.is_ok());
+    let content = result.inspect_err(|err| log_request_failure(profile, err))?;
+    replay_record(&key, profile, &content);
+    Ok(content)
 }
 
-pub async fn request_completion_stream<F>(
+async fn request_completion_stream_uncached<F>(
     profile: &LlmProfile,
     system_prompt: &str,
     user_prompt: &str,
     response_format: LlmResponseFormat,
+    tools: &[ToolDefinition],
     mut on_chunk: F,
 ) -> Result<String, String>
 where
     F: FnMut(String),
 {
     let provider = profile.provider.to_lowercase();
+    if provider == "mock" {
+        let content = request_mock(profile).await?;
+        on_chunk(content.clone());
+        return Ok(content);
+    }
+    let tools = if supports_native_tools(profile) {
+        tools
+    } else {
+        &[]
+    };
     let base_url = resolve_base_url(profile);
     if base_url.is_empty() {
         return Err("Base URL is required".to_string());
@@ -229,7 +637,43 @@ where
     let client = build_http_client()?;
 
     if provider == "anthropic" {
-        let content = request_anthropic(&client, profile, &base_url, system_prompt, user_prompt).await?;
+        let content = request_anthropic(
+            &client,
+            profile,
+            &base_url,
+            system_prompt,
+            user_prompt,
+            tools,
+        )
+        .await?;
+        on_chunk(content.clone());
+        return Ok(content);
+    }
+
+    if provider == "gemini" {
+        if profile.stream_responses {
+            return request_gemini_stream(
+                &client,
+                profile,
+                &base_url,
+                system_prompt,
+                user_prompt,
+                response_format,
+                tools,
+                &mut on_chunk,
+            )
+            .await;
+        }
+        let content = request_gemini(
+            &client,
+            profile,
+            &base_url,
+            system_prompt,
+            user_prompt,
+            response_format,
+            tools,
+        )
+        .await?;
         on_chunk(content.clone());
         return Ok(content);
     }
@@ -245,6 +689,7 @@ where
                     system_prompt,
                     user_prompt,
                     response_format,
+                    tools,
                     &mut on_chunk,
                 )
                 .await;
@@ -256,6 +701,7 @@ where
                 system_prompt,
                 user_prompt,
                 response_format,
+                tools,
                 &mut on_chunk,
             )
             .await;
@@ -268,6 +714,7 @@ where
                 system_prompt,
                 user_prompt,
                 response_format,
+                tools,
             )
             .await?
         } else {
@@ -278,6 +725,7 @@ where
                 system_prompt,
                 user_prompt,
                 response_format,
+                tools,
             )
             .await?
         };
@@ -293,6 +741,7 @@ where
             system_prompt,
             user_prompt,
             response_format,
+            tools,
             &mut on_chunk,
         )
         .await;
@@ -305,6 +754,7 @@ where
         system_prompt,
         user_prompt,
         response_format,
+        tools,
     )
     .await?;
     on_chunk(content.clone());
@@ -316,12 +766,17 @@ pub async fn fetch_models(request: LlmModelFetchRequest) -> Result<LlmModelFetch
     let client = build_http_client()?;
     match provider.as_str() {
         "openai" => fetch_openai_models(&client, &request).await,
+        "gemini" => fetch_gemini_models(&client, &request).await,
         "local" | "ollama" => fetch_local_models(&client, &provider, &request.base_url).await,
+        "mock" => Ok(LlmModelFetchResponse {
+            models: vec!["mock".to_string()],
+            source_url: request.base_url.clone(),
+        }),
         _ => Err("Model listing is not supported for this provider.".to_string()),
     }
 }
 
-fn resolve_base_url(profile: &LlmProfile) -> String {
+pub fn resolve_base_url(profile: &LlmProfile) -> String {
     let provider = profile.provider.to_lowercase();
     if !profile.base_url.trim().is_empty() {
         let base = profile.base_url.trim().trim_end_matches('/').to_string();
@@ -336,6 +791,7 @@ fn resolve_base_url(profile: &LlmProfile) -> String {
     match provider.as_str() {
         "openai" => "https://api.openai.com/v1".to_string(),
         "anthropic" => "https://api.anthropic.com/v1".to_string(),
+        "gemini" => "https://generativelanguage.googleapis.com/v1beta".to_string(),
         "local" => "http://localhost:11434/v1".to_string(),
         "ollama" => "".to_string(),
         _ => "".to_string(),
@@ -551,6 +1007,28 @@ fn parse_openai_models(value: &serde_json::Value) -> Vec<String> {
         .collect()
 }
 
+/// Gemini models that can generate content, without the `models/` prefix.
+fn parse_gemini_models(value: &serde_json::Value) -> Vec<String> {
+    let models = match value.get("models").and_then(|entry| entry.as_array()) {
+        Some(models) => models,
+        None => return Vec::new(),
+    };
+    models
+        .iter()
+        .filter(|item| {
+            item.get("supportedGenerationMethods")
+                .and_then(|methods| methods.as_array())
+                .is_some_and(|methods| {
+                    methods
+                        .iter()
+                        .any(|method| method.as_str() == Some("generateContent"))
+                })
+        })
+        .filter_map(|item| item.get("name").and_then(|name| name.as_str()))
+        .map(|name| name.trim_start_matches("models/").to_string())
+        .collect()
+}
+
 fn normalize_model_list(models: Vec<String>) -> Vec<String> {
     let mut unique = Vec::new();
     let mut seen = std::collections::HashSet::new();
@@ -596,6 +1074,9 @@ fn extract_openai_response_text(value: &serde_json::Value) -> Option<String> {
     let output = value.get("output")?.as_array()?;
     let mut parts = Vec::new();
     for item in output {
+        if item["type"] == "reasoning" {
+            continue;
+        }
         if let Some(text) = item.get("output_text").and_then(coerce_json_field) {
             parts.push(text);
             continue;
@@ -699,6 +1180,51 @@ fn extract_openai_response_stream_text(
     None
 }
 
+/// Summary and reasoning text of the `reasoning` items of a Responses API
+/// reply.
+fn openai_responses_reasoning(value: &serde_json::Value) -> String {
+    let Some(output) = value["output"].as_array() else {
+        return String::new();
+    };
+    output
+        .iter()
+        .filter(|item| item["type"] == "reasoning")
+        .flat_map(|item| {
+            let summary = item["summary"].as_array().into_iter().flatten();
+            let content = item["content"].as_array().into_iter().flatten();
+            summary.chain(content)
+        })
+        .filter_map(|part| part["text"].as_str())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Reasoning streams as `response.reasoning_*` events; their deltas and
+/// `done` texts must not reach the reply. `None` for other events.
+fn openai_responses_stream_reasoning(event: &serde_json::Value) -> Option<String> {
+    let kind = event["type"].as_str()?;
+    if !kind.starts_with("response.reasoning") {
+        return None;
+    }
+    if kind.ends_with(".delta") {
+        return Some(event["delta"].as_str().unwrap_or_default().to_string());
+    }
+    // A new summary part starts a new paragraph.
+    if kind.ends_with("_part.added") {
+        return Some("\n\n".to_string());
+    }
+    Some(String::new())
+}
+
+/// o-series and GPT-5 models, which reason before they reply.
+fn is_openai_reasoning_model(model: &str) -> bool {
+    let model = model.to_lowercase();
+    ["gpt-5", "o1", "o3", "o4"]
+        .iter()
+        .any(|prefix| model.starts_with(prefix))
+}
+
+#[allow(clippy::too_many_arguments)]
 async fn request_openai_responses_stream<F>(
     client: &Client,
     profile: &LlmProfile,
@@ -706,6 +1232,7 @@ async fn request_openai_responses_stream<F>(
     system_prompt: &str,
     user_prompt: &str,
     response_format: LlmResponseFormat,
+    tools: &[ToolDefinition],
     on_chunk: &mut F,
 ) -> Result<String, String>
 where
@@ -723,9 +1250,14 @@ where
         "max_output_tokens": profile.max_tokens,
         "stream": true
     });
-    if let Some(format) = openai_responses_response_format(response_format) {
+    if !tools.is_empty() {
+        payload["tools"] = openai_responses_tools(tools);
+    } else if let Some(format) = openai_responses_response_format(response_format) {
         payload["response_format"] = format;
     }
+    if profile.reasoning_trace && is_openai_reasoning_model(&profile.model) {
+        payload["reasoning"] = serde_json::json!({ "summary": "auto" });
+    }
 
     let mut request = client.post(&url).json(&payload);
     if !profile.api_key.trim().is_empty() {
@@ -751,6 +1283,7 @@ where
     }
 
     let mut full = String::new();
+    let mut calls = ToolCallAccumulator::default();
     let mut buffer = String::new();
     let mut stream = response.bytes_stream();
     'outer: while let Some(item) = stream.next().await {
@@ -775,6 +1308,18 @@ where
                 Ok(value) => value,
                 Err(_) => continue,
             };
+            record_usage(&profile.provider, &value["response"]);
+            if let Some(reasoning) = openai_responses_stream_reasoning(&value) {
+                record_reasoning(&reasoning);
+                continue;
+            }
+            if let Some(call) = openai_responses_stream_tool_call(&value) {
+                calls.push(call);
+                continue;
+            }
+            if is_openai_responses_tool_event(&value) {
+                continue;
+            }
             if let Some(text) = extract_openai_response_stream_text(&value, full.trim().is_empty())
             {
                 if !text.is_empty() {
@@ -796,6 +1341,17 @@ where
                 continue;
             }
             if let Ok(value) = serde_json::from_str::<serde_json::Value>(data) {
+                if let Some(reasoning) = openai_responses_stream_reasoning(&value) {
+                    record_reasoning(&reasoning);
+                    continue;
+                }
+                if let Some(call) = openai_responses_stream_tool_call(&value) {
+                    calls.push(call);
+                    continue;
+                }
+                if is_openai_responses_tool_event(&value) {
+                    continue;
+                }
                 if let Some(text) =
                     extract_openai_response_stream_text(&value, full.trim().is_empty())
                 {
@@ -808,6 +1364,10 @@ where
         }
     }
 
+    let calls = calls.into_calls();
+    if !tools.is_empty() && (!calls.is_empty() || !full.trim().is_empty()) {
+        return Ok(tool_calls_to_decision(&full, &calls, tools));
+    }
     if full.trim().is_empty() {
         return Err("LLM response is empty".to_string());
     }
@@ -821,6 +1381,7 @@ async fn request_openai_responses(
     system_prompt: &str,
     user_prompt: &str,
     response_format: LlmResponseFormat,
+    tools: &[ToolDefinition],
 ) -> Result<String, String> {
     let url = openai_responses_url(base_url);
     let mut payload = serde_json::json!({
@@ -833,9 +1394,14 @@ async fn request_openai_responses(
         "top_p": profile.top_p,
         "max_output_tokens": profile.max_tokens
     });
-    if let Some(format) = openai_responses_response_format(response_format) {
+    if !tools.is_empty() {
+        payload["tools"] = openai_responses_tools(tools);
+    } else if let Some(format) = openai_responses_response_format(response_format) {
         payload["response_format"] = format;
     }
+    if profile.reasoning_trace && is_openai_reasoning_model(&profile.model) {
+        payload["reasoning"] = serde_json::json!({ "summary": "auto" });
+    }
 
     let mut request = client.post(&url).json(&payload);
     if !profile.api_key.trim().is_empty() {
@@ -867,10 +1433,16 @@ async fn request_openai_responses(
             .unwrap_or("LLM request failed");
         return Err(format!("{} (HTTP {})", message, status.as_u16()));
     }
+    record_usage(&profile.provider, &value);
+    record_reasoning(&openai_responses_reasoning(&value));
     let content = extract_openai_response_text(&value)
         .unwrap_or_default()
         .trim()
         .to_string();
+    let calls = openai_responses_tool_calls(&value);
+    if !tools.is_empty() && (!calls.is_empty() || !content.is_empty()) {
+        return Ok(tool_calls_to_decision(&content, &calls, tools));
+    }
     if content.is_empty() {
         return Err("LLM response is empty".to_string());
     }
@@ -927,6 +1499,56 @@ async fn fetch_openai_models(
     })
 }
 
+async fn fetch_gemini_models(
+    client: &Client,
+    request: &LlmModelFetchRequest,
+) -> Result<LlmModelFetchResponse, String> {
+    if request.api_key.trim().is_empty() {
+        return Err("API key is required.".to_string());
+    }
+    let base = if request.base_url.trim().is_empty() {
+        "https://generativelanguage.googleapis.com/v1beta".to_string()
+    } else {
+        request.base_url.trim().trim_end_matches('/').to_string()
+    };
+    let url = format!("{}/models?pageSize=1000", base);
+    let response = client
+        .get(&url)
+        .header("x-goog-api-key", request.api_key.trim())
+        .send()
+        .await
+        .map_err(|e| format_reqwest_error("gemini.models", &url, &e))?;
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format_reqwest_error("gemini.models.read", &url, &e))?;
+    let value: serde_json::Value = serde_json::from_str(&body).map_err(|e| {
+        format!(
+            "Invalid JSON response (HTTP {}). error=\"{}\" body_preview=\"{}\"",
+            status.as_u16(),
+            e,
+            truncate_for_error(&body, 800)
+        )
+    })?;
+    if !status.is_success() {
+        let message = value
+            .get("error")
+            .and_then(|err| err.get("message"))
+            .and_then(|msg| msg.as_str())
+            .unwrap_or("LLM request failed");
+        return Err(format!("{} (HTTP {})", message, status.as_u16()));
+    }
+    let models = normalize_model_list(parse_gemini_models(&value));
+    if models.is_empty() {
+        return Err("No models found.".to_string());
+    }
+    Ok(LlmModelFetchResponse {
+        models,
+        source_url: url,
+    })
+}
+
 async fn fetch_local_models(
     client: &Client,
     provider: &str,
@@ -992,6 +1614,7 @@ async fn fetch_local_models(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 async fn request_openai_compatible_stream<F>(
     client: &Client,
     profile: &LlmProfile,
@@ -999,6 +1622,7 @@ async fn request_openai_compatible_stream<F>(
     system_prompt: &str,
     user_prompt: &str,
     response_format: LlmResponseFormat,
+    tools: &[ToolDefinition],
     on_chunk: &mut F,
 ) -> Result<String, String>
 where
@@ -1020,7 +1644,9 @@ where
     } else {
         payload["max_tokens"] = serde_json::json!(profile.max_tokens);
     }
-    if profile.provider.to_lowercase() == "openai" {
+    if !tools.is_empty() {
+        payload["tools"] = openai_chat_tools(tools);
+    } else if profile.provider.to_lowercase() == "openai" {
         if let Some(format) = openai_chat_response_format(response_format) {
             payload["response_format"] = format;
         }
@@ -1053,6 +1679,7 @@ where
     }
 
     let mut full = String::new();
+    let mut calls = ToolCallAccumulator::default();
     let mut buffer = String::new();
     let mut stream = response.bytes_stream();
     'outer: while let Some(item) = stream.next().await {
@@ -1077,7 +1704,10 @@ where
                 Ok(value) => value,
                 Err(_) => continue,
             };
+            record_usage(&profile.provider, &value);
             let delta = &value["choices"][0]["delta"];
+            calls.push_openai_chat_delta(delta);
+            record_reasoning(openai_chat_reasoning(delta));
             if let Some(content) = delta.get("content").and_then(|v| v.as_str()) {
                 if !content.is_empty() {
                     full.push_str(content);
@@ -1106,6 +1736,8 @@ where
             }
             if let Ok(value) = serde_json::from_str::<serde_json::Value>(data) {
                 let delta = &value["choices"][0]["delta"];
+                calls.push_openai_chat_delta(delta);
+                record_reasoning(openai_chat_reasoning(delta));
                 if let Some(content) = delta.get("content").and_then(|v| v.as_str()) {
                     if !content.is_empty() {
                         full.push_str(content);
@@ -1123,6 +1755,10 @@ where
         }
     }
 
+    let calls = calls.into_calls();
+    if !tools.is_empty() && (!calls.is_empty() || !full.trim().is_empty()) {
+        return Ok(tool_calls_to_decision(&full, &calls, tools));
+    }
     if full.trim().is_empty() {
         return Err("LLM response is empty".to_string());
     }
@@ -1136,6 +1772,7 @@ async fn request_openai_compatible(
     system_prompt: &str,
     user_prompt: &str,
     response_format: LlmResponseFormat,
+    tools: &[ToolDefinition],
 ) -> Result<String, String> {
     let url = openai_chat_url(base_url);
     let mut payload = serde_json::json!({
@@ -1152,7 +1789,9 @@ async fn request_openai_compatible(
     } else {
         payload["max_tokens"] = serde_json::json!(profile.max_tokens);
     }
-    if profile.provider.to_lowercase() == "openai" {
+    if !tools.is_empty() {
+        payload["tools"] = openai_chat_tools(tools);
+    } else if profile.provider.to_lowercase() == "openai" {
         if let Some(format) = openai_chat_response_format(response_format) {
             payload["response_format"] = format;
         }
@@ -1191,25 +1830,37 @@ async fn request_openai_compatible(
             .unwrap_or("LLM request failed");
         return Err(format!("{} (HTTP {})", message, status.as_u16()));
     }
+    record_usage(&profile.provider, &value);
+    record_reasoning(openai_chat_reasoning(&value["choices"][0]["message"]));
     let content = value["choices"][0]["message"]["content"]
         .as_str()
         .or_else(|| value["choices"][0]["text"].as_str())
         .unwrap_or("")
         .trim()
         .to_string();
+    let calls = openai_chat_tool_calls(&value["choices"][0]["message"]);
+    if !tools.is_empty() && (!calls.is_empty() || !content.is_empty()) {
+        return Ok(tool_calls_to_decision(&content, &calls, tools));
+    }
     if content.is_empty() {
         return Err("LLM response is empty".to_string());
     }
     Ok(content)
 }
 
+/// Reasoning an OpenAI-compatible server sends next to the content of a
+/// message or stream delta: `reasoning_content` (DeepSeek, vLLM) or
+/// `reasoning` (OpenRouter, Ollama).
+fn openai_chat_reasoning(message: &serde_json::Value) -> &str {
+    message["reasoning_content"]
+        .as_str()
+        .or_else(|| message["reasoning"].as_str())
+        .unwrap_or_default()
+}
+
 fn use_max_completion_tokens(profile: &LlmProfile) -> bool {
     let provider = profile.provider.to_lowercase();
-    if provider != "openai" {
-        return false;
-    }
-    let model = profile.model.to_lowercase();
-    model.starts_with("gpt-5") || model.starts_with("o1") || model.starts_with("o3")
+    provider == "openai" && is_openai_reasoning_model(&profile.model)
 }
 
 fn build_http_client() -> Result<Client, String> {
@@ -1221,6 +1872,64 @@ fn build_http_client() -> Result<Client, String> {
     builder.build().map_err(|e| e.to_string())
 }
 
+/// HTTP status of a `GET {base}/models` with the profile's credentials, used
+/// to tell unreachable endpoints apart from rejected keys.
+pub async fn probe_profile(profile: &LlmProfile) -> Result<u16, String> {
+    let provider = profile.provider.to_lowercase();
+    if provider == "mock" {
+        return Ok(200);
+    }
+    let base_url = resolve_base_url(profile);
+    if base_url.is_empty() {
+        return Err("Base URL is required".to_string());
+    }
+    let client = build_http_client()?;
+    let url = format!("{}/models", base_url.trim_end_matches('/'));
+    let mut request = client.get(&url).timeout(Duration::from_secs(15));
+    if provider == "anthropic" {
+        request = request
+            .header("x-api-key", profile.api_key.trim())
+            .header("anthropic-version", "2023-06-01");
+    } else if provider == "gemini" {
+        request = request.header("x-goog-api-key", profile.api_key.trim());
+    } else if !profile.api_key.trim().is_empty() {
+        request = request.bearer_auth(profile.api_key.trim());
+    }
+    let response = request
+        .send()
+        .await
+        .map_err(|e| format_reqwest_error("probe", &url, &e))?;
+    Ok(response.status().as_u16())
+}
+
+/// Connects to `url` with both TLS stacks; returns the rustls and native-tls
+/// outcomes in that order. `build_http_client` picks one per platform.
+pub async fn probe_tls(url: &str) -> (Result<(), String>, Result<(), String>) {
+    async fn probe(builder: reqwest::ClientBuilder, url: &str) -> Result<(), String> {
+        let client = builder
+            .timeout(Duration::from_secs(15))
+            .build()
+            .map_err(|e| e.to_string())?;
+        client
+            .head(url)
+            .send()
+            .await
+            .map(|_| ())
+            .map_err(|e| format_reqwest_error("tls", url, &e))
+    }
+    let rustls = probe(Client::builder().use_rustls_tls(), url).await;
+    let native = probe(Client::builder().use_native_tls(), url).await;
+    (rustls, native)
+}
+
+pub fn default_tls_backend() -> &'static str {
+    if cfg!(windows) {
+        "native-tls"
+    } else {
+        "rustls"
+    }
+}
+
 fn format_reqwest_error(context: &str, url: &str, err: &reqwest::Error) -> String {
     let mut details = Vec::new();
     details.push(format!("Request failed ({})", context));
@@ -1267,19 +1976,22 @@ fn truncate_for_error(value: &str, max_len: usize) -> String {
     format!("{}...", &value[..end])
 }
 
+const ANTHROPIC_MIN_THINKING_BUDGET: u32 = 1024;
+
 async fn request_anthropic(
     client: &Client,
     profile: &LlmProfile,
     base_url: &str,
     system_prompt: &str,
     user_prompt: &str,
+    tools: &[ToolDefinition],
 ) -> Result<String, String> {
     let url = if base_url.contains("/messages") {
         base_url.to_string()
     } else {
         format!("{}/messages", base_url.trim_end_matches('/'))
     };
-    let payload = serde_json::json!({
+    let mut payload = serde_json::json!({
         "model": profile.model,
         "max_tokens": profile.max_tokens,
         "temperature": profile.temperature,
@@ -1289,6 +2001,20 @@ async fn request_anthropic(
             { "role": "user", "content": user_prompt }
         ]
     });
+    if !tools.is_empty() {
+        payload["tools"] = anthropic_tools(tools);
+    }
+    // Thinking needs a budget of at least 1024 tokens below `max_tokens` and
+    // rejects sampling overrides.
+    let thinking_budget = (profile.max_tokens / 2).max(ANTHROPIC_MIN_THINKING_BUDGET);
+    if profile.reasoning_trace && thinking_budget < profile.max_tokens {
+        payload["thinking"] =
+            serde_json::json!({ "type": "enabled", "budget_tokens": thinking_budget });
+        if let Some(payload) = payload.as_object_mut() {
+            payload.remove("temperature");
+            payload.remove("top_p");
+        }
+    }
 
     let response = client
         .post(url.clone())
@@ -1319,13 +2045,293 @@ async fn request_anthropic(
             .unwrap_or("LLM request failed");
         return Err(format!("{} (HTTP {})", message, status.as_u16()));
     }
-    let content = value["content"][0]["text"]
-        .as_str()
-        .unwrap_or("")
+    record_usage(&profile.provider, &value);
+    let thinking = value["content"]
+        .as_array()
+        .into_iter()
+        .flatten()
+        .filter(|block| block["type"] == "thinking")
+        .filter_map(|block| block["thinking"].as_str())
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    record_reasoning(&thinking);
+    // With tools the reply may open with a tool_use block, so join every text block.
+    let content = value["content"]
+        .as_array()
+        .map(|blocks| {
+            blocks
+                .iter()
+                .filter_map(|block| block.get("text").and_then(|text| text.as_str()))
+                .collect::<Vec<_>>()
+                .join("")
+        })
+        .unwrap_or_default()
         .trim()
         .to_string();
+    let calls = anthropic_tool_calls(&value);
+    if !tools.is_empty() && (!calls.is_empty() || !content.is_empty()) {
+        return Ok(tool_calls_to_decision(&content, &calls, tools));
+    }
     if content.is_empty() {
         return Err("LLM response is empty".to_string());
     }
     Ok(content)
 }
+
+const GEMINI_SAFETY_CATEGORIES: &[&str] = &[
+    "HARM_CATEGORY_HARASSMENT",
+    "HARM_CATEGORY_HATE_SPEECH",
+    "HARM_CATEGORY_SEXUALLY_EXPLICIT",
+    "HARM_CATEGORY_DANGEROUS_CONTENT",
+];
+
+/// Finish reasons for which Gemini withholds the reply.
+const GEMINI_BLOCKED_FINISH_REASONS: &[&str] = &[
+    "SAFETY",
+    "RECITATION",
+    "BLOCKLIST",
+    "PROHIBITED_CONTENT",
+    "SPII",
+];
+
+/// `safety_mode` keeps Gemini's medium blocking threshold. Without it nothing
+/// is blocked, since shell output and security-related code trip the filters.
+fn gemini_safety_settings(safety_mode: bool) -> serde_json::Value {
+    let threshold = if safety_mode {
+        "BLOCK_MEDIUM_AND_ABOVE"
+    } else {
+        "BLOCK_NONE"
+    };
+    GEMINI_SAFETY_CATEGORIES
+        .iter()
+        .map(|category| serde_json::json!({ "category": category, "threshold": threshold }))
+        .collect()
+}
+
+fn gemini_model_url(base_url: &str, model: &str, method: &str) -> String {
+    format!(
+        "{}/models/{}:{}",
+        base_url.trim_end_matches('/'),
+        model.trim().trim_start_matches("models/"),
+        method
+    )
+}
+
+fn gemini_payload(
+    profile: &LlmProfile,
+    system_prompt: &str,
+    user_prompt: &str,
+    response_format: LlmResponseFormat,
+    tools: &[ToolDefinition],
+) -> serde_json::Value {
+    let mut payload = serde_json::json!({
+        "systemInstruction": { "parts": [{ "text": system_prompt }] },
+        "contents": [
+            { "role": "user", "parts": [{ "text": user_prompt }] }
+        ],
+        "generationConfig": {
+            "temperature": profile.temperature,
+            "topP": profile.top_p,
+            "maxOutputTokens": profile.max_tokens
+        },
+        "safetySettings": gemini_safety_settings(profile.safety_mode)
+    });
+    if !tools.is_empty() {
+        payload["tools"] = gemini_tools(tools);
+    } else if response_format != LlmResponseFormat::Text {
+        payload["generationConfig"]["responseMimeType"] = serde_json::json!("application/json");
+    }
+    // Models before 2.5 do not think and reject the setting.
+    let model = profile.model.trim().trim_start_matches("models/");
+    if profile.reasoning_trace && !model.starts_with("gemini-1") && !model.starts_with("gemini-2.0")
+    {
+        payload["generationConfig"]["thinkingConfig"] =
+            serde_json::json!({ "includeThoughts": true });
+    }
+    payload
+}
+
+/// Text and function calls of the first candidate of a Gemini response or
+/// stream chunk; thought summaries are recorded as reasoning instead. A
+/// prompt or reply withheld by Gemini is an error naming why.
+fn gemini_candidate(value: &serde_json::Value) -> Result<(String, Vec<ToolCall>), String> {
+    if let Some(reason) = value["promptFeedback"]["blockReason"].as_str() {
+        return Err(format!("Gemini blocked the prompt ({})", reason));
+    }
+    let candidate = &value["candidates"][0];
+    let parts = candidate["content"]["parts"]
+        .as_array()
+        .cloned()
+        .unwrap_or_default();
+    let (thoughts, parts): (Vec<_>, Vec<_>) = parts
+        .into_iter()
+        .partition(|part| part["thought"].as_bool().unwrap_or(false));
+    for thought in &thoughts {
+        record_reasoning(thought["text"].as_str().unwrap_or_default());
+    }
+    let text: String = parts
+        .iter()
+        .filter_map(|part| part.get("text").and_then(|text| text.as_str()))
+        .collect();
+    let calls = gemini_tool_calls(&parts);
+    if let Some(reason) = candidate["finishReason"].as_str() {
+        if text.is_empty() && calls.is_empty() && GEMINI_BLOCKED_FINISH_REASONS.contains(&reason) {
+            return Err(format!("Gemini withheld the reply ({})", reason));
+        }
+    }
+    Ok((text, calls))
+}
+
+async fn request_gemini(
+    client: &Client,
+    profile: &LlmProfile,
+    base_url: &str,
+    system_prompt: &str,
+    user_prompt: &str,
+    response_format: LlmResponseFormat,
+    tools: &[ToolDefinition],
+) -> Result<String, String> {
+    let url = gemini_model_url(base_url, &profile.model, "generateContent");
+    let payload = gemini_payload(profile, system_prompt, user_prompt, response_format, tools);
+    let response = client
+        .post(&url)
+        .header("x-goog-api-key", profile.api_key.trim())
+        .json(&payload)
+        .send()
+        .await
+        .map_err(|e| format_reqwest_error("gemini", &url, &e))?;
+    let status = response.status();
+    let body = response
+        .text()
+        .await
+        .map_err(|e| format_reqwest_error("gemini.read", &url, &e))?;
+    let value: serde_json::Value = serde_json::from_str(&body).map_err(|e| {
+        format!(
+            "Invalid JSON response (HTTP {}). error=\"{}\" body_preview=\"{}\"",
+            status.as_u16(),
+            e,
+            truncate_for_error(&body, 800)
+        )
+    })?;
+    if !status.is_success() {
+        let message = value
+            .get("error")
+            .and_then(|err| err.get("message"))
+            .and_then(|msg| msg.as_str())
+            .unwrap_or("LLM request failed");
+        return Err(format!("{} (HTTP {})", message, status.as_u16()));
+    }
+    record_usage(&profile.provider, &value);
+    let (content, calls) = gemini_candidate(&value)?;
+    let content = content.trim().to_string();
+    if !tools.is_empty() && (!calls.is_empty() || !content.is_empty()) {
+        return Ok(tool_calls_to_decision(&content, &calls, tools));
+    }
+    if content.is_empty() {
+        return Err("LLM response is empty".to_string());
+    }
+    Ok(content)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn request_gemini_stream<F>(
+    client: &Client,
+    profile: &LlmProfile,
+    base_url: &str,
+    system_prompt: &str,
+    user_prompt: &str,
+    response_format: LlmResponseFormat,
+    tools: &[ToolDefinition],
+    on_chunk: &mut F,
+) -> Result<String, String>
+where
+    F: FnMut(String),
+{
+    let url = format!(
+        "{}?alt=sse",
+        gemini_model_url(base_url, &profile.model, "streamGenerateContent")
+    );
+    let payload = gemini_payload(profile, system_prompt, user_prompt, response_format, tools);
+    let response = client
+        .post(&url)
+        .header("x-goog-api-key", profile.api_key.trim())
+        .json(&payload)
+        .send()
+        .await
+        .map_err(|e| format_reqwest_error("gemini.stream", &url, &e))?;
+    let status = response.status();
+    if !status.is_success() {
+        let body = response.text().await.unwrap_or_default();
+        if let Ok(value) = serde_json::from_str::<serde_json::Value>(&body) {
+            let message = value
+                .get("error")
+                .and_then(|err| err.get("message"))
+                .and_then(|msg| msg.as_str())
+                .unwrap_or("LLM request failed");
+            return Err(format!("{} (HTTP {})", message, status.as_u16()));
+        }
+        return Err(format!("LLM request failed (HTTP {})", status.as_u16()));
+    }
+
+    let mut full = String::new();
+    let mut calls = ToolCallAccumulator::default();
+    let mut usage = None;
+    let mut buffer = String::new();
+    let mut stream = response.bytes_stream();
+    while let Some(item) = stream.next().await {
+        let chunk = item.map_err(|e| e.to_string())?;
+        buffer.push_str(&String::from_utf8_lossy(&chunk));
+        while let Some(pos) = buffer.find('\n') {
+            let line = buffer[..pos].trim_end_matches('\r').to_string();
+            buffer = buffer[pos + 1..].to_string();
+            push_gemini_stream_line(&line, &mut full, &mut calls, &mut usage, on_chunk)?;
+        }
+    }
+    for line in buffer.lines() {
+        push_gemini_stream_line(line, &mut full, &mut calls, &mut usage, on_chunk)?;
+    }
+    if let Some(usage) = usage {
+        record_usage(&profile.provider, &usage);
+    }
+
+    let calls = calls.into_calls();
+    if !tools.is_empty() && (!calls.is_empty() || !full.trim().is_empty()) {
+        return Ok(tool_calls_to_decision(&full, &calls, tools));
+    }
+    if full.trim().is_empty() {
+        return Err("LLM response is empty".to_string());
+    }
+    Ok(full)
+}
+
+/// Handles one line of a Gemini SSE stream. Every `data:` line is a whole
+/// response chunk; usage counts are cumulative, so only the last is kept.
+fn push_gemini_stream_line<F>(
+    line: &str,
+    full: &mut String,
+    calls: &mut ToolCallAccumulator,
+    usage: &mut Option<serde_json::Value>,
+    on_chunk: &mut F,
+) -> Result<(), String>
+where
+    F: FnMut(String),
+{
+    let Some(data) = line.trim_end_matches('\r').strip_prefix("data:") else {
+        return Ok(());
+    };
+    let Ok(value) = serde_json::from_str::<serde_json::Value>(data.trim()) else {
+        return Ok(());
+    };
+    if value.get("usageMetadata").is_some() {
+        *usage = Some(value.clone());
+    }
+    let (text, chunk_calls) = gemini_candidate(&value)?;
+    for call in chunk_calls {
+        calls.push(call);
+    }
+    if !text.is_empty() {
+        full.push_str(&text);
+        on_chunk(text);
+    }
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+use crate::services::llm::LlmProfile;
+
+pub const MOCK_SCRIPT_ENV: &str = "TAURIHANDS_MOCK_LLM";
+
+/// A scripted sequence of responses. The file may also be a bare array of steps.
+#[derive(Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+struct MockScript {
+    #[serde(default)]
+    responses: Vec<MockStep>,
+    #[serde(default, rename = "loop")]
+    repeat: bool,
+}
+
+#[derive(Deserialize, Clone)]
+#[serde(untagged)]
+enum MockStep {
+    Text(String),
+    Detailed(MockResponse),
+}
+
+#[derive(Deserialize, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+struct MockResponse {
+    #[serde(default)]
+    content: Option<serde_json::Value>,
+    #[serde(default)]
+    delay_ms: Option<u64>,
+    #[serde(default)]
+    error: Option<String>,
+}
+
+static MOCK_CURSORS: OnceLock<Mutex<HashMap<PathBuf, usize>>> = OnceLock::new();
+
+pub fn script_path(profile: &LlmProfile) -> Option<PathBuf> {
+    let configured = profile.base_url.trim();
+    if !configured.is_empty() {
+        return Some(PathBuf::from(configured));
+    }
+    std::env::var(MOCK_SCRIPT_ENV)
+        .ok()
+        .filter(|value| !value.trim().is_empty())
+        .map(PathBuf::from)
+}
+
+/// Returns the next scripted response for the `mock` provider.
+pub async fn request_mock(profile: &LlmProfile) -> Result<String, String> {
+    let path = script_path(profile).ok_or_else(|| {
+        format!(
+            "Mock provider needs a script path in Base URL or {}",
+            MOCK_SCRIPT_ENV
+        )
+    })?;
+    let step = next_step(&path)?;
+    let response = match step {
+        MockStep::Text(content) => MockResponse {
+            content: Some(serde_json::Value::String(content)),
+            ..MockResponse::default()
+        },
+        MockStep::Detailed(response) => response,
+    };
+    if let Some(delay) = response.delay_ms.filter(|delay| *delay > 0) {
+        tokio::time::sleep(Duration::from_millis(delay)).await;
+    }
+    if let Some(error) = response.error {
+        return Err(error);
+    }
+    match response.content {
+        Some(serde_json::Value::String(content)) => Ok(content),
+        Some(value) => serde_json::to_string(&value).map_err(|e| e.to_string()),
+        None => Ok(String::new()),
+    }
+}
+
+fn next_step(path: &PathBuf) -> Result<MockStep, String> {
+    let raw = std::fs::read_to_string(path)
+        .map_err(|e| format!("Unable to read mock script {}: {}", path.display(), e))?;
+    let script = match serde_json::from_str::<Vec<MockStep>>(&raw) {
+        Ok(responses) => MockScript {
+            responses,
+            repeat: false,
+        },
+        Err(_) => serde_json::from_str::<MockScript>(&raw)
+            .map_err(|e| format!("Invalid mock script: {}", e))?,
+    };
+    if script.responses.is_empty() {
+        return Err("Mock script has no responses".to_string());
+    }
+    let mut cursors = MOCK_CURSORS
+        .get_or_init(|| Mutex::new(HashMap::new()))
+        .lock()
+        .map_err(|_| "Mock cursor lock poisoned".to_string())?;
+    let cursor = cursors.entry(path.clone()).or_insert(0);
+    let index = if script.repeat {
+        *cursor % script.responses.len()
+    } else {
+        *cursor
+    };
+    let step = script
+        .responses
+        .get(index)
+        .cloned()
+        .ok_or_else(|| "Mock script exhausted".to_string())?;
+    *cursor += 1;
+    Ok(step)
+}
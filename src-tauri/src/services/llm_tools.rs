@@ -0,0 +1,294 @@
+use serde_json::Value;
+
+use crate::services::llm::LlmProfile;
+
+/// A kernel action offered to the model as a native tool.
+#[derive(Clone)]
+pub struct ToolDefinition {
+    /// Provider-facing name. Action types contain dots, which tool names may
+    /// not, so calls are mapped back through `action_type`.
+    pub name: String,
+    pub action_type: String,
+    pub description: String,
+    /// JSON schema of the action fields, excluding `type` and `id`.
+    pub parameters: Value,
+    /// Fields added to every call of this tool, such as the server and tool
+    /// name of an MCP tool.
+    pub fields: serde_json::Map<String, Value>,
+}
+
+#[derive(Clone, Default)]
+pub struct ToolCall {
+    pub id: String,
+    pub name: String,
+    /// Raw JSON arguments as sent by the provider.
+    pub arguments: String,
+}
+
+/// Whether `profile` should use native tool calling instead of the JSON
+/// action format. Other providers keep the prompt-described JSON.
+pub fn supports_native_tools(profile: &LlmProfile) -> bool {
+    profile.tool_calling
+        && matches!(
+            profile.provider.to_lowercase().as_str(),
+            "openai" | "anthropic" | "gemini"
+        )
+}
+
+pub fn openai_chat_tools(tools: &[ToolDefinition]) -> Value {
+    tools
+        .iter()
+        .map(|tool| {
+            serde_json::json!({
+                "type": "function",
+                "function": {
+                    "name": tool.name,
+                    "description": tool.description,
+                    "parameters": tool.parameters,
+                }
+            })
+        })
+        .collect()
+}
+
+pub fn openai_responses_tools(tools: &[ToolDefinition]) -> Value {
+    tools
+        .iter()
+        .map(|tool| {
+            serde_json::json!({
+                "type": "function",
+                "name": tool.name,
+                "description": tool.description,
+                "parameters": tool.parameters,
+            })
+        })
+        .collect()
+}
+
+pub fn anthropic_tools(tools: &[ToolDefinition]) -> Value {
+    tools
+        .iter()
+        .map(|tool| {
+            serde_json::json!({
+                "name": tool.name,
+                "description": tool.description,
+                "input_schema": tool.parameters,
+            })
+        })
+        .collect()
+}
+
+/// Gemini takes one tool holding every function. `parametersJsonSchema`
+/// accepts full JSON schema, unlike `parameters`, which rejects keywords such
+/// as `additionalProperties`.
+pub fn gemini_tools(tools: &[ToolDefinition]) -> Value {
+    let declarations: Vec<Value> = tools
+        .iter()
+        .map(|tool| {
+            serde_json::json!({
+                "name": tool.name,
+                "description": tool.description,
+                "parametersJsonSchema": tool.parameters,
+            })
+        })
+        .collect();
+    serde_json::json!([{ "functionDeclarations": declarations }])
+}
+
+/// Reassembles chat-completions tool calls, whose name and arguments arrive
+/// in fragments keyed by `index`.
+#[derive(Default)]
+pub struct ToolCallAccumulator {
+    calls: Vec<ToolCall>,
+}
+
+impl ToolCallAccumulator {
+    pub fn push_openai_chat_delta(&mut self, delta: &Value) {
+        let Some(items) = delta.get("tool_calls").and_then(Value::as_array) else {
+            return;
+        };
+        for item in items {
+            let index = item
+                .get("index")
+                .and_then(Value::as_u64)
+                .map(|index| index as usize)
+                .unwrap_or(self.calls.len());
+            if self.calls.len() <= index {
+                self.calls.resize(index + 1, ToolCall::default());
+            }
+            let call = &mut self.calls[index];
+            if let Some(id) = item.get("id").and_then(Value::as_str) {
+                call.id = id.to_string();
+            }
+            let function = &item["function"];
+            if let Some(name) = function.get("name").and_then(Value::as_str) {
+                call.name.push_str(name);
+            }
+            if let Some(arguments) = function.get("arguments").and_then(Value::as_str) {
+                call.arguments.push_str(arguments);
+            }
+        }
+    }
+
+    pub fn push(&mut self, call: ToolCall) {
+        self.calls.push(call);
+    }
+
+    pub fn into_calls(self) -> Vec<ToolCall> {
+        self.calls
+            .into_iter()
+            .filter(|call| !call.name.is_empty())
+            .collect()
+    }
+}
+
+/// Tool calls of a non-streamed chat-completions message.
+pub fn openai_chat_tool_calls(message: &Value) -> Vec<ToolCall> {
+    let mut calls = ToolCallAccumulator::default();
+    calls.push_openai_chat_delta(message);
+    calls.into_calls()
+}
+
+fn openai_responses_tool_call(item: &Value) -> Option<ToolCall> {
+    if item.get("type").and_then(Value::as_str) != Some("function_call") {
+        return None;
+    }
+    Some(ToolCall {
+        id: item
+            .get("call_id")
+            .or_else(|| item.get("id"))
+            .and_then(Value::as_str)
+            .unwrap_or_default()
+            .to_string(),
+        name: item.get("name")?.as_str()?.to_string(),
+        arguments: item
+            .get("arguments")
+            .and_then(Value::as_str)
+            .unwrap_or("{}")
+            .to_string(),
+    })
+}
+
+/// Function calls in the `output` of a Responses API result.
+pub fn openai_responses_tool_calls(response: &Value) -> Vec<ToolCall> {
+    response
+        .get("output")
+        .and_then(Value::as_array)
+        .map(|items| {
+            items
+                .iter()
+                .filter_map(openai_responses_tool_call)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// A finished function call announced by a Responses API stream event.
+/// Argument deltas are ignored; the `output_item.done` event repeats them whole.
+pub fn openai_responses_stream_tool_call(event: &Value) -> Option<ToolCall> {
+    if event.get("type").and_then(Value::as_str) != Some("response.output_item.done") {
+        return None;
+    }
+    openai_responses_tool_call(event.get("item")?)
+}
+
+/// Whether a Responses API stream event carries function-call arguments
+/// rather than reply text.
+pub fn is_openai_responses_tool_event(event: &Value) -> bool {
+    event
+        .get("type")
+        .and_then(Value::as_str)
+        .is_some_and(|kind| kind.starts_with("response.function_call"))
+}
+
+/// `tool_use` blocks of an Anthropic messages response.
+pub fn anthropic_tool_calls(value: &Value) -> Vec<ToolCall> {
+    value
+        .get("content")
+        .and_then(Value::as_array)
+        .map(|blocks| {
+            blocks
+                .iter()
+                .filter(|block| block.get("type").and_then(Value::as_str) == Some("tool_use"))
+                .filter_map(|block| {
+                    Some(ToolCall {
+                        id: block
+                            .get("id")
+                            .and_then(Value::as_str)
+                            .unwrap_or_default()
+                            .to_string(),
+                        name: block.get("name")?.as_str()?.to_string(),
+                        arguments: block
+                            .get("input")
+                            .map(Value::to_string)
+                            .unwrap_or_else(|| "{}".to_string()),
+                    })
+                })
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// `functionCall` parts of a Gemini candidate's content.
+pub fn gemini_tool_calls(parts: &[Value]) -> Vec<ToolCall> {
+    parts
+        .iter()
+        .filter_map(|part| {
+            let call = part.get("functionCall")?;
+            Some(ToolCall {
+                id: call
+                    .get("id")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+                    .to_string(),
+                name: call.get("name")?.as_str()?.to_string(),
+                arguments: call
+                    .get("args")
+                    .map(Value::to_string)
+                    .unwrap_or_else(|| "{}".to_string()),
+            })
+        })
+        .collect()
+}
+
+/// Turns a native tool-calling reply into the `{"message":...,"actions":[...]}`
+/// shape the kernel parses, so both paths share the same validation.
+pub fn tool_calls_to_decision(text: &str, calls: &[ToolCall], tools: &[ToolDefinition]) -> String {
+    let text = text.trim();
+    if calls.is_empty() {
+        // Some models ignore the tools and answer in the JSON format anyway.
+        let is_json = serde_json::from_str::<Value>(text)
+            .map(|value| value.is_object() || value.is_array())
+            .unwrap_or(false);
+        if is_json {
+            return text.to_string();
+        }
+        return serde_json::json!({ "message": text, "actions": [] }).to_string();
+    }
+    let actions: Vec<Value> = calls
+        .iter()
+        .map(|call| {
+            let mut action = serde_json::from_str::<Value>(&call.arguments)
+                .ok()
+                .filter(Value::is_object)
+                .unwrap_or_else(|| serde_json::json!({}));
+            let tool = tools.iter().find(|tool| tool.name == call.name);
+            let action_type = tool
+                .map(|tool| tool.action_type.as_str())
+                .unwrap_or(&call.name);
+            for (key, value) in tool.map(|tool| &tool.fields).into_iter().flatten() {
+                action[key] = value.clone();
+            }
+            action["type"] = serde_json::json!(action_type);
+            if !call.id.is_empty() {
+                action["id"] = serde_json::json!(call.id);
+            }
+            action
+        })
+        .collect();
+    let mut decision = serde_json::json!({ "actions": actions });
+    if !text.is_empty() {
+        decision["message"] = serde_json::json!(text);
+    }
+    decision.to_string()
+}
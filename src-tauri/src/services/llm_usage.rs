@@ -0,0 +1,207 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::services::llm::LlmProfile;
+
+/// USD per million prompt and completion tokens, matched by the longest
+/// prefix of the model name so dated variants share their family's price.
+const PRICING: &[(&str, f64, f64)] = &[
+    ("gpt-5-nano", 0.05, 0.40),
+    ("gpt-5-mini", 0.25, 2.00),
+    ("gpt-5", 1.25, 10.00),
+    ("gpt-4.1-nano", 0.10, 0.40),
+    ("gpt-4.1-mini", 0.40, 1.60),
+    ("gpt-4.1", 2.00, 8.00),
+    ("gpt-4o-mini", 0.15, 0.60),
+    ("gpt-4o", 2.50, 10.00),
+    ("o1", 15.00, 60.00),
+    ("o3-mini", 1.10, 4.40),
+    ("o3", 2.00, 8.00),
+    ("o4-mini", 1.10, 4.40),
+    ("claude-opus-4", 15.00, 75.00),
+    ("claude-3-opus", 15.00, 75.00),
+    ("claude-sonnet-4", 3.00, 15.00),
+    ("claude-3-7-sonnet", 3.00, 15.00),
+    ("claude-3-5-sonnet", 3.00, 15.00),
+    ("claude-3.5-sonnet", 3.00, 15.00),
+    ("claude-3-5-haiku", 0.80, 4.00),
+    ("claude-3.5-haiku", 0.80, 4.00),
+    ("claude-3-haiku", 0.25, 1.25),
+    ("gemini-2.5-pro", 1.25, 10.00),
+    ("gemini-2.5-flash", 0.30, 2.50),
+    ("gemini-2.0-flash", 0.10, 0.40),
+    ("gemini-1.5-pro", 1.25, 5.00),
+    ("gemini-1.5-flash", 0.075, 0.30),
+];
+
+/// Providers that run locally and cost nothing per token.
+const FREE_PROVIDERS: &[&str] = &["local", "ollama", "mock"];
+
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenUsage {
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct UsageTotals {
+    pub requests: u64,
+    pub prompt_tokens: u64,
+    pub completion_tokens: u64,
+    pub cost_usd: f64,
+    /// Requests to models missing from the pricing table; `cost_usd` leaves
+    /// them out, so it is a lower bound when this is not zero.
+    pub unpriced_requests: u64,
+}
+
+impl UsageTotals {
+    fn add(&mut self, usage: TokenUsage, cost: Option<f64>) {
+        self.requests += 1;
+        self.prompt_tokens += usage.prompt_tokens;
+        self.completion_tokens += usage.completion_tokens;
+        match cost {
+            Some(cost) => self.cost_usd += cost,
+            None => self.unpriced_requests += 1,
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RunUsage {
+    pub total: UsageTotals,
+    /// By profile name.
+    pub profiles: BTreeMap<String, UsageTotals>,
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+struct UsageLedger {
+    total: UsageTotals,
+    profiles: BTreeMap<String, UsageTotals>,
+    runs: BTreeMap<String, RunUsage>,
+}
+
+/// Usage of one run, or of the whole workspace when no run is given.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct UsageReport {
+    pub run_id: Option<String>,
+    pub total: UsageTotals,
+    pub profiles: BTreeMap<String, UsageTotals>,
+    /// Per-run totals; only in the workspace report.
+    pub runs: BTreeMap<String, UsageTotals>,
+}
+
+/// Estimated cost of a request in USD; `None` when the model is not priced.
+pub fn estimate_cost(profile: &LlmProfile, usage: TokenUsage) -> Option<f64> {
+    if FREE_PROVIDERS.contains(&profile.provider.to_lowercase().as_str()) {
+        return Some(0.0);
+    }
+    let model = profile.model.trim().to_lowercase();
+    let model = model.trim_start_matches("models/");
+    let (_, prompt, completion) = PRICING
+        .iter()
+        .filter(|(prefix, _, _)| model.starts_with(prefix))
+        .max_by_key(|(prefix, _, _)| prefix.len())?;
+    Some(
+        (usage.prompt_tokens as f64 * prompt + usage.completion_tokens as f64 * completion)
+            / 1_000_000.0,
+    )
+}
+
+pub fn usage_path(root: &Path) -> PathBuf {
+    root.join(".taurihands").join("usage.json")
+}
+
+/// Token and cost totals per run and per profile, kept in
+/// `.taurihands/usage.json`. The lock serializes read-modify-write cycles of
+/// requests finishing at the same time.
+#[derive(Clone, Default)]
+pub struct UsageTracker {
+    lock: Arc<Mutex<()>>,
+}
+
+impl UsageTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds one request and returns its estimated cost.
+    pub fn record(
+        &self,
+        root: &Path,
+        run_id: &str,
+        profile: &LlmProfile,
+        usage: TokenUsage,
+    ) -> Result<Option<f64>, String> {
+        let _guard = self
+            .lock
+            .lock()
+            .map_err(|_| "Usage lock poisoned".to_string())?;
+        let mut ledger = load_ledger(root);
+        let cost = estimate_cost(profile, usage);
+        let profile_name = profile.profile_name.clone();
+        ledger.total.add(usage, cost);
+        ledger
+            .profiles
+            .entry(profile_name.clone())
+            .or_default()
+            .add(usage, cost);
+        if !run_id.is_empty() {
+            let run = ledger.runs.entry(run_id.to_string()).or_default();
+            run.total.add(usage, cost);
+            run.profiles
+                .entry(profile_name)
+                .or_default()
+                .add(usage, cost);
+        }
+        let path = usage_path(root);
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let data = serde_json::to_vec_pretty(&ledger).map_err(|e| e.to_string())?;
+        std::fs::write(path, data).map_err(|e| e.to_string())?;
+        Ok(cost)
+    }
+
+    pub fn report(&self, root: &Path, run_id: Option<&str>) -> Result<UsageReport, String> {
+        let _guard = self
+            .lock
+            .lock()
+            .map_err(|_| "Usage lock poisoned".to_string())?;
+        let ledger = load_ledger(root);
+        Ok(match run_id {
+            Some(run_id) => {
+                let run = ledger.runs.get(run_id).cloned().unwrap_or_default();
+                UsageReport {
+                    run_id: Some(run_id.to_string()),
+                    total: run.total,
+                    profiles: run.profiles,
+                    runs: BTreeMap::new(),
+                }
+            }
+            None => UsageReport {
+                run_id: None,
+                total: ledger.total,
+                profiles: ledger.profiles,
+                runs: ledger
+                    .runs
+                    .into_iter()
+                    .map(|(id, run)| (id, run.total))
+                    .collect(),
+            },
+        })
+    }
+}
+
+fn load_ledger(root: &Path) -> UsageLedger {
+    std::fs::read_to_string(usage_path(root))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
@@ -0,0 +1,89 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::filter::LevelFilter;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+use tracing_subscriber::{fmt, reload, Registry};
+
+pub const LOG_LEVEL_ENV: &str = "TAURIHANDS_LOG";
+const LOG_FILE_PREFIX: &str = "taurihands.log";
+const DEFAULT_LOG_LEVEL: LevelFilter = LevelFilter::INFO;
+
+struct LoggingHandle {
+    dir: PathBuf,
+    filter: reload::Handle<LevelFilter, Registry>,
+}
+
+static LOGGING: OnceLock<LoggingHandle> = OnceLock::new();
+// Dropping the guard flushes and stops the background writer.
+static LOG_WRITER: Mutex<Option<WorkerGuard>> = Mutex::new(None);
+
+/// Installs the global subscriber, writing daily-rotated logs into `log_dir`.
+/// `log` records from dependencies are forwarded into the same pipeline.
+pub fn init_logging(log_dir: &Path) -> Result<(), String> {
+    if LOGGING.get().is_some() {
+        return Ok(());
+    }
+    std::fs::create_dir_all(log_dir).map_err(|e| e.to_string())?;
+    let appender = tracing_appender::rolling::daily(log_dir, LOG_FILE_PREFIX);
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+    let level = std::env::var(LOG_LEVEL_ENV)
+        .ok()
+        .and_then(|value| parse_level(&value).ok())
+        .unwrap_or(DEFAULT_LOG_LEVEL);
+    let (filter, handle) = reload::Layer::new(level);
+    tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt::layer().with_writer(writer).with_ansi(false))
+        .with(fmt::layer().with_writer(std::io::stderr))
+        .try_init()
+        .map_err(|e| e.to_string())?;
+    let _ = LOGGING.set(LoggingHandle {
+        dir: log_dir.to_path_buf(),
+        filter: handle,
+    });
+    if let Ok(mut writer) = LOG_WRITER.lock() {
+        *writer = Some(guard);
+    }
+    Ok(())
+}
+
+/// Flushes buffered log lines to disk; later records are no longer written to file.
+pub fn flush_logging() {
+    if let Ok(mut writer) = LOG_WRITER.lock() {
+        writer.take();
+    }
+}
+
+pub fn set_log_level(level: &str) -> Result<String, String> {
+    let filter = parse_level(level)?;
+    let logging = LOGGING
+        .get()
+        .ok_or_else(|| "Logging is not initialized".to_string())?;
+    logging
+        .filter
+        .modify(|current| *current = filter)
+        .map_err(|e| e.to_string())?;
+    tracing::info!(level = %filter, "log level changed");
+    Ok(filter.to_string())
+}
+
+pub fn log_level() -> String {
+    LOGGING
+        .get()
+        .and_then(|logging| logging.filter.clone_current())
+        .unwrap_or(DEFAULT_LOG_LEVEL)
+        .to_string()
+}
+
+pub fn log_dir() -> Option<PathBuf> {
+    LOGGING.get().map(|logging| logging.dir.clone())
+}
+
+fn parse_level(value: &str) -> Result<LevelFilter, String> {
+    value
+        .trim()
+        .parse::<LevelFilter>()
+        .map_err(|_| format!("Unknown log level: {}", value.trim()))
+}
@@ -0,0 +1,642 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::services::audit::{now_ms, AuditEntry, AuditLog};
+use crate::services::tools::ToolResult;
+use crate::services::trust::read_trusted_config;
+
+const PROTOCOL_VERSION: &str = "2024-11-05";
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+const MAX_RESULT_CHARS: usize = 12_000;
+
+/// An MCP server the kernel may call tools on.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct McpServerConfig {
+    /// Identifies the server in `mcp.call` actions.
+    pub name: String,
+    /// "stdio" starts `command` in the workspace; "http" posts to `url`.
+    pub transport: String,
+    pub command: String,
+    pub args: Vec<String>,
+    pub env: HashMap<String, String>,
+    pub url: String,
+    pub headers: HashMap<String, String>,
+    pub enabled: bool,
+}
+
+impl Default for McpServerConfig {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            transport: "stdio".to_string(),
+            command: String::new(),
+            args: Vec::new(),
+            env: HashMap::new(),
+            url: String::new(),
+            headers: HashMap::new(),
+            enabled: true,
+        }
+    }
+}
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct McpConfig {
+    pub servers: Vec<McpServerConfig>,
+}
+
+pub fn mcp_config_path(root: &Path) -> PathBuf {
+    root.join(".taurihands").join("mcp.json")
+}
+
+pub fn load_mcp_config(root: &Path) -> McpConfig {
+    std::fs::read_to_string(mcp_config_path(root))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+/// The servers the kernel may start: none until the user trusts this mcp.json.
+pub fn load_trusted_mcp_config(root: &Path) -> McpConfig {
+    read_trusted_config(root, "mcp.json")
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_mcp_config(root: &Path, config: &McpConfig) -> Result<(), String> {
+    let mut names = Vec::new();
+    for server in &config.servers {
+        let name = server.name.trim();
+        if name.is_empty() {
+            return Err("MCP server name cannot be empty".to_string());
+        }
+        if names.contains(&name) {
+            return Err(format!("Duplicate MCP server name: {}", name));
+        }
+        names.push(name);
+        match server.transport.as_str() {
+            "stdio" if server.command.trim().is_empty() => {
+                return Err(format!("MCP server {} needs a command", name));
+            }
+            "http" if server.url.trim().is_empty() => {
+                return Err(format!("MCP server {} needs a url", name));
+            }
+            "stdio" | "http" => {}
+            other => return Err(format!("Unknown MCP transport: {}", other)),
+        }
+    }
+    let path = mcp_config_path(root);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_vec_pretty(config).map_err(|e| e.to_string())?;
+    std::fs::write(path, data).map_err(|e| e.to_string())
+}
+
+/// A tool advertised by a connected server.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpTool {
+    pub server: String,
+    pub name: String,
+    pub description: String,
+    pub input_schema: Value,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct McpServerStatus {
+    pub name: String,
+    pub transport: String,
+    pub enabled: bool,
+    pub connected: bool,
+    pub tools: Vec<String>,
+    pub error: Option<String>,
+}
+
+/// Connects lazily to the servers in a trusted `.taurihands/mcp.json`. A server that
+/// fails to connect is not retried until `refresh` or a workspace change.
+#[derive(Clone, Default)]
+pub struct McpManager {
+    inner: Arc<Mutex<McpState>>,
+}
+
+#[derive(Default)]
+struct McpState {
+    root: PathBuf,
+    config: McpConfig,
+    servers: HashMap<String, ServerSlot>,
+}
+
+#[derive(Default)]
+struct ServerSlot {
+    connection: Option<Connection>,
+    tools: Vec<McpTool>,
+    error: Option<String>,
+}
+
+impl McpManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Loads the trusted workspace config and closes every open connection.
+    pub fn configure(&self, root: &Path) {
+        if let Ok(mut state) = self.inner.lock() {
+            state.root = root.to_path_buf();
+            state.config = load_trusted_mcp_config(root);
+            state.servers.clear();
+        }
+    }
+
+    /// Tools of every enabled server, connecting to servers not tried yet.
+    pub fn tools(&self) -> Vec<McpTool> {
+        let Ok(mut state) = self.inner.lock() else {
+            return Vec::new();
+        };
+        let servers: Vec<McpServerConfig> = state
+            .config
+            .servers
+            .iter()
+            .filter(|server| server.enabled)
+            .cloned()
+            .collect();
+        let mut tools = Vec::new();
+        for server in servers {
+            let root = state.root.clone();
+            let slot = state.servers.entry(server.name.clone()).or_default();
+            if slot.connection.is_none() && slot.error.is_none() {
+                slot.connect(&server, &root);
+            }
+            tools.extend(slot.tools.iter().cloned());
+        }
+        tools
+    }
+
+    pub fn call_tool(
+        &self,
+        server: &str,
+        tool: &str,
+        arguments: &Value,
+        audit: &AuditLog,
+    ) -> Result<ToolResult, String> {
+        let mut state = self
+            .inner
+            .lock()
+            .map_err(|_| "MCP lock poisoned".to_string())?;
+        let config = state
+            .config
+            .servers
+            .iter()
+            .find(|config| config.name == server && config.enabled)
+            .cloned()
+            .ok_or_else(|| format!("Unknown or disabled MCP server: {}", server))?;
+        let root = state.root.clone();
+        let slot = state.servers.entry(server.to_string()).or_default();
+        if slot.connection.is_none() {
+            slot.connect(&config, &root);
+        }
+        let error = slot.error.clone();
+        let connection = slot.connection.as_mut().ok_or_else(|| {
+            format!(
+                "MCP server {} is not connected: {}",
+                server,
+                error.as_deref().unwrap_or("unknown error")
+            )
+        })?;
+        let arguments = if arguments.is_null() {
+            serde_json::json!({})
+        } else {
+            arguments.clone()
+        };
+        let response = connection.request(
+            "tools/call",
+            serde_json::json!({ "name": tool, "arguments": arguments }),
+        );
+        let result = match response {
+            Ok(result) => result,
+            Err(err) => {
+                // The server may have exited; connect again on the next call.
+                slot.connection = None;
+                return Err(format!("MCP call {}/{} failed: {}", server, tool, err));
+            }
+        };
+        let ok = !result
+            .get("isError")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        let text = content_text(&result);
+        audit.write(AuditEntry {
+            timestamp_ms: now_ms(),
+            action: "mcp.call".to_string(),
+            session_id: None,
+            command: Some(format!("{}/{}", server, tool)),
+            payload: serde_json::json!({ "arguments": arguments, "ok": ok }),
+        });
+        Ok(ToolResult {
+            ok,
+            stdout_excerpt: Some(truncate_chars(&text, MAX_RESULT_CHARS)),
+            stderr_excerpt: None,
+            exit_code: None,
+            artifacts: Some(serde_json::json!({
+                "mcp": { "server": server, "tool": tool, "result": result },
+            })),
+            next_suggestion: None,
+            requires_user: false,
+        })
+    }
+
+    pub fn status(&self) -> Vec<McpServerStatus> {
+        let Ok(state) = self.inner.lock() else {
+            return Vec::new();
+        };
+        state
+            .config
+            .servers
+            .iter()
+            .map(|server| {
+                let slot = state.servers.get(&server.name);
+                McpServerStatus {
+                    name: server.name.clone(),
+                    transport: server.transport.clone(),
+                    enabled: server.enabled,
+                    connected: slot.is_some_and(|slot| slot.connection.is_some()),
+                    tools: slot
+                        .map(|slot| slot.tools.iter().map(|tool| tool.name.clone()).collect())
+                        .unwrap_or_default(),
+                    error: slot.and_then(|slot| slot.error.clone()),
+                }
+            })
+            .collect()
+    }
+
+    /// Reloads the config and reconnects to every enabled server.
+    pub fn refresh(&self) -> Vec<McpServerStatus> {
+        let root = match self.inner.lock() {
+            Ok(state) => state.root.clone(),
+            Err(_) => return Vec::new(),
+        };
+        self.configure(&root);
+        self.tools();
+        self.status()
+    }
+}
+
+impl ServerSlot {
+    fn connect(&mut self, config: &McpServerConfig, root: &Path) {
+        self.tools.clear();
+        match Connection::open(config, root).and_then(|mut connection| {
+            let tools = connection.list_tools(&config.name)?;
+            Ok((connection, tools))
+        }) {
+            Ok((connection, tools)) => {
+                self.connection = Some(connection);
+                self.tools = tools;
+                self.error = None;
+            }
+            Err(err) => {
+                tracing::warn!(server = %config.name, error = %err, "mcp connect failed");
+                self.connection = None;
+                self.error = Some(err);
+            }
+        }
+    }
+}
+
+enum Connection {
+    Stdio(StdioConnection),
+    Http(HttpConnection),
+}
+
+impl Connection {
+    fn open(config: &McpServerConfig, root: &Path) -> Result<Self, String> {
+        let mut connection = match config.transport.as_str() {
+            "http" => Connection::Http(HttpConnection {
+                url: config.url.trim().to_string(),
+                headers: config.headers.clone(),
+                session_id: None,
+                next_id: 0,
+            }),
+            _ => Connection::Stdio(StdioConnection::spawn(config, root)?),
+        };
+        connection.request(
+            "initialize",
+            serde_json::json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": {},
+                "clientInfo": {
+                    "name": "TauriHands",
+                    "version": env!("CARGO_PKG_VERSION"),
+                },
+            }),
+        )?;
+        connection.notify("notifications/initialized")?;
+        Ok(connection)
+    }
+
+    fn list_tools(&mut self, server: &str) -> Result<Vec<McpTool>, String> {
+        let mut tools = Vec::new();
+        let mut cursor: Option<String> = None;
+        loop {
+            let params = match &cursor {
+                Some(cursor) => serde_json::json!({ "cursor": cursor }),
+                None => serde_json::json!({}),
+            };
+            let result = self.request("tools/list", params)?;
+            for tool in result
+                .get("tools")
+                .and_then(Value::as_array)
+                .into_iter()
+                .flatten()
+            {
+                let Some(name) = tool.get("name").and_then(Value::as_str) else {
+                    continue;
+                };
+                tools.push(McpTool {
+                    server: server.to_string(),
+                    name: name.to_string(),
+                    description: tool
+                        .get("description")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string(),
+                    input_schema: tool
+                        .get("inputSchema")
+                        .cloned()
+                        .unwrap_or_else(|| serde_json::json!({ "type": "object" })),
+                });
+            }
+            cursor = result
+                .get("nextCursor")
+                .and_then(Value::as_str)
+                .map(str::to_string);
+            if cursor.is_none() {
+                return Ok(tools);
+            }
+        }
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Result<Value, String> {
+        match self {
+            Connection::Stdio(connection) => connection.request(method, params),
+            Connection::Http(connection) => connection.request(method, params),
+        }
+    }
+
+    fn notify(&mut self, method: &str) -> Result<(), String> {
+        let message = serde_json::json!({ "jsonrpc": "2.0", "method": method });
+        match self {
+            Connection::Stdio(connection) => connection.send(&message),
+            Connection::Http(connection) => connection.post(&message).map(|_| ()),
+        }
+    }
+}
+
+/// Newline-delimited JSON-RPC over the stdin and stdout of a child process.
+struct StdioConnection {
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+    next_id: u64,
+}
+
+impl StdioConnection {
+    fn spawn(config: &McpServerConfig, root: &Path) -> Result<Self, String> {
+        let mut child = Command::new(config.command.trim())
+            .args(&config.args)
+            .envs(&config.env)
+            .current_dir(root)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| format!("Failed to start {}: {}", config.command, e))?;
+        let stdin = child.stdin.take().ok_or("MCP server stdin unavailable")?;
+        let stdout = child.stdout.take().ok_or("MCP server stdout unavailable")?;
+        let (sender, lines) = channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Ok(Self {
+            child,
+            stdin,
+            lines,
+            next_id: 0,
+        })
+    }
+
+    fn send(&mut self, message: &Value) -> Result<(), String> {
+        writeln!(self.stdin, "{}", message).map_err(|e| e.to_string())?;
+        self.stdin.flush().map_err(|e| e.to_string())
+    }
+
+    fn request(&mut self, method: &str, params: Value) -> Result<Value, String> {
+        self.next_id += 1;
+        let id = self.next_id;
+        self.send(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        }))?;
+        let deadline = Instant::now() + REQUEST_TIMEOUT;
+        loop {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            let line = match self.lines.recv_timeout(remaining) {
+                Ok(line) => line,
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(format!("{} timed out", method));
+                }
+                Err(RecvTimeoutError::Disconnected) => {
+                    return Err("MCP server exited".to_string());
+                }
+            };
+            let Ok(message) = serde_json::from_str::<Value>(&line) else {
+                continue;
+            };
+            if message.get("method").is_some() {
+                // Sampling, roots and other server requests are not supported.
+                if let Some(request_id) = message.get("id") {
+                    self.send(&serde_json::json!({
+                        "jsonrpc": "2.0",
+                        "id": request_id,
+                        "error": { "code": -32601, "message": "Method not found" },
+                    }))?;
+                }
+                continue;
+            }
+            if message.get("id").and_then(Value::as_u64) == Some(id) {
+                return response_result(message);
+            }
+        }
+    }
+}
+
+impl Drop for StdioConnection {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// The streamable HTTP transport: each message is a POST, answered with
+/// JSON or an event stream.
+struct HttpConnection {
+    url: String,
+    headers: HashMap<String, String>,
+    session_id: Option<String>,
+    next_id: u64,
+}
+
+impl HttpConnection {
+    fn request(&mut self, method: &str, params: Value) -> Result<Value, String> {
+        self.next_id += 1;
+        let id = self.next_id;
+        let body = self.post(&serde_json::json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "method": method,
+            "params": params,
+        }))?;
+        let messages: Vec<Value> = if body.trim_start().starts_with('{') {
+            serde_json::from_str(&body).into_iter().collect()
+        } else {
+            body.lines()
+                .filter_map(|line| line.strip_prefix("data:"))
+                .filter_map(|data| serde_json::from_str(data.trim()).ok())
+                .collect()
+        };
+        let response = messages
+            .into_iter()
+            .find(|message| message.get("id").and_then(Value::as_u64) == Some(id))
+            .ok_or_else(|| format!("{}: no response from MCP server", method))?;
+        response_result(response)
+    }
+
+    /// Runs on its own thread so it can block whether or not the caller is
+    /// inside the async runtime.
+    fn post(&mut self, message: &Value) -> Result<String, String> {
+        let url = self.url.clone();
+        let headers = self.headers.clone();
+        let session_id = self.session_id.clone();
+        let body = message.to_string();
+        let (session_id, body) = std::thread::spawn(move || {
+            let runtime = tokio::runtime::Builder::new_current_thread()
+                .enable_all()
+                .build()
+                .map_err(|e| e.to_string())?;
+            runtime.block_on(async move {
+                let client = reqwest::Client::builder()
+                    .timeout(REQUEST_TIMEOUT)
+                    .build()
+                    .map_err(|e| e.to_string())?;
+                let mut request = client
+                    .post(&url)
+                    .header("Content-Type", "application/json")
+                    .header("Accept", "application/json, text/event-stream")
+                    .body(body);
+                for (name, value) in &headers {
+                    request = request.header(name.as_str(), value.as_str());
+                }
+                if let Some(session_id) = &session_id {
+                    request = request.header("Mcp-Session-Id", session_id.as_str());
+                }
+                let response = request.send().await.map_err(|e| e.to_string())?;
+                let status = response.status();
+                let session_id = response
+                    .headers()
+                    .get("Mcp-Session-Id")
+                    .and_then(|value| value.to_str().ok())
+                    .map(str::to_string);
+                let body = response.text().await.map_err(|e| e.to_string())?;
+                if !status.is_success() {
+                    return Err(format!("HTTP {}: {}", status, truncate_chars(&body, 500)));
+                }
+                Ok((session_id, body))
+            })
+        })
+        .join()
+        .map_err(|_| "MCP request thread panicked".to_string())??;
+        if session_id.is_some() {
+            self.session_id = session_id;
+        }
+        Ok(body)
+    }
+}
+
+fn response_result(message: Value) -> Result<Value, String> {
+    if let Some(error) = message.get("error") {
+        return Err(format!(
+            "MCP error {}: {}",
+            error
+                .get("code")
+                .and_then(Value::as_i64)
+                .unwrap_or_default(),
+            error
+                .get("message")
+                .and_then(Value::as_str)
+                .unwrap_or("unknown error")
+        ));
+    }
+    Ok(message.get("result").cloned().unwrap_or(Value::Null))
+}
+
+/// Flattens a `tools/call` result to text; non-text content is named only.
+fn content_text(result: &Value) -> String {
+    let parts: Vec<String> = result
+        .get("content")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .map(|item| match item.get("type").and_then(Value::as_str) {
+            Some("text") => item
+                .get("text")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            Some("resource") => item
+                .pointer("/resource/text")
+                .or_else(|| item.pointer("/resource/uri"))
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string(),
+            Some(kind) => format!(
+                "[{} {}]",
+                kind,
+                item.get("mimeType")
+                    .and_then(Value::as_str)
+                    .unwrap_or_default()
+            ),
+            None => item.to_string(),
+        })
+        .collect();
+    if parts.is_empty() {
+        return result
+            .get("structuredContent")
+            .map(Value::to_string)
+            .unwrap_or_default();
+    }
+    parts.join("\n")
+}
+
+fn truncate_chars(text: &str, max_chars: usize) -> String {
+    match text.char_indices().nth(max_chars) {
+        Some((end, _)) => format!("{} [truncated]", &text[..end]),
+        None => text.to_string(),
+    }
+}
@@ -0,0 +1,438 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+/// Upper bounds, in seconds, of the latency histogram buckets.
+const BUCKETS: [f64; 11] = [0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0, 30.0, 60.0, 120.0];
+
+#[derive(Default)]
+struct Histogram {
+    counts: [u64; BUCKETS.len()],
+    sum: f64,
+    count: u64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        for (index, bound) in BUCKETS.iter().enumerate() {
+            if seconds <= *bound {
+                self.counts[index] += 1;
+            }
+        }
+        self.sum += seconds;
+        self.count += 1;
+    }
+}
+
+/// Process-wide counters for `/metrics`. Every kernel in the process reports
+/// here, so a serve-mode machine running several queued runs is one target.
+#[derive(Default)]
+struct Registry {
+    runs_started: u64,
+    runs_finished: BTreeMap<String, u64>,
+    tool_calls: BTreeMap<(String, bool), u64>,
+    tool_durations: BTreeMap<String, Histogram>,
+    tool_started: HashMap<String, Instant>,
+    llm_requests: BTreeMap<(String, bool), u64>,
+    llm_latency: BTreeMap<String, Histogram>,
+    llm_tokens: BTreeMap<(String, &'static str), u64>,
+    model_decisions: BTreeMap<String, ModelDecisions>,
+    /// Model that proposed each action still in flight, by action id.
+    action_models: HashMap<String, String>,
+    model_actions: BTreeMap<(String, String, bool), u64>,
+    pty_sessions: i64,
+}
+
+#[derive(Default)]
+struct ModelDecisions {
+    proposed: u64,
+    invalid: u64,
+    parse_errors: u64,
+}
+
+/// How one model's decisions fared, for tuning prompts and schemas.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ModelActionStats {
+    pub model: String,
+    /// Decisions that proposed actions.
+    pub decisions: u64,
+    /// Responses that failed validation, including ones fixed by the retry.
+    pub invalid_decisions: u64,
+    pub parse_errors: u64,
+    pub actions: Vec<ActionOutcomes>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionOutcomes {
+    pub action: String,
+    pub ok: u64,
+    pub failed: u64,
+}
+
+static METRICS: OnceLock<Mutex<Registry>> = OnceLock::new();
+
+fn with_registry<F: FnOnce(&mut Registry)>(update: F) {
+    let registry = METRICS.get_or_init(|| Mutex::new(Registry::default()));
+    if let Ok(mut registry) = registry.lock() {
+        update(&mut registry);
+    }
+}
+
+pub fn record_run_started() {
+    with_registry(|registry| registry.runs_started += 1);
+}
+
+pub fn record_run_finished(outcome: &str) {
+    with_registry(|registry| {
+        *registry
+            .runs_finished
+            .entry(outcome.to_string())
+            .or_default() += 1;
+    });
+}
+
+pub fn record_llm_request(provider: &str, elapsed: Duration, ok: bool) {
+    let provider = provider.to_lowercase();
+    with_registry(|registry| {
+        *registry
+            .llm_requests
+            .entry((provider.clone(), ok))
+            .or_default() += 1;
+        registry
+            .llm_latency
+            .entry(provider)
+            .or_default()
+            .observe(elapsed.as_secs_f64());
+    });
+}
+
+/// Reads the `usage` block of an OpenAI-compatible, Responses API or
+/// Anthropic reply, or the `usageMetadata` of a Gemini one; replies without
+/// either are ignored. Returns the prompt and completion tokens counted.
+pub fn record_token_usage(provider: &str, response: &serde_json::Value) -> (u64, u64) {
+    let Some(usage) = response
+        .get("usage")
+        .or_else(|| response.get("usageMetadata"))
+        .filter(|usage| usage.is_object())
+    else {
+        return (0, 0);
+    };
+    let count = |keys: [&str; 3]| {
+        keys.iter()
+            .find_map(|key| usage.get(*key).and_then(|value| value.as_u64()))
+            .unwrap_or(0)
+    };
+    let prompt = count(["prompt_tokens", "input_tokens", "promptTokenCount"]);
+    let completion = count(["completion_tokens", "output_tokens", "candidatesTokenCount"]);
+    let provider = provider.to_lowercase();
+    with_registry(|registry| {
+        *registry
+            .llm_tokens
+            .entry((provider.clone(), "prompt"))
+            .or_default() += prompt;
+        *registry
+            .llm_tokens
+            .entry((provider, "completion"))
+            .or_default() += completion;
+    });
+    (prompt, completion)
+}
+
+pub fn record_pty_session_opened() {
+    with_registry(|registry| registry.pty_sessions += 1);
+}
+
+pub fn record_pty_session_closed() {
+    with_registry(|registry| registry.pty_sessions -= 1);
+}
+
+/// Times tool calls from the kernel's `ToolCallStarted`/`ToolCallFinished`
+/// events, matched by action id, and attributes their outcome to the model
+/// named in `AgentActionProposed`.
+pub fn observe_kernel_event(event_type: &str, payload: &serde_json::Value) {
+    match event_type {
+        "AgentActionProposed" => {
+            let (Some(model), Some(actions)) =
+                (payload["model"].as_str(), payload["actions"].as_array())
+            else {
+                return;
+            };
+            with_registry(|registry| {
+                registry
+                    .model_decisions
+                    .entry(model.to_string())
+                    .or_default()
+                    .proposed += 1;
+                for id in actions.iter().filter_map(|action| action["id"].as_str()) {
+                    registry
+                        .action_models
+                        .insert(id.to_string(), model.to_string());
+                }
+            });
+            return;
+        }
+        "DecisionInvalid" => {
+            let Some(model) = payload["model"].as_str() else {
+                return;
+            };
+            let errors = payload["errors"]
+                .as_array()
+                .map_or(0, |errors| errors.len());
+            with_registry(|registry| {
+                let decisions = registry
+                    .model_decisions
+                    .entry(model.to_string())
+                    .or_default();
+                decisions.invalid += 1;
+                decisions.parse_errors += errors as u64;
+            });
+            return;
+        }
+        _ => {}
+    }
+    let action = &payload["action"];
+    let Some(id) = action["id"].as_str() else {
+        return;
+    };
+    match event_type {
+        "ToolCallStarted" => with_registry(|registry| {
+            registry.tool_started.insert(id.to_string(), Instant::now());
+        }),
+        "ToolCallFinished" => {
+            let tool = action["type"].as_str().unwrap_or("unknown").to_string();
+            let ok = payload["ok"].as_bool().unwrap_or(false);
+            with_registry(|registry| {
+                *registry.tool_calls.entry((tool.clone(), ok)).or_default() += 1;
+                if let Some(model) = registry.action_models.remove(id) {
+                    *registry
+                        .model_actions
+                        .entry((model, tool.clone(), ok))
+                        .or_default() += 1;
+                }
+                if let Some(started) = registry.tool_started.remove(id) {
+                    registry
+                        .tool_durations
+                        .entry(tool)
+                        .or_default()
+                        .observe(started.elapsed().as_secs_f64());
+                }
+            });
+        }
+        _ => {}
+    }
+}
+
+pub fn model_action_stats() -> Vec<ModelActionStats> {
+    let mut stats: BTreeMap<String, ModelActionStats> = BTreeMap::new();
+    with_registry(|registry| {
+        for (model, decisions) in &registry.model_decisions {
+            let entry = model_stats(&mut stats, model);
+            entry.decisions = decisions.proposed;
+            entry.invalid_decisions = decisions.invalid;
+            entry.parse_errors = decisions.parse_errors;
+        }
+        for ((model, action, ok), count) in &registry.model_actions {
+            let actions = &mut model_stats(&mut stats, model).actions;
+            if actions.last().is_none_or(|last| &last.action != action) {
+                actions.push(ActionOutcomes {
+                    action: action.clone(),
+                    ok: 0,
+                    failed: 0,
+                });
+            }
+            if let Some(outcomes) = actions.last_mut() {
+                if *ok {
+                    outcomes.ok += count;
+                } else {
+                    outcomes.failed += count;
+                }
+            }
+        }
+    });
+    stats.into_values().collect()
+}
+
+fn model_stats<'a>(
+    stats: &'a mut BTreeMap<String, ModelActionStats>,
+    model: &str,
+) -> &'a mut ModelActionStats {
+    stats
+        .entry(model.to_string())
+        .or_insert_with(|| ModelActionStats {
+            model: model.to_string(),
+            decisions: 0,
+            invalid_decisions: 0,
+            parse_errors: 0,
+            actions: Vec::new(),
+        })
+}
+
+/// Prometheus text exposition format, version 0.0.4.
+pub fn render() -> String {
+    let mut out = String::new();
+    with_registry(|registry| {
+        header(
+            &mut out,
+            "taurihands_runs_started_total",
+            "counter",
+            "Agent runs started.",
+        );
+        let _ = writeln!(
+            out,
+            "taurihands_runs_started_total {}",
+            registry.runs_started
+        );
+
+        header(
+            &mut out,
+            "taurihands_runs_finished_total",
+            "counter",
+            "Agent runs that ended, by outcome.",
+        );
+        for (outcome, count) in &registry.runs_finished {
+            let labels = format_labels(&[("outcome", outcome)]);
+            let _ = writeln!(out, "taurihands_runs_finished_total{} {}", labels, count);
+        }
+
+        header(
+            &mut out,
+            "taurihands_tool_calls_total",
+            "counter",
+            "Tool calls, by tool and result.",
+        );
+        for ((tool, ok), count) in &registry.tool_calls {
+            let labels = format_labels(&[("tool", tool), ("ok", &ok.to_string())]);
+            let _ = writeln!(out, "taurihands_tool_calls_total{} {}", labels, count);
+        }
+
+        header(
+            &mut out,
+            "taurihands_tool_call_duration_seconds",
+            "histogram",
+            "Tool call duration, by tool.",
+        );
+        for (tool, histogram) in &registry.tool_durations {
+            write_histogram(
+                &mut out,
+                "taurihands_tool_call_duration_seconds",
+                ("tool", tool),
+                histogram,
+            );
+        }
+
+        header(
+            &mut out,
+            "taurihands_llm_requests_total",
+            "counter",
+            "LLM requests, by provider and result.",
+        );
+        for ((provider, ok), count) in &registry.llm_requests {
+            let labels = format_labels(&[("provider", provider), ("ok", &ok.to_string())]);
+            let _ = writeln!(out, "taurihands_llm_requests_total{} {}", labels, count);
+        }
+
+        header(
+            &mut out,
+            "taurihands_llm_request_duration_seconds",
+            "histogram",
+            "LLM request latency, by provider.",
+        );
+        for (provider, histogram) in &registry.llm_latency {
+            write_histogram(
+                &mut out,
+                "taurihands_llm_request_duration_seconds",
+                ("provider", provider),
+                histogram,
+            );
+        }
+
+        header(
+            &mut out,
+            "taurihands_llm_tokens_total",
+            "counter",
+            "Tokens reported by LLM providers, by provider and kind.",
+        );
+        for ((provider, kind), count) in &registry.llm_tokens {
+            let labels = format_labels(&[("provider", provider), ("kind", kind)]);
+            let _ = writeln!(out, "taurihands_llm_tokens_total{} {}", labels, count);
+        }
+
+        header(
+            &mut out,
+            "taurihands_llm_actions_total",
+            "counter",
+            "Actions proposed by a model that finished, by model, action and result.",
+        );
+        for ((model, action, ok), count) in &registry.model_actions {
+            let labels = format_labels(&[
+                ("model", model),
+                ("action", action),
+                ("ok", &ok.to_string()),
+            ]);
+            let _ = writeln!(out, "taurihands_llm_actions_total{} {}", labels, count);
+        }
+
+        header(
+            &mut out,
+            "taurihands_llm_parse_errors_total",
+            "counter",
+            "Validation errors in LLM decisions, by model.",
+        );
+        for (model, decisions) in &registry.model_decisions {
+            let labels = format_labels(&[("model", model)]);
+            let _ = writeln!(
+                out,
+                "taurihands_llm_parse_errors_total{} {}",
+                labels, decisions.parse_errors
+            );
+        }
+
+        header(
+            &mut out,
+            "taurihands_pty_sessions",
+            "gauge",
+            "Open terminal sessions.",
+        );
+        let _ = writeln!(
+            out,
+            "taurihands_pty_sessions {}",
+            registry.pty_sessions.max(0)
+        );
+    });
+    out
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+fn write_histogram(out: &mut String, name: &str, label: (&str, &str), histogram: &Histogram) {
+    for (bound, count) in BUCKETS.iter().zip(histogram.counts.iter()) {
+        let labels = format_labels(&[label, ("le", &bound.to_string())]);
+        let _ = writeln!(out, "{}_bucket{} {}", name, labels, count);
+    }
+    let labels_inf = format_labels(&[label, ("le", "+Inf")]);
+    let _ = writeln!(out, "{}_bucket{} {}", name, labels_inf, histogram.count);
+    let labels = format_labels(&[label]);
+    let _ = writeln!(out, "{}_sum{} {}", name, labels, histogram.sum);
+    let _ = writeln!(out, "{}_count{} {}", name, labels, histogram.count);
+}
+
+fn format_labels(pairs: &[(&str, &str)]) -> String {
+    let rendered: Vec<String> = pairs
+        .iter()
+        .map(|(name, value)| {
+            let value = value
+                .replace('\\', "\\\\")
+                .replace('"', "\\\"")
+                .replace('\n', "\\n");
+            format!("{}=\"{}\"", name, value)
+        })
+        .collect();
+    format!("{{{}}}", rendered.join(","))
+}
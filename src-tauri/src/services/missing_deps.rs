@@ -0,0 +1,178 @@
+use serde::Serialize;
+use std::path::Path;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct MissingDependency {
+    pub ecosystem: String,
+    pub name: String,
+    pub program: String,
+    pub args: Vec<String>,
+}
+
+impl MissingDependency {
+    fn new(ecosystem: &str, name: &str, program: &str, args: &[&str]) -> Self {
+        let mut all_args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        all_args.push(name.to_string());
+        Self {
+            ecosystem: ecosystem.to_string(),
+            name: name.to_string(),
+            program: program.to_string(),
+            args: all_args,
+        }
+    }
+
+    pub fn install_command(&self) -> String {
+        let mut parts = vec![self.program.clone()];
+        parts.extend(self.args.iter().cloned());
+        parts.join(" ")
+    }
+}
+
+/// Classifies "module not found" style failures from node, python and go, and
+/// shell "command not found" errors for a few well known tools, and returns the
+/// install command for the missing package. Rust import errors are left alone:
+/// an undeclared module is far more often a typo than a missing crate.
+pub fn detect_missing_dependency(root: &Path, output: &str) -> Option<MissingDependency> {
+    for line in output.lines() {
+        let line = line.trim();
+        if let Some(name) = node_missing_module(line) {
+            let (program, args) = node_installer(root);
+            return Some(MissingDependency::new("npm", &name, program, args));
+        }
+        if let Some(name) = python_missing_module(line) {
+            return Some(MissingDependency::new(
+                "pip",
+                &name,
+                "python",
+                &["-m", "pip", "install"],
+            ));
+        }
+        if let Some(dependency) = missing_tool(root, line) {
+            return Some(dependency);
+        }
+        if let Some(name) = go_missing_package(line) {
+            return Some(MissingDependency::new("go", &name, "go", &["get"]));
+        }
+    }
+    None
+}
+
+fn node_installer(root: &Path) -> (&'static str, &'static [&'static str]) {
+    if root.join("pnpm-lock.yaml").is_file() {
+        ("pnpm", &["add"])
+    } else if root.join("yarn.lock").is_file() {
+        ("yarn", &["add"])
+    } else {
+        ("npm", &["install"])
+    }
+}
+
+fn node_missing_module(line: &str) -> Option<String> {
+    let spec = quoted_after(line, "Cannot find module ")
+        .or_else(|| quoted_after(line, "Cannot find package "))
+        .or_else(|| quoted_after(line, "Can't resolve "))?;
+    // Relative imports and absolute paths point at missing files, not packages.
+    if spec.starts_with('.') || spec.starts_with('/') || spec.starts_with("node:") {
+        return None;
+    }
+    let mut segments = spec.split('/');
+    let first = segments.next()?;
+    let name = if first.starts_with('@') {
+        format!("{}/{}", first, segments.next()?)
+    } else {
+        first.to_string()
+    };
+    is_package_name(&name).then_some(name)
+}
+
+fn python_missing_module(line: &str) -> Option<String> {
+    let module = quoted_after(line, "No module named ")?;
+    let top = module.split('.').next()?;
+    // Import names that differ from the name published on PyPI.
+    let package = match top {
+        "yaml" => "pyyaml",
+        "cv2" => "opencv-python",
+        "PIL" => "pillow",
+        "sklearn" => "scikit-learn",
+        "bs4" => "beautifulsoup4",
+        "dotenv" => "python-dotenv",
+        other => other,
+    };
+    is_package_name(package).then(|| package.to_string())
+}
+
+/// Command line tools with a well known package, as (binary, ecosystem,
+/// package). Unknown commands are never guessed at.
+const KNOWN_TOOLS: &[(&str, &str, &str)] = &[
+    ("tsc", "npm", "typescript"),
+    ("eslint", "npm", "eslint"),
+    ("prettier", "npm", "prettier"),
+    ("jest", "npm", "jest"),
+    ("vitest", "npm", "vitest"),
+    ("pytest", "pip", "pytest"),
+    ("black", "pip", "black"),
+    ("ruff", "pip", "ruff"),
+    ("mypy", "pip", "mypy"),
+    ("cargo-nextest", "cargo", "cargo-nextest"),
+    ("cargo-audit", "cargo", "cargo-audit"),
+    ("wasm-pack", "cargo", "wasm-pack"),
+];
+
+fn missing_tool(root: &Path, line: &str) -> Option<MissingDependency> {
+    let command = missing_command(line)?;
+    let (_, ecosystem, package) = KNOWN_TOOLS
+        .iter()
+        .find(|(binary, _, _)| *binary == command)?;
+    let (program, args): (&str, &[&str]) = match *ecosystem {
+        "npm" => node_installer(root),
+        "pip" => ("python", &["-m", "pip", "install"]),
+        _ => ("cargo", &["install"]),
+    };
+    Some(MissingDependency::new(ecosystem, package, program, args))
+}
+
+/// Name of the command a shell could not find, from bash, sh, zsh and cmd.
+fn missing_command(line: &str) -> Option<String> {
+    if let Some(rest) = line.split("command not found: ").nth(1) {
+        return rest.split_whitespace().next().map(str::to_string);
+    }
+    if let Some(head) = line
+        .strip_suffix(": command not found")
+        .or_else(|| line.strip_suffix(": not found"))
+    {
+        return head.rsplit(": ").next().map(|name| name.trim().to_string());
+    }
+    let head = line
+        .split(" is not recognized as an internal or external command")
+        .next()?;
+    if head.len() == line.len() {
+        return None;
+    }
+    let name = head.trim().trim_matches(['\'', '"']);
+    (!name.is_empty()).then(|| name.to_string())
+}
+
+fn go_missing_package(line: &str) -> Option<String> {
+    let rest = line.split("no required module provides package ").nth(1)?;
+    let package = rest.split([';', ' ', ':']).next()?.trim();
+    // Standard library packages have no dot in their first path segment.
+    package
+        .split('/')
+        .next()
+        .filter(|host| host.contains('.'))
+        .map(|_| package.to_string())
+}
+
+fn quoted_after(line: &str, marker: &str) -> Option<String> {
+    let rest = line.split(marker).nth(1)?.trim_start();
+    let quote = rest.chars().next().filter(|ch| *ch == '\'' || *ch == '"')?;
+    let value = rest[1..].split(quote).next()?;
+    (!value.is_empty()).then(|| value.to_string())
+}
+
+fn is_package_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|ch| ch.is_ascii_alphanumeric() || "@/_-.".contains(ch))
+}
@@ -1,8 +1,65 @@
-pub mod pty;
-pub mod llm;
-pub mod kernel;
 pub mod agent;
-pub mod workspace;
+pub mod app_data;
+pub mod artifact_upload;
+pub mod at_rest;
 pub mod audit;
+pub mod backup;
+pub mod bug_report;
+pub mod changed_files;
 pub mod codex;
+pub mod command_policy;
+pub mod command_translation;
+pub mod context_budget;
+pub mod coverage;
+pub mod deps_audit;
+pub mod diagnostics;
+pub mod diff;
+pub mod doctor;
+pub mod drafts;
+pub mod event_history;
+pub mod events;
+pub mod freshness;
+pub mod fuzzy_path;
+pub mod git_ops;
+#[cfg(feature = "test-support")]
+pub mod harness;
+pub mod history;
+pub mod hooks;
+pub mod ignore;
+pub mod kernel;
+pub mod llm;
+pub mod llm_mock;
+pub mod llm_tools;
+pub mod llm_usage;
+pub mod logging;
+pub mod mcp;
+pub mod metrics;
+pub mod missing_deps;
+pub mod narration;
+pub mod observation;
+pub mod patch;
+pub mod preflight;
+pub mod process;
+pub mod profiles;
+pub mod provenance;
+pub mod pty;
+pub mod pty_output;
+pub mod rate_limit;
+pub mod regression;
+pub mod report;
+pub mod resources;
+pub mod run_search;
+pub mod scratch;
+pub mod secret_store;
+pub mod secrets;
+pub mod shutdown;
+pub mod storage_gc;
+pub mod todos;
 pub mod tools;
+pub mod transcript;
+pub mod trust;
+pub mod verify;
+pub mod visibility;
+pub mod workspace;
+pub mod workspace_watch;
+pub mod write_preview;
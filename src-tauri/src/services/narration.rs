@@ -0,0 +1,179 @@
+use serde_json::Value;
+
+pub const NARRATION_EVENT_NAME: &str = "narration";
+const MAX_NARRATION_CHARS: usize = 160;
+const SOURCE_EXTENSIONS: &[&str] = &[
+    "rs", "ts", "tsx", "js", "jsx", "vue", "py", "go", "java", "kt", "c", "cc", "cpp", "h", "hpp",
+    "cs", "rb", "php", "swift",
+];
+
+/// Turns a kernel event into one short sentence for screen readers and
+/// status lines, e.g. "Ran cargo test: 3 failures in parser.rs". Events that
+/// are too chatty or already readable (streamed chunks, chat text) return None.
+pub fn narrate(event_type: &str, payload: &Value) -> Option<String> {
+    let text = match event_type {
+        "ToolCallStarted" => {
+            let action = payload.get("action")?;
+            match action_kind(action) {
+                "terminal.exec" | "terminal.run" | "tests.run" => {
+                    format!("Running {}", command_line(action))
+                }
+                _ => return None,
+            }
+        }
+        "ToolCallFinished" => narrate_tool_result(payload)?,
+        "StateChanged" => narrate_state(payload.get("reason")?.as_str()?)?.to_string(),
+        "PlanUpdated" => {
+            let steps = payload.pointer("/plan/steps")?.as_array()?.len();
+            format!("Plan updated: {} step(s)", steps)
+        }
+        "ChangeProposed" => format!(
+            "Proposed a change instead of running it: {}",
+            str_at(payload, "/change/reason").unwrap_or("mutating action")
+        ),
+        "ChangesApplied" => format!(
+            "Applied {} pending change(s)",
+            payload.get("ids")?.as_array()?.len()
+        ),
+        "MissingDependency" => format!(
+            "Missing dependency {}; suggested install: {}",
+            str_at(payload, "/dependency/name")?,
+            str_at(payload, "/command").unwrap_or("unknown")
+        ),
+        "Regression" => "A previously passing check now fails".to_string(),
+        "SecretsDetected" => "Possible secrets found in command output".to_string(),
+        "RateLimited" => str_at(payload, "/message")?.to_string(),
+        "ProviderFailover" => "Switched to a fallback LLM provider".to_string(),
+        "RunInterrupted" => "Run interrupted".to_string(),
+        "Error" => format!("Error: {}", first_line(str_at(payload, "/message")?)),
+        _ => return None,
+    };
+    Some(truncate(&text))
+}
+
+fn narrate_tool_result(payload: &Value) -> Option<String> {
+    let action = payload.get("action")?;
+    let ok = payload.get("ok").and_then(Value::as_bool).unwrap_or(false);
+    let summary = str_at(payload, "/summary").unwrap_or_default();
+    let done = match action_kind(action) {
+        "terminal.exec" | "terminal.run" | "tests.run" => format!("Ran {}", command_line(action)),
+        "fs.read" => format!("Read {}", str_at(action, "/path")?),
+        "fs.read_many" => format!("Read {} file(s)", action.get("paths")?.as_array()?.len()),
+        "fs.write" => format!("Wrote {}", str_at(action, "/path")?),
+        "fs.apply_patch" => format!("Patched {}", str_at(action, "/path")?),
+        "fs.search" => format!("Searched for {}", str_at(action, "/pattern")?),
+        "git.status" => "Checked git status".to_string(),
+        "git.diff" => "Viewed the git diff".to_string(),
+        "deps.audit" => "Audited dependencies".to_string(),
+        "code.todos" => "Listed TODO comments".to_string(),
+        "mcp.call" => format!(
+            "Called MCP tool {}/{}",
+            str_at(action, "/server")?,
+            str_at(action, "/tool")?
+        ),
+        other => format!("Finished {}", other),
+    };
+    if ok {
+        return Some(done);
+    }
+    let detail = failure_detail(summary).unwrap_or_else(|| {
+        match payload.get("exit_code").and_then(Value::as_i64) {
+            Some(code) => format!("failed with exit code {}", code),
+            None => format!("failed: {}", first_line(summary)),
+        }
+    });
+    Some(format!("{}: {}", done, detail))
+}
+
+fn narrate_state(reason: &str) -> Option<&'static str> {
+    let text = match reason {
+        "start" => "Run started",
+        "pause" => "Run paused",
+        "resume" | "continue" => "Run resumed",
+        "stop" | "user_stop" => "Run stopped",
+        "awaiting_user" => "Waiting for your reply",
+        "finished" | "steps_complete" => "Run finished",
+        "step_budget" => "Step budget reached",
+        "agent_error" | "runtime_error" => "Run stopped with an error",
+        "judge_pass" => "Task checks passed",
+        "judge_fail" => "Task checks failed",
+        _ => return None,
+    };
+    Some(text)
+}
+
+/// Counts failed tests from common runner summaries ("2 failed", "3 failures")
+/// and names the first source file mentioned in the output.
+fn failure_detail(summary: &str) -> Option<String> {
+    let words: Vec<&str> = summary.split_whitespace().collect();
+    let failures = words.windows(2).find_map(|pair| {
+        let label = pair[1].trim_matches(|ch: char| !ch.is_ascii_alphabetic());
+        if !matches!(label, "failed" | "failures" | "failure" | "failing") {
+            return None;
+        }
+        pair[0]
+            .trim_matches(|ch: char| !ch.is_ascii_digit())
+            .parse::<u32>()
+            .ok()
+            .filter(|count| *count > 0)
+    })?;
+    let file = words.iter().find_map(|word| source_file_name(word));
+    Some(match file {
+        Some(file) => format!("{} failure(s) in {}", failures, file),
+        None => format!("{} failure(s)", failures),
+    })
+}
+
+fn source_file_name(word: &str) -> Option<String> {
+    let path = word
+        .trim_matches(|ch: char| matches!(ch, '(' | ')' | '[' | ']' | '\'' | '"' | ',' | '>'))
+        .split(':')
+        .next()?;
+    let name = path.rsplit(['/', '\\']).next()?;
+    let (stem, extension) = name.rsplit_once('.')?;
+    if stem.is_empty() || !SOURCE_EXTENSIONS.contains(&extension) {
+        return None;
+    }
+    Some(name.to_string())
+}
+
+fn action_kind(action: &Value) -> &str {
+    str_at(action, "/type").unwrap_or("action")
+}
+
+fn command_line(action: &Value) -> String {
+    if let Some(cmd) = str_at(action, "/cmd") {
+        return cmd.to_string();
+    }
+    let program = str_at(action, "/program").unwrap_or("command");
+    let args: Vec<&str> = action
+        .get("args")
+        .and_then(Value::as_array)
+        .map(|args| args.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    if args.is_empty() {
+        program.to_string()
+    } else {
+        format!("{} {}", program, args.join(" "))
+    }
+}
+
+fn str_at<'a>(value: &'a Value, pointer: &str) -> Option<&'a str> {
+    value.pointer(pointer).and_then(Value::as_str)
+}
+
+fn first_line(text: &str) -> &str {
+    text.lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or("no details")
+}
+
+fn truncate(text: &str) -> String {
+    if text.chars().count() <= MAX_NARRATION_CHARS {
+        return text.to_string();
+    }
+    let mut short: String = text.chars().take(MAX_NARRATION_CHARS - 3).collect();
+    short.push_str("...");
+    short
+}
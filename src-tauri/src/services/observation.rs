@@ -0,0 +1,302 @@
+use crate::services::kernel::{merge_artifacts, Observation};
+
+const DEFAULT_SUMMARY_BYTES: usize = 2000;
+const CONTEXT_LINES: usize = 4;
+
+pub trait ObservationSummarizer: Send + Sync {
+    fn name(&self) -> &'static str;
+    fn applies_to(&self, action_type: &str) -> bool;
+    fn summarize(&self, output: &str) -> Option<String>;
+}
+
+pub struct SummaryPipeline {
+    summarizers: Vec<Box<dyn ObservationSummarizer>>,
+    max_bytes: usize,
+}
+
+impl SummaryPipeline {
+    pub fn new(max_bytes: usize) -> Self {
+        Self {
+            summarizers: Vec::new(),
+            max_bytes,
+        }
+    }
+
+    pub fn with_defaults() -> Self {
+        let mut pipeline = Self::new(DEFAULT_SUMMARY_BYTES);
+        pipeline.register(Box::new(CompilerErrorSummarizer));
+        pipeline.register(Box::new(TestFailureSummarizer));
+        pipeline.register(Box::new(StackTraceSummarizer));
+        pipeline
+    }
+
+    pub fn register(&mut self, summarizer: Box<dyn ObservationSummarizer>) {
+        self.summarizers.push(summarizer);
+    }
+
+    pub fn summarize(&self, action_type: &str, output: &str) -> (String, Option<&'static str>) {
+        if output.len() <= self.max_bytes {
+            return (output.to_string(), None);
+        }
+        for summarizer in &self.summarizers {
+            if !summarizer.applies_to(action_type) {
+                continue;
+            }
+            if let Some(summary) = summarizer.summarize(output) {
+                if !summary.trim().is_empty() {
+                    return (
+                        clip_head_tail(&summary, self.max_bytes),
+                        Some(summarizer.name()),
+                    );
+                }
+            }
+        }
+        (clip_head_tail(output, self.max_bytes), None)
+    }
+
+    pub fn apply(&self, action_type: &str, observation: &mut Observation) {
+        // File reads return bounded chunks with their own continuation markers.
+        if matches!(action_type, "fs.read" | "fs.read_many") {
+            return;
+        }
+        if is_command_action(action_type) {
+            observation.summary = collapse_progress(&observation.summary);
+        }
+        if observation.summary.len() <= self.max_bytes {
+            return;
+        }
+        let (summary, summarizer) = self.summarize(action_type, &observation.summary);
+        let full_output = std::mem::replace(&mut observation.summary, summary);
+        merge_artifacts(observation, "full_output", serde_json::json!(full_output));
+        merge_artifacts(
+            observation,
+            "summarizer",
+            serde_json::json!(summarizer.unwrap_or("head_tail")),
+        );
+    }
+}
+
+impl Default for SummaryPipeline {
+    fn default() -> Self {
+        Self::with_defaults()
+    }
+}
+
+fn is_command_action(action_type: &str) -> bool {
+    matches!(action_type, "terminal.exec" | "terminal.run" | "tests.run")
+}
+
+pub struct CompilerErrorSummarizer;
+
+impl ObservationSummarizer for CompilerErrorSummarizer {
+    fn name(&self) -> &'static str {
+        "compiler_errors"
+    }
+
+    fn applies_to(&self, action_type: &str) -> bool {
+        is_command_action(action_type)
+    }
+
+    fn summarize(&self, output: &str) -> Option<String> {
+        collect_blocks(output, CONTEXT_LINES, |line| {
+            let trimmed = line.trim_start();
+            let lower = trimmed.to_lowercase();
+            trimmed.starts_with("error[")
+                || trimmed.starts_with("error:")
+                || lower.contains(": error ts")
+                || lower.contains(": error:")
+                || lower.contains("syntaxerror")
+                || is_eslint_error_line(trimmed)
+        })
+    }
+}
+
+pub struct TestFailureSummarizer;
+
+impl ObservationSummarizer for TestFailureSummarizer {
+    fn name(&self) -> &'static str {
+        "test_failures"
+    }
+
+    fn applies_to(&self, action_type: &str) -> bool {
+        is_command_action(action_type)
+    }
+
+    fn summarize(&self, output: &str) -> Option<String> {
+        collect_blocks(output, CONTEXT_LINES, |line| {
+            let trimmed = line.trim_start();
+            trimmed.contains("FAILED")
+                || trimmed.starts_with("FAIL ")
+                || trimmed.starts_with("failures:")
+                || trimmed.starts_with("test result:")
+                || trimmed.starts_with("Tests:")
+                || trimmed.starts_with("AssertionError")
+                || trimmed.starts_with("assertion `left == right` failed")
+                || trimmed.starts_with('✕')
+                || trimmed.starts_with("E   ")
+        })
+    }
+}
+
+pub struct StackTraceSummarizer;
+
+impl ObservationSummarizer for StackTraceSummarizer {
+    fn name(&self) -> &'static str {
+        "stack_trace"
+    }
+
+    fn applies_to(&self, action_type: &str) -> bool {
+        is_command_action(action_type)
+    }
+
+    fn summarize(&self, output: &str) -> Option<String> {
+        let lines: Vec<&str> = output.lines().collect();
+        if let Some(start) = lines
+            .iter()
+            .position(|line| line.starts_with("Traceback (most recent call last)"))
+        {
+            let end = lines
+                .iter()
+                .enumerate()
+                .skip(start + 1)
+                .find(|(_, line)| !line.is_empty() && !line.starts_with(' '))
+                .map(|(index, _)| index + 1)
+                .unwrap_or(lines.len());
+            return Some(lines[start..end].join("\n"));
+        }
+        if let Some(start) = lines.iter().position(|line| line.contains("panicked at")) {
+            let end = (start + CONTEXT_LINES).min(lines.len());
+            return Some(lines[start..end].join("\n"));
+        }
+        let first_frame = lines
+            .iter()
+            .position(|line| line.trim_start().starts_with("at ") && line.starts_with(' '))?;
+        let start = first_frame.saturating_sub(2);
+        let end = (first_frame + 6).min(lines.len());
+        Some(lines[start..end].join("\n"))
+    }
+}
+
+/// Resolves carriage-return overwrites and folds runs of repeated or progress-style lines.
+pub fn collapse_progress(output: &str) -> String {
+    let mut lines: Vec<&str> = Vec::new();
+    for line in output.split('\n') {
+        let line = line.strip_suffix('\r').unwrap_or(line);
+        lines.push(line.rsplit('\r').next().unwrap_or(line));
+    }
+    let mut collapsed: Vec<String> = Vec::with_capacity(lines.len());
+    let mut index = 0;
+    while index < lines.len() {
+        let line = lines[index];
+        let shape = progress_shape(line);
+        let mut end = index + 1;
+        while end < lines.len() {
+            let next = lines[end];
+            let same = next == line || (shape.is_some() && progress_shape(next) == shape);
+            if !same {
+                break;
+            }
+            end += 1;
+        }
+        let run = end - index;
+        if run < 3 {
+            collapsed.extend(lines[index..end].iter().map(|line| line.to_string()));
+        } else if lines[index..end].iter().all(|next| *next == line) {
+            collapsed.push(line.to_string());
+            collapsed.push(format!("[previous line repeated {} more times]", run - 1));
+        } else {
+            collapsed.push(format!("[{} progress lines collapsed]", run - 1));
+            collapsed.push(lines[end - 1].to_string());
+        }
+        index = end;
+    }
+    collapsed.join("\n")
+}
+
+fn progress_shape(line: &str) -> Option<String> {
+    let trimmed = line.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    let has_digits = trimmed.chars().any(|ch| ch.is_ascii_digit());
+    let looks_like_progress = trimmed.contains('%')
+        || trimmed.contains("━")
+        || trimmed.contains("█")
+        || trimmed.contains("=>")
+        || trimmed.contains("##")
+        || trimmed.contains("[=")
+        || (has_digits && trimmed.contains('/'));
+    if !has_digits || !looks_like_progress {
+        return None;
+    }
+    let mut shape = String::with_capacity(trimmed.len());
+    for ch in trimmed.chars() {
+        if ch.is_ascii_digit() {
+            if !shape.ends_with('#') {
+                shape.push('#');
+            }
+        } else if matches!(ch, '=' | '>' | '-' | '━' | '█' | '░' | '▏' | ' ' | '.') {
+            if !shape.ends_with('~') {
+                shape.push('~');
+            }
+        } else {
+            shape.push(ch);
+        }
+    }
+    Some(shape)
+}
+
+fn is_eslint_error_line(line: &str) -> bool {
+    let mut parts = line.split_whitespace();
+    let position = parts.next().unwrap_or_default();
+    let level = parts.next().unwrap_or_default();
+    level == "error"
+        && position.contains(':')
+        && position
+            .split(':')
+            .all(|part| !part.is_empty() && part.chars().all(|ch| ch.is_ascii_digit()))
+}
+
+fn collect_blocks<F>(output: &str, context: usize, is_match: F) -> Option<String>
+where
+    F: Fn(&str) -> bool,
+{
+    let lines: Vec<&str> = output.lines().collect();
+    let mut blocks: Vec<String> = Vec::new();
+    let mut covered_until = 0usize;
+    for (index, line) in lines.iter().enumerate() {
+        if index < covered_until || !is_match(line) {
+            continue;
+        }
+        let end = (index + 1 + context).min(lines.len());
+        blocks.push(lines[index..end].join("\n"));
+        covered_until = end;
+    }
+    if blocks.is_empty() {
+        None
+    } else {
+        Some(blocks.join("\n"))
+    }
+}
+
+fn clip_head_tail(value: &str, max_bytes: usize) -> String {
+    if value.len() <= max_bytes {
+        return value.to_string();
+    }
+    let head_budget = max_bytes / 4;
+    let tail_budget = max_bytes.saturating_sub(head_budget);
+    let mut head_end = head_budget;
+    while head_end > 0 && !value.is_char_boundary(head_end) {
+        head_end -= 1;
+    }
+    let mut tail_start = value.len().saturating_sub(tail_budget);
+    while tail_start < value.len() && !value.is_char_boundary(tail_start) {
+        tail_start += 1;
+    }
+    format!(
+        "{}\n... [{} bytes omitted] ...\n{}",
+        &value[..head_end],
+        tail_start.saturating_sub(head_end),
+        &value[tail_start..]
+    )
+}
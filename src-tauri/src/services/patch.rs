@@ -0,0 +1,287 @@
+use serde::Serialize;
+use std::path::Path;
+
+/// How far, in lines, a hunk may drift from its header position and still be
+/// applied. Models often miscount line numbers but get the context right.
+const MAX_HUNK_DRIFT: usize = 200;
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HunkResult {
+    pub index: usize,
+    pub old_start: usize,
+    pub ok: bool,
+    /// 1-based line the hunk matched at, when it differs from the header.
+    pub applied_at: Option<usize>,
+    pub message: Option<String>,
+}
+
+pub struct PatchOutcome {
+    /// Patched content; `None` unless every hunk applied.
+    pub content: Option<String>,
+    pub hunks: Vec<HunkResult>,
+}
+
+impl PatchOutcome {
+    pub fn rejected(&self) -> Vec<&HunkResult> {
+        self.hunks.iter().filter(|hunk| !hunk.ok).collect()
+    }
+
+    pub fn describe(&self) -> String {
+        let rejected = self.rejected();
+        if rejected.is_empty() {
+            return format!("Applied {} hunk(s)", self.hunks.len());
+        }
+        let mut text = format!(
+            "Rejected {} of {} hunk(s); file left unchanged",
+            rejected.len(),
+            self.hunks.len()
+        );
+        for hunk in rejected {
+            text.push_str(&format!(
+                "\n  hunk {} (@@ -{}): {}",
+                hunk.index + 1,
+                hunk.old_start,
+                hunk.message.as_deref().unwrap_or("rejected")
+            ));
+        }
+        text
+    }
+}
+
+struct Hunk {
+    old_start: usize,
+    old: Vec<String>,
+    new: Vec<String>,
+    /// Old and new lines the header announces that have not been read yet.
+    old_left: usize,
+    new_left: usize,
+}
+
+impl Hunk {
+    /// 0-based index the header points at. Pure insertions (`-n,0`) name the
+    /// line they follow rather than the first line they replace.
+    fn header_index(&self) -> usize {
+        if self.old.is_empty() {
+            self.old_start
+        } else {
+            self.old_start.saturating_sub(1)
+        }
+    }
+
+    /// Whether the header's line counts are used up, so `---`, `+++` and
+    /// `diff` lines start a new file instead of being removed or added lines.
+    fn is_complete(&self) -> bool {
+        self.old_left == 0 && self.new_left == 0
+    }
+}
+
+/// Current content of the file a patch targets; `None` only when the file
+/// does not exist yet. Any other read failure is an error, so a patch is
+/// never applied to an empty stand-in for a file that could not be read.
+pub fn read_patch_target(path: &Path) -> Result<Option<String>, String> {
+    match std::fs::read_to_string(path) {
+        Ok(content) => Ok(Some(content)),
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(format!("Unable to read {}: {}", path.display(), err)),
+    }
+}
+
+/// Applies a unified diff to `original`. Hunks are matched against their
+/// context, tolerating shifted line numbers and trailing whitespace; the
+/// result only carries content when all of them apply. CRLF files keep their
+/// line endings.
+pub fn apply_patch(original: &str, patch: &str) -> Result<PatchOutcome, String> {
+    let hunks = parse_hunks(patch)?;
+    let line_ending = if original.contains("\r\n") {
+        "\r\n"
+    } else {
+        "\n"
+    };
+    let lines: Vec<&str> = original.lines().collect();
+    let mut output: Vec<String> = Vec::with_capacity(lines.len());
+    let mut cursor = 0;
+    let mut drift: isize = 0;
+    let mut results = Vec::with_capacity(hunks.len());
+    for (index, hunk) in hunks.iter().enumerate() {
+        let expected = (hunk.header_index() as isize + drift).max(0) as usize;
+        match find_hunk(&lines, &hunk.old, expected, cursor) {
+            Some(at) => {
+                output.extend(lines[cursor..at].iter().map(|line| line.to_string()));
+                output.extend(hunk.new.iter().cloned());
+                cursor = at + hunk.old.len();
+                // Headers number lines of the original, which is also what
+                // is searched, so only the match offset carries over.
+                drift = at as isize - hunk.header_index() as isize;
+                results.push(HunkResult {
+                    index,
+                    old_start: hunk.old_start,
+                    ok: true,
+                    applied_at: (at != hunk.header_index()).then_some(at + 1),
+                    message: None,
+                });
+            }
+            None => results.push(HunkResult {
+                index,
+                old_start: hunk.old_start,
+                ok: false,
+                applied_at: None,
+                message: Some(mismatch_message(&lines, &hunk.old, expected)),
+            }),
+        }
+    }
+    let content = if results.iter().all(|result| result.ok) {
+        output.extend(lines[cursor..].iter().map(|line| line.to_string()));
+        let mut content = output.join(line_ending);
+        let keep_newline = original.ends_with('\n') || original.is_empty();
+        if keep_newline && !content.is_empty() {
+            content.push_str(line_ending);
+        }
+        Some(content)
+    } else {
+        None
+    };
+    Ok(PatchOutcome {
+        content,
+        hunks: results,
+    })
+}
+
+fn parse_hunks(patch: &str) -> Result<Vec<Hunk>, String> {
+    let mut hunks: Vec<Hunk> = Vec::new();
+    let mut current: Option<Hunk> = None;
+    for line in patch.lines() {
+        let in_hunk = current.as_ref().is_some_and(|hunk| !hunk.is_complete());
+        // No hunk line starts with `@`, so a header always starts a new hunk.
+        if line.starts_with("@@") {
+            hunks.extend(current.take());
+            let (old_start, old_count, new_count) =
+                parse_header(line).ok_or_else(|| format!("Malformed hunk header: {}", line))?;
+            current = Some(Hunk {
+                old_start,
+                old: Vec::new(),
+                new: Vec::new(),
+                old_left: old_count,
+                new_left: new_count,
+            });
+            continue;
+        }
+        let file_header =
+            line.starts_with("--- ") || line.starts_with("+++ ") || line.starts_with("diff ");
+        if file_header && !in_hunk {
+            hunks.extend(current.take());
+            continue;
+        }
+        let Some(hunk) = current.as_mut() else {
+            continue;
+        };
+        // Counts only decide where a hunk ends; miscounted hunks that run on
+        // past them are still read.
+        match line.chars().next() {
+            Some('+') => {
+                hunk.new.push(line[1..].to_string());
+                hunk.new_left = hunk.new_left.saturating_sub(1);
+            }
+            Some('-') => {
+                hunk.old.push(line[1..].to_string());
+                hunk.old_left = hunk.old_left.saturating_sub(1);
+            }
+            Some(' ') => {
+                hunk.old.push(line[1..].to_string());
+                hunk.new.push(line[1..].to_string());
+                hunk.old_left = hunk.old_left.saturating_sub(1);
+                hunk.new_left = hunk.new_left.saturating_sub(1);
+            }
+            Some('\\') => {}
+            // Blank context lines often lose their leading space in transit.
+            None => {
+                hunk.old.push(String::new());
+                hunk.new.push(String::new());
+                hunk.old_left = hunk.old_left.saturating_sub(1);
+                hunk.new_left = hunk.new_left.saturating_sub(1);
+            }
+            Some(_) => return Err(format!("Unexpected line in hunk: {}", line)),
+        }
+    }
+    hunks.extend(current);
+    if hunks.is_empty() {
+        return Err("Patch contains no hunks (expected @@ -a,b +c,d @@ headers)".to_string());
+    }
+    Ok(hunks)
+}
+
+/// Old start plus old and new line counts of `@@ -a,b +c,d @@`; an omitted
+/// count means one line, an omitted new range none.
+fn parse_header(header: &str) -> Option<(usize, usize, usize)> {
+    let mut ranges = header.trim_start_matches('@').split_whitespace();
+    let (old_start, old_count) = parse_range(ranges.next()?.strip_prefix('-')?)?;
+    let new_count = match ranges.next().and_then(|range| range.strip_prefix('+')) {
+        Some(range) => parse_range(range)?.1,
+        None => 0,
+    };
+    Some((old_start, old_count, new_count))
+}
+
+fn parse_range(range: &str) -> Option<(usize, usize)> {
+    match range.split_once(',') {
+        Some((start, count)) => Some((start.parse().ok()?, count.parse().ok()?)),
+        None => Some((range.parse().ok()?, 1)),
+    }
+}
+
+fn matches_at(lines: &[&str], old: &[String], at: usize) -> bool {
+    at + old.len() <= lines.len()
+        && old
+            .iter()
+            .zip(&lines[at..])
+            .all(|(expected, actual)| expected.trim_end() == actual.trim_end())
+}
+
+/// Nearest position at or after `floor` where the hunk's old lines match,
+/// searching outward from `expected`.
+fn find_hunk(lines: &[&str], old: &[String], expected: usize, floor: usize) -> Option<usize> {
+    let expected = expected.max(floor).min(lines.len());
+    if old.is_empty() {
+        return Some(expected);
+    }
+    for distance in 0..=MAX_HUNK_DRIFT {
+        let after = expected + distance;
+        if matches_at(lines, old, after) {
+            return Some(after);
+        }
+        if let Some(before) = expected.checked_sub(distance) {
+            if distance > 0 && before >= floor && matches_at(lines, old, before) {
+                return Some(before);
+            }
+        }
+        if after >= lines.len() && expected.saturating_sub(distance) <= floor {
+            break;
+        }
+    }
+    None
+}
+
+fn mismatch_message(lines: &[&str], old: &[String], expected: usize) -> String {
+    for (offset, wanted) in old.iter().enumerate() {
+        let line = expected + offset;
+        match lines.get(line) {
+            Some(actual) if actual.trim_end() == wanted.trim_end() => continue,
+            Some(actual) => {
+                return format!(
+                    "context not found; line {} is `{}`, hunk expects `{}`",
+                    line + 1,
+                    actual.trim_end(),
+                    wanted.trim_end()
+                );
+            }
+            None => {
+                return format!(
+                    "context not found; file ends at line {}, hunk expects `{}`",
+                    lines.len(),
+                    wanted.trim_end()
+                );
+            }
+        }
+    }
+    "context overlaps an earlier hunk".to_string()
+}
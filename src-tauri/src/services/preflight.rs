@@ -0,0 +1,174 @@
+use serde::Serialize;
+use std::path::Path;
+
+use crate::services::doctor::{
+    check_git, check_llm, check_ripgrep, check_tool, CheckStatus, DoctorCheck,
+};
+use crate::services::kernel::{validate_judge_rule, JudgeRule};
+use crate::services::llm::LlmProfile;
+
+/// Project markers and the tool a run in such a project will need, with the
+/// binaries to try in order.
+const PROJECT_TOOLS: &[(&str, &str, &[&str])] = &[
+    ("Cargo.toml", "cargo", &["cargo"]),
+    ("package.json", "npm", &["npm"]),
+    ("pyproject.toml", "python", &["python3", "python"]),
+    ("requirements.txt", "python", &["python3", "python"]),
+    ("go.mod", "go", &["go"]),
+];
+
+/// Actions that need nothing outside the kernel, so a run allowed only these
+/// cannot do any work.
+const BOOKKEEPING_ACTIONS: &[&str] = &["plan.update", "task.update", "user.ask"];
+
+pub struct PreflightInput<'a> {
+    pub root: &'a Path,
+    pub profile: Option<&'a LlmProfile>,
+    pub max_steps: u32,
+    /// Actions the negotiated capabilities allow.
+    pub allowed: &'a [String],
+    pub readonly: bool,
+    pub judge_rules: &'a [JudgeRule],
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PreflightReport {
+    pub ok: bool,
+    pub checks: Vec<DoctorCheck>,
+}
+
+impl PreflightReport {
+    /// One line per failed check with its fix, for `last_error`.
+    pub fn failure_message(&self) -> String {
+        let lines: Vec<String> = self
+            .checks
+            .iter()
+            .filter(|check| check.status == CheckStatus::Fail)
+            .map(|check| match &check.fix {
+                Some(fix) => format!("{}: {} ({})", check.name, check.detail, fix),
+                None => format!("{}: {}", check.name, check.detail),
+            })
+            .collect();
+        format!("Pre-flight checks failed:\n{}", lines.join("\n"))
+    }
+}
+
+/// Checks what a run needs before its first step, so it fails up front with
+/// a fix instead of several steps in.
+pub async fn run_preflight(input: PreflightInput<'_>) -> PreflightReport {
+    let mut checks = Vec::new();
+    checks.push(match input.profile {
+        Some(profile) => check_llm(Some(profile)).await,
+        None => DoctorCheck::problem(
+            "llm_profile",
+            CheckStatus::Fail,
+            "no active LLM profile",
+            "Create a profile in Settings > LLM before starting a run.",
+        ),
+    });
+    if !input.readonly {
+        checks.push(check_workspace_writable(input.root));
+    }
+    let allows = |prefix: &str| {
+        input
+            .allowed
+            .iter()
+            .any(|action| action.starts_with(prefix))
+    };
+    if allows("git.") {
+        checks.push(check_git());
+    }
+    if allows("fs.search") {
+        checks.push(check_ripgrep());
+    }
+    if allows("terminal.") || allows("tests.") {
+        let mut seen = Vec::new();
+        for (marker, name, binaries) in PROJECT_TOOLS {
+            if seen.contains(name) || !input.root.join(marker).is_file() {
+                continue;
+            }
+            seen.push(*name);
+            checks.push(check_any_tool(name, binaries, marker));
+        }
+    }
+    checks.push(check_budget(input.max_steps));
+    checks.push(check_capabilities(input.allowed));
+    checks.extend(check_judge_rules(input.judge_rules));
+    let ok = checks.iter().all(|check| check.status != CheckStatus::Fail);
+    PreflightReport { ok, checks }
+}
+
+fn check_workspace_writable(root: &Path) -> DoctorCheck {
+    let probe = root.join(".taurihands-preflight");
+    let result = std::fs::write(&probe, b"ok").and_then(|_| std::fs::remove_file(&probe));
+    match result {
+        Ok(()) => DoctorCheck::ok("workspace", format!("{} is writable", root.display())),
+        Err(err) => DoctorCheck::problem(
+            "workspace",
+            CheckStatus::Fail,
+            format!("cannot write to {}: {}", root.display(), err),
+            "Fix the workspace's permissions, or start the run in readonly mode.",
+        ),
+    }
+}
+
+fn check_any_tool(name: &str, binaries: &[&str], marker: &str) -> DoctorCheck {
+    let fix = format!(
+        "Install {} and make sure it is on PATH; this workspace has a {}.",
+        name, marker
+    );
+    let mut last = None;
+    for binary in binaries {
+        let check = check_tool(name, binary, &fix);
+        if check.status == CheckStatus::Ok {
+            return check;
+        }
+        last = Some(check);
+    }
+    last.unwrap_or_else(|| DoctorCheck::ok(name, "nothing to check"))
+}
+
+fn check_budget(max_steps: u32) -> DoctorCheck {
+    if max_steps == 0 {
+        return DoctorCheck::problem(
+            "budget",
+            CheckStatus::Fail,
+            "the step budget is 0, so the run cannot take a single step",
+            "Set max steps to at least 1.",
+        );
+    }
+    DoctorCheck::ok("budget", format!("up to {} steps", max_steps))
+}
+
+fn check_capabilities(allowed: &[String]) -> DoctorCheck {
+    let usable = allowed
+        .iter()
+        .filter(|action| !BOOKKEEPING_ACTIONS.contains(&action.as_str()))
+        .count();
+    if usable == 0 {
+        return DoctorCheck::problem(
+            "capabilities",
+            CheckStatus::Fail,
+            "the profile, risk policy and CLI together deny every tool action",
+            "Enable tools in the LLM profile or relax the task's risk policy.",
+        );
+    }
+    DoctorCheck::ok("capabilities", format!("{} tool actions allowed", usable))
+}
+
+fn check_judge_rules(rules: &[JudgeRule]) -> Vec<DoctorCheck> {
+    rules
+        .iter()
+        .enumerate()
+        .filter_map(|(index, rule)| {
+            let err = validate_judge_rule(rule).err()?;
+            Some(DoctorCheck::problem(
+                "judge_rules",
+                CheckStatus::Fail,
+                format!("rule {} ({}): {}", index + 1, rule.rule_type, err),
+                "Fix or remove the rule in the task's judge rules.",
+            ))
+        })
+        .collect()
+}
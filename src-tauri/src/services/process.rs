@@ -0,0 +1,272 @@
+use serde::Serialize;
+use std::process::{Child, Command, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
+
+pub const KILL_GRACE_MS: u64 = 3_000;
+const POLL_INTERVAL_MS: u64 = 50;
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminationReport {
+    pub interrupted: bool,
+    pub killed: bool,
+    pub grace_ms: u64,
+    pub waited_ms: u128,
+    pub remaining_pids: Vec<u32>,
+}
+
+impl TerminationReport {
+    pub fn summary(&self) -> String {
+        if self.killed {
+            format!(
+                "Timed out; interrupt ignored for {}ms, process tree was killed.",
+                self.grace_ms
+            )
+        } else if self.interrupted {
+            format!(
+                "Timed out; process exited {}ms after interrupt.",
+                self.waited_ms
+            )
+        } else {
+            "Timed out; process had already exited.".to_string()
+        }
+    }
+}
+
+/// Soft-kills `child` and its descendants, escalating to a hard kill after `grace_ms`.
+pub fn terminate_child(child: &mut Child, grace_ms: u64) -> TerminationReport {
+    let pid = child.id();
+    let started = Instant::now();
+    let mut report = TerminationReport {
+        interrupted: false,
+        killed: false,
+        grace_ms,
+        waited_ms: 0,
+        remaining_pids: Vec::new(),
+    };
+    if matches!(child.try_wait(), Ok(Some(_))) {
+        return report;
+    }
+    let tree = process_tree(pid);
+    report.interrupted = send_interrupt(pid, &tree);
+    let deadline = started + Duration::from_millis(grace_ms);
+    while Instant::now() < deadline {
+        if matches!(child.try_wait(), Ok(Some(_))) {
+            break;
+        }
+        sleep(Duration::from_millis(POLL_INTERVAL_MS));
+    }
+    let survivors = live_pids(tree);
+    if !matches!(child.try_wait(), Ok(Some(_))) || !survivors.is_empty() {
+        kill_pids(&survivors);
+        let _ = child.kill();
+        report.killed = true;
+    }
+    let _ = child.wait();
+    report.waited_ms = started.elapsed().as_millis();
+    report.remaining_pids = live_pids(survivors);
+    report
+}
+
+/// Waits for the descendants of a shell to exit after it was sent an interrupt,
+/// killing whatever is still running once `grace_ms` has elapsed.
+pub fn reap_descendants(shell_pid: Option<u32>, grace_ms: u64) -> TerminationReport {
+    let started = Instant::now();
+    let mut report = TerminationReport {
+        interrupted: true,
+        killed: false,
+        grace_ms,
+        waited_ms: 0,
+        remaining_pids: Vec::new(),
+    };
+    let Some(shell_pid) = shell_pid else {
+        return report;
+    };
+    let deadline = started + Duration::from_millis(grace_ms);
+    let mut survivors = descendant_pids(shell_pid);
+    while !survivors.is_empty() && Instant::now() < deadline {
+        sleep(Duration::from_millis(POLL_INTERVAL_MS));
+        survivors = descendant_pids(shell_pid);
+    }
+    if !survivors.is_empty() {
+        kill_pids(&survivors);
+        report.killed = true;
+        sleep(Duration::from_millis(POLL_INTERVAL_MS));
+    }
+    report.waited_ms = started.elapsed().as_millis();
+    report.remaining_pids = live_pids(survivors);
+    report
+}
+
+pub fn child_pids(pid: u32) -> Vec<u32> {
+    if !cfg!(target_os = "linux") {
+        return process_table()
+            .into_iter()
+            .filter(|(_, parent)| *parent == pid)
+            .map(|(child, _)| child)
+            .collect();
+    }
+    let Ok(tasks) = std::fs::read_dir(format!("/proc/{}/task", pid)) else {
+        return Vec::new();
+    };
+    let mut children = Vec::new();
+    for task in tasks.flatten() {
+        if let Ok(raw) = std::fs::read_to_string(task.path().join("children")) {
+            children.extend(
+                raw.split_whitespace()
+                    .filter_map(|id| id.parse::<u32>().ok()),
+            );
+        }
+    }
+    children
+}
+
+pub fn descendant_pids(pid: u32) -> Vec<u32> {
+    // Without /proc every lookup spawns a process, so take one snapshot.
+    let table = if cfg!(target_os = "linux") {
+        None
+    } else {
+        Some(process_table())
+    };
+    let children_of = |parent: u32| match &table {
+        Some(table) => table
+            .iter()
+            .filter(|(_, ppid)| *ppid == parent)
+            .map(|(child, _)| *child)
+            .collect(),
+        None => child_pids(parent),
+    };
+    let mut pending = children_of(pid);
+    let mut found = Vec::new();
+    while let Some(next) = pending.pop() {
+        if found.contains(&next) {
+            continue;
+        }
+        pending.extend(children_of(next));
+        found.push(next);
+    }
+    found
+}
+
+/// Every process as (pid, parent pid), for platforms without /proc.
+fn process_table() -> Vec<(u32, u32)> {
+    let output = if cfg!(windows) {
+        Command::new("powershell")
+            .args([
+                "-NoProfile",
+                "-Command",
+                "Get-CimInstance Win32_Process | \
+                 ForEach-Object { \"$($_.ProcessId) $($_.ParentProcessId)\" }",
+            ])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+    } else {
+        Command::new("ps")
+            .args(["-A", "-o", "pid=,ppid="])
+            .stdin(Stdio::null())
+            .stderr(Stdio::null())
+            .output()
+    };
+    let Ok(output) = output else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pid = fields.next()?.parse().ok()?;
+            let parent = fields.next()?.parse().ok()?;
+            Some((pid, parent))
+        })
+        .collect()
+}
+
+fn process_tree(pid: u32) -> Vec<u32> {
+    let mut tree = vec![pid];
+    tree.extend(descendant_pids(pid));
+    tree
+}
+
+/// The pids in `pids` that are still running; zombies count as exited.
+fn live_pids(pids: Vec<u32>) -> Vec<u32> {
+    if pids.is_empty() || cfg!(target_os = "linux") {
+        return pids.into_iter().filter(|pid| is_alive(*pid)).collect();
+    }
+    if cfg!(windows) {
+        let table = process_table();
+        return pids
+            .into_iter()
+            .filter(|pid| table.iter().any(|(live, _)| live == pid))
+            .collect();
+    }
+    let list = pids
+        .iter()
+        .map(|pid| pid.to_string())
+        .collect::<Vec<_>>()
+        .join(",");
+    let Ok(output) = Command::new("ps")
+        .args(["-o", "pid=,stat=", "-p", &list])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+    else {
+        return Vec::new();
+    };
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pid = fields.next()?.parse().ok()?;
+            let state = fields.next()?;
+            (!state.starts_with('Z')).then_some(pid)
+        })
+        .collect()
+}
+
+fn is_alive(pid: u32) -> bool {
+    std::fs::read_to_string(format!("/proc/{}/stat", pid))
+        .ok()
+        .and_then(|raw| {
+            raw.rfind(')')
+                .and_then(|idx| raw[idx + 1..].split_whitespace().next().map(str::to_string))
+        })
+        .map(|state| state != "Z" && state != "X")
+        .unwrap_or(false)
+}
+
+fn send_interrupt(pid: u32, tree: &[u32]) -> bool {
+    if cfg!(windows) {
+        // taskkill without /F asks console processes to close, like CTRL_BREAK.
+        return quiet(Command::new("taskkill").args(["/PID", &pid.to_string(), "/T"]));
+    }
+    let mut args = vec!["-INT".to_string()];
+    args.extend(tree.iter().map(|pid| pid.to_string()));
+    quiet(Command::new("kill").args(&args))
+}
+
+fn kill_pids(pids: &[u32]) {
+    if pids.is_empty() {
+        return;
+    }
+    if cfg!(windows) {
+        for pid in pids {
+            quiet(Command::new("taskkill").args(["/PID", &pid.to_string(), "/T", "/F"]));
+        }
+        return;
+    }
+    let mut args = vec!["-KILL".to_string()];
+    args.extend(pids.iter().map(|pid| pid.to_string()));
+    quiet(Command::new("kill").args(&args));
+}
+
+fn quiet(command: &mut Command) -> bool {
+    command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()
+        .map(|status| status.success())
+        .unwrap_or(false)
+}
@@ -0,0 +1,242 @@
+use hmac::{Hmac, Mac};
+use serde::{Deserialize, Serialize};
+use sha2::Sha256;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use uuid::Uuid;
+
+pub const DEFAULT_PROFILE: &str = "default";
+const MAX_PROFILE_NAME_LEN: usize = 32;
+const PASSPHRASE_ITERATIONS: u32 = 200_000;
+const MIN_PASSPHRASE_LEN: usize = 8;
+
+/// Profile whose per-workspace data is in use; empty means the default.
+static ACTIVE_PROFILE: Mutex<String> = Mutex::new(String::new());
+
+#[derive(Clone, Serialize)]
+pub struct ProfileInfo {
+    pub name: String,
+    pub active: bool,
+    /// Whether switching to the profile asks for its passphrase.
+    pub protected: bool,
+}
+
+/// PBKDF2-HMAC-SHA256 of a profile passphrase, stored in the profile root.
+#[derive(Serialize, Deserialize)]
+struct ProfileLock {
+    salt: String,
+    iterations: u32,
+    hash: String,
+}
+
+pub fn validate_profile_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || name.len() > MAX_PROFILE_NAME_LEN {
+        return Err(format!(
+            "Profile name must be 1-{} characters",
+            MAX_PROFILE_NAME_LEN
+        ));
+    }
+    if !name
+        .chars()
+        .all(|ch| ch.is_ascii_alphanumeric() || ch == '-' || ch == '_')
+    {
+        return Err("Profile name may only contain letters, digits, '-' and '_'".to_string());
+    }
+    Ok(())
+}
+
+/// The default profile keeps the original layout directly under `base`, so
+/// existing installs keep their settings and keys.
+pub fn profile_root(base: &Path, name: &str) -> PathBuf {
+    if name == DEFAULT_PROFILE {
+        base.to_path_buf()
+    } else {
+        base.join("profiles").join(name)
+    }
+}
+
+/// Selects the profile whose run state, events, history and provenance are
+/// read and written in every workspace.
+pub fn set_active_profile(name: &str) {
+    if let Ok(mut active) = ACTIVE_PROFILE.lock() {
+        *active = name.to_string();
+    }
+}
+
+/// Per-profile data inside a workspace. The default profile keeps the
+/// original layout; others get `.taurihands/profiles/<name>`, so one user's
+/// runs and file history are not shown to another.
+pub fn workspace_data_dir(workspace_root: &Path) -> PathBuf {
+    let dir = workspace_root.join(".taurihands");
+    let active = ACTIVE_PROFILE
+        .lock()
+        .map(|active| active.clone())
+        .unwrap_or_default();
+    if active.is_empty() || active == DEFAULT_PROFILE {
+        dir
+    } else {
+        dir.join("profiles").join(active)
+    }
+}
+
+fn lock_path(base: &Path, name: &str) -> PathBuf {
+    profile_root(base, name).join("passphrase.json")
+}
+
+fn load_lock(base: &Path, name: &str) -> Option<ProfileLock> {
+    std::fs::read_to_string(lock_path(base, name))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+}
+
+pub fn is_protected(base: &Path, name: &str) -> bool {
+    name != DEFAULT_PROFILE && load_lock(base, name).is_some()
+}
+
+/// Checks the passphrase before a switch to `name`. The default profile is
+/// the install's own and needs none. A profile without a passphrase yet, new
+/// or from before passphrases existed, takes the given one as its own.
+pub fn unlock_profile(base: &Path, name: &str, passphrase: Option<&str>) -> Result<(), String> {
+    if name == DEFAULT_PROFILE {
+        return Ok(());
+    }
+    let passphrase = passphrase.unwrap_or_default();
+    match load_lock(base, name) {
+        Some(lock) => {
+            let salt = hex_decode(&lock.salt).ok_or("Profile passphrase file is corrupt")?;
+            let hash = hex(&pbkdf2_sha256(
+                passphrase.as_bytes(),
+                &salt,
+                lock.iterations,
+            ));
+            if !constant_time_eq(hash.as_bytes(), lock.hash.as_bytes()) {
+                return Err(format!("Wrong passphrase for profile {}", name));
+            }
+            Ok(())
+        }
+        None => set_profile_passphrase(base, name, passphrase),
+    }
+}
+
+pub fn set_profile_passphrase(base: &Path, name: &str, passphrase: &str) -> Result<(), String> {
+    if name == DEFAULT_PROFILE {
+        return Err("The default profile has no passphrase".to_string());
+    }
+    if passphrase.chars().count() < MIN_PASSPHRASE_LEN {
+        return Err(format!(
+            "Profile passphrase must be at least {} characters",
+            MIN_PASSPHRASE_LEN
+        ));
+    }
+    let salt = Uuid::new_v4().as_bytes().to_vec();
+    let lock = ProfileLock {
+        salt: hex(&salt),
+        iterations: PASSPHRASE_ITERATIONS,
+        hash: hex(&pbkdf2_sha256(
+            passphrase.as_bytes(),
+            &salt,
+            PASSPHRASE_ITERATIONS,
+        )),
+    };
+    let path = lock_path(base, name);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_vec_pretty(&lock).map_err(|e| e.to_string())?;
+    std::fs::write(path, data).map_err(|e| e.to_string())
+}
+
+fn pbkdf2_sha256(passphrase: &[u8], salt: &[u8], iterations: u32) -> Vec<u8> {
+    let mac = Hmac::<Sha256>::new_from_slice(passphrase).expect("HMAC accepts any key length");
+    let mut first = mac.clone();
+    first.update(salt);
+    first.update(&1u32.to_be_bytes());
+    let mut block = first.finalize().into_bytes();
+    let mut output = block.to_vec();
+    for _ in 1..iterations.max(1) {
+        let mut round = mac.clone();
+        round.update(&block);
+        block = round.finalize().into_bytes();
+        output
+            .iter_mut()
+            .zip(block.iter())
+            .for_each(|(out, byte)| *out ^= byte);
+    }
+    output
+}
+
+fn constant_time_eq(left: &[u8], right: &[u8]) -> bool {
+    left.len() == right.len()
+        && left
+            .iter()
+            .zip(right)
+            .fold(0u8, |diff, (a, b)| diff | (a ^ b))
+            == 0
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn hex_decode(value: &str) -> Option<Vec<u8>> {
+    if !value.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..value.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(value.get(index..index + 2)?, 16).ok())
+        .collect()
+}
+
+/// Per-profile audit log inside the workspace's `.taurihands` directory.
+pub fn profile_audit_log(workspace_root: &Path, name: &str) -> PathBuf {
+    let dir = workspace_root.join(".taurihands");
+    if name == DEFAULT_PROFILE {
+        dir.join("audit.log")
+    } else {
+        dir.join(format!("audit-{}.log", name))
+    }
+}
+
+pub fn list_profiles(base: &Path, active: &str) -> Vec<ProfileInfo> {
+    let mut names = vec![DEFAULT_PROFILE.to_string()];
+    if let Ok(entries) = std::fs::read_dir(base.join("profiles")) {
+        let mut named: Vec<String> = entries
+            .flatten()
+            .filter(|entry| entry.path().is_dir())
+            .map(|entry| entry.file_name().to_string_lossy().to_string())
+            .filter(|name| validate_profile_name(name).is_ok() && name != DEFAULT_PROFILE)
+            .collect();
+        named.sort();
+        names.extend(named);
+    }
+    if !names.iter().any(|name| name == active) {
+        names.push(active.to_string());
+    }
+    names
+        .into_iter()
+        .map(|name| ProfileInfo {
+            active: name == active,
+            protected: is_protected(base, &name),
+            name,
+        })
+        .collect()
+}
+
+/// Reads `--user <name>` / `--user=<name>`, falling back to `TAURIHANDS_USER`.
+/// The choice is not persisted, so the next launch does not inherit the
+/// previous user's profile.
+pub fn profile_from_args(args: &[String]) -> Option<String> {
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        if arg == "--user" {
+            return iter.next().cloned();
+        }
+        if let Some(name) = arg.strip_prefix("--user=") {
+            return Some(name.to_string());
+        }
+    }
+    std::env::var("TAURIHANDS_USER")
+        .ok()
+        .filter(|name| !name.trim().is_empty())
+}
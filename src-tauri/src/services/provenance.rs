@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::services::audit::now_ms;
+use crate::services::diff::line_origins;
+use crate::services::profiles::workspace_data_dir;
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProvenancePolicy {
+    /// Adds a `Co-authored-by: TauriHands run <id>` trailer to commits made by a run.
+    #[serde(default)]
+    pub commit_trailer: bool,
+}
+
+pub fn provenance_policy_path(root: &Path) -> PathBuf {
+    root.join(".taurihands").join("provenance-policy.json")
+}
+
+pub fn load_provenance_policy(root: &Path) -> ProvenancePolicy {
+    std::fs::read_to_string(provenance_policy_path(root))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_provenance_policy(root: &Path, policy: &ProvenancePolicy) -> Result<(), String> {
+    let path = provenance_policy_path(root);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_vec_pretty(policy).map_err(|e| e.to_string())?;
+    std::fs::write(path, data).map_err(|e| e.to_string())
+}
+
+pub fn commit_trailer(run_id: &str) -> String {
+    format!("Co-authored-by: TauriHands run <{}>", run_id)
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProvenanceSource {
+    pub run_id: Option<String>,
+    pub actor: String,
+    pub timestamp_ms: u128,
+}
+
+/// Lines `start..=end` (1-based) were last written by `source`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LineProvenance {
+    pub start: u32,
+    pub end: u32,
+    #[serde(flatten)]
+    pub source: ProvenanceSource,
+}
+
+/// Lines with no range were not written through TauriHands.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct FileProvenance {
+    pub path: String,
+    pub last_modified: ProvenanceSource,
+    pub ranges: Vec<LineProvenance>,
+}
+
+impl FileProvenance {
+    fn line_sources(&self, line_count: usize) -> Vec<Option<ProvenanceSource>> {
+        let mut lines = vec![None; line_count];
+        for range in &self.ranges {
+            for line in range.start..=range.end {
+                // Lines are 1-based; a zero start from a hand-edited index is skipped.
+                let Some(index) = (line as usize).checked_sub(1) else {
+                    continue;
+                };
+                if let Some(slot) = lines.get_mut(index) {
+                    *slot = Some(range.source.clone());
+                }
+            }
+        }
+        lines
+    }
+}
+
+pub struct ProvenanceStore {
+    root: PathBuf,
+    index_path: PathBuf,
+}
+
+impl ProvenanceStore {
+    pub fn for_root(root: &Path) -> Self {
+        Self {
+            root: root.to_path_buf(),
+            index_path: workspace_data_dir(root)
+                .join("provenance")
+                .join("index.json"),
+        }
+    }
+
+    /// Attributes the lines that changed between `before` and `after` to the
+    /// writer; unchanged lines keep their earlier attribution.
+    pub fn record(
+        &self,
+        resolved: &Path,
+        before: Option<&str>,
+        after: &str,
+        actor: &str,
+        run_id: Option<String>,
+    ) -> Result<(), String> {
+        let path = self.relative_path(resolved);
+        let mut index = self.load();
+        let before = before.unwrap_or_default();
+        let previous = index
+            .get(&path)
+            .map(|entry| entry.line_sources(before.lines().count()))
+            .unwrap_or_else(|| vec![None; before.lines().count()]);
+        let source = ProvenanceSource {
+            run_id,
+            actor: actor.to_string(),
+            timestamp_ms: now_ms(),
+        };
+        let lines: Vec<Option<ProvenanceSource>> = line_origins(before, after)
+            .into_iter()
+            .map(|origin| match origin {
+                Some(old_index) => previous.get(old_index).cloned().flatten(),
+                None => Some(source.clone()),
+            })
+            .collect();
+        index.insert(
+            path.clone(),
+            FileProvenance {
+                path,
+                last_modified: source,
+                ranges: compress(&lines),
+            },
+        );
+        self.save(&index)
+    }
+
+    pub fn for_file(&self, resolved: &Path) -> Option<FileProvenance> {
+        self.load().remove(&self.relative_path(resolved))
+    }
+
+    /// Every file written through TauriHands, by workspace-relative path.
+    pub fn all(&self) -> BTreeMap<String, FileProvenance> {
+        self.load()
+    }
+
+    fn load(&self) -> BTreeMap<String, FileProvenance> {
+        std::fs::read_to_string(&self.index_path)
+            .ok()
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default()
+    }
+
+    fn save(&self, index: &BTreeMap<String, FileProvenance>) -> Result<(), String> {
+        if let Some(parent) = self.index_path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+        let data = serde_json::to_vec(index).map_err(|e| e.to_string())?;
+        std::fs::write(&self.index_path, data).map_err(|e| e.to_string())
+    }
+
+    fn relative_path(&self, resolved: &Path) -> String {
+        let relative = resolved.strip_prefix(&self.root).unwrap_or(resolved);
+        relative.to_string_lossy().replace('\\', "/")
+    }
+}
+
+fn compress(lines: &[Option<ProvenanceSource>]) -> Vec<LineProvenance> {
+    let mut ranges: Vec<LineProvenance> = Vec::new();
+    for (index, source) in lines.iter().enumerate() {
+        let Some(source) = source else {
+            continue;
+        };
+        let line = index as u32 + 1;
+        match ranges.last_mut() {
+            Some(range) if range.end + 1 == line && range.source == *source => range.end = line,
+            _ => ranges.push(LineProvenance {
+                start: line,
+                end: line,
+                source: source.clone(),
+            }),
+        }
+    }
+    ranges
+}
@@ -1,19 +1,29 @@
 use base64::{engine::general_purpose, Engine as _};
 use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
 use serde::{Deserialize, Serialize};
-use std::collections::{HashMap, HashSet};
-use std::fs::{create_dir_all, File, OpenOptions};
-use std::io::{Read, Seek, SeekFrom, Write};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::fs::{create_dir_all, File};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
 use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
 use std::time::{Duration, Instant};
-use tauri::{AppHandle, Emitter};
 use uuid::Uuid;
 
 use crate::services::audit::{now_ms, AuditEntry, AuditLog};
+use crate::services::command_policy::enforce_command_policy;
+use crate::services::events::{EventSink, SharedBinarySink};
+use crate::services::metrics;
+use crate::services::observation::collapse_progress;
+use crate::services::process::{reap_descendants, TerminationReport, KILL_GRACE_MS};
+use crate::services::pty_output::{
+    compact_logs, load_pty_output_policy, save_pty_output_policy, spawn_output_pump, BinaryOutput,
+    OutputRing, OutputTransport, PtyOutputPolicy,
+};
+use crate::services::resources::{ResourceSampler, ResourceUsage};
 use crate::services::tools::ToolResult;
 
-const TERMINAL_OUTPUT_EVENT: &str = "terminal-output";
+const MAX_HISTORY_ENTRIES: usize = 200;
+const MAX_SCROLLBACK_MATCHES: usize = 500;
 
 #[derive(Clone)]
 pub struct TerminalManager {
@@ -33,6 +43,32 @@ pub struct TerminalSessionInfo {
     pub log_path: String,
     pub created_at_ms: u128,
     pub is_alive: bool,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    pub profile: Option<String>,
+    pub run_id: Option<String>,
+    pub task_id: Option<String>,
+}
+
+#[derive(Deserialize, Default)]
+pub struct TerminalListRequest {
+    pub run_id: Option<String>,
+    pub task_id: Option<String>,
+    pub owner: Option<String>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Default)]
+pub struct TerminalSessionMeta {
+    pub title: Option<String>,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    pub profile: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Default)]
+struct TerminalMetaStore {
+    sessions: HashMap<String, TerminalSessionMeta>,
+    profiles: HashMap<String, TerminalSessionMeta>,
 }
 
 #[derive(Deserialize)]
@@ -43,6 +79,16 @@ pub struct TerminalCreateRequest {
     pub cols: u16,
     pub rows: u16,
     pub title: Option<String>,
+    #[serde(default)]
+    pub color: Option<String>,
+    #[serde(default)]
+    pub icon: Option<String>,
+    #[serde(default)]
+    pub profile: Option<String>,
+    #[serde(default)]
+    pub run_id: Option<String>,
+    #[serde(default)]
+    pub task_id: Option<String>,
 }
 
 #[derive(Deserialize)]
@@ -63,12 +109,29 @@ pub struct TerminalKillRequest {
     pub session_id: String,
 }
 
+#[derive(Deserialize)]
+pub struct TerminalOutputTransportRequest {
+    pub session_id: String,
+}
+
 #[derive(Deserialize)]
 pub struct TerminalReplayRequest {
     pub session_id: String,
     pub max_bytes: usize,
 }
 
+#[derive(Deserialize)]
+pub struct TerminalSearchRequest {
+    pub session_id: String,
+    pub pattern: String,
+    /// Treat `pattern` as a regular expression instead of literal text.
+    #[serde(default)]
+    pub regex: bool,
+    #[serde(default)]
+    pub case_sensitive: bool,
+    pub max_results: Option<usize>,
+}
+
 #[derive(Deserialize)]
 pub struct TerminalExecRequest {
     pub command: String,
@@ -87,6 +150,15 @@ pub struct TerminalSetTitleRequest {
     pub title: String,
 }
 
+#[derive(Deserialize)]
+pub struct TerminalUpdateMetaRequest {
+    pub session_id: String,
+    pub title: Option<String>,
+    pub color: Option<String>,
+    pub icon: Option<String>,
+    pub profile: Option<String>,
+}
+
 #[derive(Deserialize)]
 pub struct TerminalSetOrderRequest {
     pub session_ids: Vec<String>,
@@ -100,22 +172,97 @@ pub struct TerminalReplayResponse {
     pub truncated: bool,
 }
 
+#[derive(Serialize)]
+pub struct ScrollbackMatch {
+    /// Byte offset of the line in the session log, as used by `terminal_replay`.
+    pub offset: u64,
+    pub line_number: usize,
+    /// The line with ANSI escapes removed; the match is found in this text.
+    pub line: String,
+}
+
+#[derive(Serialize)]
+pub struct TerminalSearchResponse {
+    pub session_id: String,
+    pub matches: Vec<ScrollbackMatch>,
+    pub truncated: bool,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TerminalHistoryEntry {
+    pub command: String,
+    pub source: String,
+    pub timestamp_ms: u128,
+}
+
+#[derive(Deserialize)]
+pub struct TerminalHistoryRequest {
+    pub session_id: String,
+    pub limit: Option<usize>,
+}
+
 struct PtySession {
     info: TerminalSessionInfo,
     master: Box<dyn MasterPty + Send>,
     writer: Box<dyn Write + Send>,
     child: Box<dyn Child + Send>,
+    history: VecDeque<TerminalHistoryEntry>,
+    line_buffer: String,
+    in_escape: bool,
+    output: Arc<Mutex<OutputRing>>,
+    binary_output: BinaryOutput,
 }
 
-#[derive(Clone, Serialize)]
-struct TerminalOutputEvent {
-    session_id: String,
-    data_base64: String,
+impl PtySession {
+    fn push_history(&mut self, command: &str, source: &str) {
+        let command = command.trim();
+        if command.is_empty() {
+            return;
+        }
+        let command = redact_command(command);
+        if self.history.back().map(|entry| entry.command == command) == Some(true) {
+            return;
+        }
+        self.history.push_back(TerminalHistoryEntry {
+            command,
+            source: source.to_string(),
+            timestamp_ms: now_ms(),
+        });
+        while self.history.len() > MAX_HISTORY_ENTRIES {
+            self.history.pop_front();
+        }
+    }
+
+    fn track_input(&mut self, data: &[u8]) {
+        for ch in String::from_utf8_lossy(data).chars() {
+            if self.in_escape {
+                if ch.is_ascii_alphabetic() || ch == '~' {
+                    self.in_escape = false;
+                }
+                continue;
+            }
+            match ch {
+                '\r' | '\n' => {
+                    let line = std::mem::take(&mut self.line_buffer);
+                    self.push_history(&line, "input");
+                }
+                '\u{7f}' | '\u{8}' => {
+                    self.line_buffer.pop();
+                }
+                '\u{3}' | '\u{15}' => self.line_buffer.clear(),
+                '\u{1b}' => self.in_escape = true,
+                ch if ch.is_control() => {}
+                ch => self.line_buffer.push(ch),
+            }
+        }
+    }
 }
 
 impl TerminalManager {
     pub fn new(logs_dir: PathBuf) -> Self {
         let _ = create_dir_all(&logs_dir);
+        compact_logs(&logs_dir, &load_pty_output_policy(&logs_dir));
         Self {
             sessions: Arc::new(Mutex::new(HashMap::new())),
             order: Arc::new(Mutex::new(Vec::new())),
@@ -125,14 +272,12 @@ impl TerminalManager {
 
     pub fn create_session(
         &self,
-        app_handle: AppHandle,
+        app_handle: &dyn EventSink,
         request: TerminalCreateRequest,
         cwd: PathBuf,
         audit: &AuditLog,
     ) -> Result<TerminalSessionInfo, String> {
-        let shell = request
-            .shell
-            .unwrap_or_else(|| default_shell().to_string());
+        let shell = request.shell.unwrap_or_else(|| default_shell().to_string());
         let pty_system = native_pty_system();
         let pair = pty_system
             .openpty(PtySize {
@@ -149,10 +294,7 @@ impl TerminalManager {
         }
         cmd.cwd(cwd.clone());
 
-        let child = pair
-            .slave
-            .spawn_command(cmd)
-            .map_err(|e| e.to_string())?;
+        let child = pair.slave.spawn_command(cmd).map_err(|e| e.to_string())?;
 
         let master = pair.master;
         let reader = master.try_clone_reader().map_err(|e| e.to_string())?;
@@ -161,16 +303,28 @@ impl TerminalManager {
         let id = Uuid::new_v4().to_string();
         let log_path = self.log_path_for(&id);
 
-        if let Some(parent) = log_path.parent() {
-            let _ = create_dir_all(parent);
-        }
-        let _ = OpenOptions::new().create(true).append(true).open(&log_path);
-
-        spawn_reader_thread(app_handle, id.clone(), log_path.clone(), reader);
+        let policy = self.output_policy();
+        let output = Arc::new(Mutex::new(OutputRing::new(policy.ring_buffer_bytes)));
+        let binary_output = BinaryOutput::default();
+        spawn_output_pump(
+            app_handle.clone_sink(),
+            id.clone(),
+            reader,
+            output.clone(),
+            binary_output.clone(),
+            log_path.clone(),
+            policy,
+        );
 
+        let saved = request
+            .profile
+            .as_ref()
+            .and_then(|profile| self.load_meta_store().profiles.get(profile).cloned())
+            .unwrap_or_default();
         let title = request
             .title
             .clone()
+            .or(saved.title)
             .unwrap_or_else(|| "Session".to_string());
 
         let info = TerminalSessionInfo {
@@ -183,19 +337,31 @@ impl TerminalManager {
             log_path: log_path.to_string_lossy().to_string(),
             created_at_ms: now_ms(),
             is_alive: true,
+            color: request.color.clone().or(saved.color),
+            icon: request.icon.clone().or(saved.icon),
+            profile: request.profile.clone(),
+            run_id: request.run_id.clone(),
+            task_id: request.task_id.clone(),
         };
+        self.persist_meta(&info);
 
         let session = PtySession {
             info: info.clone(),
             master,
             writer,
             child,
+            history: VecDeque::new(),
+            line_buffer: String::new(),
+            in_escape: false,
+            output,
+            binary_output,
         };
 
         self.sessions
             .lock()
             .map_err(|_| "Terminal session lock poisoned".to_string())?
             .insert(id.clone(), session);
+        metrics::record_pty_session_opened();
         if let Ok(mut order) = self.order.lock() {
             order.push(id.clone());
         }
@@ -211,6 +377,8 @@ impl TerminalManager {
                 "rows": info.rows,
                 "log_path": info.log_path,
                 "title": info.title,
+                "run_id": info.run_id,
+                "task_id": info.task_id,
             }),
         });
 
@@ -229,10 +397,8 @@ impl TerminalManager {
         let session = sessions
             .get_mut(&request.session_id)
             .ok_or_else(|| "Session not found".to_string())?;
-        session
-            .writer
-            .write_all(&data)
-            .map_err(|e| e.to_string())?;
+        session.writer.write_all(&data).map_err(|e| e.to_string())?;
+        session.track_input(&data);
 
         audit.write(AuditEntry {
             timestamp_ms: now_ms(),
@@ -247,6 +413,33 @@ impl TerminalManager {
         Ok(())
     }
 
+    /// Streams the session's output as raw frames to `binary`, or back as
+    /// `terminal-output` events when it is `None`. Returns the transport in
+    /// effect.
+    pub fn set_output_transport(
+        &self,
+        session_id: &str,
+        binary: Option<SharedBinarySink>,
+    ) -> Result<OutputTransport, String> {
+        let sessions = self
+            .sessions
+            .lock()
+            .map_err(|_| "Terminal session lock poisoned".to_string())?;
+        let session = sessions
+            .get(session_id)
+            .ok_or_else(|| "Session not found".to_string())?;
+        let transport = if binary.is_some() {
+            OutputTransport::Binary
+        } else {
+            OutputTransport::Json
+        };
+        *session
+            .binary_output
+            .lock()
+            .map_err(|_| "Terminal output lock poisoned".to_string())? = binary;
+        Ok(transport)
+    }
+
     pub fn resize(&self, request: TerminalResizeRequest, audit: &AuditLog) -> Result<(), String> {
         let mut sessions = self
             .sessions
@@ -290,6 +483,7 @@ impl TerminalManager {
         if let Some(mut session) = sessions.remove(&request.session_id) {
             let _ = session.child.kill();
             let _ = session.child.wait();
+            metrics::record_pty_session_closed();
         }
         if let Ok(mut order) = self.order.lock() {
             if let Some(index) = order.iter().position(|id| id == &request.session_id) {
@@ -308,6 +502,103 @@ impl TerminalManager {
         Ok(())
     }
 
+    pub fn get_history(
+        &self,
+        request: TerminalHistoryRequest,
+    ) -> Result<Vec<TerminalHistoryEntry>, String> {
+        let sessions = self
+            .sessions
+            .lock()
+            .map_err(|_| "Terminal session lock poisoned".to_string())?;
+        let session = sessions
+            .get(&request.session_id)
+            .ok_or_else(|| "Session not found".to_string())?;
+        let limit = request.limit.unwrap_or(MAX_HISTORY_ENTRIES);
+        let skip = session.history.len().saturating_sub(limit);
+        Ok(session.history.iter().skip(skip).cloned().collect())
+    }
+
+    pub fn kill_run_sessions(&self, run_id: &str, audit: &AuditLog) -> Vec<String> {
+        let ids: Vec<String> = match self.sessions.lock() {
+            Ok(sessions) => sessions
+                .values()
+                .filter(|session| session.info.run_id.as_deref() == Some(run_id))
+                .map(|session| session.info.id.clone())
+                .collect(),
+            Err(_) => return Vec::new(),
+        };
+        for id in &ids {
+            let _ = self.kill(
+                TerminalKillRequest {
+                    session_id: id.clone(),
+                },
+                audit,
+            );
+        }
+        ids
+    }
+
+    /// Interrupts every session, gives foreground commands `grace_ms` in total to
+    /// exit, then closes the shells. Returns the ids of the sessions that were closed.
+    pub fn shutdown(&self, grace_ms: u64, audit: &AuditLog) -> Vec<String> {
+        let targets: Vec<(String, Option<u32>)> = match self.sessions.lock() {
+            Ok(mut sessions) => sessions
+                .iter_mut()
+                .map(|(id, session)| {
+                    let _ = session.writer.write_all(b"\x03");
+                    let _ = session.writer.flush();
+                    (id.clone(), session.child.process_id())
+                })
+                .collect(),
+            Err(_) => return Vec::new(),
+        };
+        let deadline = Instant::now() + Duration::from_millis(grace_ms);
+        for (_, pid) in &targets {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            reap_descendants(*pid, remaining.as_millis() as u64);
+        }
+        let ids: Vec<String> = targets.into_iter().map(|(id, _)| id).collect();
+        for id in &ids {
+            let _ = self.kill(
+                TerminalKillRequest {
+                    session_id: id.clone(),
+                },
+                audit,
+            );
+        }
+        ids
+    }
+
+    pub fn list_sessions_filtered(
+        &self,
+        filter: &TerminalListRequest,
+    ) -> Result<Vec<TerminalSessionInfo>, String> {
+        let sessions = self.list_sessions()?;
+        Ok(sessions
+            .into_iter()
+            .filter(|info| match &filter.run_id {
+                Some(run_id) => info.run_id.as_ref() == Some(run_id),
+                None => true,
+            })
+            .filter(|info| match &filter.task_id {
+                Some(task_id) => info.task_id.as_ref() == Some(task_id),
+                None => true,
+            })
+            .filter(|info| match filter.owner.as_deref() {
+                Some("run") => info.run_id.is_some(),
+                Some("user") => info.run_id.is_none(),
+                _ => true,
+            })
+            .collect())
+    }
+
+    pub fn session_shell(&self, session_id: &str) -> Option<String> {
+        let sessions = self.sessions.lock().ok()?;
+        sessions
+            .get(session_id)
+            .map(|session| session.info.shell.clone())
+    }
+
     pub fn list_sessions(&self) -> Result<Vec<TerminalSessionInfo>, String> {
         let sessions = self
             .sessions
@@ -334,10 +625,36 @@ impl TerminalManager {
         Ok(result)
     }
 
+    pub fn output_policy(&self) -> PtyOutputPolicy {
+        load_pty_output_policy(&self.logs_dir)
+    }
+
+    /// Applies to sessions created afterwards.
+    pub fn set_output_policy(&self, policy: &PtyOutputPolicy) -> Result<(), String> {
+        save_pty_output_policy(&self.logs_dir, policy)
+    }
+
     pub fn replay(&self, request: TerminalReplayRequest) -> Result<TerminalReplayResponse, String> {
+        let live = self
+            .sessions
+            .lock()
+            .map_err(|_| "Terminal session lock poisoned".to_string())?
+            .get(&request.session_id)
+            .map(|session| session.output.clone());
+        if let Some(output) = live {
+            let (buffer, truncated) = output
+                .lock()
+                .map_err(|_| "Terminal output lock poisoned".to_string())?
+                .tail(request.max_bytes);
+            return Ok(TerminalReplayResponse {
+                session_id: request.session_id,
+                data_base64: general_purpose::STANDARD.encode(&buffer),
+                bytes: buffer.len(),
+                truncated,
+            });
+        }
         let log_path = self.log_path_for(&request.session_id);
-        let mut file = File::open(&log_path)
-            .map_err(|e| format!("Unable to open log: {}", e))?;
+        let mut file = File::open(&log_path).map_err(|e| format!("Unable to open log: {}", e))?;
         let metadata = file
             .metadata()
             .map_err(|e| format!("Unable to read log metadata: {}", e))?;
@@ -360,12 +677,83 @@ impl TerminalManager {
         })
     }
 
+    /// Finds `pattern` in the session's log line by line, so the frontend can
+    /// search the scrollback without loading all of it.
+    pub fn search_scrollback(
+        &self,
+        request: TerminalSearchRequest,
+    ) -> Result<TerminalSearchResponse, String> {
+        if request.pattern.is_empty() {
+            return Err("Search pattern is empty".to_string());
+        }
+        let source = if request.regex {
+            request.pattern.clone()
+        } else {
+            regex::escape(&request.pattern)
+        };
+        let matcher = regex::RegexBuilder::new(&source)
+            .case_insensitive(!request.case_sensitive)
+            .build()
+            .map_err(|e| format!("Invalid search pattern: {}", e))?;
+        let max_results = request
+            .max_results
+            .unwrap_or(MAX_SCROLLBACK_MATCHES)
+            .clamp(1, MAX_SCROLLBACK_MATCHES);
+        let file = File::open(self.log_path_for(&request.session_id))
+            .map_err(|e| format!("Unable to open log: {}", e))?;
+        let mut reader = BufReader::new(file);
+        let mut matches = Vec::new();
+        let mut truncated = false;
+        let mut offset = 0u64;
+        let mut line_number = 0;
+        let mut buffer = Vec::new();
+        loop {
+            buffer.clear();
+            let read = reader
+                .read_until(b'\n', &mut buffer)
+                .map_err(|e| format!("Unable to read log: {}", e))?;
+            if read == 0 {
+                break;
+            }
+            line_number += 1;
+            let text = String::from_utf8_lossy(&buffer);
+            let line = strip_ansi_codes(text.trim_end_matches(['\r', '\n']));
+            if matcher.is_match(&line) {
+                if matches.len() == max_results {
+                    truncated = true;
+                    break;
+                }
+                matches.push(ScrollbackMatch {
+                    offset,
+                    line_number,
+                    line,
+                });
+            }
+            offset += read as u64;
+        }
+        Ok(TerminalSearchResponse {
+            session_id: request.session_id,
+            matches,
+            truncated,
+        })
+    }
+
     pub fn exec_interactive(
         &self,
         request: TerminalExecRequest,
         cwd: PathBuf,
         audit: &AuditLog,
     ) -> Result<ToolResult, String> {
+        if let Err(reason) = enforce_command_policy(&request.command, audit) {
+            audit.write(AuditEntry {
+                timestamp_ms: now_ms(),
+                action: "terminal.exec_interactive.blocked".to_string(),
+                session_id: request.session_id.clone(),
+                command: Some(request.command.clone()),
+                payload: serde_json::json!({ "reason": reason }),
+            });
+            return Err(reason);
+        }
         if let Some(session_id) = request.session_id.clone() {
             return self.exec_in_session(request, session_id, audit);
         }
@@ -399,10 +787,7 @@ impl TerminalManager {
 
         let mut cmd = CommandBuilder::new(shell.clone());
         cmd.cwd(cwd.clone());
-        let mut child = pair
-            .slave
-            .spawn_command(cmd)
-            .map_err(|e| e.to_string())?;
+        let mut child = pair.slave.spawn_command(cmd).map_err(|e| e.to_string())?;
 
         let reader = pair.master.try_clone_reader().map_err(|e| e.to_string())?;
         let mut writer = pair.master.take_writer().map_err(|e| e.to_string())?;
@@ -414,7 +799,8 @@ impl TerminalManager {
             .map_err(|e| e.to_string())?;
         writer.flush().map_err(|e| e.to_string())?;
 
-        let (raw_output, mut exit_code, truncated, mut timed_out) =
+        let sampler = ResourceSampler::process_tree(child.process_id());
+        let (raw_output, mut exit_code, truncated, mut timed_out, duration_ms) =
             read_until_markers_from_reader(
                 reader,
                 &start_marker,
@@ -422,12 +808,20 @@ impl TerminalManager {
                 timeout_ms,
                 max_bytes,
             );
+        let resources = sampler.finish();
         let had_timeout = timed_out;
         if timed_out && !raw_output.trim().is_empty() {
             exit_code = Some(0);
             timed_out = false;
         }
 
+        let termination = if had_timeout {
+            let _ = writer.write_all(b"\x03");
+            let _ = writer.flush();
+            Some(reap_descendants(child.process_id(), KILL_GRACE_MS))
+        } else {
+            None
+        };
         let _ = child.kill();
         let _ = child.wait();
 
@@ -455,6 +849,14 @@ impl TerminalManager {
         } else {
             None
         };
+        let stderr_excerpt = match (&termination, &prompt) {
+            (Some(report), None) => Some(match stderr_excerpt {
+                Some(text) => format!("{}\n{}", text, report.summary()),
+                None => report.summary(),
+            }),
+            _ => stderr_excerpt,
+        };
+        let stderr_excerpt = with_budget_note(stderr_excerpt, &resources);
 
         audit.write(AuditEntry {
             timestamp_ms: now_ms(),
@@ -468,6 +870,8 @@ impl TerminalManager {
                 "timeout_ms": timeout_ms,
                 "max_bytes": max_bytes,
                 "truncated": truncated,
+                "resources": resources,
+                "termination": termination,
             }),
         });
 
@@ -480,6 +884,9 @@ impl TerminalManager {
                 "truncated": truncated,
                 "prompt": prompt,
                 "command": command,
+                "duration_ms": duration_ms,
+                "resources": resources,
+                "termination": termination,
             })),
             next_suggestion: None,
             requires_user,
@@ -494,7 +901,7 @@ impl TerminalManager {
     ) -> Result<ToolResult, String> {
         let timeout_ms = request.timeout_ms.unwrap_or(15000);
         let max_bytes = request.max_bytes.unwrap_or(24000).min(200_000);
-        let (shell, log_path, start_marker, end_marker_prefix, start_pos, command) = {
+        let (shell, output, start_marker, end_marker_prefix, start_pos, command, sampler) = {
             let mut sessions = self
                 .sessions
                 .lock()
@@ -503,14 +910,11 @@ impl TerminalManager {
                 .get_mut(&session_id)
                 .ok_or_else(|| "Session not found".to_string())?;
             let shell = session.info.shell.clone();
-            let log_path = PathBuf::from(&session.info.log_path);
-            let start_pos = OpenOptions::new()
-                .read(true)
-                .create(true)
-                .open(&log_path)
-                .and_then(|file| file.metadata())
-                .map(|meta| meta.len())
-                .unwrap_or(0);
+            let output = session.output.clone();
+            let start_pos = output
+                .lock()
+                .map_err(|_| "Terminal output lock poisoned".to_string())?
+                .end();
             let token = short_token();
             let (start_marker, end_marker_prefix, start_cmd, end_cmd, wrap_script) =
                 build_shell_markers(&shell, &token);
@@ -521,17 +925,29 @@ impl TerminalManager {
                 .write_all(command_block.as_bytes())
                 .map_err(|e| e.to_string())?;
             session.writer.flush().map_err(|e| e.to_string())?;
-            (shell, log_path, start_marker, end_marker_prefix, start_pos, command)
+            session.push_history(&command, "exec");
+            let sampler = ResourceSampler::descendants(session.child.process_id());
+            (
+                shell,
+                output,
+                start_marker,
+                end_marker_prefix,
+                start_pos,
+                command,
+                sampler,
+            )
         };
 
-        let (raw_output, mut exit_code, truncated, mut timed_out) = read_until_markers_from_log(
-            &log_path,
-            start_pos,
-            &start_marker,
-            &end_marker_prefix,
-            timeout_ms,
-            max_bytes,
-        )?;
+        let (raw_output, mut exit_code, truncated, mut timed_out, duration_ms) =
+            read_until_markers_from_ring(
+                &output,
+                start_pos,
+                &start_marker,
+                &end_marker_prefix,
+                timeout_ms,
+                max_bytes,
+            )?;
+        let resources = sampler.finish();
         let had_timeout = timed_out;
         if timed_out && !raw_output.trim().is_empty() {
             exit_code = Some(0);
@@ -543,6 +959,11 @@ impl TerminalManager {
             None
         };
         let requires_user = prompt.is_some();
+        let termination = if had_timeout && !requires_user {
+            self.interrupt_session(&session_id)
+        } else {
+            None
+        };
         if requires_user {
             exit_code = None;
         }
@@ -561,6 +982,14 @@ impl TerminalManager {
         } else {
             None
         };
+        let stderr_excerpt = match (&termination, &prompt) {
+            (Some(report), None) => Some(match stderr_excerpt {
+                Some(text) => format!("{}\n{}", text, report.summary()),
+                None => report.summary(),
+            }),
+            _ => stderr_excerpt,
+        };
+        let stderr_excerpt = with_budget_note(stderr_excerpt, &resources);
 
         audit.write(AuditEntry {
             timestamp_ms: now_ms(),
@@ -573,6 +1002,8 @@ impl TerminalManager {
                 "timeout_ms": timeout_ms,
                 "max_bytes": max_bytes,
                 "truncated": truncated,
+                "resources": resources,
+                "termination": termination,
             }),
         });
 
@@ -585,12 +1016,26 @@ impl TerminalManager {
                 "truncated": truncated,
                 "prompt": prompt,
                 "command": command,
+                "duration_ms": duration_ms,
+                "resources": resources,
+                "termination": termination,
             })),
             next_suggestion: None,
             requires_user,
         })
     }
 
+    fn interrupt_session(&self, session_id: &str) -> Option<TerminationReport> {
+        let pid = {
+            let mut sessions = self.sessions.lock().ok()?;
+            let session = sessions.get_mut(session_id)?;
+            let _ = session.writer.write_all(b"\x03");
+            let _ = session.writer.flush();
+            session.child.process_id()
+        };
+        Some(reap_descendants(pid, KILL_GRACE_MS))
+    }
+
     pub fn set_title(
         &self,
         request: TerminalSetTitleRequest,
@@ -608,6 +1053,7 @@ impl TerminalManager {
             .get_mut(&request.session_id)
             .ok_or_else(|| "Session not found".to_string())?;
         session.info.title = title.clone();
+        self.persist_meta(&session.info);
 
         audit.write(AuditEntry {
             timestamp_ms: now_ms(),
@@ -666,48 +1112,126 @@ impl TerminalManager {
         Ok(order.clone())
     }
 
+    pub fn update_session_meta(
+        &self,
+        request: TerminalUpdateMetaRequest,
+        audit: &AuditLog,
+    ) -> Result<TerminalSessionInfo, String> {
+        let mut sessions = self
+            .sessions
+            .lock()
+            .map_err(|_| "Terminal session lock poisoned".to_string())?;
+        let session = sessions
+            .get_mut(&request.session_id)
+            .ok_or_else(|| "Session not found".to_string())?;
+        if let Some(title) = &request.title {
+            let title = title.trim();
+            if title.is_empty() {
+                return Err("Title cannot be empty".to_string());
+            }
+            session.info.title = title.to_string();
+        }
+        if let Some(color) = &request.color {
+            session.info.color = non_empty(color);
+        }
+        if let Some(icon) = &request.icon {
+            session.info.icon = non_empty(icon);
+        }
+        if let Some(profile) = &request.profile {
+            session.info.profile = non_empty(profile);
+        }
+        let info = session.info.clone();
+        drop(sessions);
+        self.persist_meta(&info);
+
+        audit.write(AuditEntry {
+            timestamp_ms: now_ms(),
+            action: "terminal.update_session_meta".to_string(),
+            session_id: Some(request.session_id),
+            command: None,
+            payload: serde_json::json!({
+                "title": info.title,
+                "color": info.color,
+                "icon": info.icon,
+                "profile": info.profile,
+            }),
+        });
+
+        Ok(info)
+    }
+
+    fn meta_path(&self) -> PathBuf {
+        self.logs_dir.join("sessions-meta.json")
+    }
+
+    fn load_meta_store(&self) -> TerminalMetaStore {
+        std::fs::read_to_string(self.meta_path())
+            .ok()
+            .and_then(|raw| serde_json::from_str(&raw).ok())
+            .unwrap_or_default()
+    }
+
+    fn persist_meta(&self, info: &TerminalSessionInfo) {
+        let mut store = self.load_meta_store();
+        let meta = TerminalSessionMeta {
+            title: Some(info.title.clone()),
+            color: info.color.clone(),
+            icon: info.icon.clone(),
+            profile: info.profile.clone(),
+        };
+        if let Some(profile) = &info.profile {
+            store.profiles.insert(profile.clone(), meta.clone());
+        }
+        store.sessions.insert(info.id.clone(), meta);
+        if let Ok(data) = serde_json::to_vec_pretty(&store) {
+            let _ = std::fs::write(self.meta_path(), data);
+        }
+    }
+
     fn log_path_for(&self, session_id: &str) -> PathBuf {
         self.logs_dir.join(format!("pty-{}.log", session_id))
     }
 }
 
-fn spawn_reader_thread(
-    app_handle: AppHandle,
-    session_id: String,
-    log_path: PathBuf,
-    mut reader: Box<dyn Read + Send>,
-) {
-    std::thread::spawn(move || {
-        if let Some(parent) = log_path.parent() {
-            let _ = create_dir_all(parent);
+fn redact_command(command: &str) -> String {
+    const SECRET_FLAGS: &[&str] = &["--password", "--passwd", "--token", "--secret", "--api-key"];
+    const SECRET_NAMES: &[&str] = &["PASSWORD", "PASSWD", "TOKEN", "SECRET", "API_KEY", "APIKEY"];
+    let mut redacted = Vec::new();
+    let mut mask_next = false;
+    for part in command.split(' ') {
+        if mask_next {
+            redacted.push("***".to_string());
+            mask_next = false;
+            continue;
         }
-        let mut log_file = OpenOptions::new()
-            .create(true)
-            .append(true)
-            .open(&log_path)
-            .ok();
-        let mut buffer = [0u8; 8192];
-        loop {
-            match reader.read(&mut buffer) {
-                Ok(0) => break,
-                Ok(count) => {
-                    let bytes = &buffer[..count];
-                    let payload = TerminalOutputEvent {
-                        session_id: session_id.clone(),
-                        data_base64: general_purpose::STANDARD.encode(bytes),
-                    };
-                    let _ = app_handle.emit(TERMINAL_OUTPUT_EVENT, payload);
-                    if let Some(file) = log_file.as_mut() {
-                        let _ = file.write_all(bytes);
-                    }
-                }
-                Err(_) => break,
+        let lower = part.to_lowercase();
+        if SECRET_FLAGS.contains(&lower.as_str()) || lower == "bearer" {
+            redacted.push(part.to_string());
+            mask_next = true;
+            continue;
+        }
+        if let Some((key, _)) = part.split_once('=') {
+            let upper = key.trim_start_matches('-').to_uppercase().replace('-', "_");
+            if SECRET_NAMES.iter().any(|name| upper.contains(name)) {
+                redacted.push(format!("{}=***", key));
+                continue;
             }
         }
-    });
+        redacted.push(part.to_string());
+    }
+    redacted.join(" ")
 }
 
-fn default_shell() -> &'static str {
+fn non_empty(value: &str) -> Option<String> {
+    let trimmed = value.trim();
+    if trimmed.is_empty() {
+        None
+    } else {
+        Some(trimmed.to_string())
+    }
+}
+
+pub(crate) fn default_shell() -> &'static str {
     if cfg!(windows) {
         "powershell.exe"
     } else {
@@ -715,22 +1239,97 @@ fn default_shell() -> &'static str {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum ShellKind {
+    Posix,
+    Fish,
+    PowerShell,
+    Cmd,
+    Nushell,
+}
+
+impl ShellKind {
+    fn detect(shell: &str) -> Self {
+        let program = shell
+            .trim()
+            .rsplit(['/', '\\'])
+            .next()
+            .unwrap_or_default()
+            .to_lowercase();
+        let program = program.strip_suffix(".exe").unwrap_or(&program);
+        match program {
+            "powershell" | "pwsh" => ShellKind::PowerShell,
+            "cmd" => ShellKind::Cmd,
+            "nu" | "nushell" => ShellKind::Nushell,
+            "fish" => ShellKind::Fish,
+            _ => ShellKind::Posix,
+        }
+    }
+
+    fn exit_code_expr(self) -> &'static str {
+        match self {
+            ShellKind::Posix => "$?",
+            ShellKind::Fish => "$status",
+            ShellKind::PowerShell => "$LASTEXITCODE",
+            ShellKind::Cmd => "%errorlevel%",
+            ShellKind::Nushell => "$env.LAST_EXIT_CODE",
+        }
+    }
+}
+
 fn build_shell_markers(shell: &str, token: &str) -> (String, String, String, String, bool) {
     let start_marker = format!("__TAURIHANDS_START:{}__", token);
     let end_marker_prefix = format!("__TAURIHANDS_END:{}:", token);
-    let lower = shell.to_lowercase();
-    if lower.contains("powershell") || lower.contains("pwsh") {
-        let start_cmd = format!("Write-Output '{}'", start_marker);
-        let end_cmd = format!(
-            "Write-Output ('{}' + $LASTEXITCODE)",
-            end_marker_prefix
-        );
-        (start_marker, end_marker_prefix, start_cmd, end_cmd, true)
-    } else {
-        let start_cmd = format!("echo \"{}\"", start_marker);
-        let end_cmd = format!("echo \"{}$?\"", end_marker_prefix);
-        (start_marker, end_marker_prefix, start_cmd, end_cmd, false)
+    let kind = ShellKind::detect(shell);
+    let wrap_script = kind == ShellKind::PowerShell;
+    let exit_code = kind.exit_code_expr();
+    if let Some(helper) = marker_helper_path() {
+        let helper = match kind {
+            ShellKind::PowerShell => Some(format!("& '{}'", helper.replace('\'', "''"))),
+            ShellKind::Cmd => Some(format!("\"{}\"", helper)),
+            ShellKind::Nushell if !helper.contains('\'') => Some(format!("^'{}'", helper)),
+            ShellKind::Nushell => None,
+            ShellKind::Posix | ShellKind::Fish => {
+                Some(format!("'{}'", helper.replace('\'', "'\\''")))
+            }
+        };
+        if let Some(helper) = helper {
+            let start_cmd = format!("{} --th-mark {} start", helper, token);
+            let end_cmd = format!("{} --th-mark {} end {}", helper, token, exit_code);
+            return (
+                start_marker,
+                end_marker_prefix,
+                start_cmd,
+                end_cmd,
+                wrap_script,
+            );
+        }
     }
+    let (start_cmd, end_cmd) = match kind {
+        ShellKind::PowerShell => (
+            format!("Write-Output '{}'", start_marker),
+            format!("Write-Output ('{}' + {})", end_marker_prefix, exit_code),
+        ),
+        ShellKind::Cmd => (
+            format!("echo {}", start_marker),
+            format!("echo {}{}", end_marker_prefix, exit_code),
+        ),
+        ShellKind::Nushell => (
+            format!("print \"{}\"", start_marker),
+            format!("print $\"{}({})\"", end_marker_prefix, exit_code),
+        ),
+        ShellKind::Posix | ShellKind::Fish => (
+            format!("echo \"{}\"", start_marker),
+            format!("echo \"{}{}\"", end_marker_prefix, exit_code),
+        ),
+    };
+    (
+        start_marker,
+        end_marker_prefix,
+        start_cmd,
+        end_cmd,
+        wrap_script,
+    )
 }
 
 fn build_command_block(command: &str, start_cmd: &str, end_cmd: &str, wrap_script: bool) -> String {
@@ -758,6 +1357,18 @@ fn build_command_block(command: &str, start_cmd: &str, end_cmd: &str, wrap_scrip
     command_block
 }
 
+/// Shell commands keep running past a budget, so they are only flagged.
+fn with_budget_note(stderr_excerpt: Option<String>, resources: &ResourceUsage) -> Option<String> {
+    let Some(reason) = &resources.over_budget else {
+        return stderr_excerpt;
+    };
+    let note = format!("Over the resource budget: {}.", reason);
+    Some(match stderr_excerpt {
+        Some(text) => format!("{}\n{}", text, note),
+        None => note,
+    })
+}
+
 fn detect_confirmation_prompt(output: &str) -> Option<String> {
     let cleaned = strip_ansi_codes(output);
     let mut candidates: Vec<String> = cleaned
@@ -911,7 +1522,15 @@ fn extract_between_markers(
     raw: &str,
     start_marker: &str,
     end_marker_prefix: &str,
-) -> Option<(String, Option<i32>)> {
+) -> Option<(String, Option<i32>, Option<u128>)> {
+    if let Some(token) = start_marker
+        .strip_prefix("__TAURIHANDS_START:")
+        .and_then(|rest| rest.strip_suffix("__"))
+    {
+        if let Some(found) = extract_between_frames(raw, token) {
+            return Some(found);
+        }
+    }
     let start_idx = find_marker_line(raw, start_marker)?;
     let after_marker = start_idx + start_marker.len();
     let start_line_end = raw[after_marker..]
@@ -927,7 +1546,100 @@ fn extract_between_markers(
         .unwrap_or(raw.len());
     let exit_segment = &raw[after_end..line_end];
     let exit_code = parse_exit_code(exit_segment);
-    Some((captured, exit_code))
+    Some((captured, exit_code, None))
+}
+
+fn extract_between_frames(raw: &str, token: &str) -> Option<(String, Option<i32>, Option<u128>)> {
+    let frames = parse_marker_frames(raw);
+    let start = frames
+        .iter()
+        .find(|(_, _, frame)| frame.token == token && frame.phase == "start");
+    let capture_from = start.map(|(_, end, _)| *end).unwrap_or(0);
+    let (end_idx, _, end_frame) = frames.iter().find(|(idx, _, frame)| {
+        frame.token == token && frame.phase == "end" && *idx >= capture_from
+    })?;
+    let captured = raw[capture_from..*end_idx]
+        .trim_start_matches(['\r', '\n'])
+        .to_string();
+    let duration = start.map(|(_, _, frame)| end_frame.ts_ms.saturating_sub(frame.ts_ms));
+    Some((captured, end_frame.exit_code, duration))
+}
+
+#[derive(Serialize, Deserialize)]
+struct MarkerFrame {
+    #[serde(rename = "t")]
+    token: String,
+    #[serde(rename = "p")]
+    phase: String,
+    #[serde(rename = "c", default)]
+    exit_code: Option<i32>,
+    #[serde(rename = "ms")]
+    ts_ms: u128,
+}
+
+type MarkerCapture = (String, Option<i32>, bool, bool, Option<u128>);
+
+const MARKER_FRAME_PREFIX: &str = "<<THMARK:";
+const MARKER_FRAME_SUFFIX: &str = ">>";
+
+fn parse_marker_frames(raw: &str) -> Vec<(usize, usize, MarkerFrame)> {
+    let mut frames = Vec::new();
+    let mut cursor = 0;
+    while let Some(found) = raw[cursor..].find(MARKER_FRAME_PREFIX) {
+        let idx = cursor + found;
+        let payload_start = idx + MARKER_FRAME_PREFIX.len();
+        let Some(len) = raw[payload_start..].find(MARKER_FRAME_SUFFIX) else {
+            break;
+        };
+        let payload_end = payload_start + len;
+        cursor = payload_end + MARKER_FRAME_SUFFIX.len();
+        let frame = general_purpose::STANDARD
+            .decode(raw[payload_start..payload_end].trim())
+            .ok()
+            .and_then(|bytes| serde_json::from_slice::<MarkerFrame>(&bytes).ok());
+        if let Some(frame) = frame {
+            let line_end = raw[cursor..]
+                .find('\n')
+                .map(|offset| cursor + offset + 1)
+                .unwrap_or(cursor);
+            frames.push((idx, line_end, frame));
+        }
+    }
+    frames
+}
+
+/// Entry point for `--th-mark <token> <start|end> [exit_code]`, used by exec markers.
+pub fn run_marker_helper(args: &[String]) -> i32 {
+    let (Some(token), Some(phase)) = (args.first(), args.get(1)) else {
+        eprintln!("usage: --th-mark <token> <start|end> [exit_code]");
+        return 2;
+    };
+    let exit_code = args
+        .get(2)
+        .and_then(|value| value.trim().parse::<i32>().ok());
+    let frame = MarkerFrame {
+        token: token.clone(),
+        phase: phase.clone(),
+        exit_code,
+        ts_ms: now_ms(),
+    };
+    let payload = serde_json::to_vec(&frame).unwrap_or_default();
+    println!(
+        "{}{}{}",
+        MARKER_FRAME_PREFIX,
+        general_purpose::STANDARD.encode(payload),
+        MARKER_FRAME_SUFFIX
+    );
+    exit_code.unwrap_or(0)
+}
+
+fn marker_helper_path() -> Option<String> {
+    if cfg!(windows) && !cfg!(debug_assertions) {
+        return None;
+    }
+    std::env::current_exe()
+        .ok()
+        .map(|path| path.to_string_lossy().to_string())
 }
 
 fn find_marker_line(raw: &str, marker: &str) -> Option<usize> {
@@ -980,10 +1692,11 @@ fn read_until_markers_from_reader(
     end_marker_prefix: &str,
     timeout_ms: u64,
     max_bytes: usize,
-) -> (String, Option<i32>, bool, bool) {
+) -> MarkerCapture {
     let deadline = Instant::now() + Duration::from_millis(timeout_ms);
     let mut raw_output = String::new();
     let mut exit_code = None;
+    let mut duration_ms = None;
     let (sender, receiver) = std::sync::mpsc::channel::<Vec<u8>>();
 
     std::thread::spawn(move || {
@@ -1009,19 +1722,20 @@ fn read_until_markers_from_reader(
             Ok(bytes) => {
                 let chunk = String::from_utf8_lossy(&bytes);
                 raw_output.push_str(&chunk);
-                if let Some((captured, code)) =
+                if let Some((captured, code, duration)) =
                     extract_between_markers(&raw_output, start_marker, end_marker_prefix)
                 {
                     raw_output = captured;
                     exit_code = code;
+                    duration_ms = duration;
                     break;
                 }
                 if raw_output.len() > max_bytes * 4 {
                     if let Some(idx) = raw_output.find(start_marker) {
                         raw_output = raw_output[idx..].to_string();
                     } else {
-                        raw_output =
-                            raw_output[raw_output.len().saturating_sub(max_bytes * 2)..].to_string();
+                        raw_output = raw_output[raw_output.len().saturating_sub(max_bytes * 2)..]
+                            .to_string();
                     }
                 }
             }
@@ -1033,60 +1747,61 @@ fn read_until_markers_from_reader(
     }
 
     let timed_out = exit_code.is_none();
-    let cleaned = sanitize_terminal_output(&raw_output);
+    let cleaned = collapse_progress(&sanitize_terminal_output(&raw_output));
     let (stdout_excerpt, truncated) = truncate_utf8(&cleaned, max_bytes);
-    (stdout_excerpt, exit_code, truncated, timed_out)
+    (stdout_excerpt, exit_code, truncated, timed_out, duration_ms)
 }
 
-fn read_until_markers_from_log(
-    log_path: &PathBuf,
+/// Follows the session's output ring from `start_pos` rather than the log
+/// file, which may be rotated while the command runs.
+fn read_until_markers_from_ring(
+    output: &Mutex<OutputRing>,
     start_pos: u64,
     start_marker: &str,
     end_marker_prefix: &str,
     timeout_ms: u64,
     max_bytes: usize,
-) -> Result<(String, Option<i32>, bool, bool), String> {
-    let mut file = OpenOptions::new()
-        .read(true)
-        .create(true)
-        .open(log_path)
-        .map_err(|e| format!("Unable to read log: {}", e))?;
-    file.seek(SeekFrom::Start(start_pos))
-        .map_err(|e| format!("Unable to seek log: {}", e))?;
-
+) -> Result<MarkerCapture, String> {
     let deadline = Instant::now() + Duration::from_millis(timeout_ms);
     let mut raw_output = String::new();
     let mut exit_code = None;
-    let mut buffer = [0u8; 8192];
+    let mut duration_ms = None;
+    let mut position = start_pos;
 
     while Instant::now() < deadline {
-        let count = file.read(&mut buffer).map_err(|e| e.to_string())?;
-        if count == 0 {
+        let (bytes, next) = output
+            .lock()
+            .map_err(|_| "Terminal output lock poisoned".to_string())?
+            .read_from(position);
+        position = next;
+        if bytes.is_empty() {
             std::thread::sleep(Duration::from_millis(40));
             continue;
         }
-        let chunk = String::from_utf8_lossy(&buffer[..count]);
+        let chunk = String::from_utf8_lossy(&bytes);
         raw_output.push_str(&chunk);
-        if let Some((captured, code)) =
+        if let Some((captured, code, duration)) =
             extract_between_markers(&raw_output, start_marker, end_marker_prefix)
         {
             raw_output = captured;
             exit_code = code;
+            duration_ms = duration;
             break;
         }
         if raw_output.len() > max_bytes * 4 {
             if let Some(idx) = raw_output.find(start_marker) {
                 raw_output = raw_output[idx..].to_string();
             } else {
-                raw_output = raw_output[raw_output.len().saturating_sub(max_bytes * 2)..].to_string();
+                raw_output =
+                    raw_output[raw_output.len().saturating_sub(max_bytes * 2)..].to_string();
             }
         }
     }
 
     let timed_out = exit_code.is_none();
-    let cleaned = sanitize_terminal_output(&raw_output);
+    let cleaned = collapse_progress(&sanitize_terminal_output(&raw_output));
     let (stdout_excerpt, truncated) = truncate_utf8(&cleaned, max_bytes);
-    Ok((stdout_excerpt, exit_code, truncated, timed_out))
+    Ok((stdout_excerpt, exit_code, truncated, timed_out, duration_ms))
 }
 
 fn parse_exit_code(value: &str) -> Option<i32> {
@@ -1131,6 +1846,12 @@ fn sanitize_terminal_output(value: &str) -> String {
             continue;
         }
         if ch == '\r' {
+            if matches!(chars.peek(), Some('\n')) {
+                continue;
+            }
+            // A bare carriage return rewinds to the start of the line, as a terminal would.
+            let line_start = output.rfind('\n').map(|idx| idx + 1).unwrap_or(0);
+            output.truncate(line_start);
             continue;
         }
         output.push(ch);
@@ -0,0 +1,351 @@
+use base64::{engine::general_purpose, Engine as _};
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fs::{create_dir_all, File, OpenOptions};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::services::events::{SharedBinarySink, SharedEventSink};
+
+const TERMINAL_OUTPUT_EVENT: &str = "terminal-output";
+
+/// First byte of every binary output frame, bumped when the layout changes.
+const OUTPUT_FRAME_VERSION: u8 = 1;
+
+/// How a session's output reaches the frontend: `terminal-output` events with
+/// base64 data, or raw frames on a binary channel the consumer opened.
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OutputTransport {
+    Json,
+    Binary,
+}
+
+/// The binary channel a session's output goes to, if one was negotiated.
+/// Shared between the session and its output pump.
+pub type BinaryOutput = Arc<Mutex<Option<SharedBinarySink>>>;
+
+/// How terminal output is buffered, streamed to the frontend and kept on disk.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct PtyOutputPolicy {
+    /// Bytes of recent output kept in memory per session for replay and
+    /// command capture.
+    pub ring_buffer_bytes: usize,
+    /// Buffered output is flushed once it reaches this size...
+    pub flush_bytes: usize,
+    /// ...or once the oldest buffered byte is this old.
+    pub flush_interval_ms: u64,
+    /// Upper bound on `terminal-output` events per second and session. While
+    /// the limit holds output back, only the newest `max_pending_bytes` are
+    /// kept for the next event; the rest is reported as dropped.
+    pub max_events_per_sec: u32,
+    pub max_pending_bytes: usize,
+    /// A session log is rotated to `pty-<id>.log.1` past this size.
+    pub max_log_bytes: u64,
+    /// Logs of closed sessions older than this are deleted on startup.
+    pub log_retention_days: u64,
+}
+
+impl Default for PtyOutputPolicy {
+    fn default() -> Self {
+        Self {
+            ring_buffer_bytes: 1024 * 1024,
+            flush_bytes: 16 * 1024,
+            flush_interval_ms: 16,
+            max_events_per_sec: 30,
+            max_pending_bytes: 256 * 1024,
+            max_log_bytes: 16 * 1024 * 1024,
+            log_retention_days: 7,
+        }
+    }
+}
+
+pub fn pty_output_policy_path(logs_dir: &Path) -> PathBuf {
+    logs_dir.join("output-policy.json")
+}
+
+pub fn load_pty_output_policy(logs_dir: &Path) -> PtyOutputPolicy {
+    std::fs::read_to_string(pty_output_policy_path(logs_dir))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_pty_output_policy(logs_dir: &Path, policy: &PtyOutputPolicy) -> Result<(), String> {
+    create_dir_all(logs_dir).map_err(|e| e.to_string())?;
+    let data = serde_json::to_vec_pretty(policy).map_err(|e| e.to_string())?;
+    std::fs::write(pty_output_policy_path(logs_dir), data).map_err(|e| e.to_string())
+}
+
+/// The most recent output of a session, addressed by absolute byte offsets so
+/// readers can resume where they left off.
+pub struct OutputRing {
+    data: VecDeque<u8>,
+    capacity: usize,
+    total: u64,
+}
+
+impl OutputRing {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            data: VecDeque::new(),
+            capacity: capacity.max(1),
+            total: 0,
+        }
+    }
+
+    pub fn push(&mut self, bytes: &[u8]) {
+        self.total += bytes.len() as u64;
+        let bytes = &bytes[bytes.len().saturating_sub(self.capacity)..];
+        let overflow = (self.data.len() + bytes.len()).saturating_sub(self.capacity);
+        self.data.drain(..overflow);
+        self.data.extend(bytes);
+    }
+
+    /// Offset one past the last byte written.
+    pub fn end(&self) -> u64 {
+        self.total
+    }
+
+    /// Bytes from `offset` onward and the offset to continue from. Output that
+    /// has already left the ring is skipped.
+    pub fn read_from(&self, offset: u64) -> (Vec<u8>, u64) {
+        let start = self.total - self.data.len() as u64;
+        let skip = offset.saturating_sub(start).min(self.data.len() as u64) as usize;
+        (self.data.iter().skip(skip).copied().collect(), self.total)
+    }
+
+    /// The last `max_bytes` bytes and whether older output exists.
+    pub fn tail(&self, max_bytes: usize) -> (Vec<u8>, bool) {
+        let skip = self.data.len().saturating_sub(max_bytes);
+        let bytes = self.data.iter().skip(skip).copied().collect();
+        (bytes, skip > 0 || self.total > self.data.len() as u64)
+    }
+}
+
+/// Append-only session log that rotates to a single `.1` generation.
+struct RotatingLog {
+    path: PathBuf,
+    file: Option<File>,
+    written: u64,
+    max_bytes: u64,
+}
+
+impl RotatingLog {
+    fn open(path: PathBuf, max_bytes: u64) -> Self {
+        let file = open_append(&path);
+        let written = file
+            .as_ref()
+            .and_then(|file| file.metadata().ok())
+            .map(|meta| meta.len())
+            .unwrap_or(0);
+        Self {
+            path,
+            file,
+            written,
+            max_bytes,
+        }
+    }
+
+    fn write(&mut self, bytes: &[u8]) {
+        if self.max_bytes > 0 && self.written + bytes.len() as u64 > self.max_bytes {
+            self.file = None;
+            let _ = std::fs::rename(&self.path, rotated_path(&self.path));
+            self.file = open_append(&self.path);
+            self.written = 0;
+        }
+        if let Some(file) = self.file.as_mut() {
+            if file.write_all(bytes).is_ok() {
+                self.written += bytes.len() as u64;
+            }
+        }
+    }
+}
+
+fn open_append(path: &Path) -> Option<File> {
+    if let Some(parent) = path.parent() {
+        let _ = create_dir_all(parent);
+    }
+    OpenOptions::new().create(true).append(true).open(path).ok()
+}
+
+fn rotated_path(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".1");
+    path.with_file_name(name)
+}
+
+/// Deletes `pty-*.log` files (and their rotations) untouched for longer than
+/// the retention period. Runs before any session of this manager exists, so
+/// only logs of closed sessions are affected.
+pub fn compact_logs(logs_dir: &Path, policy: &PtyOutputPolicy) {
+    let Ok(entries) = std::fs::read_dir(logs_dir) else {
+        return;
+    };
+    let retention = Duration::from_secs(policy.log_retention_days * 24 * 60 * 60);
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if !name.starts_with("pty-") || !(name.ends_with(".log") || name.ends_with(".log.1")) {
+            continue;
+        }
+        let age = entry
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok());
+        if age.is_some_and(|age| age > retention) {
+            let _ = std::fs::remove_file(entry.path());
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct TerminalOutputEvent {
+    session_id: String,
+    data_base64: String,
+    #[serde(skip_serializing_if = "is_zero")]
+    dropped_bytes: u64,
+}
+
+fn is_zero(value: &u64) -> bool {
+    *value == 0
+}
+
+/// `[version: u8][id length: u16 LE][session id][dropped bytes: u64 LE][data]`,
+/// the fields of a `terminal-output` event without base64 or JSON.
+pub fn encode_output_frame(session_id: &str, dropped_bytes: u64, data: &[u8]) -> Vec<u8> {
+    let id = session_id.as_bytes();
+    let id = &id[..id.len().min(u16::MAX as usize)];
+    let mut frame = Vec::with_capacity(1 + 2 + id.len() + 8 + data.len());
+    frame.push(OUTPUT_FRAME_VERSION);
+    frame.extend_from_slice(&(id.len() as u16).to_le_bytes());
+    frame.extend_from_slice(id);
+    frame.extend_from_slice(&dropped_bytes.to_le_bytes());
+    frame.extend_from_slice(data);
+    frame
+}
+
+/// Sends a frame on the session's binary channel. Returns false when there is
+/// none or it is gone; a dead channel is dropped so later output goes back to
+/// JSON events.
+fn send_binary_frame(binary: &BinaryOutput, frame: impl FnOnce() -> Vec<u8>) -> bool {
+    let Ok(mut slot) = binary.lock() else {
+        return false;
+    };
+    let Some(sink) = slot.as_ref() else {
+        return false;
+    };
+    if sink.send_frame(frame()).is_ok() {
+        return true;
+    }
+    *slot = None;
+    false
+}
+
+/// Reads a session's PTY on one thread, recording everything to the ring and
+/// the log, and coalesces what is streamed to the frontend on another so a
+/// command printing megabytes does not flood the event bus. Streamed output
+/// goes to the session's binary channel while it has one.
+pub fn spawn_output_pump(
+    sink: SharedEventSink,
+    session_id: String,
+    mut reader: Box<dyn Read + Send>,
+    ring: Arc<Mutex<OutputRing>>,
+    binary: BinaryOutput,
+    log_path: PathBuf,
+    policy: PtyOutputPolicy,
+) {
+    let (sender, receiver) = channel::<Vec<u8>>();
+    let max_log_bytes = policy.max_log_bytes;
+    std::thread::spawn(move || {
+        let mut log = RotatingLog::open(log_path, max_log_bytes);
+        let mut buffer = [0u8; 8192];
+        loop {
+            match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(count) => {
+                    let bytes = &buffer[..count];
+                    if let Ok(mut ring) = ring.lock() {
+                        ring.push(bytes);
+                    }
+                    log.write(bytes);
+                    // The frontend side only stops when the session is gone.
+                    let _ = sender.send(bytes.to_vec());
+                }
+                Err(_) => break,
+            }
+        }
+    });
+    std::thread::spawn(move || {
+        let flush_after = Duration::from_millis(policy.flush_interval_ms);
+        let min_gap = Duration::from_millis(1000 / u64::from(policy.max_events_per_sec.max(1)));
+        let mut pending: Vec<u8> = Vec::new();
+        let mut pending_since = Instant::now();
+        let mut dropped: u64 = 0;
+        let mut last_emit: Option<Instant> = None;
+        let mut open = true;
+        while open || !pending.is_empty() {
+            let wait = if pending.is_empty() {
+                Duration::from_secs(3600)
+            } else {
+                let due_by_age = flush_after.saturating_sub(pending_since.elapsed());
+                let due_by_rate = last_emit
+                    .map(|last| min_gap.saturating_sub(last.elapsed()))
+                    .unwrap_or_default();
+                due_by_age.max(due_by_rate).max(Duration::from_millis(1))
+            };
+            let received = if open {
+                receiver.recv_timeout(wait)
+            } else {
+                Err(RecvTimeoutError::Disconnected)
+            };
+            match received {
+                Ok(bytes) => {
+                    if pending.is_empty() {
+                        pending_since = Instant::now();
+                    }
+                    pending.extend_from_slice(&bytes);
+                    let overflow = pending
+                        .len()
+                        .saturating_sub(policy.max_pending_bytes.max(1));
+                    if overflow > 0 {
+                        pending.drain(..overflow);
+                        dropped += overflow as u64;
+                    }
+                }
+                Err(RecvTimeoutError::Timeout) => {}
+                Err(RecvTimeoutError::Disconnected) => open = false,
+            }
+            if pending.is_empty() {
+                continue;
+            }
+            let due = !open
+                || pending.len() >= policy.flush_bytes
+                || pending_since.elapsed() >= flush_after;
+            let allowed = !open || last_emit.is_none_or(|last| last.elapsed() >= min_gap);
+            if !(due && allowed) {
+                continue;
+            }
+            let sent = send_binary_frame(&binary, || {
+                encode_output_frame(&session_id, dropped, &pending)
+            });
+            if !sent {
+                let payload = TerminalOutputEvent {
+                    session_id: session_id.clone(),
+                    data_base64: general_purpose::STANDARD.encode(&pending),
+                    dropped_bytes: dropped,
+                };
+                if let Ok(payload) = serde_json::to_value(payload) {
+                    sink.emit_json(TERMINAL_OUTPUT_EVENT, payload);
+                }
+            }
+            pending.clear();
+            dropped = 0;
+            last_emit = Some(Instant::now());
+        }
+    });
+}
@@ -0,0 +1,115 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+
+const WINDOW_MS: u128 = 60_000;
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ActionRateLimit {
+    pub action_type: String,
+    #[serde(default)]
+    pub max_per_step: Option<u32>,
+    #[serde(default)]
+    pub max_per_minute: Option<u32>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RateLimiter {
+    limits: Vec<ActionRateLimit>,
+    step_counts: HashMap<String, u32>,
+    history: HashMap<String, VecDeque<u128>>,
+}
+
+impl RateLimiter {
+    pub fn new(limits: Vec<ActionRateLimit>) -> Self {
+        Self {
+            limits,
+            step_counts: HashMap::new(),
+            history: HashMap::new(),
+        }
+    }
+
+    pub fn with_defaults() -> Self {
+        Self::new(default_rate_limits())
+    }
+
+    pub fn limits(&self) -> &[ActionRateLimit] {
+        &self.limits
+    }
+
+    pub fn set_limits(&mut self, limits: Vec<ActionRateLimit>) {
+        self.limits = limits;
+        self.step_counts.clear();
+        self.history.clear();
+    }
+
+    pub fn begin_step(&mut self) {
+        self.step_counts.clear();
+    }
+
+    pub fn check(&mut self, action_type: &str, now: u128) -> Result<(), String> {
+        let Some(limit) = self
+            .limits
+            .iter()
+            .find(|limit| limit.action_type == action_type)
+        else {
+            return Ok(());
+        };
+        let step_count = self.step_counts.get(action_type).copied().unwrap_or(0);
+        if let Some(max) = limit.max_per_step {
+            if step_count >= max {
+                return Err(format!(
+                    "Rate limit: {} is limited to {} per step. Back off and continue in the next step.",
+                    action_type, max
+                ));
+            }
+        }
+        let history = self.history.entry(action_type.to_string()).or_default();
+        while let Some(ts) = history.front() {
+            if now.saturating_sub(*ts) >= WINDOW_MS {
+                history.pop_front();
+            } else {
+                break;
+            }
+        }
+        if let Some(max) = limit.max_per_minute {
+            if history.len() as u32 >= max {
+                let retry_ms = history
+                    .front()
+                    .map(|ts| WINDOW_MS.saturating_sub(now.saturating_sub(*ts)))
+                    .unwrap_or(WINDOW_MS);
+                return Err(format!(
+                    "Rate limit: {} is limited to {} per minute. Retry in {}s or use a different approach.",
+                    action_type,
+                    max,
+                    retry_ms.div_ceil(1000)
+                ));
+            }
+        }
+        history.push_back(now);
+        self.step_counts
+            .insert(action_type.to_string(), step_count.saturating_add(1));
+        Ok(())
+    }
+}
+
+impl Default for RateLimiter {
+    fn default() -> Self {
+        Self::with_defaults()
+    }
+}
+
+pub fn default_rate_limits() -> Vec<ActionRateLimit> {
+    vec![
+        ActionRateLimit {
+            action_type: "fs.write".to_string(),
+            max_per_step: Some(3),
+            max_per_minute: None,
+        },
+        ActionRateLimit {
+            action_type: "terminal.exec".to_string(),
+            max_per_step: None,
+            max_per_minute: Some(10),
+        },
+    ]
+}
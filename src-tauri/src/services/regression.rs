@@ -0,0 +1,193 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+
+const MAX_TRACKED_EDITS: usize = 20;
+const MAX_REPORTED_TESTS: usize = 20;
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TestOutcome {
+    Passed,
+    Failed,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct RegressedTest {
+    pub name: String,
+    pub last_passed_step: u32,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TestRegression {
+    pub step: u32,
+    pub tests: Vec<RegressedTest>,
+    pub edits: Vec<String>,
+}
+
+impl TestRegression {
+    /// Observation text that asks the model to fix the regression before moving on.
+    pub fn corrective_observation(&self) -> String {
+        let names: Vec<String> = self
+            .tests
+            .iter()
+            .take(MAX_REPORTED_TESTS)
+            .map(|test| format!("{} (passed at step {})", test.name, test.last_passed_step))
+            .collect();
+        let mut text = format!(
+            "Regression: {} previously passing test(s) now fail: {}.",
+            self.tests.len(),
+            names.join(", ")
+        );
+        if self.tests.len() > MAX_REPORTED_TESTS {
+            text.push_str(&format!(
+                " ... {} more.",
+                self.tests.len() - MAX_REPORTED_TESTS
+            ));
+        }
+        if !self.edits.is_empty() {
+            text.push_str(&format!(
+                " Edits since they passed: {}.",
+                self.edits.join("; ")
+            ));
+        }
+        text.push_str(" Fix the regression before continuing with the plan.");
+        text
+    }
+}
+
+/// Remembers the latest outcome of every test seen in a run and flags tests
+/// that flip from passing to failing after the agent changed something.
+#[derive(Default)]
+pub struct RegressionWatchdog {
+    outcomes: HashMap<String, (TestOutcome, u32)>,
+    edits: Vec<String>,
+}
+
+impl RegressionWatchdog {
+    pub fn reset(&mut self) {
+        self.outcomes.clear();
+        self.edits.clear();
+    }
+
+    pub fn note_edit(&mut self, edit: String) {
+        if self.edits.contains(&edit) {
+            return;
+        }
+        if self.edits.len() >= MAX_TRACKED_EDITS {
+            self.edits.remove(0);
+        }
+        self.edits.push(edit);
+    }
+
+    pub fn record(
+        &mut self,
+        step: u32,
+        results: BTreeMap<String, TestOutcome>,
+    ) -> Option<TestRegression> {
+        if results.is_empty() {
+            return None;
+        }
+        let edits = std::mem::take(&mut self.edits);
+        let mut regressed = Vec::new();
+        for (name, outcome) in results {
+            let previous = self.outcomes.insert(name.clone(), (outcome, step));
+            if let (Some((TestOutcome::Passed, last_passed_step)), TestOutcome::Failed) =
+                (previous, outcome)
+            {
+                regressed.push(RegressedTest {
+                    name,
+                    last_passed_step,
+                });
+            }
+        }
+        if regressed.is_empty() || edits.is_empty() {
+            return None;
+        }
+        Some(TestRegression {
+            step,
+            tests: regressed,
+            edits,
+        })
+    }
+}
+
+/// Extracts per-test outcomes from cargo test, pytest, go test and jest/vitest output.
+pub fn parse_test_results(output: &str) -> BTreeMap<String, TestOutcome> {
+    let mut results = BTreeMap::new();
+    for line in output.lines() {
+        let line = line.trim();
+        if let Some((name, outcome)) = parse_cargo_line(line)
+            .or_else(|| parse_pytest_line(line))
+            .or_else(|| parse_go_line(line))
+            .or_else(|| parse_jest_line(line))
+        {
+            // A failure anywhere in the output wins over an earlier pass line.
+            let entry = results.entry(name).or_insert(outcome);
+            if outcome == TestOutcome::Failed {
+                *entry = TestOutcome::Failed;
+            }
+        }
+    }
+    results
+}
+
+fn parse_cargo_line(line: &str) -> Option<(String, TestOutcome)> {
+    let rest = line.strip_prefix("test ")?;
+    let (name, status) = rest.rsplit_once(" ... ")?;
+    let outcome = match status.trim() {
+        "ok" => TestOutcome::Passed,
+        "FAILED" => TestOutcome::Failed,
+        _ => return None,
+    };
+    Some((name.trim().to_string(), outcome))
+}
+
+fn parse_pytest_line(line: &str) -> Option<(String, TestOutcome)> {
+    if let Some(rest) = line.strip_prefix("FAILED ") {
+        let name = rest.split(" - ").next()?.trim();
+        return name
+            .contains("::")
+            .then(|| (name.to_string(), TestOutcome::Failed));
+    }
+    let (name, rest) = line.split_once(' ')?;
+    if !name.contains("::") {
+        return None;
+    }
+    let outcome = match rest.split_whitespace().next()? {
+        "PASSED" => TestOutcome::Passed,
+        "FAILED" | "ERROR" => TestOutcome::Failed,
+        _ => return None,
+    };
+    Some((name.to_string(), outcome))
+}
+
+fn parse_go_line(line: &str) -> Option<(String, TestOutcome)> {
+    let (outcome, rest) = if let Some(rest) = line.strip_prefix("--- PASS: ") {
+        (TestOutcome::Passed, rest)
+    } else if let Some(rest) = line.strip_prefix("--- FAIL: ") {
+        (TestOutcome::Failed, rest)
+    } else {
+        return None;
+    };
+    let name = rest.split_whitespace().next()?;
+    Some((name.to_string(), outcome))
+}
+
+fn parse_jest_line(line: &str) -> Option<(String, TestOutcome)> {
+    let (outcome, rest) = if let Some(rest) = line.strip_prefix('✓') {
+        (TestOutcome::Passed, rest)
+    } else if let Some(rest) = line.strip_prefix('✕').or_else(|| line.strip_prefix('×')) {
+        (TestOutcome::Failed, rest)
+    } else {
+        return None;
+    };
+    // Drop the trailing duration, e.g. "renders header (12 ms)".
+    let name = match rest.trim().rsplit_once(" (") {
+        Some((name, tail)) if tail.ends_with("s)") => name,
+        _ => rest.trim(),
+    };
+    if name.is_empty() {
+        return None;
+    }
+    Some((name.to_string(), outcome))
+}
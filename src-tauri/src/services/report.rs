@@ -0,0 +1,321 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::services::diff::ChangeSummary;
+use crate::services::kernel::{KernelEvent, RunState};
+
+const MAX_TOOL_OUTPUT_CHARS: usize = 1_200;
+
+#[derive(Clone, Copy, Serialize, Deserialize, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportFormat {
+    #[default]
+    Markdown,
+    Html,
+}
+
+impl ReportFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            ReportFormat::Markdown => "md",
+            ReportFormat::Html => "html",
+        }
+    }
+}
+
+#[derive(Deserialize)]
+pub struct RunExportRequest {
+    pub run_id: Option<String>,
+    #[serde(default)]
+    pub format: ReportFormat,
+    /// File name under `.taurihands/reports`; defaults to `run-<id>.<ext>`.
+    pub path: Option<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunExportResponse {
+    pub path: String,
+    pub format: ReportFormat,
+    pub bytes: usize,
+}
+
+enum Block {
+    Heading(u8, String),
+    Paragraph(String),
+    List(Vec<String>),
+    Code(String),
+}
+
+struct ToolCall {
+    action_type: String,
+    detail: String,
+    ok: bool,
+    exit_code: Option<i64>,
+    output: String,
+}
+
+pub fn render_report(state: &RunState, events: &[KernelEvent], format: ReportFormat) -> String {
+    let blocks = build_blocks(state, events);
+    match format {
+        ReportFormat::Markdown => render_markdown(&blocks),
+        ReportFormat::Html => render_html(&blocks, &report_title(state)),
+    }
+}
+
+fn report_title(state: &RunState) -> String {
+    state
+        .plan
+        .as_ref()
+        .map(|plan| plan.goal.trim())
+        .filter(|goal| !goal.is_empty())
+        .map(|goal| format!("Run report: {}", goal))
+        .unwrap_or_else(|| format!("Run report: {}", state.run_id))
+}
+
+fn build_blocks(state: &RunState, events: &[KernelEvent]) -> Vec<Block> {
+    let mut blocks = vec![
+        Block::Heading(1, report_title(state)),
+        Block::List(vec![
+            format!("Run: {}", state.run_id),
+            format!("State: {:?}", state.agent_state),
+            format!("Turns: {}", state.turn),
+            format!(
+                "Steps used: {}/{}",
+                state.budget.used_steps, state.budget.max_steps
+            ),
+        ]),
+    ];
+
+    if let Some(plan) = state.plan.as_ref().filter(|plan| !plan.steps.is_empty()) {
+        blocks.push(Block::Heading(2, "Plan".to_string()));
+        blocks.push(Block::List(
+            plan.steps
+                .iter()
+                .map(|step| {
+                    let mark = if step.done { "x" } else { " " };
+                    format!("[{}] {} ({})", mark, step.title, step.status)
+                })
+                .collect(),
+        ));
+    }
+
+    let messages: Vec<_> = state
+        .messages
+        .iter()
+        .filter(|message| !message.content.trim().is_empty())
+        .collect();
+    if !messages.is_empty() {
+        blocks.push(Block::Heading(2, "Conversation".to_string()));
+        for message in messages {
+            blocks.push(Block::Heading(3, message.role.clone()));
+            blocks.push(Block::Paragraph(message.content.trim().to_string()));
+        }
+    }
+
+    let tool_calls = collect_tool_calls(events);
+    if !tool_calls.is_empty() {
+        blocks.push(Block::Heading(2, "Tool calls".to_string()));
+        for call in tool_calls {
+            let status = match (call.ok, call.exit_code) {
+                (true, _) => "ok".to_string(),
+                (false, Some(code)) => format!("failed, exit {}", code),
+                (false, None) => "failed".to_string(),
+            };
+            blocks.push(Block::Heading(
+                3,
+                format!("{} {} ({})", call.action_type, call.detail, status),
+            ));
+            if !call.output.trim().is_empty() {
+                blocks.push(Block::Code(trim_output(&call.output)));
+            }
+        }
+    }
+
+    let changes = collect_changes(events);
+    if !changes.is_empty() {
+        blocks.push(Block::Heading(2, "Changes".to_string()));
+        blocks.push(Block::List(
+            changes.values().map(ChangeSummary::describe).collect(),
+        ));
+    }
+    if !state.pending_changes.is_empty() {
+        blocks.push(Block::Heading(2, "Pending changes".to_string()));
+        blocks.push(Block::List(
+            state
+                .pending_changes
+                .iter()
+                .map(|change| format!("{}: {}", change.id, change.reason))
+                .collect(),
+        ));
+    }
+    blocks
+}
+
+fn collect_tool_calls(events: &[KernelEvent]) -> Vec<ToolCall> {
+    events
+        .iter()
+        .filter(|event| event.event_type == "ToolCallFinished")
+        .map(|event| {
+            let action = event.payload.get("action").cloned().unwrap_or_default();
+            ToolCall {
+                action_type: action
+                    .get("type")
+                    .and_then(|value| value.as_str())
+                    .unwrap_or("action")
+                    .to_string(),
+                detail: action_detail(&action),
+                ok: event
+                    .payload
+                    .get("ok")
+                    .and_then(|value| value.as_bool())
+                    .unwrap_or(false),
+                exit_code: event
+                    .payload
+                    .get("exit_code")
+                    .and_then(|value| value.as_i64()),
+                output: event
+                    .payload
+                    .get("summary")
+                    .and_then(|value| value.as_str())
+                    .unwrap_or_default()
+                    .to_string(),
+            }
+        })
+        .collect()
+}
+
+fn action_detail(action: &serde_json::Value) -> String {
+    if let Some(cmd) = action.get("cmd").and_then(|value| value.as_str()) {
+        return format!("`{}`", cmd);
+    }
+    if let Some(program) = action.get("program").and_then(|value| value.as_str()) {
+        let mut parts = vec![program];
+        if let Some(args) = action.get("args").and_then(|value| value.as_array()) {
+            parts.extend(args.iter().filter_map(|arg| arg.as_str()));
+        }
+        return format!("`{}`", parts.join(" "));
+    }
+    for key in ["path", "pattern", "question"] {
+        if let Some(value) = action.get(key).and_then(|value| value.as_str()) {
+            return value.to_string();
+        }
+    }
+    String::new()
+}
+
+/// Merges the per-write change summaries so each file is listed once.
+fn collect_changes(events: &[KernelEvent]) -> BTreeMap<String, ChangeSummary> {
+    let mut changes: BTreeMap<String, ChangeSummary> = BTreeMap::new();
+    for event in events
+        .iter()
+        .filter(|event| event.event_type == "Observation")
+    {
+        let Some(change) = event
+            .payload
+            .pointer("/observation/artifacts/changes")
+            .and_then(|value| serde_json::from_value::<ChangeSummary>(value.clone()).ok())
+        else {
+            continue;
+        };
+        match changes.get_mut(&change.path) {
+            Some(existing) => {
+                existing.lines_added += change.lines_added;
+                existing.lines_removed += change.lines_removed;
+                existing.hunks += change.hunks;
+                for symbol in change.symbols {
+                    if !existing.symbols.contains(&symbol) {
+                        existing.symbols.push(symbol);
+                    }
+                }
+            }
+            None => {
+                changes.insert(change.path.clone(), change);
+            }
+        }
+    }
+    changes
+}
+
+fn trim_output(output: &str) -> String {
+    let trimmed = output.trim();
+    if trimmed.chars().count() <= MAX_TOOL_OUTPUT_CHARS {
+        return trimmed.to_string();
+    }
+    let mut text: String = trimmed.chars().take(MAX_TOOL_OUTPUT_CHARS).collect();
+    text.push_str("\n... (truncated)");
+    text
+}
+
+fn render_markdown(blocks: &[Block]) -> String {
+    let mut out = String::new();
+    for block in blocks {
+        match block {
+            Block::Heading(level, text) => {
+                out.push_str(&"#".repeat(*level as usize));
+                out.push(' ');
+                out.push_str(text);
+                out.push_str("\n\n");
+            }
+            Block::Paragraph(text) => {
+                out.push_str(text);
+                out.push_str("\n\n");
+            }
+            Block::List(items) => {
+                for item in items {
+                    out.push_str("- ");
+                    out.push_str(item);
+                    out.push('\n');
+                }
+                out.push('\n');
+            }
+            Block::Code(text) => {
+                let fence = if text.contains("```") { "````" } else { "```" };
+                out.push_str(&format!("{}\n{}\n{}\n\n", fence, text, fence));
+            }
+        }
+    }
+    out
+}
+
+fn render_html(blocks: &[Block], title: &str) -> String {
+    let mut body = String::new();
+    for block in blocks {
+        match block {
+            Block::Heading(level, text) => {
+                body.push_str(&format!("<h{0}>{1}</h{0}>\n", level, escape_html(text)));
+            }
+            Block::Paragraph(text) => {
+                body.push_str(&format!(
+                    "<p>{}</p>\n",
+                    escape_html(text).replace('\n', "<br>")
+                ));
+            }
+            Block::List(items) => {
+                body.push_str("<ul>\n");
+                for item in items {
+                    body.push_str(&format!("<li>{}</li>\n", escape_html(item)));
+                }
+                body.push_str("</ul>\n");
+            }
+            Block::Code(text) => {
+                body.push_str(&format!("<pre><code>{}</code></pre>\n", escape_html(text)));
+            }
+        }
+    }
+    format!(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>{}</title>\n\
+         <style>body{{font-family:sans-serif;max-width:960px;margin:2rem auto;padding:0 1rem}}\
+         pre{{background:#f4f4f4;padding:0.75rem;overflow-x:auto}}</style>\n\
+         </head>\n<body>\n{}</body>\n</html>\n",
+        escape_html(title),
+        body
+    )
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
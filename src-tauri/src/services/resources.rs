@@ -0,0 +1,210 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+use sysinfo::{Pid, ProcessRefreshKind, ProcessesToUpdate, System};
+
+use crate::services::app_data::app_data_dir;
+
+const SAMPLE_INTERVAL_MS: u64 = 50;
+
+#[derive(Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceUsage {
+    pub duration_ms: u128,
+    pub cpu_time_ms: Option<u64>,
+    pub peak_memory_bytes: Option<u64>,
+    /// Which budget limit the command went over, if any.
+    pub over_budget: Option<String>,
+}
+
+/// Limits for a single command run by the agent. Unset limits are not enforced.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceBudget {
+    pub max_cpu_time_ms: Option<u64>,
+    pub max_memory_bytes: Option<u64>,
+}
+
+impl ResourceBudget {
+    fn is_empty(&self) -> bool {
+        self.max_cpu_time_ms.is_none() && self.max_memory_bytes.is_none()
+    }
+
+    fn check(&self, cpu_time_ms: u64, memory_bytes: u64) -> Option<String> {
+        if let Some(limit) = self.max_cpu_time_ms.filter(|limit| cpu_time_ms > *limit) {
+            return Some(format!(
+                "CPU time {} ms is over the budget of {} ms",
+                cpu_time_ms, limit
+            ));
+        }
+        self.max_memory_bytes
+            .filter(|limit| memory_bytes > *limit)
+            .map(|limit| {
+                format!(
+                    "memory {} bytes is over the budget of {} bytes",
+                    memory_bytes, limit
+                )
+            })
+    }
+}
+
+fn budget_path() -> Option<PathBuf> {
+    app_data_dir().map(|dir| dir.join("resource-budget.json"))
+}
+
+/// Without an app data directory no budget is enforced.
+pub fn load_resource_budget() -> ResourceBudget {
+    budget_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_resource_budget(budget: &ResourceBudget) -> Result<(), String> {
+    let path =
+        budget_path().ok_or_else(|| "No app data directory for resource budgets".to_string())?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_vec_pretty(budget).map_err(|e| e.to_string())?;
+    std::fs::write(path, data).map_err(|e| e.to_string())
+}
+
+#[derive(Default)]
+struct SampleTotals {
+    cpu_time_ms: HashMap<u32, u64>,
+    peak_memory_bytes: Option<u64>,
+}
+
+/// Samples CPU time and resident memory of a process tree until `finish` is
+/// called, and notes the first time the tree goes over the resource budget.
+pub struct ResourceSampler {
+    started: Instant,
+    stop: Arc<AtomicBool>,
+    over_budget: Arc<Mutex<Option<String>>>,
+    handle: Option<JoinHandle<SampleTotals>>,
+}
+
+impl ResourceSampler {
+    /// Tracks `pid` and every process spawned below it.
+    pub fn process_tree(pid: Option<u32>) -> Self {
+        Self::start(pid, true)
+    }
+
+    /// Tracks only the children of `pid`, e.g. commands run inside a long-lived shell.
+    pub fn descendants(pid: Option<u32>) -> Self {
+        Self::start(pid, false)
+    }
+
+    fn start(pid: Option<u32>, include_root: bool) -> Self {
+        let stop = Arc::new(AtomicBool::new(false));
+        let over_budget = Arc::new(Mutex::new(None));
+        let budget = load_resource_budget();
+        let handle = pid.map(|pid| {
+            let stop = stop.clone();
+            let over_budget = over_budget.clone();
+            std::thread::spawn(move || {
+                let mut system = System::new();
+                let mut totals = SampleTotals::default();
+                loop {
+                    let memory = sample_tree(&mut system, pid, include_root, &mut totals);
+                    if !budget.is_empty() {
+                        let cpu_time_ms = totals.cpu_time_ms.values().sum();
+                        if let Some(reason) = budget.check(cpu_time_ms, memory) {
+                            if let Ok(mut slot) = over_budget.lock() {
+                                slot.get_or_insert(reason);
+                            }
+                        }
+                    }
+                    if stop.load(Ordering::Relaxed) {
+                        break;
+                    }
+                    std::thread::sleep(Duration::from_millis(SAMPLE_INTERVAL_MS));
+                }
+                totals
+            })
+        });
+        Self {
+            started: Instant::now(),
+            stop,
+            over_budget,
+            handle,
+        }
+    }
+
+    /// Why the tracked processes went over the budget, once they have.
+    pub fn over_budget(&self) -> Option<String> {
+        self.over_budget.lock().ok().and_then(|slot| slot.clone())
+    }
+
+    pub fn finish(mut self) -> ResourceUsage {
+        let duration_ms = self.started.elapsed().as_millis();
+        self.stop.store(true, Ordering::Relaxed);
+        let totals = self.handle.take().and_then(|handle| handle.join().ok());
+        let over_budget = self.over_budget();
+        match totals {
+            Some(totals) => ResourceUsage {
+                duration_ms,
+                cpu_time_ms: Some(totals.cpu_time_ms.values().sum()),
+                peak_memory_bytes: totals.peak_memory_bytes,
+                over_budget,
+            },
+            None => ResourceUsage {
+                duration_ms,
+                ..ResourceUsage::default()
+            },
+        }
+    }
+}
+
+impl Drop for ResourceSampler {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+/// Refreshes every process once and adds up the tree below `root`. Returns
+/// the tree's current resident memory.
+fn sample_tree(
+    system: &mut System,
+    root: u32,
+    include_root: bool,
+    totals: &mut SampleTotals,
+) -> u64 {
+    system.refresh_processes_specifics(
+        ProcessesToUpdate::All,
+        true,
+        ProcessRefreshKind::nothing().with_cpu().with_memory(),
+    );
+    let processes = system.processes();
+    let mut children: HashMap<Pid, Vec<Pid>> = HashMap::new();
+    for process in processes.values() {
+        if let Some(parent) = process.parent() {
+            children.entry(parent).or_default().push(process.pid());
+        }
+    }
+    let mut pending = vec![Pid::from_u32(root)];
+    let mut memory_total = 0u64;
+    let mut seen_any = false;
+    while let Some(pid) = pending.pop() {
+        pending.extend(children.get(&pid).into_iter().flatten().copied());
+        if pid.as_u32() == root && !include_root {
+            continue;
+        }
+        let Some(process) = processes.get(&pid) else {
+            continue;
+        };
+        seen_any = true;
+        let entry = totals.cpu_time_ms.entry(pid.as_u32()).or_insert(0);
+        *entry = (*entry).max(process.accumulated_cpu_time());
+        memory_total = memory_total.saturating_add(process.memory());
+    }
+    if seen_any {
+        totals.peak_memory_bytes = Some(totals.peak_memory_bytes.unwrap_or(0).max(memory_total));
+    }
+    memory_total
+}
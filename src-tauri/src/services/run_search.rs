@@ -0,0 +1,136 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::services::kernel::{normalize_tags, RunAgentState, RunState};
+
+const DEFAULT_LIMIT: usize = 100;
+
+/// Filters for `runs_search`; every field that is set must match.
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct RunSearchQuery {
+    /// Tags the run or its task must all carry.
+    pub tags: Vec<String>,
+    /// Inclusive bounds on the run start, in epoch milliseconds.
+    pub since: Option<u128>,
+    pub until: Option<u128>,
+    pub outcome: Option<RunAgentState>,
+    /// Case-insensitive substring of the plan goal, task goal or first prompt.
+    pub text: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Deserialize)]
+pub struct RunTagsRequest {
+    pub run_id: Option<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// What a run inherits from the task it was started for.
+pub struct TaskLabels {
+    pub goal: String,
+    pub tags: Vec<String>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RunSummary {
+    pub run_id: String,
+    pub goal: Option<String>,
+    pub tags: Vec<String>,
+    pub task_id: Option<String>,
+    pub task_tags: Vec<String>,
+    pub outcome: RunAgentState,
+    pub started_at: u128,
+    pub updated_at: u128,
+    pub used_steps: u32,
+    pub last_error: Option<String>,
+}
+
+/// Matches persisted runs against `query`, newest first.
+pub fn search_runs(
+    runs: Vec<(RunState, u128)>,
+    tasks: &HashMap<String, TaskLabels>,
+    query: &RunSearchQuery,
+) -> Vec<RunSummary> {
+    let wanted_tags = normalize_tags(query.tags.clone());
+    let text = query
+        .text
+        .as_deref()
+        .map(|text| text.trim().to_lowercase())
+        .filter(|text| !text.is_empty());
+    let mut matches: Vec<RunSummary> = runs
+        .into_iter()
+        .filter_map(|(run, updated_at)| {
+            let task = run.task_id.as_ref().and_then(|id| tasks.get(id));
+            let started_at = if run.started_at > 0 {
+                run.started_at
+            } else {
+                updated_at
+            };
+            if query.since.is_some_and(|since| started_at < since)
+                || query.until.is_some_and(|until| started_at > until)
+            {
+                return None;
+            }
+            if query
+                .outcome
+                .as_ref()
+                .is_some_and(|outcome| *outcome != run.agent_state)
+            {
+                return None;
+            }
+            let task_tags = task.map(|task| task.tags.clone()).unwrap_or_default();
+            if !wanted_tags
+                .iter()
+                .all(|tag| run.tags.contains(tag) || task_tags.contains(tag))
+            {
+                return None;
+            }
+            let goal = run_goal(&run, task);
+            if let Some(text) = text.as_deref() {
+                let first_prompt = run
+                    .messages
+                    .iter()
+                    .find(|message| message.role == "user")
+                    .map(|message| message.content.as_str());
+                let haystacks = [
+                    goal.as_deref(),
+                    task.map(|task| task.goal.as_str()),
+                    first_prompt,
+                ];
+                if !haystacks
+                    .iter()
+                    .flatten()
+                    .any(|value| value.to_lowercase().contains(text))
+                {
+                    return None;
+                }
+            }
+            Some(RunSummary {
+                run_id: run.run_id,
+                goal,
+                tags: run.tags,
+                task_id: run.task_id,
+                task_tags,
+                outcome: run.agent_state,
+                started_at,
+                updated_at,
+                used_steps: run.budget.used_steps,
+                last_error: run.last_error,
+            })
+        })
+        .collect();
+    matches.sort_by_key(|run| std::cmp::Reverse(run.started_at));
+    matches.truncate(query.limit.unwrap_or(DEFAULT_LIMIT));
+    matches
+}
+
+fn run_goal(run: &RunState, task: Option<&TaskLabels>) -> Option<String> {
+    run.plan
+        .as_ref()
+        .map(|plan| plan.goal.clone())
+        .or_else(|| task.map(|task| task.goal.clone()))
+        .filter(|goal| !goal.trim().is_empty())
+}
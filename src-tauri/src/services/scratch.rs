@@ -0,0 +1,40 @@
+use std::path::{Path, PathBuf};
+
+/// Stands for the run's scratch directory in action paths and commands.
+pub const SCRATCH_ALIAS: &str = "$SCRATCH";
+
+/// Keeps scratch files out of `git status` and `git diff`.
+pub const SCRATCH_EXCLUDE_PATHSPEC: &str = ":(exclude).taurihands/scratch";
+
+/// `.taurihands/scratch/<run_id>`, relative to the workspace root.
+pub fn scratch_relative(run_id: &str) -> String {
+    format!(".taurihands/scratch/{}", run_id)
+}
+
+pub fn scratch_dir(root: &Path, run_id: &str) -> PathBuf {
+    root.join(".taurihands").join("scratch").join(run_id)
+}
+
+/// Replaces every `$SCRATCH` in `text`; `None` when there is none.
+pub fn expand_scratch(text: &str, replacement: &str) -> Option<String> {
+    text.contains(SCRATCH_ALIAS)
+        .then(|| text.replace(SCRATCH_ALIAS, replacement))
+}
+
+/// Git arguments that limit a command to the workspace minus the scratch dirs.
+pub fn without_scratch_pathspec() -> Vec<String> {
+    vec![
+        "--".to_string(),
+        ".".to_string(),
+        SCRATCH_EXCLUDE_PATHSPEC.to_string(),
+    ]
+}
+
+pub fn remove_scratch_dir(root: &Path, run_id: &str) -> Result<bool, String> {
+    let dir = scratch_dir(root, run_id);
+    if !dir.exists() {
+        return Ok(false);
+    }
+    std::fs::remove_dir_all(&dir).map_err(|e| e.to_string())?;
+    Ok(true)
+}
@@ -0,0 +1,34 @@
+use crate::services::at_rest::KEYCHAIN_SERVICE;
+
+/// Named credentials in the OS keychain, for settings that must reference a
+/// secret without containing it.
+pub struct SecretStore;
+
+impl SecretStore {
+    fn entry(name: &str) -> Result<keyring::Entry, String> {
+        if name.trim().is_empty() {
+            return Err("Secret name is empty".to_string());
+        }
+        keyring::Entry::new(KEYCHAIN_SERVICE, &format!("secret-{}", name.trim()))
+            .map_err(|e| format!("OS keychain unavailable: {}", e))
+    }
+
+    pub fn get(name: &str) -> Result<String, String> {
+        Self::entry(name)?
+            .get_password()
+            .map_err(|e| format!("Secret {} not found in the OS keychain: {}", name, e))
+    }
+
+    pub fn set(name: &str, value: &str) -> Result<(), String> {
+        Self::entry(name)?
+            .set_password(value)
+            .map_err(|e| format!("Unable to store secret {}: {}", name, e))
+    }
+
+    pub fn delete(name: &str) -> Result<(), String> {
+        match Self::entry(name)?.delete_credential() {
+            Ok(()) | Err(keyring::Error::NoEntry) => Ok(()),
+            Err(err) => Err(format!("Unable to delete secret {}: {}", name, err)),
+        }
+    }
+}
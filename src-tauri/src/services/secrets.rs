@@ -0,0 +1,257 @@
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::path::Path;
+
+use crate::services::git_ops::git_output;
+
+const MIN_ENTROPY_TOKEN_LEN: usize = 20;
+const MIN_ENTROPY_BITS: f64 = 3.5;
+const MAX_FINDINGS: usize = 50;
+const MAX_UNTRACKED_FILE_BYTES: u64 = 256 * 1024;
+const SECRET_NAME_HINTS: &[&str] = &[
+    "key",
+    "secret",
+    "token",
+    "password",
+    "passwd",
+    "credential",
+    "auth",
+];
+
+/// Known token shapes: (kind, prefix, minimum total length).
+const TOKEN_PREFIXES: &[(&str, &str, usize)] = &[
+    ("AWS access key", "AKIA", 20),
+    ("AWS access key", "ASIA", 20),
+    ("GitHub token", "ghp_", 40),
+    ("GitHub token", "gho_", 40),
+    ("GitHub token", "ghs_", 40),
+    ("GitHub token", "github_pat_", 60),
+    ("OpenAI API key", "sk-", 40),
+    ("Anthropic API key", "sk-ant-", 40),
+    ("Slack token", "xoxb-", 30),
+    ("Slack token", "xoxp-", 30),
+    ("Google API key", "AIza", 39),
+    ("Stripe key", "sk_live_", 30),
+];
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SecretFinding {
+    pub path: String,
+    pub line: Option<u32>,
+    pub kind: String,
+    pub excerpt: String,
+    /// SHA-256 of the full matched value; the excerpt alone is too short to
+    /// tell two secrets apart.
+    #[serde(skip)]
+    value_hash: String,
+}
+
+impl SecretFinding {
+    /// Identifies one secret at one line, so acknowledging it never covers a
+    /// different value that happens to share the masked excerpt.
+    pub fn fingerprint(&self) -> String {
+        let line = self.line.map(|line| line.to_string()).unwrap_or_default();
+        format!("{}:{}:{}:{}", self.path, line, self.kind, self.value_hash)
+    }
+}
+
+pub fn format_findings(findings: &[SecretFinding]) -> String {
+    findings
+        .iter()
+        .map(|finding| match finding.line {
+            Some(line) => format!(
+                "{}:{} {} ({})",
+                finding.path, line, finding.kind, finding.excerpt
+            ),
+            None => format!("{} {} ({})", finding.path, finding.kind, finding.excerpt),
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Scans uncommitted changes against HEAD and, optionally, untracked files.
+/// Workspaces that are not git repositories yield no findings.
+pub fn scan_workspace(root: &Path, include_untracked: bool) -> Vec<SecretFinding> {
+    let mut findings = git_output(root, &["diff", "HEAD", "-U0", "--no-color"])
+        .map(|diff| scan_diff(&diff))
+        .unwrap_or_default();
+    if include_untracked {
+        let untracked =
+            git_output(root, &["ls-files", "--others", "--exclude-standard"]).unwrap_or_default();
+        for path in untracked.lines().filter(|line| !line.trim().is_empty()) {
+            let resolved = root.join(path);
+            let small = std::fs::metadata(&resolved)
+                .map(|meta| meta.len() <= MAX_UNTRACKED_FILE_BYTES)
+                .unwrap_or(false);
+            if !small {
+                continue;
+            }
+            // Binary files fail UTF-8 decoding and are skipped.
+            if let Ok(content) = std::fs::read_to_string(&resolved) {
+                findings.extend(scan_content(path, &content));
+            }
+            if findings.len() >= MAX_FINDINGS {
+                break;
+            }
+        }
+    }
+    findings.truncate(MAX_FINDINGS);
+    findings
+}
+
+/// Scans the added lines of a unified diff for secret-looking strings.
+pub fn scan_diff(diff: &str) -> Vec<SecretFinding> {
+    let mut findings = Vec::new();
+    let mut path = String::new();
+    let mut line_no: Option<u32> = None;
+    for line in diff.lines() {
+        if let Some(rest) = line.strip_prefix("+++ ") {
+            path = rest.trim().trim_start_matches("b/").to_string();
+            line_no = None;
+            continue;
+        }
+        if line.starts_with("--- ") {
+            continue;
+        }
+        if let Some(rest) = line.strip_prefix("@@ ") {
+            line_no = parse_hunk_start(rest);
+            continue;
+        }
+        if let Some(added) = line.strip_prefix('+') {
+            findings.extend(scan_line(&path, line_no, added));
+            line_no = line_no.map(|value| value + 1);
+        } else if !line.starts_with('-') {
+            line_no = line_no.map(|value| value + 1);
+        }
+        if findings.len() >= MAX_FINDINGS {
+            break;
+        }
+    }
+    findings.truncate(MAX_FINDINGS);
+    findings
+}
+
+/// Scans a whole file, e.g. one that is untracked and has no diff yet.
+pub fn scan_content(path: &str, content: &str) -> Vec<SecretFinding> {
+    let mut findings = Vec::new();
+    for (index, line) in content.lines().enumerate() {
+        findings.extend(scan_line(path, Some(index as u32 + 1), line));
+        if findings.len() >= MAX_FINDINGS {
+            break;
+        }
+    }
+    findings.truncate(MAX_FINDINGS);
+    findings
+}
+
+fn parse_hunk_start(header: &str) -> Option<u32> {
+    let new_range = header
+        .split_whitespace()
+        .find(|part| part.starts_with('+'))?;
+    new_range
+        .trim_start_matches('+')
+        .split(',')
+        .next()?
+        .parse()
+        .ok()
+}
+
+/// Replaces every secret the scanner recognizes in `text` with `replacement`,
+/// line by line.
+pub fn redact_text(text: &str, replacement: &str) -> String {
+    text.split_inclusive('\n')
+        .map(|line| {
+            secret_values(line)
+                .into_iter()
+                .fold(line.to_string(), |line, (_, value)| {
+                    line.replace(value, replacement)
+                })
+        })
+        .collect()
+}
+
+fn scan_line(path: &str, line: Option<u32>, text: &str) -> Vec<SecretFinding> {
+    secret_values(text)
+        .into_iter()
+        .map(|(kind, value)| SecretFinding {
+            path: path.to_string(),
+            line,
+            kind: kind.to_string(),
+            excerpt: mask(value),
+            value_hash: Sha256::digest(value.as_bytes())
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect(),
+        })
+        .collect()
+}
+
+/// Each secret-looking value in one line, with its kind.
+fn secret_values(text: &str) -> Vec<(&'static str, &str)> {
+    if text.contains("-----BEGIN") && text.contains("PRIVATE KEY-----") {
+        return vec![("Private key", text.trim())];
+    }
+    let mut values = Vec::new();
+    for token in tokens(text) {
+        let known = TOKEN_PREFIXES
+            .iter()
+            .filter(|(_, prefix, min_len)| token.starts_with(prefix) && token.len() >= *min_len)
+            .max_by_key(|(_, prefix, _)| prefix.len());
+        if let Some((kind, _, _)) = known {
+            values.push((*kind, token));
+        }
+    }
+    if values.is_empty() {
+        if let Some(value) = assigned_secret(text) {
+            values.push(("High-entropy secret", value));
+        }
+    }
+    values
+}
+
+fn tokens(text: &str) -> impl Iterator<Item = &str> {
+    text.split(|ch: char| !(ch.is_ascii_alphanumeric() || "_-+/=.".contains(ch)))
+        .filter(|token| token.len() >= 16)
+}
+
+/// Matches `name = "value"` / `name: value` where the name hints at a credential
+/// and the value looks random rather than like a placeholder or an identifier.
+fn assigned_secret(text: &str) -> Option<&str> {
+    let (name, value) = text.split_once('=').or_else(|| text.split_once(':'))?;
+    let name = name.trim().to_lowercase();
+    if !SECRET_NAME_HINTS.iter().any(|hint| name.contains(hint)) {
+        return None;
+    }
+    let value = value
+        .trim()
+        .trim_end_matches([',', ';'])
+        .trim_matches(['"', '\'', '`']);
+    if value.len() < MIN_ENTROPY_TOKEN_LEN || value.contains(char::is_whitespace) {
+        return None;
+    }
+    if value.contains("${") || value.contains("process.env") || value.contains("env(") {
+        return None;
+    }
+    (shannon_entropy(value) >= MIN_ENTROPY_BITS).then_some(value)
+}
+
+fn shannon_entropy(value: &str) -> f64 {
+    let mut counts: HashMap<char, usize> = HashMap::new();
+    for ch in value.chars() {
+        *counts.entry(ch).or_default() += 1;
+    }
+    let len = value.chars().count() as f64;
+    counts
+        .values()
+        .map(|count| {
+            let p = *count as f64 / len;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+fn mask(value: &str) -> String {
+    let visible: String = value.chars().take(4).collect();
+    format!("{}…", visible)
+}
@@ -0,0 +1,66 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use crate::services::agent::AgentManager;
+use crate::services::audit::{now_ms, AuditEntry, AuditLog};
+use crate::services::events::EventSink;
+use crate::services::kernel::KernelManager;
+use crate::services::logging::flush_logging;
+use crate::services::pty::TerminalManager;
+
+const KERNEL_DRAIN_MS: u64 = 2_000;
+const TERMINAL_GRACE_MS: u64 = 2_000;
+
+/// Winds down running work when the application exits so runs are not left
+/// marked as running and PTY children are not orphaned.
+#[derive(Clone)]
+pub struct ShutdownCoordinator {
+    kernel: KernelManager,
+    agent: AgentManager,
+    terminal: TerminalManager,
+    audit: AuditLog,
+    done: Arc<AtomicBool>,
+}
+
+impl ShutdownCoordinator {
+    pub fn new(
+        kernel: KernelManager,
+        agent: AgentManager,
+        terminal: TerminalManager,
+        audit: AuditLog,
+    ) -> Self {
+        Self {
+            kernel,
+            agent,
+            terminal,
+            audit,
+            done: Arc::new(AtomicBool::new(false)),
+        }
+    }
+
+    pub fn shutdown(&self, app: &dyn EventSink) {
+        if self.done.swap(true, Ordering::SeqCst) {
+            return;
+        }
+        tracing::info!("shutting down");
+        let run = self.kernel.shutdown(app, KERNEL_DRAIN_MS);
+        if let Err(err) = &run {
+            tracing::warn!(error = %err, "unable to interrupt run");
+        }
+        let _ = self.agent.pause(app);
+        let sessions = self.terminal.shutdown(TERMINAL_GRACE_MS, &self.audit);
+        self.audit.write(AuditEntry {
+            timestamp_ms: now_ms(),
+            action: "app.shutdown".to_string(),
+            session_id: None,
+            command: None,
+            payload: serde_json::json!({
+                "runId": run.as_ref().ok().map(|state| state.run_id.clone()),
+                "agentState": run.as_ref().ok().map(|state| state.agent_state.clone()),
+                "closedSessions": sessions,
+            }),
+        });
+        tracing::info!(closed_sessions = sessions.len(), "shutdown complete");
+        flush_logging();
+    }
+}
@@ -0,0 +1,255 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+use std::fs::create_dir_all;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+use crate::services::at_rest::AtRest;
+
+/// How long run data is kept before `storage_gc` may delete it.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GcPolicy {
+    pub retention_days: u64,
+    /// Also delete the runs, events and artifacts of tasks that no longer exist.
+    pub remove_orphaned_runs: bool,
+}
+
+impl Default for GcPolicy {
+    fn default() -> Self {
+        Self {
+            retention_days: 30,
+            remove_orphaned_runs: true,
+        }
+    }
+}
+
+pub fn gc_policy_path(root: &Path) -> PathBuf {
+    root.join(".taurihands").join("gc-policy.json")
+}
+
+pub fn load_gc_policy(root: &Path) -> GcPolicy {
+    std::fs::read_to_string(gc_policy_path(root))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_gc_policy(root: &Path, policy: &GcPolicy) -> Result<(), String> {
+    let path = gc_policy_path(root);
+    if let Some(parent) = path.parent() {
+        create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_vec_pretty(policy).map_err(|e| e.to_string())?;
+    std::fs::write(path, data).map_err(|e| e.to_string())
+}
+
+#[derive(Default, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct StorageGcRequest {
+    /// Delete the candidates; without it only the report is produced.
+    pub apply: bool,
+    /// Overrides the saved policy for this call.
+    pub retention_days: Option<u64>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct CategoryUsage {
+    pub category: String,
+    pub files: u64,
+    pub bytes: u64,
+    pub reclaimable_bytes: u64,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GcCandidate {
+    pub category: String,
+    /// Relative to the workspace root.
+    pub path: String,
+    pub bytes: u64,
+    pub reason: String,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StorageGcReport {
+    pub applied: bool,
+    pub retention_days: u64,
+    pub categories: Vec<CategoryUsage>,
+    pub candidates: Vec<GcCandidate>,
+    pub freed_bytes: u64,
+    pub errors: Vec<String>,
+}
+
+/// One top-level entry of a category directory: a file or a per-run folder.
+struct Entry {
+    path: PathBuf,
+    /// Run or terminal session the entry belongs to. Entries without one,
+    /// such as policy and metadata files, are counted but never collected.
+    owner: Option<String>,
+    bytes: u64,
+    files: u64,
+    modified: Option<SystemTime>,
+}
+
+const CATEGORIES: &[&str] = &["events", "runs", "artifacts", "terminal", "trash"];
+
+/// Reports disk usage under `.taurihands` and deletes, when `apply` is set,
+/// entries past the retention period or left behind by deleted tasks.
+/// `in_use` holds run and session ids that must be kept regardless of age.
+/// `at_rest` opens run state sealed with the workspace key.
+pub fn storage_gc(
+    root: &Path,
+    at_rest: &AtRest,
+    policy: &GcPolicy,
+    in_use: &HashSet<String>,
+    apply: bool,
+) -> StorageGcReport {
+    let data_dir = root.join(".taurihands");
+    let retention = Duration::from_secs(policy.retention_days * 24 * 60 * 60);
+    let orphaned = if policy.remove_orphaned_runs {
+        orphaned_runs(&data_dir, at_rest)
+    } else {
+        HashSet::new()
+    };
+    let mut report = StorageGcReport {
+        applied: apply,
+        retention_days: policy.retention_days,
+        categories: Vec::new(),
+        candidates: Vec::new(),
+        freed_bytes: 0,
+        errors: Vec::new(),
+    };
+    for category in CATEGORIES {
+        let mut usage = CategoryUsage {
+            category: category.to_string(),
+            files: 0,
+            bytes: 0,
+            reclaimable_bytes: 0,
+        };
+        for entry in category_entries(&data_dir.join(category), category) {
+            usage.files += entry.files;
+            usage.bytes += entry.bytes;
+            let Some(owner) = entry.owner.as_ref() else {
+                continue;
+            };
+            if in_use.contains(owner) {
+                continue;
+            }
+            let reason = if *category != "terminal" && orphaned.contains(owner) {
+                "task was deleted".to_string()
+            } else if entry
+                .modified
+                .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                .is_some_and(|age| age > retention)
+            {
+                format!("older than {} days", policy.retention_days)
+            } else {
+                continue;
+            };
+            usage.reclaimable_bytes += entry.bytes;
+            if apply {
+                let removed = if entry.path.is_dir() {
+                    std::fs::remove_dir_all(&entry.path)
+                } else {
+                    std::fs::remove_file(&entry.path)
+                };
+                match removed {
+                    Ok(()) => report.freed_bytes += entry.bytes,
+                    Err(err) => {
+                        report
+                            .errors
+                            .push(format!("{}: {}", entry.path.display(), err));
+                        continue;
+                    }
+                }
+            }
+            report.candidates.push(GcCandidate {
+                category: category.to_string(),
+                path: entry
+                    .path
+                    .strip_prefix(root)
+                    .unwrap_or(&entry.path)
+                    .to_string_lossy()
+                    .replace('\\', "/"),
+                bytes: entry.bytes,
+                reason,
+            });
+        }
+        report.categories.push(usage);
+    }
+    report
+}
+
+fn category_entries(dir: &Path, category: &str) -> Vec<Entry> {
+    let Ok(items) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut entries = Vec::new();
+    for item in items.flatten() {
+        let name = item.file_name().to_string_lossy().to_string();
+        let owner = match category {
+            "events" => name.strip_suffix(".jsonl").map(str::to_string),
+            "runs" => name.strip_suffix(".json").map(str::to_string),
+            "terminal" => name
+                .strip_prefix("pty-")
+                .and_then(|rest| {
+                    rest.strip_suffix(".log")
+                        .or_else(|| rest.strip_suffix(".log.1"))
+                })
+                .map(str::to_string),
+            _ => Some(name),
+        };
+        let path = item.path();
+        let (files, bytes, modified) = tree_usage(&path);
+        entries.push(Entry {
+            path,
+            owner,
+            bytes,
+            files,
+            modified,
+        });
+    }
+    entries
+}
+
+/// File count, total size and newest modification time below `path`.
+fn tree_usage(path: &Path) -> (u64, u64, Option<SystemTime>) {
+    let Ok(meta) = std::fs::symlink_metadata(path) else {
+        return (0, 0, None);
+    };
+    if !meta.is_dir() {
+        return (1, meta.len(), meta.modified().ok());
+    }
+    let mut totals = (0, 0, None);
+    if let Ok(items) = std::fs::read_dir(path) {
+        for item in items.flatten() {
+            let (files, bytes, modified) = tree_usage(&item.path());
+            totals.0 += files;
+            totals.1 += bytes;
+            totals.2 = totals.2.max(modified);
+        }
+    }
+    totals
+}
+
+/// Runs whose task directory no longer exists.
+fn orphaned_runs(data_dir: &Path, at_rest: &AtRest) -> HashSet<String> {
+    let tasks_dir = data_dir.join("tasks");
+    let Ok(items) = std::fs::read_dir(data_dir.join("runs")) else {
+        return HashSet::new();
+    };
+    items
+        .flatten()
+        .filter_map(|item| {
+            let name = item.file_name().to_string_lossy().to_string();
+            let run_id = name.strip_suffix(".json")?.to_string();
+            let raw = at_rest.read_sealed(&item.path()).ok()?;
+            let state: serde_json::Value = serde_json::from_str(&raw).ok()?;
+            let task_id = state.get("taskId")?.as_str()?;
+            (!tasks_dir.join(task_id).is_dir()).then_some(run_id)
+        })
+        .collect()
+}
@@ -0,0 +1,193 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::process::Command;
+
+use crate::services::audit::{now_ms, AuditEntry, AuditLog};
+use crate::services::tools::ToolResult;
+
+const MAX_TODOS: usize = 500;
+const MAX_BLAME_TODOS: usize = 100;
+const MAX_BLAME_FILES: usize = 30;
+const MAX_SUMMARY_TODOS: usize = 80;
+const TODO_PATTERN: &str = r"(//|#|/\*|\*|<!--|--)\s*(TODO|FIXME|HACK)\b";
+
+#[derive(Clone, Debug, Serialize)]
+pub struct TodoItem {
+    pub path: String,
+    pub line: u32,
+    pub tag: String,
+    pub text: String,
+    pub author: Option<String>,
+}
+
+/// Finds TODO/FIXME/HACK comments under `scope` (the whole workspace by default).
+/// Authors come from `TODO(name):` markers, or from git blame when few enough
+/// items were found for blame to stay cheap.
+pub fn scan_todos(root: &Path, scope: Option<&Path>) -> Result<Vec<TodoItem>, String> {
+    // Searching a relative scope keeps the reported paths workspace-relative.
+    let scope = scope
+        .and_then(|scope| scope.strip_prefix(root).ok())
+        .filter(|relative| !relative.as_os_str().is_empty())
+        .unwrap_or(Path::new("."));
+    let mut cmd = Command::new("rg");
+    cmd.args([
+        "--no-heading",
+        "--with-filename",
+        "--line-number",
+        "--color",
+        "never",
+    ])
+    .arg("-e")
+    .arg(TODO_PATTERN)
+    .arg(scope)
+    .current_dir(root);
+    let output = cmd
+        .output()
+        .map_err(|e| format!("Failed to run rg: {}", e))?;
+    // rg exits with 1 when nothing matched.
+    if !output.status.success() && output.status.code() != Some(1) {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    let mut todos: Vec<TodoItem> = String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .filter_map(parse_match)
+        .take(MAX_TODOS)
+        .collect();
+    todos.sort_by(|left, right| left.path.cmp(&right.path).then(left.line.cmp(&right.line)));
+    if todos.len() <= MAX_BLAME_TODOS {
+        attach_blame_authors(root, &mut todos);
+    }
+    Ok(todos)
+}
+
+pub fn todos_tool_result(todos: &[TodoItem], audit: &AuditLog) -> ToolResult {
+    audit.write(AuditEntry {
+        timestamp_ms: now_ms(),
+        action: "code.todos".to_string(),
+        session_id: None,
+        command: None,
+        payload: serde_json::json!({ "count": todos.len() }),
+    });
+    let mut lines: Vec<String> = todos
+        .iter()
+        .take(MAX_SUMMARY_TODOS)
+        .map(|todo| {
+            let author = todo
+                .author
+                .as_ref()
+                .map(|author| format!(" ({})", author))
+                .unwrap_or_default();
+            format!(
+                "{}:{} {} {}{}",
+                todo.path, todo.line, todo.tag, todo.text, author
+            )
+        })
+        .collect();
+    if todos.len() > MAX_SUMMARY_TODOS {
+        lines.push(format!("... {} more", todos.len() - MAX_SUMMARY_TODOS));
+    }
+    let summary = if todos.is_empty() {
+        "No TODO/FIXME/HACK comments found".to_string()
+    } else {
+        format!("{} TODO comment(s):\n{}", todos.len(), lines.join("\n"))
+    };
+    ToolResult {
+        ok: true,
+        stdout_excerpt: Some(summary),
+        stderr_excerpt: None,
+        exit_code: Some(0),
+        artifacts: Some(serde_json::json!({ "todos": todos })),
+        next_suggestion: None,
+        requires_user: false,
+    }
+}
+
+/// Parses an `rg` line such as `src/main.rs:12:    // TODO(ana): handle errors`.
+fn parse_match(line: &str) -> Option<TodoItem> {
+    let (path, rest) = line.split_once(':')?;
+    let (line_no, text) = rest.split_once(':')?;
+    let line_no = line_no.parse().ok()?;
+    let (tag, after) = ["TODO", "FIXME", "HACK"]
+        .iter()
+        .filter_map(|tag| text.find(tag).map(|index| (index, *tag)))
+        .min()
+        .map(|(index, tag)| (tag, &text[index + tag.len()..]))?;
+    let mut author = None;
+    let mut after = after;
+    if let Some(rest) = after.strip_prefix('(') {
+        if let Some((name, tail)) = rest.split_once(')') {
+            if !name.trim().is_empty() {
+                author = Some(name.trim().to_string());
+            }
+            after = tail;
+        }
+    }
+    let text = after
+        .trim_start_matches([':', '-', ' '])
+        .trim()
+        .trim_end_matches("*/")
+        .trim_end_matches("-->")
+        .trim()
+        .to_string();
+    Some(TodoItem {
+        path: path.trim_start_matches("./").replace('\\', "/"),
+        line: line_no,
+        tag: tag.to_string(),
+        text,
+        author,
+    })
+}
+
+fn attach_blame_authors(root: &Path, todos: &mut [TodoItem]) {
+    let mut by_file: BTreeMap<String, Vec<usize>> = BTreeMap::new();
+    for (index, todo) in todos.iter().enumerate() {
+        if todo.author.is_none() {
+            by_file.entry(todo.path.clone()).or_default().push(index);
+        }
+    }
+    for (path, indices) in by_file.into_iter().take(MAX_BLAME_FILES) {
+        let mut cmd = Command::new("git");
+        cmd.arg("blame").arg("--line-porcelain");
+        for index in &indices {
+            let line = todos[*index].line;
+            cmd.arg("-L").arg(format!("{},{}", line, line));
+        }
+        let Ok(output) = cmd.arg("--").arg(&path).current_dir(root).output() else {
+            return;
+        };
+        if !output.status.success() {
+            // Untracked files and non-git workspaces have no blame.
+            continue;
+        }
+        let authors = parse_blame_authors(&String::from_utf8_lossy(&output.stdout));
+        for index in indices {
+            if let Some(author) = authors.get(&todos[index].line) {
+                todos[index].author = Some(author.clone());
+            }
+        }
+    }
+}
+
+/// Maps final line numbers to authors from `git blame --line-porcelain` output.
+fn parse_blame_authors(output: &str) -> BTreeMap<u32, String> {
+    let mut authors = BTreeMap::new();
+    let mut current_line = None;
+    for line in output.lines() {
+        if let Some(author) = line.strip_prefix("author ") {
+            if let Some(line_no) = current_line {
+                // Uncommitted lines are attributed to "Not Committed Yet".
+                if author != "Not Committed Yet" {
+                    authors.insert(line_no, author.to_string());
+                }
+            }
+            continue;
+        }
+        let mut parts = line.split_whitespace();
+        let header = parts.next().unwrap_or_default();
+        if header.len() == 40 && header.chars().all(|ch| ch.is_ascii_hexdigit()) {
+            current_line = parts.nth(1).and_then(|value| value.parse().ok());
+        }
+    }
+    authors
+}
@@ -1,10 +1,20 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
-use std::process::Command;
+use std::io::Read;
+use std::process::{Command, Stdio};
+use std::thread::sleep;
+use std::time::{Duration, Instant};
 
 use crate::services::audit::{now_ms, AuditEntry, AuditLog};
+use crate::services::command_policy::enforce_command_policy;
+use crate::services::patch::PatchOutcome;
+use crate::services::process::{terminate_child, TerminationReport, KILL_GRACE_MS};
+use crate::services::resources::ResourceSampler;
 
 const MAX_EXCERPT_BYTES: usize = 12_000;
+/// Summaries such as test totals and coverage are printed last, so the end of
+/// a truncated stream is kept as well.
+const MAX_TAIL_BYTES: usize = 4_000;
 const MAX_READ_BYTES: usize = 240_000;
 
 #[derive(Serialize)]
@@ -38,12 +48,20 @@ pub struct WriteFileRequest {
     pub content: String,
 }
 
+#[derive(Deserialize)]
+pub struct ApplyPatchRequest {
+    pub path: String,
+    pub patch: String,
+}
+
 #[derive(Deserialize)]
 pub struct SearchRequest {
     pub pattern: String,
     pub paths: Option<Vec<String>>,
     pub glob: Option<String>,
     pub max_results: Option<usize>,
+    /// Overrides the visibility policy for this call.
+    pub include_hidden: Option<bool>,
 }
 
 #[derive(Serialize)]
@@ -60,7 +78,9 @@ pub fn run_command(
     audit: &AuditLog,
 ) -> Result<ToolResult, String> {
     let args = request.args.unwrap_or_default();
-    if let Some(reason) = is_dangerous_command(&request.program, &args) {
+    let blocked = is_dangerous_command(&request.program, &args)
+        .or_else(|| enforce_command_policy(&format_command(&request.program, &args), audit).err());
+    if let Some(reason) = blocked {
         audit.write(AuditEntry {
             timestamp_ms: now_ms(),
             action: "tool.run_command.blocked".to_string(),
@@ -78,11 +98,44 @@ pub fn run_command(
         command.envs(env);
     }
 
-    let output = command.output().map_err(|e| e.to_string())?;
-    let stdout = String::from_utf8_lossy(&output.stdout);
-    let stderr = String::from_utf8_lossy(&output.stderr);
+    command.stdout(Stdio::piped()).stderr(Stdio::piped());
+    let mut child = command.spawn().map_err(|e| e.to_string())?;
+    let sampler = ResourceSampler::process_tree(Some(child.id()));
+    let stdout_reader = child.stdout.take().map(spawn_reader);
+    let stderr_reader = child.stderr.take().map(spawn_reader);
+    let started = Instant::now();
+    let mut termination: Option<TerminationReport> = None;
+    let status = loop {
+        if let Some(status) = child.try_wait().map_err(|e| e.to_string())? {
+            break Some(status);
+        }
+        let timed_out = request
+            .timeout_ms
+            .map(|timeout| started.elapsed() >= Duration::from_millis(timeout))
+            .unwrap_or(false);
+        if timed_out || sampler.over_budget().is_some() {
+            termination = Some(terminate_child(&mut child, KILL_GRACE_MS));
+            break child.try_wait().ok().flatten();
+        }
+        sleep(Duration::from_millis(20));
+    };
+    let exit_code = status.and_then(|status| status.code());
+    let resources = sampler.finish();
+    let output_stdout = join_reader(stdout_reader);
+    let output_stderr = join_reader(stderr_reader);
+    let stdout = String::from_utf8_lossy(&output_stdout);
+    let stderr = String::from_utf8_lossy(&output_stderr);
     let (stdout_excerpt, stdout_truncated) = truncate_utf8(&stdout, MAX_EXCERPT_BYTES);
-    let (stderr_excerpt, stderr_truncated) = truncate_utf8(&stderr, MAX_EXCERPT_BYTES);
+    let (mut stderr_excerpt, stderr_truncated) = truncate_utf8(&stderr, MAX_EXCERPT_BYTES);
+    if let Some(report) = &termination {
+        if !stderr_excerpt.is_empty() && !stderr_excerpt.ends_with('\n') {
+            stderr_excerpt.push('\n');
+        }
+        match &resources.over_budget {
+            Some(reason) => stderr_excerpt.push_str(&format!("Stopped: {}.", reason)),
+            None => stderr_excerpt.push_str(&report.summary()),
+        }
+    }
 
     audit.write(AuditEntry {
         timestamp_ms: now_ms(),
@@ -91,21 +144,28 @@ pub fn run_command(
         command: Some(format_command(&request.program, &args)),
         payload: serde_json::json!({
             "cwd": default_cwd,
-            "exit_code": output.status.code(),
-            "stdout_bytes": output.stdout.len(),
-            "stderr_bytes": output.stderr.len(),
+            "exit_code": exit_code,
+            "stdout_bytes": output_stdout.len(),
+            "stderr_bytes": output_stderr.len(),
             "stdout_truncated": stdout_truncated,
             "stderr_truncated": stderr_truncated,
             "timeout_ms": request.timeout_ms,
+            "resources": resources,
+            "termination": termination,
         }),
     });
 
     Ok(ToolResult {
-        ok: output.status.success(),
+        ok: termination.is_none() && status.map(|status| status.success()).unwrap_or(false),
         stdout_excerpt: Some(stdout_excerpt),
         stderr_excerpt: Some(stderr_excerpt),
-        exit_code: output.status.code(),
-        artifacts: None,
+        exit_code,
+        artifacts: Some(serde_json::json!({
+            "resources": resources,
+            "termination": termination,
+            "stdout_tail": stdout_truncated.then(|| tail_utf8(&stdout, MAX_TAIL_BYTES)),
+            "stderr_tail": stderr_truncated.then(|| tail_utf8(&stderr, MAX_TAIL_BYTES)),
+        })),
         next_suggestion: None,
         requires_user: false,
     })
@@ -143,11 +203,7 @@ pub fn read_file(
     }
 }
 
-pub fn write_file(
-    request: WriteFileRequest,
-    bytes_written: usize,
-    audit: &AuditLog,
-) -> ToolResult {
+pub fn write_file(request: WriteFileRequest, bytes_written: usize, audit: &AuditLog) -> ToolResult {
     audit.write(AuditEntry {
         timestamp_ms: now_ms(),
         action: "fs.write_file".to_string(),
@@ -173,11 +229,47 @@ pub fn write_file(
     }
 }
 
-pub fn search(
-    request: SearchRequest,
-    matches: Vec<SearchMatch>,
+pub fn apply_patch(
+    request: &ApplyPatchRequest,
+    outcome: &PatchOutcome,
+    bytes_written: usize,
     audit: &AuditLog,
 ) -> ToolResult {
+    let applied = outcome.content.is_some();
+    audit.write(AuditEntry {
+        timestamp_ms: now_ms(),
+        action: "fs.apply_patch".to_string(),
+        session_id: None,
+        command: None,
+        payload: serde_json::json!({
+            "path": request.path,
+            "applied": applied,
+            "hunks": outcome.hunks.len(),
+            "rejected": outcome.rejected().len(),
+            "bytes_written": bytes_written,
+        }),
+    });
+
+    ToolResult {
+        ok: applied,
+        stdout_excerpt: None,
+        stderr_excerpt: (!applied).then(|| outcome.describe()),
+        exit_code: Some(if applied { 0 } else { 1 }),
+        artifacts: Some(serde_json::json!({
+            "path": request.path,
+            "bytes_written": bytes_written,
+            "hunks": outcome.hunks,
+        })),
+        next_suggestion: (!applied).then(|| {
+            "Re-read the file and resend only the rejected hunks with context copied \
+             from its current content."
+                .to_string()
+        }),
+        requires_user: false,
+    }
+}
+
+pub fn search(request: SearchRequest, matches: Vec<SearchMatch>, audit: &AuditLog) -> ToolResult {
     audit.write(AuditEntry {
         timestamp_ms: now_ms(),
         action: "fs.search".to_string(),
@@ -228,7 +320,68 @@ fn truncate_utf8(value: &str, max_len: usize) -> (String, bool) {
     (value[..end].to_string(), true)
 }
 
-fn is_dangerous_command(program: &str, args: &[String]) -> Option<String> {
+fn tail_utf8(value: &str, max_len: usize) -> &str {
+    let mut start = value.len().saturating_sub(max_len);
+    while !value.is_char_boundary(start) {
+        start += 1;
+    }
+    &value[start..]
+}
+
+pub fn is_mutating_command(program: &str, args: &[String]) -> Option<String> {
+    if let Some(reason) = is_dangerous_command(program, args) {
+        return Some(reason);
+    }
+    let mut joined = program.to_lowercase();
+    if !args.is_empty() {
+        joined.push(' ');
+        joined.push_str(&args.join(" ").to_lowercase());
+    }
+    let patterns = [
+        "rm ",
+        "rmdir",
+        "del ",
+        "mv ",
+        "move ",
+        "cp ",
+        "copy ",
+        "mkdir",
+        "touch ",
+        "chmod",
+        "chown",
+        "sed -i",
+        "git commit",
+        "git push",
+        "git reset",
+        "git checkout",
+        "git clean",
+        "git merge",
+        "git rebase",
+        "git apply",
+        "git stash",
+        "npm install",
+        "npm uninstall",
+        "pnpm add",
+        "yarn add",
+        "pip install",
+        "cargo add",
+        "cargo install",
+        "remove-item",
+        "set-content",
+        "out-file",
+    ];
+    for pattern in patterns {
+        if joined.starts_with(pattern) || joined.contains(&format!(" {}", pattern)) {
+            return Some(format!("Mutating command pattern: {}", pattern.trim()));
+        }
+    }
+    if joined.contains(" > ") || joined.contains(" >> ") {
+        return Some("Mutating command pattern: output redirection".to_string());
+    }
+    None
+}
+
+pub fn is_dangerous_command(program: &str, args: &[String]) -> Option<String> {
     let mut joined = program.to_lowercase();
     if !args.is_empty() {
         joined.push(' ');
@@ -253,3 +406,19 @@ fn is_dangerous_command(program: &str, args: &[String]) -> Option<String> {
     }
     None
 }
+
+pub(crate) fn spawn_reader<R: Read + Send + 'static>(
+    mut source: R,
+) -> std::thread::JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buffer = Vec::new();
+        let _ = source.read_to_end(&mut buffer);
+        buffer
+    })
+}
+
+pub(crate) fn join_reader(reader: Option<std::thread::JoinHandle<Vec<u8>>>) -> Vec<u8> {
+    reader
+        .and_then(|handle| handle.join().ok())
+        .unwrap_or_default()
+}
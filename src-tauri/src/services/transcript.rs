@@ -0,0 +1,337 @@
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::services::kernel::{ChatMessage, Plan, PlanStep};
+
+const MAX_IMPORTED_MESSAGES: usize = 200;
+const MAX_TOOL_TEXT_CHARS: usize = 600;
+const MAX_GOAL_CHARS: usize = 200;
+
+#[derive(Deserialize)]
+pub struct TranscriptImportRequest {
+    pub path: Option<String>,
+    pub content: Option<String>,
+}
+
+#[derive(Clone, Copy, Serialize, PartialEq, Debug)]
+#[serde(rename_all = "camelCase")]
+pub enum TranscriptFormat {
+    OpenHands,
+    Claude,
+    ChatMessages,
+    PlainText,
+}
+
+pub struct ImportedTranscript {
+    pub format: TranscriptFormat,
+    pub messages: Vec<ChatMessage>,
+    pub plan: Option<Plan>,
+}
+
+/// Detects the transcript flavour and converts it into run messages and a plan.
+/// Supported: OpenHands event streams, Claude JSON/JSONL transcripts,
+/// `{role, content}` chat arrays and `User:`/`Assistant:` plain text.
+pub fn parse_transcript(raw: &str) -> Result<ImportedTranscript, String> {
+    let raw = raw.trim_start_matches('\u{feff}').trim();
+    if raw.is_empty() {
+        return Err("Transcript is empty".to_string());
+    }
+    let (format, mut messages) = match serde_json::from_str::<serde_json::Value>(raw) {
+        Ok(value) => parse_json_transcript(&value)?,
+        Err(_) => match parse_json_lines(raw) {
+            Some(values) => parse_json_items(&values)?,
+            None => (TranscriptFormat::PlainText, parse_plain_text(raw)),
+        },
+    };
+    messages.retain(|message| !message.content.trim().is_empty());
+    if messages.is_empty() {
+        return Err("No messages found in transcript".to_string());
+    }
+    if messages.len() > MAX_IMPORTED_MESSAGES {
+        messages.drain(..messages.len() - MAX_IMPORTED_MESSAGES);
+    }
+    let plan = extract_plan(&messages);
+    Ok(ImportedTranscript {
+        format,
+        messages,
+        plan,
+    })
+}
+
+fn parse_json_transcript(
+    value: &serde_json::Value,
+) -> Result<(TranscriptFormat, Vec<ChatMessage>), String> {
+    if let Some(items) = value.as_array() {
+        return parse_json_items(items);
+    }
+    for key in ["messages", "history", "events", "trajectory"] {
+        if let Some(items) = value.get(key).and_then(|items| items.as_array()) {
+            return parse_json_items(items);
+        }
+    }
+    Err("Unrecognized transcript JSON".to_string())
+}
+
+fn parse_json_lines(raw: &str) -> Option<Vec<serde_json::Value>> {
+    raw.lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(|line| serde_json::from_str(line).ok())
+        .collect()
+}
+
+fn parse_json_items(
+    items: &[serde_json::Value],
+) -> Result<(TranscriptFormat, Vec<ChatMessage>), String> {
+    let is_openhands = items.iter().any(|item| {
+        item.get("source").is_some()
+            && (item.get("action").is_some() || item.get("observation").is_some())
+    });
+    if is_openhands {
+        let messages = items.iter().filter_map(openhands_message).collect();
+        return Ok((TranscriptFormat::OpenHands, messages));
+    }
+    let is_claude = items.iter().any(|item| {
+        item.get("message").is_some()
+            && matches!(
+                item.get("type").and_then(|value| value.as_str()),
+                Some("user" | "assistant")
+            )
+    });
+    if is_claude {
+        let messages = items
+            .iter()
+            .filter_map(|item| item.get("message"))
+            .filter_map(role_content_message)
+            .collect();
+        return Ok((TranscriptFormat::Claude, messages));
+    }
+    let messages: Vec<ChatMessage> = items.iter().filter_map(role_content_message).collect();
+    if messages.is_empty() {
+        return Err("Unrecognized transcript entries".to_string());
+    }
+    Ok((TranscriptFormat::ChatMessages, messages))
+}
+
+fn role_content_message(item: &serde_json::Value) -> Option<ChatMessage> {
+    let role = normalize_role(item.get("role")?.as_str()?)?;
+    let content = content_text(item.get("content")?);
+    Some(ChatMessage {
+        role: role.to_string(),
+        content,
+    })
+}
+
+fn openhands_message(item: &serde_json::Value) -> Option<ChatMessage> {
+    let source = item.get("source").and_then(|value| value.as_str())?;
+    let role = if source == "user" {
+        "user"
+    } else {
+        "assistant"
+    };
+    let args = item.get("args").cloned().unwrap_or_default();
+    let arg = |key: &str| {
+        args.get(key)
+            .and_then(|value| value.as_str())
+            .unwrap_or_default()
+            .to_string()
+    };
+    if let Some(observation) = item.get("observation").and_then(|value| value.as_str()) {
+        if source == "user" {
+            return None;
+        }
+        let content = item
+            .get("content")
+            .and_then(|value| value.as_str())
+            .unwrap_or_default();
+        return Some(ChatMessage {
+            role: "assistant".to_string(),
+            content: format!("Observation ({}):\n{}", observation, trim_chars(content)),
+        });
+    }
+    let action = item.get("action").and_then(|value| value.as_str())?;
+    let content = match action {
+        "message" => {
+            let text = arg("content");
+            if text.is_empty() {
+                item.get("message")
+                    .and_then(|value| value.as_str())
+                    .unwrap_or_default()
+                    .to_string()
+            } else {
+                text
+            }
+        }
+        "run" => format!("Ran `{}`", arg("command")),
+        "read" => format!("Read {}", arg("path")),
+        "write" | "edit" => format!("Edited {}", arg("path")),
+        "finish" => {
+            let thought = arg("final_thought");
+            if thought.is_empty() {
+                "Finished.".to_string()
+            } else {
+                thought
+            }
+        }
+        "system" | "recall" | "change_agent_state" => return None,
+        _ => item
+            .get("message")
+            .and_then(|value| value.as_str())
+            .unwrap_or_default()
+            .to_string(),
+    };
+    Some(ChatMessage {
+        role: role.to_string(),
+        content,
+    })
+}
+
+fn content_text(content: &serde_json::Value) -> String {
+    if let Some(text) = content.as_str() {
+        return text.to_string();
+    }
+    let Some(blocks) = content.as_array() else {
+        return String::new();
+    };
+    let mut parts = Vec::new();
+    for block in blocks {
+        match block.get("type").and_then(|value| value.as_str()) {
+            Some("text") => {
+                if let Some(text) = block.get("text").and_then(|value| value.as_str()) {
+                    parts.push(text.to_string());
+                }
+            }
+            Some("tool_use") => {
+                let name = block
+                    .get("name")
+                    .and_then(|value| value.as_str())
+                    .unwrap_or("tool");
+                let input = block.get("input").map(|input| input.to_string());
+                parts.push(format!(
+                    "Tool call {}: {}",
+                    name,
+                    trim_chars(&input.unwrap_or_default())
+                ));
+            }
+            Some("tool_result") => {
+                let text = block.get("content").map(content_text).unwrap_or_default();
+                parts.push(format!("Tool result:\n{}", trim_chars(&text)));
+            }
+            _ => {}
+        }
+    }
+    parts.join("\n\n")
+}
+
+fn parse_plain_text(raw: &str) -> Vec<ChatMessage> {
+    let mut messages: Vec<ChatMessage> = Vec::new();
+    for line in raw.lines() {
+        if let Some((role, rest)) = plain_text_speaker(line) {
+            messages.push(ChatMessage {
+                role: role.to_string(),
+                content: rest.trim().to_string(),
+            });
+            continue;
+        }
+        match messages.last_mut() {
+            Some(message) => {
+                message.content.push('\n');
+                message.content.push_str(line);
+            }
+            None => messages.push(ChatMessage {
+                role: "user".to_string(),
+                content: line.to_string(),
+            }),
+        }
+    }
+    for message in messages.iter_mut() {
+        message.content = message.content.trim().to_string();
+    }
+    messages
+}
+
+fn plain_text_speaker(line: &str) -> Option<(&'static str, &str)> {
+    let trimmed = line.trim_start_matches('#').trim_start();
+    let (speaker, rest) = match trimmed.split_once(':') {
+        Some((speaker, rest)) => (speaker, rest),
+        None if line.starts_with('#') => (trimmed, ""),
+        None => return None,
+    };
+    let role = normalize_role(speaker.trim().trim_matches('*'))?;
+    Some((role, rest))
+}
+
+fn normalize_role(role: &str) -> Option<&'static str> {
+    match role.trim().to_lowercase().as_str() {
+        "user" | "human" | "you" => Some("user"),
+        "assistant" | "ai" | "agent" | "claude" | "model" => Some("assistant"),
+        _ => None,
+    }
+}
+
+/// Uses the newest assistant checklist or numbered list as the plan and the
+/// first user message as its goal.
+fn extract_plan(messages: &[ChatMessage]) -> Option<Plan> {
+    let goal = messages
+        .iter()
+        .find(|message| message.role == "user")
+        .and_then(|message| message.content.lines().find(|line| !line.trim().is_empty()))
+        .map(|line| line.trim().chars().take(MAX_GOAL_CHARS).collect::<String>())?;
+    let steps = messages
+        .iter()
+        .rev()
+        .filter(|message| message.role == "assistant")
+        .map(|message| list_steps(&message.content))
+        .find(|steps| steps.len() >= 2)
+        .unwrap_or_default();
+    if steps.is_empty() {
+        return None;
+    }
+    Some(Plan {
+        version: 1,
+        goal,
+        steps: steps
+            .into_iter()
+            .map(|(title, done)| PlanStep {
+                id: format!("plan_{}", Uuid::new_v4()),
+                title,
+                status: if done { "done" } else { "pending" }.to_string(),
+                done,
+            })
+            .collect(),
+    })
+}
+
+fn list_steps(content: &str) -> Vec<(String, bool)> {
+    let mut steps = Vec::new();
+    for line in content.lines() {
+        let line = line.trim();
+        let checklist = ["- [ ] ", "* [ ] ", "- [x] ", "* [x] ", "- [X] ", "* [X] "]
+            .iter()
+            .find_map(|prefix| {
+                line.strip_prefix(prefix)
+                    .map(|rest| (rest, prefix.contains('x') || prefix.contains('X')))
+            });
+        if let Some((title, done)) = checklist {
+            steps.push((title.trim().to_string(), done));
+            continue;
+        }
+        let digits = line.chars().take_while(|ch| ch.is_ascii_digit()).count();
+        if digits > 0 {
+            if let Some(title) = line[digits..].strip_prefix(". ") {
+                steps.push((title.trim().to_string(), false));
+            }
+        }
+    }
+    steps.retain(|(title, _)| !title.is_empty());
+    steps
+}
+
+fn trim_chars(text: &str) -> String {
+    let text = text.trim();
+    if text.chars().count() <= MAX_TOOL_TEXT_CHARS {
+        return text.to_string();
+    }
+    let mut trimmed: String = text.chars().take(MAX_TOOL_TEXT_CHARS).collect();
+    trimmed.push_str("...");
+    trimmed
+}
@@ -0,0 +1,153 @@
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use crate::services::app_data::{app_data_dir, workspace_key};
+
+/// Workspace files that make the app run programs. They come with the repo,
+/// so none of them is acted on until the user trusts its current contents.
+pub const TRUSTED_CONFIGS: &[&str] = &["hooks.json", "verify.json", "mcp.json"];
+
+static TRUST_LOCK: Mutex<()> = Mutex::new(());
+
+/// Approved content hash per workspace root and config file.
+#[derive(Default, Serialize, Deserialize)]
+struct TrustStore {
+    workspaces: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ConfigTrust {
+    pub name: String,
+    pub present: bool,
+    /// False when the file changed since it was trusted, or never was.
+    pub trusted: bool,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceTrust {
+    pub root: String,
+    pub configs: Vec<ConfigTrust>,
+}
+
+impl WorkspaceTrust {
+    /// Whether a config file is present but not trusted, so the user must be
+    /// asked before it takes effect.
+    pub fn needs_approval(&self) -> bool {
+        self.configs
+            .iter()
+            .any(|config| config.present && !config.trusted)
+    }
+}
+
+/// Trust decisions live in the app data dir, never inside a workspace where a
+/// repo could ship its own. Without one nothing is trusted.
+fn trust_path() -> Option<PathBuf> {
+    app_data_dir().map(|dir| dir.join("workspace-trust.json"))
+}
+
+fn config_path(root: &Path, name: &str) -> PathBuf {
+    root.join(".taurihands").join(name)
+}
+
+fn hash_bytes(bytes: &[u8]) -> String {
+    Sha256::digest(bytes)
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+fn content_hash(path: &Path) -> Option<String> {
+    std::fs::read(path).ok().map(|bytes| hash_bytes(&bytes))
+}
+
+fn load_store() -> TrustStore {
+    trust_path()
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+fn save_store(store: &TrustStore) -> Result<(), String> {
+    let path =
+        trust_path().ok_or_else(|| "No app data directory for trust decisions".to_string())?;
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_vec_pretty(store).map_err(|e| e.to_string())?;
+    std::fs::write(path, data).map_err(|e| e.to_string())
+}
+
+/// Contents of `.taurihands/<name>` when they are exactly what the user
+/// trusted for this workspace. A missing or changed file yields `None`, so
+/// callers fall back to their defaults. Reading and checking the same bytes
+/// leaves no window for a swap in between.
+pub fn read_trusted_config(root: &Path, name: &str) -> Option<String> {
+    let bytes = std::fs::read(config_path(root, name)).ok()?;
+    let hash = hash_bytes(&bytes);
+    let trusted = {
+        let _guard = TRUST_LOCK.lock();
+        load_store()
+            .workspaces
+            .get(&workspace_key(root))
+            .and_then(|configs| configs.get(name))
+            .is_some_and(|trusted| *trusted == hash)
+    };
+    trusted.then(|| String::from_utf8_lossy(&bytes).to_string())
+}
+
+pub fn workspace_trust(root: &Path) -> WorkspaceTrust {
+    let configs = TRUSTED_CONFIGS
+        .iter()
+        .map(|name| ConfigTrust {
+            name: name.to_string(),
+            present: config_path(root, name).is_file(),
+            trusted: read_trusted_config(root, name).is_some(),
+        })
+        .collect();
+    WorkspaceTrust {
+        root: workspace_key(root),
+        configs,
+    }
+}
+
+/// Trusts the current contents of every config file in the workspace, or
+/// forgets all of them. Approval has to be given again after a file changes.
+pub fn set_workspace_trust(root: &Path, trusted: bool) -> Result<WorkspaceTrust, String> {
+    {
+        let _guard = TRUST_LOCK.lock();
+        let mut store = load_store();
+        let key = workspace_key(root);
+        if trusted {
+            let configs: BTreeMap<String, String> = TRUSTED_CONFIGS
+                .iter()
+                .filter_map(|name| {
+                    content_hash(&config_path(root, name)).map(|hash| (name.to_string(), hash))
+                })
+                .collect();
+            store.workspaces.insert(key, configs);
+        } else {
+            store.workspaces.remove(&key);
+        }
+        save_store(&store)?;
+    }
+    Ok(workspace_trust(root))
+}
+
+/// Trusts one config file as just written by the user through the app.
+pub fn trust_config(root: &Path, name: &str) -> Result<(), String> {
+    let hash =
+        content_hash(&config_path(root, name)).ok_or_else(|| format!("Unable to read {}", name))?;
+    let _guard = TRUST_LOCK.lock();
+    let mut store = load_store();
+    store
+        .workspaces
+        .entry(workspace_key(root))
+        .or_default()
+        .insert(name.to_string(), hash);
+    save_store(&store)
+}
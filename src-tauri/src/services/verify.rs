@@ -0,0 +1,172 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::services::audit::AuditLog;
+use crate::services::tools::{run_command, CommandRequest};
+use crate::services::trust::read_trusted_config;
+
+const DEFAULT_VERIFY_TIMEOUT_MS: u64 = 30_000;
+const MAX_VERIFY_OUTPUT_CHARS: usize = 1_200;
+
+#[derive(Clone, Serialize, Deserialize, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyPolicy {
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default)]
+    pub checks: Vec<VerifyCheck>,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyCheck {
+    pub name: String,
+    #[serde(default)]
+    pub extensions: Vec<String>,
+    pub program: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct VerifyResult {
+    pub name: String,
+    pub ok: bool,
+    pub exit_code: Option<i32>,
+    pub output: String,
+}
+
+pub fn verify_policy_path(root: &Path) -> PathBuf {
+    root.join(".taurihands").join("verify.json")
+}
+
+pub fn load_verify_policy(root: &Path) -> VerifyPolicy {
+    std::fs::read_to_string(verify_policy_path(root))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+/// The policy the kernel acts on: the default, which runs nothing, until the
+/// user trusts this verify.json.
+pub fn load_trusted_verify_policy(root: &Path) -> VerifyPolicy {
+    read_trusted_config(root, "verify.json")
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_verify_policy(root: &Path, policy: &VerifyPolicy) -> Result<(), String> {
+    let path = verify_policy_path(root);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_vec_pretty(policy).map_err(|e| e.to_string())?;
+    std::fs::write(path, data).map_err(|e| e.to_string())
+}
+
+/// Runs the built-in syntax check and any matching project checks for a written file.
+pub fn verify_written_file(
+    root: &Path,
+    policy: &VerifyPolicy,
+    path: &str,
+    resolved: &Path,
+    audit: &AuditLog,
+) -> Vec<VerifyResult> {
+    if !policy.enabled {
+        return Vec::new();
+    }
+    let extension = resolved
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    let mut results = Vec::new();
+    if let Some(result) = builtin_syntax_check(&extension, resolved) {
+        results.push(result);
+    }
+    for check in &policy.checks {
+        let matches = check.extensions.is_empty()
+            || check
+                .extensions
+                .iter()
+                .any(|ext| ext.trim_start_matches('.').eq_ignore_ascii_case(&extension));
+        if !matches {
+            continue;
+        }
+        results.push(run_check(root, check, path, audit));
+    }
+    results
+}
+
+pub fn format_verify_results(results: &[VerifyResult]) -> String {
+    let mut lines = Vec::new();
+    for result in results {
+        let status = if result.ok { "passed" } else { "FAILED" };
+        lines.push(format!("[verify] {} {}", result.name, status));
+        if !result.ok && !result.output.trim().is_empty() {
+            lines.push(result.output.trim().to_string());
+        }
+    }
+    lines.join("\n")
+}
+
+fn builtin_syntax_check(extension: &str, resolved: &Path) -> Option<VerifyResult> {
+    if extension != "json" {
+        return None;
+    }
+    let content = std::fs::read_to_string(resolved).ok()?;
+    let parsed = serde_json::from_str::<serde_json::Value>(&content);
+    Some(VerifyResult {
+        name: "json syntax".to_string(),
+        ok: parsed.is_ok(),
+        exit_code: None,
+        output: parsed.err().map(|err| err.to_string()).unwrap_or_default(),
+    })
+}
+
+fn run_check(root: &Path, check: &VerifyCheck, path: &str, audit: &AuditLog) -> VerifyResult {
+    let args = check
+        .args
+        .iter()
+        .map(|arg| arg.replace("{path}", path))
+        .collect();
+    let cwd = root.to_string_lossy().to_string();
+    let result = run_command(
+        CommandRequest {
+            program: check.program.clone(),
+            args: Some(args),
+            cwd: Some(cwd.clone()),
+            env: None,
+            timeout_ms: Some(check.timeout_ms.unwrap_or(DEFAULT_VERIFY_TIMEOUT_MS)),
+        },
+        &cwd,
+        audit,
+    );
+    match result {
+        Ok(result) => {
+            let mut output = result.stderr_excerpt.unwrap_or_default();
+            if output.trim().is_empty() {
+                output = result.stdout_excerpt.unwrap_or_default();
+            }
+            VerifyResult {
+                name: check.name.clone(),
+                ok: result.ok,
+                exit_code: result.exit_code,
+                output: output
+                    .trim()
+                    .chars()
+                    .take(MAX_VERIFY_OUTPUT_CHARS)
+                    .collect(),
+            }
+        }
+        Err(err) => VerifyResult {
+            name: check.name.clone(),
+            ok: false,
+            exit_code: None,
+            output: err,
+        },
+    }
+}
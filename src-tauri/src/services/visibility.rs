@@ -0,0 +1,98 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+
+/// How file tools treat dotfiles and paths inside dot-directories.
+#[derive(Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HiddenMode {
+    /// Listed, searched and accessible like any other path.
+    Show,
+    /// Left out of listings and searches, but accessible when named explicitly.
+    #[default]
+    Explicit,
+    /// Left out of listings and searches, and refused when named explicitly.
+    Deny,
+}
+
+/// The one visibility rule shared by tree listing, search, and the read and
+/// write tools. User calls can override it with `include_hidden`; agent
+/// actions can only opt into what the policy does not deny.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct VisibilityPolicy {
+    pub hidden: HiddenMode,
+}
+
+pub fn visibility_policy_path(root: &Path) -> PathBuf {
+    root.join(".taurihands").join("visibility.json")
+}
+
+pub fn load_visibility_policy(root: &Path) -> VisibilityPolicy {
+    std::fs::read_to_string(visibility_policy_path(root))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_visibility_policy(root: &Path, policy: &VisibilityPolicy) -> Result<(), String> {
+    let path = visibility_policy_path(root);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_vec_pretty(policy).map_err(|e| e.to_string())?;
+    std::fs::write(path, data).map_err(|e| e.to_string())
+}
+
+impl VisibilityPolicy {
+    /// Whether listings and searches include hidden entries.
+    pub fn lists_hidden(&self, include_hidden: Option<bool>) -> bool {
+        include_hidden.unwrap_or(self.hidden == HiddenMode::Show)
+    }
+
+    /// `include_hidden` as requested by an agent action. Only the user may
+    /// override `Deny`, so the agent's request is dropped there.
+    pub fn agent_include_hidden(&self, include_hidden: Option<bool>) -> Option<bool> {
+        if self.hidden == HiddenMode::Deny {
+            Some(false)
+        } else {
+            include_hidden
+        }
+    }
+
+    /// Whether a path the caller did not name, such as a fuzzy match, may be
+    /// used in its place. Hidden paths only qualify when they are listed.
+    pub fn allows_resolved(&self, root: &Path, path: &str) -> bool {
+        self.hidden == HiddenMode::Show || !is_hidden_path(root, path)
+    }
+
+    /// Checks a path named explicitly by a tool call. Returns whether it is
+    /// hidden, so the caller can report the access.
+    pub fn check_access(
+        &self,
+        root: &Path,
+        path: &str,
+        include_hidden: Option<bool>,
+    ) -> Result<bool, String> {
+        if !is_hidden_path(root, path) {
+            return Ok(false);
+        }
+        let allowed = include_hidden.unwrap_or(self.hidden != HiddenMode::Deny);
+        if !allowed {
+            return Err(format!(
+                "{} is a hidden path; the visibility policy denies it without include_hidden",
+                path
+            ));
+        }
+        Ok(true)
+    }
+}
+
+/// Whether any component below the workspace root starts with a dot.
+pub fn is_hidden_path(root: &Path, path: &str) -> bool {
+    let path = Path::new(path);
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    relative.components().any(|component| match component {
+        Component::Normal(name) => name.to_string_lossy().starts_with('.'),
+        _ => false,
+    })
+}
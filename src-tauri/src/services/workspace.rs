@@ -1,12 +1,28 @@
+use serde::Serialize;
 use std::collections::HashSet;
 use std::env;
 use std::fs;
 use std::path::{Component, Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
+use crate::services::scratch::scratch_relative;
+
+/// Path prefixes accepted wherever a workspace path is, so paths the model
+/// writes do not depend on where, or on which OS, the workspace lives.
+pub const PATH_ALIASES: &[(&str, &str)] = &[
+    ("@workspace", "the workspace root"),
+    (
+        "@scratch",
+        "this run's scratch directory, deleted when the run ends",
+    ),
+    ("@artifacts", "the artifact store, kept across runs"),
+];
+
 #[derive(Clone, Default)]
 pub struct WorkspaceState {
     root: Arc<Mutex<PathBuf>>,
+    /// Run whose scratch directory `@scratch` names.
+    scratch_run: Arc<Mutex<Option<String>>>,
 }
 
 impl WorkspaceState {
@@ -14,9 +30,47 @@ impl WorkspaceState {
         let canonical = canonicalize_or(root);
         Self {
             root: Arc::new(Mutex::new(canonical)),
+            scratch_run: Arc::new(Mutex::new(None)),
         }
     }
 
+    pub fn set_scratch_run(&self, run_id: Option<String>) {
+        *self.scratch_run.lock().expect("workspace lock poisoned") = run_id;
+    }
+
+    /// Rewrites a leading path alias, e.g. `@scratch/out.txt`, to the path it
+    /// stands for relative to the root. `None` when `input` has no alias.
+    pub fn expand_alias(&self, input: &str) -> Result<Option<String>, String> {
+        let trimmed = strip_wrapping_quotes(input.trim());
+        let (alias, rest) = match trimmed.find(['/', '\\']) {
+            Some(index) => (&trimmed[..index], &trimmed[index + 1..]),
+            None => (trimmed, ""),
+        };
+        let base = match alias {
+            "@workspace" => String::new(),
+            "@scratch" => {
+                let run_id = self
+                    .scratch_run
+                    .lock()
+                    .expect("workspace lock poisoned")
+                    .clone()
+                    .ok_or("@scratch is only available during a run")?;
+                scratch_relative(&run_id)
+            }
+            "@artifacts" => ".taurihands/artifacts".to_string(),
+            _ => return Ok(None),
+        };
+        let rest = rest.replace('\\', "/");
+        let rest = rest.trim_matches('/');
+        let expanded = match (base.is_empty(), rest.is_empty()) {
+            (true, true) => ".".to_string(),
+            (true, false) => rest.to_string(),
+            (false, true) => base,
+            (false, false) => format!("{}/{}", base, rest),
+        };
+        Ok(Some(expanded))
+    }
+
     pub fn root(&self) -> PathBuf {
         self.root.lock().expect("workspace lock poisoned").clone()
     }
@@ -36,7 +90,8 @@ impl WorkspaceState {
 
     pub fn resolve_path(&self, input: &str) -> Result<PathBuf, String> {
         let root = self.root();
-        let candidate = resolve_candidate(&root, input);
+        let expanded = self.expand_alias(input)?;
+        let candidate = resolve_candidate(&root, expanded.as_deref().unwrap_or(input));
         let canonical = candidate
             .canonicalize()
             .map_err(|e| format!("Path not found: {}", e))?;
@@ -46,7 +101,8 @@ impl WorkspaceState {
 
     pub fn resolve_path_for_write(&self, input: &str) -> Result<PathBuf, String> {
         let root = self.root();
-        let candidate = resolve_candidate(&root, input);
+        let expanded = self.expand_alias(input)?;
+        let candidate = resolve_candidate(&root, expanded.as_deref().unwrap_or(input));
         if candidate.exists() {
             let canonical = candidate
                 .canonicalize()
@@ -61,6 +117,195 @@ impl WorkspaceState {
     }
 }
 
+const SIMILAR_DIR_LIMIT: usize = 5;
+const SIMILAR_DIR_MAX_DEPTH: usize = 4;
+const SIMILAR_DIR_MAX_VISITS: usize = 2_000;
+const SIMILAR_DIR_SKIP: &[&str] = &[".git", "node_modules", "target", "dist", ".taurihands"];
+
+/// Explains why a directory argument (e.g. an action's cwd) could not be used.
+/// Paths are reported relative to the workspace root.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct DirectoryDiagnosis {
+    pub requested: String,
+    pub reason: String,
+    pub nearest_parent: String,
+    pub similar: Vec<String>,
+}
+
+impl DirectoryDiagnosis {
+    pub fn describe(&self) -> String {
+        let mut text = format!(
+            "cwd \"{}\" is not usable: {}.\nNearest existing parent: {}",
+            self.requested, self.reason, self.nearest_parent
+        );
+        if self.similar.is_empty() {
+            text.push_str("\nNo similarly named directories found.");
+        } else {
+            text.push_str(&format!(
+                "\nSimilar directories: {}",
+                self.similar.join(", ")
+            ));
+        }
+        text.push_str("\nRetry with one of these as cwd, or omit cwd to use the workspace root.");
+        text
+    }
+}
+
+impl WorkspaceState {
+    /// Resolves a directory, or describes the nearest existing parent and
+    /// similarly named directories so the caller can retry with a valid one.
+    pub fn resolve_dir(&self, input: &str) -> Result<PathBuf, DirectoryDiagnosis> {
+        let expanded = self.expand_alias(input).ok().flatten();
+        let input = expanded.as_deref().unwrap_or(input);
+        let reason = match self.resolve_path(input) {
+            Ok(resolved) if resolved.is_dir() => return Ok(resolved),
+            Ok(_) => "path is a file, not a directory".to_string(),
+            Err(err) => err,
+        };
+        let root = canonicalize_or(self.root());
+        let candidate = lexical_normalize(&resolve_candidate(&root, input));
+        let mut parent = candidate.as_path();
+        while !(parent.is_dir() && parent.starts_with(&root)) {
+            match parent.parent() {
+                Some(next) if next.starts_with(&root) => parent = next,
+                _ => {
+                    parent = &root;
+                    break;
+                }
+            }
+        }
+        let missing = candidate
+            .strip_prefix(parent)
+            .ok()
+            .and_then(|rest| rest.components().next())
+            .map(|component| component.as_os_str().to_string_lossy().to_string())
+            .or_else(|| {
+                candidate
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+            })
+            .unwrap_or_default();
+        let target = candidate
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let mut similar = Vec::new();
+        for dir in similar_dirs(parent, &missing)
+            .into_iter()
+            .chain(similar_dirs_in_tree(&root, &target))
+        {
+            let relative = relative_display(&root, &dir);
+            if !similar.contains(&relative) && similar.len() < SIMILAR_DIR_LIMIT {
+                similar.push(relative);
+            }
+        }
+        Err(DirectoryDiagnosis {
+            requested: input.to_string(),
+            reason,
+            nearest_parent: relative_display(&root, parent),
+            similar,
+        })
+    }
+}
+
+fn relative_display(root: &Path, path: &Path) -> String {
+    match path.strip_prefix(root) {
+        Ok(rest) if rest.as_os_str().is_empty() => ".".to_string(),
+        Ok(rest) => rest.to_string_lossy().replace('\\', "/"),
+        Err(_) => display_path(path),
+    }
+}
+
+fn similar_dirs(parent: &Path, name: &str) -> Vec<PathBuf> {
+    let Ok(entries) = fs::read_dir(parent) else {
+        return Vec::new();
+    };
+    let mut scored: Vec<(usize, PathBuf)> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_dir())
+        .filter_map(|path| {
+            let entry_name = path.file_name()?.to_string_lossy().to_string();
+            name_distance(name, &entry_name).map(|score| (score, path))
+        })
+        .collect();
+    scored.sort();
+    scored.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Finds directories anywhere in the workspace named like `name`, e.g. a cwd of
+/// `frontend` when the project lives in `apps/frontend`.
+fn similar_dirs_in_tree(root: &Path, name: &str) -> Vec<PathBuf> {
+    if name.is_empty() {
+        return Vec::new();
+    }
+    let mut found = Vec::new();
+    let mut queue = vec![(root.to_path_buf(), 0)];
+    let mut visits = 0;
+    while let Some((dir, depth)) = queue.pop() {
+        visits += 1;
+        if visits > SIMILAR_DIR_MAX_VISITS || depth > SIMILAR_DIR_MAX_DEPTH {
+            continue;
+        }
+        let Ok(entries) = fs::read_dir(&dir) else {
+            continue;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if !path.is_dir() {
+                continue;
+            }
+            let entry_name = entry.file_name().to_string_lossy().to_string();
+            if SIMILAR_DIR_SKIP.contains(&entry_name.as_str()) {
+                continue;
+            }
+            if entry_name.eq_ignore_ascii_case(name) {
+                found.push(path.clone());
+            }
+            queue.push((path, depth + 1));
+        }
+    }
+    found.sort();
+    found
+}
+
+/// Scores how close a directory name is to the requested one; `None` if unrelated.
+fn name_distance(requested: &str, candidate: &str) -> Option<usize> {
+    let requested = requested.to_lowercase();
+    let candidate = candidate.to_lowercase();
+    if requested.is_empty() {
+        return None;
+    }
+    if requested == candidate {
+        return Some(0);
+    }
+    if candidate.starts_with(&requested) || requested.starts_with(&candidate) {
+        return Some(1);
+    }
+    let distance = edit_distance(&requested, &candidate);
+    let allowed = (requested.chars().count() / 3).max(1);
+    (distance <= allowed).then_some(distance + 1)
+}
+
+pub fn edit_distance(left: &str, right: &str) -> usize {
+    let right: Vec<char> = right.chars().collect();
+    let mut previous: Vec<usize> = (0..=right.len()).collect();
+    for (i, left_char) in left.chars().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, right_char) in right.iter().enumerate() {
+            let cost = usize::from(left_char != *right_char);
+            current.push(
+                (previous[j] + cost)
+                    .min(previous[j + 1] + 1)
+                    .min(current[j] + 1),
+            );
+        }
+        previous = current;
+    }
+    previous[right.len()]
+}
+
 pub fn default_workspace_root() -> PathBuf {
     let cwd = env::current_dir().unwrap_or_else(|_| PathBuf::from("."));
     if cwd.file_name().and_then(|name| name.to_str()) == Some("src-tauri") {
@@ -82,6 +327,70 @@ pub fn display_path(path: &Path) -> String {
     }
 }
 
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IgnoreStatus {
+    pub is_git_repo: bool,
+    pub ignored: bool,
+    pub ignored_by: Option<String>,
+}
+
+pub fn taurihands_ignore_status(root: &Path) -> IgnoreStatus {
+    let is_git_repo = root.join(".git").exists();
+    let ignored_by = [".gitignore", ".git/info/exclude"]
+        .into_iter()
+        .find(|file| file_ignores_taurihands(&root.join(file)))
+        .map(|file| file.to_string());
+    IgnoreStatus {
+        is_git_repo,
+        ignored: ignored_by.is_some(),
+        ignored_by,
+    }
+}
+
+pub fn ignore_taurihands(root: &Path, target: &str) -> Result<IgnoreStatus, String> {
+    let status = taurihands_ignore_status(root);
+    if !status.is_git_repo {
+        return Err("Workspace is not a git repository".to_string());
+    }
+    if status.ignored {
+        return Ok(status);
+    }
+    let path = match target {
+        "gitignore" => root.join(".gitignore"),
+        "exclude" => {
+            let git_dir = root.join(".git");
+            if !git_dir.is_dir() {
+                return Err(".git is not a directory; use gitignore instead".to_string());
+            }
+            git_dir.join("info").join("exclude")
+        }
+        other => return Err(format!("Unknown ignore target: {}", other)),
+    };
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let mut content = fs::read_to_string(&path).unwrap_or_default();
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push('\n');
+    }
+    content.push_str(".taurihands/\n");
+    fs::write(&path, content).map_err(|e| e.to_string())?;
+    Ok(taurihands_ignore_status(root))
+}
+
+fn file_ignores_taurihands(path: &Path) -> bool {
+    let Ok(content) = fs::read_to_string(path) else {
+        return false;
+    };
+    content.lines().any(|line| {
+        matches!(
+            line.trim(),
+            ".taurihands" | ".taurihands/" | "/.taurihands" | "/.taurihands/"
+        )
+    })
+}
+
 const READ_FALLBACK_EXTS: &[&str] = &[
     "ts", "tsx", "js", "jsx", "vue", "mjs", "cjs", "mts", "cts", "json", "md", "toml", "yaml",
     "yml",
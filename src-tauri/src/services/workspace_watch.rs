@@ -0,0 +1,239 @@
+use notify::event::{ModifyKind, RenameMode};
+use notify::{recommended_watcher, Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::services::events::SharedEventSink;
+use crate::services::ignore::IgnoreRules;
+
+pub const WORKSPACE_CHANGED_EVENT: &str = "workspace-changed";
+/// Changes are sent once the workspace has been quiet this long...
+const DEBOUNCE: Duration = Duration::from_millis(300);
+/// ...or at the latest this long after the first pending change.
+const MAX_DELAY: Duration = Duration::from_secs(2);
+
+/// Payload of `workspace-changed`, with workspace-relative `/` paths.
+#[derive(Clone, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WorkspaceChanges {
+    pub created: Vec<String>,
+    pub modified: Vec<String>,
+    pub deleted: Vec<String>,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum ChangeKind {
+    Created,
+    Modified,
+    Deleted,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct WatchStatus {
+    pub watching: bool,
+    pub root: Option<String>,
+    /// The `show_hidden` the watch was started with.
+    pub show_hidden: Option<bool>,
+}
+
+struct ActiveWatch {
+    root: PathBuf,
+    show_hidden: Option<bool>,
+    // Dropping the watcher closes the channel, which ends the debounce thread.
+    _watcher: RecommendedWatcher,
+}
+
+/// Watches the workspace for file changes made outside the app.
+#[derive(Clone, Default)]
+pub struct WorkspaceWatcher {
+    active: Arc<Mutex<Option<ActiveWatch>>>,
+}
+
+impl WorkspaceWatcher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Starts watching `root`, replacing any earlier watch. Paths matched by
+    /// `rules`, or inside an ignored directory, are not reported.
+    pub fn start(
+        &self,
+        app: SharedEventSink,
+        root: PathBuf,
+        rules: IgnoreRules,
+        show_hidden: Option<bool>,
+    ) -> Result<WatchStatus, String> {
+        let (sender, receiver) = channel();
+        let mut watcher = recommended_watcher(sender).map_err(|e| e.to_string())?;
+        watcher
+            .watch(&root, RecursiveMode::Recursive)
+            .map_err(|e| e.to_string())?;
+        let thread_root = root.clone();
+        std::thread::spawn(move || debounce_changes(receiver, &thread_root, &rules, app));
+        let mut active = self
+            .active
+            .lock()
+            .map_err(|_| "Workspace watcher lock poisoned".to_string())?;
+        *active = Some(ActiveWatch {
+            root,
+            show_hidden,
+            _watcher: watcher,
+        });
+        Ok(status_of(&active))
+    }
+
+    pub fn stop(&self) -> Result<WatchStatus, String> {
+        let mut active = self
+            .active
+            .lock()
+            .map_err(|_| "Workspace watcher lock poisoned".to_string())?;
+        *active = None;
+        Ok(status_of(&active))
+    }
+
+    pub fn status(&self) -> WatchStatus {
+        self.active
+            .lock()
+            .map(|active| status_of(&active))
+            .unwrap_or(WatchStatus {
+                watching: false,
+                root: None,
+                show_hidden: None,
+            })
+    }
+}
+
+fn status_of(active: &Option<ActiveWatch>) -> WatchStatus {
+    WatchStatus {
+        watching: active.is_some(),
+        root: active
+            .as_ref()
+            .map(|watch| watch.root.to_string_lossy().to_string()),
+        show_hidden: active.as_ref().and_then(|watch| watch.show_hidden),
+    }
+}
+
+fn debounce_changes(
+    receiver: Receiver<notify::Result<Event>>,
+    root: &Path,
+    rules: &IgnoreRules,
+    app: SharedEventSink,
+) {
+    let mut pending: BTreeMap<String, ChangeKind> = BTreeMap::new();
+    let mut first_pending: Option<Instant> = None;
+    loop {
+        let (quiet, closed) = match receiver.recv_timeout(DEBOUNCE) {
+            Ok(Ok(event)) => {
+                for (path, kind) in classify(&event) {
+                    let Some(relative) = relative_path(root, &path) else {
+                        continue;
+                    };
+                    if is_ignored_path(rules, &relative, path.is_dir()) {
+                        continue;
+                    }
+                    match merge_change(pending.get(&relative).copied(), kind) {
+                        Some(kind) => pending.insert(relative, kind),
+                        None => pending.remove(&relative),
+                    };
+                    first_pending.get_or_insert_with(Instant::now);
+                }
+                (false, false)
+            }
+            Ok(Err(err)) => {
+                tracing::warn!(error = %err, "workspace watch error");
+                (false, false)
+            }
+            Err(RecvTimeoutError::Timeout) => (true, false),
+            Err(RecvTimeoutError::Disconnected) => (true, true),
+        };
+        let overdue = first_pending.is_some_and(|since| since.elapsed() >= MAX_DELAY);
+        if !pending.is_empty() && (quiet || overdue) {
+            emit_changes(&app, std::mem::take(&mut pending));
+            first_pending = None;
+        }
+        if closed {
+            break;
+        }
+    }
+}
+
+fn classify(event: &Event) -> Vec<(PathBuf, ChangeKind)> {
+    let single = |kind| {
+        event
+            .paths
+            .iter()
+            .map(|path| (path.clone(), kind))
+            .collect::<Vec<_>>()
+    };
+    match &event.kind {
+        EventKind::Create(_) => single(ChangeKind::Created),
+        EventKind::Remove(_) => single(ChangeKind::Deleted),
+        EventKind::Modify(ModifyKind::Name(RenameMode::From)) => single(ChangeKind::Deleted),
+        EventKind::Modify(ModifyKind::Name(RenameMode::To)) => single(ChangeKind::Created),
+        EventKind::Modify(ModifyKind::Name(RenameMode::Both)) if event.paths.len() == 2 => vec![
+            (event.paths[0].clone(), ChangeKind::Deleted),
+            (event.paths[1].clone(), ChangeKind::Created),
+        ],
+        // Renames reported without a direction: decide by what is left on disk.
+        EventKind::Modify(ModifyKind::Name(_)) => event
+            .paths
+            .iter()
+            .map(|path| {
+                let kind = if path.exists() {
+                    ChangeKind::Created
+                } else {
+                    ChangeKind::Deleted
+                };
+                (path.clone(), kind)
+            })
+            .collect(),
+        EventKind::Modify(_) => single(ChangeKind::Modified),
+        _ => Vec::new(),
+    }
+}
+
+/// Folds a new change into the one already pending for the same path;
+/// a file created and deleted within one batch is not reported at all.
+fn merge_change(previous: Option<ChangeKind>, next: ChangeKind) -> Option<ChangeKind> {
+    match (previous, next) {
+        (None, next) => Some(next),
+        (Some(ChangeKind::Created), ChangeKind::Deleted) => None,
+        (Some(ChangeKind::Created), _) => Some(ChangeKind::Created),
+        (Some(ChangeKind::Deleted), ChangeKind::Created) => Some(ChangeKind::Modified),
+        (Some(ChangeKind::Modified), ChangeKind::Created) => Some(ChangeKind::Modified),
+        (Some(_), next) => Some(next),
+    }
+}
+
+fn relative_path(root: &Path, path: &Path) -> Option<String> {
+    let relative = path.strip_prefix(root).ok()?;
+    let relative = relative.to_string_lossy().replace('\\', "/");
+    (!relative.is_empty()).then_some(relative)
+}
+
+/// Like `fs_list_tree`, which never descends into an ignored directory.
+fn is_ignored_path(rules: &IgnoreRules, relative: &str, is_dir: bool) -> bool {
+    let parts: Vec<&str> = relative.split('/').collect();
+    (1..parts.len()).any(|end| rules.is_ignored(&parts[..end].join("/"), true))
+        || rules.is_ignored(relative, is_dir)
+}
+
+fn emit_changes(app: &SharedEventSink, pending: BTreeMap<String, ChangeKind>) {
+    let mut changes = WorkspaceChanges::default();
+    for (path, kind) in pending {
+        match kind {
+            ChangeKind::Created => changes.created.push(path),
+            ChangeKind::Modified => changes.modified.push(path),
+            ChangeKind::Deleted => changes.deleted.push(path),
+        }
+    }
+    app.emit_json(
+        WORKSPACE_CHANGED_EVENT,
+        serde_json::to_value(changes).unwrap_or_default(),
+    );
+}
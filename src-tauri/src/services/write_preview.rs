@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::services::audit::now_ms;
+
+/// Holds each `fs.write` for review: the run stops with a diff until the
+/// write is approved or rejected.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct WritePreviewPolicy {
+    pub enabled: bool,
+}
+
+pub fn write_preview_policy_path(root: &Path) -> PathBuf {
+    root.join(".taurihands").join("write-preview.json")
+}
+
+pub fn load_write_preview_policy(root: &Path) -> WritePreviewPolicy {
+    std::fs::read_to_string(write_preview_policy_path(root))
+        .ok()
+        .and_then(|raw| serde_json::from_str(&raw).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_write_preview_policy(root: &Path, policy: &WritePreviewPolicy) -> Result<(), String> {
+    let path = write_preview_policy_path(root);
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let data = serde_json::to_vec_pretty(policy).map_err(|e| e.to_string())?;
+    std::fs::write(path, data).map_err(|e| e.to_string())
+}
+
+/// Copies the file about to be overwritten to
+/// `.taurihands/backups/writes/<timestamp>/<path>`. Returns `None` when the
+/// file does not exist yet.
+pub fn backup_before_write(root: &Path, target: &Path) -> Result<Option<PathBuf>, String> {
+    if !target.is_file() {
+        return Ok(None);
+    }
+    let relative = target.strip_prefix(root).map_err(|e| e.to_string())?;
+    let backup = root
+        .join(".taurihands")
+        .join("backups")
+        .join("writes")
+        .join(now_ms().to_string())
+        .join(relative);
+    if let Some(parent) = backup.parent() {
+        std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    std::fs::copy(target, &backup).map_err(|e| e.to_string())?;
+    Ok(Some(backup))
+}
@@ -0,0 +1,199 @@
+use std::sync::{Arc, Mutex};
+use tauri::menu::{IsMenuItem, Menu, MenuEvent, MenuItem, PredefinedMenuItem, Submenu};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Emitter, Listener, Manager};
+
+use crate::services::kernel::{KernelEvent, RunAgentState, RunState, KERNEL_EVENT_NAME};
+use crate::{load_workspace_settings, set_workspace_root, AppState};
+
+const TRAY_ID: &str = "main";
+const MAX_TRAY_WORKSPACES: usize = 5;
+
+/// Tray icon mirroring the run status with pause/resume/stop and recent
+/// workspaces, so long runs can be followed with the main window minimized.
+pub fn init(app: &AppHandle) -> tauri::Result<()> {
+    let (menu, key) = build_menu(app)?;
+    let mut builder = TrayIconBuilder::with_id(TRAY_ID)
+        .menu(&menu)
+        .tooltip(tooltip(&app.state::<AppState>().kernel.snapshot()))
+        .on_menu_event(handle_menu_event);
+    if let Some(icon) = app.default_window_icon().cloned() {
+        builder = builder.icon(icon);
+    }
+    builder.build(app)?;
+
+    // Rebuild only when the visible status changes; StateChanged is frequent.
+    let last_key = Arc::new(Mutex::new(key));
+    let handle = app.clone();
+    app.listen(KERNEL_EVENT_NAME, move |event| {
+        let is_state_change = serde_json::from_str::<KernelEvent>(event.payload())
+            .is_ok_and(|event| event.event_type == "StateChanged");
+        if is_state_change {
+            refresh_if_changed(&handle, &last_key);
+        }
+    });
+    Ok(())
+}
+
+/// Rebuilds the menu, e.g. after the workspace changed.
+pub fn refresh(app: &AppHandle) {
+    let Some(tray) = app.tray_by_id(TRAY_ID) else {
+        return;
+    };
+    if let Ok((menu, _)) = build_menu(app) {
+        let _ = tray.set_menu(Some(menu));
+    }
+    let _ = tray.set_tooltip(Some(tooltip(&app.state::<AppState>().kernel.snapshot())));
+}
+
+fn refresh_if_changed(app: &AppHandle, last_key: &Mutex<String>) {
+    let key = menu_key(
+        &app.state::<AppState>().kernel.snapshot(),
+        &recent_workspaces(app),
+    );
+    let Ok(mut last) = last_key.lock() else {
+        return;
+    };
+    if *last != key {
+        *last = key;
+        drop(last);
+        refresh(app);
+    }
+}
+
+fn build_menu(app: &AppHandle) -> tauri::Result<(Menu<tauri::Wry>, String)> {
+    let run = app.state::<AppState>().kernel.snapshot();
+    let recent = recent_workspaces(app);
+    let status = MenuItem::with_id(app, "status", status_label(&run), false, None::<&str>)?;
+    let show = MenuItem::with_id(app, "show", "Show TauriHands", true, None::<&str>)?;
+    let pause = MenuItem::with_id(
+        app,
+        "pause",
+        "Pause run",
+        run.agent_state == RunAgentState::Running,
+        None::<&str>,
+    )?;
+    let resume = MenuItem::with_id(
+        app,
+        "resume",
+        "Resume run",
+        run.agent_state == RunAgentState::Paused,
+        None::<&str>,
+    )?;
+    let stop = MenuItem::with_id(
+        app,
+        "stop",
+        "Stop run",
+        matches!(
+            run.agent_state,
+            RunAgentState::Running | RunAgentState::Paused | RunAgentState::AwaitingUser
+        ),
+        None::<&str>,
+    )?;
+    let workspace_items = recent
+        .iter()
+        .enumerate()
+        .map(|(index, path)| {
+            MenuItem::with_id(
+                app,
+                format!("workspace:{}", index),
+                path,
+                true,
+                None::<&str>,
+            )
+        })
+        .collect::<tauri::Result<Vec<_>>>()?;
+    let workspace_refs: Vec<&dyn IsMenuItem<tauri::Wry>> = workspace_items
+        .iter()
+        .map(|item| item as &dyn IsMenuItem<tauri::Wry>)
+        .collect();
+    let workspaces = Submenu::with_items(
+        app,
+        "Recent workspaces",
+        !workspace_refs.is_empty(),
+        &workspace_refs,
+    )?;
+    let menu = Menu::with_items(
+        app,
+        &[
+            &status,
+            &PredefinedMenuItem::separator(app)?,
+            &show,
+            &pause,
+            &resume,
+            &stop,
+            &PredefinedMenuItem::separator(app)?,
+            &workspaces,
+            &PredefinedMenuItem::separator(app)?,
+            &PredefinedMenuItem::quit(app, Some("Quit"))?,
+        ],
+    )?;
+    Ok((menu, menu_key(&run, &recent)))
+}
+
+fn handle_menu_event(app: &AppHandle, event: MenuEvent) {
+    let state = app.state::<AppState>();
+    let id = event.id().as_ref();
+    let result = match id {
+        "show" => {
+            if let Some(window) = app.get_webview_window("main") {
+                let _ = window.unminimize();
+                let _ = window.show();
+                let _ = window.set_focus();
+            }
+            Ok(())
+        }
+        "pause" => state.kernel.pause(app).map(|_| ()),
+        "resume" => state.kernel.resume(app).map(|_| ()),
+        "stop" => state.kernel.stop(app).map(|_| ()),
+        _ => match id
+            .strip_prefix("workspace:")
+            .and_then(|index| index.parse::<usize>().ok())
+            .and_then(|index| recent_workspaces(app).into_iter().nth(index))
+        {
+            Some(root) => set_workspace_root(app.clone(), state, root).map(|display| {
+                let _ = app.emit("workspace-changed", display);
+            }),
+            None => Ok(()),
+        },
+    };
+    if let Err(err) = result {
+        tracing::warn!(action = %id, error = %err, "tray action failed");
+    }
+}
+
+fn recent_workspaces(app: &AppHandle) -> Vec<String> {
+    load_workspace_settings(&app.state::<AppState>().settings_path())
+        .map(|settings| settings.recent_workspaces)
+        .unwrap_or_default()
+        .into_iter()
+        .take(MAX_TRAY_WORKSPACES)
+        .collect()
+}
+
+fn status_label(run: &RunState) -> String {
+    let state = match run.agent_state {
+        RunAgentState::Idle => "Idle",
+        RunAgentState::Running => "Running",
+        RunAgentState::Paused => "Paused",
+        RunAgentState::AwaitingUser => "Waiting for input",
+        RunAgentState::Error => "Error",
+        RunAgentState::Finished => "Finished",
+        RunAgentState::Interrupted => "Interrupted",
+    };
+    if run.budget.used_steps == 0 {
+        return format!("Run: {}", state);
+    }
+    format!(
+        "Run: {} (step {}/{})",
+        state, run.budget.used_steps, run.budget.max_steps
+    )
+}
+
+fn tooltip(run: &RunState) -> String {
+    format!("TauriHands - {}", status_label(run))
+}
+
+fn menu_key(run: &RunState, recent: &[String]) -> String {
+    format!("{}|{}", status_label(run), recent.join("|"))
+}
@@ -0,0 +1,135 @@
+//! Drives the kernel end to end against the scripted `mock` LLM provider.
+
+use serde_json::{json, Value};
+use std::time::Duration;
+use tauri_app_lib::harness::{HeadlessKernel, RunAgentState};
+
+const RUN_TIMEOUT: Duration = Duration::from_secs(20);
+
+/// One scripted LLM reply carrying `decision` as its JSON content.
+fn reply(decision: Value) -> Value {
+    json!({ "content": decision })
+}
+
+/// A kernel limited to file actions, so pre-flight does not depend on which
+/// tools the machine running the tests has installed.
+fn file_only_kernel(responses: Vec<Value>) -> HeadlessKernel {
+    let harness = HeadlessKernel::new(responses).unwrap();
+    harness
+        .kernel
+        .set_cli_allowed_actions(Some(vec!["fs.read".to_string(), "fs.write".to_string()]));
+    harness
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn multi_step_run_finishes() {
+    let harness = file_only_kernel(vec![
+        reply(json!({ "actions": [{ "type": "fs.read", "id": "read-1", "path": "notes.txt" }] })),
+        reply(json!({ "actions": [{
+            "type": "fs.write",
+            "id": "write-1",
+            "path": "summary.txt",
+            "content": "Summary: one note"
+        }] })),
+    ]);
+    harness.write_file("notes.txt", "one note\n").unwrap();
+    // Keeps the run going until the summary exists.
+    let rules = json!([{
+        "rule_type": "file_exists",
+        "pattern": "summary.txt",
+        "action": "",
+        "command": null,
+        "fail_match": null,
+        "success_match": null
+    }]);
+    harness
+        .kernel
+        .set_judge_rules(serde_json::from_value(rules).unwrap())
+        .unwrap();
+
+    harness
+        .start(
+            "Summarize notes",
+            vec!["Read notes".to_string(), "Write summary".to_string()],
+            10,
+        )
+        .await
+        .unwrap();
+    let state = harness.wait_until_idle(RUN_TIMEOUT).await.unwrap();
+
+    assert_eq!(
+        state.agent_state,
+        RunAgentState::Finished,
+        "{:?}",
+        state.last_error
+    );
+    assert_eq!(
+        harness.read_file("summary.txt").unwrap(),
+        "Summary: one note"
+    );
+    let types = harness.events.kernel_event_types();
+    let proposed = types
+        .iter()
+        .filter(|kind| *kind == "AgentActionProposed")
+        .count();
+    assert_eq!(proposed, 2);
+    assert!(!types.iter().any(|kind| kind == "PolicyDenied"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn denied_action_is_rejected() {
+    let harness = file_only_kernel(vec![
+        reply(json!({ "actions": [{
+            "type": "terminal.run",
+            "id": "run-1",
+            "program": "touch",
+            "args": ["blocked.txt"]
+        }] })),
+        reply(json!({ "message": "Commands are not allowed here" })),
+    ]);
+
+    harness
+        .start("Create a file", vec!["Create".to_string()], 10)
+        .await
+        .unwrap();
+    let state = harness.wait_until_idle(RUN_TIMEOUT).await.unwrap();
+
+    assert_eq!(
+        state.agent_state,
+        RunAgentState::Finished,
+        "{:?}",
+        state.last_error
+    );
+    assert!(harness.read_file("blocked.txt").is_err());
+    assert!(harness
+        .events
+        .kernel_event_types()
+        .iter()
+        .any(|kind| kind == "PolicyDenied"));
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn user_ask_waits_for_the_user() {
+    let harness = file_only_kernel(vec![reply(json!({ "actions": [{
+        "type": "user.ask",
+        "id": "ask-1",
+        "question": "Which file should I change?"
+    }] }))]);
+
+    harness
+        .start("Change a file", vec!["Change".to_string()], 10)
+        .await
+        .unwrap();
+    let state = harness.wait_until_idle(RUN_TIMEOUT).await.unwrap();
+
+    assert_eq!(
+        state.agent_state,
+        RunAgentState::AwaitingUser,
+        "{:?}",
+        state.last_error
+    );
+    assert!(state
+        .messages
+        .iter()
+        .any(|message| message.content == "Which file should I change?"));
+}
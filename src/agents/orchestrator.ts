@@ -4,7 +4,14 @@ import { reactive } from "vue";
 import { terminalStore } from "../stores/terminal";
 import { missionStore } from "../stores/mission";
 
-type RunAgentState = "IDLE" | "RUNNING" | "PAUSED" | "AWAITING_USER" | "ERROR" | "FINISHED";
+type RunAgentState =
+  | "IDLE"
+  | "RUNNING"
+  | "PAUSED"
+  | "AWAITING_USER"
+  | "ERROR"
+  | "FINISHED"
+  | "INTERRUPTED";
 
 type ChatMessage = {
   role: string;
@@ -116,18 +123,24 @@ type LlmStream = {
   active: boolean;
 };
 
-type JudgeCheck = {
+export type JudgeSeverity = "blocker" | "warning" | "info";
+
+export type JudgeCheck = {
   id: string;
   type: string;
+  severity?: JudgeSeverity;
+  weight?: number;
   status: string;
+  description?: string;
   reason?: string | null;
   evidence?: string[];
 };
 
-type JudgeResult = {
+export type JudgeResult = {
   status: string;
+  message?: string;
+  score?: number;
   reasons: string[];
-  evidence: string[];
   checks: JudgeCheck[];
 };
 
@@ -146,6 +159,7 @@ type AgentStoreState = {
   llmStream: LlmStream;
   judgeResult: JudgeResult | null;
   timelineFocusId: string;
+  narration: string;
 };
 
 const state = reactive<AgentStoreState>({
@@ -163,6 +177,7 @@ const state = reactive<AgentStoreState>({
   },
   judgeResult: null,
   timelineFocusId: "",
+  narration: "",
 });
 
 const listeners = new Set<(next: AgentStoreState) => void>();
@@ -352,6 +367,32 @@ function applyEvent(event: KernelEvent) {
       message,
       timestamp: event.ts,
     });
+  } else if (event.type === "Regression") {
+    const payload = event.payload as { regression?: { tests?: { name: string }[] } };
+    const names = payload.regression?.tests?.map((test) => test.name) ?? [];
+    state.logs.unshift({
+      id: event.id,
+      level: "warn",
+      message: `Regression: ${names.join(", ") || "previously passing tests now fail"}`,
+      timestamp: event.ts,
+    });
+  } else if (event.type === "SecretsDetected") {
+    const payload = event.payload as { findings?: { path: string }[] };
+    const paths = Array.from(new Set(payload.findings?.map((finding) => finding.path) ?? []));
+    state.logs.unshift({
+      id: event.id,
+      level: "warn",
+      message: `Possible secrets in ${paths.join(", ") || "workspace changes"}`,
+      timestamp: event.ts,
+    });
+  } else if (event.type === "MissingDependency") {
+    const payload = event.payload as { command?: string; dependency?: { name: string } };
+    state.logs.unshift({
+      id: event.id,
+      level: "warn",
+      message: `Missing dependency ${payload.dependency?.name ?? ""}: ${payload.command ?? ""}`.trim(),
+      timestamp: event.ts,
+    });
   } else if (event.type === "JudgeResult") {
     const payload = event.payload as { result?: JudgeResult };
     if (payload?.result) {
@@ -371,7 +412,9 @@ function describeAction(action: Record<string, unknown>) {
     case "terminal.run":
       return `${String(action.program ?? "")} ${(action.args as string[] | undefined)?.join(" ") ?? ""}`.trim();
     case "fs.read":
-      return String(action.path ?? "");
+      return action.offset ? `${String(action.path ?? "")} @${String(action.offset)}` : String(action.path ?? "");
+    case "fs.read_many":
+      return ((action.paths as string[] | undefined) ?? []).join(", ");
     case "fs.write":
       return String(action.path ?? "");
     case "fs.search":
@@ -380,6 +423,18 @@ function describeAction(action: Record<string, unknown>) {
       return "git status";
     case "git.diff":
       return action.path ? `git diff ${String(action.path)}` : "git diff";
+    case "git.commit":
+      return `git commit: ${String(action.message ?? "").split("\n")[0]}`;
+    case "git.branch":
+      return `git branch ${String(action.name ?? "")}`;
+    case "git.stash":
+      return action.pop ? "git stash pop" : "git stash";
+    case "git.checkout":
+      return `git checkout ${String(action.target ?? "")}`;
+    case "deps.audit":
+      return "dependency audit";
+    case "code.todos":
+      return action.path ? `TODOs in ${String(action.path)}` : "TODOs";
     case "tests.run":
       return `${String(action.program ?? "")} ${(action.args as string[] | undefined)?.join(" ") ?? ""}`.trim();
     default:
@@ -420,6 +475,22 @@ async function initKernelStore() {
   } catch (error) {
     console.warn("Unable to subscribe to kernel events", error);
   }
+  try {
+    await listen<{ text: string }>("narration", (event) => {
+      state.narration = event.payload.text;
+      notify();
+    });
+  } catch (error) {
+    console.warn("Unable to subscribe to narration events", error);
+  }
+}
+
+async function setNarration(enabled: boolean) {
+  await invoke("kernel_set_narration", { enabled });
+  if (!enabled) {
+    state.narration = "";
+    notify();
+  }
 }
 
 async function start() {
@@ -501,4 +572,5 @@ export const agentStore = {
   userInput,
   updatePlan,
   updatePlanStatus,
+  setNarration,
 };
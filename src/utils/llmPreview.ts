@@ -93,12 +93,22 @@ function toActionPreview(action: RawAction | null | undefined): LlmPreviewAction
       };
     case "fs.read": {
       const path = coerceString(action.path);
+      const offset = typeof action.offset === "number" ? String(action.offset) : coerceString(action.offset);
       return {
         type,
         title: type,
+        detail: offset ? `offset ${offset}` : undefined,
         path: path || undefined,
       };
     }
+    case "fs.read_many": {
+      const paths = Array.isArray(action.paths) ? action.paths.map((item) => String(item)) : [];
+      return {
+        type,
+        title: type,
+        detail: paths.length ? paths.join(", ") : undefined,
+      };
+    }
     case "fs.search": {
       const path = coerceString(action.path);
       const pattern = coerceString(action.pattern);
@@ -121,11 +131,32 @@ function toActionPreview(action: RawAction | null | undefined): LlmPreviewAction
     }
     case "git.status":
     case "git.diff":
+    case "deps.audit":
+    case "code.todos":
       return {
         type,
         title: type,
         detail: coerceString(action.path) || undefined,
       };
+    case "git.commit":
+      return {
+        type,
+        title: type,
+        detail: coerceString(action.message) || undefined,
+      };
+    case "git.branch":
+    case "git.checkout":
+      return {
+        type,
+        title: type,
+        detail: coerceString(action.name ?? action.target) || undefined,
+      };
+    case "git.stash":
+      return {
+        type,
+        title: type,
+        detail: action.pop ? "pop" : coerceString(action.message) || undefined,
+      };
     case "tests.run":
       return {
         type,